@@ -153,6 +153,10 @@ export interface SimpleExposure {
   // Dithering
   dither: boolean;
   ditherEvery: number;
+
+  // ETA
+  estimatedStartTime?: Date;
+  estimatedEndTime?: Date;
 }
 
 export function createDefaultExposure(): SimpleExposure {
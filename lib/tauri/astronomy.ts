@@ -3,7 +3,10 @@
  */
 
 import { isTauri, invoke } from "./platform";
-import type { Coordinates } from "../nina/simple-sequence-types";
+import type {
+  Coordinates,
+  SimpleTarget,
+} from "../nina/simple-sequence-types";
 
 export interface ObserverLocation {
   latitude: number;
@@ -21,6 +24,17 @@ export interface VisibilityWindow {
   isVisible: boolean;
 }
 
+export interface RiseSetTimes {
+  /** First time the target climbs above the limit, if it does */
+  rise: string | null;
+  /** First time the target drops below the limit, if it does */
+  set: string | null;
+  transit: string;
+  transitAltitude: number;
+  alwaysUp: boolean;
+  neverUp: boolean;
+}
+
 export interface TwilightTimes {
   date: string;
   sunrise: string | null;
@@ -60,6 +74,108 @@ export interface CelestialPosition {
   distanceKm: number | null;
 }
 
+export type ImagingGrade =
+  | "excellent"
+  | "good"
+  | "fair"
+  | "poor"
+  | "noDarkness";
+
+export interface DarkCalendarDay {
+  date: string;
+  astronomicalDusk: string | null;
+  astronomicalDawn: string | null;
+  darkHours: number;
+  moonFreeDarkHours: number;
+  moonRise: string | null;
+  moonSet: string | null;
+  moonIllumination: number;
+  moonPhaseName: string;
+  grade: ImagingGrade;
+  color: string;
+}
+
+export interface DarkCalendar {
+  month: string;
+  days: DarkCalendarDay[];
+  totalDarkHours: number;
+  totalMoonFreeDarkHours: number;
+}
+
+export type DsoType =
+  | "galaxy"
+  | "globularCluster"
+  | "openCluster"
+  | "emissionNebula"
+  | "reflectionNebula"
+  | "planetaryNebula"
+  | "supernovaRemnant"
+  | "darkNebula"
+  | "other";
+
+export interface DeepSkyObject {
+  id: string;
+  commonName: string | null;
+  objectType: DsoType;
+  raHours: number;
+  decDegrees: number;
+  sizeArcmin: number;
+  magnitude: number | null;
+}
+
+export interface CatalogFilter {
+  objectTypes?: DsoType[];
+  catalogs?: string[];
+  maxMagnitude?: number;
+  minSize?: number;
+  maxSize?: number;
+  query?: string;
+  /** Largest angular distance from the galactic plane (|b|) in degrees */
+  maxGalacticLatitude?: number;
+}
+
+export interface FieldOfView {
+  width: number;
+  height: number;
+}
+
+export interface TargetRecommendation {
+  object: DeepSkyObject;
+  score: number;
+  visibleHours: number;
+  visibleFrom: string;
+  visibleUntil: string;
+  maxAltitude: number;
+  maxAltitudeTime: string;
+  moonSeparation: number | null;
+  fovFill: number | null;
+  target: SimpleTarget;
+}
+
+export interface SeasonNight {
+  date: string;
+  darkHours: number;
+  usableHours: number;
+}
+
+export interface SeasonWindow {
+  startDate: string;
+  endDate: string;
+  nights: number;
+  totalUsableHours: number;
+  bestDate: string;
+}
+
+export interface TargetSeason {
+  year: number;
+  minAltitude: number;
+  minDarkHours: number;
+  nights: SeasonNight[];
+  windows: SeasonWindow[];
+  bestWindow: SeasonWindow | null;
+  totalUsableHours: number;
+}
+
 export interface BatchCoordinateResult {
   id: string;
   altitude: number;
@@ -77,6 +193,27 @@ const DEFAULT_LOCATION: ObserverLocation = {
   timezoneOffset: 0,
 };
 
+/**
+ * Rise, transit and set times of a target over the night starting on
+ * `date`. Times are measured against the horizon profile of the site given
+ * by `siteId`, or of the active site when no location is passed.
+ */
+export async function calculateTargetRiseSet(
+  coordinates: Coordinates,
+  location: ObserverLocation | undefined,
+  date: string,
+  minAltitude: number = 0,
+  siteId?: string,
+): Promise<RiseSetTimes> {
+  return invoke<RiseSetTimes>("calculate_target_rise_set", {
+    coordinates,
+    location,
+    siteId,
+    date,
+    minAltitude,
+  });
+}
+
 /**
  * Calculate visibility window for a target
  */
@@ -398,6 +535,84 @@ export async function calculateVisibilityRange(
   return results;
 }
 
+/**
+ * Generate the darkness and moon calendar of a month (YYYY-MM)
+ */
+export async function generateDarkCalendar(
+  location: ObserverLocation,
+  month: string,
+): Promise<DarkCalendar> {
+  if (isTauri()) {
+    return invoke<DarkCalendar>("generate_dark_calendar", {
+      location,
+      month,
+    });
+  }
+
+  throw new Error("Dark calendar requires desktop app");
+}
+
+/**
+ * Search the built-in deep-sky catalog
+ */
+export async function searchDsoCatalog(
+  filter: CatalogFilter = {},
+): Promise<DeepSkyObject[]> {
+  if (isTauri()) {
+    return invoke<DeepSkyObject[]>("search_dso_catalog", { filter });
+  }
+
+  throw new Error("Catalog search requires desktop app");
+}
+
+/**
+ * Rank catalog objects for the night starting on date
+ */
+export async function recommendTargetsTonight(
+  location: ObserverLocation,
+  date: string,
+  filter?: CatalogFilter,
+  fov?: FieldOfView,
+  minAltitude: number = 30,
+  limit: number = 10,
+): Promise<TargetRecommendation[]> {
+  if (isTauri()) {
+    return invoke<TargetRecommendation[]>("recommend_targets_tonight", {
+      location,
+      date,
+      filter,
+      fov,
+      minAltitude,
+      limit,
+    });
+  }
+
+  throw new Error("Target recommendations require desktop app");
+}
+
+/**
+ * Usable dark hours of a target for each night of a year
+ */
+export async function calculateTargetSeason(
+  coordinates: Coordinates,
+  location: ObserverLocation,
+  year: number,
+  minAltitude: number = 30,
+  minDarkHours: number = 2,
+): Promise<TargetSeason> {
+  if (isTauri()) {
+    return invoke<TargetSeason>("calculate_target_season", {
+      coordinates,
+      location,
+      year,
+      minAltitude,
+      minDarkHours,
+    });
+  }
+
+  throw new Error("Season planning requires desktop app");
+}
+
 /**
  * Calculate altitude curve for plotting
  */
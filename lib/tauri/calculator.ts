@@ -5,7 +5,9 @@
 import { isTauri, invoke } from "./platform";
 import type {
   SimpleSequence,
+  SimpleTarget,
   Coordinates,
+  FilterInfo,
 } from "../nina/simple-sequence-types";
 
 export interface RaResult {
@@ -66,12 +68,26 @@ export async function calculateSequenceEtas(
     const newTarget = { ...target };
     let targetDuration = target.delay;
 
-    for (const exp of target.exposures) {
-      if (exp.enabled) {
-        const remaining = Math.max(0, exp.totalCount - exp.progressCount);
+    newTarget.exposures = target.exposures.map((exp) => {
+      const newExp = {
+        ...exp,
+        estimatedStartTime: undefined,
+        estimatedEndTime: undefined,
+      };
+      const remaining = exp.enabled
+        ? Math.max(0, exp.totalCount - exp.progressCount)
+        : 0;
+      if (remaining > 0) {
+        newExp.estimatedStartTime = new Date(
+          currentTime.getTime() + targetDuration * 1000,
+        );
         targetDuration += remaining * (exp.exposureTime + downloadTime);
+        newExp.estimatedEndTime = new Date(
+          currentTime.getTime() + targetDuration * 1000,
+        );
       }
-    }
+      return newExp;
+    });
 
     newTarget.estimatedStartTime = new Date(currentTime);
     newTarget.estimatedDuration = targetDuration;
@@ -157,6 +173,79 @@ export async function calculateAngularSeparation(
   return (Math.acos(Math.min(1, Math.max(-1, cosSep))) * 180) / Math.PI;
 }
 
+export interface SeparationMatrix {
+  /** Target ids, in the order of the rows and columns */
+  targetIds: string[];
+  /** Separations in degrees */
+  separations: number[][];
+}
+
+/**
+ * Separations in degrees between every pair of targets, in one call
+ */
+export async function calculateSeparationMatrix(
+  targets: SimpleTarget[],
+): Promise<SeparationMatrix> {
+  if (isTauri()) {
+    return invoke<SeparationMatrix>("calculate_separation_matrix", {
+      targets,
+    });
+  }
+
+  // Browser fallback
+  const separations = await Promise.all(
+    targets.map((from) =>
+      Promise.all(
+        targets.map((to) =>
+          from === to
+            ? 0
+            : calculateAngularSeparation(from.coordinates, to.coordinates),
+        ),
+      ),
+    ),
+  );
+  return { targetIds: targets.map((t) => t.id), separations };
+}
+
+export interface NearbyTarget {
+  targetId: string;
+  targetName: string;
+  /** Separation from the position in degrees */
+  separation: number;
+}
+
+/**
+ * Targets within `radius` degrees of a position, nearest first
+ */
+export async function findTargetsNear(
+  coordinates: Coordinates,
+  targets: SimpleTarget[],
+  radius: number,
+): Promise<NearbyTarget[]> {
+  if (isTauri()) {
+    return invoke<NearbyTarget[]>("find_targets_near", {
+      coordinates,
+      targets,
+      radius,
+    });
+  }
+
+  // Browser fallback
+  const nearby = await Promise.all(
+    targets.map(async (target) => ({
+      targetId: target.id,
+      targetName: target.targetName,
+      separation: await calculateAngularSeparation(
+        coordinates,
+        target.coordinates,
+      ),
+    })),
+  );
+  return nearby
+    .filter((n) => n.separation <= radius)
+    .sort((a, b) => a.separation - b.separation);
+}
+
 /**
  * Convert RA to decimal
  */
@@ -397,3 +486,167 @@ export async function formatDec(
   const sign = negative ? "-" : "+";
   return `${sign}${degrees}° ${minutes.toString().padStart(2, "0")}' ${seconds.toFixed(1)}"`;
 }
+
+/** Galactic coordinates in degrees */
+export interface GalacticCoordinates {
+  /** Galactic longitude l in [0, 360) */
+  longitude: number;
+  /** Galactic latitude b, 0 on the galactic plane */
+  latitude: number;
+}
+
+/** Ecliptic coordinates in degrees, mean ecliptic and equinox of J2000 */
+export interface EclipticCoordinates {
+  /** Ecliptic longitude λ in [0, 360) */
+  longitude: number;
+  /** Ecliptic latitude β */
+  latitude: number;
+}
+
+/**
+ * Galactic coordinates of a J2000 position
+ */
+export async function equatorialToGalactic(
+  coordinates: Coordinates,
+): Promise<GalacticCoordinates> {
+  return invoke<GalacticCoordinates>("equatorial_to_galactic", {
+    coordinates,
+  });
+}
+
+/**
+ * J2000 coordinates of a galactic position
+ */
+export async function galacticToEquatorial(
+  galactic: GalacticCoordinates,
+): Promise<Coordinates> {
+  return invoke<Coordinates>("galactic_to_equatorial", { galactic });
+}
+
+/**
+ * Ecliptic coordinates of a J2000 position
+ */
+export async function equatorialToEcliptic(
+  coordinates: Coordinates,
+): Promise<EclipticCoordinates> {
+  return invoke<EclipticCoordinates>("equatorial_to_ecliptic", {
+    coordinates,
+  });
+}
+
+/**
+ * J2000 coordinates of an ecliptic position
+ */
+export async function eclipticToEquatorial(
+  ecliptic: EclipticCoordinates,
+): Promise<Coordinates> {
+  return invoke<Coordinates>("ecliptic_to_equatorial", { ecliptic });
+}
+
+export interface FlatFilter {
+  filter: FilterInfo;
+  /** Throughput relative to a clear filter, 0-1 */
+  transmission: number;
+}
+
+/** Flat panel, camera and flat settings; omitted fields use defaults */
+export interface FlatPanelSettings {
+  /** Mean level in ADU, half of full scale when omitted */
+  targetAdu?: number;
+  brightness?: number;
+  maxBrightness?: number;
+  /** Electrons per pixel per second at full brightness, clear filter */
+  panelFlux?: number;
+  /** System gain at the flats' gain setting */
+  electronsPerAdu?: number;
+  biasAdu?: number;
+  /** Defaults to the active equipment profile's camera */
+  bitDepth?: number;
+  gain?: number;
+  offset?: number;
+  minExposure?: number;
+  maxExposure?: number;
+  /** Change the brightness per filter to keep exposures within limits */
+  adjustBrightness?: boolean;
+  /** Flats per filter */
+  count?: number;
+  filters?: FlatFilter[];
+}
+
+export interface FlatExposure {
+  /** Empty for unfiltered flats */
+  filter: string;
+  exposureTime: number;
+  brightness: number;
+  expectedAdu: number;
+  warning?: string;
+}
+
+export interface FlatPlan {
+  exposures: FlatExposure[];
+  /** Calibration target taking the flats */
+  target: SimpleTarget;
+}
+
+/**
+ * Flat exposure times per filter and a calibration target taking them
+ */
+export async function calculateFlatExposures(
+  settings: FlatPanelSettings,
+): Promise<FlatPlan> {
+  return invoke<FlatPlan>("calculate_flat_exposures", { settings });
+}
+
+export type SamplingClass = "undersampled" | "optimal" | "oversampled";
+
+export interface BinnedSampling {
+  binning: number;
+  /** Arcseconds per pixel */
+  imageScale: number;
+  pixelsPerFwhm: number;
+  classification: SamplingClass;
+}
+
+export interface GuideOptics {
+  /** Microns */
+  pixelSize: number;
+  /** Millimeters */
+  focalLength: number;
+}
+
+export interface GuideSampling {
+  /** Arcseconds per pixel */
+  imageScale: number;
+  /** Guide scale over the unbinned imaging scale */
+  scaleRatio: number;
+  suitable: boolean;
+}
+
+export interface SamplingAnalysis {
+  /** Unbinned arcseconds per pixel */
+  imageScale: number;
+  /** Seeing FWHM in arcseconds */
+  seeing: number;
+  pixelsPerFwhm: number;
+  classification: SamplingClass;
+  suggestedBinning: number;
+  /** Sampling at binning 1x1 to 4x4 */
+  binnings: BinnedSampling[];
+  guiding?: GuideSampling;
+}
+
+/**
+ * Sampling of the equipment at a seeing FWHM, with the binning that suits
+ * it. Uses the active equipment profile unless one is given.
+ */
+export async function analyzeSampling(
+  seeingArcsec: number,
+  guide?: GuideOptics,
+  profile?: Record<string, unknown>,
+): Promise<SamplingAnalysis> {
+  return invoke<SamplingAnalysis>("analyze_sampling", {
+    profile,
+    seeingArcsec,
+    guide,
+  });
+}
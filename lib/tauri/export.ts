@@ -15,6 +15,7 @@ export type ExportFormat =
   | "xml_apt"
   | "stellarium"
   | "voyager"
+  | "voyagerRoboTarget"
   | "nina_target_set"
   | "json";
 
@@ -7,6 +7,7 @@ import type {
   SimpleSequence,
   SimpleTarget,
 } from "../nina/simple-sequence-types";
+import type { EditorSequence } from "../nina/types";
 
 export interface FileInfo {
   path: string;
@@ -57,6 +58,28 @@ export async function loadSimpleSequenceFile(
   throw new Error("File loading not supported in browser mode");
 }
 
+/**
+ * Sequence loaded by `loadAnySequence`, tagged with the detected format
+ */
+export type LoadedSequence =
+  | { format: "simpleJson"; sequence: SimpleSequence }
+  | { format: "editorJson"; sequence: EditorSequence }
+  | { format: "ninaSequence"; sequence: EditorSequence }
+  | { format: "ninaTemplate"; sequence: EditorSequence }
+  | { format: "xmlTargetSet"; sequence: SimpleSequence; warnings: string[] };
+
+/**
+ * Load a sequence file in any format the app opens, detected from the
+ * content: simple or editor JSON, a NINA sequence or template, or a legacy
+ * NINA XML target set
+ */
+export async function loadAnySequence(path: string): Promise<LoadedSequence> {
+  if (isTauri()) {
+    return invoke<LoadedSequence>("load_any_sequence", { path });
+  }
+  throw new Error("File loading not supported in browser mode");
+}
+
 /**
  * Save simple sequence to file
  */
@@ -218,6 +241,39 @@ export async function autoSaveSequence(
   return key;
 }
 
+/** Backend auto-save state of one open sequence session */
+export interface SessionAutoSave {
+  sessionId: string;
+  /** Revision of the last snapshot */
+  revision: number;
+  savedAt?: string;
+  /** Autosave file of the last snapshot */
+  path?: string;
+  /** Edits are waiting for a snapshot */
+  pending: boolean;
+}
+
+export interface AutoSaveStatus {
+  enabled: boolean;
+  intervalSeconds: number;
+  /** Unsaved edits that trigger a snapshot right away, 0 for none */
+  afterEdits: number;
+  lastSaveAt?: string;
+  lastError?: string;
+  sessions: SessionAutoSave[];
+}
+
+/**
+ * Auto-save settings and the backend's snapshots of open sessions. The
+ * backend saves sessions on its own; no frontend timer is needed for them.
+ */
+export async function getAutoSaveStatus(): Promise<AutoSaveStatus | null> {
+  if (isTauri()) {
+    return invoke<AutoSaveStatus>("get_auto_save_status");
+  }
+  return null;
+}
+
 /**
  * Load auto-saved sequence
  */
@@ -3,7 +3,10 @@
  */
 
 import { isTauri, invoke } from "./platform";
-import type { SimpleTarget } from "../nina/simple-sequence-types";
+import type {
+  SimpleSequence,
+  SimpleTarget,
+} from "../nina/simple-sequence-types";
 import {
   SequenceEntityStatus,
   SequenceMode,
@@ -125,6 +128,36 @@ export async function importVoyagerContent(
   };
 }
 
+/** Sequence read from a NINA legacy XML target set */
+export interface NinaTargetSetImport {
+  sequence: SimpleSequence;
+  /** Targets that could not be read */
+  warnings: string[];
+}
+
+/**
+ * Import a NINA legacy XML target set (`ArrayOfCaptureSequenceList`) with
+ * its exposures, filters, binning and autofocus options
+ */
+export async function importNinaXmlTargetSet(
+  content: string,
+): Promise<NinaTargetSetImport> {
+  return invoke<NinaTargetSetImport>("import_nina_xml_target_set", {
+    content,
+  });
+}
+
+/**
+ * Import a NINA legacy XML target set file
+ */
+export async function importNinaXmlTargetSetFile(
+  path: string,
+): Promise<NinaTargetSetImport> {
+  return invoke<NinaTargetSetImport>("import_nina_xml_target_set_file", {
+    path,
+  });
+}
+
 /**
  * Import targets from XML content
  */
@@ -251,11 +284,15 @@ export async function importFitsFile(
 }
 
 /**
- * Batch import from multiple files (Tauri only)
+ * Batch import from multiple files (Tauri only). Pass an `operationId` to
+ * allow stopping it with `cancelOperation`.
  */
-export async function batchImportFiles(paths: string[]): Promise<ImportResult> {
+export async function batchImportFiles(
+  paths: string[],
+  operationId?: string,
+): Promise<ImportResult> {
   if (isTauri()) {
-    return invoke<ImportResult>("batch_import_files", { paths });
+    return invoke<ImportResult>("batch_import_files", { paths, operationId });
   }
 
   throw new Error("Batch import requires desktop app");
@@ -22,3 +22,5 @@ export * from "./astronomy";
 export * from "./import";
 export * from "./export";
 export * from "./optimizer";
+export * from "./jobs";
+export * from "./sessions";
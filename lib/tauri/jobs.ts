@@ -0,0 +1,136 @@
+/**
+ * Background jobs for long-running calculations (desktop only)
+ */
+
+import { isTauri, invoke, getTauriCore, toCommandError } from "./platform";
+import type {
+  Coordinates,
+  SimpleSequence,
+} from "../nina/simple-sequence-types";
+import type { ObserverLocation } from "./astronomy";
+
+export type JobState =
+  | "queued"
+  | "running"
+  | "completed"
+  | "failed"
+  | "cancelled";
+
+export interface JobStatus {
+  id: string;
+  kind: string;
+  state: JobState;
+  /** 0 to 1 */
+  progress: number;
+  message?: string | null;
+  createdAt: string;
+  startedAt?: string | null;
+  finishedAt?: string | null;
+  /** Output of a completed job */
+  result?: unknown;
+  error?: string | null;
+}
+
+export type JobRequest =
+  | {
+      kind: "batchVisibility";
+      sequence: SimpleSequence;
+      location?: ObserverLocation;
+      siteId?: string;
+      date: string;
+      minAltitude: number;
+    }
+  | {
+      kind: "bestObservationDate";
+      sequence: SimpleSequence;
+      location?: ObserverLocation;
+      siteId?: string;
+      startDate: string;
+      endDate: string;
+    }
+  | {
+      kind: "splitAcrossNights";
+      sequence: SimpleSequence;
+      location?: ObserverLocation;
+      siteId?: string;
+      startDate: string;
+      endDate: string;
+    }
+  | {
+      kind: "targetSeason";
+      coordinates: Coordinates;
+      location?: ObserverLocation;
+      siteId?: string;
+      year: number;
+      minAltitude?: number;
+      minDarkHours?: number;
+    }
+  | { kind: "refreshLibrary" };
+
+/**
+ * Start a background job; every status change is passed to `onEvent`
+ */
+export async function startJob(
+  request: JobRequest,
+  onEvent: (status: JobStatus) => void,
+): Promise<string> {
+  const core = await getTauriCore();
+  if (!core) {
+    throw new Error("Background jobs require desktop app");
+  }
+  const channel = new core.Channel<JobStatus>();
+  channel.onmessage = onEvent;
+  try {
+    return await core.invoke<string>("start_job", {
+      request,
+      onEvent: channel,
+    });
+  } catch (e) {
+    throw toCommandError("start_job", e);
+  }
+}
+
+/**
+ * Current status of a job, including its result once completed
+ */
+export async function getJobStatus(jobId: string): Promise<JobStatus> {
+  return invoke<JobStatus>("get_job_status", { jobId });
+}
+
+/**
+ * All known jobs, newest first, without results
+ */
+export async function listJobs(): Promise<JobStatus[]> {
+  if (isTauri()) {
+    return invoke<JobStatus[]>("list_jobs");
+  }
+  return [];
+}
+
+/**
+ * Ask a queued or running job to stop
+ */
+export async function cancelJob(jobId: string): Promise<void> {
+  return invoke<void>("cancel_job", { jobId });
+}
+
+/**
+ * Stop a directly called command started with `operationId`. Resolves to
+ * false when nothing runs under that id.
+ */
+export async function cancelOperation(operationId: string): Promise<boolean> {
+  if (isTauri()) {
+    return invoke<boolean>("cancel_operation", { operationId });
+  }
+  return false;
+}
+
+/**
+ * Forget finished jobs and their results
+ */
+export async function clearFinishedJobs(): Promise<number> {
+  if (isTauri()) {
+    return invoke<number>("clear_finished_jobs");
+  }
+  return 0;
+}
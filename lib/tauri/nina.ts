@@ -2,8 +2,13 @@
  * NINA format operations with Tauri/browser fallback
  */
 
-import { isTauri, invoke } from "./platform";
-import type { EditorSequence, EditorSequenceItem } from "../nina/types";
+import { isTauri, invoke, CommandError } from "./platform";
+import type {
+  EditorSequence,
+  EditorSequenceItem,
+  EditorTarget,
+} from "../nina/types";
+import type { SimpleSequence } from "../nina/simple-sequence-types";
 
 /**
  * Export editor sequence to NINA JSON format
@@ -46,7 +51,8 @@ export async function validateNinaFormat(
       await invoke<void>("validate_nina_format", { json });
       return { valid: true, errors: [] };
     } catch (e) {
-      const errors = Array.isArray(e) ? e : [String(e)];
+      const errors =
+        e instanceof CommandError && e.details ? e.details : [String(e)];
       return { valid: false, errors };
     }
   }
@@ -117,6 +123,53 @@ export async function exportTemplateToNina(
   return exportToNinaJson(sequence);
 }
 
+/**
+ * Convert a simple sequence to an editor sequence: a Deep Sky Object
+ * container per target and a Smart Exposure per enabled exposure row
+ */
+export async function convertSimpleToEditor(
+  sequence: SimpleSequence,
+): Promise<EditorSequence> {
+  return invoke<EditorSequence>("convert_simple_to_editor", { sequence });
+}
+
+export interface SimpleConversion {
+  sequence: SimpleSequence;
+  /** Items and triggers left out of the simple sequence */
+  warnings: string[];
+}
+
+/**
+ * Convert an editor sequence to a simple sequence, as far as the simple
+ * format can express it
+ */
+export async function convertEditorToSimple(
+  sequence: EditorSequence,
+): Promise<SimpleConversion> {
+  return invoke<SimpleConversion>("convert_editor_to_simple", { sequence });
+}
+
+export interface TargetSyncResult {
+  container: EditorSequenceItem;
+  /** Ids of the changed items, the container included */
+  updatedItemIds: string[];
+}
+
+/**
+ * Write edited target info into a Deep Sky Object container and the slew,
+ * center and rotate instructions inside it. The container is renamed when
+ * its name followed the old target name.
+ */
+export async function syncTargetIntoContainer(
+  container: EditorSequenceItem,
+  target: EditorTarget,
+): Promise<TargetSyncResult> {
+  return invoke<TargetSyncResult>("sync_target_into_container", {
+    container,
+    target,
+  });
+}
+
 /**
  * Get NINA type short name
  */
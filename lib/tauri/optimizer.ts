@@ -3,8 +3,15 @@
  */
 
 import { isTauri, invoke } from "./platform";
-import type { SimpleSequence } from "../nina/simple-sequence-types";
-import type { ObserverLocation, VisibilityWindow } from "./astronomy";
+import type {
+  SimpleSequence,
+  SimpleTarget,
+} from "../nina/simple-sequence-types";
+import type {
+  ObserverLocation,
+  RiseSetTimes,
+  VisibilityWindow,
+} from "./astronomy";
 
 export type OptimizationStrategy =
   | "max_altitude"
@@ -13,7 +20,8 @@ export type OptimizationStrategy =
   | "visibility_duration"
   | "minimize_slew"
   | "moon_avoidance"
-  | "combined";
+  | "combined"
+  | "time_windowed";
 
 export interface OptimizationResult {
   success: boolean;
@@ -23,6 +31,10 @@ export interface OptimizationResult {
   warnings: string[];
   estimatedTotalRuntime: number;
   estimatedSlewTime: number;
+  /** Slew time saved against nearest-neighbor ordering, in percent */
+  slewImprovementPercent?: number;
+  /** Hours imaged above the altitude threshold, for time_windowed */
+  scheduledIntegrationHours?: number;
 }
 
 export interface ConflictResult {
@@ -52,6 +64,58 @@ export interface TargetScheduleInfo {
   optimalEndTime: string | null;
   qualityScore: number;
   conflicts: string[];
+  /** Rise and set over the site's horizon profile, and transit */
+  riseSet: RiseSetTimes;
+  /** Dark time in which the target meets its constraints */
+  imagingWindow: ImagingWindow;
+}
+
+/** Observing constraints of a target; unset fields don't constrain */
+export interface ObservingConstraints {
+  minAltitude?: number;
+  maxAltitude?: number;
+  maxAirmass?: number;
+  /** Hours, negative east of the meridian */
+  minHourAngle?: number;
+  /** Hours, positive west of the meridian */
+  maxHourAngle?: number;
+  minMoonSeparation?: number;
+  /** Percent */
+  maxMoonIllumination?: number;
+  earliestStart?: string;
+  latestStart?: string;
+}
+
+export interface ImagingWindowOptions {
+  /** Sun altitude in degrees below which the sky counts as dark (-18) */
+  maxSunAltitude?: number;
+  /** Minimum target altitude when the constraints set none (20) */
+  defaultMinAltitude?: number;
+  /** Minutes before the meridian kept free for a meridian flip */
+  flipMinutesBefore?: number;
+  /** Minutes after the meridian kept free for a meridian flip */
+  flipMinutesAfter?: number;
+}
+
+export interface ImagingSegment {
+  start: string;
+  end: string;
+  durationHours: number;
+}
+
+export interface ImagingWindow {
+  /** In time order; split where a constraint or the meridian flip interrupts */
+  segments: ImagingSegment[];
+  totalHours: number;
+  darkStart: string | null;
+  darkEnd: string | null;
+}
+
+export interface ExposureEta {
+  exposureId: string;
+  frameCount: number;
+  etaStart: string;
+  etaEnd: string;
 }
 
 export interface BatchCalculationResult {
@@ -59,6 +123,7 @@ export interface BatchCalculationResult {
   runtime: number;
   etaStart: string | null;
   etaEnd: string | null;
+  exposures: ExposureEta[];
 }
 
 export interface ValidationReport {
@@ -83,10 +148,59 @@ export interface SessionTimeEstimate {
   slewTimeSeconds: number;
   autofocusTimeSeconds: number;
   centeringTimeSeconds: number;
+  ditherTimeSeconds: number;
+  guidingTimeSeconds: number;
   totalTimeSeconds: number;
   availableDarkTimeSeconds: number;
   fitsInNight: boolean;
   utilizationPercentage: number;
+  /** Spread of the total, with finish times from astronomical dusk */
+  runtimeDistribution?: RuntimeDistribution;
+}
+
+/** Spread of the uncertain parts of a session, in seconds */
+export interface MonteCarloOptions {
+  iterations?: number;
+  seed?: number;
+  /** Standard deviation of each frame's download time */
+  downloadJitter?: number;
+  /** Standard deviation of each dither settle */
+  ditherSettleDeviation?: number;
+  /** Extra autofocus runs per hour of exposure */
+  autofocusRunsPerHour?: number;
+}
+
+export interface RuntimeDistribution {
+  iterations: number;
+  meanSeconds: number;
+  p50Seconds: number;
+  p90Seconds: number;
+  p50Finish?: string;
+  p90Finish?: string;
+}
+
+export interface NightPlan {
+  date: string;
+  darkStart: string;
+  darkEnd: string;
+  darkHours: number;
+  scheduledHours: number;
+  /** Targets with counts set so only the night's frames remain */
+  sequence: SimpleSequence;
+}
+
+export interface LeftoverTarget {
+  targetId: string;
+  targetName: string;
+  remainingFrames: number;
+  remainingHours: number;
+  reason: string;
+}
+
+export interface NightSplitResult {
+  nights: NightPlan[];
+  leftovers: LeftoverTarget[];
+  warnings: string[];
 }
 
 export interface StrategyInfo {
@@ -181,6 +295,7 @@ export async function calculateParallelEtas(
       runtime,
       etaStart,
       etaEnd,
+      exposures: [],
     });
   }
 
@@ -219,9 +334,44 @@ export async function getTargetScheduleInfo(
     optimalEndTime: null,
     qualityScore: 50,
     conflicts: [],
+    riseSet: {
+      rise: `${date}T18:00:00Z`,
+      set: `${date}T06:00:00Z`,
+      transit: `${date}T00:00:00Z`,
+      transitAltitude: 60,
+      alwaysUp: false,
+      neverUp: false,
+    },
+    imagingWindow: {
+      segments: [],
+      totalHours: 0,
+      darkStart: null,
+      darkEnd: null,
+    },
   }));
 }
 
+/**
+ * Usable imaging segments of a target over the night starting on `date`:
+ * dark time in which the constraints (the target's own unless given) are
+ * met, outside the meridian flip exclusion
+ */
+export async function calculateImagingWindow(
+  target: SimpleTarget,
+  location: ObserverLocation,
+  date: string,
+  constraints?: ObservingConstraints,
+  options?: ImagingWindowOptions,
+): Promise<ImagingWindow> {
+  return invoke<ImagingWindow>("calculate_imaging_window", {
+    target,
+    location,
+    date,
+    constraints,
+    options,
+  });
+}
+
 /**
  * Apply optimized order to sequence
  */
@@ -297,6 +447,27 @@ export async function splitSequenceByTarget(
   }));
 }
 
+/**
+ * Partition the sequence's remaining frames over a range of nights
+ */
+export async function splitSequenceAcrossNights(
+  sequence: SimpleSequence,
+  location: ObserverLocation,
+  startDate: string,
+  endDate: string,
+): Promise<NightSplitResult> {
+  if (isTauri()) {
+    return invoke<NightSplitResult>("split_sequence_across_nights", {
+      sequence,
+      location,
+      startDate,
+      endDate,
+    });
+  }
+
+  throw new Error("Multi-night planning requires desktop app");
+}
+
 /**
  * Get available optimization strategies
  */
@@ -356,6 +527,7 @@ export async function batchCalculateVisibility(
   location: ObserverLocation,
   date: string,
   minAltitude: number = 20,
+  operationId?: string,
 ): Promise<Array<{ id: string; visibility: VisibilityWindow }>> {
   if (isTauri()) {
     return invoke<Array<[string, VisibilityWindow]>>(
@@ -365,6 +537,7 @@ export async function batchCalculateVisibility(
         location,
         date,
         minAltitude,
+        operationId,
       },
     ).then((data) => data.map(([id, visibility]) => ({ id, visibility })));
   }
@@ -420,6 +593,7 @@ export async function findBestObservationDate(
   location: ObserverLocation,
   startDate: string,
   endDate: string,
+  operationId?: string,
 ): Promise<BestDateResult> {
   if (isTauri()) {
     return invoke<BestDateResult>("find_best_observation_date", {
@@ -427,6 +601,7 @@ export async function findBestObservationDate(
       location,
       startDate,
       endDate,
+      operationId,
     });
   }
 
@@ -450,6 +625,7 @@ export async function estimateSessionTime(
   location: ObserverLocation,
   date: string,
   includeSlewTime: boolean = true,
+  monteCarlo?: MonteCarloOptions,
 ): Promise<SessionTimeEstimate> {
   if (isTauri()) {
     return invoke<SessionTimeEstimate>("estimate_session_time", {
@@ -457,6 +633,7 @@ export async function estimateSessionTime(
       location,
       date,
       includeSlewTime,
+      monteCarlo,
     });
   }
 
@@ -475,6 +652,8 @@ export async function estimateSessionTime(
     slewTimeSeconds: slewTime,
     autofocusTimeSeconds: autofocusTime,
     centeringTimeSeconds: centeringTime,
+    ditherTimeSeconds: 0,
+    guidingTimeSeconds: 0,
     totalTimeSeconds: totalTime,
     availableDarkTimeSeconds: availableDarkTime,
     fitsInNight: totalTime <= availableDarkTime,
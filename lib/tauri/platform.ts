@@ -48,6 +48,93 @@ export async function getTauriCore() {
   }
 }
 
+/**
+ * Machine-readable kind of a failed command
+ */
+export type ErrorCode =
+  | "notFound"
+  | "invalidInput"
+  | "validation"
+  | "parse"
+  | "io"
+  | "permissionDenied"
+  | "network"
+  | "cancelled"
+  | "unsupported"
+  | "internal";
+
+/**
+ * Command payload field rejected by the input checks
+ */
+export interface FieldError {
+  /** Path of the field in the payload, such as `location.latitude` */
+  field: string;
+  message: string;
+}
+
+/**
+ * Error a command fails with
+ */
+export interface AppErrorPayload {
+  code: ErrorCode;
+  /** Message to show the user */
+  message: string;
+  /** What was being done when it failed */
+  context?: string;
+  /** Each problem found by a failed validation */
+  details?: string[];
+  /** Each payload field rejected before the command ran */
+  fields?: FieldError[];
+}
+
+/**
+ * Rejection of a Tauri command, carrying its error code
+ */
+export class CommandError extends Error {
+  readonly code: ErrorCode;
+  readonly context?: string;
+  readonly details?: string[];
+  readonly fields?: FieldError[];
+
+  constructor(
+    readonly command: string,
+    payload: AppErrorPayload,
+  ) {
+    super(payload.message);
+    this.name = "CommandError";
+    this.code = payload.code;
+    this.context = payload.context;
+    this.details = payload.details;
+    this.fields = payload.fields;
+  }
+
+  toString(): string {
+    return this.message;
+  }
+}
+
+function isAppErrorPayload(value: unknown): value is AppErrorPayload {
+  return (
+    typeof value === "object" &&
+    value !== null &&
+    typeof (value as AppErrorPayload).code === "string" &&
+    typeof (value as AppErrorPayload).message === "string"
+  );
+}
+
+/**
+ * Turn a command rejection into a `CommandError`
+ */
+export function toCommandError(command: string, error: unknown): unknown {
+  if (isAppErrorPayload(error)) {
+    return new CommandError(command, error);
+  }
+  if (typeof error === "string") {
+    return new CommandError(command, { code: "internal", message: error });
+  }
+  return error;
+}
+
 /**
  * Invoke a Tauri command with fallback
  */
@@ -59,7 +146,11 @@ export async function invoke<T>(
   if (!tauri) {
     throw new Error(`Tauri not available. Cannot invoke command: ${command}`);
   }
-  return tauri.invoke<T>(command, args);
+  try {
+    return await tauri.invoke<T>(command, args);
+  } catch (e) {
+    throw toCommandError(command, e);
+  }
 }
 
 /**
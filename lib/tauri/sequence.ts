@@ -12,7 +12,9 @@ import type {
   SimpleSequence,
   SimpleTarget,
   SimpleExposure,
+  FilterInfo,
 } from "../nina/simple-sequence-types";
+import type { EditorSequence } from "../nina/types";
 
 export interface ValidationResult {
   valid: boolean;
@@ -29,6 +31,49 @@ export interface SequenceStatistics {
   completedRuntime: number;
   remainingRuntime: number;
   progressPercentage: number;
+  filters: FilterIntegration[];
+  imageTypes: ImageTypeIntegration[];
+  exposureLengths: ExposureLengthCount[];
+  ditherEvents: number;
+  storage: StorageEstimate | null;
+}
+
+export interface FilterIntegration {
+  filter: string | null;
+  frameCount: number;
+  remainingFrames: number;
+  totalIntegration: number;
+  remainingIntegration: number;
+  percentOfIntegration: number;
+}
+
+export interface ImageTypeIntegration {
+  imageType: string;
+  frameCount: number;
+  totalIntegration: number;
+}
+
+export interface ExposureLengthCount {
+  exposureTime: number;
+  frameCount: number;
+  totalIntegration: number;
+}
+
+export interface TargetStorage {
+  targetId: string;
+  targetName: string;
+  totalBytes: number;
+  remainingBytes: number;
+}
+
+export interface StorageEstimate {
+  frameWidth: number;
+  frameHeight: number;
+  bitDepth: number;
+  bytesPerFrame: number;
+  totalBytes: number;
+  remainingBytes: number;
+  targets: TargetStorage[];
 }
 
 /**
@@ -257,14 +302,215 @@ export async function duplicateTarget(
   return newTarget;
 }
 
+export interface EditorItemDuplicate {
+  sequence: EditorSequence;
+  /** Id of the copy */
+  itemId: string;
+}
+
+/**
+ * Duplicate an editor item, inserting the copy named "Copy of ..." after
+ * the original. The copy and everything in it get fresh ids. With `deep`
+ * unset a container is copied without its items.
+ */
+export async function duplicateEditorItem(
+  sequence: EditorSequence,
+  itemId: string,
+  deep: boolean,
+): Promise<EditorItemDuplicate> {
+  return invoke<EditorItemDuplicate>("duplicate_editor_item", {
+    sequence,
+    itemId,
+    deep,
+  });
+}
+
+/** Parent id of the top-level items of each sequence area */
+export type EditorArea = "start" | "target" | "end";
+
+/**
+ * Move an editor item into a new parent at `index`, counted after the item
+ * is taken out. The parent is an area, a container or a trigger; moving a
+ * container into itself or anything inside it is rejected.
+ */
+export async function moveEditorItem(
+  sequence: EditorSequence,
+  itemId: string,
+  newParentId: EditorArea | string,
+  index: number,
+): Promise<EditorSequence> {
+  return invoke<EditorSequence>("move_editor_item", {
+    sequence,
+    itemId,
+    newParentId,
+    index,
+  });
+}
+
+/**
+ * Reorder the children of an area, container or trigger. `orderedIds`
+ * must list each child exactly once.
+ */
+export async function reorderItems(
+  sequence: EditorSequence,
+  parentId: EditorArea | string,
+  orderedIds: string[],
+): Promise<EditorSequence> {
+  return invoke<EditorSequence>("reorder_items", {
+    sequence,
+    parentId,
+    orderedIds,
+  });
+}
+
+/** Selects exposures across a sequence; empty fields match everything */
+export interface ExposureSelector {
+  targetIds?: string[];
+  /** Case-insensitive text in the target name */
+  targetNameContains?: string;
+  /** Case-insensitive filter name */
+  filterName?: string;
+  imageType?: ImageType;
+  enabledOnly?: boolean;
+}
+
+export interface ExposureToggleResult {
+  sequence: SimpleSequence;
+  /** Number of exposures whose `enabled` flag changed */
+  changed: number;
+}
+
+/**
+ * Enable or disable every exposure matched by `selector`, e.g. all Ha
+ * exposures for a moonlit night
+ */
+export async function setExposuresEnabled(
+  sequence: SimpleSequence,
+  enabled: boolean,
+  selector?: ExposureSelector,
+): Promise<ExposureToggleResult> {
+  return invoke<ExposureToggleResult>("set_exposures_enabled", {
+    sequence,
+    selector,
+    enabled,
+  });
+}
+
+/** A series of exposures whose exposure time or gain runs from start to end */
+export interface ExposureRamp {
+  start: number;
+  end: number;
+  steps: number;
+  /** Equal differences (linear) or equal ratios (logarithmic) between steps */
+  scaling?: "linear" | "logarithmic";
+  parameter?: "exposureTime" | "gain";
+}
+
+/**
+ * Append a ramp of exposures to a target, e.g. twilight flats or a bright
+ * core bracketed over several stops. Each step copies `template`.
+ */
+export async function generateExposureRamp(
+  sequence: SimpleSequence,
+  targetId: string,
+  ramp: ExposureRamp,
+  template?: SimpleExposure,
+): Promise<SimpleSequence> {
+  return invoke<SimpleSequence>("generate_exposure_ramp", {
+    sequence,
+    targetId,
+    ramp,
+    template,
+  });
+}
+
+export interface FilterOffsetResult {
+  sequence: EditorSequence;
+  /** Switch Filter items whose focus offset was changed */
+  updatedItemIds: string[];
+  /** Filters switched to without an offset or a refocus */
+  warnings: string[];
+}
+
+/**
+ * Write per-filter focus offsets (from the active filter set unless
+ * given) into the sequence's Switch Filter items
+ */
+export async function applyFilterOffsetsToSequence(
+  sequence: EditorSequence,
+  filters?: FilterInfo[],
+): Promise<FilterOffsetResult> {
+  return invoke<FilterOffsetResult>("apply_filter_offsets_to_sequence", {
+    sequence,
+    filters,
+  });
+}
+
+export interface ItemStatusResult {
+  sequence: EditorSequence;
+  /** Number of items whose status changed */
+  changed: number;
+}
+
+/**
+ * Set the status of several editor items at once. Fails without changing
+ * anything when an id is unknown.
+ */
+export async function setItemsStatus(
+  sequence: EditorSequence,
+  itemIds: string[],
+  status: SequenceEntityStatus,
+): Promise<ItemStatusResult> {
+  return invoke<ItemStatusResult>("set_items_status", {
+    sequence,
+    itemIds,
+    status,
+  });
+}
+
+export type NormalizationFixKind =
+  | "coordinates"
+  | "angle"
+  | "whitespace"
+  | "removedExposure"
+  | "removedTarget"
+  | "duplicateId";
+
+export interface NormalizationFix {
+  kind: NormalizationFixKind;
+  /** Id of the sequence, target or exposure, after any id change */
+  entityId: string;
+  description: string;
+}
+
+export interface NormalizationResult {
+  sequence: SimpleSequence;
+  fixes: NormalizationFix[];
+}
+
+/**
+ * Fix common defects in one pass: out-of-range coordinates, angles
+ * outside [0, 360), stray whitespace, exposures with no frames, targets
+ * without exposures, and missing or duplicate ids
+ */
+export async function normalizeSequence(
+  sequence: SimpleSequence,
+): Promise<NormalizationResult> {
+  return invoke<NormalizationResult>("normalize_sequence", { sequence });
+}
+
 /**
  * Get sequence statistics
  */
 export async function getSequenceStatistics(
   sequence: SimpleSequence,
+  equipmentProfileId?: string,
 ): Promise<SequenceStatistics> {
   if (isTauri()) {
-    return invoke<SequenceStatistics>("get_sequence_statistics", { sequence });
+    return invoke<SequenceStatistics>("get_sequence_statistics", {
+      sequence,
+      equipmentProfileId,
+    });
   }
 
   // Browser fallback
@@ -298,15 +544,30 @@ export async function getSequenceStatistics(
     completedRuntime,
     remainingRuntime,
     progressPercentage,
+    filters: [],
+    imageTypes: [],
+    exposureLengths: [],
+    ditherEvents: 0,
+    storage: null,
   };
 }
 
 /**
- * Generate unique ID
+ * Text form of an id, after the .NET GUID format specifiers D, N, B and P.
+ * NINA writes GUIDs hyphenated.
+ */
+export type IdFormat = "hyphenated" | "simple" | "braced" | "parenthesized";
+
+/**
+ * Generate unique ID, hyphenated unless another format is given. With a
+ * seed the id is derived from it and the same seed gives the same id.
  */
-export async function generateId(): Promise<string> {
+export async function generateId(
+  format?: IdFormat,
+  seed?: string,
+): Promise<string> {
   if (isTauri()) {
-    return invoke<string>("generate_id");
+    return invoke<string>("generate_id", { format, seed });
   }
   return crypto.randomUUID();
 }
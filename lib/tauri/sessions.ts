@@ -0,0 +1,147 @@
+/**
+ * Sequences held open by the backend and edited by session id
+ */
+
+import { isTauri, invoke } from "./platform";
+import type { SimpleSequence } from "../nina/simple-sequence-types";
+
+export interface SessionInfo {
+  id: string;
+  /** File the sequence was opened from or last saved to */
+  path?: string;
+  title: string;
+  targetCount: number;
+  /** Changed since opened or last saved */
+  dirty: boolean;
+  /** Number of updates applied since opened */
+  revision: number;
+  openedAt: string;
+}
+
+/**
+ * Open a simple sequence file in a backend session and return the session
+ * id. A file that is already open returns its existing session.
+ */
+export async function openSequence(path: string): Promise<string> {
+  return invoke<string>("open_sequence", { path });
+}
+
+/**
+ * Summaries of the open sequences, oldest first
+ */
+export async function getOpenSequences(): Promise<SessionInfo[]> {
+  if (isTauri()) {
+    return invoke<SessionInfo[]>("get_open_sequences");
+  }
+  return [];
+}
+
+/**
+ * Current sequence of a session
+ */
+export async function getOpenSequence(
+  sessionId: string,
+): Promise<SimpleSequence> {
+  return invoke<SimpleSequence>("get_open_sequence", { sessionId });
+}
+
+/**
+ * Apply a JSON merge patch (RFC 7396) to a session's sequence: objects
+ * merge, `null` removes a field and anything else replaces it
+ */
+export async function updateSequence(
+  sessionId: string,
+  patch: Record<string, unknown>,
+): Promise<SessionInfo> {
+  return invoke<SessionInfo>("update_sequence", { sessionId, patch });
+}
+
+/**
+ * JSON patch operation (RFC 6902) on a sequence's JSON form
+ */
+export type SequencePatchOperation =
+  | { op: "add" | "replace" | "test"; path: string; value: unknown }
+  | { op: "remove"; path: string }
+  | { op: "move" | "copy"; from: string; path: string };
+
+/**
+ * Apply a JSON patch (RFC 6902) to a session's sequence. All operations
+ * apply or none do. With `baseRevision`, the patch is refused when the
+ * sequence changed since that revision.
+ */
+export async function applySequencePatch(
+  sessionId: string,
+  patch: SequencePatchOperation[],
+  baseRevision?: number,
+): Promise<SessionInfo> {
+  return invoke<SessionInfo>("apply_sequence_patch", {
+    sessionId,
+    patch,
+    baseRevision,
+  });
+}
+
+/**
+ * Save a session's sequence, to `path` when given or else to the file it
+ * was opened from
+ */
+export async function saveOpenSequence(
+  sessionId: string,
+  path?: string,
+): Promise<SessionInfo> {
+  return invoke<SessionInfo>("save_open_sequence", { sessionId, path });
+}
+
+/**
+ * Close a session, saving it first when `save` is set. A failed save
+ * leaves the session open.
+ */
+export async function closeSequence(
+  sessionId: string,
+  save: boolean,
+): Promise<void> {
+  return invoke<void>("close_sequence", { sessionId, save });
+}
+
+/** Event emitted to every window when an open sequence changes */
+export const SESSION_CHANGED_EVENT = "session://changed";
+
+export interface SessionChange {
+  kind: "opened" | "updated" | "saved" | "closed";
+  session: SessionInfo;
+}
+
+export interface WindowBinding {
+  /** Tauri window label */
+  label: string;
+  sessionId: string;
+}
+
+/**
+ * Open a sequence in a new window, from a file or an already open session.
+ * The new window loads `simple?session=<id>`.
+ */
+export async function openSequenceInNewWindow(
+  source: { path: string } | { sessionId: string },
+): Promise<WindowBinding> {
+  return invoke<WindowBinding>("open_sequence_in_new_window", source);
+}
+
+/**
+ * Bind the current window to a session
+ */
+export async function bindWindowSession(
+  sessionId: string,
+): Promise<WindowBinding> {
+  return invoke<WindowBinding>("bind_window_session", { sessionId });
+}
+
+/**
+ * Session the current window is bound to, if any
+ */
+export async function getWindowSession(): Promise<SessionInfo | null> {
+  if (isTauri()) {
+    return invoke<SessionInfo | null>("get_window_session");
+  }
+  return null;
+}
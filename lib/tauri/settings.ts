@@ -10,6 +10,10 @@ export interface AppSettings {
   maxRecentFiles: number;
   autoSaveEnabled: boolean;
   autoSaveInterval: number;
+  /** Auto-save once this many edits are unsaved, 0 for the interval only */
+  autoSaveAfterEdits?: number;
+  /** Keep the ids of entities imported from NINA files stable on reimport */
+  stableNinaIds?: boolean;
   windowWidth?: number;
   windowHeight?: number;
   windowX?: number;
@@ -143,6 +147,52 @@ export async function clearRecentFiles(): Promise<void> {
   await saveSettings(settings);
 }
 
+/** Recent file as last opened, saved or exported by the app */
+export interface RecentFileMetadata {
+  targetCount?: number;
+  /** Light frame integration in seconds */
+  totalIntegration?: number;
+  /** Export format name, e.g. "ninaTargetSet" */
+  lastExportFormat?: string;
+}
+
+export interface RecentFileDetails extends RecentFileMetadata {
+  path: string;
+  pinned: boolean;
+  exists: boolean;
+  /** Title read from the file */
+  title?: string;
+  size?: number;
+  modified?: string;
+}
+
+/**
+ * Recent files with their metadata, pinned ones first. Each is checked for
+ * existence and its title read from the file.
+ */
+export async function getRecentFileDetails(): Promise<RecentFileDetails[]> {
+  if (isTauri()) {
+    return invoke<RecentFileDetails[]>("get_recent_file_details");
+  }
+
+  const settings = await loadSettings();
+  return settings.recentFiles.map((path) => ({
+    path,
+    pinned: false,
+    exists: true,
+  }));
+}
+
+/**
+ * Pin or unpin a recent file; pinned files never drop off the list
+ */
+export async function setRecentFilePinned(
+  path: string,
+  pinned: boolean,
+): Promise<void> {
+  return invoke<void>("set_recent_file_pinned", { path, pinned });
+}
+
 /**
  * Get last directory
  */
@@ -0,0 +1,112 @@
+//! Altitude sampling benchmarks
+//!
+//! Compares one `observed_alt_az` call per sample, as visibility windows
+//! used to be computed, with `altitude_curve`, for the workload of a
+//! best-date search: 200 targets over 30 nights at 10-minute samples.
+
+use chrono::{NaiveDate, TimeZone, Utc};
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use rayon::prelude::*;
+
+use app_lib::models::Coordinates;
+use app_lib::services::astronomy::{
+    altitude_curve, calculate_visibility_window, datetime_to_jd, observed_alt_az, ObserverLocation,
+};
+
+const TARGETS: usize = 200;
+const NIGHTS: usize = 30;
+const SAMPLES: usize = 145;
+
+fn location() -> ObserverLocation {
+    ObserverLocation {
+        latitude: 45.0,
+        longitude: 10.0,
+        elevation: 200.0,
+        timezone_offset: 1,
+        sky_brightness: None,
+        corrections: Default::default(),
+    }
+}
+
+fn targets() -> Vec<Coordinates> {
+    (0..TARGETS)
+        .map(|i| {
+            let ra = (i as f64 * 7.3) % 24.0;
+            let dec = (i as f64 * 13.7) % 140.0 - 50.0;
+            Coordinates::from_decimal(ra, dec)
+        })
+        .collect()
+}
+
+fn night_starts() -> Vec<f64> {
+    let first = datetime_to_jd(Utc.with_ymd_and_hms(2024, 10, 1, 0, 0, 0).unwrap());
+    (0..NIGHTS).map(|night| first + night as f64).collect()
+}
+
+fn point_samples(ra: f64, dec: f64, location: &ObserverLocation, jd_start: f64) -> Vec<f64> {
+    (0..SAMPLES)
+        .map(|i| observed_alt_az(ra, dec, location, jd_start + i as f64 / 144.0).0)
+        .collect()
+}
+
+fn bench_altitude_curves(c: &mut Criterion) {
+    let location = location();
+    let targets = targets();
+    let nights = night_starts();
+    let mut group = c.benchmark_group("altitude_curves_200x30");
+    group.sample_size(20);
+
+    group.bench_function("point_samples", |b| {
+        b.iter(|| {
+            for target in &targets {
+                let (ra, dec) = (target.ra_to_decimal(), target.dec_to_decimal());
+                for &jd in &nights {
+                    black_box(point_samples(ra, dec, &location, jd));
+                }
+            }
+        })
+    });
+
+    group.bench_function("altitude_curve", |b| {
+        b.iter(|| {
+            for target in &targets {
+                let (ra, dec) = (target.ra_to_decimal(), target.dec_to_decimal());
+                for &jd in &nights {
+                    black_box(altitude_curve(ra, dec, &location, jd, 1.0 / 144.0, SAMPLES));
+                }
+            }
+        })
+    });
+
+    group.bench_function("altitude_curve_parallel", |b| {
+        b.iter(|| {
+            targets.par_iter().for_each(|target| {
+                let (ra, dec) = (target.ra_to_decimal(), target.dec_to_decimal());
+                for &jd in &nights {
+                    black_box(altitude_curve(ra, dec, &location, jd, 1.0 / 144.0, SAMPLES));
+                }
+            })
+        })
+    });
+
+    group.finish();
+}
+
+fn bench_visibility_windows(c: &mut Criterion) {
+    let location = location();
+    let targets = targets();
+    let first = NaiveDate::from_ymd_opt(2024, 10, 1).unwrap();
+
+    c.bench_function("visibility_windows_200x30_parallel", |b| {
+        b.iter(|| {
+            targets.par_iter().for_each(|target| {
+                for date in first.iter_days().take(NIGHTS) {
+                    black_box(calculate_visibility_window(target, &location, date, 30.0));
+                }
+            })
+        })
+    });
+}
+
+criterion_group!(benches, bench_altitude_curves, bench_visibility_windows);
+criterion_main!(benches);
@@ -0,0 +1,410 @@
+//! Headless command line mode
+//!
+//! `cobalt-task-editor --cli <command> ...` runs one batch operation on the
+//! services layer without starting the GUI, for scripts and CI. Inputs are
+//! simple sequence JSON files or any format the importer understands.
+//!
+//! Exit status is 0 on success, 1 when the operation fails (including
+//! validation errors) and 2 on usage errors.
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use chrono::{NaiveDate, Utc};
+
+use crate::models::SimpleSequence;
+use crate::services::astronomy::ObserverLocation;
+use crate::services::export_service::{
+    export_sequence, export_sequence_multi, generate_session_report, ExportFormat, ExportOptions,
+    SessionReportOptions,
+};
+use crate::services::sequence_optimizer::{
+    apply_optimized_order, optimize_sequence, OptimizationStrategy,
+};
+use crate::services::{file_service, import_service, serializer, settings_service, validator};
+use crate::state::AppState;
+
+/// First argument that selects the CLI instead of the GUI
+pub const CLI_FLAG: &str = "--cli";
+
+const USAGE: &str = "\
+Usage: cobalt-task-editor --cli <command> [options]
+
+Commands:
+  validate <input>                        Validate a sequence
+  convert <input> --to <format> [-o <file>]
+                                          Convert to another format
+  export <input> --to <format,...> [--dir <directory>]
+                                          Export to several formats
+  optimize <input> [--strategy <name>] [--date <YYYY-MM-DD>] [-o <file>]
+                                          Reorder targets for a night
+  report <input> [--date <YYYY-MM-DD>] [-o <file>]
+                                          Write an HTML session report
+  help                                    Show this help
+
+Location options (optimize, report):
+  --lat <deg> --lon <deg> [--elevation <m>] [--timezone <h>]
+  --site <id>                             Saved observing site
+  Without either, the active observing site is used.
+
+Formats: csv, csv_telescopius, xml, xml_apt, stellarium, voyager,
+  voyager_robo_target, nina_target_set (nina), json
+Strategies: maxAltitude, transitTime, visibilityStart, visibilityDuration,
+  minimizeSlew, moonAvoidance, combined, priorityWeighted, timeWindowed
+
+Without -o, output is written to stdout.";
+
+const COMMANDS: [&str; 6] = [
+    "validate", "convert", "export", "optimize", "report", "help",
+];
+
+/// Parsed command line
+#[derive(Debug)]
+struct CliArgs {
+    command: String,
+    positional: Vec<String>,
+    options: HashMap<String, String>,
+}
+
+impl CliArgs {
+    fn input(&self) -> Result<&str, String> {
+        self.positional
+            .first()
+            .map(String::as_str)
+            .ok_or_else(|| format!("{} needs an input file", self.command))
+    }
+
+    fn option(&self, name: &str) -> Option<&str> {
+        self.options.get(name).map(String::as_str)
+    }
+
+    fn number(&self, name: &str) -> Result<Option<f64>, String> {
+        self.option(name)
+            .map(|value| {
+                value
+                    .parse::<f64>()
+                    .map_err(|_| format!("--{} must be a number: {}", name, value))
+            })
+            .transpose()
+    }
+
+    fn date(&self) -> Result<NaiveDate, String> {
+        match self.option("date") {
+            Some(date) => NaiveDate::parse_from_str(date, "%Y-%m-%d")
+                .map_err(|e| format!("Invalid date format: {}", e)),
+            None => Ok(Utc::now().date_naive()),
+        }
+    }
+}
+
+fn parse_args(args: &[String]) -> Result<CliArgs, String> {
+    let mut iter = args.iter();
+    let command = iter.next().ok_or("No command given")?.clone();
+    if !COMMANDS.contains(&command.as_str()) {
+        return Err(format!("Unknown command: {}", command));
+    }
+
+    let mut positional = Vec::new();
+    let mut options = HashMap::new();
+    while let Some(arg) = iter.next() {
+        let name = match arg.as_str() {
+            "-o" => "output",
+            arg => match arg.strip_prefix("--") {
+                Some(name) => name,
+                None => {
+                    positional.push(arg.to_string());
+                    continue;
+                }
+            },
+        };
+        let value = iter
+            .next()
+            .ok_or_else(|| format!("Missing value for {}", arg))?;
+        options.insert(name.to_string(), value.clone());
+    }
+
+    Ok(CliArgs {
+        command,
+        positional,
+        options,
+    })
+}
+
+/// Run the CLI with the arguments after [`CLI_FLAG`] and return the exit
+/// status
+pub fn run(args: &[String]) -> i32 {
+    let args = match parse_args(args) {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, USAGE);
+            return 2;
+        }
+    };
+    if args.command == "help" {
+        println!("{}", USAGE);
+        return 0;
+    }
+
+    let runtime = match tokio::runtime::Runtime::new() {
+        Ok(runtime) => runtime,
+        Err(e) => {
+            eprintln!("error: Failed to start runtime: {}", e);
+            return 1;
+        }
+    };
+    match runtime.block_on(execute(&args)) {
+        Ok(true) => 0,
+        Ok(false) => 1,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            1
+        }
+    }
+}
+
+/// Run a command, returning whether it succeeded
+async fn execute(args: &CliArgs) -> Result<bool, String> {
+    match args.command.as_str() {
+        "validate" => validate(args).await,
+        "convert" => convert(args).await,
+        "export" => export(args).await,
+        "optimize" => optimize(args).await,
+        "report" => report(args).await,
+        other => Err(format!("Unknown command: {}", other)),
+    }
+}
+
+/// Load a simple sequence file, or import targets from another format
+async fn load_sequence(path: &str) -> Result<SimpleSequence, String> {
+    let content = tokio::fs::read_to_string(path)
+        .await
+        .map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    if let Ok(sequence) = serializer::deserialize_simple_sequence_json(&content) {
+        return Ok(sequence);
+    }
+
+    let path = Path::new(path);
+    let extension = path
+        .extension()
+        .map(|e| e.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let result = import_service::detect_and_import(&content, &extension)?;
+    for warning in &result.warnings {
+        eprintln!("warning: {}", warning);
+    }
+    if result.targets.is_empty() {
+        return Err(format!(
+            "No targets found in {}: {}",
+            path.display(),
+            result.errors.join("; ")
+        ));
+    }
+
+    let title = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "Imported Sequence".to_string());
+    let mut sequence = SimpleSequence::new(title);
+    sequence.targets = result.targets;
+    Ok(sequence)
+}
+
+async fn write_output(args: &CliArgs, content: &str) -> Result<(), String> {
+    match args.option("output") {
+        Some(path) => file_service::write_file(Path::new(path), content)
+            .await
+            .map_err(|e| e.to_string()),
+        None => {
+            println!("{}", content);
+            Ok(())
+        }
+    }
+}
+
+/// Observer location from `--lat`/`--lon`, or from the saved sites
+async fn location(state: &AppState, args: &CliArgs) -> Result<ObserverLocation, String> {
+    match (args.number("lat")?, args.number("lon")?) {
+        (Some(latitude), Some(longitude)) => Ok(ObserverLocation {
+            latitude,
+            longitude,
+            elevation: args.number("elevation")?.unwrap_or(0.0),
+            timezone_offset: args.number("timezone")?.unwrap_or(0.0) as i32,
+            ..Default::default()
+        }),
+        (None, None) => {
+            settings_service::load_settings(state).await?;
+            settings_service::resolve_location(state, None, args.option("site").map(str::to_string))
+                .map_err(String::from)
+        }
+        _ => Err("--lat and --lon must be given together".to_string()),
+    }
+}
+
+fn export_formats(args: &CliArgs) -> Result<Vec<ExportFormat>, String> {
+    let names = args.option("to").ok_or("Missing --to <format>")?;
+    names
+        .split(',')
+        .map(|name| {
+            ExportFormat::from_name(name.trim())
+                .ok_or_else(|| format!("Unknown export format: {}", name))
+        })
+        .collect()
+}
+
+async fn validate(args: &CliArgs) -> Result<bool, String> {
+    let sequence = load_sequence(args.input()?).await?;
+    let result = validator::validate_simple_sequence(&sequence);
+
+    for error in &result.errors {
+        println!("error: {}", error);
+    }
+    for warning in &result.warnings {
+        println!("warning: {}", warning);
+    }
+    println!(
+        "{}: {} error(s), {} warning(s)",
+        sequence.title,
+        result.errors.len(),
+        result.warnings.len()
+    );
+    Ok(result.valid)
+}
+
+async fn convert(args: &CliArgs) -> Result<bool, String> {
+    let sequence = load_sequence(args.input()?).await?;
+    let formats = export_formats(args)?;
+    let [format] = formats[..] else {
+        return Err("convert takes a single --to format; use export for several".to_string());
+    };
+
+    let result = export_sequence(
+        &sequence,
+        &ExportOptions {
+            format,
+            ..Default::default()
+        },
+    );
+    for error in &result.errors {
+        eprintln!("error: {}", error);
+    }
+    if result.success {
+        write_output(args, &result.content).await?;
+    }
+    Ok(result.success)
+}
+
+async fn export(args: &CliArgs) -> Result<bool, String> {
+    let sequence = load_sequence(args.input()?).await?;
+    let formats = export_formats(args)?;
+    let directory = Path::new(args.option("dir").unwrap_or("."));
+
+    let results =
+        export_sequence_multi(&sequence, &formats, &ExportOptions::default(), directory).await;
+    for result in &results {
+        match &result.path {
+            Some(path) => println!("{:?}: {}", result.format, path),
+            None => eprintln!("{:?}: {}", result.format, result.errors.join("; ")),
+        }
+    }
+    Ok(results.iter().all(|r| r.success))
+}
+
+async fn optimize(args: &CliArgs) -> Result<bool, String> {
+    let mut sequence = load_sequence(args.input()?).await?;
+    let state = AppState::default();
+    let location = location(&state, args).await?;
+    let strategy = match args.option("strategy") {
+        Some(name) => serde_json::from_value::<OptimizationStrategy>(name.into())
+            .map_err(|_| format!("Unknown strategy: {}", name))?,
+        None => OptimizationStrategy::Combined,
+    };
+
+    let mount = settings_service::get_active_equipment_profile(&state)
+        .map(|p| p.mount)
+        .unwrap_or_default();
+    let result = optimize_sequence(&sequence, &location, args.date()?, strategy, &mount);
+    for improvement in &result.improvements {
+        eprintln!("{}", improvement);
+    }
+    for warning in &result.warnings {
+        eprintln!("warning: {}", warning);
+    }
+    if result.success {
+        apply_optimized_order(&mut sequence, &result.optimized_order);
+        let json =
+            serializer::serialize_simple_sequence_json(&sequence).map_err(|e| e.to_string())?;
+        write_output(args, &json).await?;
+    }
+    Ok(result.success)
+}
+
+async fn report(args: &CliArgs) -> Result<bool, String> {
+    let sequence = load_sequence(args.input()?).await?;
+    let location = location(&AppState::default(), args).await?;
+
+    let result = generate_session_report(
+        &sequence,
+        &location,
+        args.date()?,
+        &SessionReportOptions::default(),
+    );
+    for error in &result.errors {
+        eprintln!("error: {}", error);
+    }
+    if result.success {
+        write_output(args, &result.content).await?;
+    }
+    Ok(result.success)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_args() {
+        let parsed = parse_args(&args(&[
+            "convert", "in.csv", "--to", "nina", "-o", "out.json",
+        ]))
+        .unwrap();
+        assert_eq!(parsed.command, "convert");
+        assert_eq!(parsed.input().unwrap(), "in.csv");
+        assert_eq!(parsed.option("output"), Some("out.json"));
+        assert_eq!(
+            export_formats(&parsed).unwrap(),
+            vec![ExportFormat::NinaTargetSet]
+        );
+
+        assert!(parse_args(&args(&["frobnicate"])).is_err());
+        assert!(parse_args(&args(&["convert", "in.csv", "--to"])).is_err());
+        assert!(parse_args(&args(&[])).is_err());
+    }
+
+    #[test]
+    fn test_run_convert() {
+        let dir = std::env::temp_dir().join(format!("cobalt-cli-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let input = dir.join("targets.csv");
+        std::fs::write(&input, "Name,RA,Dec\nM31,00:42:44,+41:16:09\n").unwrap();
+        let output = dir.join("targets.skylist.txt");
+
+        let status = run(&args(&[
+            "convert",
+            &input.display().to_string(),
+            "--to",
+            "stellarium",
+            "-o",
+            &output.display().to_string(),
+        ]));
+        assert_eq!(status, 0);
+        assert!(std::fs::read_to_string(&output).unwrap().contains("M31"));
+
+        assert_eq!(run(&args(&["validate", "missing.json"])), 1);
+        assert_eq!(run(&args(&["convert"])), 1);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
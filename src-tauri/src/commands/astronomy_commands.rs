@@ -2,28 +2,43 @@
 //!
 //! Tauri commands for advanced astronomical calculations
 
-use chrono::{DateTime, NaiveDate, Utc};
-use tauri::command;
+use chrono::{DateTime, Datelike, Utc};
+use tauri::{command, State};
 
-use crate::models::Coordinates;
+use crate::error::{AppError, ResultExt};
+use crate::models::{CoordinateEpoch, Coordinates, MovingTarget, SimpleSequence};
+use crate::services::altitude_curve::{self, AltitudeCurve, CurveCacheStats};
 use crate::services::astronomy::{
-    batch_calculate_positions, calculate_observation_quality, calculate_twilight,
-    calculate_visibility_window, datetime_to_jd, find_optimal_observation_time,
-    get_moon_phase_info, moon_illumination, moon_position, ra_dec_to_alt_az, sun_position,
-    BatchCoordinateResult, CelestialPosition, MoonPhaseInfo, ObservationQuality, ObserverLocation,
-    TwilightTimes, VisibilityWindow,
+    apparent_coordinates, batch_calculate_positions, calculate_observation_quality,
+    calculate_twilight, calculate_visibility_window, datetime_to_jd, epoch_year_to_jd,
+    find_optimal_observation_time, get_moon_phase_info, moon_illumination, moon_position,
+    observed_alt_az, sun_position, BatchCoordinateResult, CelestialPosition, MoonPhaseInfo,
+    ObservationQuality, ObserverLocation, RiseSetTimes, TwilightTimes, VisibilityWindow,
 };
+use crate::services::dark_calendar::{self, DarkCalendar};
+use crate::services::dso_catalog::{self, CatalogFilter, DeepSkyObject};
+use crate::services::job_queue::JobHandle;
+use crate::services::satellite::{self, SatelliteTransit, Tle, TleImportResult};
+use crate::services::target_recommendation::{self, FieldOfView, TargetRecommendation};
+use crate::services::target_season::{self, TargetSeason};
+use crate::services::weather::{self, NightForecast, WeatherProviderInfo};
+use crate::services::{path_guard, settings_service};
+use crate::state::SharedState;
+
+use super::input;
 
 /// Calculate visibility window for a target
 #[command]
 pub async fn calculate_target_visibility(
+    state: State<'_, SharedState>,
     coordinates: Coordinates,
-    location: ObserverLocation,
+    location: Option<ObserverLocation>,
+    site_id: Option<String>,
     date: String,
     min_altitude: f64,
-) -> Result<VisibilityWindow, String> {
-    let date = NaiveDate::parse_from_str(&date, "%Y-%m-%d")
-        .map_err(|e| format!("Invalid date format: {}", e))?;
+) -> Result<VisibilityWindow, AppError> {
+    let location = input::location(&state, location, site_id)?;
+    let date = input::date("date", &date)?;
 
     Ok(calculate_visibility_window(
         &coordinates,
@@ -33,24 +48,51 @@ pub async fn calculate_target_visibility(
     ))
 }
 
+/// Rise, transit and set times of a target over the night starting on
+/// `date`, above `min_altitude` (0 by default) and the site's horizon
+/// profile
+#[command]
+pub async fn calculate_target_rise_set(
+    state: State<'_, SharedState>,
+    coordinates: Coordinates,
+    location: Option<ObserverLocation>,
+    site_id: Option<String>,
+    date: String,
+    min_altitude: Option<f64>,
+) -> Result<RiseSetTimes, AppError> {
+    let site = settings_service::resolve_site(&state, location.is_some(), site_id.as_deref());
+    let location = input::location(&state, location, site_id)?;
+    let date = input::date("date", &date)?;
+
+    Ok(crate::services::astronomy::calculate_target_rise_set(
+        &coordinates,
+        &location,
+        date,
+        min_altitude.unwrap_or(0.0),
+        site.as_ref(),
+    ))
+}
+
 /// Calculate twilight times for a location and date
 #[command]
 pub async fn calculate_twilight_times(
-    location: ObserverLocation,
+    state: State<'_, SharedState>,
+    location: Option<ObserverLocation>,
+    site_id: Option<String>,
     date: String,
-) -> Result<TwilightTimes, String> {
-    let date = NaiveDate::parse_from_str(&date, "%Y-%m-%d")
-        .map_err(|e| format!("Invalid date format: {}", e))?;
+) -> Result<TwilightTimes, AppError> {
+    let location = input::location(&state, location, site_id)?;
+    let date = input::date("date", &date)?;
 
     Ok(calculate_twilight(&location, date))
 }
 
 /// Get Moon phase information
 #[command]
-pub async fn get_moon_phase(datetime: Option<String>) -> Result<MoonPhaseInfo, String> {
+pub async fn get_moon_phase(datetime: Option<String>) -> Result<MoonPhaseInfo, AppError> {
     let dt = match datetime {
         Some(s) => DateTime::parse_from_rfc3339(&s)
-            .map_err(|e| format!("Invalid datetime format: {}", e))?
+            .map_err(|e| AppError::InvalidInput(format!("Invalid datetime format: {}", e)))?
             .with_timezone(&Utc),
         None => Utc::now(),
     };
@@ -61,13 +103,16 @@ pub async fn get_moon_phase(datetime: Option<String>) -> Result<MoonPhaseInfo, S
 /// Calculate observation quality score
 #[command]
 pub async fn calculate_quality_score(
+    state: State<'_, SharedState>,
     coordinates: Coordinates,
-    location: ObserverLocation,
+    location: Option<ObserverLocation>,
+    site_id: Option<String>,
     datetime: Option<String>,
-) -> Result<ObservationQuality, String> {
+) -> Result<ObservationQuality, AppError> {
+    let location = input::location(&state, location, site_id)?;
     let dt = match datetime {
         Some(s) => DateTime::parse_from_rfc3339(&s)
-            .map_err(|e| format!("Invalid datetime format: {}", e))?
+            .map_err(|e| AppError::InvalidInput(format!("Invalid datetime format: {}", e)))?
             .with_timezone(&Utc),
         None => Utc::now(),
     };
@@ -78,13 +123,15 @@ pub async fn calculate_quality_score(
 /// Find optimal observation time for a target
 #[command]
 pub async fn find_optimal_time(
+    state: State<'_, SharedState>,
     coordinates: Coordinates,
-    location: ObserverLocation,
+    location: Option<ObserverLocation>,
+    site_id: Option<String>,
     date: String,
     min_altitude: f64,
-) -> Result<Option<String>, String> {
-    let date = NaiveDate::parse_from_str(&date, "%Y-%m-%d")
-        .map_err(|e| format!("Invalid date format: {}", e))?;
+) -> Result<Option<String>, AppError> {
+    let location = input::location(&state, location, site_id)?;
+    let date = input::date("date", &date)?;
 
     let result = find_optimal_observation_time(&coordinates, &location, date, min_altitude);
     Ok(result.map(|dt| dt.to_rfc3339()))
@@ -93,14 +140,17 @@ pub async fn find_optimal_time(
 /// Batch calculate positions for multiple targets
 #[command]
 pub async fn batch_calculate_target_positions(
+    state: State<'_, SharedState>,
     targets: Vec<(String, Coordinates)>,
-    location: ObserverLocation,
+    location: Option<ObserverLocation>,
+    site_id: Option<String>,
     datetime: Option<String>,
     min_altitude: f64,
-) -> Result<Vec<BatchCoordinateResult>, String> {
+) -> Result<Vec<BatchCoordinateResult>, AppError> {
+    let location = input::location(&state, location, site_id)?;
     let dt = match datetime {
         Some(s) => DateTime::parse_from_rfc3339(&s)
-            .map_err(|e| format!("Invalid datetime format: {}", e))?
+            .map_err(|e| AppError::InvalidInput(format!("Invalid datetime format: {}", e)))?
             .with_timezone(&Utc),
         None => Utc::now(),
     };
@@ -116,19 +166,22 @@ pub async fn batch_calculate_target_positions(
 /// Get Sun position
 #[command]
 pub async fn get_sun_position(
-    location: ObserverLocation,
+    state: State<'_, SharedState>,
+    location: Option<ObserverLocation>,
+    site_id: Option<String>,
     datetime: Option<String>,
-) -> Result<CelestialPosition, String> {
+) -> Result<CelestialPosition, AppError> {
+    let location = input::location(&state, location, site_id)?;
     let dt = match datetime {
         Some(s) => DateTime::parse_from_rfc3339(&s)
-            .map_err(|e| format!("Invalid datetime format: {}", e))?
+            .map_err(|e| AppError::InvalidInput(format!("Invalid datetime format: {}", e)))?
             .with_timezone(&Utc),
         None => Utc::now(),
     };
 
     let jd = datetime_to_jd(dt);
     let (ra, dec) = sun_position(jd);
-    let (alt, az) = ra_dec_to_alt_az(ra, dec, location.latitude, location.longitude, jd);
+    let (alt, az) = observed_alt_az(ra, dec, &location, jd);
 
     Ok(CelestialPosition {
         altitude: alt,
@@ -142,19 +195,22 @@ pub async fn get_sun_position(
 /// Get Moon position
 #[command]
 pub async fn get_moon_position(
-    location: ObserverLocation,
+    state: State<'_, SharedState>,
+    location: Option<ObserverLocation>,
+    site_id: Option<String>,
     datetime: Option<String>,
-) -> Result<CelestialPosition, String> {
+) -> Result<CelestialPosition, AppError> {
+    let location = input::location(&state, location, site_id)?;
     let dt = match datetime {
         Some(s) => DateTime::parse_from_rfc3339(&s)
-            .map_err(|e| format!("Invalid datetime format: {}", e))?
+            .map_err(|e| AppError::InvalidInput(format!("Invalid datetime format: {}", e)))?
             .with_timezone(&Utc),
         None => Utc::now(),
     };
 
     let jd = datetime_to_jd(dt);
     let (ra, dec, distance) = moon_position(jd);
-    let (alt, az) = ra_dec_to_alt_az(ra, dec, location.latitude, location.longitude, jd);
+    let (alt, az) = observed_alt_az(ra, dec, &location, jd);
 
     Ok(CelestialPosition {
         altitude: alt,
@@ -168,13 +224,16 @@ pub async fn get_moon_position(
 /// Calculate altitude and azimuth for coordinates
 #[command]
 pub async fn calculate_alt_az(
+    state: State<'_, SharedState>,
     coordinates: Coordinates,
-    location: ObserverLocation,
+    location: Option<ObserverLocation>,
+    site_id: Option<String>,
     datetime: Option<String>,
-) -> Result<(f64, f64), String> {
+) -> Result<(f64, f64), AppError> {
+    let location = input::location(&state, location, site_id)?;
     let dt = match datetime {
         Some(s) => DateTime::parse_from_rfc3339(&s)
-            .map_err(|e| format!("Invalid datetime format: {}", e))?
+            .map_err(|e| AppError::InvalidInput(format!("Invalid datetime format: {}", e)))?
             .with_timezone(&Utc),
         None => Utc::now(),
     };
@@ -183,18 +242,12 @@ pub async fn calculate_alt_az(
     let ra = coordinates.ra_to_decimal();
     let dec = coordinates.dec_to_decimal();
 
-    Ok(ra_dec_to_alt_az(
-        ra,
-        dec,
-        location.latitude,
-        location.longitude,
-        jd,
-    ))
+    Ok(observed_alt_az(ra, dec, &location, jd))
 }
 
 /// Get current Moon illumination percentage
 #[command]
-pub async fn get_moon_illumination_now() -> Result<f64, String> {
+pub async fn get_moon_illumination_now() -> Result<f64, AppError> {
     let jd = datetime_to_jd(Utc::now());
     Ok(moon_illumination(jd))
 }
@@ -202,20 +255,16 @@ pub async fn get_moon_illumination_now() -> Result<f64, String> {
 /// Calculate multiple visibility windows for a date range
 #[command]
 pub async fn calculate_visibility_range(
+    state: State<'_, SharedState>,
     coordinates: Coordinates,
-    location: ObserverLocation,
+    location: Option<ObserverLocation>,
+    site_id: Option<String>,
     start_date: String,
     end_date: String,
     min_altitude: f64,
-) -> Result<Vec<VisibilityWindow>, String> {
-    let start = NaiveDate::parse_from_str(&start_date, "%Y-%m-%d")
-        .map_err(|e| format!("Invalid start date: {}", e))?;
-    let end = NaiveDate::parse_from_str(&end_date, "%Y-%m-%d")
-        .map_err(|e| format!("Invalid end date: {}", e))?;
-
-    if end < start {
-        return Err("End date must be after start date".to_string());
-    }
+) -> Result<Vec<VisibilityWindow>, AppError> {
+    let location = input::location(&state, location, site_id)?;
+    let (start, end) = input::date_range(&start_date, &end_date)?;
 
     let mut results = Vec::new();
     let mut current = start;
@@ -236,18 +285,14 @@ pub async fn calculate_visibility_range(
 /// Calculate twilight times for a date range
 #[command]
 pub async fn calculate_twilight_range(
-    location: ObserverLocation,
+    state: State<'_, SharedState>,
+    location: Option<ObserverLocation>,
+    site_id: Option<String>,
     start_date: String,
     end_date: String,
-) -> Result<Vec<TwilightTimes>, String> {
-    let start = NaiveDate::parse_from_str(&start_date, "%Y-%m-%d")
-        .map_err(|e| format!("Invalid start date: {}", e))?;
-    let end = NaiveDate::parse_from_str(&end_date, "%Y-%m-%d")
-        .map_err(|e| format!("Invalid end date: {}", e))?;
-
-    if end < start {
-        return Err("End date must be after start date".to_string());
-    }
+) -> Result<Vec<TwilightTimes>, AppError> {
+    let location = input::location(&state, location, site_id)?;
+    let (start, end) = input::date_range(&start_date, &end_date)?;
 
     let mut results = Vec::new();
     let mut current = start;
@@ -260,16 +305,97 @@ pub async fn calculate_twilight_range(
     Ok(results)
 }
 
+/// Generate the darkness and moon calendar of a month (`YYYY-MM`)
+#[command]
+pub async fn generate_dark_calendar(
+    state: State<'_, SharedState>,
+    location: Option<ObserverLocation>,
+    site_id: Option<String>,
+    month: String,
+) -> Result<DarkCalendar, AppError> {
+    let location = input::location(&state, location, site_id)?;
+    let first = input::month("month", &month)?;
+
+    dark_calendar::generate_dark_calendar(&location, first.year(), first.month())
+        .map_err(AppError::from)
+}
+
+/// Search the built-in deep-sky catalog
+#[command]
+pub async fn search_dso_catalog(filter: CatalogFilter) -> Result<Vec<DeepSkyObject>, AppError> {
+    Ok(dso_catalog::search_catalog(&filter)
+        .into_iter()
+        .copied()
+        .collect())
+}
+
+/// Rank catalog objects for the night starting on `date`. Without an
+/// explicit field of view the active equipment profile's is used.
+#[command]
+#[allow(clippy::too_many_arguments)]
+pub async fn recommend_targets_tonight(
+    state: State<'_, SharedState>,
+    location: Option<ObserverLocation>,
+    site_id: Option<String>,
+    date: String,
+    filter: Option<CatalogFilter>,
+    fov: Option<FieldOfView>,
+    min_altitude: Option<f64>,
+    limit: Option<usize>,
+) -> Result<Vec<TargetRecommendation>, AppError> {
+    let location = input::location(&state, location, site_id)?;
+    let date = input::date("date", &date)?;
+    let fov = fov.or_else(|| {
+        settings_service::get_active_equipment_profile(&state)
+            .and_then(|profile| FieldOfView::from_profile(&profile))
+    });
+
+    Ok(target_recommendation::recommend_targets_tonight(
+        &location,
+        date,
+        &filter.unwrap_or_default(),
+        fov.as_ref(),
+        min_altitude.unwrap_or(30.0),
+        limit.unwrap_or(10),
+    ))
+}
+
+/// Usable dark hours of a target for each night of a year and its best
+/// imaging season
+#[command]
+pub async fn calculate_target_season(
+    state: State<'_, SharedState>,
+    coordinates: Coordinates,
+    location: Option<ObserverLocation>,
+    site_id: Option<String>,
+    year: i32,
+    min_altitude: Option<f64>,
+    min_dark_hours: Option<f64>,
+) -> Result<TargetSeason, AppError> {
+    let location = input::location(&state, location, site_id)?;
+    target_season::calculate_target_season(
+        &coordinates,
+        &location,
+        year,
+        min_altitude.unwrap_or(30.0),
+        min_dark_hours.unwrap_or(2.0),
+        &JobHandle::detached(),
+    )
+    .map_err(AppError::from)
+}
+
 /// Convert RA/Dec to Alt/Az for a time range (for plotting)
 #[command]
 pub async fn calculate_altitude_curve(
+    state: State<'_, SharedState>,
     coordinates: Coordinates,
-    location: ObserverLocation,
+    location: Option<ObserverLocation>,
+    site_id: Option<String>,
     date: String,
     interval_minutes: i32,
-) -> Result<Vec<(String, f64, f64)>, String> {
-    let date = NaiveDate::parse_from_str(&date, "%Y-%m-%d")
-        .map_err(|e| format!("Invalid date format: {}", e))?;
+) -> Result<Vec<(String, f64, f64)>, AppError> {
+    let location = input::location(&state, location, site_id)?;
+    let date = input::date("date", &date)?;
 
     let ra = coordinates.ra_to_decimal();
     let dec = coordinates.dec_to_decimal();
@@ -282,24 +408,61 @@ pub async fn calculate_altitude_curve(
     for i in 0..(24 * 60 / interval) {
         let dt = start + chrono::Duration::minutes(i * interval);
         let jd = datetime_to_jd(dt);
-        let (alt, az) = ra_dec_to_alt_az(ra, dec, location.latitude, location.longitude, jd);
+        let (alt, az) = observed_alt_az(ra, dec, &location, jd);
         results.push((dt.to_rfc3339(), alt, az));
     }
 
     Ok(results)
 }
 
+/// Get a sampled altitude/azimuth/airmass/moon separation curve for a
+/// night, served from cache when the same curve was requested before
+#[command]
+pub async fn get_altitude_curve(
+    state: State<'_, SharedState>,
+    coordinates: Coordinates,
+    location: Option<ObserverLocation>,
+    site_id: Option<String>,
+    date: String,
+    interval_minutes: u32,
+) -> Result<AltitudeCurve, AppError> {
+    let location = input::location(&state, location, site_id)?;
+    let date = input::date("date", &date)?;
+
+    let curve =
+        altitude_curve::get_altitude_curve(&state, &coordinates, &location, date, interval_minutes);
+    Ok((*curve).clone())
+}
+
+/// Clear cached altitude curves
+#[command]
+pub async fn clear_altitude_curve_cache(state: State<'_, SharedState>) -> Result<(), AppError> {
+    altitude_curve::clear_curve_cache(&state);
+    Ok(())
+}
+
+/// Get altitude curve cache statistics
+#[command]
+pub async fn get_altitude_curve_cache_stats(
+    state: State<'_, SharedState>,
+) -> Result<CurveCacheStats, AppError> {
+    Ok(altitude_curve::get_curve_cache_stats(&state))
+}
+
 /// Check if target is currently above horizon
 #[command]
 pub async fn is_target_visible(
+    state: State<'_, SharedState>,
     coordinates: Coordinates,
-    location: ObserverLocation,
+    location: Option<ObserverLocation>,
+    site_id: Option<String>,
     min_altitude: f64,
-) -> Result<bool, String> {
+) -> Result<bool, AppError> {
+    let location = input::location(&state, location, site_id)?;
     let jd = datetime_to_jd(Utc::now());
     let ra = coordinates.ra_to_decimal();
     let dec = coordinates.dec_to_decimal();
-    let (alt, _) = ra_dec_to_alt_az(ra, dec, location.latitude, location.longitude, jd);
+    let (alt, _) = observed_alt_az(ra, dec, &location, jd);
 
     Ok(alt >= min_altitude)
 }
@@ -307,13 +470,16 @@ pub async fn is_target_visible(
 /// Get air mass for current position
 #[command]
 pub async fn calculate_air_mass(
+    state: State<'_, SharedState>,
     coordinates: Coordinates,
-    location: ObserverLocation,
+    location: Option<ObserverLocation>,
+    site_id: Option<String>,
     datetime: Option<String>,
-) -> Result<Option<f64>, String> {
+) -> Result<Option<f64>, AppError> {
+    let location = input::location(&state, location, site_id)?;
     let dt = match datetime {
         Some(s) => DateTime::parse_from_rfc3339(&s)
-            .map_err(|e| format!("Invalid datetime format: {}", e))?
+            .map_err(|e| AppError::InvalidInput(format!("Invalid datetime format: {}", e)))?
             .with_timezone(&Utc),
         None => Utc::now(),
     };
@@ -321,7 +487,192 @@ pub async fn calculate_air_mass(
     let jd = datetime_to_jd(dt);
     let ra = coordinates.ra_to_decimal();
     let dec = coordinates.dec_to_decimal();
-    let (alt, _) = ra_dec_to_alt_az(ra, dec, location.latitude, location.longitude, jd);
+    let (alt, _) = observed_alt_az(ra, dec, &location, jd);
 
     Ok(crate::services::astronomy::air_mass(alt))
 }
+
+/// Precess coordinates between two Julian epoch years (e.g. 2000.0 -> 2025.5)
+#[command]
+pub async fn precess_coordinates(
+    coordinates: Coordinates,
+    from_epoch: f64,
+    to_epoch: f64,
+) -> Result<Coordinates, AppError> {
+    Ok(crate::services::astronomy::precess_coordinates(
+        &coordinates,
+        epoch_year_to_jd(from_epoch),
+        epoch_year_to_jd(to_epoch),
+    ))
+}
+
+/// Get apparent (JNow) coordinates for J2000 coordinates
+#[command]
+pub async fn get_apparent_coordinates(
+    coordinates: Coordinates,
+    datetime: Option<String>,
+) -> Result<Coordinates, AppError> {
+    let dt = match datetime {
+        Some(s) => DateTime::parse_from_rfc3339(&s)
+            .map_err(|e| AppError::InvalidInput(format!("Invalid datetime format: {}", e)))?
+            .with_timezone(&Utc),
+        None => Utc::now(),
+    };
+
+    Ok(apparent_coordinates(&coordinates, datetime_to_jd(dt)))
+}
+
+/// Convert coordinates between J2000 and JNow
+#[command]
+pub async fn convert_coordinates_epoch(
+    coordinates: Coordinates,
+    epoch: CoordinateEpoch,
+    datetime: Option<String>,
+) -> Result<Coordinates, AppError> {
+    let dt = match datetime {
+        Some(s) => DateTime::parse_from_rfc3339(&s)
+            .map_err(|e| AppError::InvalidInput(format!("Invalid datetime format: {}", e)))?
+            .with_timezone(&Utc),
+        None => Utc::now(),
+    };
+
+    Ok(crate::services::astronomy::convert_coordinates_epoch(
+        &coordinates,
+        epoch,
+        datetime_to_jd(dt),
+    ))
+}
+
+/// Calculate the position of a comet/asteroid at a given time
+#[command]
+pub async fn calculate_moving_target_position(
+    moving_target: MovingTarget,
+    datetime: Option<String>,
+) -> Result<Coordinates, AppError> {
+    let dt = match datetime {
+        Some(s) => DateTime::parse_from_rfc3339(&s)
+            .map_err(|e| AppError::InvalidInput(format!("Invalid datetime format: {}", e)))?
+            .with_timezone(&Utc),
+        None => Utc::now(),
+    };
+
+    crate::services::ephemeris::moving_target_coordinates(&moving_target, dt).ok_or_else(|| {
+        AppError::NotFound("No ephemeris data or orbital elements for this time".to_string())
+    })
+}
+
+/// Update coordinates of all moving targets in a sequence for a given time
+#[command]
+pub async fn update_moving_target_positions(
+    mut sequence: SimpleSequence,
+    datetime: Option<String>,
+) -> Result<SimpleSequence, AppError> {
+    let dt = match datetime {
+        Some(s) => DateTime::parse_from_rfc3339(&s)
+            .map_err(|e| AppError::InvalidInput(format!("Invalid datetime format: {}", e)))?
+            .with_timezone(&Utc),
+        None => Utc::now(),
+    };
+
+    crate::services::ephemeris::update_moving_target_coordinates(&mut sequence, dt);
+    Ok(sequence)
+}
+
+/// Import TLE content into the satellite catalog
+#[command]
+pub async fn import_tle_content(
+    state: State<'_, SharedState>,
+    content: String,
+) -> Result<TleImportResult, AppError> {
+    Ok(satellite::import_tle(&state, &content))
+}
+
+/// Import a TLE file into the satellite catalog
+#[command]
+pub async fn import_tle_file(
+    state: State<'_, SharedState>,
+    path: String,
+) -> Result<TleImportResult, AppError> {
+    let path = path_guard::check_path(&state, &path)?;
+    let content = tokio::fs::read_to_string(&path)
+        .await
+        .context("Failed to read file")?;
+    Ok(satellite::import_tle(&state, &content))
+}
+
+/// Get the loaded satellite catalog
+#[command]
+pub async fn get_tle_catalog(state: State<'_, SharedState>) -> Result<Vec<Tle>, AppError> {
+    Ok(satellite::get_catalog(&state))
+}
+
+/// Clear the satellite catalog
+#[command]
+pub async fn clear_tle_catalog(state: State<'_, SharedState>) -> Result<(), AppError> {
+    satellite::clear_catalog(&state);
+    Ok(())
+}
+
+/// Predict satellite transits through a target's field of view
+#[command]
+pub async fn predict_transits(
+    state: State<'_, SharedState>,
+    coordinates: Coordinates,
+    location: Option<ObserverLocation>,
+    site_id: Option<String>,
+    start_time: String,
+    end_time: String,
+    fov_radius: Option<f64>,
+) -> Result<Vec<SatelliteTransit>, AppError> {
+    let location = input::location(&state, location, site_id)?;
+    let start = DateTime::parse_from_rfc3339(&start_time)
+        .map_err(|e| AppError::InvalidInput(format!("Invalid datetime format: {}", e)))?
+        .with_timezone(&Utc);
+    let end = DateTime::parse_from_rfc3339(&end_time)
+        .map_err(|e| AppError::InvalidInput(format!("Invalid datetime format: {}", e)))?
+        .with_timezone(&Utc);
+    if end <= start {
+        return Err(AppError::InvalidInput(
+            "End time must be after start time".to_string(),
+        ));
+    }
+
+    Ok(satellite::predict_transits(
+        &satellite::get_catalog(&state),
+        &coordinates,
+        &location,
+        start,
+        end,
+        fov_radius.unwrap_or(satellite::DEFAULT_TRANSIT_RADIUS),
+    ))
+}
+
+/// Get the cloud forecast for the night starting on a date
+#[command]
+pub async fn get_night_forecast(
+    state: State<'_, SharedState>,
+    location: Option<ObserverLocation>,
+    site_id: Option<String>,
+    date: String,
+    provider: Option<String>,
+) -> Result<NightForecast, AppError> {
+    let location = input::location(&state, location, site_id)?;
+    let date = input::date("date", &date)?;
+    let provider = provider
+        .as_deref()
+        .unwrap_or(weather::DEFAULT_WEATHER_PROVIDER);
+
+    weather::get_night_forecasts(&state, provider, &location, date, date)
+        .await?
+        .into_iter()
+        .next()
+        .ok_or_else(|| AppError::NotFound("No forecast available".to_string()))
+}
+
+/// List available weather providers
+#[command]
+pub async fn list_weather_providers(
+    state: State<'_, SharedState>,
+) -> Result<Vec<WeatherProviderInfo>, AppError> {
+    Ok(weather::list_providers(&state))
+}
@@ -1,78 +1,201 @@
 //! Backup and recovery commands
 
-use tauri::command;
+use tauri::{command, State};
 
-use crate::models::SimpleSequence;
-use crate::services::backup_service::{self, BackupMetadata, BackupType};
+use crate::error::AppError;
+use crate::models::{BackupRetentionPolicy, EditorSequence, SimpleSequence};
+use crate::services::backup_service::{
+    self, BackupMetadata, BackupPreview, BackupStatistics, BackupType,
+};
+use crate::services::edit_journal::JournalOp;
+use crate::services::settings_service;
+use crate::state::SharedState;
 
-/// Create backup
-#[command]
-pub async fn create_backup(
-    sequence: SimpleSequence,
-    backup_type: String,
-) -> Result<BackupMetadata, String> {
-    let backup_type = match backup_type.as_str() {
+use super::input;
+
+fn parse_backup_type(backup_type: &str) -> BackupType {
+    match backup_type {
         "auto" => BackupType::Auto,
         "manual" => BackupType::Manual,
         "before_save" => BackupType::BeforeSave,
         "crash" => BackupType::Crash,
         _ => BackupType::Manual,
-    };
+    }
+}
+
+/// Create backup
+#[command]
+pub async fn create_backup(
+    sequence: SimpleSequence,
+    backup_type: String,
+) -> Result<BackupMetadata, AppError> {
+    backup_service::create_backup(&sequence, parse_backup_type(&backup_type))
+        .await
+        .map_err(AppError::from)
+}
 
-    backup_service::create_backup(&sequence, backup_type).await
+/// Create backup of editor sequence
+#[command]
+pub async fn create_editor_backup(
+    sequence: EditorSequence,
+    backup_type: String,
+) -> Result<BackupMetadata, AppError> {
+    backup_service::create_editor_backup(&sequence, parse_backup_type(&backup_type))
+        .await
+        .map_err(AppError::from)
 }
 
 /// List backups
 #[command]
-pub async fn list_backups(sequence_id: Option<String>) -> Result<Vec<BackupMetadata>, String> {
-    backup_service::list_backups(sequence_id.as_deref()).await
+pub async fn list_backups(sequence_id: Option<String>) -> Result<Vec<BackupMetadata>, AppError> {
+    backup_service::list_backups(sequence_id.as_deref())
+        .await
+        .map_err(AppError::from)
 }
 
 /// Restore backup
 #[command]
-pub async fn restore_backup(backup_id: String) -> Result<SimpleSequence, String> {
+pub async fn restore_backup(backup_id: String) -> Result<SimpleSequence, AppError> {
+    input::id("backupId", &backup_id)?;
     backup_service::restore_backup(&backup_id).await
 }
 
+/// Summarize a backup's contents without restoring it
+#[command]
+pub async fn preview_backup(backup_id: String) -> Result<BackupPreview, AppError> {
+    input::id("backupId", &backup_id)?;
+    backup_service::preview_backup(&backup_id).await
+}
+
+/// Restore selected targets from a backup into the given sequence
+#[command]
+pub async fn restore_backup_partial(
+    backup_id: String,
+    sequence: SimpleSequence,
+    target_ids: Vec<String>,
+) -> Result<SimpleSequence, AppError> {
+    backup_service::restore_backup_partial(&backup_id, sequence, &target_ids).await
+}
+
+/// Restore editor sequence backup
+#[command]
+pub async fn restore_editor_backup(backup_id: String) -> Result<EditorSequence, AppError> {
+    input::id("backupId", &backup_id)?;
+    backup_service::restore_editor_backup(&backup_id).await
+}
+
 /// Delete backup
 #[command]
-pub async fn delete_backup(backup_id: String) -> Result<(), String> {
+pub async fn delete_backup(backup_id: String) -> Result<(), AppError> {
+    input::id("backupId", &backup_id)?;
     backup_service::delete_backup(&backup_id).await
 }
 
 /// Clean old backups
 #[command]
-pub async fn clean_old_backups(max_age_days: i64, max_count: usize) -> Result<usize, String> {
-    backup_service::clean_old_backups(max_age_days, max_count).await
+pub async fn clean_old_backups(max_age_days: i64, max_count: usize) -> Result<usize, AppError> {
+    backup_service::clean_old_backups(max_age_days, max_count)
+        .await
+        .map_err(AppError::from)
+}
+
+/// Prune backups with the configured retention policy
+#[command]
+pub async fn apply_backup_retention(
+    state: State<'_, SharedState>,
+    sequence_id: Option<String>,
+) -> Result<usize, AppError> {
+    let policy = settings_service::get_backup_retention(&state);
+    backup_service::apply_retention_policy(sequence_id.as_deref(), &policy)
+        .await
+        .map_err(AppError::from)
+}
+
+/// Get backup retention policy
+#[command]
+pub fn get_backup_retention(state: State<'_, SharedState>) -> BackupRetentionPolicy {
+    settings_service::get_backup_retention(&state)
+}
+
+/// Set backup retention policy
+#[command]
+pub async fn set_backup_retention(
+    state: State<'_, SharedState>,
+    policy: BackupRetentionPolicy,
+) -> Result<(), AppError> {
+    settings_service::set_backup_retention(&state, policy).await
+}
+
+/// Get whether files are backed up before being overwritten
+#[command]
+pub fn get_backup_on_save(state: State<'_, SharedState>) -> bool {
+    settings_service::get_backup_on_save(&state)
+}
+
+/// Enable or disable backup-on-save
+#[command]
+pub async fn set_backup_on_save(
+    state: State<'_, SharedState>,
+    enabled: bool,
+) -> Result<(), AppError> {
+    settings_service::set_backup_on_save(&state, enabled).await
+}
+
+/// Get backup disk usage
+#[command]
+pub async fn get_backup_statistics() -> Result<BackupStatistics, AppError> {
+    backup_service::get_backup_statistics()
+        .await
+        .map_err(AppError::from)
 }
 
 /// Save crash recovery data
 #[command]
-pub async fn save_crash_recovery(sequence: SimpleSequence) -> Result<String, String> {
-    backup_service::save_crash_recovery(&sequence).await
+pub async fn save_crash_recovery(sequence: SimpleSequence) -> Result<String, AppError> {
+    backup_service::save_crash_recovery(&sequence)
+        .await
+        .map_err(AppError::from)
+}
+
+/// Append edit operations to a sequence's crash recovery journal
+#[command]
+pub async fn journal_edit(sequence_id: String, ops: Vec<JournalOp>) -> Result<(), AppError> {
+    input::id("sequenceId", &sequence_id)?;
+    backup_service::journal_edit(&sequence_id, &ops)
+        .await
+        .map_err(AppError::from)
 }
 
 /// Load crash recovery data
 #[command]
-pub async fn load_crash_recovery(sequence_id: String) -> Result<Option<SimpleSequence>, String> {
-    backup_service::load_crash_recovery(&sequence_id).await
+pub async fn load_crash_recovery(sequence_id: String) -> Result<Option<SimpleSequence>, AppError> {
+    input::id("sequenceId", &sequence_id)?;
+    backup_service::load_crash_recovery(&sequence_id)
+        .await
+        .map_err(AppError::from)
 }
 
 /// Clear crash recovery data
 #[command]
-pub async fn clear_crash_recovery(sequence_id: String) -> Result<(), String> {
-    backup_service::clear_crash_recovery(&sequence_id).await
+pub async fn clear_crash_recovery(sequence_id: String) -> Result<(), AppError> {
+    input::id("sequenceId", &sequence_id)?;
+    backup_service::clear_crash_recovery(&sequence_id)
+        .await
+        .map_err(AppError::from)
 }
 
 /// List crash recovery files
 #[command]
-pub async fn list_crash_recovery() -> Result<Vec<String>, String> {
-    backup_service::list_crash_recovery().await
+pub async fn list_crash_recovery() -> Result<Vec<String>, AppError> {
+    backup_service::list_crash_recovery()
+        .await
+        .map_err(AppError::from)
 }
 
 /// Check if crash recovery exists
 #[command]
-pub async fn has_crash_recovery(sequence_id: String) -> Result<bool, String> {
+pub async fn has_crash_recovery(sequence_id: String) -> Result<bool, AppError> {
+    input::id("sequenceId", &sequence_id)?;
     let path = backup_service::get_crash_recovery_directory().join(format!("{}.json", sequence_id));
     Ok(path.exists())
 }
@@ -1,28 +1,59 @@
 //! Calculator commands for astronomy and timing
 
 use chrono::{DateTime, Utc};
-use tauri::command;
+use tauri::{command, State};
 
+use crate::error::AppError;
 use crate::models::*;
-use crate::services::calculator;
+use crate::services::flat_calculator::{self, FlatPanelSettings, FlatPlan};
+use crate::services::sampling::{self, GuideOptics, SamplingAnalysis};
+use crate::services::{calculator, settings_service, units};
+use crate::state::SharedState;
 
-/// Calculate sequence runtime
+use super::input;
+
+/// Calculate sequence runtime (uses the active equipment profile's download times if set)
 #[command]
-pub fn calculate_sequence_runtime(sequence: SimpleSequence) -> f64 {
-    calculator::calculate_sequence_runtime(&sequence)
+pub fn calculate_sequence_runtime(state: State<'_, SharedState>, sequence: SimpleSequence) -> f64 {
+    match settings_service::get_active_equipment_profile(&state) {
+        Some(profile) => calculator::calculate_sequence_runtime_with_profile(&sequence, &profile),
+        None => calculator::calculate_sequence_runtime(&sequence),
+    }
 }
 
-/// Calculate sequence ETAs
+/// Calculate sequence ETAs down to each exposure row, including dither,
+/// filter change and autofocus overheads (uses the active equipment
+/// profile's overheads unless given)
 #[command]
-pub fn calculate_sequence_etas(mut sequence: SimpleSequence) -> SimpleSequence {
-    calculator::calculate_sequence_etas(&mut sequence);
+pub fn calculate_sequence_etas(
+    state: State<'_, SharedState>,
+    mut sequence: SimpleSequence,
+    overheads: Option<OverheadProfile>,
+) -> SimpleSequence {
+    let overheads = overheads.unwrap_or_else(|| {
+        settings_service::get_active_equipment_profile(&state)
+            .map(|p| p.overheads)
+            .unwrap_or_default()
+    });
+    calculator::calculate_sequence_etas(&mut sequence, Some(&overheads));
     sequence
 }
 
-/// Calculate exposure runtime
+/// Calculate exposure runtime including dither settling (uses the active
+/// equipment profile's overheads unless given)
 #[command]
-pub fn calculate_exposure_runtime(exposure: SimpleExposure, download_time: f64) -> f64 {
-    calculator::calculate_exposure_runtime(&exposure, download_time)
+pub fn calculate_exposure_runtime(
+    state: State<'_, SharedState>,
+    exposure: SimpleExposure,
+    download_time: f64,
+    overheads: Option<OverheadProfile>,
+) -> f64 {
+    let overheads = overheads.unwrap_or_else(|| {
+        settings_service::get_active_equipment_profile(&state)
+            .map(|p| p.overheads)
+            .unwrap_or_default()
+    });
+    calculator::calculate_exposure_runtime(&exposure, download_time, Some(&overheads))
 }
 
 /// Calculate target runtime
@@ -39,19 +70,23 @@ pub fn format_duration(seconds: f64) -> String {
 
 /// Format time
 #[command]
-pub fn format_time(datetime: String) -> Result<String, String> {
+pub fn format_time(state: State<'_, SharedState>, datetime: String) -> Result<String, AppError> {
     let dt: DateTime<Utc> = datetime
         .parse()
-        .map_err(|e| format!("Invalid datetime: {}", e))?;
-    Ok(calculator::format_time(dt))
+        .map_err(|e| AppError::InvalidInput(format!("Invalid datetime: {}", e)))?;
+    Ok(units::format_clock(
+        &dt,
+        true,
+        &settings_service::get_unit_preferences(&state),
+    ))
 }
 
 /// Calculate end time
 #[command]
-pub fn calculate_end_time(start: String, duration_seconds: f64) -> Result<String, String> {
+pub fn calculate_end_time(start: String, duration_seconds: f64) -> Result<String, AppError> {
     let start_dt: DateTime<Utc> = start
         .parse()
-        .map_err(|e| format!("Invalid datetime: {}", e))?;
+        .map_err(|e| AppError::InvalidInput(format!("Invalid datetime: {}", e)))?;
     let end_dt = calculator::calculate_end_time(start_dt, duration_seconds);
     Ok(end_dt.to_rfc3339())
 }
@@ -62,6 +97,27 @@ pub fn calculate_angular_separation(coord1: Coordinates, coord2: Coordinates) ->
     calculator::angular_separation(&coord1, &coord2)
 }
 
+/// Separations in degrees between every pair of targets
+#[command]
+pub fn calculate_separation_matrix(targets: Vec<SimpleTarget>) -> calculator::SeparationMatrix {
+    calculator::calculate_separation_matrix(&targets)
+}
+
+/// Targets within `radius` degrees of a position, nearest first
+#[command]
+pub fn find_targets_near(
+    coordinates: Coordinates,
+    targets: Vec<SimpleTarget>,
+    radius: f64,
+) -> Result<Vec<calculator::NearbyTarget>, AppError> {
+    input::non_negative("radius", radius)?;
+    Ok(calculator::find_targets_near(
+        &coordinates,
+        &targets,
+        radius,
+    ))
+}
+
 /// Convert RA to decimal
 #[command]
 pub fn ra_to_decimal(hours: i32, minutes: i32, seconds: f64) -> f64 {
@@ -120,11 +176,11 @@ pub fn calculate_altitude(
     latitude: f64,
     longitude: f64,
     datetime: Option<String>,
-) -> Result<f64, String> {
+) -> Result<f64, AppError> {
     let dt = if let Some(dt_str) = datetime {
         dt_str
             .parse()
-            .map_err(|e| format!("Invalid datetime: {}", e))?
+            .map_err(|e| AppError::InvalidInput(format!("Invalid datetime: {}", e)))?
     } else {
         Utc::now()
     };
@@ -147,11 +203,11 @@ pub fn is_above_horizon(
     longitude: f64,
     min_altitude: f64,
     datetime: Option<String>,
-) -> Result<bool, String> {
+) -> Result<bool, AppError> {
     let dt = if let Some(dt_str) = datetime {
         dt_str
             .parse()
-            .map_err(|e| format!("Invalid datetime: {}", e))?
+            .map_err(|e| AppError::InvalidInput(format!("Invalid datetime: {}", e)))?
     } else {
         Utc::now()
     };
@@ -168,11 +224,11 @@ pub fn is_above_horizon(
 
 /// Calculate moon phase
 #[command]
-pub fn calculate_moon_phase(datetime: Option<String>) -> Result<f64, String> {
+pub fn calculate_moon_phase(datetime: Option<String>) -> Result<f64, AppError> {
     let dt = if let Some(dt_str) = datetime {
         dt_str
             .parse()
-            .map_err(|e| format!("Invalid datetime: {}", e))?
+            .map_err(|e| AppError::InvalidInput(format!("Invalid datetime: {}", e)))?
     } else {
         Utc::now()
     };
@@ -182,11 +238,11 @@ pub fn calculate_moon_phase(datetime: Option<String>) -> Result<f64, String> {
 
 /// Calculate moon illumination
 #[command]
-pub fn calculate_moon_illumination(datetime: Option<String>) -> Result<f64, String> {
+pub fn calculate_moon_illumination(datetime: Option<String>) -> Result<f64, AppError> {
     let dt = if let Some(dt_str) = datetime {
         dt_str
             .parse()
-            .map_err(|e| format!("Invalid datetime: {}", e))?
+            .map_err(|e| AppError::InvalidInput(format!("Invalid datetime: {}", e)))?
     } else {
         Utc::now()
     };
@@ -196,19 +252,19 @@ pub fn calculate_moon_illumination(datetime: Option<String>) -> Result<f64, Stri
 
 /// Parse RA string
 #[command]
-pub fn parse_ra(ra_string: String) -> Result<RaResult, String> {
+pub fn parse_ra(ra_string: String) -> Result<RaResult, AppError> {
     Coordinates::parse_ra(&ra_string)
         .map(|(hours, minutes, seconds)| RaResult {
             hours,
             minutes,
             seconds,
         })
-        .ok_or_else(|| "Invalid RA format".to_string())
+        .ok_or_else(|| AppError::InvalidInput("Invalid RA format".to_string()))
 }
 
 /// Parse Dec string
 #[command]
-pub fn parse_dec(dec_string: String) -> Result<DecResult, String> {
+pub fn parse_dec(dec_string: String) -> Result<DecResult, AppError> {
     Coordinates::parse_dec(&dec_string)
         .map(|(degrees, minutes, seconds, negative)| DecResult {
             degrees,
@@ -216,7 +272,7 @@ pub fn parse_dec(dec_string: String) -> Result<DecResult, String> {
             seconds,
             negative,
         })
-        .ok_or_else(|| "Invalid Dec format".to_string())
+        .ok_or_else(|| AppError::InvalidInput("Invalid Dec format".to_string()))
 }
 
 /// Format RA
@@ -231,3 +287,66 @@ pub fn format_dec(degrees: i32, minutes: i32, seconds: f64, negative: bool) -> S
     let sign = if negative { "-" } else { "+" };
     format!("{}{}° {:02}' {:.1}\"", sign, degrees, minutes, seconds)
 }
+
+/// Galactic coordinates of a J2000 position
+#[command]
+pub fn equatorial_to_galactic(coordinates: Coordinates) -> GalacticCoordinates {
+    coordinates.to_galactic()
+}
+
+/// J2000 coordinates of a galactic position
+#[command]
+pub fn galactic_to_equatorial(galactic: GalacticCoordinates) -> Coordinates {
+    Coordinates::from_galactic(&galactic)
+}
+
+/// Ecliptic coordinates (J2000) of a J2000 position
+#[command]
+pub fn equatorial_to_ecliptic(coordinates: Coordinates) -> EclipticCoordinates {
+    coordinates.to_ecliptic()
+}
+
+/// J2000 coordinates of an ecliptic position
+#[command]
+pub fn ecliptic_to_equatorial(ecliptic: EclipticCoordinates) -> Coordinates {
+    Coordinates::from_ecliptic(&ecliptic)
+}
+
+/// Flat exposure times per filter and a calibration target taking them.
+/// The bit depth defaults to the active equipment profile's camera.
+#[command]
+pub fn calculate_flat_exposures(
+    state: State<'_, SharedState>,
+    mut settings: FlatPanelSettings,
+) -> Result<FlatPlan, AppError> {
+    if settings.bit_depth.is_none() {
+        settings.bit_depth =
+            settings_service::get_active_equipment_profile(&state).map(|p| p.camera.bit_depth);
+    }
+    flat_calculator::calculate_flat_exposures(&settings).map_err(AppError::InvalidInput)
+}
+
+/// Sampling of the equipment (the active profile unless given) at a seeing
+/// FWHM in arcseconds, with the binning that suits it and, when guide
+/// optics are given, the guide camera's scale against the imaging scale
+#[command]
+pub fn analyze_sampling(
+    state: State<'_, SharedState>,
+    profile: Option<EquipmentProfile>,
+    seeing_arcsec: f64,
+    guide: Option<GuideOptics>,
+) -> Result<SamplingAnalysis, AppError> {
+    let profile = profile
+        .or_else(|| settings_service::get_active_equipment_profile(&state))
+        .ok_or_else(|| {
+            AppError::InvalidInput("No equipment profile given or active".to_string())
+        })?;
+    let mut analysis =
+        sampling::analyze_sampling(&profile, seeing_arcsec).map_err(AppError::InvalidInput)?;
+    if let Some(guide) = guide {
+        analysis.guiding = Some(
+            sampling::analyze_guide_sampling(&profile, &guide).map_err(AppError::InvalidInput)?,
+        );
+    }
+    Ok(analysis)
+}
@@ -1,109 +1,179 @@
 //! Clipboard commands
 
-use tauri::command;
+use tauri::{command, AppHandle, State};
+use tauri_plugin_clipboard_manager::ClipboardExt;
 
+use crate::error::AppError;
 use crate::models::{EditorSequenceItem, SimpleExposure, SimpleTarget};
-use crate::services::clipboard_service::{self, ClipboardContent};
+use crate::services::clipboard_service::{self, ClipboardContent, ClipboardSlotInfo};
+use crate::services::nina_type_registry;
+use crate::state::{AppState, SharedState};
 
 /// Copy target to clipboard
 #[command]
-pub fn copy_target(target: SimpleTarget) {
-    clipboard_service::copy_target(target);
+pub fn copy_target(state: State<'_, SharedState>, target: SimpleTarget) {
+    state.clipboard.copy_target(target);
 }
 
 /// Copy multiple targets to clipboard
 #[command]
-pub fn copy_targets(targets: Vec<SimpleTarget>) {
-    clipboard_service::copy_targets(targets);
+pub fn copy_targets(state: State<'_, SharedState>, targets: Vec<SimpleTarget>) {
+    state.clipboard.copy_targets(targets);
 }
 
 /// Copy exposure to clipboard
 #[command]
-pub fn copy_exposure(exposure: SimpleExposure) {
-    clipboard_service::copy_exposure(exposure);
+pub fn copy_exposure(state: State<'_, SharedState>, exposure: SimpleExposure) {
+    state.clipboard.copy_exposure(exposure);
 }
 
 /// Copy multiple exposures to clipboard
 #[command]
-pub fn copy_exposures(exposures: Vec<SimpleExposure>) {
-    clipboard_service::copy_exposures(exposures);
+pub fn copy_exposures(state: State<'_, SharedState>, exposures: Vec<SimpleExposure>) {
+    state.clipboard.copy_exposures(exposures);
 }
 
 /// Paste target from clipboard
 #[command]
-pub fn paste_target() -> Option<SimpleTarget> {
-    clipboard_service::paste_target()
+pub fn paste_target(state: State<'_, SharedState>) -> Option<SimpleTarget> {
+    state.clipboard.paste_target()
 }
 
 /// Paste targets from clipboard
 #[command]
-pub fn paste_targets() -> Option<Vec<SimpleTarget>> {
-    clipboard_service::paste_targets()
+pub fn paste_targets(state: State<'_, SharedState>) -> Option<Vec<SimpleTarget>> {
+    state.clipboard.paste_targets()
 }
 
 /// Paste exposure from clipboard
 #[command]
-pub fn paste_exposure() -> Option<SimpleExposure> {
-    clipboard_service::paste_exposure()
+pub fn paste_exposure(state: State<'_, SharedState>) -> Option<SimpleExposure> {
+    state.clipboard.paste_exposure()
 }
 
 /// Paste exposures from clipboard
 #[command]
-pub fn paste_exposures() -> Option<Vec<SimpleExposure>> {
-    clipboard_service::paste_exposures()
+pub fn paste_exposures(state: State<'_, SharedState>) -> Option<Vec<SimpleExposure>> {
+    state.clipboard.paste_exposures()
 }
 
 /// Check if clipboard has content
 #[command]
-pub fn has_clipboard_content() -> bool {
-    clipboard_service::has_clipboard_content()
+pub fn has_clipboard_content(state: State<'_, SharedState>) -> bool {
+    state.clipboard.has_content()
 }
 
 /// Check if clipboard has specific content type
 #[command]
-pub fn has_clipboard_content_type(content_type: String) -> bool {
-    clipboard_service::has_clipboard_content_type(&content_type)
+pub fn has_clipboard_content_type(state: State<'_, SharedState>, content_type: String) -> bool {
+    state.clipboard.has_content_type(&content_type)
 }
 
 /// Clear clipboard
 #[command]
-pub fn clear_clipboard() {
-    clipboard_service::clear_clipboard();
+pub fn clear_clipboard(state: State<'_, SharedState>) {
+    state.clipboard.clear();
+}
+
+/// Store content, or the current clipboard content, in a named slot
+#[command]
+pub async fn copy_to_slot(
+    state: State<'_, SharedState>,
+    name: String,
+    content: Option<ClipboardContent>,
+) -> Result<ClipboardSlotInfo, AppError> {
+    state.clipboard.copy_to_slot(&name, content).await
+}
+
+/// Load a named slot into the clipboard
+#[command]
+pub async fn paste_from_slot(
+    state: State<'_, SharedState>,
+    name: String,
+) -> Result<ClipboardContent, AppError> {
+    state.clipboard.paste_from_slot(&name).await
+}
+
+/// List named clipboard slots
+#[command]
+pub async fn list_clipboard_slots(
+    state: State<'_, SharedState>,
+) -> Result<Vec<ClipboardSlotInfo>, AppError> {
+    Ok(state.clipboard.list_slots().await)
+}
+
+/// Delete a named clipboard slot
+#[command]
+pub async fn delete_clipboard_slot(
+    state: State<'_, SharedState>,
+    name: String,
+) -> Result<(), AppError> {
+    state.clipboard.delete_slot(&name).await
 }
 
 /// Get clipboard content as JSON (for system clipboard sync)
 #[command]
-pub fn get_clipboard_json() -> Option<String> {
-    clipboard_service::serialize_clipboard_content()
+pub fn get_clipboard_json(state: State<'_, SharedState>) -> Option<String> {
+    state.clipboard.serialize_content()
 }
 
 /// Set clipboard content from JSON (for system clipboard sync)
 #[command]
-pub fn set_clipboard_json(json: String) -> bool {
+pub fn set_clipboard_json(state: State<'_, SharedState>, json: String) -> bool {
     if let Some(content) = clipboard_service::deserialize_clipboard_content(&json) {
-        clipboard_service::copy_to_clipboard(content);
+        state.clipboard.copy(content);
         true
     } else {
         false
     }
 }
 
+/// Copy sequence items to the internal clipboard and as a NINA JSON
+/// fragment to the system clipboard
+fn copy_sequence_content(app: &AppHandle, state: &AppState, content: ClipboardContent) {
+    if let Some(fragment) =
+        clipboard_service::nina_fragment(&content, &nina_type_registry::plugin_types(state))
+    {
+        match app.clipboard().write_text(fragment.as_str()) {
+            Ok(()) => state.clipboard.remember_system_fragment(fragment),
+            Err(e) => log::warn!("Failed to write system clipboard: {}", e),
+        }
+    }
+    state.clipboard.copy(content);
+}
+
+/// Sequence items to paste, preferring NINA items copied in other applications
+fn sequence_paste_content(app: &AppHandle, state: &AppState) -> Option<ClipboardContent> {
+    let system_text = app.clipboard().read_text().ok();
+    let plugins = nina_type_registry::plugin_types(state);
+    state
+        .clipboard
+        .sequence_content(system_text.as_deref(), &plugins)
+}
+
 /// Copy sequence item to clipboard
 #[command]
-pub fn copy_sequence_item(item: EditorSequenceItem) {
-    clipboard_service::copy_to_clipboard(ClipboardContent::SequenceItem(item));
+pub fn copy_sequence_item(app: AppHandle, state: State<'_, SharedState>, item: EditorSequenceItem) {
+    copy_sequence_content(&app, &state, ClipboardContent::SequenceItem(item));
 }
 
 /// Copy multiple sequence items to clipboard
 #[command]
-pub fn copy_sequence_items(items: Vec<EditorSequenceItem>) {
-    clipboard_service::copy_to_clipboard(ClipboardContent::SequenceItems(items));
+pub fn copy_sequence_items(
+    app: AppHandle,
+    state: State<'_, SharedState>,
+    items: Vec<EditorSequenceItem>,
+) {
+    copy_sequence_content(&app, &state, ClipboardContent::SequenceItems(items));
 }
 
 /// Paste sequence item from clipboard
 #[command]
-pub fn paste_sequence_item() -> Option<EditorSequenceItem> {
-    match clipboard_service::get_clipboard_content()? {
+pub fn paste_sequence_item(
+    app: AppHandle,
+    state: State<'_, SharedState>,
+) -> Option<EditorSequenceItem> {
+    match sequence_paste_content(&app, &state)? {
         ClipboardContent::SequenceItem(mut item) => {
             // Generate new IDs
             regenerate_item_ids(&mut item);
@@ -115,8 +185,11 @@ pub fn paste_sequence_item() -> Option<EditorSequenceItem> {
 
 /// Paste sequence items from clipboard
 #[command]
-pub fn paste_sequence_items() -> Option<Vec<EditorSequenceItem>> {
-    match clipboard_service::get_clipboard_content()? {
+pub fn paste_sequence_items(
+    app: AppHandle,
+    state: State<'_, SharedState>,
+) -> Option<Vec<EditorSequenceItem>> {
+    match sequence_paste_content(&app, &state)? {
         ClipboardContent::SequenceItems(items) => Some(
             items
                 .into_iter()
@@ -2,32 +2,69 @@
 //!
 //! Tauri commands for exporting sequences to various formats
 
-use tauri::command;
+use tauri::{command, State};
 
+use crate::error::{AppError, ResultExt};
 use crate::models::{SimpleSequence, SimpleTarget};
+use crate::services::astronomy::ObserverLocation;
 use crate::services::export_service::{
     export_sequence, export_to_apt_xml, export_to_csv, export_to_json, export_to_nina_target_set,
     export_to_stellarium, export_to_telescopius_csv, export_to_voyager, export_to_xml, format_dec,
     format_ra, generate_csv_content, generate_xml_content, CoordinateFormat, ExportFormat,
-    ExportOptions, ExportResult,
+    ExportOptions, ExportResult, FormatExportResult, SessionReportOptions,
 };
+use crate::services::units::localize_decimal;
+use crate::services::voyager_robotarget::export_to_robotarget_json;
+use crate::services::{path_guard, settings_service};
+use crate::state::{AppState, SharedState};
+
+use super::input;
+
+/// Fill in the user's unit preferences unless the caller chose some
+fn with_unit_preferences(state: &AppState, mut options: ExportOptions) -> ExportOptions {
+    if options.units.is_none() {
+        options.units = Some(settings_service::get_unit_preferences(state));
+    }
+    options
+}
+
+/// Note the export format on the recent file entry of the sequence's file
+async fn remember_export_format(state: &AppState, sequence: &SimpleSequence, format: ExportFormat) {
+    let Some(path) = &sequence.save_path else {
+        return;
+    };
+    let Some(name) = serde_json::to_value(format)
+        .ok()
+        .and_then(|v| v.as_str().map(str::to_string))
+    else {
+        return;
+    };
+    if let Err(e) = settings_service::record_recent_file_export(state, path, &name).await {
+        log::warn!("Failed to remember export format: {}", e);
+    }
+}
 
 /// Export sequence with options
 #[command]
 pub async fn export_sequence_with_options(
+    state: State<'_, SharedState>,
     sequence: SimpleSequence,
     options: ExportOptions,
-) -> Result<ExportResult, String> {
-    Ok(export_sequence(&sequence, &options))
+) -> Result<ExportResult, AppError> {
+    Ok(export_sequence(
+        &sequence,
+        &with_unit_preferences(&state, options),
+    ))
 }
 
 /// Export sequence to CSV
 #[command]
 pub async fn export_to_csv_format(
+    state: State<'_, SharedState>,
     sequence: SimpleSequence,
     include_exposures: bool,
     include_progress: bool,
-) -> Result<ExportResult, String> {
+) -> Result<ExportResult, AppError> {
     let options = ExportOptions {
         format: ExportFormat::Csv,
         include_exposures,
@@ -35,6 +72,8 @@ pub async fn export_to_csv_format(
         include_progress,
         decimal_places: 2,
         coordinate_format: CoordinateFormat::Sexagesimal,
+        units: Some(settings_service::get_unit_preferences(&state)),
+        ..Default::default()
     };
     Ok(export_to_csv(&sequence, &options))
 }
@@ -43,7 +82,7 @@ pub async fn export_to_csv_format(
 #[command]
 pub async fn export_to_telescopius_format(
     sequence: SimpleSequence,
-) -> Result<ExportResult, String> {
+) -> Result<ExportResult, AppError> {
     let options = ExportOptions::default();
     Ok(export_to_telescopius_csv(&sequence, &options))
 }
@@ -54,7 +93,7 @@ pub async fn export_to_xml_format(
     sequence: SimpleSequence,
     include_exposures: bool,
     include_settings: bool,
-) -> Result<ExportResult, String> {
+) -> Result<ExportResult, AppError> {
     let options = ExportOptions {
         format: ExportFormat::Xml,
         include_exposures,
@@ -62,20 +101,23 @@ pub async fn export_to_xml_format(
         include_progress: false,
         decimal_places: 2,
         coordinate_format: CoordinateFormat::Sexagesimal,
+        ..Default::default()
     };
     Ok(export_to_xml(&sequence, &options))
 }
 
 /// Export sequence to APT XML format
 #[command]
-pub async fn export_to_apt_format(sequence: SimpleSequence) -> Result<ExportResult, String> {
+pub async fn export_to_apt_format(sequence: SimpleSequence) -> Result<ExportResult, AppError> {
     let options = ExportOptions::default();
     Ok(export_to_apt_xml(&sequence, &options))
 }
 
 /// Export sequence to Stellarium skylist
 #[command]
-pub async fn export_to_stellarium_format(sequence: SimpleSequence) -> Result<ExportResult, String> {
+pub async fn export_to_stellarium_format(
+    sequence: SimpleSequence,
+) -> Result<ExportResult, AppError> {
     let options = ExportOptions::default();
     Ok(export_to_stellarium(&sequence, &options))
 }
@@ -85,7 +127,7 @@ pub async fn export_to_stellarium_format(sequence: SimpleSequence) -> Result<Exp
 pub async fn export_to_voyager_format(
     sequence: SimpleSequence,
     include_exposures: bool,
-) -> Result<ExportResult, String> {
+) -> Result<ExportResult, AppError> {
     let options = ExportOptions {
         format: ExportFormat::Voyager,
         include_exposures,
@@ -93,31 +135,47 @@ pub async fn export_to_voyager_format(
         include_progress: false,
         decimal_places: 2,
         coordinate_format: CoordinateFormat::SexagesimalColon,
+        ..Default::default()
     };
     Ok(export_to_voyager(&sequence, &options))
 }
 
+/// Export sequence to Voyager RoboTarget JSON
+#[command]
+pub async fn export_to_voyager_robotarget_format(
+    sequence: SimpleSequence,
+    include_exposures: bool,
+) -> Result<ExportResult, AppError> {
+    let options = ExportOptions {
+        format: ExportFormat::VoyagerRoboTarget,
+        include_exposures,
+        ..Default::default()
+    };
+    Ok(export_to_robotarget_json(&sequence, &options))
+}
+
 /// Export sequence to NINA Target Set format
 #[command]
 pub async fn export_to_nina_target_set_format(
     sequence: SimpleSequence,
-) -> Result<ExportResult, String> {
+) -> Result<ExportResult, AppError> {
     Ok(export_to_nina_target_set(&sequence))
 }
 
 /// Export sequence to JSON
 #[command]
-pub async fn export_to_json_format(sequence: SimpleSequence) -> Result<ExportResult, String> {
+pub async fn export_to_json_format(sequence: SimpleSequence) -> Result<ExportResult, AppError> {
     Ok(export_to_json(&sequence))
 }
 
 /// Generate CSV content from targets
 #[command]
 pub async fn generate_targets_csv(
+    state: State<'_, SharedState>,
     targets: Vec<SimpleTarget>,
     coordinate_format: String,
     decimal_places: usize,
-) -> Result<String, String> {
+) -> Result<String, AppError> {
     let coord_format = match coordinate_format.to_lowercase().as_str() {
         "decimal" => CoordinateFormat::Decimal,
         "degrees" => CoordinateFormat::DecimalDegrees,
@@ -132,6 +190,8 @@ pub async fn generate_targets_csv(
         include_progress: false,
         decimal_places,
         coordinate_format: coord_format,
+        units: Some(settings_service::get_unit_preferences(&state)),
+        ..Default::default()
     };
 
     Ok(generate_csv_content(&targets, &options))
@@ -143,7 +203,7 @@ pub async fn generate_targets_xml(
     targets: Vec<SimpleTarget>,
     coordinate_format: String,
     decimal_places: usize,
-) -> Result<String, String> {
+) -> Result<String, AppError> {
     let coord_format = match coordinate_format.to_lowercase().as_str() {
         "decimal" => CoordinateFormat::Decimal,
         "degrees" => CoordinateFormat::DecimalDegrees,
@@ -158,6 +218,7 @@ pub async fn generate_targets_xml(
         include_progress: false,
         decimal_places,
         coordinate_format: coord_format,
+        ..Default::default()
     };
 
     Ok(generate_xml_content(&targets, &options))
@@ -166,49 +227,82 @@ pub async fn generate_targets_xml(
 /// Export sequence to file
 #[command]
 pub async fn export_sequence_to_file(
+    state: State<'_, SharedState>,
     sequence: SimpleSequence,
     path: String,
     options: ExportOptions,
-) -> Result<(), String> {
-    let result = export_sequence(&sequence, &options);
+) -> Result<(), AppError> {
+    let format = options.format;
+    let result = export_sequence(&sequence, &with_unit_preferences(&state, options));
 
     if !result.success {
-        return Err(result.errors.join(", "));
+        return Err(AppError::Validation(result.errors));
     }
 
+    let path = path_guard::check_path(&state, &path)?;
     tokio::fs::write(&path, result.content)
         .await
-        .map_err(|e| format!("Failed to write file: {}", e))
+        .context("Failed to write file")?;
+    remember_export_format(&state, &sequence, format).await;
+    Ok(())
+}
+
+/// Export sequence to several formats at once, one file per format in `dir`
+#[command]
+pub async fn export_sequence_multi(
+    state: State<'_, SharedState>,
+    sequence: SimpleSequence,
+    formats: Vec<ExportFormat>,
+    dir: String,
+    options: Option<ExportOptions>,
+) -> Result<Vec<FormatExportResult>, AppError> {
+    let dir = path_guard::check_path(&state, &dir)?;
+    let options = with_unit_preferences(&state, options.unwrap_or_default());
+    let results =
+        crate::services::export_service::export_sequence_multi(&sequence, &formats, &options, &dir)
+            .await;
+    if let Some(last) = results.iter().rev().find(|r| r.success) {
+        remember_export_format(&state, &sequence, last.format).await;
+    }
+    Ok(results)
 }
 
 /// Export targets to file
 #[command]
 pub async fn export_targets_to_file(
+    state: State<'_, SharedState>,
     targets: Vec<SimpleTarget>,
     path: String,
     format: String,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     let content = match format.to_lowercase().as_str() {
         "csv" => {
-            let options = ExportOptions::default();
+            let options = with_unit_preferences(&state, ExportOptions::default());
             generate_csv_content(&targets, &options)
         }
         "xml" => {
             let options = ExportOptions::default();
             generate_xml_content(&targets, &options)
         }
-        _ => return Err(format!("Unsupported format: {}", format)),
+        _ => {
+            return Err(AppError::Unsupported(format!(
+                "Unsupported format: {}",
+                format
+            )))
+        }
     };
 
+    let path = path_guard::check_path(&state, &path)?;
     tokio::fs::write(&path, content)
         .await
-        .map_err(|e| format!("Failed to write file: {}", e))
+        .context("Failed to write file")
 }
 
 /// Format coordinates for display
 #[command]
 #[allow(clippy::too_many_arguments)]
 pub async fn format_coordinates(
+    state: State<'_, SharedState>,
     ra_hours: i32,
     ra_minutes: i32,
     ra_seconds: f64,
@@ -218,7 +312,7 @@ pub async fn format_coordinates(
     negative_dec: bool,
     format: String,
     decimal_places: usize,
-) -> Result<(String, String), String> {
+) -> Result<(String, String), AppError> {
     let coords = crate::models::Coordinates {
         ra_hours,
         ra_minutes,
@@ -227,6 +321,7 @@ pub async fn format_coordinates(
         dec_minutes,
         dec_seconds,
         negative_dec,
+        epoch: Default::default(),
     };
 
     let coord_format = match format.to_lowercase().as_str() {
@@ -236,15 +331,16 @@ pub async fn format_coordinates(
         _ => CoordinateFormat::Sexagesimal,
     };
 
+    let units = settings_service::get_unit_preferences(&state);
     Ok((
-        format_ra(&coords, coord_format, decimal_places),
-        format_dec(&coords, coord_format, decimal_places),
+        localize_decimal(&format_ra(&coords, coord_format, decimal_places), &units),
+        localize_decimal(&format_dec(&coords, coord_format, decimal_places), &units),
     ))
 }
 
 /// Get available export formats
 #[command]
-pub async fn get_export_formats() -> Result<Vec<(String, String, String)>, String> {
+pub async fn get_export_formats() -> Result<Vec<(String, String, String)>, AppError> {
     Ok(vec![
         (
             "csv".to_string(),
@@ -276,6 +372,11 @@ pub async fn get_export_formats() -> Result<Vec<(String, String, String)>, Strin
             "Voyager".to_string(),
             "Voyager sequence format".to_string(),
         ),
+        (
+            "voyager_robotarget".to_string(),
+            "Voyager RoboTarget".to_string(),
+            "Voyager RoboTarget JSON".to_string(),
+        ),
         (
             "nina_target_set".to_string(),
             "NINA Target Set".to_string(),
@@ -289,9 +390,32 @@ pub async fn get_export_formats() -> Result<Vec<(String, String, String)>, Strin
     ])
 }
 
+/// Generate a standalone HTML report for the scheduled night
+#[command]
+pub async fn generate_session_report(
+    state: State<'_, SharedState>,
+    sequence: SimpleSequence,
+    location: Option<ObserverLocation>,
+    site_id: Option<String>,
+    date: String,
+    options: Option<SessionReportOptions>,
+) -> Result<ExportResult, AppError> {
+    let location = input::location(&state, location, site_id)?;
+    let date = input::date("date", &date)?;
+
+    let mut options = options.unwrap_or_default();
+    if options.units.is_none() {
+        options.units = Some(settings_service::get_unit_preferences(&state));
+    }
+
+    Ok(crate::services::export_service::generate_session_report(
+        &sequence, &location, date, &options,
+    ))
+}
+
 /// Get available coordinate formats
 #[command]
-pub async fn get_coordinate_formats() -> Result<Vec<(String, String, String)>, String> {
+pub async fn get_coordinate_formats() -> Result<Vec<(String, String, String)>, AppError> {
     Ok(vec![
         (
             "sexagesimal".to_string(),
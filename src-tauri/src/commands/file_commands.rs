@@ -1,10 +1,22 @@
 //! File operation commands
 
-use std::path::PathBuf;
-use tauri::command;
+use std::path::Path;
 
+use tauri::{command, State};
+
+use crate::error::{AppError, ResultExt};
 use crate::models::*;
-use crate::services::{file_service, serializer, settings_service};
+use crate::services::auto_save::{self, AutoSaveStatus};
+use crate::services::file_watcher::{self, ReloadResult, WatchedFileKind};
+use crate::services::sequence_archive::{self, ArchiveThumbnail, SequenceArchive};
+use crate::services::sequence_format::{self, LoadedSequence};
+use crate::services::sequence_statistics::{self, DestinationSpaceCheck};
+use crate::services::{
+    backup_service, file_service, nina_type_registry, path_guard, serializer, settings_service,
+};
+use crate::state::{AppState, SharedState};
+
+use super::input;
 
 /// Open file dialog and return selected path
 #[command]
@@ -13,7 +25,7 @@ pub async fn show_open_dialog(
     _filters: Option<Vec<FileFilter>>,
     _default_path: Option<String>,
     _multiple: Option<bool>,
-) -> Result<Option<Vec<String>>, String> {
+) -> Result<Option<Vec<String>>, AppError> {
     // This will be handled by tauri-plugin-dialog on the frontend
     // This command is for additional processing if needed
     Ok(None)
@@ -26,7 +38,7 @@ pub async fn show_save_dialog(
     _filters: Option<Vec<FileFilter>>,
     _default_path: Option<String>,
     _default_name: Option<String>,
-) -> Result<Option<String>, String> {
+) -> Result<Option<String>, AppError> {
     // This will be handled by tauri-plugin-dialog on the frontend
     Ok(None)
 }
@@ -40,36 +52,52 @@ pub struct FileFilter {
 
 /// Read file contents
 #[command]
-pub async fn read_file_contents(path: String) -> Result<String, String> {
-    let path = PathBuf::from(&path);
-    file_service::read_file(&path)
-        .await
-        .map_err(|e| e.to_string())
+pub async fn read_file_contents(
+    state: State<'_, SharedState>,
+    path: String,
+) -> Result<String, AppError> {
+    let path = path_guard::check_path(&state, &path)?;
+    file_service::read_file(&path).await.map_err(AppError::from)
 }
 
 /// Write file contents
 #[command]
-pub async fn write_file_contents(path: String, contents: String) -> Result<(), String> {
-    let path = PathBuf::from(&path);
+pub async fn write_file_contents(
+    state: State<'_, SharedState>,
+    path: String,
+    contents: String,
+) -> Result<(), AppError> {
+    let path = path_guard::check_path(&state, &path)?;
     file_service::write_file(&path, &contents)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(AppError::from)
 }
 
 /// Load simple sequence from file
 #[command]
-pub async fn load_simple_sequence_file(path: String) -> Result<SimpleSequence, String> {
-    let path = PathBuf::from(&path);
-    let sequence = file_service::load_simple_sequence(&path)
+pub async fn load_simple_sequence_file(
+    state: State<'_, SharedState>,
+    path: String,
+) -> Result<SimpleSequence, AppError> {
+    open_simple_sequence(&state, &path_guard::check_path(&state, &path)?).await
+}
+
+/// Load a simple sequence from a checked path and remember it as recent
+pub(crate) async fn open_simple_sequence(
+    state: &AppState,
+    path: &Path,
+) -> Result<SimpleSequence, AppError> {
+    let sequence = file_service::load_simple_sequence(path)
         .await
-        .map_err(|e| e.to_string())?;
+        .context(format!("Opening {}", path.display()))?;
 
     // Add to recent files
-    settings_service::add_recent_file(&path.display().to_string()).await?;
+    settings_service::add_recent_sequence_file(state, &path.display().to_string(), &sequence)
+        .await?;
 
     // Update last directory
     if let Some(parent) = path.parent() {
-        settings_service::set_last_directory(&parent.display().to_string()).await?;
+        settings_service::set_last_directory(state, &parent.display().to_string()).await?;
     }
 
     Ok(sequence)
@@ -78,35 +106,87 @@ pub async fn load_simple_sequence_file(path: String) -> Result<SimpleSequence, S
 /// Save simple sequence to file
 #[command]
 pub async fn save_simple_sequence_file(
+    state: State<'_, SharedState>,
     path: String,
     sequence: SimpleSequence,
-) -> Result<(), String> {
-    let path = PathBuf::from(&path);
-    file_service::save_simple_sequence(&path, &sequence)
+) -> Result<(), AppError> {
+    write_simple_sequence(&state, &path_guard::check_path(&state, &path)?, &sequence).await
+}
+
+/// Save a simple sequence to a checked path, backing up the file it
+/// replaces, and remember it as recent
+pub(crate) async fn write_simple_sequence(
+    state: &AppState,
+    path: &Path,
+    sequence: &SimpleSequence,
+) -> Result<(), AppError> {
+    // A failed backup must not keep the user from saving
+    if let Err(e) = backup_service::backup_simple_file_before_save(state, path).await {
+        log::warn!("Failed to back up {} before saving: {}", path.display(), e);
+    }
+
+    file_service::save_simple_sequence(path, sequence)
         .await
-        .map_err(|e| e.to_string())?;
+        .context(format!("Saving {}", path.display()))?;
+    acknowledge_save(state, path).await;
 
     // Add to recent files
-    settings_service::add_recent_file(&path.display().to_string()).await?;
+    settings_service::add_recent_sequence_file(state, &path.display().to_string(), sequence)
+        .await?;
 
     // Update last directory
     if let Some(parent) = path.parent() {
-        settings_service::set_last_directory(&parent.display().to_string()).await?;
+        settings_service::set_last_directory(state, &parent.display().to_string()).await?;
     }
 
     Ok(())
 }
 
+/// Load a sequence file in any format the app opens, detected from the
+/// content: simple or editor JSON, a NINA sequence or template, or a
+/// legacy NINA XML target set
+#[command]
+pub async fn load_any_sequence(
+    state: State<'_, SharedState>,
+    path: String,
+) -> Result<LoadedSequence, AppError> {
+    let path = path_guard::check_path(&state, &path)?;
+    let stable_ids = state.settings.read().stable_nina_ids;
+    let plugins = nina_type_registry::plugin_types(&state);
+    let loaded = sequence_format::load_any_sequence(&path, stable_ids, &plugins)
+        .await
+        .context(format!("Opening {}", path.display()))?;
+
+    // Add to recent files
+    let recent = path.display().to_string();
+    match &loaded {
+        LoadedSequence::SimpleJson { sequence } | LoadedSequence::XmlTargetSet { sequence, .. } => {
+            settings_service::add_recent_sequence_file(&state, &recent, sequence).await?
+        }
+        _ => settings_service::add_recent_file(&state, &recent).await?,
+    }
+
+    // Update last directory
+    if let Some(parent) = path.parent() {
+        settings_service::set_last_directory(&state, &parent.display().to_string()).await?;
+    }
+
+    Ok(loaded)
+}
+
 /// Load editor sequence from file
 #[command]
-pub async fn load_editor_sequence_file(path: String) -> Result<EditorSequence, String> {
-    let path = PathBuf::from(&path);
+pub async fn load_editor_sequence_file(
+    state: State<'_, SharedState>,
+    path: String,
+) -> Result<EditorSequence, AppError> {
+    let path = path_guard::check_path(&state, &path)?;
     let sequence = file_service::load_editor_sequence(&path)
         .await
-        .map_err(|e| e.to_string())?;
+        .context(format!("Opening {}", path.display()))?;
 
     // Add to recent files
-    settings_service::add_recent_file(&path.display().to_string()).await?;
+    settings_service::add_recent_file(&state, &path.display().to_string()).await?;
 
     Ok(sequence)
 }
@@ -114,102 +194,277 @@ pub async fn load_editor_sequence_file(path: String) -> Result<EditorSequence, S
 /// Save editor sequence to file
 #[command]
 pub async fn save_editor_sequence_file(
+    state: State<'_, SharedState>,
     path: String,
     sequence: EditorSequence,
-) -> Result<(), String> {
-    let path = PathBuf::from(&path);
+) -> Result<(), AppError> {
+    let path = path_guard::check_path(&state, &path)?;
+    // A failed backup must not keep the user from saving
+    if let Err(e) = backup_service::backup_editor_file_before_save(&state, &path).await {
+        log::warn!("Failed to back up {} before saving: {}", path.display(), e);
+    }
+
     file_service::save_editor_sequence(&path, &sequence)
         .await
-        .map_err(|e| e.to_string())?;
+        .context(format!("Saving {}", path.display()))?;
+    acknowledge_save(&state, &path).await;
 
     // Add to recent files
-    settings_service::add_recent_file(&path.display().to_string()).await?;
+    settings_service::add_recent_file(&state, &path.display().to_string()).await?;
 
     Ok(())
 }
 
+/// Save a sequence archive (`.ctes`). The site and equipment profile
+/// default to the active ones.
+#[command]
+pub async fn save_sequence_archive(
+    state: State<'_, SharedState>,
+    path: String,
+    sequence: SimpleSequence,
+    site_id: Option<String>,
+    equipment_profile_id: Option<String>,
+    thumbnails: Option<Vec<ArchiveThumbnail>>,
+) -> Result<(), AppError> {
+    let path = path_guard::check_path(&state, &path)?;
+    let site = match site_id {
+        Some(id) => Some(
+            settings_service::get_site(&state, &id)
+                .ok_or_else(|| AppError::NotFound(format!("Observing site not found: {}", id)))?,
+        ),
+        None => settings_service::get_active_site(&state),
+    };
+    let equipment_profile = match equipment_profile_id {
+        Some(id) => Some(
+            settings_service::get_equipment_profile(&state, &id).ok_or_else(|| {
+                AppError::NotFound(format!("Equipment profile not found: {}", id))
+            })?,
+        ),
+        None => settings_service::get_active_equipment_profile(&state),
+    };
+
+    let archive = SequenceArchive {
+        sequence,
+        site,
+        equipment_profile,
+        thumbnails: thumbnails.unwrap_or_default(),
+    };
+    sequence_archive::save_sequence_archive(&path, &archive).await?;
+
+    settings_service::add_recent_sequence_file(
+        &state,
+        &path.display().to_string(),
+        &archive.sequence,
+    )
+    .await?;
+    Ok(())
+}
+
+/// Load a sequence archive (`.ctes`)
+#[command]
+pub async fn load_sequence_archive(
+    state: State<'_, SharedState>,
+    path: String,
+) -> Result<SequenceArchive, AppError> {
+    let path = path_guard::check_path(&state, &path)?;
+    let archive = sequence_archive::load_sequence_archive(&path).await?;
+
+    settings_service::add_recent_sequence_file(
+        &state,
+        &path.display().to_string(),
+        &archive.sequence,
+    )
+    .await?;
+    if let Some(parent) = path.parent() {
+        settings_service::set_last_directory(&state, &parent.display().to_string()).await?;
+    }
+
+    Ok(archive)
+}
+
+/// Take the editor's own save as the watched file's new baseline
+pub(crate) async fn acknowledge_save(state: &AppState, path: &Path) {
+    if let Err(e) = file_watcher::acknowledge_save(state, path).await {
+        log::warn!("Failed to update file watcher after saving: {}", e);
+    }
+}
+
+/// Watch the open sequence file for external changes, emitting
+/// `file://changed` events
+#[command]
+pub async fn watch_sequence_file(
+    state: State<'_, SharedState>,
+    path: String,
+    kind: WatchedFileKind,
+) -> Result<(), AppError> {
+    file_watcher::watch_file(&state, &path_guard::check_path(&state, &path)?, kind)
+        .await
+        .map_err(AppError::from)
+}
+
+/// Stop watching the open sequence file
+#[command]
+pub fn unwatch_sequence_file(state: State<'_, SharedState>) {
+    file_watcher::unwatch_file(&state);
+}
+
+/// Reload the watched sequence file if it changed on disk. Reports a
+/// conflict instead when there are unsaved edits, unless `force` is set.
+#[command]
+pub async fn reload_if_changed(
+    state: State<'_, SharedState>,
+    path: String,
+    has_unsaved_changes: bool,
+    force: Option<bool>,
+) -> Result<ReloadResult, AppError> {
+    file_watcher::reload_if_changed(
+        &state,
+        &path_guard::check_path(&state, &path)?,
+        has_unsaved_changes,
+        force.unwrap_or(false),
+    )
+    .await
+    .map_err(AppError::from)
+}
+
 /// Import targets from CSV
 #[command]
-pub async fn import_targets_csv(path: String) -> Result<Vec<SimpleTarget>, String> {
-    let path = PathBuf::from(&path);
+pub async fn import_targets_csv(
+    state: State<'_, SharedState>,
+    path: String,
+) -> Result<Vec<SimpleTarget>, AppError> {
+    let path = path_guard::check_path(&state, &path)?;
     file_service::import_targets_from_csv(&path)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(AppError::from)
 }
 
 /// Import targets from CSV content
 #[command]
-pub async fn import_targets_csv_content(content: String) -> Result<Vec<SimpleTarget>, String> {
-    serializer::import_from_csv(&content).map_err(|e| e.to_string())
+pub async fn import_targets_csv_content(content: String) -> Result<Vec<SimpleTarget>, AppError> {
+    serializer::import_from_csv(&content).map_err(AppError::from)
 }
 
 /// Export simple sequence to CSV
 #[command]
-pub fn export_sequence_csv(sequence: SimpleSequence) -> Result<String, String> {
-    serializer::export_to_csv(&sequence).map_err(|e| e.to_string())
+pub fn export_sequence_csv(sequence: SimpleSequence) -> Result<String, AppError> {
+    serializer::export_to_csv(&sequence).map_err(AppError::from)
 }
 
 /// Export simple sequence to XML
 #[command]
-pub fn export_sequence_xml(sequence: SimpleSequence) -> Result<String, String> {
-    serializer::export_to_xml(&sequence).map_err(|e| e.to_string())
+pub fn export_sequence_xml(sequence: SimpleSequence) -> Result<String, AppError> {
+    serializer::export_to_xml(&sequence).map_err(AppError::from)
 }
 
 /// Export simple sequence to NINA target set format
 #[command]
-pub fn export_sequence_target_set(sequence: SimpleSequence) -> Result<String, String> {
-    serializer::export_to_target_set(&sequence).map_err(|e| e.to_string())
+pub fn export_sequence_target_set(sequence: SimpleSequence) -> Result<String, AppError> {
+    serializer::export_to_target_set(&sequence).map_err(AppError::from)
 }
 
 /// Get file info
 #[command]
-pub async fn get_file_info(path: String) -> Result<file_service::FileInfo, String> {
-    let path = PathBuf::from(&path);
+pub async fn get_file_info(
+    state: State<'_, SharedState>,
+    path: String,
+) -> Result<file_service::FileInfo, AppError> {
+    let path = path_guard::check_path(&state, &path)?;
     file_service::get_file_info(&path)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(AppError::from)
+}
+
+/// Free space at an image destination compared with the bytes the night's
+/// frames need
+#[command]
+pub fn check_destination_space(
+    state: State<'_, SharedState>,
+    path: String,
+    required_bytes: Option<u64>,
+) -> Result<DestinationSpaceCheck, AppError> {
+    let checked = path_guard::check_path(&state, &path)?;
+    let space = file_service::get_disk_space(&checked)?;
+    Ok(sequence_statistics::check_destination_space(
+        &path,
+        space,
+        required_bytes.unwrap_or(0),
+    ))
 }
 
 /// List directory contents
 #[command]
 pub async fn list_directory(
+    state: State<'_, SharedState>,
     path: String,
     extensions: Option<Vec<String>>,
-) -> Result<Vec<file_service::FileInfo>, String> {
-    let path = PathBuf::from(&path);
+) -> Result<Vec<file_service::FileInfo>, AppError> {
+    let path = path_guard::check_path(&state, &path)?;
     let ext_refs: Option<Vec<&str>> = extensions
         .as_ref()
         .map(|v| v.iter().map(|s| s.as_str()).collect());
 
     file_service::list_directory(&path, ext_refs.as_deref())
         .await
-        .map_err(|e| e.to_string())
+        .map_err(AppError::from)
 }
 
 /// Check if file exists
 #[command]
-pub async fn file_exists(path: String) -> Result<bool, String> {
-    let path = PathBuf::from(&path);
+pub async fn file_exists(state: State<'_, SharedState>, path: String) -> Result<bool, AppError> {
+    let path = path_guard::check_path(&state, &path)?;
     Ok(file_service::file_exists(&path).await)
 }
 
 /// Delete file
 #[command]
-pub async fn delete_file(path: String) -> Result<(), String> {
-    let path = PathBuf::from(&path);
+pub async fn delete_file(state: State<'_, SharedState>, path: String) -> Result<(), AppError> {
+    let path = path_guard::check_path(&state, &path)?;
     file_service::delete_file(&path)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(AppError::from)
 }
 
 /// Copy file
 #[command]
-pub async fn copy_file(from: String, to: String) -> Result<(), String> {
-    let from = PathBuf::from(&from);
-    let to = PathBuf::from(&to);
+pub async fn copy_file(
+    state: State<'_, SharedState>,
+    from: String,
+    to: String,
+) -> Result<(), AppError> {
+    let from = path_guard::check_path(&state, &from)?;
+    let to = path_guard::check_path(&state, &to)?;
     file_service::copy_file(&from, &to)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(AppError::from)
+}
+
+/// Approve a directory for file commands, e.g. the folder of a file the
+/// user picked in a dialog. Returns the normalized path.
+#[command]
+pub async fn add_allowed_directory(
+    state: State<'_, SharedState>,
+    path: String,
+) -> Result<String, AppError> {
+    path_guard::add_allowed_directory(&state, &path).await
+}
+
+/// Withdraw a directory approval
+#[command]
+pub async fn remove_allowed_directory(
+    state: State<'_, SharedState>,
+    path: String,
+) -> Result<(), AppError> {
+    path_guard::remove_allowed_directory(&state, &path).await
+}
+
+/// List the directories file commands may access
+#[command]
+pub fn list_allowed_directories(state: State<'_, SharedState>) -> Vec<String> {
+    path_guard::allowed_roots(&state)
+        .iter()
+        .map(|p| p.display().to_string())
+        .collect()
 }
 
 /// Get default save directory
@@ -228,51 +483,50 @@ pub fn get_app_data_directory() -> String {
 
 /// Auto-save sequence
 #[command]
-pub async fn auto_save_sequence(sequence: SimpleSequence) -> Result<String, String> {
+pub async fn auto_save_sequence(sequence: SimpleSequence) -> Result<String, AppError> {
     let path = file_service::create_auto_save_path(&sequence.id);
 
     // Ensure directory exists
     if let Some(parent) = path.parent() {
-        tokio::fs::create_dir_all(parent)
-            .await
-            .map_err(|e| e.to_string())?;
+        tokio::fs::create_dir_all(parent).await?;
     }
 
-    let contents =
-        serializer::serialize_simple_sequence_json(&sequence).map_err(|e| e.to_string())?;
+    let contents = serializer::serialize_simple_sequence_json(&sequence)?;
 
-    file_service::write_file(&path, &contents)
-        .await
-        .map_err(|e| e.to_string())?;
+    file_service::write_file(&path, &contents).await?;
 
     Ok(path.display().to_string())
 }
 
+/// Auto-save settings and the backend's snapshots of open sessions
+#[command]
+pub fn get_auto_save_status(state: State<'_, SharedState>) -> AutoSaveStatus {
+    auto_save::get_auto_save_status(&state)
+}
+
 /// Load auto-saved sequence
 #[command]
-pub async fn load_auto_save(sequence_id: String) -> Result<Option<SimpleSequence>, String> {
+pub async fn load_auto_save(sequence_id: String) -> Result<Option<SimpleSequence>, AppError> {
+    input::id("sequenceId", &sequence_id)?;
     let path = file_service::create_auto_save_path(&sequence_id);
 
     if !file_service::file_exists(&path).await {
         return Ok(None);
     }
 
-    let sequence = file_service::load_simple_sequence(&path)
-        .await
-        .map_err(|e| e.to_string())?;
+    let sequence = file_service::load_simple_sequence(&path).await?;
 
     Ok(Some(sequence))
 }
 
 /// Clear auto-save
 #[command]
-pub async fn clear_auto_save(sequence_id: String) -> Result<(), String> {
+pub async fn clear_auto_save(sequence_id: String) -> Result<(), AppError> {
+    input::id("sequenceId", &sequence_id)?;
     let path = file_service::create_auto_save_path(&sequence_id);
 
     if file_service::file_exists(&path).await {
-        file_service::delete_file(&path)
-            .await
-            .map_err(|e| e.to_string())?;
+        file_service::delete_file(&path).await?;
     }
 
     Ok(())
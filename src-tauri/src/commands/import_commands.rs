@@ -2,45 +2,73 @@
 //!
 //! Tauri commands for importing targets from various formats
 
-use tauri::command;
-
-use crate::models::SimpleTarget;
+use chrono::Utc;
+use tauri::{command, State};
+
+use crate::error::{AppError, ResultExt};
+use crate::models::{SimpleSequence, SimpleTarget};
+use crate::services::csv_io;
+use crate::services::ephemeris::{
+    create_moving_target, parse_horizons_ephemeris, parse_mpc_elements,
+};
+use crate::services::fits_header::{self, FitsDirectoryScan};
+use crate::services::image_library::{
+    self, ImageLibraryAnalysis, SubtractAcquiredResult, DEFAULT_MATCH_TOLERANCE_ARCMIN,
+};
+use crate::services::import_preview::{
+    self, ImportCommitResult, ImportPreview, ImportSelection, DEFAULT_DUPLICATE_RADIUS_ARCMIN,
+};
 use crate::services::import_service::{
-    create_target_from_fits, detect_csv_format, parse_apt_format, parse_csv_content,
-    parse_fits_header, parse_stellarium_skylist, parse_voyager_format, parse_xml_content,
-    CsvColumnMapping, FitsHeaderInfo, ImportResult,
+    self, apply_platesolve_to_target, create_target_from_fits, detect_csv_format, parse_apt_format,
+    parse_csv_content, parse_fits_header, parse_platesolve_result, parse_stellarium_skylist,
+    parse_voyager_format, parse_xisf_header, parse_xml_content, CsvColumnMapping, FitsHeaderInfo,
+    ImportResult, NinaTargetSetImport, PlateSolveResult,
 };
+use crate::services::job_queue::Operation;
+use crate::services::path_guard;
+use crate::services::sgp_import::{import_sgp_sequence, SgpImportResult};
+use crate::services::voyager_robotarget::parse_robotarget_json;
+use crate::services::xisf_header;
+use crate::state::SharedState;
+
+use super::input;
 
 /// Import targets from CSV content
 #[command]
 pub async fn import_csv_content(
     content: String,
     mapping: Option<CsvColumnMapping>,
-) -> Result<ImportResult, String> {
+) -> Result<ImportResult, AppError> {
     Ok(parse_csv_content(&content, mapping))
 }
 
 /// Import targets from Stellarium skylist content
 #[command]
-pub async fn import_stellarium_content(content: String) -> Result<ImportResult, String> {
+pub async fn import_stellarium_content(content: String) -> Result<ImportResult, AppError> {
     Ok(parse_stellarium_skylist(&content))
 }
 
 /// Import targets from APT format content
 #[command]
-pub async fn import_apt_content(content: String) -> Result<ImportResult, String> {
+pub async fn import_apt_content(content: String) -> Result<ImportResult, AppError> {
     Ok(parse_apt_format(&content))
 }
 
 /// Import targets from Voyager format content
 #[command]
-pub async fn import_voyager_content(content: String) -> Result<ImportResult, String> {
+pub async fn import_voyager_content(content: String) -> Result<ImportResult, AppError> {
     Ok(parse_voyager_format(&content))
 }
 
+/// Import targets from Voyager RoboTarget JSON content
+#[command]
+pub async fn import_voyager_robotarget_content(content: String) -> Result<ImportResult, AppError> {
+    Ok(parse_robotarget_json(&content))
+}
+
 /// Import targets from XML content
 #[command]
-pub async fn import_xml_content(content: String) -> Result<ImportResult, String> {
+pub async fn import_xml_content(content: String) -> Result<ImportResult, AppError> {
     Ok(parse_xml_content(&content))
 }
 
@@ -49,114 +77,244 @@ pub async fn import_xml_content(content: String) -> Result<ImportResult, String>
 pub async fn import_auto_detect(
     content: String,
     file_extension: Option<String>,
-) -> Result<ImportResult, String> {
-    let ext = file_extension.unwrap_or_default().to_lowercase();
-
-    // Try to detect by extension first
-    match ext.as_str() {
-        "csv" => Ok(parse_csv_content(&content, None)),
-        "skylist" | "sl" => Ok(parse_stellarium_skylist(&content)),
-        "xml" => Ok(parse_xml_content(&content)),
-        _ => {
-            // Try to detect by content
-            if content.trim().starts_with("<?xml") || content.trim().starts_with("<") {
-                Ok(parse_xml_content(&content))
-            } else if content.contains("[") && content.contains("RA=") {
-                Ok(parse_voyager_format(&content))
-            } else if content.starts_with("#") || content.contains("designation") {
-                Ok(parse_stellarium_skylist(&content))
-            } else {
-                // Default to CSV
-                Ok(parse_csv_content(&content, None))
-            }
-        }
-    }
+) -> Result<ImportResult, AppError> {
+    import_service::detect_and_import(&content, &file_extension.unwrap_or_default())
+        .map_err(AppError::from)
 }
 
 /// Detect CSV format from headers
 #[command]
-pub async fn detect_csv_format_from_headers(headers: Vec<String>) -> Result<String, String> {
+pub async fn detect_csv_format_from_headers(headers: Vec<String>) -> Result<String, AppError> {
     let format = detect_csv_format(&headers);
     Ok(format!("{:?}", format))
 }
 
 /// Parse FITS header from bytes
 #[command]
-pub async fn parse_fits_header_bytes(data: Vec<u8>) -> Result<FitsHeaderInfo, String> {
-    parse_fits_header(&data)
+pub async fn parse_fits_header_bytes(data: Vec<u8>) -> Result<FitsHeaderInfo, AppError> {
+    parse_fits_header(&data).map_err(AppError::from)
+}
+
+/// Parse XISF header from bytes
+#[command]
+pub async fn parse_xisf_header_bytes(data: Vec<u8>) -> Result<FitsHeaderInfo, AppError> {
+    parse_xisf_header(&data).map_err(AppError::from)
 }
 
 /// Create target from FITS header info
 #[command]
 pub async fn create_target_from_fits_info(
     info: FitsHeaderInfo,
-) -> Result<Option<SimpleTarget>, String> {
+) -> Result<Option<SimpleTarget>, AppError> {
     Ok(create_target_from_fits(&info))
 }
 
 /// Import from CSV file
 #[command]
 pub async fn import_csv_file(
+    state: State<'_, SharedState>,
     path: String,
     mapping: Option<CsvColumnMapping>,
-) -> Result<ImportResult, String> {
+) -> Result<ImportResult, AppError> {
+    let path = path_guard::check_path(&state, &path)?;
     let content = tokio::fs::read_to_string(&path)
         .await
-        .map_err(|e| format!("Failed to read file: {}", e))?;
+        .context("Failed to read file")?;
 
     Ok(parse_csv_content(&content, mapping))
 }
 
 /// Import from Stellarium file
 #[command]
-pub async fn import_stellarium_file(path: String) -> Result<ImportResult, String> {
+pub async fn import_stellarium_file(
+    state: State<'_, SharedState>,
+    path: String,
+) -> Result<ImportResult, AppError> {
+    let path = path_guard::check_path(&state, &path)?;
     let content = tokio::fs::read_to_string(&path)
         .await
-        .map_err(|e| format!("Failed to read file: {}", e))?;
+        .context("Failed to read file")?;
 
     Ok(parse_stellarium_skylist(&content))
 }
 
 /// Import from XML file
 #[command]
-pub async fn import_xml_file(path: String) -> Result<ImportResult, String> {
+pub async fn import_xml_file(
+    state: State<'_, SharedState>,
+    path: String,
+) -> Result<ImportResult, AppError> {
+    let path = path_guard::check_path(&state, &path)?;
     let content = tokio::fs::read_to_string(&path)
         .await
-        .map_err(|e| format!("Failed to read file: {}", e))?;
+        .context("Failed to read file")?;
 
     Ok(parse_xml_content(&content))
 }
 
 /// Import from FITS file (header only)
 #[command]
-pub async fn import_fits_file(path: String) -> Result<Option<SimpleTarget>, String> {
-    let data = tokio::fs::read(&path)
+pub async fn import_fits_file(
+    state: State<'_, SharedState>,
+    path: String,
+) -> Result<Option<SimpleTarget>, AppError> {
+    let path = path_guard::check_path(&state, &path)?;
+    let info = tokio::task::spawn_blocking(move || fits_header::read_fits_header_file(&path))
         .await
-        .map_err(|e| format!("Failed to read file: {}", e))?;
+        .map_err(|e| AppError::Internal(format!("Failed to read file: {}", e)))??;
+    Ok(create_target_from_fits(&info))
+}
 
-    let info = parse_fits_header(&data)?;
+/// Import from XISF file (header only)
+#[command]
+pub async fn import_xisf_file(
+    state: State<'_, SharedState>,
+    path: String,
+) -> Result<Option<SimpleTarget>, AppError> {
+    let path = path_guard::check_path(&state, &path)?;
+    let info = tokio::task::spawn_blocking(move || xisf_header::read_xisf_header_file(&path))
+        .await
+        .map_err(|e| AppError::Internal(format!("Failed to read file: {}", e)))??;
     Ok(create_target_from_fits(&info))
 }
 
-/// Batch import from multiple files
+/// Aggregate per-object exposure statistics of the FITS files in a folder
+#[command]
+pub async fn batch_scan_fits_directory(
+    state: State<'_, SharedState>,
+    directory: String,
+) -> Result<FitsDirectoryScan, AppError> {
+    let directory = path_guard::check_path(&state, &directory)?;
+    tokio::task::spawn_blocking(move || fits_header::scan_fits_directory(&directory))
+        .await
+        .map_err(|e| AppError::Internal(format!("FITS scan failed: {}", e)))?
+        .map_err(AppError::from)
+}
+
+/// Acquired integration per target from the FITS and XISF files in a folder
+#[command]
+pub async fn analyze_image_library(
+    state: State<'_, SharedState>,
+    directory: String,
+) -> Result<ImageLibraryAnalysis, AppError> {
+    let directory = path_guard::check_path(&state, &directory)?;
+    tokio::task::spawn_blocking(move || image_library::analyze_image_library(&directory))
+        .await
+        .map_err(|e| AppError::Internal(format!("Image library scan failed: {}", e)))?
+        .map_err(AppError::from)
+}
+
+/// Reduce exposure counts of a sequence by the frames already acquired
+#[command]
+pub async fn subtract_acquired_from_sequence(
+    sequence: SimpleSequence,
+    analysis: ImageLibraryAnalysis,
+    tolerance_arcmin: Option<f64>,
+) -> Result<SubtractAcquiredResult, AppError> {
+    Ok(image_library::subtract_acquired_from_sequence(
+        &sequence,
+        &analysis,
+        tolerance_arcmin.unwrap_or(DEFAULT_MATCH_TOLERANCE_ARCMIN),
+    ))
+}
+
+/// Import a Sequence Generator Pro sequence from content
+#[command]
+pub async fn import_sgp_content(content: String) -> Result<SgpImportResult, AppError> {
+    import_sgp_sequence(&content).map_err(AppError::from)
+}
+
+/// Import a Sequence Generator Pro sequence file (.sgf)
+#[command]
+pub async fn import_sgp_file(
+    state: State<'_, SharedState>,
+    path: String,
+) -> Result<SgpImportResult, AppError> {
+    let path = path_guard::check_path(&state, &path)?;
+    let content = tokio::fs::read_to_string(&path)
+        .await
+        .context("Failed to read file")?;
+
+    import_sgp_sequence(&content).map_err(AppError::from)
+}
+
+/// Import a NINA legacy XML target set from content
+#[command]
+pub async fn import_nina_xml_target_set(content: String) -> Result<NinaTargetSetImport, AppError> {
+    import_service::import_nina_xml_target_set(&content).map_err(AppError::from)
+}
+
+/// Import a NINA legacy XML target set file
+#[command]
+pub async fn import_nina_xml_target_set_file(
+    state: State<'_, SharedState>,
+    path: String,
+) -> Result<NinaTargetSetImport, AppError> {
+    let path = path_guard::check_path(&state, &path)?;
+    let content = tokio::fs::read_to_string(&path)
+        .await
+        .context("Failed to read file")?;
+
+    import_service::import_nina_xml_target_set(&content).map_err(AppError::from)
+}
+
+/// Parse plate solve result content (ASTAP .ini or WCS header)
+#[command]
+pub async fn parse_platesolve_content(content: String) -> Result<PlateSolveResult, AppError> {
+    parse_platesolve_result(&content).map_err(AppError::from)
+}
+
+/// Import a plate solve result file and update the target's coordinates and rotation
 #[command]
-pub async fn batch_import_files(paths: Vec<String>) -> Result<ImportResult, String> {
+pub async fn import_platesolve_result(
+    state: State<'_, SharedState>,
+    path: String,
+    target: SimpleTarget,
+) -> Result<SimpleTarget, AppError> {
+    let path = path_guard::check_path(&state, &path)?;
+    let data = tokio::fs::read(&path)
+        .await
+        .context("Failed to read file")?;
+
+    let result = parse_platesolve_result(&String::from_utf8_lossy(&data))?;
+    let mut target = target;
+    apply_platesolve_to_target(&mut target, &result);
+    Ok(target)
+}
+
+/// Batch import from multiple files. Cancelling `operation_id` stops
+/// before the next file.
+#[command]
+pub async fn batch_import_files(
+    state: State<'_, SharedState>,
+    paths: Vec<String>,
+    operation_id: Option<String>,
+) -> Result<ImportResult, AppError> {
+    let operation = Operation::begin(&state, operation_id);
     let mut all_targets = Vec::new();
     let mut all_errors = Vec::new();
     let mut all_warnings = Vec::new();
     let mut total_rows = 0;
 
     for path in &paths {
-        let ext = std::path::Path::new(path)
+        operation.checkpoint()?;
+        let path = match path_guard::check_path(&state, path) {
+            Ok(path) => path,
+            Err(e) => {
+                all_errors.push(e.to_string());
+                continue;
+            }
+        };
+        let ext = path
             .extension()
             .and_then(|e| e.to_str())
             .unwrap_or("")
             .to_lowercase();
 
-        let content = match tokio::fs::read_to_string(path).await {
+        let content = match tokio::fs::read_to_string(&path).await {
             Ok(c) => c,
             Err(e) => {
-                all_errors.push(format!("Failed to read {}: {}", path, e));
+                all_errors.push(format!("Failed to read {}: {}", path.display(), e));
                 continue;
             }
         };
@@ -191,7 +349,7 @@ pub async fn batch_import_files(paths: Vec<String>) -> Result<ImportResult, Stri
 pub async fn validate_csv_mapping(
     headers: Vec<String>,
     mapping: CsvColumnMapping,
-) -> Result<Vec<String>, String> {
+) -> Result<Vec<String>, AppError> {
     let mut errors = Vec::new();
     let headers_lower: Vec<String> = headers.iter().map(|h| h.to_lowercase()).collect();
 
@@ -226,20 +384,94 @@ pub async fn validate_csv_mapping(
 pub async fn preview_csv_content(
     content: String,
     max_rows: usize,
-) -> Result<Vec<Vec<String>>, String> {
-    let mut rows = Vec::new();
+) -> Result<Vec<Vec<String>>, AppError> {
+    Ok(csv_io::read_records(&content, None)?
+        .into_iter()
+        .take(max_rows)
+        .map(|record| record.fields)
+        .collect())
+}
 
-    for (idx, line) in content.lines().enumerate() {
-        if idx >= max_rows {
-            break;
-        }
+/// Preview CSV rows with validation status and duplicate flags. The
+/// preview is kept for `commit_import`.
+#[command]
+pub async fn preview_csv_import(
+    state: State<'_, SharedState>,
+    content: String,
+    mapping: Option<CsvColumnMapping>,
+    existing_targets: Vec<SimpleTarget>,
+    duplicate_radius_arcmin: Option<f64>,
+) -> Result<ImportPreview, AppError> {
+    let preview = import_preview::preview_csv(
+        &content,
+        mapping,
+        &existing_targets,
+        duplicate_radius_arcmin.unwrap_or(DEFAULT_DUPLICATE_RADIUS_ARCMIN),
+    );
+    import_preview::store_preview(&state, &preview);
+    Ok(preview)
+}
 
-        let fields: Vec<String> = line
-            .split(',')
-            .map(|s| s.trim().trim_matches('"').to_string())
-            .collect();
-        rows.push(fields);
-    }
+/// Preview content of any supported format, detected like
+/// `import_auto_detect`
+#[command]
+pub async fn preview_import_content(
+    state: State<'_, SharedState>,
+    content: String,
+    file_extension: Option<String>,
+    existing_targets: Vec<SimpleTarget>,
+    duplicate_radius_arcmin: Option<f64>,
+) -> Result<ImportPreview, AppError> {
+    let radius = duplicate_radius_arcmin.unwrap_or(DEFAULT_DUPLICATE_RADIUS_ARCMIN);
+    let is_csv = file_extension
+        .as_deref()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("csv"));
+
+    let preview = if is_csv {
+        import_preview::preview_csv(&content, None, &existing_targets, radius)
+    } else {
+        let result = import_auto_detect(content, file_extension).await?;
+        import_preview::preview_import_result(result, &existing_targets, radius)
+    };
+    import_preview::store_preview(&state, &preview);
+    Ok(preview)
+}
+
+/// Create targets from the selected rows of a preview
+#[command]
+pub async fn commit_import(
+    state: State<'_, SharedState>,
+    selection: ImportSelection,
+) -> Result<ImportCommitResult, AppError> {
+    import_preview::commit_import(&state, &selection)
+}
 
-    Ok(rows)
+/// Drop a preview that will not be committed
+#[command]
+pub async fn discard_import_preview(
+    state: State<'_, SharedState>,
+    preview_id: String,
+) -> Result<(), AppError> {
+    input::id("previewId", &preview_id)?;
+    import_preview::discard_preview(&state, &preview_id);
+    Ok(())
+}
+
+/// Import comets/asteroids from MPC one-line orbital elements
+#[command]
+pub async fn import_mpc_elements_content(content: String) -> Result<Vec<SimpleTarget>, AppError> {
+    let now = Utc::now();
+    Ok(parse_mpc_elements(&content)?
+        .into_iter()
+        .map(|moving| create_moving_target(moving, now))
+        .collect())
+}
+
+/// Import a moving target from a JPL Horizons ephemeris table
+#[command]
+pub async fn import_horizons_ephemeris_content(content: String) -> Result<SimpleTarget, AppError> {
+    Ok(create_moving_target(
+        parse_horizons_ephemeris(&content)?,
+        Utc::now(),
+    ))
 }
@@ -0,0 +1,137 @@
+//! Command input checks
+//!
+//! Checks payload fields before they reach the services. Bad fields are
+//! reported by their payload name in an [`AppError::InvalidFields`], so the
+//! frontend can point at the offending input.
+
+use chrono::NaiveDate;
+use validator::{Validate, ValidationErrors};
+
+use crate::error::{AppError, AppResult, FieldError};
+use crate::services::astronomy::ObserverLocation;
+use crate::services::settings_service;
+use crate::state::AppState;
+
+const DATE_FORMAT: &str = "%Y-%m-%d";
+
+fn fail(fields: Vec<FieldError>) -> AppResult<()> {
+    if fields.is_empty() {
+        Ok(())
+    } else {
+        Err(AppError::InvalidFields(fields))
+    }
+}
+
+/// `sky_brightness` as the frontend sends it, `skyBrightness`
+fn camel_case(name: &str) -> String {
+    let mut result = String::with_capacity(name.len());
+    let mut upper = false;
+    for c in name.chars() {
+        if c == '_' {
+            upper = true;
+        } else if upper {
+            result.extend(c.to_uppercase());
+            upper = false;
+        } else {
+            result.push(c);
+        }
+    }
+    result
+}
+
+/// Field errors of a derived validation, under the payload field `prefix`
+fn field_errors(prefix: &str, errors: &ValidationErrors) -> Vec<FieldError> {
+    let mut fields: Vec<FieldError> = errors
+        .field_errors()
+        .into_iter()
+        .flat_map(|(name, errors)| {
+            let field = format!("{}.{}", prefix, camel_case(name));
+            errors.iter().map(move |error| {
+                let message = error
+                    .message
+                    .as_ref()
+                    .map(|message| message.to_string())
+                    .unwrap_or_else(|| format!("failed the {} check", error.code));
+                FieldError::new(field.clone(), message)
+            })
+        })
+        .collect();
+    fields.sort_by(|a, b| a.field.cmp(&b.field));
+    fields
+}
+
+fn id_error(field: &str, value: &str) -> Option<FieldError> {
+    value
+        .trim()
+        .is_empty()
+        .then(|| FieldError::new(field, "must not be empty"))
+}
+
+/// Reject an empty or blank id
+pub(crate) fn id(field: &str, value: &str) -> AppResult<()> {
+    fail(id_error(field, value).into_iter().collect())
+}
+
+/// Reject a negative or non-finite number
+pub(crate) fn non_negative(field: &str, value: f64) -> AppResult<()> {
+    let error = (!value.is_finite() || value < 0.0)
+        .then(|| FieldError::new(field, "must be a non-negative number"));
+    fail(error.into_iter().collect())
+}
+
+/// Check the location or site id, then resolve the location the command
+/// works for
+pub(crate) fn location(
+    state: &AppState,
+    location: Option<ObserverLocation>,
+    site_id: Option<String>,
+) -> AppResult<ObserverLocation> {
+    let mut fields = Vec::new();
+    if let Some(id) = &site_id {
+        fields.extend(id_error("siteId", id));
+    }
+    if let Some(Err(errors)) = location.as_ref().map(Validate::validate) {
+        fields.extend(field_errors("location", &errors));
+    }
+    fail(fields)?;
+
+    settings_service::resolve_location(state, location, site_id)
+}
+
+fn parse_date(field: &str, value: &str) -> Result<NaiveDate, FieldError> {
+    NaiveDate::parse_from_str(value.trim(), DATE_FORMAT).map_err(|_| {
+        FieldError::new(
+            field,
+            format!("must be a YYYY-MM-DD date, got \"{}\"", value),
+        )
+    })
+}
+
+/// Parse a `YYYY-MM-DD` date
+pub(crate) fn date(field: &str, value: &str) -> AppResult<NaiveDate> {
+    parse_date(field, value).map_err(|error| AppError::InvalidFields(vec![error]))
+}
+
+/// Parse `startDate` and `endDate`, with the end not before the start
+pub(crate) fn date_range(start: &str, end: &str) -> AppResult<(NaiveDate, NaiveDate)> {
+    match (parse_date("startDate", start), parse_date("endDate", end)) {
+        (Ok(start), Ok(end)) if end < start => Err(AppError::InvalidFields(vec![FieldError::new(
+            "endDate",
+            "must not be before the start date",
+        )])),
+        (Ok(start), Ok(end)) => Ok((start, end)),
+        (start, end) => Err(AppError::InvalidFields(
+            [start.err(), end.err()].into_iter().flatten().collect(),
+        )),
+    }
+}
+
+/// Parse a `YYYY-MM` month into its first day
+pub(crate) fn month(field: &str, value: &str) -> AppResult<NaiveDate> {
+    NaiveDate::parse_from_str(&format!("{}-01", value.trim()), DATE_FORMAT).map_err(|_| {
+        AppError::InvalidFields(vec![FieldError::new(
+            field,
+            format!("must be a YYYY-MM month, got \"{}\"", value),
+        )])
+    })
+}
@@ -0,0 +1,247 @@
+//! Background job commands
+//!
+//! Heavy optimizer and astronomy calculations started as background jobs.
+//! Each job reports its status on the channel passed to `start_job`.
+
+use std::path::Path;
+
+use serde::Deserialize;
+use serde_json::Value;
+use tauri::ipc::Channel;
+use tauri::{command, State};
+
+use crate::error::AppError;
+use crate::models::{Coordinates, SimpleSequence};
+use crate::services::astronomy::ObserverLocation;
+use crate::services::ephemeris::update_moving_targets_for_night;
+use crate::services::job_queue::{self, run_blocking, JobHandle, JobListener, JobStatus};
+use crate::services::sequence_optimizer::{calculate_visibility_parallel, score_observation_dates};
+use crate::services::{night_split, sequence_library, settings_service, target_season};
+use crate::state::SharedState;
+
+use super::input;
+use super::optimizer_commands::{best_observation_date, BestDateResult};
+
+/// Work a background job can do
+#[derive(Debug, Clone, Deserialize)]
+#[serde(
+    tag = "kind",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum JobRequest {
+    /// Visibility windows of every target on one date
+    BatchVisibility {
+        sequence: SimpleSequence,
+        location: Option<ObserverLocation>,
+        site_id: Option<String>,
+        date: String,
+        min_altitude: f64,
+    },
+    /// Best night for the sequence in a date range, without forecasts
+    BestObservationDate {
+        sequence: SimpleSequence,
+        location: Option<ObserverLocation>,
+        site_id: Option<String>,
+        start_date: String,
+        end_date: String,
+    },
+    /// Remaining frames partitioned over a range of nights
+    SplitAcrossNights {
+        sequence: SimpleSequence,
+        location: Option<ObserverLocation>,
+        site_id: Option<String>,
+        start_date: String,
+        end_date: String,
+    },
+    /// Yearly imaging season of a target
+    TargetSeason {
+        coordinates: Coordinates,
+        location: Option<ObserverLocation>,
+        site_id: Option<String>,
+        year: i32,
+        min_altitude: Option<f64>,
+        min_dark_hours: Option<f64>,
+    },
+    /// Re-index the sequence library folder
+    RefreshLibrary,
+}
+
+impl JobRequest {
+    fn kind(&self) -> &'static str {
+        match self {
+            JobRequest::BatchVisibility { .. } => "batchVisibility",
+            JobRequest::BestObservationDate { .. } => "bestObservationDate",
+            JobRequest::SplitAcrossNights { .. } => "splitAcrossNights",
+            JobRequest::TargetSeason { .. } => "targetSeason",
+            JobRequest::RefreshLibrary => "refreshLibrary",
+        }
+    }
+}
+
+fn to_value<T: serde::Serialize>(result: Result<T, String>) -> Result<Value, String> {
+    serde_json::to_value(result?).map_err(|e| format!("Failed to serialize job result: {}", e))
+}
+
+/// Work of a job, run on the blocking thread pool
+type JobWork = Box<dyn FnOnce(JobHandle) -> Result<Value, String> + Send>;
+
+/// Check the request and turn it into the job's work, so bad input fails
+/// the start call instead of the job
+fn prepare(state: &SharedState, request: JobRequest) -> Result<JobWork, AppError> {
+    Ok(match request {
+        JobRequest::BatchVisibility {
+            mut sequence,
+            location,
+            site_id,
+            date,
+            min_altitude,
+        } => {
+            let location = input::location(state, location, site_id)?;
+            let date = input::date("date", &date)?;
+            update_moving_targets_for_night(&mut sequence, &location, date);
+            Box::new(move |job| {
+                to_value(calculate_visibility_parallel(
+                    &sequence.targets,
+                    &location,
+                    date,
+                    min_altitude,
+                    &job,
+                ))
+            })
+        }
+        JobRequest::BestObservationDate {
+            sequence,
+            location,
+            site_id,
+            start_date,
+            end_date,
+        } => {
+            let location = input::location(state, location, site_id)?;
+            let (start, end) = input::date_range(&start_date, &end_date)?;
+            Box::new(move |job| {
+                let scores = score_observation_dates(&sequence, &location, start, end, &job)?;
+                let (best_date, best_score, date_scores) =
+                    best_observation_date(start, scores, &[]);
+                to_value(Ok(BestDateResult {
+                    best_date: best_date.format("%Y-%m-%d").to_string(),
+                    best_score,
+                    date_scores,
+                    weather_warning: None,
+                }))
+            })
+        }
+        JobRequest::SplitAcrossNights {
+            mut sequence,
+            location,
+            site_id,
+            start_date,
+            end_date,
+        } => {
+            let location = input::location(state, location, site_id)?;
+            let (start, end) = input::date_range(&start_date, &end_date)?;
+            update_moving_targets_for_night(&mut sequence, &location, start);
+            Box::new(move |job| {
+                to_value(night_split::split_sequence_across_nights(
+                    &sequence, &location, start, end, &job,
+                ))
+            })
+        }
+        JobRequest::TargetSeason {
+            coordinates,
+            location,
+            site_id,
+            year,
+            min_altitude,
+            min_dark_hours,
+        } => {
+            let location = input::location(state, location, site_id)?;
+            Box::new(move |job| {
+                to_value(target_season::calculate_target_season(
+                    &coordinates,
+                    &location,
+                    year,
+                    min_altitude.unwrap_or(30.0),
+                    min_dark_hours.unwrap_or(2.0),
+                    &job,
+                ))
+            })
+        }
+        JobRequest::RefreshLibrary => {
+            let directory = settings_service::get_library_directory(state).ok_or_else(|| {
+                AppError::NotFound("No sequence library folder is set".to_string())
+            })?;
+            let state = state.clone();
+            Box::new(move |_| {
+                to_value(
+                    tauri::async_runtime::block_on(sequence_library::refresh_library_index(
+                        &state,
+                        Path::new(&directory),
+                    ))
+                    .map_err(String::from),
+                )
+            })
+        }
+    })
+}
+
+/// Start a background job and return its id. Status changes, including
+/// progress, are sent on `on_event`.
+#[command]
+pub async fn start_job(
+    state: State<'_, SharedState>,
+    request: JobRequest,
+    on_event: Channel<JobStatus>,
+) -> Result<String, AppError> {
+    let kind = request.kind();
+    let work = prepare(&state, request)?;
+    let listener: JobListener = Box::new(move |status| {
+        if let Err(e) = on_event.send(status.clone()) {
+            log::warn!("Failed to send job status: {}", e);
+        }
+    });
+
+    Ok(job_queue::start_job(
+        &state,
+        kind,
+        Some(listener),
+        move |job| async move { run_blocking(move || work(job)).await },
+    ))
+}
+
+/// Current status of a job, including its result once completed
+#[command]
+pub fn get_job_status(
+    state: State<'_, SharedState>,
+    job_id: String,
+) -> Result<JobStatus, AppError> {
+    input::id("jobId", &job_id)?;
+    job_queue::get_job_status(&state, &job_id)
+        .ok_or_else(|| AppError::NotFound(format!("Job not found: {}", job_id)))
+}
+
+/// All known jobs, newest first
+#[command]
+pub fn list_jobs(state: State<'_, SharedState>) -> Vec<JobStatus> {
+    job_queue::list_jobs(&state)
+}
+
+/// Ask a queued or running job to stop
+#[command]
+pub fn cancel_job(state: State<'_, SharedState>, job_id: String) -> Result<(), AppError> {
+    input::id("jobId", &job_id)?;
+    job_queue::cancel_job(&state, &job_id)
+}
+
+/// Ask a directly called command running with `operation_id` to stop.
+/// Returns false when nothing runs under that id.
+#[command]
+pub fn cancel_operation(state: State<'_, SharedState>, operation_id: String) -> bool {
+    job_queue::cancel_operation(&state, &operation_id)
+}
+
+/// Forget finished jobs and their results
+#[command]
+pub fn clear_finished_jobs(state: State<'_, SharedState>) -> usize {
+    job_queue::clear_finished_jobs(&state)
+}
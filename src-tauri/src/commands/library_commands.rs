@@ -0,0 +1,62 @@
+//! Sequence library commands
+
+use std::path::Path;
+use tauri::{command, State};
+
+use crate::error::AppError;
+use crate::services::sequence_library::{self, LibraryEntry, LibraryIndex, LibrarySearchQuery};
+use crate::services::{path_guard, settings_service};
+use crate::state::SharedState;
+
+/// Get the sequence library folder
+#[command]
+pub fn get_library_directory(state: State<'_, SharedState>) -> Option<String> {
+    settings_service::get_library_directory(&state)
+}
+
+/// Set the sequence library folder and index it
+#[command]
+pub async fn set_library_directory(
+    state: State<'_, SharedState>,
+    path: Option<String>,
+) -> Result<Option<LibraryIndex>, AppError> {
+    let path = path
+        .map(|path| path_guard::check_directory(&path))
+        .transpose()?;
+    settings_service::set_library_directory(&state, path.clone()).await?;
+    match path {
+        Some(path) => sequence_library::refresh_library_index(&state, Path::new(&path))
+            .await
+            .map(Some),
+        None => Ok(None),
+    }
+}
+
+/// Re-index the sequence library folder
+#[command]
+pub async fn refresh_library_index(
+    state: State<'_, SharedState>,
+) -> Result<LibraryIndex, AppError> {
+    let directory = settings_service::get_library_directory(&state)
+        .ok_or_else(|| AppError::InvalidInput("No sequence library folder is set".to_string()))?;
+    sequence_library::refresh_library_index(&state, Path::new(&directory)).await
+}
+
+/// Get the last built library index
+#[command]
+pub async fn get_library_index(
+    state: State<'_, SharedState>,
+) -> Result<Option<LibraryIndex>, AppError> {
+    Ok(sequence_library::get_library_index(&state).await)
+}
+
+/// Search the sequence library
+#[command]
+pub async fn search_sequence_library(
+    state: State<'_, SharedState>,
+    query: LibrarySearchQuery,
+) -> Result<Vec<LibraryEntry>, AppError> {
+    sequence_library::search_sequence_library(&state, &query)
+        .await
+        .map_err(AppError::from)
+}
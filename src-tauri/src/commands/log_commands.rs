@@ -1,36 +1,42 @@
 //! Logging commands
 
-use tauri::command;
+use tauri::{command, State};
 
-use crate::services::log_service::{self, LogEntry, LogLevel};
+use crate::error::AppError;
+use crate::services::log_service::{
+    self, LogEntry, LogExportFormat, LogLevel, LogTimeRange, SupportBundleInfo,
+};
+use crate::services::path_guard;
+use crate::state::SharedState;
 
 /// Log debug message
 #[command]
-pub fn log_debug(category: String, message: String) {
-    log_service::log_debug(&category, &message);
+pub fn log_debug(state: State<'_, SharedState>, category: String, message: String) {
+    log_service::log_debug(&state, &category, &message);
 }
 
 /// Log info message
 #[command]
-pub fn log_info(category: String, message: String) {
-    log_service::log_info(&category, &message);
+pub fn log_info(state: State<'_, SharedState>, category: String, message: String) {
+    log_service::log_info(&state, &category, &message);
 }
 
 /// Log warning message
 #[command]
-pub fn log_warning(category: String, message: String) {
-    log_service::log_warning(&category, &message);
+pub fn log_warning(state: State<'_, SharedState>, category: String, message: String) {
+    log_service::log_warning(&state, &category, &message);
 }
 
 /// Log error message
 #[command]
-pub fn log_error(category: String, message: String) {
-    log_service::log_error(&category, &message);
+pub fn log_error(state: State<'_, SharedState>, category: String, message: String) {
+    log_service::log_error(&state, &category, &message);
 }
 
 /// Log with details
 #[command]
 pub fn log_with_details(
+    state: State<'_, SharedState>,
     level: String,
     category: String,
     message: String,
@@ -44,18 +50,28 @@ pub fn log_with_details(
         _ => LogLevel::Info,
     };
 
-    log_service::log_with_details(level, &category, &message, details);
+    log_service::log_with_details(&state, level, &category, &message, details);
 }
 
 /// Log operation
 #[command]
-pub fn log_operation(operation: String, target: String, success: bool, error: Option<String>) {
-    log_service::log_operation(&operation, &target, success, error.as_deref());
+pub fn log_operation(
+    state: State<'_, SharedState>,
+    operation: String,
+    target: String,
+    success: bool,
+    error: Option<String>,
+) {
+    log_service::log_operation(&state, &operation, &target, success, error.as_deref());
 }
 
 /// Get recent logs
 #[command]
-pub fn get_recent_logs(count: usize, level_filter: Option<String>) -> Vec<LogEntry> {
+pub fn get_recent_logs(
+    state: State<'_, SharedState>,
+    count: usize,
+    level_filter: Option<String>,
+) -> Vec<LogEntry> {
     let level = level_filter.and_then(|l| match l.as_str() {
         "debug" => Some(LogLevel::Debug),
         "info" => Some(LogLevel::Info),
@@ -64,41 +80,84 @@ pub fn get_recent_logs(count: usize, level_filter: Option<String>) -> Vec<LogEnt
         _ => None,
     });
 
-    log_service::get_recent_logs(count, level)
+    log_service::get_recent_logs(&state, count, level)
 }
 
 /// Get logs by category
 #[command]
-pub fn get_logs_by_category(category: String, count: usize) -> Vec<LogEntry> {
-    log_service::get_logs_by_category(&category, count)
+pub fn get_logs_by_category(
+    state: State<'_, SharedState>,
+    category: String,
+    count: usize,
+) -> Vec<LogEntry> {
+    log_service::get_logs_by_category(&state, &category, count)
 }
 
 /// Clear log buffer
 #[command]
-pub fn clear_log_buffer() {
-    log_service::clear_log_buffer();
+pub fn clear_log_buffer(state: State<'_, SharedState>) {
+    log_service::clear_log_buffer(&state);
 }
 
 /// Flush logs to file
 #[command]
-pub async fn flush_logs() -> Result<usize, String> {
-    log_service::flush_logs_to_file().await
+pub async fn flush_logs(state: State<'_, SharedState>) -> Result<usize, AppError> {
+    log_service::flush_logs_to_file(&state).await
 }
 
 /// Read log file
 #[command]
-pub async fn read_log_file(date: String) -> Result<String, String> {
+pub async fn read_log_file(date: String) -> Result<String, AppError> {
     log_service::read_log_file(&date).await
 }
 
 /// List log files
 #[command]
-pub async fn list_log_files() -> Result<Vec<String>, String> {
+pub async fn list_log_files() -> Result<Vec<String>, AppError> {
     log_service::list_log_files().await
 }
 
 /// Clean old logs
 #[command]
-pub async fn clean_old_logs(max_age_days: i64) -> Result<usize, String> {
+pub async fn clean_old_logs(max_age_days: i64) -> Result<usize, AppError> {
     log_service::clean_old_logs(max_age_days).await
 }
+
+/// Delete the oldest log files until they fit in `max_total_size` bytes
+#[command]
+pub async fn clean_logs_by_size(max_total_size: u64) -> Result<usize, AppError> {
+    log_service::clean_logs_by_size(max_total_size).await
+}
+
+/// Export logs in a time range as JSONL or CSV
+#[command]
+pub async fn export_logs(
+    state: State<'_, SharedState>,
+    range: Option<LogTimeRange>,
+    format: LogExportFormat,
+    path: String,
+) -> Result<usize, AppError> {
+    log_service::export_logs(
+        &state,
+        &range.unwrap_or_default(),
+        format,
+        &path_guard::check_path(&state, &path)?,
+    )
+    .await
+}
+
+/// Create a zip with recent logs, redacted settings and the current
+/// autosave for bug reports
+#[command]
+pub async fn create_support_bundle(
+    state: State<'_, SharedState>,
+    path: String,
+    sequence_id: Option<String>,
+) -> Result<SupportBundleInfo, AppError> {
+    log_service::create_support_bundle(
+        &state,
+        &path_guard::check_path(&state, &path)?,
+        sequence_id.as_deref(),
+    )
+    .await
+}
@@ -9,10 +9,15 @@ pub mod clipboard_commands;
 pub mod export_commands;
 pub mod file_commands;
 pub mod import_commands;
+mod input;
+pub mod job_commands;
+pub mod library_commands;
 pub mod log_commands;
 pub mod nina_commands;
 pub mod optimizer_commands;
+pub mod remote_commands;
 pub mod sequence_commands;
+pub mod session_commands;
 pub mod settings_commands;
 pub mod template_commands;
 
@@ -23,9 +28,13 @@ pub use clipboard_commands::*;
 pub use export_commands::*;
 pub use file_commands::*;
 pub use import_commands::*;
+pub use job_commands::*;
+pub use library_commands::*;
 pub use log_commands::*;
 pub use nina_commands::*;
 pub use optimizer_commands::*;
+pub use remote_commands::*;
 pub use sequence_commands::*;
+pub use session_commands::*;
 pub use settings_commands::*;
 pub use template_commands::*;
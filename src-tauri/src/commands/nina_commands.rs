@@ -1,55 +1,78 @@
 //! NINA format commands
 
-use std::path::PathBuf;
-use tauri::command;
+use tauri::{command, State};
 
-use crate::models::EditorSequence;
-use crate::services::{file_service, nina_serializer};
+use crate::error::AppError;
+use crate::models::{EditorSequence, EditorSequenceItem, EditorTarget, SimpleSequence};
+use crate::services::nina_remote::{self, NinaConnection, NinaEquipmentStatus};
+use crate::services::nina_type_registry::{self, NinaTypeSchema};
+use crate::services::sequence_convert::{self, SimpleConversion};
+use crate::services::target_sync::{self, TargetSyncResult};
+use crate::services::{file_service, nina_serializer, path_guard};
+use crate::state::SharedState;
 
 /// Export editor sequence to NINA JSON format
 #[command]
-pub fn export_to_nina_json(sequence: EditorSequence) -> Result<String, String> {
-    nina_serializer::export_to_nina(&sequence)
+pub fn export_to_nina_json(
+    state: State<'_, SharedState>,
+    sequence: EditorSequence,
+) -> Result<String, AppError> {
+    let plugins = nina_type_registry::plugin_types(&state);
+    nina_serializer::export_to_nina(&sequence, &plugins).map_err(AppError::from)
 }
 
 /// Import NINA JSON to editor sequence
 #[command]
-pub fn import_from_nina_json(json: String) -> Result<EditorSequence, String> {
-    nina_serializer::import_from_nina(&json)
+pub fn import_from_nina_json(
+    state: State<'_, SharedState>,
+    json: String,
+) -> Result<EditorSequence, AppError> {
+    let stable_ids = state.settings.read().stable_nina_ids;
+    let plugins = nina_type_registry::plugin_types(&state);
+    nina_serializer::import_from_nina(&json, stable_ids, &plugins).map_err(AppError::Parse)
 }
 
 /// Validate NINA JSON format
 #[command]
-pub fn validate_nina_format(json: String) -> Result<(), Vec<String>> {
-    nina_serializer::validate_nina_json(&json)
+pub fn validate_nina_format(json: String) -> Result<(), AppError> {
+    nina_serializer::validate_nina_json(&json).map_err(AppError::from)
 }
 
 /// Save editor sequence to NINA JSON file
 #[command]
-pub async fn save_nina_sequence_file(path: String, sequence: EditorSequence) -> Result<(), String> {
-    let json = nina_serializer::export_to_nina(&sequence)?;
-    let path = PathBuf::from(&path);
-    file_service::write_file(&path, &json)
-        .await
-        .map_err(|e| e.to_string())
+pub async fn save_nina_sequence_file(
+    state: State<'_, SharedState>,
+    path: String,
+    sequence: EditorSequence,
+) -> Result<(), AppError> {
+    let plugins = nina_type_registry::plugin_types(&state);
+    let json = nina_serializer::export_to_nina(&sequence, &plugins)?;
+    let path = path_guard::check_path(&state, &path)?;
+    file_service::write_file(&path, &json).await?;
+    super::file_commands::acknowledge_save(&state, &path).await;
+    Ok(())
 }
 
 /// Load editor sequence from NINA JSON file
 #[command]
-pub async fn load_nina_sequence_file(path: String) -> Result<EditorSequence, String> {
-    let path = PathBuf::from(&path);
-    let content = file_service::read_file(&path)
-        .await
-        .map_err(|e| e.to_string())?;
-    nina_serializer::import_from_nina(&content)
+pub async fn load_nina_sequence_file(
+    state: State<'_, SharedState>,
+    path: String,
+) -> Result<EditorSequence, AppError> {
+    let path = path_guard::check_path(&state, &path)?;
+    let content = file_service::read_file(&path).await?;
+    let stable_ids = state.settings.read().stable_nina_ids;
+    let plugins = nina_type_registry::plugin_types(&state);
+    nina_serializer::import_from_nina(&content, stable_ids, &plugins).map_err(AppError::Parse)
 }
 
 /// Export template to NINA format
 #[command]
 pub fn export_template_to_nina(
+    state: State<'_, SharedState>,
     items: Vec<crate::models::EditorSequenceItem>,
     name: String,
-) -> Result<String, String> {
+) -> Result<String, AppError> {
     // Create a temporary sequence with just target items
     let sequence = EditorSequence {
         id: uuid::Uuid::new_v4().to_string(),
@@ -60,7 +83,36 @@ pub fn export_template_to_nina(
         global_triggers: Vec::new(),
     };
 
-    nina_serializer::export_to_nina(&sequence)
+    let plugins = nina_type_registry::plugin_types(&state);
+    nina_serializer::export_to_nina(&sequence, &plugins).map_err(AppError::from)
+}
+
+/// Convert a simple sequence to an editor sequence
+#[command]
+pub fn convert_simple_to_editor(sequence: SimpleSequence) -> EditorSequence {
+    sequence_convert::convert_simple_to_editor(&sequence)
+}
+
+/// Convert an editor sequence to a simple sequence, reporting what the
+/// simple format cannot hold
+#[command]
+pub fn convert_editor_to_simple(sequence: EditorSequence) -> SimpleConversion {
+    sequence_convert::convert_editor_to_simple(&sequence)
+}
+
+/// Write edited target info into a Deep Sky Object container and the
+/// slew, center and rotate instructions inside it
+#[command]
+pub fn sync_target_into_container(
+    mut container: EditorSequenceItem,
+    target: EditorTarget,
+) -> Result<TargetSyncResult, AppError> {
+    let updated_item_ids = target_sync::sync_target_into_container(&mut container, &target)
+        .map_err(AppError::InvalidInput)?;
+    Ok(TargetSyncResult {
+        container,
+        updated_item_ids,
+    })
 }
 
 /// Get NINA type short name
@@ -79,7 +131,11 @@ pub fn get_nina_type_short_name(full_type: String) -> String {
 
 /// Get NINA type category
 #[command]
-pub fn get_nina_type_category(full_type: String) -> String {
+pub fn get_nina_type_category(state: State<'_, SharedState>, full_type: String) -> String {
+    if let Some(plugin) = nina_type_registry::find_plugin_type(&state, &full_type) {
+        return plugin.category;
+    }
+
     // Extract from "NINA.Sequencer.SequenceItem.Camera.CoolCamera, NINA.Sequencer"
     let parts: Vec<&str> = full_type.split('.').collect();
     if parts.len() >= 4 {
@@ -94,11 +150,47 @@ pub fn get_nina_type_category(full_type: String) -> String {
 
 /// Check if NINA type is a container
 #[command]
-pub fn is_nina_container_type(type_str: String) -> bool {
-    type_str.contains("Container")
-        || type_str.contains("SmartExposure")
-        || type_str.contains("InstructionSet")
-        || type_str.contains("DeepSkyObject")
+pub fn is_nina_container_type(state: State<'_, SharedState>, type_str: String) -> bool {
+    nina_type_registry::is_container_type(&type_str, &nina_type_registry::plugin_types(&state))
+}
+
+/// Get the parameter schema of a NINA type
+#[command]
+pub fn get_nina_item_schema(
+    state: State<'_, SharedState>,
+    item_type: String,
+) -> Result<NinaTypeSchema, AppError> {
+    nina_type_registry::get_schema(&item_type, &nina_type_registry::plugin_types(&state))
+        .ok_or_else(|| AppError::InvalidInput(format!("Unknown NINA type: {}", item_type)))
+}
+
+/// List NINA types with their schemas, optionally limited to a category
+#[command]
+pub fn list_nina_item_types(
+    state: State<'_, SharedState>,
+    category: Option<String>,
+) -> Vec<NinaTypeSchema> {
+    nina_type_registry::list_types(
+        category.as_deref(),
+        &nina_type_registry::plugin_types(&state),
+    )
+}
+
+/// Reload plugin type definitions from disk
+#[command]
+pub async fn reload_nina_plugin_types(
+    state: State<'_, SharedState>,
+) -> Result<Vec<NinaTypeSchema>, AppError> {
+    nina_type_registry::load_plugin_types(&state).await?;
+    Ok(nina_type_registry::plugin_types(&state))
+}
+
+/// Get the plugin type definitions file path
+#[command]
+pub fn get_nina_plugin_types_path() -> String {
+    nina_type_registry::get_plugin_types_path()
+        .display()
+        .to_string()
 }
 
 /// Get all NINA type categories
@@ -124,3 +216,58 @@ pub fn get_nina_categories() -> Vec<String> {
         "Trigger".to_string(),
     ]
 }
+
+/// Connect to NINA's Advanced API plugin
+#[command]
+pub async fn connect_to_nina(
+    state: State<'_, SharedState>,
+    host: String,
+    port: Option<u16>,
+) -> Result<NinaConnection, AppError> {
+    nina_remote::connect(&state, &host, port)
+        .await
+        .map_err(AppError::Network)
+}
+
+/// Disconnect from NINA
+#[command]
+pub fn disconnect_from_nina(state: State<'_, SharedState>) {
+    nina_remote::disconnect(&state);
+}
+
+/// Get the current NINA connection
+#[command]
+pub fn get_nina_connection(state: State<'_, SharedState>) -> Option<NinaConnection> {
+    nina_remote::connection(&state)
+}
+
+/// Load an editor sequence into the connected NINA instance
+#[command]
+pub async fn push_sequence_to_nina(
+    state: State<'_, SharedState>,
+    sequence: EditorSequence,
+) -> Result<(), AppError> {
+    nina_remote::push_sequence(&state, &sequence)
+        .await
+        .map_err(AppError::Network)
+}
+
+/// Fetch the sequence loaded in the connected NINA instance
+#[command]
+pub async fn pull_current_sequence_from_nina(
+    state: State<'_, SharedState>,
+) -> Result<EditorSequence, AppError> {
+    nina_remote::pull_current_sequence(&state)
+        .await
+        .map_err(AppError::Network)
+}
+
+/// Get the equipment state of the connected NINA instance
+#[command]
+pub async fn get_nina_equipment_status(
+    state: State<'_, SharedState>,
+) -> Result<NinaEquipmentStatus, AppError> {
+    nina_remote::get_equipment_status(&state)
+        .await
+        .map_err(AppError::Network)
+}
@@ -2,28 +2,48 @@
 //!
 //! Tauri commands for sequence optimization and scheduling
 
+use std::collections::HashMap;
+
 use chrono::{DateTime, NaiveDate, Utc};
-use tauri::command;
+use tauri::{command, State};
 
-use crate::models::SimpleSequence;
+use crate::error::AppError;
+use crate::models::{ObservingConstraints, OverheadProfile, SimpleSequence, SimpleTarget};
 use crate::services::astronomy::ObserverLocation;
+use crate::services::ephemeris::update_moving_targets_for_night;
+use crate::services::job_queue::{JobHandle, Operation};
+use crate::services::night_split::{self, NightSplitResult};
+use crate::services::observing_constraints::{self, ImagingWindow, ImagingWindowOptions};
+use crate::services::runtime_uncertainty::{
+    self, MonteCarloOptions, RuntimeDistribution, SessionVariables,
+};
 use crate::services::sequence_optimizer::{
-    apply_optimized_order, calculate_etas_parallel, calculate_visibility_parallel,
-    detect_conflicts, get_schedule_info, merge_sequences, optimize_sequence, split_sequence,
-    BatchCalculationResult, ConflictResult, OptimizationResult, OptimizationStrategy,
-    TargetScheduleInfo,
+    apply_exposure_counts, apply_optimized_order, calculate_etas_parallel,
+    calculate_visibility_parallel, detect_conflicts, get_schedule_info, merge_sequences,
+    optimize_sequence, rebalance_exposure_counts, score_observation_dates, split_sequence,
+    BatchCalculationResult, ConflictResult, ExposureCountProposal, OptimizationResult,
+    OptimizationStrategy, RebalanceMode, RebalanceResult, TargetScheduleInfo,
 };
+use crate::services::simulator::{self, SimulationOptions, SimulationResult};
+use crate::services::timeline::{build_sequence_timeline, SequenceTimeline};
+use crate::services::{calculator, dark_calendar, satellite, settings_service, weather};
+use crate::state::SharedState;
+
+use super::input;
 
 /// Optimize sequence target order
 #[command]
 pub async fn optimize_target_order(
-    sequence: SimpleSequence,
-    location: ObserverLocation,
+    state: State<'_, SharedState>,
+    mut sequence: SimpleSequence,
+    location: Option<ObserverLocation>,
+    site_id: Option<String>,
     date: String,
     strategy: String,
-) -> Result<OptimizationResult, String> {
-    let date = NaiveDate::parse_from_str(&date, "%Y-%m-%d")
-        .map_err(|e| format!("Invalid date format: {}", e))?;
+) -> Result<OptimizationResult, AppError> {
+    let location = input::location(&state, location, site_id)?;
+    let date = input::date("date", &date)?;
+    update_moving_targets_for_night(&mut sequence, &location, date);
 
     let strategy = match strategy.to_lowercase().as_str() {
         "max_altitude" | "maxaltitude" => OptimizationStrategy::MaxAltitude,
@@ -32,52 +52,113 @@ pub async fn optimize_target_order(
         "visibility_duration" | "visibilityduration" => OptimizationStrategy::VisibilityDuration,
         "minimize_slew" | "minimizeslew" => OptimizationStrategy::MinimizeSlew,
         "moon_avoidance" | "moonavoidance" => OptimizationStrategy::MoonAvoidance,
+        "priority_weighted" | "priorityweighted" | "priority" => {
+            OptimizationStrategy::PriorityWeighted
+        }
+        "time_windowed" | "timewindowed" => OptimizationStrategy::TimeWindowed,
         _ => OptimizationStrategy::Combined,
     };
 
-    Ok(optimize_sequence(&sequence, &location, date, strategy))
+    let mount = settings_service::get_active_equipment_profile(&state)
+        .map(|p| p.mount)
+        .unwrap_or_default();
+    Ok(optimize_sequence(
+        &sequence, &location, date, strategy, &mount,
+    ))
 }
 
 /// Detect scheduling conflicts
 #[command]
 pub async fn detect_schedule_conflicts(
-    sequence: SimpleSequence,
-    location: ObserverLocation,
+    state: State<'_, SharedState>,
+    mut sequence: SimpleSequence,
+    location: Option<ObserverLocation>,
+    site_id: Option<String>,
     date: String,
-) -> Result<ConflictResult, String> {
-    let date = NaiveDate::parse_from_str(&date, "%Y-%m-%d")
-        .map_err(|e| format!("Invalid date format: {}", e))?;
+) -> Result<ConflictResult, AppError> {
+    let location = input::location(&state, location, site_id)?;
+    let date = input::date("date", &date)?;
+    update_moving_targets_for_night(&mut sequence, &location, date);
 
-    Ok(detect_conflicts(&sequence, &location, date))
+    Ok(detect_conflicts(
+        &sequence,
+        &location,
+        date,
+        &satellite::get_catalog(&state),
+    ))
 }
 
-/// Calculate ETAs for all targets (parallel)
+/// Calculate ETAs for all targets and exposure rows (parallel), including
+/// dither, filter change and autofocus overheads (uses the active equipment
+/// profile's overheads unless given)
 #[command]
 pub async fn calculate_parallel_etas(
+    state: State<'_, SharedState>,
     sequence: SimpleSequence,
     start_time: Option<String>,
-) -> Result<Vec<BatchCalculationResult>, String> {
+    overheads: Option<OverheadProfile>,
+) -> Result<Vec<BatchCalculationResult>, AppError> {
     let start = match start_time {
         Some(s) => DateTime::parse_from_rfc3339(&s)
-            .map_err(|e| format!("Invalid datetime format: {}", e))?
+            .map_err(|e| AppError::InvalidInput(format!("Invalid datetime format: {}", e)))?
             .with_timezone(&Utc),
         None => Utc::now(),
     };
 
-    Ok(calculate_etas_parallel(&sequence, start))
+    let overheads = overheads.unwrap_or_else(|| {
+        settings_service::get_active_equipment_profile(&state)
+            .map(|p| p.overheads)
+            .unwrap_or_default()
+    });
+
+    Ok(calculate_etas_parallel(&sequence, start, Some(&overheads)))
 }
 
 /// Get scheduling info for all targets
 #[command]
 pub async fn get_target_schedule_info(
-    sequence: SimpleSequence,
-    location: ObserverLocation,
+    state: State<'_, SharedState>,
+    mut sequence: SimpleSequence,
+    location: Option<ObserverLocation>,
+    site_id: Option<String>,
     date: String,
-) -> Result<Vec<TargetScheduleInfo>, String> {
-    let date = NaiveDate::parse_from_str(&date, "%Y-%m-%d")
-        .map_err(|e| format!("Invalid date format: {}", e))?;
+) -> Result<Vec<TargetScheduleInfo>, AppError> {
+    let site = settings_service::resolve_site(&state, location.is_some(), site_id.as_deref());
+    let location = input::location(&state, location, site_id)?;
+    let date = input::date("date", &date)?;
+    update_moving_targets_for_night(&mut sequence, &location, date);
 
-    Ok(get_schedule_info(&sequence, &location, date))
+    Ok(get_schedule_info(&sequence, &location, date, site.as_ref()))
+}
+
+/// Usable imaging segments of a target over the night starting on `date`:
+/// dark time in which the constraints (the target's own unless given) are
+/// met, outside the meridian flip exclusion
+#[command]
+pub async fn calculate_imaging_window(
+    state: State<'_, SharedState>,
+    target: SimpleTarget,
+    location: Option<ObserverLocation>,
+    site_id: Option<String>,
+    date: String,
+    constraints: Option<ObservingConstraints>,
+    options: Option<ImagingWindowOptions>,
+) -> Result<ImagingWindow, AppError> {
+    let location = input::location(&state, location, site_id)?;
+    let date = input::date("date", &date)?;
+    let constraints = constraints.unwrap_or(target.constraints);
+    let errors = constraints.validate();
+    if !errors.is_empty() {
+        return Err(AppError::InvalidInput(errors.join("; ")));
+    }
+
+    Ok(observing_constraints::calculate_imaging_window(
+        &target.coordinates,
+        &location,
+        date,
+        &constraints,
+        &options.unwrap_or_default(),
+    ))
 }
 
 /// Apply optimized order to sequence
@@ -85,7 +166,7 @@ pub async fn get_target_schedule_info(
 pub async fn apply_optimization(
     mut sequence: SimpleSequence,
     order: Vec<String>,
-) -> Result<SimpleSequence, String> {
+) -> Result<SimpleSequence, AppError> {
     apply_optimized_order(&mut sequence, &order);
     Ok(sequence)
 }
@@ -95,7 +176,7 @@ pub async fn apply_optimization(
 pub async fn merge_multiple_sequences(
     sequences: Vec<SimpleSequence>,
     title: Option<String>,
-) -> Result<SimpleSequence, String> {
+) -> Result<SimpleSequence, AppError> {
     Ok(merge_sequences(&sequences, title))
 }
 
@@ -103,13 +184,13 @@ pub async fn merge_multiple_sequences(
 #[command]
 pub async fn split_sequence_by_target(
     sequence: SimpleSequence,
-) -> Result<Vec<SimpleSequence>, String> {
+) -> Result<Vec<SimpleSequence>, AppError> {
     Ok(split_sequence(&sequence))
 }
 
 /// Get available optimization strategies
 #[command]
-pub async fn get_optimization_strategies() -> Result<Vec<(String, String, String)>, String> {
+pub async fn get_optimization_strategies() -> Result<Vec<(String, String, String)>, AppError> {
     Ok(vec![
         (
             "max_altitude".to_string(),
@@ -146,40 +227,61 @@ pub async fn get_optimization_strategies() -> Result<Vec<(String, String, String
             "Combined".to_string(),
             "Use a combined optimization score".to_string(),
         ),
+        (
+            "priority_weighted".to_string(),
+            "Priority Weighted".to_string(),
+            "Give higher-priority targets their time first when windows conflict".to_string(),
+        ),
+        (
+            "time_windowed".to_string(),
+            "Time Windowed".to_string(),
+            "Schedule the night by each target's altitude when it would be imaged".to_string(),
+        ),
     ])
 }
 
 /// Calculate visibility for all targets in parallel
 #[command]
 pub async fn batch_calculate_visibility(
-    sequence: SimpleSequence,
-    location: ObserverLocation,
+    state: State<'_, SharedState>,
+    mut sequence: SimpleSequence,
+    location: Option<ObserverLocation>,
+    site_id: Option<String>,
     date: String,
     min_altitude: f64,
-) -> Result<Vec<(String, crate::services::astronomy::VisibilityWindow)>, String> {
-    let date = NaiveDate::parse_from_str(&date, "%Y-%m-%d")
-        .map_err(|e| format!("Invalid date format: {}", e))?;
-
-    Ok(calculate_visibility_parallel(
+    operation_id: Option<String>,
+) -> Result<Vec<(String, crate::services::astronomy::VisibilityWindow)>, AppError> {
+    let operation = Operation::begin(&state, operation_id);
+    let location = input::location(&state, location, site_id)?;
+    let date = input::date("date", &date)?;
+    update_moving_targets_for_night(&mut sequence, &location, date);
+
+    calculate_visibility_parallel(
         &sequence.targets,
         &location,
         date,
         min_altitude,
-    ))
+        operation.handle(),
+    )
+    .map_err(|e| operation.error(e))
 }
 
 /// Validate sequence for a specific date
 #[command]
 pub async fn validate_sequence_for_date(
-    sequence: SimpleSequence,
-    location: ObserverLocation,
+    state: State<'_, SharedState>,
+    mut sequence: SimpleSequence,
+    location: Option<ObserverLocation>,
+    site_id: Option<String>,
     date: String,
-) -> Result<ValidationReport, String> {
-    let date = NaiveDate::parse_from_str(&date, "%Y-%m-%d")
-        .map_err(|e| format!("Invalid date format: {}", e))?;
+) -> Result<ValidationReport, AppError> {
+    let site = settings_service::resolve_site(&state, location.is_some(), site_id.as_deref());
+    let location = input::location(&state, location, site_id)?;
+    let date = input::date("date", &date)?;
+    update_moving_targets_for_night(&mut sequence, &location, date);
 
-    let conflicts = detect_conflicts(&sequence, &location, date);
-    let schedule_info = get_schedule_info(&sequence, &location, date);
+    let conflicts = detect_conflicts(&sequence, &location, date, &satellite::get_catalog(&state));
+    let schedule_info = get_schedule_info(&sequence, &location, date, site.as_ref());
 
     let visible_count = schedule_info
         .iter()
@@ -231,53 +333,79 @@ pub struct ValidationReport {
 
 /// Find best observation date in a range
 #[command]
+#[allow(clippy::too_many_arguments)]
 pub async fn find_best_observation_date(
+    state: State<'_, SharedState>,
     sequence: SimpleSequence,
-    location: ObserverLocation,
+    location: Option<ObserverLocation>,
+    site_id: Option<String>,
     start_date: String,
     end_date: String,
-) -> Result<BestDateResult, String> {
-    let start = NaiveDate::parse_from_str(&start_date, "%Y-%m-%d")
-        .map_err(|e| format!("Invalid start date: {}", e))?;
-    let end = NaiveDate::parse_from_str(&end_date, "%Y-%m-%d")
-        .map_err(|e| format!("Invalid end date: {}", e))?;
-
-    if end < start {
-        return Err("End date must be after start date".to_string());
-    }
+    use_weather_forecast: Option<bool>,
+    weather_provider: Option<String>,
+    operation_id: Option<String>,
+) -> Result<BestDateResult, AppError> {
+    let operation = Operation::begin(&state, operation_id);
+    let location = input::location(&state, location, site_id)?;
+    let (start, end) = input::date_range(&start_date, &end_date)?;
+
+    // Forecast weighting is best-effort: a failed fetch leaves scores unweighted
+    let mut weather_warning = None;
+    let forecasts = if use_weather_forecast.unwrap_or(false) {
+        let provider = weather_provider
+            .as_deref()
+            .unwrap_or(weather::DEFAULT_WEATHER_PROVIDER);
+        match weather::get_night_forecasts(&state, provider, &location, start, end).await {
+            Ok(forecasts) => forecasts,
+            Err(e) => {
+                weather_warning = Some(e.to_string());
+                Vec::new()
+            }
+        }
+    } else {
+        Vec::new()
+    };
+
+    let scores = score_observation_dates(&sequence, &location, start, end, operation.handle())
+        .map_err(|e| operation.error(e))?;
+    let (best_date, best_score, date_scores) = best_observation_date(start, scores, &forecasts);
+
+    Ok(BestDateResult {
+        best_date: best_date.format("%Y-%m-%d").to_string(),
+        best_score,
+        date_scores,
+        weather_warning,
+    })
+}
 
+/// Weight night scores by the cloud cover forecast and pick the best
+/// night, `start` when none scores above zero
+pub(crate) fn best_observation_date(
+    start: NaiveDate,
+    scores: Vec<(NaiveDate, f64)>,
+    forecasts: &[weather::NightForecast],
+) -> (NaiveDate, f64, Vec<(String, f64)>) {
     let mut best_date = start;
     let mut best_score = 0.0;
-    let mut date_scores = Vec::new();
-
-    let mut current = start;
-    while current <= end {
-        let schedule_info = get_schedule_info(&sequence, &location, current);
+    let mut date_scores = Vec::with_capacity(scores.len());
 
-        let score: f64 = schedule_info
+    for (date, score) in scores {
+        let date_str = date.format("%Y-%m-%d").to_string();
+        let score = forecasts
             .iter()
-            .filter(|i| i.visibility_window.is_visible)
-            .map(|i| i.quality_score + i.visibility_window.duration_hours * 5.0)
-            .sum();
+            .find(|f| f.date == date_str)
+            .map(|f| score * weather::cloud_cover_weight(f))
+            .unwrap_or(score);
 
-        date_scores.push((current.format("%Y-%m-%d").to_string(), score));
+        date_scores.push((date_str, score));
 
         if score > best_score {
             best_score = score;
-            best_date = current;
-        }
-
-        current = current.succ_opt().unwrap_or(current);
-        if current == end && current != start {
-            break;
+            best_date = date;
         }
     }
 
-    Ok(BestDateResult {
-        best_date: best_date.format("%Y-%m-%d").to_string(),
-        best_score,
-        date_scores,
-    })
+    (best_date, best_score, date_scores)
 }
 
 /// Best date result
@@ -287,75 +415,142 @@ pub struct BestDateResult {
     pub best_date: String,
     pub best_score: f64,
     pub date_scores: Vec<(String, f64)>,
+    /// Set when forecast weighting was requested but could not be applied
+    pub weather_warning: Option<String>,
 }
 
-/// Estimate total session time
+/// Estimate total session time, with a Monte Carlo spread of the total
+/// when `monte_carlo` options are given
 #[command]
 pub async fn estimate_session_time(
+    state: State<'_, SharedState>,
     sequence: SimpleSequence,
-    location: ObserverLocation,
+    location: Option<ObserverLocation>,
+    site_id: Option<String>,
     date: String,
     include_slew_time: bool,
-) -> Result<SessionTimeEstimate, String> {
-    let date = NaiveDate::parse_from_str(&date, "%Y-%m-%d")
-        .map_err(|e| format!("Invalid date format: {}", e))?;
+    monte_carlo: Option<MonteCarloOptions>,
+) -> Result<SessionTimeEstimate, AppError> {
+    let location = input::location(&state, location, site_id)?;
+    let date = input::date("date", &date)?;
+    if let Some(options) = &monte_carlo {
+        let errors = options.validate();
+        if !errors.is_empty() {
+            return Err(AppError::InvalidInput(errors.join("; ")));
+        }
+    }
 
-    let download_time = sequence.estimated_download_time;
+    let profile = settings_service::get_active_equipment_profile(&state);
 
     // Calculate imaging time
-    let imaging_time: f64 = sequence
-        .targets
-        .iter()
-        .map(|t| t.runtime(download_time))
-        .sum();
+    let imaging_time: f64 = match profile {
+        Some(ref profile) => {
+            calculator::calculate_sequence_runtime_with_profile(&sequence, profile)
+        }
+        None => sequence.total_runtime(),
+    };
 
     // Estimate slew time
     let slew_time = if include_slew_time && sequence.targets.len() > 1 {
-        let slew_speed = 3.0; // degrees per second
-        let settle_time = 5.0;
+        let mount = profile
+            .as_ref()
+            .map(|p| p.mount.clone())
+            .unwrap_or_default();
 
         let mut total_slew = 0.0;
         for i in 1..sequence.targets.len() {
-            let dist = crate::models::coordinates::angular_separation(
+            total_slew += mount.slew_time_between(
                 &sequence.targets[i - 1].coordinates,
                 &sequence.targets[i].coordinates,
             );
-            total_slew += dist / slew_speed + settle_time;
         }
         total_slew
     } else {
         0.0
     };
 
+    // Frames, exposure and download times for the Monte Carlo spread
+    let exposures = sequence.targets.iter().flat_map(|t| &t.exposures);
+    let frames: u64 = exposures
+        .clone()
+        .filter(|e| e.enabled)
+        .map(|e| e.remaining().max(0) as u64)
+        .sum();
+    let exposure_time: f64 = exposures.clone().map(|e| e.runtime(0.0)).sum();
+    let download_time: f64 = exposures
+        .clone()
+        .map(|e| {
+            let download = profile
+                .as_ref()
+                .map_or(sequence.estimated_download_time, |p| {
+                    p.download_time(&e.binning)
+                });
+            e.runtime(download) - e.runtime(0.0)
+        })
+        .sum();
+    let dithers: u64 = exposures.map(|e| e.dither_count().max(0) as u64).sum();
+
+    let overheads = profile.map(|p| p.overheads).unwrap_or_default();
+
     // Estimate autofocus time
     let autofocus_time: f64 = sequence
         .targets
         .iter()
-        .filter(|t| t.auto_focus_on_start)
+        .map(|t| calculator::autofocus_overhead(t, &overheads))
+        .sum();
+
+    // Estimate dither settling and guider start after each slew
+    let dither_time: f64 = sequence
+        .targets
+        .iter()
+        .flat_map(|t| &t.exposures)
+        .map(|e| calculator::dither_overhead(e, &overheads))
+        .sum();
+    let guiding_time: f64 = sequence
+        .targets
+        .iter()
+        .filter(|t| t.start_guiding && t.exposures.iter().any(|e| e.runtime(0.0) > 0.0))
         .count() as f64
-        * 120.0; // 2 minutes per autofocus
+        * overheads.guiding_start_delay;
 
     // Estimate centering time
     let centering_time: f64 =
         sequence.targets.iter().filter(|t| t.center_target).count() as f64 * 60.0; // 1 minute per center
 
-    let total_time = imaging_time + slew_time + autofocus_time + centering_time;
+    let total_time =
+        imaging_time + slew_time + autofocus_time + centering_time + dither_time + guiding_time;
 
-    // Get twilight info
-    let twilight = crate::services::astronomy::calculate_twilight(&location, date);
+    // Darkness of the local night starting on `date`
+    let twilight = dark_calendar::night_twilight(&location, date);
     let available_time = match (twilight.astronomical_dusk, twilight.astronomical_dawn) {
-        (Some(dusk), Some(dawn)) => {
-            let dawn_next = dawn + chrono::Duration::days(1);
-            (dawn_next - dusk).num_seconds() as f64
-        }
+        (Some(dusk), Some(dawn)) => (dawn - dusk).num_seconds() as f64,
         _ => 0.0,
     };
 
+    let runtime_distribution = monte_carlo.map(|options| {
+        let session = SessionVariables {
+            total_time,
+            frames,
+            download_time,
+            dithers,
+            dither_time,
+            exposure_time,
+            autofocus_duration: overheads.autofocus_duration,
+        };
+        runtime_uncertainty::estimate_runtime_distribution(
+            &session,
+            &options,
+            twilight.astronomical_dusk,
+        )
+    });
+
     Ok(SessionTimeEstimate {
         imaging_time_seconds: imaging_time,
         slew_time_seconds: slew_time,
         autofocus_time_seconds: autofocus_time,
         centering_time_seconds: centering_time,
+        dither_time_seconds: dither_time,
+        guiding_time_seconds: guiding_time,
         total_time_seconds: total_time,
         available_dark_time_seconds: available_time,
         fits_in_night: total_time <= available_time,
@@ -364,6 +559,7 @@ pub async fn estimate_session_time(
         } else {
             0.0
         },
+        runtime_distribution,
     })
 }
 
@@ -375,8 +571,126 @@ pub struct SessionTimeEstimate {
     pub slew_time_seconds: f64,
     pub autofocus_time_seconds: f64,
     pub centering_time_seconds: f64,
+    pub dither_time_seconds: f64,
+    pub guiding_time_seconds: f64,
     pub total_time_seconds: f64,
     pub available_dark_time_seconds: f64,
     pub fits_in_night: bool,
     pub utilization_percentage: f64,
+    /// Spread of the total, with finish times from astronomical dusk
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub runtime_distribution: Option<RuntimeDistribution>,
+}
+
+/// Simulate running the sequence on a night, with slews, autofocus,
+/// meridian flips and dithering, using the active equipment profile
+#[command]
+pub async fn simulate_sequence(
+    state: State<'_, SharedState>,
+    mut sequence: SimpleSequence,
+    location: Option<ObserverLocation>,
+    site_id: Option<String>,
+    date: String,
+    options: Option<SimulationOptions>,
+) -> Result<SimulationResult, AppError> {
+    let location = input::location(&state, location, site_id)?;
+    let date = input::date("date", &date)?;
+    update_moving_targets_for_night(&mut sequence, &location, date);
+
+    let profile = settings_service::get_active_equipment_profile(&state);
+    Ok(simulator::simulate_sequence(
+        &sequence,
+        &location,
+        date,
+        profile.as_ref(),
+        &options.unwrap_or_default(),
+    ))
+}
+
+/// Get Gantt-style timeline segments for the sequence on a night
+#[command]
+pub async fn get_sequence_timeline(
+    state: State<'_, SharedState>,
+    mut sequence: SimpleSequence,
+    location: Option<ObserverLocation>,
+    site_id: Option<String>,
+    date: String,
+    options: Option<SimulationOptions>,
+) -> Result<SequenceTimeline, AppError> {
+    let location = input::location(&state, location, site_id)?;
+    let date = input::date("date", &date)?;
+    update_moving_targets_for_night(&mut sequence, &location, date);
+
+    let profile = settings_service::get_active_equipment_profile(&state);
+    Ok(build_sequence_timeline(
+        &sequence,
+        &location,
+        date,
+        profile.as_ref(),
+        &options.unwrap_or_default(),
+    ))
+}
+
+/// Propose exposure counts that make the sequence fill the dark time of a
+/// night. Counts are only returned; apply them with `apply_rebalanced_counts`.
+#[command]
+pub async fn rebalance_exposures(
+    state: State<'_, SharedState>,
+    mut sequence: SimpleSequence,
+    location: Option<ObserverLocation>,
+    site_id: Option<String>,
+    date: String,
+    mode: String,
+    weights: Option<HashMap<String, f64>>,
+) -> Result<RebalanceResult, AppError> {
+    let location = input::location(&state, location, site_id)?;
+    let date = input::date("date", &date)?;
+    update_moving_targets_for_night(&mut sequence, &location, date);
+
+    let mode = match mode.to_lowercase().as_str() {
+        "weighted" | "priority" => RebalanceMode::Weighted,
+        _ => RebalanceMode::Proportional,
+    };
+
+    Ok(rebalance_exposure_counts(
+        &sequence,
+        &location,
+        date,
+        mode,
+        &weights.unwrap_or_default(),
+    ))
+}
+
+/// Apply confirmed exposure count proposals to the sequence
+#[command]
+pub async fn apply_rebalanced_counts(
+    mut sequence: SimpleSequence,
+    proposals: Vec<ExposureCountProposal>,
+) -> Result<SimpleSequence, AppError> {
+    apply_exposure_counts(&mut sequence, &proposals);
+    Ok(sequence)
+}
+
+/// Partition the sequence's remaining frames over a range of nights
+#[command]
+pub async fn split_sequence_across_nights(
+    state: State<'_, SharedState>,
+    mut sequence: SimpleSequence,
+    location: Option<ObserverLocation>,
+    site_id: Option<String>,
+    start_date: String,
+    end_date: String,
+) -> Result<NightSplitResult, AppError> {
+    let location = input::location(&state, location, site_id)?;
+    let (start, end) = input::date_range(&start_date, &end_date)?;
+    update_moving_targets_for_night(&mut sequence, &location, start);
+
+    night_split::split_sequence_across_nights(
+        &sequence,
+        &location,
+        start,
+        end,
+        &JobHandle::detached(),
+    )
+    .map_err(AppError::from)
 }
@@ -0,0 +1,74 @@
+//! Remote API commands
+
+use tauri::{command, State};
+
+use crate::error::AppError;
+use crate::models::{RemoteApiSettings, SimpleSequence};
+use crate::services::remote_api::{self, RemoteApiStatus};
+use crate::services::settings_service;
+use crate::state::SharedState;
+
+/// Get the remote API settings, including the access token
+#[command]
+pub async fn get_remote_api_settings(
+    state: State<'_, SharedState>,
+) -> Result<RemoteApiSettings, AppError> {
+    settings_service::get_remote_api_settings(&state).await
+}
+
+/// Save the remote API settings and start or stop the server to match
+#[command]
+pub async fn set_remote_api_settings(
+    state: State<'_, SharedState>,
+    settings: RemoteApiSettings,
+) -> Result<RemoteApiStatus, AppError> {
+    let settings = settings_service::set_remote_api_settings(&state, settings).await?;
+    if settings.enabled {
+        remote_api::start_server(&state, &settings)
+            .await
+            .map_err(AppError::from)
+    } else {
+        remote_api::stop_server(&state);
+        Ok(remote_api::server_status(&state))
+    }
+}
+
+/// Replace the access token, restarting a running server with it
+#[command]
+pub async fn regenerate_remote_api_token(
+    state: State<'_, SharedState>,
+) -> Result<RemoteApiSettings, AppError> {
+    let settings = settings_service::regenerate_remote_api_token(&state).await?;
+    if remote_api::server_status(&state).running {
+        remote_api::start_server(&state, &settings).await?;
+    }
+    Ok(settings)
+}
+
+/// Get the remote API server state
+#[command]
+pub fn get_remote_api_status(state: State<'_, SharedState>) -> RemoteApiStatus {
+    remote_api::server_status(&state)
+}
+
+/// Start the remote API server with the saved settings
+#[command]
+pub async fn start_remote_api(state: State<'_, SharedState>) -> Result<RemoteApiStatus, AppError> {
+    let settings = settings_service::get_remote_api_settings(&state).await?;
+    remote_api::start_server(&state, &settings)
+        .await
+        .map_err(AppError::from)
+}
+
+/// Stop the remote API server
+#[command]
+pub fn stop_remote_api(state: State<'_, SharedState>) -> RemoteApiStatus {
+    remote_api::stop_server(&state);
+    remote_api::server_status(&state)
+}
+
+/// Publish the plan open in the editor to remote clients
+#[command]
+pub fn set_remote_api_sequence(state: State<'_, SharedState>, sequence: Option<SimpleSequence>) {
+    remote_api::set_current_sequence(&state, sequence);
+}
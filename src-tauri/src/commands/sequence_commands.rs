@@ -1,20 +1,81 @@
 //! Sequence operation commands
 
-use tauri::command;
+use chrono::Utc;
+use tauri::{command, State};
 
+use crate::error::AppError;
 use crate::models::*;
-use crate::services::{serializer, validator};
+use crate::services::editor_edit::{self, EditorItemDuplicate, ItemStatusResult};
+use crate::services::filter_offsets::{self, FilterOffsetResult};
+use crate::services::sequence_edit::{
+    self, BulkEditResult, DuplicateTargetGroup, ExposureChangeSet, ExposureRamp, ExposureSelector,
+    ExposureToggleResult, MergeStrategy, MergeTargetsResult,
+};
+use crate::services::sequence_normalize::{self, NormalizationResult};
+use crate::services::sequence_progress::{self, SequenceProgressSummary};
+use crate::services::sequence_search::{self, SearchHit};
+use crate::services::sequence_statistics::{
+    self, ExposureLengthCount, FilterIntegration, ImageTypeIntegration, StorageEstimate,
+};
+use crate::services::stable_id::{self, IdFormat};
+use crate::services::validator::ValidationRuleInfo;
+use crate::services::{
+    image_library, nina_type_registry, path_guard, serializer, settings_service, validator,
+};
+use crate::state::SharedState;
+
+use super::input;
 
 /// Validate simple sequence
 #[command]
-pub fn validate_simple_sequence(sequence: SimpleSequence) -> ValidationResult {
-    validator::validate_simple_sequence(&sequence)
+pub fn validate_simple_sequence(
+    state: State<'_, SharedState>,
+    sequence: SimpleSequence,
+) -> ValidationResult {
+    validator::validate_simple_sequence_with_rules(
+        &sequence,
+        &settings_service::list_filters(&state),
+        settings_service::get_active_equipment_profile(&state).as_ref(),
+        &settings_service::get_validation_rule_configs(&state),
+    )
 }
 
 /// Validate editor sequence
 #[command]
-pub fn validate_editor_sequence(sequence: EditorSequence) -> ValidationResult {
-    validator::validate_editor_sequence(&sequence)
+pub fn validate_editor_sequence(
+    state: State<'_, SharedState>,
+    sequence: EditorSequence,
+) -> ValidationResult {
+    validator::validate_editor_sequence_with_rules(
+        &sequence,
+        &settings_service::get_validation_rule_configs(&state),
+        &nina_type_registry::plugin_types(&state),
+    )
+}
+
+/// List validation rules with the user's overrides applied
+#[command]
+pub fn list_validation_rules(state: State<'_, SharedState>) -> Vec<ValidationRuleInfo> {
+    validator::list_validation_rules(&settings_service::get_validation_rule_configs(&state))
+}
+
+/// Set or clear the override for a validation rule
+#[command]
+pub async fn set_validation_rule_config(
+    state: State<'_, SharedState>,
+    rule_id: String,
+    config: Option<ValidationRuleConfig>,
+) -> Result<Vec<ValidationRuleInfo>, AppError> {
+    if let Some(ref config) = config {
+        validator::validate_rule_config(&rule_id, config)?;
+    } else if validator::find_rule(&rule_id).is_none() {
+        return Err(AppError::NotFound(format!(
+            "Unknown validation rule: {}",
+            rule_id
+        )));
+    }
+    settings_service::set_validation_rule_config(&state, &rule_id, config).await?;
+    Ok(list_validation_rules(state))
 }
 
 /// Validate NINA JSON
@@ -31,32 +92,39 @@ pub fn validate_coordinates(coordinates: Coordinates) -> ValidationResult {
 
 /// Serialize simple sequence to JSON
 #[command]
-pub fn serialize_simple_sequence(sequence: SimpleSequence) -> Result<String, String> {
-    serializer::serialize_simple_sequence_json(&sequence).map_err(|e| e.to_string())
+pub fn serialize_simple_sequence(sequence: SimpleSequence) -> Result<String, AppError> {
+    serializer::serialize_simple_sequence_json(&sequence).map_err(AppError::from)
 }
 
 /// Deserialize simple sequence from JSON
 #[command]
-pub fn deserialize_simple_sequence(json: String) -> Result<SimpleSequence, String> {
-    serializer::deserialize_simple_sequence_json(&json).map_err(|e| e.to_string())
+pub fn deserialize_simple_sequence(json: String) -> Result<SimpleSequence, AppError> {
+    serializer::deserialize_simple_sequence_json(&json).map_err(AppError::from)
 }
 
 /// Serialize editor sequence to JSON
 #[command]
-pub fn serialize_editor_sequence(sequence: EditorSequence) -> Result<String, String> {
-    serializer::serialize_editor_sequence_json(&sequence).map_err(|e| e.to_string())
+pub fn serialize_editor_sequence(sequence: EditorSequence) -> Result<String, AppError> {
+    serializer::serialize_editor_sequence_json(&sequence).map_err(AppError::from)
 }
 
 /// Deserialize editor sequence from JSON
 #[command]
-pub fn deserialize_editor_sequence(json: String) -> Result<EditorSequence, String> {
-    serializer::deserialize_editor_sequence_json(&json).map_err(|e| e.to_string())
+pub fn deserialize_editor_sequence(json: String) -> Result<EditorSequence, AppError> {
+    serializer::deserialize_editor_sequence_json(&json).map_err(AppError::from)
 }
 
 /// Create new simple sequence
 #[command]
-pub fn create_simple_sequence(title: Option<String>) -> SimpleSequence {
-    SimpleSequence::new(title.unwrap_or_else(|| "Target Set".to_string()))
+pub fn create_simple_sequence(
+    state: State<'_, SharedState>,
+    title: Option<String>,
+) -> SimpleSequence {
+    let mut sequence = SimpleSequence::new(title.unwrap_or_else(|| "Target Set".to_string()));
+    if let Some(profile) = settings_service::get_active_equipment_profile(&state) {
+        sequence.estimated_download_time = profile.default_download_time;
+    }
+    sequence
 }
 
 /// Create new editor sequence
@@ -76,10 +144,18 @@ pub fn create_target(name: Option<String>) -> SimpleTarget {
     target
 }
 
-/// Create new exposure
+/// Create new exposure (defaults from the active equipment profile if set)
 #[command]
-pub fn create_exposure() -> SimpleExposure {
-    SimpleExposure::default()
+pub fn create_exposure(state: State<'_, SharedState>) -> SimpleExposure {
+    let mut exposure = SimpleExposure::default();
+    if let Some(profile) = settings_service::get_active_equipment_profile(&state) {
+        exposure.exposure_time = profile.default_exposure_time;
+        exposure.total_count = profile.default_exposure_count;
+        exposure.gain = profile.camera.default_gain;
+        exposure.offset = profile.camera.default_offset;
+        exposure.filter = profile.filters.first().cloned();
+    }
+    exposure
 }
 
 /// Duplicate target
@@ -111,18 +187,61 @@ pub fn duplicate_exposure(exposure: SimpleExposure) -> SimpleExposure {
     new_exposure
 }
 
+/// Duplicate an editor item with fresh ids for it and everything in it,
+/// inserting the copy after the original. With `deep` unset a container
+/// is copied without its items.
+#[command]
+pub fn duplicate_editor_item(
+    mut sequence: EditorSequence,
+    item_id: String,
+    deep: bool,
+) -> Result<EditorItemDuplicate, AppError> {
+    input::id("itemId", &item_id)?;
+    let item_id = editor_edit::duplicate_editor_item(&mut sequence, &item_id, deep)?;
+    Ok(EditorItemDuplicate { sequence, item_id })
+}
+
+/// Move an editor item into a new parent at `index`. The parent is an area
+/// (`start`, `target`, `end`), a container or a trigger; a container cannot
+/// move into itself or anything inside it.
+#[command]
+pub fn move_editor_item(
+    mut sequence: EditorSequence,
+    item_id: String,
+    new_parent_id: String,
+    index: usize,
+) -> Result<EditorSequence, AppError> {
+    input::id("itemId", &item_id)?;
+    input::id("newParentId", &new_parent_id)?;
+    editor_edit::move_editor_item(&mut sequence, &item_id, &new_parent_id, index)?;
+    Ok(sequence)
+}
+
+/// Reorder the children of an area, container or trigger; `ordered_ids`
+/// must list each child exactly once
+#[command]
+pub fn reorder_items(
+    mut sequence: EditorSequence,
+    parent_id: String,
+    ordered_ids: Vec<String>,
+) -> Result<EditorSequence, AppError> {
+    input::id("parentId", &parent_id)?;
+    editor_edit::reorder_items(&mut sequence, &parent_id, &ordered_ids)?;
+    Ok(sequence)
+}
+
 /// Copy exposures to all targets
 #[command]
 pub fn copy_exposures_to_all_targets(
     mut sequence: SimpleSequence,
     source_target_id: String,
-) -> Result<SimpleSequence, String> {
+) -> Result<SimpleSequence, AppError> {
     let source_exposures = sequence
         .targets
         .iter()
         .find(|t| t.id == source_target_id)
         .map(|t| t.exposures.clone())
-        .ok_or_else(|| "Source target not found".to_string())?;
+        .ok_or_else(|| AppError::NotFound("Source target not found".to_string()))?;
 
     for target in &mut sequence.targets {
         if target.id != source_target_id {
@@ -143,6 +262,145 @@ pub fn copy_exposures_to_all_targets(
     Ok(sequence)
 }
 
+/// Apply a change set to selected exposures across the sequence
+#[command]
+pub fn bulk_edit_exposures(
+    sequence: SimpleSequence,
+    selector: Option<ExposureSelector>,
+    changes: ExposureChangeSet,
+    dry_run: bool,
+) -> Result<BulkEditResult, AppError> {
+    sequence_edit::bulk_edit_exposures(&sequence, &selector.unwrap_or_default(), &changes, dry_run)
+        .map_err(AppError::from)
+}
+
+/// Enable or disable the exposures matched by the selector (all of them
+/// when it is omitted)
+#[command]
+pub fn set_exposures_enabled(
+    mut sequence: SimpleSequence,
+    selector: Option<ExposureSelector>,
+    enabled: bool,
+) -> ExposureToggleResult {
+    let changed =
+        sequence_edit::set_exposures_enabled(&mut sequence, &selector.unwrap_or_default(), enabled);
+    ExposureToggleResult { sequence, changed }
+}
+
+/// Append a ramp of exposures to a target, each a copy of `template` (a
+/// default exposure when omitted) with the ramped exposure time or gain
+#[command]
+pub fn generate_exposure_ramp(
+    mut sequence: SimpleSequence,
+    target_id: String,
+    ramp: ExposureRamp,
+    template: Option<SimpleExposure>,
+) -> Result<SimpleSequence, AppError> {
+    input::id("targetId", &target_id)?;
+    sequence_edit::append_exposure_ramp(
+        &mut sequence,
+        &target_id,
+        &ramp,
+        &template.unwrap_or_default(),
+    )?;
+    Ok(sequence)
+}
+
+/// Set the status of several editor items at once, e.g. `DISABLED` to
+/// skip them
+#[command]
+pub fn set_items_status(
+    mut sequence: EditorSequence,
+    item_ids: Vec<String>,
+    status: SequenceEntityStatus,
+) -> Result<ItemStatusResult, AppError> {
+    for id in &item_ids {
+        input::id("itemIds", id)?;
+    }
+    let changed = editor_edit::set_items_status(&mut sequence, &item_ids, status)?;
+    Ok(ItemStatusResult { sequence, changed })
+}
+
+/// Write per-filter focus offsets (from the active filter set unless
+/// given) into the sequence's Switch Filter items, warning about filters
+/// without an offset that are switched to without a refocus
+#[command]
+pub fn apply_filter_offsets_to_sequence(
+    state: State<'_, SharedState>,
+    sequence: EditorSequence,
+    filters: Option<Vec<FilterInfo>>,
+) -> FilterOffsetResult {
+    let filters = filters.unwrap_or_else(|| settings_service::list_filters(&state));
+    filter_offsets::apply_filter_offsets(sequence, &filters)
+}
+
+/// Fix common defects of imported sequences in one pass, reporting every
+/// fix applied
+#[command]
+pub fn normalize_sequence(sequence: SimpleSequence) -> NormalizationResult {
+    sequence_normalize::normalize_sequence(sequence)
+}
+
+/// Find targets carrying any (or, with `match_all`, every) of the tags
+#[command]
+pub fn search_targets_by_tag(
+    sequence: SimpleSequence,
+    tags: Vec<String>,
+    match_all: Option<bool>,
+) -> Vec<SimpleTarget> {
+    sequence_edit::search_targets_by_tag(&sequence, &tags, match_all.unwrap_or(false))
+        .into_iter()
+        .cloned()
+        .collect()
+}
+
+/// Find groups of targets within `tolerance_arcmin` of each other
+#[command]
+pub fn find_duplicate_targets(
+    sequence: SimpleSequence,
+    tolerance_arcmin: f64,
+) -> Result<Vec<DuplicateTargetGroup>, AppError> {
+    if tolerance_arcmin.is_nan() || tolerance_arcmin < 0.0 {
+        return Err(AppError::InvalidInput(
+            "Tolerance must not be negative".to_string(),
+        ));
+    }
+    Ok(sequence_edit::find_duplicate_targets(
+        &sequence,
+        tolerance_arcmin,
+    ))
+}
+
+/// Merge targets into the first of them, combining their exposure lists
+#[command]
+pub fn merge_targets(
+    sequence: SimpleSequence,
+    ids: Vec<String>,
+    strategy: Option<MergeStrategy>,
+) -> Result<MergeTargetsResult, AppError> {
+    sequence_edit::merge_targets(&sequence, &ids, strategy.unwrap_or_default())
+}
+
+/// Search a simple sequence and/or an editor sequence for `query`
+#[command]
+pub fn search_sequence(
+    query: String,
+    sequence: Option<SimpleSequence>,
+    editor_sequence: Option<EditorSequence>,
+) -> Vec<SearchHit> {
+    let mut hits = Vec::new();
+    if let Some(ref sequence) = sequence {
+        hits.extend(sequence_search::search_simple_sequence(sequence, &query));
+    }
+    if let Some(ref editor_sequence) = editor_sequence {
+        hits.extend(sequence_search::search_editor_sequence(
+            editor_sequence,
+            &query,
+        ));
+    }
+    hits
+}
+
 /// Reset target progress
 #[command]
 pub fn reset_target_progress(mut target: SimpleTarget) -> SimpleTarget {
@@ -168,9 +426,14 @@ pub fn reset_sequence_progress(mut sequence: SimpleSequence) -> SimpleSequence {
     sequence
 }
 
-/// Get sequence statistics
+/// Get sequence statistics. Storage is estimated with the camera of the
+/// given equipment profile, or the active one.
 #[command]
-pub fn get_sequence_statistics(sequence: SimpleSequence) -> SequenceStatistics {
+pub fn get_sequence_statistics(
+    state: State<'_, SharedState>,
+    sequence: SimpleSequence,
+    equipment_profile_id: Option<String>,
+) -> SequenceStatistics {
     let total_targets = sequence.targets.len();
     let total_exposures: i32 = sequence
         .targets
@@ -210,6 +473,13 @@ pub fn get_sequence_statistics(sequence: SimpleSequence) -> SequenceStatistics {
         0.0
     };
 
+    let profile = match equipment_profile_id {
+        Some(id) => settings_service::get_equipment_profile(&state, &id),
+        None => settings_service::get_active_equipment_profile(&state),
+    };
+    let storage = profile.and_then(|p| sequence_statistics::estimate_storage(&sequence, &p.camera));
+    let breakdown = sequence_statistics::integration_breakdown(&sequence);
+
     SequenceStatistics {
         total_targets,
         total_exposures,
@@ -219,9 +489,30 @@ pub fn get_sequence_statistics(sequence: SimpleSequence) -> SequenceStatistics {
         completed_runtime,
         remaining_runtime,
         progress_percentage,
+        filters: breakdown.filters,
+        image_types: breakdown.image_types,
+        exposure_lengths: breakdown.exposure_lengths,
+        dither_events: breakdown.dither_events,
+        storage,
     }
 }
 
+/// Disk space the sequence's frames need with the given equipment
+/// profile, or the active one
+#[command]
+pub fn estimate_storage_requirements(
+    state: State<'_, SharedState>,
+    sequence: SimpleSequence,
+    profile: Option<EquipmentProfile>,
+) -> Result<StorageEstimate, AppError> {
+    let profile = profile
+        .or_else(|| settings_service::get_active_equipment_profile(&state))
+        .ok_or("No equipment profile is selected")?;
+    sequence_statistics::estimate_storage(&sequence, &profile.camera).ok_or_else(|| {
+        AppError::InvalidInput(format!("{} has no camera sensor size", profile.name))
+    })
+}
+
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct SequenceStatistics {
@@ -233,12 +524,63 @@ pub struct SequenceStatistics {
     pub completed_runtime: f64,
     pub remaining_runtime: f64,
     pub progress_percentage: f64,
+    /// Light frame totals per filter
+    pub filters: Vec<FilterIntegration>,
+    pub image_types: Vec<ImageTypeIntegration>,
+    pub exposure_lengths: Vec<ExposureLengthCount>,
+    /// Dithers still to be done
+    pub dither_events: i32,
+    /// `None` without an equipment profile with a known sensor size
+    pub storage: Option<StorageEstimate>,
+}
+
+/// Acquired vs planned counts per target and filter with a projected finish
+/// date. Frames in `library_directory` count as acquired when it is given.
+#[command]
+pub async fn get_sequence_progress_summary(
+    state: State<'_, SharedState>,
+    sequence: SimpleSequence,
+    nightly_hours: Option<f64>,
+    start_date: Option<String>,
+    library_directory: Option<String>,
+    tolerance_arcmin: Option<f64>,
+) -> Result<SequenceProgressSummary, AppError> {
+    let start_date = match start_date {
+        Some(date) => input::date("startDate", &date)?,
+        None => Utc::now().date_naive(),
+    };
+    let analysis = match library_directory {
+        Some(directory) => {
+            let directory = path_guard::check_path(&state, &directory)?;
+            let analysis = tokio::task::spawn_blocking(move || {
+                image_library::analyze_image_library(&directory)
+            })
+            .await
+            .map_err(|e| format!("Image library scan failed: {}", e))??;
+            Some(analysis)
+        }
+        None => None,
+    };
+    let profile = settings_service::get_active_equipment_profile(&state);
+
+    Ok(sequence_progress::summarize_progress(
+        &sequence,
+        profile.as_ref(),
+        analysis.as_ref().map(|a| {
+            (
+                a,
+                tolerance_arcmin.unwrap_or(image_library::DEFAULT_MATCH_TOLERANCE_ARCMIN),
+            )
+        }),
+        nightly_hours.unwrap_or(sequence_progress::DEFAULT_NIGHTLY_HOURS),
+        start_date,
+    ))
 }
 
 /// Check if type is a container
 #[command]
-pub fn is_container_type(type_str: String) -> bool {
-    validator::is_container_type(&type_str)
+pub fn is_container_type(state: State<'_, SharedState>, type_str: String) -> bool {
+    validator::is_container_type(&type_str, &nina_type_registry::plugin_types(&state))
 }
 
 /// Get short type name
@@ -253,8 +595,9 @@ pub fn get_type_category(full_type: String) -> String {
     validator::get_type_category(&full_type)
 }
 
-/// Generate new UUID
+/// Generate an id in `format`, hyphenated by default. With `seed` the id
+/// is derived from it and the same seed always gives the same id.
 #[command]
-pub fn generate_id() -> String {
-    uuid::Uuid::new_v4().to_string()
+pub fn generate_id(format: Option<IdFormat>, seed: Option<String>) -> String {
+    stable_id::generate_id(format.unwrap_or_default(), seed.as_deref())
 }
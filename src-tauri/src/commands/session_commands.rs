@@ -0,0 +1,177 @@
+//! Open sequence session commands
+//!
+//! Sequences held by the backend and edited by session id, and the windows
+//! showing them
+
+use json_patch::Patch;
+use serde_json::Value;
+use tauri::{command, AppHandle, State, WebviewUrl, WebviewWindow, WebviewWindowBuilder};
+
+use crate::error::AppError;
+use crate::models::SimpleSequence;
+use crate::services::path_guard;
+use crate::services::sequence_session::{SessionInfo, WindowBinding};
+use crate::state::{AppState, SharedState};
+
+use super::file_commands::{open_simple_sequence, write_simple_sequence};
+use super::input;
+
+/// Open a simple sequence file in a backend session and return the
+/// session id. A file that is already open returns its existing session.
+#[command]
+pub async fn open_sequence(
+    state: State<'_, SharedState>,
+    path: String,
+) -> Result<String, AppError> {
+    let path = path_guard::check_path(&state, &path)?;
+    let sequence = open_simple_sequence(&state, &path).await?;
+    Ok(state.sessions.open(sequence, Some(path)).id)
+}
+
+/// Summaries of the open sequences, oldest first
+#[command]
+pub fn get_open_sequences(state: State<'_, SharedState>) -> Vec<SessionInfo> {
+    state.sessions.list()
+}
+
+/// Current sequence of a session
+#[command]
+pub fn get_open_sequence(
+    state: State<'_, SharedState>,
+    session_id: String,
+) -> Result<SimpleSequence, AppError> {
+    input::id("sessionId", &session_id)?;
+    state.sessions.sequence(&session_id)
+}
+
+/// Apply a JSON merge patch to a session's sequence
+#[command]
+pub fn update_sequence(
+    state: State<'_, SharedState>,
+    session_id: String,
+    patch: Value,
+) -> Result<SessionInfo, AppError> {
+    input::id("sessionId", &session_id)?;
+    state.sessions.update(&session_id, &patch)
+}
+
+/// Apply a JSON patch (RFC 6902) to a session's sequence. All operations
+/// apply or none do. With `base_revision`, the patch is refused when the
+/// sequence changed since that revision.
+#[command]
+pub fn apply_sequence_patch(
+    state: State<'_, SharedState>,
+    session_id: String,
+    patch: Patch,
+    base_revision: Option<u64>,
+) -> Result<SessionInfo, AppError> {
+    input::id("sessionId", &session_id)?;
+    state
+        .sessions
+        .apply_patch(&session_id, &patch, base_revision)
+}
+
+/// Save a session's sequence to its file
+async fn save_session(
+    state: &AppState,
+    session_id: &str,
+    path: Option<String>,
+) -> Result<SessionInfo, AppError> {
+    let session = state.sessions.session(session_id)?;
+    let path = match path {
+        Some(path) => path_guard::check_path(state, &path)?,
+        None => session.path.ok_or_else(|| {
+            AppError::InvalidInput("The sequence has no file yet; give a path to save it to".into())
+        })?,
+    };
+    write_simple_sequence(state, &path, &session.sequence).await?;
+    state
+        .sessions
+        .mark_saved(session_id, &path, session.revision)
+}
+
+/// Save a session's sequence, to `path` when given or else to the file it
+/// was opened from
+#[command]
+pub async fn save_open_sequence(
+    state: State<'_, SharedState>,
+    session_id: String,
+    path: Option<String>,
+) -> Result<SessionInfo, AppError> {
+    input::id("sessionId", &session_id)?;
+    save_session(&state, &session_id, path).await
+}
+
+/// Close a session, saving it first when `save` is set. A failed save
+/// leaves the session open.
+#[command]
+pub async fn close_sequence(
+    state: State<'_, SharedState>,
+    session_id: String,
+    save: bool,
+) -> Result<(), AppError> {
+    input::id("sessionId", &session_id)?;
+    if save {
+        save_session(&state, &session_id, None).await?;
+    }
+    state.sessions.close(&session_id)?;
+    Ok(())
+}
+
+/// Open a sequence in a new window bound to its session, from `path` or an
+/// already open `session_id`. The window shares the clipboard and session
+/// store with the others.
+#[command]
+pub async fn open_sequence_in_new_window(
+    app: AppHandle,
+    state: State<'_, SharedState>,
+    path: Option<String>,
+    session_id: Option<String>,
+) -> Result<WindowBinding, AppError> {
+    let session = match (path, session_id) {
+        (_, Some(session_id)) => {
+            input::id("sessionId", &session_id)?;
+            state.sessions.info(&session_id)?
+        }
+        (Some(path), None) => {
+            let path = path_guard::check_path(&state, &path)?;
+            let sequence = open_simple_sequence(&state, &path).await?;
+            state.sessions.open(sequence, Some(path))
+        }
+        (None, None) => {
+            return Err(AppError::InvalidInput(
+                "Give a path or a session id to open".into(),
+            ))
+        }
+    };
+
+    let label = format!("sequence-{}", uuid::Uuid::new_v4().simple());
+    let url = WebviewUrl::App(format!("simple?session={}", session.id).into());
+    WebviewWindowBuilder::new(&app, &label, url)
+        .title(format!("{} - Cobalt Task Editor", session.title))
+        .inner_size(1280.0, 800.0)
+        .min_inner_size(800.0, 600.0)
+        .build()
+        .map_err(|e| AppError::Internal(format!("Failed to open window: {}", e)))?;
+    state.sessions.bind_window(&label, &session.id)
+}
+
+/// Bind the calling window to a session
+#[command]
+pub fn bind_window_session(
+    window: WebviewWindow,
+    state: State<'_, SharedState>,
+    session_id: String,
+) -> Result<WindowBinding, AppError> {
+    input::id("sessionId", &session_id)?;
+    state.sessions.bind_window(window.label(), &session_id)
+}
+
+/// Session the calling window is bound to
+#[command]
+pub fn get_window_session(
+    window: WebviewWindow,
+    state: State<'_, SharedState>,
+) -> Option<SessionInfo> {
+    state.sessions.window_session(window.label())
+}
@@ -1,80 +1,131 @@
 //! Settings commands
 
-use tauri::command;
+use tauri::{command, State};
 
-use crate::models::AppSettings;
-use crate::services::settings_service;
+use crate::error::AppError;
+use crate::models::{
+    AppSettings, EquipmentProfile, FilterInfo, FilterSet, MountProfile, ObservingSite,
+    UnitPreferences,
+};
+use crate::services::alpaca::{self, AlpacaDevice, AlpacaDiscoveryResult};
+use crate::services::path_guard;
+use crate::services::phd2::{self, Phd2SettleTimes};
+use crate::services::settings_service::{self, RecentFileDetails, SettingsMigrationReport};
+use crate::services::slew_calibration::{self, SlewCalibration, SlewRecord};
+use crate::state::SharedState;
+
+use super::input;
 
 /// Load settings
 #[command]
-pub async fn load_settings() -> Result<AppSettings, String> {
-    settings_service::load_settings().await
+pub async fn load_settings(state: State<'_, SharedState>) -> Result<AppSettings, AppError> {
+    settings_service::load_settings(&state).await
 }
 
-/// Save settings
+/// Save settings. The library folder and allowed directories are not
+/// taken from `settings`; use their own commands to change them.
 #[command]
-pub async fn save_settings(settings: AppSettings) -> Result<(), String> {
-    settings_service::save_settings(&settings).await
+pub async fn save_settings(
+    state: State<'_, SharedState>,
+    settings: AppSettings,
+) -> Result<(), AppError> {
+    settings_service::save_frontend_settings(&state, settings)
+        .await
+        .map(|_| ())
 }
 
 /// Get current settings
 #[command]
-pub fn get_settings() -> AppSettings {
-    settings_service::get_settings()
+pub fn get_settings(state: State<'_, SharedState>) -> AppSettings {
+    settings_service::get_settings(&state)
 }
 
 /// Get recent files
 #[command]
-pub fn get_recent_files() -> Vec<String> {
-    settings_service::get_recent_files()
+pub fn get_recent_files(state: State<'_, SharedState>) -> Vec<String> {
+    settings_service::get_recent_files(&state)
 }
 
 /// Add recent file
 #[command]
-pub async fn add_recent_file(path: String) -> Result<(), String> {
-    settings_service::add_recent_file(&path).await
+pub async fn add_recent_file(state: State<'_, SharedState>, path: String) -> Result<(), AppError> {
+    let path = path_guard::check_path(&state, &path)?;
+    settings_service::add_recent_file(&state, &path.display().to_string()).await
 }
 
 /// Remove recent file
 #[command]
-pub async fn remove_recent_file(path: String) -> Result<(), String> {
-    settings_service::remove_recent_file(&path).await
+pub async fn remove_recent_file(
+    state: State<'_, SharedState>,
+    path: String,
+) -> Result<(), AppError> {
+    settings_service::remove_recent_file(&state, &path).await
 }
 
 /// Clear recent files
 #[command]
-pub async fn clear_recent_files() -> Result<(), String> {
-    settings_service::clear_recent_files().await
+pub async fn clear_recent_files(state: State<'_, SharedState>) -> Result<(), AppError> {
+    settings_service::clear_recent_files(&state).await
+}
+
+/// Recent files with their metadata, pinned ones first. Each is checked
+/// for existence and its title read from the file.
+#[command]
+pub async fn get_recent_file_details(
+    state: State<'_, SharedState>,
+) -> Result<Vec<RecentFileDetails>, AppError> {
+    Ok(settings_service::get_recent_file_details(&state).await)
+}
+
+/// Pin or unpin a recent file; pinned files never drop off the list
+#[command]
+pub async fn set_recent_file_pinned(
+    state: State<'_, SharedState>,
+    path: String,
+    pinned: bool,
+) -> Result<(), AppError> {
+    // Pinning adds the file to the list; unpinning a stale entry must work
+    let path = if pinned {
+        path_guard::check_path(&state, &path)?.display().to_string()
+    } else {
+        path
+    };
+    settings_service::set_recent_file_pinned(&state, &path, pinned).await
 }
 
 /// Get last directory
 #[command]
-pub fn get_last_directory() -> Option<String> {
-    settings_service::get_last_directory()
+pub fn get_last_directory(state: State<'_, SharedState>) -> Option<String> {
+    settings_service::get_last_directory(&state)
 }
 
 /// Set last directory
 #[command]
-pub async fn set_last_directory(path: String) -> Result<(), String> {
-    settings_service::set_last_directory(&path).await
+pub async fn set_last_directory(
+    state: State<'_, SharedState>,
+    path: String,
+) -> Result<(), AppError> {
+    let path = path_guard::check_path(&state, &path)?;
+    settings_service::set_last_directory(&state, &path.display().to_string()).await
 }
 
 /// Save window state
 #[command]
 pub async fn save_window_state(
+    state: State<'_, SharedState>,
     width: u32,
     height: u32,
     x: Option<i32>,
     y: Option<i32>,
     maximized: bool,
-) -> Result<(), String> {
-    settings_service::save_window_state(width, height, x, y, maximized).await
+) -> Result<(), AppError> {
+    settings_service::save_window_state(&state, width, height, x, y, maximized).await
 }
 
 /// Get window state
 #[command]
-pub fn get_window_state() -> WindowState {
-    let (width, height, x, y, maximized) = settings_service::get_window_state();
+pub fn get_window_state(state: State<'_, SharedState>) -> WindowState {
+    let (width, height, x, y, maximized) = settings_service::get_window_state(&state);
     WindowState {
         width,
         height,
@@ -94,38 +145,322 @@ pub struct WindowState {
     pub maximized: bool,
 }
 
+/// Get the migration report of the last settings load, if the file
+/// needed migrating or came from a newer version
+#[command]
+pub fn get_settings_migration_report(
+    state: State<'_, SharedState>,
+) -> Option<SettingsMigrationReport> {
+    settings_service::get_settings_migration_report(&state)
+}
+
+/// Export portable settings to a profile file
+#[command]
+pub async fn export_settings_profile(
+    state: State<'_, SharedState>,
+    path: String,
+) -> Result<(), AppError> {
+    settings_service::export_settings_profile(&state, &path_guard::check_path(&state, &path)?).await
+}
+
+/// Import a settings profile, keeping machine-specific settings
+#[command]
+pub async fn import_settings_profile(
+    state: State<'_, SharedState>,
+    path: String,
+) -> Result<AppSettings, AppError> {
+    settings_service::import_settings_profile(&state, &path_guard::check_path(&state, &path)?).await
+}
+
 /// Set theme
 #[command]
-pub async fn set_theme(theme: String) -> Result<(), String> {
-    settings_service::set_theme(&theme).await
+pub async fn set_theme(state: State<'_, SharedState>, theme: String) -> Result<(), AppError> {
+    settings_service::set_theme(&state, &theme).await
 }
 
 /// Get theme
 #[command]
-pub fn get_theme() -> String {
-    settings_service::get_theme()
+pub fn get_theme(state: State<'_, SharedState>) -> String {
+    settings_service::get_theme(&state)
 }
 
 /// Set language
 #[command]
-pub async fn set_language(language: String) -> Result<(), String> {
-    settings_service::set_language(&language).await
+pub async fn set_language(state: State<'_, SharedState>, language: String) -> Result<(), AppError> {
+    settings_service::set_language(&state, &language).await
 }
 
 /// Get language
 #[command]
-pub fn get_language() -> String {
-    settings_service::get_language()
+pub fn get_language(state: State<'_, SharedState>) -> String {
+    settings_service::get_language(&state)
+}
+
+/// Set unit and locale preferences
+#[command]
+pub async fn set_unit_preferences(
+    state: State<'_, SharedState>,
+    preferences: UnitPreferences,
+) -> Result<(), AppError> {
+    settings_service::set_unit_preferences(&state, preferences).await
+}
+
+/// Get unit and locale preferences
+#[command]
+pub fn get_unit_preferences(state: State<'_, SharedState>) -> UnitPreferences {
+    settings_service::get_unit_preferences(&state)
+}
+
+/// Get the default unit preferences of a locale (e.g. "de-DE")
+#[command]
+pub fn get_locale_unit_preferences(locale: String) -> UnitPreferences {
+    UnitPreferences::for_locale(&locale)
 }
 
 /// Set estimated download time
 #[command]
-pub async fn set_estimated_download_time(seconds: f64) -> Result<(), String> {
-    settings_service::set_estimated_download_time(seconds).await
+pub async fn set_estimated_download_time(
+    state: State<'_, SharedState>,
+    seconds: f64,
+) -> Result<(), AppError> {
+    settings_service::set_estimated_download_time(&state, seconds).await
 }
 
 /// Get estimated download time
 #[command]
-pub fn get_estimated_download_time() -> f64 {
-    settings_service::get_estimated_download_time()
+pub fn get_estimated_download_time(state: State<'_, SharedState>) -> f64 {
+    settings_service::get_estimated_download_time(&state)
+}
+
+/// List equipment profiles
+#[command]
+pub fn list_equipment_profiles(state: State<'_, SharedState>) -> Vec<EquipmentProfile> {
+    settings_service::list_equipment_profiles(&state)
+}
+
+/// Get equipment profile by id
+#[command]
+pub fn get_equipment_profile(
+    state: State<'_, SharedState>,
+    id: String,
+) -> Option<EquipmentProfile> {
+    settings_service::get_equipment_profile(&state, &id)
+}
+
+/// Create a new equipment profile with default values
+#[command]
+pub fn create_equipment_profile(name: Option<String>) -> EquipmentProfile {
+    let mut profile = EquipmentProfile::default();
+    if let Some(n) = name {
+        profile.name = n;
+    }
+    profile
+}
+
+/// Save (add or update) equipment profile
+#[command]
+pub async fn save_equipment_profile(
+    state: State<'_, SharedState>,
+    profile: EquipmentProfile,
+) -> Result<(), AppError> {
+    settings_service::save_equipment_profile(&state, profile).await
+}
+
+/// Delete equipment profile
+#[command]
+pub async fn delete_equipment_profile(
+    state: State<'_, SharedState>,
+    id: String,
+) -> Result<(), AppError> {
+    input::id("id", &id)?;
+    settings_service::delete_equipment_profile(&state, &id).await
+}
+
+/// Set active equipment profile
+#[command]
+pub async fn set_active_equipment_profile(
+    state: State<'_, SharedState>,
+    id: Option<String>,
+) -> Result<(), AppError> {
+    settings_service::set_active_equipment_profile(&state, id).await
+}
+
+/// Get active equipment profile
+#[command]
+pub fn get_active_equipment_profile(state: State<'_, SharedState>) -> Option<EquipmentProfile> {
+    settings_service::get_active_equipment_profile(&state)
+}
+
+/// List filters in the active filter set
+#[command]
+pub fn list_filters(state: State<'_, SharedState>) -> Vec<FilterInfo> {
+    settings_service::list_filters(&state)
+}
+
+/// List saved filter sets
+#[command]
+pub fn list_filter_sets(state: State<'_, SharedState>) -> Vec<FilterSet> {
+    settings_service::list_filter_sets(&state)
+}
+
+/// Save (add or update) filter set
+#[command]
+pub async fn save_filter_set(
+    state: State<'_, SharedState>,
+    filter_set: FilterSet,
+) -> Result<(), AppError> {
+    settings_service::save_filter_set(&state, filter_set).await
+}
+
+/// Delete filter set
+#[command]
+pub async fn delete_filter_set(state: State<'_, SharedState>, id: String) -> Result<(), AppError> {
+    input::id("id", &id)?;
+    settings_service::delete_filter_set(&state, &id).await
+}
+
+/// Set active filter set
+#[command]
+pub async fn set_active_filter_set(
+    state: State<'_, SharedState>,
+    id: Option<String>,
+) -> Result<(), AppError> {
+    settings_service::set_active_filter_set(&state, id).await
+}
+
+/// Get active filter set
+#[command]
+pub fn get_active_filter_set(state: State<'_, SharedState>) -> Option<FilterSet> {
+    settings_service::get_active_filter_set(&state)
+}
+
+/// List saved observing sites
+#[command]
+pub fn list_sites(state: State<'_, SharedState>) -> Vec<ObservingSite> {
+    settings_service::list_sites(&state)
+}
+
+/// Get observing site by id
+#[command]
+pub fn get_site(state: State<'_, SharedState>, id: String) -> Option<ObservingSite> {
+    settings_service::get_site(&state, &id)
+}
+
+/// Save (add or update) observing site
+#[command]
+pub async fn save_site(state: State<'_, SharedState>, site: ObservingSite) -> Result<(), AppError> {
+    settings_service::save_site(&state, site).await
+}
+
+/// Add a new observing site
+#[command]
+pub async fn add_site(
+    state: State<'_, SharedState>,
+    site: ObservingSite,
+) -> Result<ObservingSite, AppError> {
+    settings_service::add_site(&state, site).await
+}
+
+/// Delete observing site
+#[command]
+pub async fn delete_site(state: State<'_, SharedState>, id: String) -> Result<(), AppError> {
+    input::id("id", &id)?;
+    settings_service::delete_site(&state, &id).await
+}
+
+/// Set active observing site
+#[command]
+pub async fn set_active_site(
+    state: State<'_, SharedState>,
+    id: Option<String>,
+) -> Result<(), AppError> {
+    settings_service::set_active_site(&state, id).await
+}
+
+/// Get active observing site
+#[command]
+pub fn get_active_site(state: State<'_, SharedState>) -> Option<ObservingSite> {
+    settings_service::get_active_site(&state)
+}
+
+/// Find ASCOM Alpaca servers on the local network, plus the given
+/// `host:port` servers, and read their cameras and telescopes
+#[command]
+pub async fn discover_alpaca_devices(
+    timeout_ms: Option<u64>,
+    hosts: Option<Vec<String>>,
+) -> AlpacaDiscoveryResult {
+    let timeout = timeout_ms.unwrap_or(alpaca::DEFAULT_DISCOVERY_TIMEOUT_MS);
+    alpaca::discover_devices(
+        std::time::Duration::from_millis(timeout),
+        &hosts.unwrap_or_default(),
+    )
+    .await
+}
+
+/// Fill an equipment profile from a discovered Alpaca device, without
+/// saving it
+#[command]
+pub fn apply_alpaca_device_to_profile(
+    mut profile: EquipmentProfile,
+    device: AlpacaDevice,
+) -> EquipmentProfile {
+    alpaca::apply_device_to_profile(&mut profile, &device);
+    profile
+}
+
+/// Build an observing site from a discovered Alpaca telescope, without
+/// saving it
+#[command]
+pub fn create_site_from_alpaca_device(device: AlpacaDevice) -> Result<ObservingSite, AppError> {
+    alpaca::site_from_device(&device).map_err(AppError::from)
+}
+
+/// Estimate settle times from an exported PHD2 profile
+#[command]
+pub async fn import_phd2_profile(
+    state: State<'_, SharedState>,
+    path: String,
+) -> Result<Phd2SettleTimes, AppError> {
+    let path = path_guard::check_path(&state, &path)?;
+    phd2::import_profile(&path).await.map_err(AppError::from)
+}
+
+/// Measure settle times from PHD2's event server while the user dithers
+/// or starts guiding
+#[command]
+pub async fn measure_phd2_settle_times(
+    host: Option<String>,
+    port: Option<u16>,
+    duration_seconds: Option<u64>,
+    max_settles: Option<usize>,
+) -> Result<Phd2SettleTimes, AppError> {
+    phd2::measure_settle_times(
+        host.as_deref().unwrap_or("localhost"),
+        port.unwrap_or(phd2::DEFAULT_EVENT_PORT),
+        std::time::Duration::from_secs(duration_seconds.unwrap_or(600)),
+        max_settles.unwrap_or(10),
+    )
+    .await
+    .map_err(AppError::Network)
+}
+
+/// Use PHD2 settle times as a profile's dither and guiding overheads,
+/// without saving it
+#[command]
+pub fn apply_phd2_settle_times(
+    mut profile: EquipmentProfile,
+    times: Phd2SettleTimes,
+) -> EquipmentProfile {
+    times.apply_to(&mut profile.overheads);
+    profile
+}
+
+/// Fit a mount's slew model to logged slews, without saving it
+#[command]
+pub fn calibrate_slew_model(
+    mount: MountProfile,
+    records: Vec<SlewRecord>,
+) -> Result<SlewCalibration, AppError> {
+    slew_calibration::calibrate_slew_model(&mount, &records).map_err(AppError::from)
 }
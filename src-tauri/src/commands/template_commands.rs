@@ -1,11 +1,20 @@
 //! Template management commands
 
-use tauri::command;
+use serde_json::Value;
+use std::collections::HashMap;
+use tauri::{command, State};
 
+use crate::error::AppError;
 use crate::models::{SimpleExposure, SimpleSequence, SimpleTarget};
+use crate::services::path_guard;
+use crate::services::template_bundle::{self, ImportConflictPolicy, TemplateBundleImportResult};
 use crate::services::template_service::{
     self, ExposureSetTemplate, SimpleSequenceTemplate, TargetTemplate, TemplateMetadata,
+    TemplateVariable,
 };
+use crate::state::SharedState;
+
+use super::input;
 
 /// Save simple sequence as template
 #[command]
@@ -15,108 +24,145 @@ pub async fn save_sequence_template(
     category: String,
     tags: Vec<String>,
     sequence: SimpleSequence,
-) -> Result<TemplateMetadata, String> {
+) -> Result<TemplateMetadata, AppError> {
     template_service::save_simple_sequence_template(&name, &description, &category, tags, sequence)
         .await
 }
 
 /// Load simple sequence template
 #[command]
-pub async fn load_sequence_template(id: String) -> Result<SimpleSequenceTemplate, String> {
+pub async fn load_sequence_template(id: String) -> Result<SimpleSequenceTemplate, AppError> {
+    input::id("id", &id)?;
     template_service::load_simple_sequence_template(&id).await
 }
 
 /// List simple sequence templates
 #[command]
-pub async fn list_sequence_templates() -> Result<Vec<TemplateMetadata>, String> {
+pub async fn list_sequence_templates() -> Result<Vec<TemplateMetadata>, AppError> {
     template_service::list_simple_sequence_templates().await
 }
 
 /// Delete simple sequence template
 #[command]
-pub async fn delete_sequence_template(id: String) -> Result<(), String> {
+pub async fn delete_sequence_template(id: String) -> Result<(), AppError> {
+    input::id("id", &id)?;
     template_service::delete_simple_sequence_template(&id).await
 }
 
-/// Save target as template
+/// Save target as template. Fields may hold `{{variable}}` placeholders.
 #[command]
 pub async fn save_target_template(
     name: String,
     description: String,
     tags: Vec<String>,
-    target: SimpleTarget,
-) -> Result<TemplateMetadata, String> {
-    template_service::save_target_template(&name, &description, tags, target).await
+    target: Value,
+    variables: Option<Vec<TemplateVariable>>,
+) -> Result<TemplateMetadata, AppError> {
+    template_service::save_target_template(
+        &name,
+        &description,
+        tags,
+        target,
+        variables.unwrap_or_default(),
+    )
+    .await
 }
 
 /// Load target template
 #[command]
-pub async fn load_target_template(id: String) -> Result<TargetTemplate, String> {
+pub async fn load_target_template(id: String) -> Result<TargetTemplate, AppError> {
+    input::id("id", &id)?;
     template_service::load_target_template(&id).await
 }
 
 /// List target templates
 #[command]
-pub async fn list_target_templates() -> Result<Vec<TemplateMetadata>, String> {
+pub async fn list_target_templates() -> Result<Vec<TemplateMetadata>, AppError> {
     template_service::list_target_templates().await
 }
 
-/// Save exposure set as template
+/// Save exposure set as template. Fields may hold `{{variable}}` placeholders.
 #[command]
 pub async fn save_exposure_template(
     name: String,
     description: String,
     tags: Vec<String>,
-    exposures: Vec<SimpleExposure>,
-) -> Result<TemplateMetadata, String> {
-    template_service::save_exposure_set_template(&name, &description, tags, exposures).await
+    exposures: Vec<Value>,
+    variables: Option<Vec<TemplateVariable>>,
+) -> Result<TemplateMetadata, AppError> {
+    template_service::save_exposure_set_template(
+        &name,
+        &description,
+        tags,
+        exposures,
+        variables.unwrap_or_default(),
+    )
+    .await
 }
 
 /// Load exposure set template
 #[command]
-pub async fn load_exposure_template(id: String) -> Result<ExposureSetTemplate, String> {
+pub async fn load_exposure_template(id: String) -> Result<ExposureSetTemplate, AppError> {
+    input::id("id", &id)?;
     template_service::load_exposure_set_template(&id).await
 }
 
 /// List exposure set templates
 #[command]
-pub async fn list_exposure_templates() -> Result<Vec<TemplateMetadata>, String> {
+pub async fn list_exposure_templates() -> Result<Vec<TemplateMetadata>, AppError> {
     template_service::list_exposure_set_templates().await
 }
 
-/// Apply target template (returns new target with new ID)
+/// Save a user copy of a built-in template
 #[command]
-pub async fn apply_target_template(id: String) -> Result<SimpleTarget, String> {
-    let template = template_service::load_target_template(&id).await?;
-    let mut target = template.target;
+pub async fn clone_builtin_template(
+    id: String,
+    name: Option<String>,
+) -> Result<TemplateMetadata, AppError> {
+    template_service::clone_builtin_template(&id, name.as_deref()).await
+}
 
-    // Generate new ID
-    target.id = uuid::Uuid::new_v4().to_string();
-    target.status = crate::models::SequenceEntityStatus::Created;
+/// Export templates of any kind to a bundle file for sharing
+#[command]
+pub async fn export_template_bundle(
+    state: State<'_, SharedState>,
+    ids: Vec<String>,
+    path: String,
+) -> Result<usize, AppError> {
+    template_bundle::export_template_bundle(&ids, &path_guard::check_path(&state, &path)?).await
+}
 
-    // Reset progress for exposures
-    for exp in &mut target.exposures {
-        exp.id = uuid::Uuid::new_v4().to_string();
-        exp.progress_count = 0;
-        exp.status = crate::models::SequenceEntityStatus::Created;
-    }
+/// Import a template bundle file. Collisions are renamed unless
+/// `on_conflict` says otherwise.
+#[command]
+pub async fn import_template_bundle(
+    state: State<'_, SharedState>,
+    path: String,
+    on_conflict: Option<ImportConflictPolicy>,
+) -> Result<TemplateBundleImportResult, AppError> {
+    template_bundle::import_template_bundle(
+        &path_guard::check_path(&state, &path)?,
+        on_conflict.unwrap_or_default(),
+    )
+    .await
+}
 
-    Ok(target)
+/// Apply target template with variable values (returns new target with new ID)
+#[command]
+pub async fn apply_target_template(
+    id: String,
+    variables: Option<HashMap<String, Value>>,
+) -> Result<SimpleTarget, AppError> {
+    let template = template_service::load_target_template(&id).await?;
+    template_service::instantiate_target_template(&template, &variables.unwrap_or_default())
 }
 
-/// Apply exposure set template (returns new exposures with new IDs)
+/// Apply exposure set template with variable values (returns new exposures with new IDs)
 #[command]
-pub async fn apply_exposure_template(id: String) -> Result<Vec<SimpleExposure>, String> {
+pub async fn apply_exposure_template(
+    id: String,
+    variables: Option<HashMap<String, Value>>,
+) -> Result<Vec<SimpleExposure>, AppError> {
     let template = template_service::load_exposure_set_template(&id).await?;
-
-    Ok(template
-        .exposures
-        .into_iter()
-        .map(|mut exp| {
-            exp.id = uuid::Uuid::new_v4().to_string();
-            exp.progress_count = 0;
-            exp.status = crate::models::SequenceEntityStatus::Created;
-            exp
-        })
-        .collect())
+    template_service::instantiate_exposure_template(&template, &variables.unwrap_or_default())
 }
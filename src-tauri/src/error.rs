@@ -0,0 +1,310 @@
+//! Application errors
+//!
+//! Every command fails with an [`AppError`]. The frontend receives it as
+//! `{ code, message, context?, details? }`: a machine-readable code, a
+//! message to show the user, what was being done when it failed and, for
+//! validation failures, each problem found. Rejected command inputs also
+//! carry `fields`, one entry per offending payload field.
+
+use serde::ser::SerializeStruct;
+use serde::{Deserialize, Serialize, Serializer};
+use thiserror::Error;
+
+use crate::services::file_service::FileError;
+use crate::services::serializer::SerializerError;
+
+/// Machine-readable kind of an [`AppError`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ErrorCode {
+    NotFound,
+    InvalidInput,
+    Validation,
+    Parse,
+    Io,
+    PermissionDenied,
+    Network,
+    Cancelled,
+    Unsupported,
+    Internal,
+}
+
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum AppError {
+    #[error("{0}")]
+    NotFound(String),
+    #[error("{0}")]
+    InvalidInput(String),
+    /// Command payload fields that failed the input checks
+    #[error("Invalid input: {}", join_fields(.0))]
+    InvalidFields(Vec<FieldError>),
+    /// Every problem found while validating
+    #[error("{}", .0.join("; "))]
+    Validation(Vec<String>),
+    #[error("{0}")]
+    Parse(String),
+    #[error("{0}")]
+    Io(String),
+    #[error("{0}")]
+    PermissionDenied(String),
+    #[error("{0}")]
+    Network(String),
+    #[error("Cancelled")]
+    Cancelled,
+    #[error("{0}")]
+    Unsupported(String),
+    #[error("{0}")]
+    Internal(String),
+    /// Another error with what was being done when it happened
+    #[error("{context}: {source}")]
+    Context {
+        context: String,
+        source: Box<AppError>,
+    },
+}
+
+pub type AppResult<T> = Result<T, AppError>;
+
+/// Problem with one field of a command payload
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FieldError {
+    /// Path of the field in the payload, such as `location.latitude`
+    pub field: String,
+    pub message: String,
+}
+
+impl FieldError {
+    pub fn new(field: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            field: field.into(),
+            message: message.into(),
+        }
+    }
+}
+
+impl std::fmt::Display for FieldError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.field, self.message)
+    }
+}
+
+fn join_fields(fields: &[FieldError]) -> String {
+    fields
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+impl AppError {
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            AppError::NotFound(_) => ErrorCode::NotFound,
+            AppError::InvalidInput(_) | AppError::InvalidFields(_) => ErrorCode::InvalidInput,
+            AppError::Validation(_) => ErrorCode::Validation,
+            AppError::Parse(_) => ErrorCode::Parse,
+            AppError::Io(_) => ErrorCode::Io,
+            AppError::PermissionDenied(_) => ErrorCode::PermissionDenied,
+            AppError::Network(_) => ErrorCode::Network,
+            AppError::Cancelled => ErrorCode::Cancelled,
+            AppError::Unsupported(_) => ErrorCode::Unsupported,
+            AppError::Internal(_) => ErrorCode::Internal,
+            AppError::Context { source, .. } => source.code(),
+        }
+    }
+
+    /// Wrap the error with what was being done
+    pub fn context(self, context: impl Into<String>) -> Self {
+        AppError::Context {
+            context: context.into(),
+            source: Box::new(self),
+        }
+    }
+
+    /// Problems found by a failed validation
+    pub fn details(&self) -> Option<&[String]> {
+        match self {
+            AppError::Validation(problems) => Some(problems),
+            AppError::Context { source, .. } => source.details(),
+            _ => None,
+        }
+    }
+
+    /// Payload fields rejected by the input checks
+    pub fn fields(&self) -> Option<&[FieldError]> {
+        match self {
+            AppError::InvalidFields(fields) => Some(fields),
+            AppError::Context { source, .. } => source.fields(),
+            _ => None,
+        }
+    }
+}
+
+/// Add context to a failed result
+pub trait ResultExt<T> {
+    fn context(self, context: impl Into<String>) -> AppResult<T>;
+}
+
+impl<T, E: Into<AppError>> ResultExt<T> for Result<T, E> {
+    fn context(self, context: impl Into<String>) -> AppResult<T> {
+        self.map_err(|e| e.into().context(context))
+    }
+}
+
+impl Serialize for AppError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("AppError", 5)?;
+        state.serialize_field("code", &self.code())?;
+        state.serialize_field("message", &self.to_string())?;
+        if let AppError::Context { context, .. } = self {
+            state.serialize_field("context", context)?;
+        }
+        if let Some(details) = self.details() {
+            state.serialize_field("details", details)?;
+        }
+        if let Some(fields) = self.fields() {
+            state.serialize_field("fields", fields)?;
+        }
+        state.end()
+    }
+}
+
+/// Failures reported as plain strings carry no kind. Code that knows what
+/// went wrong returns the matching variant instead.
+impl From<String> for AppError {
+    fn from(message: String) -> Self {
+        AppError::Internal(message)
+    }
+}
+
+impl From<&str> for AppError {
+    fn from(message: &str) -> Self {
+        AppError::Internal(message.to_string())
+    }
+}
+
+/// For code that reports failures as strings
+impl From<AppError> for String {
+    fn from(error: AppError) -> Self {
+        error.to_string()
+    }
+}
+
+impl From<Vec<String>> for AppError {
+    fn from(problems: Vec<String>) -> Self {
+        AppError::Validation(problems)
+    }
+}
+
+impl From<std::io::Error> for AppError {
+    fn from(e: std::io::Error) -> Self {
+        match e.kind() {
+            std::io::ErrorKind::NotFound => AppError::NotFound(e.to_string()),
+            std::io::ErrorKind::PermissionDenied => AppError::PermissionDenied(e.to_string()),
+            _ => AppError::Io(e.to_string()),
+        }
+    }
+}
+
+impl From<serde_json::Error> for AppError {
+    fn from(e: serde_json::Error) -> Self {
+        AppError::Parse(e.to_string())
+    }
+}
+
+impl From<reqwest::Error> for AppError {
+    fn from(e: reqwest::Error) -> Self {
+        AppError::Network(e.to_string())
+    }
+}
+
+impl From<SerializerError> for AppError {
+    fn from(e: SerializerError) -> Self {
+        match e {
+            SerializerError::InvalidFormat(_) => AppError::InvalidInput(e.to_string()),
+            _ => AppError::Parse(e.to_string()),
+        }
+    }
+}
+
+impl From<FileError> for AppError {
+    fn from(e: FileError) -> Self {
+        match e {
+            FileError::Io(io) => io.into(),
+            FileError::Serialization(serialization) => serialization.into(),
+            FileError::NotFound(_) => AppError::NotFound(e.to_string()),
+            FileError::InvalidFormat(_) => AppError::Parse(e.to_string()),
+            FileError::PermissionDenied(_) => AppError::PermissionDenied(e.to_string()),
+            FileError::AtomicSaveFailed { .. } => AppError::Io(e.to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_messages_are_not_classified_by_wording() {
+        for message in [
+            "File not found: a.json",
+            "Access denied: /etc is outside the allowed directories",
+            "Cancelled",
+        ] {
+            assert_eq!(AppError::from(message).code(), ErrorCode::Internal);
+        }
+    }
+
+    #[test]
+    fn test_serialized_shape() {
+        let error: AppResult<()> = Err(AppError::NotFound("File not found: a.json".into()));
+        let value = serde_json::to_value(error.context("Opening sequence").unwrap_err()).unwrap();
+        assert_eq!(
+            value,
+            serde_json::json!({
+                "code": "notFound",
+                "message": "Opening sequence: File not found: a.json",
+                "context": "Opening sequence",
+            })
+        );
+
+        let validation = AppError::from(vec!["Missing name".to_string(), "Bad RA".to_string()]);
+        let value = serde_json::to_value(&validation).unwrap();
+        assert_eq!(value["code"], "validation");
+        assert_eq!(value["message"], "Missing name; Bad RA");
+        assert_eq!(value["details"].as_array().unwrap().len(), 2);
+        assert!(value.get("context").is_none());
+    }
+
+    #[test]
+    fn test_invalid_fields_shape() {
+        let error = AppError::InvalidFields(vec![
+            FieldError::new("location.latitude", "must be between -90 and 90"),
+            FieldError::new("date", "must be a date like 2024-03-21"),
+        ]);
+        assert_eq!(error.code(), ErrorCode::InvalidInput);
+
+        let value = serde_json::to_value(&error).unwrap();
+        assert_eq!(
+            value["message"],
+            "Invalid input: location.latitude: must be between -90 and 90; date: must be a date like 2024-03-21"
+        );
+        assert_eq!(
+            value["fields"][0],
+            serde_json::json!({
+                "field": "location.latitude",
+                "message": "must be between -90 and 90",
+            })
+        );
+        assert!(value.get("details").is_none());
+    }
+
+    #[test]
+    fn test_io_error_kinds() {
+        let error: AppError = std::io::Error::from(std::io::ErrorKind::NotFound).into();
+        assert_eq!(error.code(), ErrorCode::NotFound);
+        let error: AppError = std::io::Error::from(std::io::ErrorKind::PermissionDenied).into();
+        assert_eq!(error.code(), ErrorCode::PermissionDenied);
+    }
+}
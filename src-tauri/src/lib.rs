@@ -2,18 +2,24 @@
 //!
 //! A cross-platform desktop application for editing NINA astronomy sequences.
 
+pub mod cli;
 pub mod commands;
+pub mod error;
 pub mod models;
 pub mod services;
+pub mod state;
 
 #[cfg(test)]
 mod tests;
 
 use commands::*;
+use tauri::{Emitter, Manager};
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     tauri::Builder::default()
+        // Shared application state
+        .manage(state::SharedState::default())
         // Register plugins
         .plugin(
             tauri_plugin_log::Builder::default()
@@ -33,27 +39,40 @@ pub fn run() {
             read_file_contents,
             write_file_contents,
             load_simple_sequence_file,
+            load_any_sequence,
             save_simple_sequence_file,
             load_editor_sequence_file,
             save_editor_sequence_file,
+            save_sequence_archive,
+            load_sequence_archive,
+            watch_sequence_file,
+            unwatch_sequence_file,
+            reload_if_changed,
             import_targets_csv,
             import_targets_csv_content,
             export_sequence_csv,
             export_sequence_xml,
             export_sequence_target_set,
             get_file_info,
+            check_destination_space,
             list_directory,
             file_exists,
             delete_file,
             copy_file,
+            add_allowed_directory,
+            remove_allowed_directory,
+            list_allowed_directories,
             get_default_save_directory,
             get_app_data_directory,
             auto_save_sequence,
             load_auto_save,
             clear_auto_save,
+            get_auto_save_status,
             // Sequence commands
             validate_simple_sequence,
             validate_editor_sequence,
+            list_validation_rules,
+            set_validation_rule_config,
             validate_nina_json,
             validate_coordinates,
             serialize_simple_sequence,
@@ -66,10 +85,25 @@ pub fn run() {
             create_exposure,
             duplicate_target,
             duplicate_exposure,
+            duplicate_editor_item,
+            move_editor_item,
+            reorder_items,
             copy_exposures_to_all_targets,
+            bulk_edit_exposures,
+            set_exposures_enabled,
+            generate_exposure_ramp,
+            set_items_status,
+            normalize_sequence,
+            apply_filter_offsets_to_sequence,
+            search_targets_by_tag,
+            find_duplicate_targets,
+            merge_targets,
+            search_sequence,
             reset_target_progress,
             reset_sequence_progress,
             get_sequence_statistics,
+            estimate_storage_requirements,
+            get_sequence_progress_summary,
             is_container_type,
             get_short_type_name,
             get_type_category,
@@ -78,10 +112,15 @@ pub fn run() {
             load_settings,
             save_settings,
             get_settings,
+            get_settings_migration_report,
+            export_settings_profile,
+            import_settings_profile,
             get_recent_files,
             add_recent_file,
             remove_recent_file,
             clear_recent_files,
+            get_recent_file_details,
+            set_recent_file_pinned,
             get_last_directory,
             set_last_directory,
             save_window_state,
@@ -90,8 +129,38 @@ pub fn run() {
             get_theme,
             set_language,
             get_language,
+            set_unit_preferences,
+            get_unit_preferences,
+            get_locale_unit_preferences,
             set_estimated_download_time,
             get_estimated_download_time,
+            list_equipment_profiles,
+            get_equipment_profile,
+            create_equipment_profile,
+            save_equipment_profile,
+            delete_equipment_profile,
+            set_active_equipment_profile,
+            get_active_equipment_profile,
+            list_filters,
+            list_filter_sets,
+            save_filter_set,
+            delete_filter_set,
+            set_active_filter_set,
+            get_active_filter_set,
+            list_sites,
+            get_site,
+            save_site,
+            add_site,
+            delete_site,
+            set_active_site,
+            get_active_site,
+            discover_alpaca_devices,
+            apply_alpaca_device_to_profile,
+            create_site_from_alpaca_device,
+            import_phd2_profile,
+            measure_phd2_settle_times,
+            apply_phd2_settle_times,
+            calibrate_slew_model,
             // Calculator commands
             calculate_sequence_runtime,
             calculate_sequence_etas,
@@ -101,6 +170,8 @@ pub fn run() {
             format_time,
             calculate_end_time,
             calculate_angular_separation,
+            calculate_separation_matrix,
+            find_targets_near,
             ra_to_decimal,
             decimal_to_ra,
             dec_to_decimal,
@@ -113,6 +184,12 @@ pub fn run() {
             parse_dec,
             format_ra,
             format_dec,
+            equatorial_to_galactic,
+            galactic_to_equatorial,
+            equatorial_to_ecliptic,
+            ecliptic_to_equatorial,
+            calculate_flat_exposures,
+            analyze_sampling,
             // Clipboard commands
             copy_target,
             copy_targets,
@@ -125,6 +202,10 @@ pub fn run() {
             has_clipboard_content,
             has_clipboard_content_type,
             clear_clipboard,
+            copy_to_slot,
+            paste_from_slot,
+            list_clipboard_slots,
+            delete_clipboard_slot,
             get_clipboard_json,
             set_clipboard_json,
             copy_sequence_item,
@@ -144,13 +225,27 @@ pub fn run() {
             list_exposure_templates,
             apply_target_template,
             apply_exposure_template,
+            clone_builtin_template,
+            export_template_bundle,
+            import_template_bundle,
             // Backup commands
             create_backup,
+            create_editor_backup,
             list_backups,
             restore_backup,
+            restore_editor_backup,
+            preview_backup,
+            restore_backup_partial,
             delete_backup,
             clean_old_backups,
+            apply_backup_retention,
+            get_backup_retention,
+            set_backup_retention,
+            get_backup_on_save,
+            set_backup_on_save,
+            get_backup_statistics,
             save_crash_recovery,
+            journal_edit,
             load_crash_recovery,
             clear_crash_recovery,
             list_crash_recovery,
@@ -169,6 +264,15 @@ pub fn run() {
             read_log_file,
             list_log_files,
             clean_old_logs,
+            clean_logs_by_size,
+            export_logs,
+            create_support_bundle,
+            // Sequence library commands
+            get_library_directory,
+            set_library_directory,
+            refresh_library_index,
+            get_library_index,
+            search_sequence_library,
             // NINA format commands
             export_to_nina_json,
             import_from_nina_json,
@@ -176,12 +280,26 @@ pub fn run() {
             save_nina_sequence_file,
             load_nina_sequence_file,
             export_template_to_nina,
+            convert_simple_to_editor,
+            convert_editor_to_simple,
+            sync_target_into_container,
             get_nina_type_short_name,
             get_nina_type_category,
             is_nina_container_type,
+            get_nina_item_schema,
+            list_nina_item_types,
+            reload_nina_plugin_types,
+            get_nina_plugin_types_path,
             get_nina_categories,
+            connect_to_nina,
+            disconnect_from_nina,
+            get_nina_connection,
+            push_sequence_to_nina,
+            pull_current_sequence_from_nina,
+            get_nina_equipment_status,
             // Astronomy commands
             calculate_target_visibility,
+            calculate_target_rise_set,
             calculate_twilight_times,
             get_moon_phase,
             calculate_quality_score,
@@ -193,26 +311,63 @@ pub fn run() {
             get_moon_illumination_now,
             calculate_visibility_range,
             calculate_twilight_range,
+            generate_dark_calendar,
+            search_dso_catalog,
+            recommend_targets_tonight,
+            calculate_target_season,
             calculate_altitude_curve,
+            get_altitude_curve,
+            clear_altitude_curve_cache,
+            get_altitude_curve_cache_stats,
             is_target_visible,
             calculate_air_mass,
+            precess_coordinates,
+            get_apparent_coordinates,
+            convert_coordinates_epoch,
+            calculate_moving_target_position,
+            update_moving_target_positions,
+            import_tle_content,
+            import_tle_file,
+            get_tle_catalog,
+            clear_tle_catalog,
+            predict_transits,
+            get_night_forecast,
+            list_weather_providers,
             // Import commands
             import_csv_content,
             import_stellarium_content,
             import_apt_content,
             import_voyager_content,
+            import_voyager_robotarget_content,
             import_xml_content,
             import_auto_detect,
             detect_csv_format_from_headers,
             parse_fits_header_bytes,
+            parse_xisf_header_bytes,
             create_target_from_fits_info,
             import_csv_file,
             import_stellarium_file,
             import_xml_file,
             import_fits_file,
+            import_xisf_file,
+            batch_scan_fits_directory,
+            analyze_image_library,
+            subtract_acquired_from_sequence,
+            import_sgp_content,
+            import_sgp_file,
+            import_nina_xml_target_set,
+            import_nina_xml_target_set_file,
+            parse_platesolve_content,
+            import_platesolve_result,
+            import_mpc_elements_content,
+            import_horizons_ephemeris_content,
             batch_import_files,
             validate_csv_mapping,
             preview_csv_content,
+            preview_csv_import,
+            preview_import_content,
+            commit_import,
+            discard_import_preview,
             // Export commands
             export_sequence_with_options,
             export_to_csv_format,
@@ -221,20 +376,24 @@ pub fn run() {
             export_to_apt_format,
             export_to_stellarium_format,
             export_to_voyager_format,
+            export_to_voyager_robotarget_format,
             export_to_nina_target_set_format,
             export_to_json_format,
             generate_targets_csv,
             generate_targets_xml,
             export_sequence_to_file,
+            export_sequence_multi,
             export_targets_to_file,
             format_coordinates,
             get_export_formats,
             get_coordinate_formats,
+            generate_session_report,
             // Optimizer commands
             optimize_target_order,
             detect_schedule_conflicts,
             calculate_parallel_etas,
             get_target_schedule_info,
+            calculate_imaging_window,
             apply_optimization,
             merge_multiple_sequences,
             split_sequence_by_target,
@@ -243,19 +402,155 @@ pub fn run() {
             validate_sequence_for_date,
             find_best_observation_date,
             estimate_session_time,
+            simulate_sequence,
+            get_sequence_timeline,
+            rebalance_exposures,
+            apply_rebalanced_counts,
+            split_sequence_across_nights,
+            // Background job commands
+            start_job,
+            get_job_status,
+            list_jobs,
+            cancel_job,
+            cancel_operation,
+            clear_finished_jobs,
+            // Remote API commands
+            get_remote_api_settings,
+            set_remote_api_settings,
+            regenerate_remote_api_token,
+            get_remote_api_status,
+            start_remote_api,
+            stop_remote_api,
+            set_remote_api_sequence,
+            // Open sequence sessions
+            open_sequence,
+            get_open_sequences,
+            get_open_sequence,
+            update_sequence,
+            apply_sequence_patch,
+            save_open_sequence,
+            close_sequence,
+            open_sequence_in_new_window,
+            bind_window_session,
+            get_window_session,
         ])
         .setup(|app| {
+            let state = app.state::<state::SharedState>().inner().clone();
+
+            // Push log entries to the frontend log console
+            let log_handle = app.handle().clone();
+            services::log_service::set_log_listener(
+                &state,
+                Some(Box::new(move |entry| {
+                    if let Err(e) = log_handle.emit(services::log_service::LOG_ENTRY_EVENT, entry) {
+                        log::warn!("Failed to emit log entry: {}", e);
+                    }
+                })),
+            );
+
+            // Notify the frontend when the open sequence file changes on disk
+            let watch_handle = app.handle().clone();
+            services::file_watcher::set_file_change_listener(
+                &state,
+                Some(Box::new(move |event| {
+                    if let Err(e) =
+                        watch_handle.emit(services::file_watcher::FILE_CHANGED_EVENT, event)
+                    {
+                        log::warn!("Failed to emit file change: {}", e);
+                    }
+                })),
+            );
+
+            // Forward equipment updates from a connected NINA instance
+            let nina_handle = app.handle().clone();
+            services::nina_remote::set_equipment_listener(
+                &state,
+                Some(Box::new(move |status| {
+                    if let Err(e) =
+                        nina_handle.emit(services::nina_remote::NINA_EQUIPMENT_EVENT, status)
+                    {
+                        log::warn!("Failed to emit NINA equipment status: {}", e);
+                    }
+                })),
+            );
+
+            // Hand plans pushed by remote clients to the frontend
+            let remote_handle = app.handle().clone();
+            services::remote_api::set_remote_api_listener(
+                &state,
+                Some(Box::new(move |event| {
+                    if let Err(e) =
+                        remote_handle.emit(services::remote_api::REMOTE_API_EVENT, event)
+                    {
+                        log::warn!("Failed to emit remote API event: {}", e);
+                    }
+                })),
+            );
+
+            // Tell every window about changes to open sequences
+            let session_handle = app.handle().clone();
+            state.sessions.set_listener(Some(Box::new(move |change| {
+                if let Err(e) =
+                    session_handle.emit(services::sequence_session::SESSION_CHANGED_EVENT, change)
+                {
+                    log::warn!("Failed to emit session change: {}", e);
+                }
+            })));
+
+            // Auto-save open sessions in the background
+            services::auto_save::start(state.clone());
+
             // Initialize settings on startup
-            let _handle = app.handle().clone();
             tauri::async_runtime::spawn(async move {
-                if let Err(e) = services::settings_service::load_settings().await {
+                if let Err(e) = services::settings_service::load_settings(&state).await {
                     log::warn!("Failed to load settings: {}", e);
                 }
+                if let Err(e) = services::nina_type_registry::load_plugin_types(&state).await {
+                    log::warn!("Failed to load plugin types: {}", e);
+                }
+                let retention = services::settings_service::get_backup_retention(&state);
+                if let Err(e) =
+                    services::backup_service::apply_retention_policy(None, &retention).await
+                {
+                    log::warn!("Failed to prune backups: {}", e);
+                }
+                if let Some(directory) = services::settings_service::get_library_directory(&state) {
+                    if let Err(e) = services::sequence_library::refresh_library_index(
+                        &state,
+                        std::path::Path::new(&directory),
+                    )
+                    .await
+                    {
+                        log::warn!("Failed to index sequence library: {}", e);
+                    }
+                }
+                if services::settings_service::get_settings(&state)
+                    .remote_api
+                    .enabled
+                {
+                    let started = async {
+                        let settings =
+                            services::settings_service::get_remote_api_settings(&state).await?;
+                        services::remote_api::start_server(&state, &settings).await
+                    }
+                    .await;
+                    if let Err(e) = started {
+                        log::warn!("Failed to start remote API: {}", e);
+                    }
+                }
             });
 
             log::info!("Cobalt Task Editor started");
             Ok(())
         })
+        .on_window_event(|window, event| {
+            if let tauri::WindowEvent::Destroyed = event {
+                window
+                    .state::<state::SharedState>()
+                    .sessions
+                    .release_window(window.label());
+            }
+        })
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
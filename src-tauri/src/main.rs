@@ -2,5 +2,9 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().map(String::as_str) == Some(app_lib::cli::CLI_FLAG) {
+        std::process::exit(app_lib::cli::run(&args[1..]));
+    }
     app_lib::run();
 }
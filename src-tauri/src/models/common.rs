@@ -1,6 +1,10 @@
 //! Common types and enums used across the application
 
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use super::equipment::{EquipmentProfile, FilterSet};
+use super::site::ObservingSite;
 
 /// Status of a sequence entity
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
@@ -66,6 +70,42 @@ pub enum SequenceMode {
     Rotate,
 }
 
+/// Scheduling priority of a target
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, Default,
+)]
+#[serde(rename_all = "camelCase")]
+pub enum TargetPriority {
+    Low,
+    #[default]
+    Normal,
+    High,
+    Critical,
+}
+
+impl TargetPriority {
+    /// Relative share of the night used when weighting targets
+    pub fn weight(&self) -> f64 {
+        match self {
+            TargetPriority::Low => 0.5,
+            TargetPriority::Normal => 1.0,
+            TargetPriority::High => 2.0,
+            TargetPriority::Critical => 4.0,
+        }
+    }
+
+    /// Parse a priority name or its level (1 = low … 4 = critical)
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "low" | "1" => Some(TargetPriority::Low),
+            "normal" | "medium" | "2" => Some(TargetPriority::Normal),
+            "high" | "3" => Some(TargetPriority::High),
+            "critical" | "urgent" | "4" => Some(TargetPriority::Critical),
+            _ => None,
+        }
+    }
+}
+
 /// Binning mode for camera
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub struct BinningMode {
@@ -93,20 +133,47 @@ pub struct FilterInfo {
     pub auto_focus_exposure_time: Option<f64>,
 }
 
+/// Recent file as last opened, saved or exported by the app
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecentFileMetadata {
+    #[serde(default)]
+    pub target_count: Option<usize>,
+    /// Light frame integration in seconds
+    #[serde(default)]
+    pub total_integration: Option<f64>,
+    /// Format of the last export, as its serialized `ExportFormat` name
+    #[serde(default)]
+    pub last_export_format: Option<String>,
+}
+
 /// Application settings
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct AppSettings {
+    /// Settings schema version, see [`SETTINGS_VERSION`]
+    #[serde(default)]
+    pub version: u32,
     /// Last opened directory
     pub last_directory: Option<String>,
     /// Recent files list
     pub recent_files: Vec<String>,
-    /// Maximum recent files to keep
+    /// Maximum recent files to keep, not counting pinned ones
     pub max_recent_files: usize,
+    /// Recent files that never drop off the list
+    #[serde(default)]
+    pub pinned_files: Vec<String>,
+    /// What was last seen of each recent file, by path
+    #[serde(default)]
+    pub recent_file_metadata: HashMap<String, RecentFileMetadata>,
     /// Auto-save enabled
     pub auto_save_enabled: bool,
     /// Auto-save interval in seconds
     pub auto_save_interval: u32,
+    /// Auto-save as soon as this many edits are unsaved, 0 to only save on
+    /// the interval
+    #[serde(default = "default_auto_save_after_edits")]
+    pub auto_save_after_edits: u32,
     /// Window width
     pub window_width: Option<u32>,
     /// Window height
@@ -123,16 +190,70 @@ pub struct AppSettings {
     pub language: String,
     /// Estimated download time in seconds
     pub estimated_download_time: f64,
+    /// Saved equipment profiles
+    #[serde(default)]
+    pub equipment_profiles: Vec<EquipmentProfile>,
+    /// Id of the active equipment profile
+    #[serde(default)]
+    pub active_equipment_profile_id: Option<String>,
+    /// Filter library
+    #[serde(default)]
+    pub filter_sets: Vec<FilterSet>,
+    /// Id of the active filter set
+    #[serde(default)]
+    pub active_filter_set_id: Option<String>,
+    /// Saved observing sites
+    #[serde(default)]
+    pub observing_sites: Vec<ObservingSite>,
+    /// Id of the active observing site
+    #[serde(default)]
+    pub active_site_id: Option<String>,
+    /// Validation rule overrides by rule id
+    #[serde(default)]
+    pub validation_rules: HashMap<String, ValidationRuleConfig>,
+    /// Give entities imported from NINA files ids derived from their
+    /// position, so reimporting a file keeps them
+    #[serde(default)]
+    pub stable_nina_ids: bool,
+    /// Back up the previous file contents whenever a sequence file is saved
+    #[serde(default)]
+    pub backup_on_save: bool,
+    /// Which backups to keep when pruning
+    #[serde(default)]
+    pub backup_retention: BackupRetentionPolicy,
+    /// Units and number formats for displayed and exported values
+    #[serde(default)]
+    pub unit_preferences: UnitPreferences,
+    /// Folder indexed as the sequence library
+    #[serde(default)]
+    pub library_directory: Option<String>,
+    /// Directories the user approved for file commands
+    #[serde(default)]
+    pub allowed_directories: Vec<String>,
+    /// Embedded HTTP API for remote control
+    #[serde(default)]
+    pub remote_api: RemoteApiSettings,
+}
+
+fn default_auto_save_after_edits() -> u32 {
+    20
 }
 
+/// Current settings schema version. Files without a version are version 0.
+pub const SETTINGS_VERSION: u32 = 1;
+
 impl Default for AppSettings {
     fn default() -> Self {
         Self {
+            version: SETTINGS_VERSION,
             last_directory: None,
             recent_files: Vec::new(),
             max_recent_files: 10,
+            pinned_files: Vec::new(),
+            recent_file_metadata: HashMap::new(),
             auto_save_enabled: true,
             auto_save_interval: 300,
+            auto_save_after_edits: default_auto_save_after_edits(),
             window_width: Some(1280),
             window_height: Some(800),
             window_x: None,
@@ -141,6 +262,167 @@ impl Default for AppSettings {
             theme: "system".to_string(),
             language: "en".to_string(),
             estimated_download_time: 5.0,
+            equipment_profiles: Vec::new(),
+            active_equipment_profile_id: None,
+            filter_sets: Vec::new(),
+            active_filter_set_id: None,
+            observing_sites: Vec::new(),
+            active_site_id: None,
+            validation_rules: HashMap::new(),
+            stable_nina_ids: false,
+            backup_on_save: false,
+            backup_retention: BackupRetentionPolicy::default(),
+            unit_preferences: UnitPreferences::default(),
+            library_directory: None,
+            allowed_directories: Vec::new(),
+            remote_api: RemoteApiSettings::default(),
+        }
+    }
+}
+
+/// Embedded HTTP API. Off by default and bound to the loopback
+/// interface unless another address is configured.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RemoteApiSettings {
+    /// Start the server with the application
+    pub enabled: bool,
+    /// Address to listen on, e.g. `0.0.0.0` for access from the LAN
+    pub bind_address: String,
+    pub port: u16,
+    /// Bearer token required on every request; generated when empty
+    pub token: String,
+}
+
+impl Default for RemoteApiSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            bind_address: "127.0.0.1".to_string(),
+            port: 8765,
+            token: String::new(),
+        }
+    }
+}
+
+/// Backup retention policy, applied per sequence. A backup survives
+/// pruning if any of the rules keeps it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupRetentionPolicy {
+    /// Number of most recent backups always kept
+    pub keep_last: usize,
+    /// Number of days for which the newest backup of the day is kept
+    pub keep_daily: usize,
+    /// Number of ISO weeks for which the newest backup of the week is kept
+    pub keep_weekly: usize,
+}
+
+impl Default for BackupRetentionPolicy {
+    fn default() -> Self {
+        Self {
+            keep_last: 5,
+            keep_daily: 7,
+            keep_weekly: 4,
+        }
+    }
+}
+
+/// Measurement system for lengths and temperatures
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum UnitSystem {
+    #[default]
+    Metric,
+    Imperial,
+}
+
+/// Clock format for times of day
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ClockFormat {
+    #[default]
+    #[serde(rename = "24h")]
+    TwentyFourHour,
+    #[serde(rename = "12h")]
+    TwelveHour,
+}
+
+/// Units and number formats. The defaults match the US formats used
+/// before these preferences existed, apart from metric units.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UnitPreferences {
+    #[serde(default)]
+    pub unit_system: UnitSystem,
+    #[serde(default)]
+    pub clock_format: ClockFormat,
+    #[serde(default = "default_decimal_separator")]
+    pub decimal_separator: char,
+    #[serde(default = "default_csv_delimiter")]
+    pub csv_delimiter: char,
+}
+
+fn default_decimal_separator() -> char {
+    '.'
+}
+
+fn default_csv_delimiter() -> char {
+    ','
+}
+
+impl Default for UnitPreferences {
+    fn default() -> Self {
+        Self {
+            unit_system: UnitSystem::Metric,
+            clock_format: ClockFormat::TwentyFourHour,
+            decimal_separator: default_decimal_separator(),
+            csv_delimiter: default_csv_delimiter(),
+        }
+    }
+}
+
+impl UnitPreferences {
+    /// Customary formats for a locale such as "de" or "en-US"
+    pub fn for_locale(locale: &str) -> Self {
+        let locale = locale.to_lowercase().replace('_', "-");
+        let language = locale.split('-').next().unwrap_or_default();
+        let region = locale.split('-').nth(1).unwrap_or_default();
+
+        let decimal_comma = matches!(
+            language,
+            "de" | "fr"
+                | "es"
+                | "it"
+                | "nl"
+                | "pt"
+                | "ru"
+                | "pl"
+                | "cs"
+                | "sk"
+                | "sv"
+                | "da"
+                | "nb"
+                | "fi"
+                | "tr"
+                | "uk"
+        );
+        let imperial = region == "us" || region == "lr" || region == "mm";
+        let twelve_hour = matches!(language, "en") && region != "gb" && region != "ie";
+
+        Self {
+            unit_system: if imperial {
+                UnitSystem::Imperial
+            } else {
+                UnitSystem::Metric
+            },
+            clock_format: if twelve_hour {
+                ClockFormat::TwelveHour
+            } else {
+                ClockFormat::TwentyFourHour
+            },
+            decimal_separator: if decimal_comma { ',' } else { '.' },
+            // Spreadsheets in decimal-comma locales split CSV on semicolons
+            csv_delimiter: if decimal_comma { ';' } else { ',' },
         }
     }
 }
@@ -178,6 +460,43 @@ impl FileFormat {
     }
 }
 
+/// Severity of a validation issue
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ValidationSeverity {
+    Info,
+    Warning,
+    Error,
+}
+
+/// Issue reported by a validation rule
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ValidationIssue {
+    pub rule_id: String,
+    pub severity: ValidationSeverity,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target_index: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exposure_index: Option<usize>,
+    /// Editor item the issue refers to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub item_id: Option<String>,
+}
+
+/// User override for a validation rule. Unset fields keep the rule default.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ValidationRuleConfig {
+    #[serde(default)]
+    pub enabled: Option<bool>,
+    #[serde(default)]
+    pub severity: Option<ValidationSeverity>,
+    #[serde(default)]
+    pub threshold: Option<f64>,
+}
+
 /// Result of a validation operation
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -185,6 +504,9 @@ pub struct ValidationResult {
     pub valid: bool,
     pub errors: Vec<String>,
     pub warnings: Vec<String>,
+    /// Every issue with its rule id, including info-level ones
+    #[serde(default)]
+    pub issues: Vec<ValidationIssue>,
 }
 
 impl ValidationResult {
@@ -193,6 +515,7 @@ impl ValidationResult {
             valid: true,
             errors: Vec::new(),
             warnings: Vec::new(),
+            issues: Vec::new(),
         }
     }
 
@@ -201,6 +524,7 @@ impl ValidationResult {
             valid: false,
             errors: vec![message.into()],
             warnings: Vec::new(),
+            issues: Vec::new(),
         }
     }
 
@@ -209,6 +533,25 @@ impl ValidationResult {
             valid: errors.is_empty(),
             errors,
             warnings: Vec::new(),
+            issues: Vec::new(),
+        }
+    }
+
+    /// Build a result from rule issues; only errors make it invalid
+    pub fn from_issues(issues: Vec<ValidationIssue>) -> Self {
+        let messages = |severity| {
+            issues
+                .iter()
+                .filter(|i| i.severity == severity)
+                .map(|i| i.message.clone())
+                .collect::<Vec<_>>()
+        };
+        let errors = messages(ValidationSeverity::Error);
+        Self {
+            valid: errors.is_empty(),
+            errors,
+            warnings: messages(ValidationSeverity::Warning),
+            issues,
         }
     }
 }
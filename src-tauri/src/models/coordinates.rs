@@ -13,6 +13,22 @@ pub struct Coordinates {
     pub dec_minutes: i32,
     pub dec_seconds: f64,
     pub negative_dec: bool,
+    /// Equinox the coordinates are referred to
+    #[serde(default)]
+    pub epoch: CoordinateEpoch,
+}
+
+/// Coordinate epoch
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum CoordinateEpoch {
+    /// Mean equator and equinox of J2000.0
+    #[default]
+    J2000,
+    /// Apparent equator and equinox of date
+    JNow,
+    /// Mean equator and equinox of date: precessed, without nutation and
+    /// aberration
+    MeanOfDate,
 }
 
 impl Coordinates {
@@ -34,6 +50,7 @@ impl Coordinates {
             dec_minutes,
             dec_seconds,
             negative_dec,
+            epoch: CoordinateEpoch::J2000,
         }
     }
 
@@ -81,6 +98,7 @@ impl Coordinates {
             dec_minutes: dec_m,
             dec_seconds: (dec_s * 100.0).round() / 100.0,
             negative_dec,
+            epoch: CoordinateEpoch::J2000,
         }
     }
 
@@ -203,10 +221,19 @@ impl From<&NinaInputCoordinates> for Coordinates {
             dec_minutes: nina.dec_minutes,
             dec_seconds: nina.dec_seconds,
             negative_dec: nina.negative_dec.unwrap_or(false),
+            epoch: CoordinateEpoch::J2000,
         }
     }
 }
 
+/// Movement of the right ascension and declination axes between two
+/// coordinates, in degrees. The RA axis takes the shorter way round.
+pub fn axis_separation(coord1: &Coordinates, coord2: &Coordinates) -> (f64, f64) {
+    let ra = (coord2.ra_to_degrees() - coord1.ra_to_degrees()).rem_euclid(360.0);
+    let dec = (coord2.dec_to_decimal() - coord1.dec_to_decimal()).abs();
+    (ra.min(360.0 - ra), dec)
+}
+
 /// Angular separation calculation between two coordinates
 pub fn angular_separation(coord1: &Coordinates, coord2: &Coordinates) -> f64 {
     let ra1 = coord1.ra_to_degrees().to_radians();
@@ -220,6 +247,101 @@ pub fn angular_separation(coord1: &Coordinates, coord2: &Coordinates) -> f64 {
     cos_sep.acos().to_degrees()
 }
 
+/// Galactic coordinates in degrees
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GalacticCoordinates {
+    /// Galactic longitude l in [0, 360)
+    pub longitude: f64,
+    /// Galactic latitude b, 0 on the galactic plane
+    pub latitude: f64,
+}
+
+/// Ecliptic coordinates in degrees, referred to the mean ecliptic and
+/// equinox of J2000.0
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EclipticCoordinates {
+    /// Ecliptic longitude λ in [0, 360)
+    pub longitude: f64,
+    /// Ecliptic latitude β
+    pub latitude: f64,
+}
+
+/// Rotation from J2000 equatorial to galactic coordinates (Hipparcos,
+/// vol. 1, sec. 1.5.3); its transpose rotates back
+const GALACTIC_ROTATION: [[f64; 3]; 3] = [
+    [-0.054_875_560_4, -0.873_437_090_2, -0.483_835_015_5],
+    [0.494_109_427_9, -0.444_829_630_0, 0.746_982_244_5],
+    [-0.867_666_149_0, -0.198_076_373_4, 0.455_983_776_2],
+];
+
+/// Obliquity of the ecliptic at J2000.0 in degrees
+const J2000_OBLIQUITY: f64 = 23.439_291_1;
+
+/// Unit vector of a longitude and latitude in degrees
+fn unit_vector(longitude: f64, latitude: f64) -> [f64; 3] {
+    let (lon, lat) = (longitude.to_radians(), latitude.to_radians());
+    [lat.cos() * lon.cos(), lat.cos() * lon.sin(), lat.sin()]
+}
+
+/// Longitude in [0, 360) and latitude in degrees of a unit vector
+fn spherical(v: [f64; 3]) -> (f64, f64) {
+    let longitude = v[1].atan2(v[0]).to_degrees().rem_euclid(360.0);
+    let latitude = v[2].clamp(-1.0, 1.0).asin().to_degrees();
+    (if longitude >= 360.0 { 0.0 } else { longitude }, latitude)
+}
+
+fn rotate(m: &[[f64; 3]; 3], v: [f64; 3]) -> [f64; 3] {
+    m.map(|row| row[0] * v[0] + row[1] * v[1] + row[2] * v[2])
+}
+
+fn transpose(m: &[[f64; 3]; 3]) -> [[f64; 3]; 3] {
+    [0, 1, 2].map(|i| [m[0][i], m[1][i], m[2][i]])
+}
+
+/// Rotation from equatorial to ecliptic coordinates, about the equinox
+fn ecliptic_rotation() -> [[f64; 3]; 3] {
+    let (sin, cos) = J2000_OBLIQUITY.to_radians().sin_cos();
+    [[1.0, 0.0, 0.0], [0.0, cos, sin], [0.0, -sin, cos]]
+}
+
+impl Coordinates {
+    /// Galactic coordinates, taking these coordinates as J2000
+    pub fn to_galactic(&self) -> GalacticCoordinates {
+        let v = unit_vector(self.ra_to_degrees(), self.dec_to_decimal());
+        let (longitude, latitude) = spherical(rotate(&GALACTIC_ROTATION, v));
+        GalacticCoordinates {
+            longitude,
+            latitude,
+        }
+    }
+
+    /// J2000 coordinates of a galactic position
+    pub fn from_galactic(galactic: &GalacticCoordinates) -> Self {
+        let v = unit_vector(galactic.longitude, galactic.latitude);
+        let (ra, dec) = spherical(rotate(&transpose(&GALACTIC_ROTATION), v));
+        Self::from_decimal(ra / 15.0, dec)
+    }
+
+    /// Ecliptic coordinates, taking these coordinates as J2000
+    pub fn to_ecliptic(&self) -> EclipticCoordinates {
+        let v = unit_vector(self.ra_to_degrees(), self.dec_to_decimal());
+        let (longitude, latitude) = spherical(rotate(&ecliptic_rotation(), v));
+        EclipticCoordinates {
+            longitude,
+            latitude,
+        }
+    }
+
+    /// J2000 coordinates of an ecliptic position
+    pub fn from_ecliptic(ecliptic: &EclipticCoordinates) -> Self {
+        let v = unit_vector(ecliptic.longitude, ecliptic.latitude);
+        let (ra, dec) = spherical(rotate(&transpose(&ecliptic_rotation()), v));
+        Self::from_decimal(ra / 15.0, dec)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -248,4 +370,37 @@ mod tests {
         assert_eq!(coords.dec_degrees, 45);
         assert_eq!(coords.dec_minutes, 30);
     }
+
+    #[test]
+    fn test_galactic_conversion() {
+        // Sgr A*, near the galactic center
+        let center = Coordinates::new(17, 45, 40.04, 29, 0, 28.2, true);
+        let galactic = center.to_galactic();
+        assert!(galactic.longitude > 359.9 || galactic.longitude < 0.1);
+        assert!(galactic.latitude.abs() < 0.1);
+
+        let m31 = Coordinates::new(0, 42, 44.3, 41, 16, 9.0, false).to_galactic();
+        assert!((m31.longitude - 121.17).abs() < 0.01);
+        assert!((m31.latitude + 21.57).abs() < 0.01);
+
+        let back = Coordinates::from_galactic(&m31);
+        assert_eq!((back.ra_hours, back.ra_minutes), (0, 42));
+        assert!((back.dec_to_decimal() - 41.2692).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_ecliptic_conversion() {
+        // The summer solstice point lies on the ecliptic at 90°
+        let solstice = Coordinates::from_decimal(6.0, J2000_OBLIQUITY);
+        let ecliptic = solstice.to_ecliptic();
+        assert!((ecliptic.longitude - 90.0).abs() < 0.001);
+        assert!(ecliptic.latitude.abs() < 0.001);
+
+        let pole = Coordinates::from_ecliptic(&EclipticCoordinates {
+            longitude: 0.0,
+            latitude: 90.0,
+        });
+        assert!((pole.ra_to_decimal() - 18.0).abs() < 0.001);
+        assert!((pole.dec_to_decimal() - (90.0 - J2000_OBLIQUITY)).abs() < 0.001);
+    }
 }
@@ -0,0 +1,482 @@
+//! Equipment profile types
+//!
+//! An equipment profile describes the imaging rig (camera, filters,
+//! telescope, mount) and provides the defaults used when creating
+//! exposures and estimating runtimes and slew times.
+
+use serde::{Deserialize, Serialize};
+
+use super::common::{BinningMode, FilterInfo};
+use super::coordinates::{axis_separation, Coordinates};
+
+/// Camera settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CameraProfile {
+    pub name: String,
+    /// Pixel size in microns
+    pub pixel_size: f64,
+    pub width: u32,
+    pub height: u32,
+    /// Bits per pixel of the saved frames
+    #[serde(default = "default_bit_depth")]
+    pub bit_depth: u32,
+    pub default_gain: i32,
+    pub default_offset: i32,
+}
+
+fn default_bit_depth() -> u32 {
+    16
+}
+
+impl Default for CameraProfile {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            pixel_size: 3.76,
+            width: 6248,
+            height: 4176,
+            bit_depth: default_bit_depth(),
+            default_gain: -1,
+            default_offset: -1,
+        }
+    }
+}
+
+/// Telescope optics
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TelescopeProfile {
+    pub name: String,
+    /// Focal length in millimeters
+    pub focal_length: f64,
+    /// Aperture in millimeters
+    pub aperture: f64,
+}
+
+impl Default for TelescopeProfile {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            focal_length: 500.0,
+            aperture: 100.0,
+        }
+    }
+}
+
+/// Mount slew characteristics
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MountProfile {
+    pub name: String,
+    /// Slew rate in degrees per second, for axes without their own rate
+    pub slew_rate: f64,
+    /// Right ascension axis slew rate in degrees per second
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ra_slew_rate: Option<f64>,
+    /// Declination axis slew rate in degrees per second
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dec_slew_rate: Option<f64>,
+    /// Axis acceleration in degrees per second squared; 0 treats the axes
+    /// as reaching full speed at once
+    #[serde(default)]
+    pub acceleration: f64,
+    /// Settle time after a slew in seconds
+    pub settle_time: f64,
+    /// Meridian flip with re-centering and guider restart, in seconds
+    #[serde(default = "default_pier_flip_time")]
+    pub pier_flip_time: f64,
+}
+
+fn default_pier_flip_time() -> f64 {
+    300.0
+}
+
+impl Default for MountProfile {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            slew_rate: 3.0,
+            ra_slew_rate: None,
+            dec_slew_rate: None,
+            acceleration: 0.0,
+            settle_time: 5.0,
+            pier_flip_time: default_pier_flip_time(),
+        }
+    }
+}
+
+impl MountProfile {
+    fn base_rate(&self) -> f64 {
+        if self.slew_rate > 0.0 {
+            self.slew_rate
+        } else {
+            MountProfile::default().slew_rate
+        }
+    }
+
+    /// Right ascension slew rate in degrees per second
+    pub fn ra_rate(&self) -> f64 {
+        self.ra_slew_rate
+            .filter(|rate| *rate > 0.0)
+            .unwrap_or_else(|| self.base_rate())
+    }
+
+    /// Declination slew rate in degrees per second
+    pub fn dec_rate(&self) -> f64 {
+        self.dec_slew_rate
+            .filter(|rate| *rate > 0.0)
+            .unwrap_or_else(|| self.base_rate())
+    }
+
+    /// Time for one axis to move `distance` degrees, speeding up to `rate`
+    /// and slowing down again
+    fn axis_time(&self, distance: f64, rate: f64) -> f64 {
+        if self.acceleration <= 0.0 {
+            return distance / rate;
+        }
+        // Distance covered while speeding up and slowing down
+        let ramp = rate * rate / self.acceleration;
+        if distance >= ramp {
+            distance / rate + rate / self.acceleration
+        } else {
+            2.0 * (distance / self.acceleration).sqrt()
+        }
+    }
+
+    /// Estimate slew time in seconds for axis movements in degrees. Both
+    /// axes move at once, so the slower one sets the time.
+    pub fn slew_time_axes(&self, ra_distance: f64, dec_distance: f64) -> f64 {
+        let ra = self.axis_time(ra_distance.abs(), self.ra_rate());
+        let dec = self.axis_time(dec_distance.abs(), self.dec_rate());
+        ra.max(dec) + self.settle_time
+    }
+
+    /// Estimate slew time in seconds when both axes move `distance` degrees
+    pub fn slew_time(&self, distance: f64) -> f64 {
+        self.slew_time_axes(distance, distance)
+    }
+
+    /// Estimate slew time in seconds between two positions
+    pub fn slew_time_between(&self, from: &Coordinates, to: &Coordinates) -> f64 {
+        let (ra_distance, dec_distance) = axis_separation(from, to);
+        self.slew_time_axes(ra_distance, dec_distance)
+    }
+
+    /// Validate the mount model
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+
+        if self.slew_rate <= 0.0 {
+            errors.push("Slew rate must be positive".to_string());
+        }
+        if [self.ra_slew_rate, self.dec_slew_rate]
+            .iter()
+            .flatten()
+            .any(|rate| *rate <= 0.0)
+        {
+            errors.push("Axis slew rates must be positive".to_string());
+        }
+        if self.acceleration < 0.0 {
+            errors.push("Slew acceleration cannot be negative".to_string());
+        }
+        if self.settle_time < 0.0 || self.pier_flip_time < 0.0 {
+            errors.push("Settle and pier flip times cannot be negative".to_string());
+        }
+
+        errors
+    }
+}
+
+/// Autofocus run duration with one filter
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FilterAutofocusTime {
+    pub filter: String,
+    /// Duration in seconds
+    pub seconds: f64,
+}
+
+/// Time spent outside exposures, in seconds
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct OverheadProfile {
+    /// Dither plus guider settle
+    pub dither_settle_time: f64,
+    /// Guider start and settle after a slew
+    pub guiding_start_delay: f64,
+    /// Filter wheel move between two filters
+    pub filter_change_time: f64,
+    /// Autofocus run duration when no filter-specific entry exists
+    pub autofocus_duration: f64,
+    pub autofocus_durations: Vec<FilterAutofocusTime>,
+}
+
+impl Default for OverheadProfile {
+    fn default() -> Self {
+        Self {
+            dither_settle_time: 15.0,
+            guiding_start_delay: 30.0,
+            filter_change_time: 5.0,
+            autofocus_duration: 120.0,
+            autofocus_durations: Vec::new(),
+        }
+    }
+}
+
+impl OverheadProfile {
+    /// Autofocus duration with the given filter, falling back to the default
+    pub fn autofocus_time(&self, filter: Option<&str>) -> f64 {
+        filter
+            .and_then(|name| {
+                self.autofocus_durations
+                    .iter()
+                    .find(|a| a.filter.eq_ignore_ascii_case(name))
+            })
+            .map(|a| a.seconds)
+            .unwrap_or(self.autofocus_duration)
+    }
+
+    /// Validate the overheads
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+
+        if self.dither_settle_time < 0.0 || self.guiding_start_delay < 0.0 {
+            errors.push("Dither and guiding overheads cannot be negative".to_string());
+        }
+        if self.filter_change_time < 0.0 {
+            errors.push("Filter change time cannot be negative".to_string());
+        }
+        if self.autofocus_duration < 0.0 || self.autofocus_durations.iter().any(|a| a.seconds < 0.0)
+        {
+            errors.push("Autofocus duration cannot be negative".to_string());
+        }
+
+        errors
+    }
+}
+
+/// Download time for a binning mode
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BinningDownloadTime {
+    pub binning: BinningMode,
+    /// Download time in seconds
+    pub seconds: f64,
+}
+
+/// Equipment profile
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EquipmentProfile {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub camera: CameraProfile,
+    #[serde(default)]
+    pub filters: Vec<FilterInfo>,
+    #[serde(default)]
+    pub telescope: TelescopeProfile,
+    #[serde(default)]
+    pub mount: MountProfile,
+    #[serde(default)]
+    pub download_times: Vec<BinningDownloadTime>,
+    #[serde(default)]
+    pub overheads: OverheadProfile,
+    /// Download time used when no binning-specific entry exists
+    pub default_download_time: f64,
+    pub default_exposure_time: f64,
+    pub default_exposure_count: i32,
+}
+
+impl Default for EquipmentProfile {
+    fn default() -> Self {
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            name: "Default".to_string(),
+            camera: CameraProfile::default(),
+            filters: Vec::new(),
+            telescope: TelescopeProfile::default(),
+            mount: MountProfile::default(),
+            download_times: Vec::new(),
+            overheads: OverheadProfile::default(),
+            default_download_time: 5.0,
+            default_exposure_time: 60.0,
+            default_exposure_count: 10,
+        }
+    }
+}
+
+impl EquipmentProfile {
+    /// Download time for the given binning, falling back to the default
+    pub fn download_time(&self, binning: &BinningMode) -> f64 {
+        self.download_times
+            .iter()
+            .find(|d| d.binning == *binning)
+            .map(|d| d.seconds)
+            .unwrap_or(self.default_download_time)
+    }
+
+    /// Image scale in arcseconds per pixel
+    pub fn image_scale(&self) -> f64 {
+        if self.telescope.focal_length <= 0.0 {
+            return 0.0;
+        }
+        206.265 * self.camera.pixel_size / self.telescope.focal_length
+    }
+
+    /// Validate the profile
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+
+        if self.name.trim().is_empty() {
+            errors.push("Profile name is required".to_string());
+        }
+        if self.telescope.focal_length <= 0.0 {
+            errors.push("Focal length must be positive".to_string());
+        }
+        errors.extend(self.mount.validate());
+        if self.default_download_time < 0.0 || self.download_times.iter().any(|d| d.seconds < 0.0) {
+            errors.push("Download time cannot be negative".to_string());
+        }
+        errors.extend(self.overheads.validate());
+        if self.default_exposure_time <= 0.0 {
+            errors.push("Default exposure time must be positive".to_string());
+        }
+        if self.default_exposure_count < 1 {
+            errors.push("Default exposure count must be at least 1".to_string());
+        }
+
+        errors
+    }
+}
+
+/// Named set of filters (filter wheel contents)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FilterSet {
+    pub id: String,
+    pub name: String,
+    pub filters: Vec<FilterInfo>,
+}
+
+impl FilterSet {
+    /// Find a filter by name (case-insensitive)
+    pub fn find(&self, name: &str) -> Option<&FilterInfo> {
+        self.filters
+            .iter()
+            .find(|f| f.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Validate the filter set
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+
+        if self.name.trim().is_empty() {
+            errors.push("Filter set name is required".to_string());
+        }
+
+        for (i, filter) in self.filters.iter().enumerate() {
+            if filter.name.trim().is_empty() {
+                errors.push(format!("Filter {}: name is required", i + 1));
+            }
+            if filter.position < 0 {
+                errors.push(format!(
+                    "Filter '{}': position cannot be negative",
+                    filter.name
+                ));
+            }
+            if self.filters[..i]
+                .iter()
+                .any(|f| f.name.eq_ignore_ascii_case(&filter.name))
+            {
+                errors.push(format!("Duplicate filter name '{}'", filter.name));
+            }
+            if self.filters[..i]
+                .iter()
+                .any(|f| f.position == filter.position)
+            {
+                errors.push(format!("Duplicate filter position {}", filter.position));
+            }
+        }
+
+        errors
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_download_time_per_binning() {
+        let mut profile = EquipmentProfile::default();
+        profile.download_times.push(BinningDownloadTime {
+            binning: BinningMode { x: 2, y: 2 },
+            seconds: 1.5,
+        });
+
+        assert_eq!(profile.download_time(&BinningMode { x: 2, y: 2 }), 1.5);
+        assert_eq!(profile.download_time(&BinningMode::default()), 5.0);
+    }
+
+    #[test]
+    fn test_mount_slew_time() {
+        let mount = MountProfile::default();
+        assert!((mount.slew_time(30.0) - 15.0).abs() < 1e-9);
+
+        // Dec is slower; the axes move together
+        let mount = MountProfile {
+            ra_slew_rate: Some(4.0),
+            dec_slew_rate: Some(2.0),
+            ..Default::default()
+        };
+        assert!((mount.slew_time_axes(40.0, 10.0) - 15.0).abs() < 1e-9);
+        assert!((mount.slew_time_axes(8.0, 30.0) - 20.0).abs() < 1e-9);
+
+        // At 2 deg/s² the axes reach 4 deg/s within 4 degrees
+        let mount = MountProfile {
+            slew_rate: 4.0,
+            acceleration: 2.0,
+            settle_time: 0.0,
+            ..Default::default()
+        };
+        assert!((mount.slew_time(40.0) - 12.0).abs() < 1e-9);
+        assert!((mount.slew_time(2.0) - 2.0).abs() < 1e-9);
+
+        let from = Coordinates::from_decimal(23.0, 10.0);
+        let to = Coordinates::from_decimal(1.0, 40.0);
+        // 30° in RA across 0h, 30° in Dec
+        assert!((mount.slew_time_between(&from, &to) - 9.5).abs() < 1e-6);
+        assert!(mount.validate().is_empty());
+    }
+
+    #[test]
+    fn test_validate_profile() {
+        assert!(EquipmentProfile::default().validate().is_empty());
+
+        let profile = EquipmentProfile {
+            name: String::new(),
+            default_exposure_count: 0,
+            ..Default::default()
+        };
+        assert_eq!(profile.validate().len(), 2);
+    }
+
+    #[test]
+    fn test_filter_set_validate() {
+        let filter = |name: &str, position| FilterInfo {
+            name: name.to_string(),
+            position,
+            ..Default::default()
+        };
+        let set = FilterSet {
+            id: "set".to_string(),
+            name: "LRGB".to_string(),
+            filters: vec![filter("L", 0), filter("R", 1), filter("r", 1)],
+        };
+
+        assert_eq!(set.validate().len(), 2);
+        assert_eq!(set.find("l").map(|f| f.position), Some(0));
+    }
+}
@@ -5,10 +5,16 @@
 
 pub mod common;
 pub mod coordinates;
+pub mod equipment;
+pub mod moving_target;
 pub mod sequence;
 pub mod simple_sequence;
+pub mod site;
 
 pub use common::*;
 pub use coordinates::*;
+pub use equipment::*;
+pub use moving_target::*;
 pub use sequence::*;
 pub use simple_sequence::*;
+pub use site::*;
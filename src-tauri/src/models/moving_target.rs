@@ -0,0 +1,66 @@
+//! Non-sidereal (moving) target types
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Kind of moving target
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum MovingTargetKind {
+    Comet,
+    #[default]
+    Asteroid,
+    Other,
+}
+
+/// Heliocentric osculating orbital elements, referred to the J2000 ecliptic.
+/// Elliptic orbits from mean anomaly are stored with their perihelion time.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OrbitalElements {
+    /// Epoch of osculation (JD)
+    pub epoch: f64,
+    /// Perihelion distance (AU)
+    pub perihelion_distance: f64,
+    pub eccentricity: f64,
+    /// Inclination (degrees)
+    pub inclination: f64,
+    /// Argument of perihelion (degrees)
+    pub arg_perihelion: f64,
+    /// Longitude of the ascending node (degrees)
+    pub ascending_node: f64,
+    /// Time of perihelion passage (JD)
+    pub perihelion_time: f64,
+}
+
+/// One row of an ephemeris table (astrometric J2000)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EphemerisEntry {
+    pub time: DateTime<Utc>,
+    /// Right ascension (hours)
+    pub ra: f64,
+    /// Declination (degrees)
+    pub dec: f64,
+}
+
+/// Comet, asteroid or other non-sidereal target. Positions come from the
+/// ephemeris table when it covers the requested time, otherwise from the
+/// orbital elements.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MovingTarget {
+    pub designation: String,
+    pub kind: MovingTargetKind,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub elements: Option<OrbitalElements>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ephemeris: Vec<EphemerisEntry>,
+}
+
+impl MovingTarget {
+    /// Time range covered by the ephemeris table
+    pub fn ephemeris_range(&self) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+        Some((self.ephemeris.first()?.time, self.ephemeris.last()?.time))
+    }
+}
@@ -3,11 +3,17 @@
 //! These types represent the simplified NINA sequence format
 //! used for basic target and exposure management.
 
+use std::collections::HashMap;
+
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
-use super::common::{BinningMode, FilterInfo, ImageType, SequenceEntityStatus, SequenceMode};
+use super::common::{
+    BinningMode, FilterInfo, ImageType, SequenceEntityStatus, SequenceMode, TargetPriority,
+};
 use super::coordinates::Coordinates;
+use super::equipment::OverheadProfile;
+use super::moving_target::MovingTarget;
 
 /// Simple exposure settings
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -33,6 +39,12 @@ pub struct SimpleExposure {
     // Dithering
     pub dither: bool,
     pub dither_every: i32,
+
+    // ETA
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub estimated_start_time: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub estimated_end_time: Option<DateTime<Utc>>,
 }
 
 impl Default for SimpleExposure {
@@ -51,6 +63,8 @@ impl Default for SimpleExposure {
             progress_count: 0,
             dither: false,
             dither_every: 1,
+            estimated_start_time: None,
+            estimated_end_time: None,
         }
     }
 }
@@ -70,6 +84,14 @@ impl SimpleExposure {
         remaining * (self.exposure_time + download_time)
     }
 
+    /// Number of dithers while taking the remaining exposures
+    pub fn dither_count(&self) -> i32 {
+        if !self.enabled || !self.dither || self.dither_every < 1 {
+            return 0;
+        }
+        self.remaining() / self.dither_every
+    }
+
     /// Validate the exposure
     pub fn validate(&self) -> Vec<String> {
         let mut errors = Vec::new();
@@ -91,6 +113,92 @@ impl SimpleExposure {
     }
 }
 
+/// Conditions a target must meet while it is imaged. Unset fields don't
+/// constrain.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ObservingConstraints {
+    /// Degrees
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_altitude: Option<f64>,
+    /// Degrees
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_altitude: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_airmass: Option<f64>,
+    /// Hours, negative east of the meridian
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_hour_angle: Option<f64>,
+    /// Hours, positive west of the meridian
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_hour_angle: Option<f64>,
+    /// Degrees
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_moon_separation: Option<f64>,
+    /// Percent
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_moon_illumination: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub earliest_start: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub latest_start: Option<DateTime<Utc>>,
+}
+
+impl ObservingConstraints {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Check that ranges are ordered and values are in range
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+
+        for (label, value) in [
+            ("Minimum altitude", self.min_altitude),
+            ("Maximum altitude", self.max_altitude),
+        ] {
+            if value.is_some_and(|v| !(-90.0..=90.0).contains(&v)) {
+                errors.push(format!("{} must be between -90° and 90°", label));
+            }
+        }
+        if let (Some(min), Some(max)) = (self.min_altitude, self.max_altitude) {
+            if min > max {
+                errors.push("Minimum altitude is above maximum altitude".to_string());
+            }
+        }
+        if self.max_airmass.is_some_and(|v| v < 1.0) {
+            errors.push("Maximum airmass must be at least 1".to_string());
+        }
+        for (label, value) in [
+            ("Minimum hour angle", self.min_hour_angle),
+            ("Maximum hour angle", self.max_hour_angle),
+        ] {
+            if value.is_some_and(|v| !(-12.0..=12.0).contains(&v)) {
+                errors.push(format!("{} must be between -12h and 12h", label));
+            }
+        }
+        if self
+            .min_moon_separation
+            .is_some_and(|v| !(0.0..=180.0).contains(&v))
+        {
+            errors.push("Minimum moon separation must be between 0° and 180°".to_string());
+        }
+        if self
+            .max_moon_illumination
+            .is_some_and(|v| !(0.0..=100.0).contains(&v))
+        {
+            errors.push("Maximum moon illumination must be between 0% and 100%".to_string());
+        }
+        if let (Some(earliest), Some(latest)) = (self.earliest_start, self.latest_start) {
+            if earliest > latest {
+                errors.push("Earliest start is after latest start".to_string());
+            }
+        }
+
+        errors
+    }
+}
+
 /// Simple target (DSO container)
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -115,6 +223,18 @@ pub struct SimpleTarget {
     pub rotate_target: bool,
     pub start_guiding: bool,
 
+    // Scheduling
+    #[serde(default)]
+    pub priority: TargetPriority,
+    /// Minimum total integration wanted (seconds)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_integration: Option<f64>,
+    /// Integration beyond which the target needs no more time (seconds)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_integration: Option<f64>,
+    #[serde(default, skip_serializing_if = "ObservingConstraints::is_empty")]
+    pub constraints: ObservingConstraints,
+
     // Autofocus options
     pub auto_focus_on_start: bool,
     pub auto_focus_on_filter_change: bool,
@@ -137,6 +257,19 @@ pub struct SimpleTarget {
     pub estimated_end_time: Option<DateTime<Utc>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub estimated_duration: Option<f64>,
+
+    // Non-sidereal target (comet/asteroid)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub moving_target: Option<MovingTarget>,
+
+    // User annotations
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notes: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// Free-form key/value data kept with the target
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub metadata: HashMap<String, serde_json::Value>,
 }
 
 impl Default for SimpleTarget {
@@ -156,6 +289,10 @@ impl Default for SimpleTarget {
             center_target: true,
             rotate_target: false,
             start_guiding: true,
+            priority: TargetPriority::Normal,
+            min_integration: None,
+            max_integration: None,
+            constraints: ObservingConstraints::default(),
             auto_focus_on_start: true,
             auto_focus_on_filter_change: false,
             auto_focus_after_set_time: false,
@@ -170,11 +307,151 @@ impl Default for SimpleTarget {
             estimated_start_time: None,
             estimated_end_time: None,
             estimated_duration: None,
+            moving_target: None,
+            notes: None,
+            tags: Vec::new(),
+            metadata: HashMap::new(),
         }
     }
 }
 
+/// One frame in the order a target's exposures are taken
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExposureStep {
+    /// Index into the target's exposures
+    pub exposure_index: usize,
+    /// Frame number within the exposure row, from 1
+    pub frame: i32,
+    /// A dither follows this frame
+    pub dither_after: bool,
+}
+
+/// When one frame is taken, in seconds from the start of its target
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FrameTiming {
+    /// Index into the target's exposures
+    pub exposure_index: usize,
+    pub start: f64,
+    pub end: f64,
+}
+
 impl SimpleTarget {
+    /// Order in which NINA takes the remaining frames. Standard mode
+    /// finishes each exposure row in turn; rotate mode takes one frame from
+    /// each row per pass. Dithers follow every `dither_every` frames of the
+    /// same row, so in rotate mode they are spread across the passes.
+    pub fn execution_plan(&self) -> Vec<ExposureStep> {
+        let remaining: Vec<i32> = self
+            .exposures
+            .iter()
+            .map(|e| if e.enabled { e.remaining() } else { 0 })
+            .collect();
+
+        let order: Vec<usize> = match self.mode {
+            SequenceMode::Standard => remaining
+                .iter()
+                .enumerate()
+                .flat_map(|(i, &n)| std::iter::repeat(i).take(n.max(0) as usize))
+                .collect(),
+            SequenceMode::Rotate => {
+                let passes = remaining.iter().copied().max().unwrap_or(0);
+                (0..passes)
+                    .flat_map(|pass| {
+                        remaining
+                            .iter()
+                            .enumerate()
+                            .filter(move |(_, &n)| pass < n)
+                            .map(|(i, _)| i)
+                    })
+                    .collect()
+            }
+        };
+
+        let mut taken = vec![0; self.exposures.len()];
+        order
+            .into_iter()
+            .map(|index| {
+                let exposure = &self.exposures[index];
+                taken[index] += 1;
+                ExposureStep {
+                    exposure_index: index,
+                    frame: taken[index],
+                    dither_after: exposure.dither
+                        && exposure.dither_every > 0
+                        && taken[index] % exposure.dither_every == 0,
+                }
+            })
+            .collect()
+    }
+
+    /// Frame timings in execution order and the time at which the target
+    /// is done. Frames start after the target delay; with overheads, the
+    /// autofocus run at start, filter changes with their autofocus runs
+    /// when enabled, and dither settling are spent between frames.
+    fn timeline(
+        &self,
+        download_time: f64,
+        overheads: Option<&OverheadProfile>,
+    ) -> (Vec<FrameTiming>, f64) {
+        let mut frames = Vec::new();
+        let mut offset = self.delay as f64;
+        let mut current_filter = None;
+
+        for (i, step) in self.execution_plan().into_iter().enumerate() {
+            let exposure = &self.exposures[step.exposure_index];
+            let filter = exposure.filter.as_ref().map(|f| f.name.as_str());
+            if let Some(overheads) = overheads {
+                if i == 0 {
+                    if self.auto_focus_on_start {
+                        offset += overheads.autofocus_time(filter);
+                    }
+                } else if filter != current_filter {
+                    offset += overheads.filter_change_time;
+                    if self.auto_focus_on_filter_change {
+                        offset += overheads.autofocus_time(filter);
+                    }
+                }
+            }
+            current_filter = filter;
+
+            let start = offset;
+            offset += exposure.exposure_time + download_time;
+            frames.push(FrameTiming {
+                exposure_index: step.exposure_index,
+                start,
+                end: offset,
+            });
+
+            if step.dither_after {
+                offset += overheads.map_or(0.0, |o| o.dither_settle_time);
+            }
+        }
+        (frames, offset)
+    }
+
+    /// Start and end of each remaining frame in execution order, including
+    /// the dither, filter change and autofocus overheads when given
+    pub fn frame_timings(
+        &self,
+        download_time: f64,
+        overheads: Option<&OverheadProfile>,
+    ) -> Vec<FrameTiming> {
+        self.timeline(download_time, overheads).0
+    }
+
+    /// Runtime in seconds, including the dither, filter change and
+    /// autofocus overheads when given
+    pub fn runtime_with_overheads(
+        &self,
+        download_time: f64,
+        overheads: Option<&OverheadProfile>,
+    ) -> f64 {
+        match overheads {
+            Some(_) => self.timeline(download_time, overheads).1,
+            None => self.runtime(download_time),
+        }
+    }
+
     /// Calculate total runtime in seconds
     pub fn runtime(&self, download_time: f64) -> f64 {
         let mut total = self.delay as f64;
@@ -194,6 +471,30 @@ impl SimpleTarget {
         self.exposures.iter().map(|e| e.remaining()).sum()
     }
 
+    /// Check for a tag, ignoring case
+    pub fn has_tag(&self, tag: &str) -> bool {
+        let tag = tag.trim();
+        self.tags.iter().any(|t| t.trim().eq_ignore_ascii_case(tag))
+    }
+
+    /// Planned integration time of enabled exposures in seconds
+    pub fn integration_time(&self) -> f64 {
+        self.exposures
+            .iter()
+            .filter(|e| e.enabled)
+            .map(|e| e.total_count.max(0) as f64 * e.exposure_time)
+            .sum()
+    }
+
+    /// Integration time already captured in seconds
+    pub fn completed_integration_time(&self) -> f64 {
+        self.exposures
+            .iter()
+            .filter(|e| e.enabled)
+            .map(|e| e.progress_count.max(0) as f64 * e.exposure_time)
+            .sum()
+    }
+
     /// Validate the target
     pub fn validate(&self) -> Vec<String> {
         let mut errors = Vec::new();
@@ -204,6 +505,18 @@ impl SimpleTarget {
 
         errors.extend(self.coordinates.validate());
 
+        if self.min_integration.is_some_and(|v| v < 0.0) {
+            errors.push("Minimum integration cannot be negative".to_string());
+        }
+        if self.max_integration.is_some_and(|v| v <= 0.0) {
+            errors.push("Maximum integration must be positive".to_string());
+        }
+        if let (Some(min), Some(max)) = (self.min_integration, self.max_integration) {
+            if min > max {
+                errors.push("Minimum integration exceeds maximum integration".to_string());
+            }
+        }
+
         for exposure in &self.exposures {
             errors.extend(exposure.validate());
         }
@@ -354,14 +667,35 @@ impl SimpleSequence {
         self.targets.iter_mut().find(|t| t.id == id)
     }
 
-    /// Calculate ETAs for all targets
-    pub fn calculate_etas(&mut self) {
+    /// Calculate ETAs for all targets and their exposure rows, including
+    /// the dither, filter change and autofocus overheads when given. An
+    /// exposure row runs from the start of its first remaining frame to the
+    /// end of its last; rows with nothing left to take get no ETA.
+    pub fn calculate_etas(&mut self, overheads: Option<&OverheadProfile>) {
         let download_time = self.estimated_download_time;
         let mut current_time = Utc::now();
         let mut total_duration = 0.0;
 
         for target in &mut self.targets {
-            let target_duration = target.runtime(download_time);
+            let (frames, end) = target.timeline(download_time, overheads);
+            let target_duration = match overheads {
+                Some(_) => end,
+                None => target.runtime(download_time),
+            };
+
+            for exposure in &mut target.exposures {
+                exposure.estimated_start_time = None;
+                exposure.estimated_end_time = None;
+            }
+            for frame in &frames {
+                let exposure = &mut target.exposures[frame.exposure_index];
+                exposure
+                    .estimated_start_time
+                    .get_or_insert(current_time + chrono::Duration::seconds(frame.start as i64));
+                exposure.estimated_end_time =
+                    Some(current_time + chrono::Duration::seconds(frame.end as i64));
+            }
+
             target.estimated_start_time = Some(current_time);
             target.estimated_duration = Some(target_duration);
             current_time += chrono::Duration::seconds(target_duration as i64);
@@ -394,6 +728,22 @@ impl SimpleSequence {
     }
 }
 
+/// Text form of a metadata value for CSV cells and XML elements. Strings
+/// are written as-is unless they would read back as another JSON value.
+pub fn metadata_value_to_text(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::String(s) if serde_json::from_str::<serde_json::Value>(s).is_err() => {
+            s.clone()
+        }
+        other => other.to_string(),
+    }
+}
+
+/// Inverse of [`metadata_value_to_text`]
+pub fn metadata_value_from_text(text: &str) -> serde_json::Value {
+    serde_json::from_str(text).unwrap_or_else(|_| serde_json::Value::String(text.to_string()))
+}
+
 /// Export format for NINA target set
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "PascalCase")]
@@ -0,0 +1,202 @@
+//! Observing site types
+//!
+//! An observing site is a persisted observer location together with its
+//! sky quality (Bortle class and/or SQM reading) and local horizon profile.
+
+use serde::{Deserialize, Serialize};
+
+/// Typical zenith sky brightness (mag/arcsec²) for Bortle classes 1-9
+const BORTLE_SQM: [f64; 9] = [21.9, 21.6, 21.4, 20.9, 19.9, 19.1, 18.5, 18.0, 17.5];
+
+/// Point on the local horizon profile
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HorizonPoint {
+    /// Azimuth in degrees (0 = North, 90 = East)
+    pub azimuth: f64,
+    /// Obstruction altitude in degrees
+    pub altitude: f64,
+}
+
+/// Observing site
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ObservingSite {
+    pub id: String,
+    pub name: String,
+    pub latitude: f64,
+    pub longitude: f64,
+    /// Elevation in meters
+    pub elevation: f64,
+    /// Hours from UTC
+    #[serde(default)]
+    pub timezone_offset: i32,
+    /// Bortle dark-sky class (1-9)
+    #[serde(default)]
+    pub bortle: Option<u8>,
+    /// Sky quality meter reading (mag/arcsec²)
+    #[serde(default)]
+    pub sqm: Option<f64>,
+    /// Horizon profile, in any azimuth order
+    #[serde(default)]
+    pub horizon: Vec<HorizonPoint>,
+}
+
+impl Default for ObservingSite {
+    fn default() -> Self {
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            name: "Default".to_string(),
+            latitude: 0.0,
+            longitude: 0.0,
+            elevation: 0.0,
+            timezone_offset: 0,
+            bortle: None,
+            sqm: None,
+            horizon: Vec::new(),
+        }
+    }
+}
+
+impl ObservingSite {
+    /// Zenith sky brightness in mag/arcsec²: the SQM reading, or the
+    /// typical value for the Bortle class
+    pub fn sky_brightness(&self) -> Option<f64> {
+        self.sqm.or_else(|| {
+            self.bortle
+                .filter(|b| (1..=9).contains(b))
+                .map(|b| BORTLE_SQM[b as usize - 1])
+        })
+    }
+
+    /// Bortle class: the configured class, or the class estimated from SQM
+    pub fn bortle_class(&self) -> Option<u8> {
+        self.bortle.or_else(|| {
+            self.sqm.map(|sqm| {
+                BORTLE_SQM
+                    .iter()
+                    .position(|&limit| sqm >= limit - 0.15)
+                    .map(|i| i as u8 + 1)
+                    .unwrap_or(9)
+            })
+        })
+    }
+
+    /// Horizon obstruction altitude at an azimuth, linearly interpolated
+    /// between profile points (0 when no profile is defined)
+    pub fn horizon_altitude(&self, azimuth: f64) -> f64 {
+        if self.horizon.is_empty() {
+            return 0.0;
+        }
+
+        let mut points = self.horizon.clone();
+        points.sort_by(|a, b| a.azimuth.total_cmp(&b.azimuth));
+
+        let az = azimuth.rem_euclid(360.0);
+        let after = points.iter().position(|p| p.azimuth >= az);
+        let (p0, p1) = match after {
+            Some(0) | None => (points[points.len() - 1], points[0]),
+            Some(i) => (points[i - 1], points[i]),
+        };
+
+        let span = (p1.azimuth - p0.azimuth).rem_euclid(360.0);
+        if span == 0.0 {
+            return p0.altitude;
+        }
+        let t = (az - p0.azimuth).rem_euclid(360.0) / span;
+        p0.altitude + (p1.altitude - p0.altitude) * t
+    }
+
+    /// Validate the site
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+
+        if self.name.trim().is_empty() {
+            errors.push("Site name is required".to_string());
+        }
+        if !(-90.0..=90.0).contains(&self.latitude) {
+            errors.push("Latitude must be between -90 and 90".to_string());
+        }
+        if !(-180.0..=180.0).contains(&self.longitude) {
+            errors.push("Longitude must be between -180 and 180".to_string());
+        }
+        if let Some(bortle) = self.bortle {
+            if !(1..=9).contains(&bortle) {
+                errors.push("Bortle class must be between 1 and 9".to_string());
+            }
+        }
+        if let Some(sqm) = self.sqm {
+            if !(10.0..=23.0).contains(&sqm) {
+                errors.push("SQM must be between 10 and 23 mag/arcsec²".to_string());
+            }
+        }
+        if self
+            .horizon
+            .iter()
+            .any(|p| !(-90.0..=90.0).contains(&p.altitude))
+        {
+            errors.push("Horizon altitudes must be between -90 and 90".to_string());
+        }
+
+        errors
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sky_brightness_from_bortle_and_sqm() {
+        let backyard = ObservingSite {
+            bortle: Some(7),
+            ..Default::default()
+        };
+        assert_eq!(backyard.sky_brightness(), Some(18.5));
+
+        let dark_site = ObservingSite {
+            sqm: Some(21.8),
+            ..Default::default()
+        };
+        assert_eq!(dark_site.bortle_class(), Some(1));
+        assert_eq!(dark_site.sky_brightness(), Some(21.8));
+        assert!(ObservingSite::default().sky_brightness().is_none());
+    }
+
+    #[test]
+    fn test_horizon_interpolation() {
+        let site = ObservingSite {
+            horizon: vec![
+                HorizonPoint {
+                    azimuth: 90.0,
+                    altitude: 20.0,
+                },
+                HorizonPoint {
+                    azimuth: 0.0,
+                    altitude: 10.0,
+                },
+                HorizonPoint {
+                    azimuth: 270.0,
+                    altitude: 0.0,
+                },
+            ],
+            ..Default::default()
+        };
+
+        assert!((site.horizon_altitude(45.0) - 15.0).abs() < 1e-9);
+        assert!((site.horizon_altitude(315.0) - 5.0).abs() < 1e-9);
+        assert!((site.horizon_altitude(180.0) - 10.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_validate_site() {
+        assert!(ObservingSite::default().validate().is_empty());
+
+        let site = ObservingSite {
+            latitude: 95.0,
+            bortle: Some(10),
+            ..Default::default()
+        };
+        assert_eq!(site.validate().len(), 2);
+    }
+}
@@ -0,0 +1,502 @@
+//! ASCOM Alpaca discovery
+//!
+//! Finds Alpaca servers on the local network and reads the values an
+//! equipment profile or observing site needs from their cameras and
+//! telescopes, so they don't have to be typed in. Devices are only read,
+//! never connected; a device that is not connected reports no values.
+//!
+//! Discovery broadcasts `alpacadiscovery1` to UDP port 32227 and collects
+//! the `{"AlpacaPort": n}` answers. Servers whose broadcasts don't reach
+//! this machine can be listed explicitly.
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::net::{Ipv4Addr, SocketAddr};
+use std::time::Duration;
+use tokio::net::UdpSocket;
+
+use crate::models::{EquipmentProfile, ObservingSite};
+
+/// UDP port Alpaca servers listen on for discovery
+pub const DISCOVERY_PORT: u16 = 32227;
+
+const DISCOVERY_MESSAGE: &[u8] = b"alpacadiscovery1";
+
+/// Time to wait for discovery answers when none is given
+pub const DEFAULT_DISCOVERY_TIMEOUT_MS: u64 = 2000;
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Client id sent with every request
+const CLIENT_ID: u32 = 7423;
+
+static CLIENT: Lazy<reqwest::Client> = Lazy::new(|| {
+    reqwest::Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .build()
+        .unwrap_or_default()
+});
+
+/// Camera values read from an Alpaca camera
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AlpacaCameraInfo {
+    pub sensor_name: Option<String>,
+    /// Microns
+    pub pixel_size_x: Option<f64>,
+    /// Microns
+    pub pixel_size_y: Option<f64>,
+    /// Pixels
+    pub width: Option<u32>,
+    /// Pixels
+    pub height: Option<u32>,
+}
+
+/// Site and optics values read from an Alpaca telescope
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AlpacaMountInfo {
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    /// Meters
+    pub elevation: Option<f64>,
+    /// Millimeters
+    pub focal_length: Option<f64>,
+    /// Millimeters
+    pub aperture: Option<f64>,
+}
+
+/// Device configured on an Alpaca server
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AlpacaDevice {
+    /// Server root, e.g. `http://192.168.1.20:11111`
+    pub server: String,
+    pub device_name: String,
+    /// Alpaca device type, e.g. `Camera` or `Telescope`
+    pub device_type: String,
+    pub device_number: u32,
+    pub unique_id: String,
+    pub camera: Option<AlpacaCameraInfo>,
+    pub mount: Option<AlpacaMountInfo>,
+    /// First error while reading the device's values
+    pub error: Option<String>,
+}
+
+/// Alpaca server and its devices
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AlpacaServer {
+    pub address: String,
+    pub port: u16,
+    pub devices: Vec<AlpacaDevice>,
+    /// Why the device list could not be read
+    pub error: Option<String>,
+}
+
+/// Servers found on the network
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AlpacaDiscoveryResult {
+    pub servers: Vec<AlpacaServer>,
+    pub errors: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ConfiguredDevice {
+    device_name: String,
+    device_type: String,
+    device_number: u32,
+    #[serde(rename = "UniqueID", default)]
+    unique_id: String,
+}
+
+/// Alpaca port from a discovery answer
+pub fn parse_discovery_response(data: &[u8]) -> Option<u16> {
+    #[derive(Deserialize)]
+    struct Answer {
+        #[serde(rename = "AlpacaPort")]
+        alpaca_port: u16,
+    }
+    serde_json::from_slice::<Answer>(data)
+        .ok()
+        .map(|answer| answer.alpaca_port)
+}
+
+/// Unwrap an Alpaca response envelope
+pub fn unwrap_alpaca_value(body: &str) -> Result<Value, String> {
+    #[derive(Deserialize)]
+    #[serde(rename_all = "PascalCase")]
+    struct Envelope {
+        #[serde(default)]
+        value: Value,
+        #[serde(default)]
+        error_number: i64,
+        #[serde(default)]
+        error_message: String,
+    }
+
+    let envelope: Envelope =
+        serde_json::from_str(body).map_err(|e| format!("Unexpected Alpaca response: {}", e))?;
+    if envelope.error_number != 0 {
+        return Err(format!(
+            "Alpaca error {:#x}: {}",
+            envelope.error_number, envelope.error_message
+        ));
+    }
+    Ok(envelope.value)
+}
+
+async fn get_value(url: &str) -> Result<Value, String> {
+    let response = CLIENT
+        .get(url)
+        .query(&[("ClientID", CLIENT_ID)])
+        .send()
+        .await
+        .map_err(|e| format!("Failed to reach {}: {}", url, e))?;
+    let status = response.status();
+    let body = response
+        .text()
+        .await
+        .map_err(|e| format!("Failed to read {}: {}", url, e))?;
+    if !status.is_success() {
+        return Err(format!("{} returned HTTP {}: {}", url, status, body.trim()));
+    }
+    unwrap_alpaca_value(&body)
+}
+
+/// Broadcast a discovery request and collect the answering servers
+async fn broadcast_discovery(timeout: Duration) -> Result<Vec<SocketAddr>, String> {
+    let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0))
+        .await
+        .map_err(|e| format!("Failed to open discovery socket: {}", e))?;
+    socket
+        .set_broadcast(true)
+        .map_err(|e| format!("Failed to enable broadcast: {}", e))?;
+    socket
+        .send_to(DISCOVERY_MESSAGE, (Ipv4Addr::BROADCAST, DISCOVERY_PORT))
+        .await
+        .map_err(|e| format!("Failed to send discovery request: {}", e))?;
+
+    let deadline = tokio::time::Instant::now() + timeout;
+    let mut servers = Vec::new();
+    let mut buffer = [0u8; 1024];
+    while let Ok(received) = tokio::time::timeout_at(deadline, socket.recv_from(&mut buffer)).await
+    {
+        let (length, from) = match received {
+            Ok(received) => received,
+            Err(e) => {
+                log::debug!("Alpaca discovery receive failed: {}", e);
+                continue;
+            }
+        };
+        if let Some(port) = parse_discovery_response(&buffer[..length]) {
+            let server = SocketAddr::new(from.ip(), port);
+            if !servers.contains(&server) {
+                servers.push(server);
+            }
+        }
+    }
+    Ok(servers)
+}
+
+/// Read the named properties, keeping the first error
+async fn read_properties(base: &str, names: &[&str]) -> (Vec<Option<Value>>, Option<String>) {
+    let mut values = Vec::with_capacity(names.len());
+    let mut error = None;
+    for name in names {
+        match get_value(&format!("{}/{}", base, name)).await {
+            Ok(value) => values.push(Some(value)),
+            Err(e) => {
+                error.get_or_insert(e);
+                values.push(None);
+            }
+        }
+    }
+    (values, error)
+}
+
+fn as_f64(value: &Option<Value>) -> Option<f64> {
+    value.as_ref().and_then(Value::as_f64)
+}
+
+async fn read_device(server: &str, configured: ConfiguredDevice) -> AlpacaDevice {
+    let mut device = AlpacaDevice {
+        server: server.to_string(),
+        device_name: configured.device_name,
+        device_type: configured.device_type,
+        device_number: configured.device_number,
+        unique_id: configured.unique_id,
+        camera: None,
+        mount: None,
+        error: None,
+    };
+    let base = format!(
+        "{}/api/v1/{}/{}",
+        server,
+        device.device_type.to_lowercase(),
+        device.device_number
+    );
+
+    match device.device_type.to_lowercase().as_str() {
+        "camera" => {
+            let (values, error) = read_properties(
+                &base,
+                &[
+                    "sensorname",
+                    "pixelsizex",
+                    "pixelsizey",
+                    "cameraxsize",
+                    "cameraysize",
+                ],
+            )
+            .await;
+            device.camera = Some(AlpacaCameraInfo {
+                sensor_name: values[0]
+                    .as_ref()
+                    .and_then(Value::as_str)
+                    .filter(|name| !name.is_empty())
+                    .map(str::to_string),
+                pixel_size_x: as_f64(&values[1]),
+                pixel_size_y: as_f64(&values[2]),
+                width: as_f64(&values[3]).map(|v| v as u32),
+                height: as_f64(&values[4]).map(|v| v as u32),
+            });
+            device.error = error;
+        }
+        "telescope" => {
+            let (values, error) = read_properties(
+                &base,
+                &[
+                    "sitelatitude",
+                    "sitelongitude",
+                    "siteelevation",
+                    "focallength",
+                    "aperturediameter",
+                ],
+            )
+            .await;
+            // Alpaca reports optics in meters
+            device.mount = Some(AlpacaMountInfo {
+                latitude: as_f64(&values[0]),
+                longitude: as_f64(&values[1]),
+                elevation: as_f64(&values[2]),
+                focal_length: as_f64(&values[3]).filter(|v| *v > 0.0).map(|v| v * 1000.0),
+                aperture: as_f64(&values[4]).filter(|v| *v > 0.0).map(|v| v * 1000.0),
+            });
+            device.error = error;
+        }
+        _ => {}
+    }
+    device
+}
+
+async fn read_server(address: SocketAddr) -> AlpacaServer {
+    let root = format!("http://{}", address);
+    let mut server = AlpacaServer {
+        address: address.ip().to_string(),
+        port: address.port(),
+        devices: Vec::new(),
+        error: None,
+    };
+
+    let configured = get_value(&format!("{}/management/v1/configureddevices", root))
+        .await
+        .and_then(|value| {
+            serde_json::from_value::<Vec<ConfiguredDevice>>(value)
+                .map_err(|e| format!("Unexpected device list: {}", e))
+        });
+    match configured {
+        Ok(configured) => {
+            for device in configured {
+                server.devices.push(read_device(&root, device).await);
+            }
+        }
+        Err(e) => server.error = Some(e),
+    }
+    server
+}
+
+/// Find Alpaca servers by broadcast plus the given `host:port` addresses
+/// and read their cameras and telescopes
+pub async fn discover_devices(timeout: Duration, hosts: &[String]) -> AlpacaDiscoveryResult {
+    let mut errors = Vec::new();
+    let mut addresses = match broadcast_discovery(timeout).await {
+        Ok(addresses) => addresses,
+        Err(e) => {
+            errors.push(e);
+            Vec::new()
+        }
+    };
+    for host in hosts {
+        match tokio::net::lookup_host(host.as_str()).await {
+            Ok(mut resolved) => {
+                if let Some(address) = resolved.next().filter(|a| !addresses.contains(a)) {
+                    addresses.push(address);
+                }
+            }
+            Err(e) => errors.push(format!("Failed to resolve {}: {}", host, e)),
+        }
+    }
+
+    let servers = futures::future::join_all(addresses.into_iter().map(read_server)).await;
+    AlpacaDiscoveryResult { servers, errors }
+}
+
+/// Fill the profile's camera or telescope values from a discovered device.
+/// Values the device did not report are left unchanged.
+pub fn apply_device_to_profile(profile: &mut EquipmentProfile, device: &AlpacaDevice) {
+    if let Some(camera) = &device.camera {
+        if profile.camera.name.is_empty() {
+            profile.camera.name = camera
+                .sensor_name
+                .as_ref()
+                .map(|sensor| format!("{} ({})", device.device_name, sensor))
+                .unwrap_or_else(|| device.device_name.clone());
+        }
+        if let Some(pixel_size) = camera.pixel_size_x.or(camera.pixel_size_y) {
+            profile.camera.pixel_size = pixel_size;
+        }
+        if let Some(width) = camera.width {
+            profile.camera.width = width;
+        }
+        if let Some(height) = camera.height {
+            profile.camera.height = height;
+        }
+    }
+    if let Some(mount) = &device.mount {
+        profile.mount.name = device.device_name.clone();
+        if let Some(focal_length) = mount.focal_length {
+            profile.telescope.focal_length = focal_length;
+        }
+        if let Some(aperture) = mount.aperture {
+            profile.telescope.aperture = aperture;
+        }
+    }
+}
+
+/// Observing site at the location reported by a discovered telescope
+pub fn site_from_device(device: &AlpacaDevice) -> Result<ObservingSite, String> {
+    let mount = device
+        .mount
+        .as_ref()
+        .ok_or_else(|| format!("{} is not a telescope", device.device_name))?;
+    let (Some(latitude), Some(longitude)) = (mount.latitude, mount.longitude) else {
+        return Err(format!(
+            "{} did not report its site location{}",
+            device.device_name,
+            device
+                .error
+                .as_ref()
+                .map(|e| format!(": {}", e))
+                .unwrap_or_default()
+        ));
+    };
+
+    Ok(ObservingSite {
+        name: device.device_name.clone(),
+        latitude,
+        longitude,
+        elevation: mount.elevation.unwrap_or(0.0),
+        // Whole hours from the longitude until the user sets the zone
+        timezone_offset: (longitude / 15.0).round() as i32,
+        ..Default::default()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn device(camera: Option<AlpacaCameraInfo>, mount: Option<AlpacaMountInfo>) -> AlpacaDevice {
+        AlpacaDevice {
+            server: "http://10.0.0.2:11111".to_string(),
+            device_name: "Observatory".to_string(),
+            device_type: "Camera".to_string(),
+            device_number: 0,
+            unique_id: String::new(),
+            camera,
+            mount,
+            error: None,
+        }
+    }
+
+    #[test]
+    fn test_parse_responses() {
+        assert_eq!(
+            parse_discovery_response(br#"{"AlpacaPort": 11111}"#),
+            Some(11111)
+        );
+        assert_eq!(parse_discovery_response(b"alpacadiscovery1"), None);
+
+        let value = unwrap_alpaca_value(
+            r#"{"Value": 3.76, "ClientTransactionID": 0, "ServerTransactionID": 1, "ErrorNumber": 0, "ErrorMessage": ""}"#,
+        )
+        .unwrap();
+        assert_eq!(value.as_f64(), Some(3.76));
+        let error =
+            unwrap_alpaca_value(r#"{"ErrorNumber": 1031, "ErrorMessage": "Not connected"}"#)
+                .unwrap_err();
+        assert!(error.contains("0x407") && error.contains("Not connected"));
+
+        let devices: Vec<ConfiguredDevice> = serde_json::from_str(
+            r#"[{"DeviceName": "Sim Camera", "DeviceType": "Camera", "DeviceNumber": 0, "UniqueID": "abc"}]"#,
+        )
+        .unwrap();
+        assert_eq!(devices[0].device_type, "Camera");
+        assert_eq!(devices[0].unique_id, "abc");
+    }
+
+    #[test]
+    fn test_apply_device_to_profile() {
+        let mut profile = EquipmentProfile::default();
+        profile.camera.width = 1000;
+        let camera = device(
+            Some(AlpacaCameraInfo {
+                sensor_name: Some("IMX571".to_string()),
+                pixel_size_x: Some(3.76),
+                width: Some(6248),
+                ..Default::default()
+            }),
+            None,
+        );
+        apply_device_to_profile(&mut profile, &camera);
+        assert_eq!(profile.camera.pixel_size, 3.76);
+        assert_eq!(profile.camera.width, 6248);
+        assert!(profile.camera.name.contains("IMX571"));
+
+        let telescope = device(
+            None,
+            Some(AlpacaMountInfo {
+                focal_length: Some(530.0),
+                ..Default::default()
+            }),
+        );
+        let height = profile.camera.height;
+        apply_device_to_profile(&mut profile, &telescope);
+        assert_eq!(profile.telescope.focal_length, 530.0);
+        assert_eq!(profile.camera.height, height);
+    }
+
+    #[test]
+    fn test_site_from_device() {
+        let telescope = device(
+            None,
+            Some(AlpacaMountInfo {
+                latitude: Some(48.2),
+                longitude: Some(16.4),
+                elevation: Some(180.0),
+                ..Default::default()
+            }),
+        );
+        let site = site_from_device(&telescope).unwrap();
+        assert_eq!(site.latitude, 48.2);
+        assert_eq!(site.elevation, 180.0);
+        assert_eq!(site.timezone_offset, 1);
+
+        assert!(site_from_device(&device(None, Some(AlpacaMountInfo::default()))).is_err());
+        assert!(site_from_device(&device(None, None)).is_err());
+    }
+}
@@ -0,0 +1,280 @@
+//! Altitude curve service
+//!
+//! Samples altitude, azimuth, airmass and moon separation for a target
+//! over a night, and memoizes the results in an LRU cache keyed by
+//! (target, location, date, interval) so charts can be redrawn cheaply.
+
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+
+use crate::models::coordinates::angular_separation;
+use crate::models::Coordinates;
+use crate::services::astronomy::{
+    air_mass, datetime_to_jd, moon_position, observed_alt_az, ObserverLocation,
+};
+use crate::services::dark_calendar::night_start;
+use crate::state::AppState;
+
+/// Maximum number of cached curves
+const CACHE_CAPACITY: usize = 128;
+
+/// Sampled curve, stored as parallel arrays
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AltitudeCurve {
+    pub start_time: DateTime<Utc>,
+    pub interval_minutes: u32,
+    /// Sample times as Unix timestamps in milliseconds
+    pub timestamps: Vec<i64>,
+    pub altitudes: Vec<f64>,
+    pub azimuths: Vec<f64>,
+    /// Airmass, None when below the horizon
+    pub airmass: Vec<Option<f64>>,
+    /// Angular distance to the Moon in degrees
+    pub moon_separation: Vec<f64>,
+}
+
+/// Cache statistics
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CurveCacheStats {
+    pub entries: usize,
+    pub capacity: usize,
+    pub hits: u64,
+    pub misses: u64,
+}
+
+// ============================================================================
+// LRU Cache
+// ============================================================================
+
+/// Most recently used altitude curves, part of the
+/// [`AppState`](crate::state::AppState)
+#[derive(Default)]
+pub struct CurveCache {
+    entries: HashMap<String, Arc<AltitudeCurve>>,
+    order: VecDeque<String>,
+    hits: u64,
+    misses: u64,
+}
+
+impl CurveCache {
+    fn get(&mut self, key: &str) -> Option<Arc<AltitudeCurve>> {
+        let curve = self.entries.get(key).cloned();
+        match curve {
+            Some(_) => {
+                self.hits += 1;
+                self.touch(key);
+            }
+            None => self.misses += 1,
+        }
+        curve
+    }
+
+    fn insert(&mut self, key: String, curve: Arc<AltitudeCurve>) {
+        if self.entries.insert(key.clone(), curve).is_some() {
+            self.touch(&key);
+            return;
+        }
+
+        self.order.push_back(key);
+        while self.order.len() > CACHE_CAPACITY {
+            if let Some(oldest) = self.order.pop_front() {
+                self.entries.remove(&oldest);
+            }
+        }
+    }
+
+    fn touch(&mut self, key: &str) {
+        if let Some(pos) = self.order.iter().position(|k| k == key) {
+            if let Some(k) = self.order.remove(pos) {
+                self.order.push_back(k);
+            }
+        }
+    }
+}
+
+fn cache_key(
+    coords: &Coordinates,
+    location: &ObserverLocation,
+    date: NaiveDate,
+    interval_minutes: u32,
+) -> String {
+    format!(
+        "{:.6}|{:.6}|{:.5}|{:.5}|{}|{}|{}|{:.1}|{}|{}|{:.1}|{:.1}",
+        coords.ra_to_decimal(),
+        coords.dec_to_decimal(),
+        location.latitude,
+        location.longitude,
+        location.timezone_offset,
+        date,
+        interval_minutes,
+        location.elevation,
+        location.corrections.refraction,
+        location.corrections.horizon_dip,
+        location.corrections.pressure_hpa,
+        location.corrections.temperature_c
+    )
+}
+
+// ============================================================================
+// Sampling
+// ============================================================================
+
+/// Sample a curve over 24 hours starting at local noon of `date`, so the
+/// night is centered in the chart
+pub fn sample_altitude_curve(
+    coords: &Coordinates,
+    location: &ObserverLocation,
+    date: NaiveDate,
+    interval_minutes: u32,
+) -> AltitudeCurve {
+    let interval = interval_minutes.clamp(1, 240);
+    let start_time = night_start(location, date);
+
+    let ra = coords.ra_to_decimal();
+    let dec = coords.dec_to_decimal();
+    let samples = (24 * 60 / interval) as usize + 1;
+
+    let mut curve = AltitudeCurve {
+        start_time,
+        interval_minutes: interval,
+        timestamps: Vec::with_capacity(samples),
+        altitudes: Vec::with_capacity(samples),
+        azimuths: Vec::with_capacity(samples),
+        airmass: Vec::with_capacity(samples),
+        moon_separation: Vec::with_capacity(samples),
+    };
+
+    for i in 0..samples {
+        let time = start_time + Duration::minutes(i as i64 * interval as i64);
+        let jd = datetime_to_jd(time);
+        let (alt, az) = observed_alt_az(ra, dec, location, jd);
+        let (moon_ra, moon_dec, _) = moon_position(jd);
+
+        curve.timestamps.push(time.timestamp_millis());
+        curve.altitudes.push(alt);
+        curve.azimuths.push(az);
+        curve.airmass.push(air_mass(alt));
+        curve.moon_separation.push(angular_separation(
+            coords,
+            &Coordinates::from_decimal(moon_ra, moon_dec),
+        ));
+    }
+
+    curve
+}
+
+/// Get a curve from the cache, sampling it on a miss
+pub fn get_altitude_curve(
+    state: &AppState,
+    coords: &Coordinates,
+    location: &ObserverLocation,
+    date: NaiveDate,
+    interval_minutes: u32,
+) -> Arc<AltitudeCurve> {
+    let key = cache_key(coords, location, date, interval_minutes);
+
+    if let Some(curve) = state.altitude_curves.write().get(&key) {
+        return curve;
+    }
+
+    let curve = Arc::new(sample_altitude_curve(
+        coords,
+        location,
+        date,
+        interval_minutes,
+    ));
+    state.altitude_curves.write().insert(key, curve.clone());
+    curve
+}
+
+/// Clear the curve cache
+pub fn clear_curve_cache(state: &AppState) {
+    let mut cache = state.altitude_curves.write();
+    cache.entries.clear();
+    cache.order.clear();
+}
+
+/// Get cache statistics
+pub fn get_curve_cache_stats(state: &AppState) -> CurveCacheStats {
+    let cache = state.altitude_curves.read();
+    CurveCacheStats {
+        entries: cache.entries.len(),
+        capacity: CACHE_CAPACITY,
+        hits: cache.hits,
+        misses: cache.misses,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_location() -> ObserverLocation {
+        ObserverLocation {
+            latitude: 40.0,
+            longitude: -74.0,
+            elevation: 0.0,
+            timezone_offset: -5,
+            sky_brightness: None,
+            corrections: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_sample_altitude_curve() {
+        let coords = Coordinates::from_decimal(0.712, 41.27);
+        let date = NaiveDate::from_ymd_opt(2024, 10, 1).unwrap();
+
+        let curve = sample_altitude_curve(&coords, &test_location(), date, 15);
+
+        assert_eq!(curve.timestamps.len(), 97);
+        assert_eq!(curve.altitudes.len(), curve.timestamps.len());
+        assert_eq!(curve.moon_separation.len(), curve.timestamps.len());
+        assert_eq!(curve.timestamps[1] - curve.timestamps[0], 15 * 60 * 1000);
+        assert!(curve
+            .altitudes
+            .iter()
+            .zip(&curve.airmass)
+            .all(|(alt, am)| (*alt > 0.0) == am.is_some()));
+    }
+
+    #[test]
+    fn test_get_altitude_curve_cached() {
+        let coords = Coordinates::from_decimal(5.588, -5.39);
+        let date = NaiveDate::from_ymd_opt(2024, 1, 10).unwrap();
+        let state = AppState::default();
+
+        let first = get_altitude_curve(&state, &coords, &test_location(), date, 7);
+        let second = get_altitude_curve(&state, &coords, &test_location(), date, 7);
+
+        assert!(Arc::ptr_eq(&first, &second));
+    }
+
+    #[test]
+    fn test_curve_cache_eviction() {
+        let mut cache = CurveCache::default();
+        let curve = Arc::new(sample_altitude_curve(
+            &Coordinates::default(),
+            &test_location(),
+            NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(),
+            240,
+        ));
+
+        for i in 0..=CACHE_CAPACITY {
+            cache.insert(i.to_string(), curve.clone());
+            if i == 0 {
+                continue;
+            }
+            // Keep the first entry hot so it survives eviction
+            assert!(cache.get("0").is_some());
+        }
+
+        assert_eq!(cache.entries.len(), CACHE_CAPACITY);
+        assert!(cache.get("0").is_some());
+        assert!(cache.get("1").is_none());
+    }
+}
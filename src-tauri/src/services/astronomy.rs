@@ -5,21 +5,40 @@
 //! - Optimal observation times
 //! - Sun/Moon positions
 //! - Twilight calculations
+//! - Precession and apparent place (J2000 / JNow)
+//! - Refraction and horizon dip corrections
 
 use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveTime, Timelike, Utc};
 use serde::{Deserialize, Serialize};
 use std::f64::consts::PI;
+use validator::Validate;
 
-use crate::models::Coordinates;
+use crate::models::{CoordinateEpoch, Coordinates, ObservingSite};
+use crate::services::dark_calendar;
 
 /// Observer location
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
 #[serde(rename_all = "camelCase")]
 pub struct ObserverLocation {
+    #[validate(range(min = -90.0, max = 90.0, message = "must be between -90 and 90 degrees"))]
     pub latitude: f64,
+    #[validate(range(min = -180.0, max = 180.0, message = "must be between -180 and 180 degrees"))]
     pub longitude: f64,
-    pub elevation: f64,       // meters
+    #[validate(range(min = -500.0, max = 9000.0, message = "must be between -500 and 9000 meters"))]
+    pub elevation: f64, // meters
+    #[validate(range(min = -12, max = 14, message = "must be between -12 and 14 hours"))]
     pub timezone_offset: i32, // hours from UTC
+    /// Zenith sky brightness in mag/arcsec² (SQM), if known
+    #[serde(default)]
+    #[validate(range(
+        min = 10.0,
+        max = 23.0,
+        message = "must be between 10 and 23 mag/arcsec²"
+    ))]
+    pub sky_brightness: Option<f64>,
+    /// Refraction and horizon dip applied to observed altitudes
+    #[serde(default)]
+    pub corrections: AltitudeCorrections,
 }
 
 impl Default for ObserverLocation {
@@ -29,10 +48,65 @@ impl Default for ObserverLocation {
             longitude: 0.0,
             elevation: 0.0,
             timezone_offset: 0,
+            sky_brightness: None,
+            corrections: AltitudeCorrections::default(),
+        }
+    }
+}
+
+impl From<&ObservingSite> for ObserverLocation {
+    fn from(site: &ObservingSite) -> Self {
+        Self {
+            latitude: site.latitude,
+            longitude: site.longitude,
+            elevation: site.elevation,
+            timezone_offset: site.timezone_offset,
+            sky_brightness: site.sky_brightness(),
+            corrections: AltitudeCorrections::default(),
         }
     }
 }
 
+/// Corrections turning geometric altitudes into what an observer sees
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct AltitudeCorrections {
+    /// Apply atmospheric refraction
+    pub refraction: bool,
+    /// Measure altitudes from the visible horizon, lowered by the
+    /// observer's elevation
+    pub horizon_dip: bool,
+    /// Atmospheric pressure in hPa
+    pub pressure_hpa: f64,
+    /// Air temperature in °C
+    pub temperature_c: f64,
+}
+
+impl Default for AltitudeCorrections {
+    fn default() -> Self {
+        Self {
+            refraction: false,
+            horizon_dip: false,
+            pressure_hpa: 1010.0,
+            temperature_c: 10.0,
+        }
+    }
+}
+
+impl AltitudeCorrections {
+    /// Observed altitude for a geometric altitude at the given elevation
+    pub fn apply(&self, altitude: f64, elevation: f64) -> f64 {
+        let mut observed = altitude;
+        if self.refraction {
+            observed += refraction(altitude, self.pressure_hpa, self.temperature_c);
+        }
+        if self.horizon_dip {
+            observed += horizon_dip(elevation);
+        }
+        observed
+    }
+}
+
 /// Visibility window for a target
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -45,6 +119,23 @@ pub struct VisibilityWindow {
     pub is_visible: bool,
 }
 
+/// Rise, transit and set of a target over one night
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RiseSetTimes {
+    /// First time the target climbs above the limit, if it does
+    pub rise: Option<DateTime<Utc>>,
+    /// First time the target drops below the limit, if it does
+    pub set: Option<DateTime<Utc>>,
+    /// Highest point of the target
+    pub transit: DateTime<Utc>,
+    pub transit_altitude: f64,
+    /// Above the limit the whole time
+    pub always_up: bool,
+    /// Never above the limit
+    pub never_up: bool,
+}
+
 /// Sun/Moon position
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -93,6 +184,8 @@ pub struct ObservationQuality {
     pub altitude_score: f64,
     pub moon_score: f64,
     pub twilight_score: f64,
+    /// Sky brightness multiplier (1.0 for pristine or unknown skies)
+    pub sky_factor: f64,
     pub recommendations: Vec<String>,
 }
 
@@ -118,6 +211,9 @@ const DEG_TO_RAD: f64 = PI / 180.0;
 const RAD_TO_DEG: f64 = 180.0 / PI;
 const J2000: f64 = 2451545.0;
 const SYNODIC_MONTH: f64 = 29.530588853;
+const AU_KM: f64 = 149_597_870.7;
+/// Mean apparent semidiameter of the Sun in degrees
+const SUN_SEMIDIAMETER: f64 = 0.2667;
 
 // ============================================================================
 // Julian Date Calculations
@@ -233,6 +329,103 @@ pub fn ra_dec_to_alt_az(
     (altitude, azimuth)
 }
 
+/// Calculate altitude and azimuth as seen from `location`, with its
+/// refraction and horizon dip corrections applied
+pub fn observed_alt_az(
+    ra_hours: f64,
+    dec_degrees: f64,
+    location: &ObserverLocation,
+    jd: f64,
+) -> (f64, f64) {
+    let (alt, az) = ra_dec_to_alt_az(
+        ra_hours,
+        dec_degrees,
+        location.latitude,
+        location.longitude,
+        jd,
+    );
+    (location.corrections.apply(alt, location.elevation), az)
+}
+
+/// Sidereal time gained per day, in degrees
+const SIDEREAL_DEGREES_PER_DAY: f64 = 360.98564736629;
+
+/// Samples [`altitude_curve`] advances together
+const CURVE_LANES: usize = 8;
+
+/// Observed altitudes of a fixed RA/Dec at `count` times `step_days` apart
+/// from `jd_start`.
+///
+/// Sidereal time is taken as linear over the span, so the hour angle
+/// advances by a fixed rotation per sample instead of fresh trig calls,
+/// and azimuth is never computed. Samples run in lanes of
+/// [`CURVE_LANES`] so the per-lane arithmetic vectorizes.
+pub fn altitude_curve(
+    ra_hours: f64,
+    dec_degrees: f64,
+    location: &ObserverLocation,
+    jd_start: f64,
+    step_days: f64,
+    count: usize,
+) -> Vec<f64> {
+    let dec = dec_degrees.to_radians();
+    let lat = location.latitude.to_radians();
+    let sin_part = lat.sin() * dec.sin();
+    let cos_part = lat.cos() * dec.cos();
+
+    let ha_start = (lst(jd_start, location.longitude) - ra_hours * 15.0).to_radians();
+    let ha_step = (SIDEREAL_DEGREES_PER_DAY * step_days).to_radians();
+    let mut cos_ha: [f64; CURVE_LANES] =
+        std::array::from_fn(|lane| (ha_start + lane as f64 * ha_step).cos());
+    let mut sin_ha: [f64; CURVE_LANES] =
+        std::array::from_fn(|lane| (ha_start + lane as f64 * ha_step).sin());
+    let stride = CURVE_LANES as f64 * ha_step;
+    let (stride_sin, stride_cos) = stride.sin_cos();
+
+    let mut altitudes = Vec::with_capacity(count);
+    while altitudes.len() < count {
+        let mut sin_alt = [0.0; CURVE_LANES];
+        for ((lane_sin_alt, cos_ha), sin_ha) in sin_alt.iter_mut().zip(&mut cos_ha).zip(&mut sin_ha)
+        {
+            *lane_sin_alt = (sin_part + cos_part * *cos_ha).clamp(-1.0, 1.0);
+            let cos = *cos_ha;
+            *cos_ha = cos * stride_cos - *sin_ha * stride_sin;
+            *sin_ha = *sin_ha * stride_cos + cos * stride_sin;
+        }
+        let take = (count - altitudes.len()).min(CURVE_LANES);
+        altitudes.extend(sin_alt[..take].iter().map(|sin_alt| {
+            location
+                .corrections
+                .apply(sin_alt.asin().to_degrees(), location.elevation)
+        }));
+    }
+
+    altitudes
+}
+
+/// Atmospheric refraction in degrees for a geometric altitude
+/// (Sæmundsson), scaled for pressure and temperature
+pub fn refraction(altitude: f64, pressure_hpa: f64, temperature_c: f64) -> f64 {
+    // The formula diverges well below the horizon; hold the horizon value
+    let h = altitude.max(-1.0);
+    let minutes = 1.02 / (h + 10.3 / (h + 5.11)).to_radians().tan();
+    minutes / 60.0 * (pressure_hpa / 1010.0) * (283.0 / (273.0 + temperature_c))
+}
+
+/// Atmospheric refraction in degrees for an apparent altitude (Bennett),
+/// scaled for pressure and temperature
+pub fn refraction_from_apparent(altitude: f64, pressure_hpa: f64, temperature_c: f64) -> f64 {
+    let h = altitude.max(-1.0);
+    let minutes = 1.0 / (h + 7.31 / (h + 4.4)).to_radians().tan();
+    minutes / 60.0 * (pressure_hpa / 1010.0) * (283.0 / (273.0 + temperature_c))
+}
+
+/// Dip of the visible horizon in degrees for an observer `elevation`
+/// meters above it
+pub fn horizon_dip(elevation: f64) -> f64 {
+    0.0293 * elevation.max(0.0).sqrt()
+}
+
 /// Calculate hour angle
 pub fn hour_angle(ra_hours: f64, longitude: f64, jd: f64) -> f64 {
     let lst_deg = lst(jd, longitude);
@@ -264,10 +457,7 @@ pub fn air_mass(altitude: f64) -> Option<f64> {
 /// Calculate Sun position
 pub fn sun_position(jd: f64) -> (f64, f64) {
     let n = jd - J2000;
-    let l = (280.460 + 0.9856474 * n).rem_euclid(360.0);
-    let g = (357.528 + 0.9856003 * n).rem_euclid(360.0).to_radians();
-
-    let lambda = l + 1.915 * g.sin() + 0.020 * (2.0 * g).sin();
+    let lambda = sun_ecliptic_longitude(jd);
     let epsilon = 23.439 - 0.0000004 * n;
 
     let lambda_rad = lambda.to_radians();
@@ -281,74 +471,476 @@ pub fn sun_position(jd: f64) -> (f64, f64) {
     (ra_hours, dec.to_degrees())
 }
 
+/// Sun ecliptic longitude in degrees
+pub(crate) fn sun_ecliptic_longitude(jd: f64) -> f64 {
+    let n = jd - J2000;
+    let l = (280.460 + 0.9856474 * n).rem_euclid(360.0);
+    let g = (357.528 + 0.9856003 * n).rem_euclid(360.0).to_radians();
+
+    l + 1.915 * g.sin() + 0.020 * (2.0 * g).sin()
+}
+
+/// Earth-Sun distance in AU
+pub(crate) fn sun_distance(jd: f64) -> f64 {
+    let g = (357.528 + 0.9856003 * (jd - J2000))
+        .rem_euclid(360.0)
+        .to_radians();
+    1.00014 - 0.01671 * g.cos() - 0.00014 * (2.0 * g).cos()
+}
+
 /// Calculate Sun altitude at given location and time
 pub fn sun_altitude(location: &ObserverLocation, jd: f64) -> f64 {
     let (ra, dec) = sun_position(jd);
-    let (alt, _) = ra_dec_to_alt_az(ra, dec, location.latitude, location.longitude, jd);
+    let (alt, _) = observed_alt_az(ra, dec, location, jd);
     alt
 }
 
+// ============================================================================
+// Precession and Apparent Place
+// ============================================================================
+
+/// Convert a Julian epoch year (e.g. 2000.0) to a Julian Date
+pub fn epoch_year_to_jd(year: f64) -> f64 {
+    J2000 + (year - 2000.0) * 365.25
+}
+
+/// Precess RA (hours) / Dec (degrees) between two equinoxes (Meeus 21.3)
+fn precess(ra_hours: f64, dec: f64, from_jd: f64, to_jd: f64) -> (f64, f64) {
+    let big_t = (from_jd - J2000) / 36525.0;
+    let t = (to_jd - from_jd) / 36525.0;
+
+    let base = 2306.2181 + 1.39656 * big_t - 0.000139 * big_t * big_t;
+    let zeta = base * t + (0.30188 - 0.000344 * big_t) * t * t + 0.017998 * t.powi(3);
+    let z = base * t + (1.09468 + 0.000066 * big_t) * t * t + 0.018203 * t.powi(3);
+    let theta = (2004.3109 - 0.85330 * big_t - 0.000217 * big_t * big_t) * t
+        - (0.42665 + 0.000217 * big_t) * t * t
+        - 0.041833 * t.powi(3);
+
+    let (zeta, z, theta) = (
+        (zeta / 3600.0) * DEG_TO_RAD,
+        (z / 3600.0) * DEG_TO_RAD,
+        (theta / 3600.0) * DEG_TO_RAD,
+    );
+    let ra = ra_hours * 15.0 * DEG_TO_RAD;
+    let dec = dec * DEG_TO_RAD;
+
+    let a = dec.cos() * (ra + zeta).sin();
+    let b = theta.cos() * dec.cos() * (ra + zeta).cos() - theta.sin() * dec.sin();
+    let c = theta.sin() * dec.cos() * (ra + zeta).cos() + theta.cos() * dec.sin();
+
+    let new_ra = (a.atan2(b) + z) * RAD_TO_DEG / 15.0;
+    let new_dec = c.clamp(-1.0, 1.0).asin() * RAD_TO_DEG;
+
+    (new_ra.rem_euclid(24.0), new_dec)
+}
+
+/// Mean obliquity of the ecliptic in degrees (Meeus 22.2, low precision)
+fn mean_obliquity(jd: f64) -> f64 {
+    let t = (jd - J2000) / 36525.0;
+    23.439291 - 0.0130042 * t
+}
+
+/// Nutation in longitude and obliquity in arcseconds (Meeus 22, low precision)
+fn nutation(jd: f64) -> (f64, f64) {
+    let t = (jd - J2000) / 36525.0;
+
+    let omega = (125.04452 - 1934.136261 * t) * DEG_TO_RAD;
+    let l_sun = (280.4665 + 36000.7698 * t) * DEG_TO_RAD;
+    let l_moon = (218.3165 + 481267.8813 * t) * DEG_TO_RAD;
+    let d_psi = -17.20 * omega.sin() - 1.32 * (2.0 * l_sun).sin() - 0.23 * (2.0 * l_moon).sin()
+        + 0.21 * (2.0 * omega).sin();
+    let d_eps = 9.20 * omega.cos() + 0.57 * (2.0 * l_sun).cos() + 0.10 * (2.0 * l_moon).cos()
+        - 0.09 * (2.0 * omega).cos();
+
+    (d_psi, d_eps)
+}
+
+/// Nutation and aberration corrections (RA, Dec) in degrees at the given
+/// position referred to the equinox of date (Meeus 22 and 23.3, low precision)
+fn apparent_corrections(ra_hours: f64, dec: f64, jd: f64) -> (f64, f64) {
+    let t = (jd - J2000) / 36525.0;
+
+    let (d_psi, d_eps) = nutation(jd);
+    let eps = (mean_obliquity(jd) + d_eps / 3600.0) * DEG_TO_RAD;
+
+    let ra = ra_hours * 15.0 * DEG_TO_RAD;
+    let dec = dec * DEG_TO_RAD;
+    let tan_dec = dec.tan();
+
+    let nut_ra = (eps.cos() + eps.sin() * ra.sin() * tan_dec) * d_psi - ra.cos() * tan_dec * d_eps;
+    let nut_dec = eps.sin() * ra.cos() * d_psi + ra.sin() * d_eps;
+
+    // Annual aberration (arcseconds)
+    let kappa = 20.49552;
+    let e = 0.016708634 - 0.000042037 * t;
+    let pi = (102.93735 + 1.71946 * t) * DEG_TO_RAD;
+    let sun = sun_ecliptic_longitude(jd) * DEG_TO_RAD;
+
+    let ab_ra = (-kappa * (ra.cos() * sun.cos() * eps.cos() + ra.sin() * sun.sin())
+        + e * kappa * (ra.cos() * pi.cos() * eps.cos() + ra.sin() * pi.sin()))
+        / dec.cos();
+    let term = eps.tan() * dec.cos() - ra.sin() * dec.sin();
+    let ab_dec = -kappa * (sun.cos() * eps.cos() * term + ra.cos() * dec.sin() * sun.sin())
+        + e * kappa * (pi.cos() * eps.cos() * term + ra.cos() * dec.sin() * pi.sin());
+
+    ((nut_ra + ab_ra) / 3600.0, (nut_dec + ab_dec) / 3600.0)
+}
+
+/// Precess mean coordinates from one equinox to another. The result is
+/// tagged J2000 when the target equinox is J2000.0, mean of date
+/// otherwise.
+pub fn precess_coordinates(coords: &Coordinates, from_jd: f64, to_jd: f64) -> Coordinates {
+    let (ra, dec) = precess(
+        coords.ra_to_decimal(),
+        coords.dec_to_decimal(),
+        from_jd,
+        to_jd,
+    );
+
+    let mut result = Coordinates::from_decimal(ra, dec);
+    if (to_jd - J2000).abs() > 1e-6 {
+        result.epoch = CoordinateEpoch::MeanOfDate;
+    }
+    result
+}
+
+/// Mean coordinates of date at `jd` as (RA hours, Dec degrees). Mean of
+/// date coordinates are taken to be referred to the equinox of `jd`.
+fn mean_of_date(coords: &Coordinates, jd: f64) -> (f64, f64) {
+    let ra = coords.ra_to_decimal();
+    let dec = coords.dec_to_decimal();
+    match coords.epoch {
+        CoordinateEpoch::J2000 => precess(ra, dec, J2000, jd),
+        CoordinateEpoch::MeanOfDate => (ra, dec),
+        CoordinateEpoch::JNow => {
+            let (d_ra, d_dec) = apparent_corrections(ra, dec, jd);
+            (
+                (ra - d_ra / 15.0).rem_euclid(24.0),
+                (dec - d_dec).clamp(-90.0, 90.0),
+            )
+        }
+    }
+}
+
+/// Apparent (JNow) coordinates at `jd`, adding precession, nutation and
+/// annual aberration to J2000 coordinates and nutation and aberration to
+/// mean coordinates of date
+pub fn apparent_coordinates(coords: &Coordinates, jd: f64) -> Coordinates {
+    if coords.epoch == CoordinateEpoch::JNow {
+        return coords.clone();
+    }
+
+    let (ra, dec) = mean_of_date(coords, jd);
+    let (d_ra, d_dec) = apparent_corrections(ra, dec, jd);
+
+    let mut result = Coordinates::from_decimal(
+        (ra + d_ra / 15.0).rem_euclid(24.0),
+        (dec + d_dec).clamp(-90.0, 90.0),
+    );
+    result.epoch = CoordinateEpoch::JNow;
+    result
+}
+
+/// J2000 coordinates for apparent (JNow) or mean of date coordinates at
+/// `jd`
+pub fn mean_coordinates_j2000(coords: &Coordinates, jd: f64) -> Coordinates {
+    if coords.epoch == CoordinateEpoch::J2000 {
+        return coords.clone();
+    }
+
+    let (ra, dec) = mean_of_date(coords, jd);
+    let (ra, dec) = precess(ra, dec, jd, J2000);
+    Coordinates::from_decimal(ra, dec)
+}
+
+/// Convert coordinates to the given epoch, using `jd` as the date for JNow
+/// and mean of date
+pub fn convert_coordinates_epoch(
+    coords: &Coordinates,
+    epoch: CoordinateEpoch,
+    jd: f64,
+) -> Coordinates {
+    match epoch {
+        CoordinateEpoch::J2000 => mean_coordinates_j2000(coords, jd),
+        CoordinateEpoch::JNow => apparent_coordinates(coords, jd),
+        CoordinateEpoch::MeanOfDate => {
+            let (ra, dec) = mean_of_date(coords, jd);
+            let mut result = Coordinates::from_decimal(ra, dec);
+            result.epoch = CoordinateEpoch::MeanOfDate;
+            result
+        }
+    }
+}
+
 // ============================================================================
 // Moon Position
 // ============================================================================
 
-/// Calculate Moon position (simplified)
-pub fn moon_position(jd: f64) -> (f64, f64, f64) {
+/// Periodic terms for lunar longitude and distance (Meeus table 47.A):
+/// multiples of D, M, M', F, then Σl (1e-6 degrees) and Σr (1e-3 km)
+const MOON_LR_TERMS: [(i8, i8, i8, i8, f64, f64); 60] = [
+    (0, 0, 1, 0, 6288774.0, -20905355.0),
+    (2, 0, -1, 0, 1274027.0, -3699111.0),
+    (2, 0, 0, 0, 658314.0, -2955968.0),
+    (0, 0, 2, 0, 213618.0, -569925.0),
+    (0, 1, 0, 0, -185116.0, 48888.0),
+    (0, 0, 0, 2, -114332.0, -3149.0),
+    (2, 0, -2, 0, 58793.0, 246158.0),
+    (2, -1, -1, 0, 57066.0, -152138.0),
+    (2, 0, 1, 0, 53322.0, -170733.0),
+    (2, -1, 0, 0, 45758.0, -204586.0),
+    (0, 1, -1, 0, -40923.0, -129620.0),
+    (1, 0, 0, 0, -34720.0, 108743.0),
+    (0, 1, 1, 0, -30383.0, 104755.0),
+    (2, 0, 0, -2, 15327.0, 10321.0),
+    (0, 0, 1, 2, -12528.0, 0.0),
+    (0, 0, 1, -2, 10980.0, 79661.0),
+    (4, 0, -1, 0, 10675.0, -34782.0),
+    (0, 0, 3, 0, 10034.0, -23210.0),
+    (4, 0, -2, 0, 8548.0, -21636.0),
+    (2, 1, -1, 0, -7888.0, 24208.0),
+    (2, 1, 0, 0, -6766.0, 30824.0),
+    (1, 0, -1, 0, -5163.0, -8379.0),
+    (1, 1, 0, 0, 4987.0, -16675.0),
+    (2, -1, 1, 0, 4036.0, -12831.0),
+    (2, 0, 2, 0, 3994.0, -10445.0),
+    (4, 0, 0, 0, 3861.0, -11650.0),
+    (2, 0, -3, 0, 3665.0, 14403.0),
+    (0, 1, -2, 0, -2689.0, -7003.0),
+    (2, 0, -1, 2, -2602.0, 0.0),
+    (2, -1, -2, 0, 2390.0, 10056.0),
+    (1, 0, 1, 0, -2348.0, 6322.0),
+    (2, -2, 0, 0, 2236.0, -9884.0),
+    (0, 1, 2, 0, -2120.0, 5751.0),
+    (0, 2, 0, 0, -2069.0, 0.0),
+    (2, -2, -1, 0, 2048.0, -4950.0),
+    (2, 0, 1, -2, -1773.0, 4130.0),
+    (2, 0, 0, 2, -1595.0, 0.0),
+    (4, -1, -1, 0, 1215.0, -3958.0),
+    (0, 0, 2, 2, -1110.0, 0.0),
+    (3, 0, -1, 0, -892.0, 3258.0),
+    (2, 1, 1, 0, -810.0, 2616.0),
+    (4, -1, -2, 0, 759.0, -1897.0),
+    (0, 2, -1, 0, -713.0, -2117.0),
+    (2, 2, -1, 0, -700.0, 2354.0),
+    (2, 1, -2, 0, 691.0, 0.0),
+    (2, -1, 0, -2, 596.0, 0.0),
+    (4, 0, 1, 0, 549.0, -1423.0),
+    (0, 0, 4, 0, 537.0, -1117.0),
+    (4, -1, 0, 0, 520.0, -1571.0),
+    (1, 0, -2, 0, -487.0, -1739.0),
+    (2, 1, 0, -2, -399.0, 0.0),
+    (0, 0, 2, -2, -381.0, -4421.0),
+    (1, 1, 1, 0, 351.0, 0.0),
+    (3, 0, -2, 0, -340.0, 0.0),
+    (4, 0, -3, 0, 330.0, 0.0),
+    (2, -1, 2, 0, 327.0, 0.0),
+    (0, 2, 1, 0, -323.0, 1165.0),
+    (1, 1, -1, 0, 299.0, 0.0),
+    (2, 0, 3, 0, 294.0, 0.0),
+    (2, 0, -1, -2, 0.0, 8752.0),
+];
+
+/// Periodic terms for lunar latitude (Meeus table 47.B):
+/// multiples of D, M, M', F, then Σb (1e-6 degrees)
+const MOON_B_TERMS: [(i8, i8, i8, i8, f64); 60] = [
+    (0, 0, 0, 1, 5128122.0),
+    (0, 0, 1, 1, 280602.0),
+    (0, 0, 1, -1, 277693.0),
+    (2, 0, 0, -1, 173237.0),
+    (2, 0, -1, 1, 55413.0),
+    (2, 0, -1, -1, 46271.0),
+    (2, 0, 0, 1, 32573.0),
+    (0, 0, 2, 1, 17198.0),
+    (2, 0, 1, -1, 9266.0),
+    (0, 0, 2, -1, 8822.0),
+    (2, -1, 0, -1, 8216.0),
+    (2, 0, -2, -1, 4324.0),
+    (2, 0, 1, 1, 4200.0),
+    (2, 1, 0, -1, -3359.0),
+    (2, -1, -1, 1, 2463.0),
+    (2, -1, 0, 1, 2211.0),
+    (2, -1, -1, -1, 2065.0),
+    (0, 1, -1, -1, -1870.0),
+    (4, 0, -1, -1, 1828.0),
+    (0, 1, 0, 1, -1794.0),
+    (0, 0, 0, 3, -1749.0),
+    (0, 1, -1, 1, -1565.0),
+    (1, 0, 0, 1, -1491.0),
+    (0, 1, 1, 1, -1475.0),
+    (0, 1, 1, -1, -1410.0),
+    (0, 1, 0, -1, -1344.0),
+    (1, 0, 0, -1, -1335.0),
+    (0, 0, 3, 1, 1107.0),
+    (4, 0, 0, -1, 1021.0),
+    (4, 0, -1, 1, 833.0),
+    (0, 0, 1, -3, 777.0),
+    (4, 0, -2, 1, 671.0),
+    (2, 0, 0, -3, 607.0),
+    (2, 0, 2, -1, 596.0),
+    (2, -1, 1, -1, 491.0),
+    (2, 0, -2, 1, -451.0),
+    (0, 0, 3, -1, 439.0),
+    (2, 0, 2, 1, 422.0),
+    (2, 0, -3, -1, 421.0),
+    (2, 1, -1, 1, -366.0),
+    (2, 1, 0, 1, -351.0),
+    (4, 0, 0, 1, 331.0),
+    (2, -1, 1, 1, 315.0),
+    (2, -2, 0, -1, 302.0),
+    (0, 0, 1, 3, -283.0),
+    (2, 1, 1, -1, -229.0),
+    (1, 1, 0, -1, 223.0),
+    (1, 1, 0, 1, 223.0),
+    (0, 1, -2, -1, -220.0),
+    (2, 1, -1, -1, -220.0),
+    (1, 0, 1, 1, -185.0),
+    (2, -1, -2, -1, 181.0),
+    (0, 1, 2, 1, -177.0),
+    (4, 0, -2, -1, 176.0),
+    (4, -1, -1, -1, 166.0),
+    (1, 0, 1, -1, -164.0),
+    (4, 0, 1, -1, 132.0),
+    (1, 0, -1, -1, -119.0),
+    (4, -1, 0, -1, 115.0),
+    (2, -2, 0, 1, 107.0),
+];
+
+/// Geocentric ecliptic longitude and latitude (degrees, mean equinox of
+/// date) and distance (km) of the Moon (Meeus 47)
+pub(crate) fn moon_ecliptic_position(jd: f64) -> (f64, f64, f64) {
     let t = (jd - J2000) / 36525.0;
+    let t2 = t * t;
+    let t3 = t2 * t;
+    let t4 = t3 * t;
+
+    // Mean longitude, elongation, anomalies and argument of latitude
+    let l0 = 218.3164477 + 481267.88123421 * t - 0.0015786 * t2 + t3 / 538841.0 - t4 / 65194000.0;
+    let d = 297.8501921 + 445267.1114034 * t - 0.0018819 * t2 + t3 / 545868.0 - t4 / 113065000.0;
+    let m = 357.5291092 + 35999.0502909 * t - 0.0001536 * t2 + t3 / 24490000.0;
+    let mp = 134.9633964 + 477198.8675055 * t + 0.0087414 * t2 + t3 / 69699.0 - t4 / 14712000.0;
+    let f = 93.2720950 + 483202.0175233 * t - 0.0036539 * t2 - t3 / 3526000.0 + t4 / 863310000.0;
+
+    // Venus, Jupiter and Earth flattening perturbations
+    let a1 = (119.75 + 131.849 * t).rem_euclid(360.0) * DEG_TO_RAD;
+    let a2 = (53.09 + 479264.290 * t).rem_euclid(360.0) * DEG_TO_RAD;
+    let a3 = (313.45 + 481266.484 * t).rem_euclid(360.0) * DEG_TO_RAD;
+
+    // Eccentricity of Earth's orbit scales terms involving the Sun's anomaly
+    let e = 1.0 - 0.002516 * t - 0.0000074 * t2;
+    let e_factor = |k: i8| match k.abs() {
+        1 => e,
+        2 => e * e,
+        _ => 1.0,
+    };
 
-    // Mean longitude
-    let l0 = (218.3164477 + 481267.88123421 * t).rem_euclid(360.0);
-    // Mean anomaly
-    let m = (134.9633964 + 477198.8675055 * t)
-        .rem_euclid(360.0)
-        .to_radians();
-    // Mean elongation
-    let d = (297.8501921 + 445267.1114034 * t)
-        .rem_euclid(360.0)
-        .to_radians();
-    // Argument of latitude
-    let f = (93.272095 + 483202.0175233 * t)
-        .rem_euclid(360.0)
-        .to_radians();
+    let (l0, d, m, mp, f) = (
+        l0.rem_euclid(360.0),
+        d.rem_euclid(360.0) * DEG_TO_RAD,
+        m.rem_euclid(360.0) * DEG_TO_RAD,
+        mp.rem_euclid(360.0) * DEG_TO_RAD,
+        f.rem_euclid(360.0) * DEG_TO_RAD,
+    );
+    let arg = |cd: i8, cm: i8, cmp: i8, cf: i8| {
+        cd as f64 * d + cm as f64 * m + cmp as f64 * mp + cf as f64 * f
+    };
+
+    let mut sum_l = 0.0;
+    let mut sum_r = 0.0;
+    for &(cd, cm, cmp, cf, sl, sr) in MOON_LR_TERMS.iter() {
+        let a = arg(cd, cm, cmp, cf);
+        let scale = e_factor(cm);
+        sum_l += sl * scale * a.sin();
+        sum_r += sr * scale * a.cos();
+    }
+
+    let mut sum_b = 0.0;
+    for &(cd, cm, cmp, cf, sb) in MOON_B_TERMS.iter() {
+        sum_b += sb * e_factor(cm) * arg(cd, cm, cmp, cf).sin();
+    }
+
+    let l0_rad = l0 * DEG_TO_RAD;
+    sum_l += 3958.0 * a1.sin() + 1962.0 * (l0_rad - f).sin() + 318.0 * a2.sin();
+    sum_b += -2235.0 * l0_rad.sin()
+        + 382.0 * a3.sin()
+        + 175.0 * (a1 - f).sin()
+        + 175.0 * (a1 + f).sin()
+        + 127.0 * (l0_rad - mp).sin()
+        - 115.0 * (l0_rad + mp).sin();
+
+    let lambda = (l0 + sum_l / 1_000_000.0).rem_euclid(360.0);
+    let beta = sum_b / 1_000_000.0;
+    let distance = 385000.56 + sum_r / 1000.0;
 
-    // Longitude correction
-    let dl = 6.289 * m.sin()
-        + 1.274 * (2.0 * d - m).sin()
-        + 0.658 * (2.0 * d).sin()
-        + 0.214 * (2.0 * m).sin()
-        - 0.186 * (d).sin();
+    (lambda, beta, distance)
+}
 
-    // Latitude
-    let b = 5.128 * f.sin();
+/// Calculate apparent geocentric Moon position: RA (hours), Dec (degrees)
+/// and distance (km), using the truncated ELP-2000/82 series of Meeus 47
+pub fn moon_position(jd: f64) -> (f64, f64, f64) {
+    let (lambda, beta, distance) = moon_ecliptic_position(jd);
+    let (d_psi, d_eps) = nutation(jd);
 
-    let lambda = (l0 + dl).to_radians();
-    let beta = b.to_radians();
-    let epsilon = 23.439_f64.to_radians();
+    let lambda = (lambda + d_psi / 3600.0) * DEG_TO_RAD;
+    let beta = beta * DEG_TO_RAD;
+    let epsilon = (mean_obliquity(jd) + d_eps / 3600.0) * DEG_TO_RAD;
 
     // Convert to RA/Dec
     let ra = (epsilon.cos() * lambda.sin() * beta.cos() - epsilon.sin() * beta.sin())
         .atan2(lambda.cos() * beta.cos());
     let dec = (epsilon.sin() * lambda.sin() * beta.cos() + epsilon.cos() * beta.sin()).asin();
 
-    // Distance in km (simplified)
-    let distance = 385001.0 - 20905.0 * m.cos();
-
     // Normalize RA to 0-24 hours
     let ra_hours = (ra.to_degrees() / 15.0).rem_euclid(24.0);
     (ra_hours, dec.to_degrees(), distance)
 }
 
-/// Calculate Moon phase
+/// Moon phase angle (Sun-Moon-Earth) in degrees: 0 at full, 180 at new
+/// (Meeus 48.2 and 48.3)
+pub fn moon_phase_angle(jd: f64) -> f64 {
+    let (lambda, beta, moon_distance) = moon_ecliptic_position(jd);
+    let sun_lambda = sun_ecliptic_longitude(jd);
+    let sun_distance_km = sun_distance(jd) * AU_KM;
+
+    let cos_psi = beta.to_radians().cos() * (lambda - sun_lambda).to_radians().cos();
+    let psi = cos_psi.clamp(-1.0, 1.0).acos();
+
+    (sun_distance_km * psi.sin())
+        .atan2(moon_distance - sun_distance_km * cos_psi)
+        .to_degrees()
+}
+
+/// Calculate Moon phase as the fraction of the lunation elapsed
+/// (0 = new, 0.25 = first quarter, 0.5 = full, 0.75 = last quarter)
 pub fn moon_phase(jd: f64) -> f64 {
-    let days_since_new = (jd - 2451550.1).rem_euclid(SYNODIC_MONTH);
-    days_since_new / SYNODIC_MONTH
+    let (lambda, _, _) = moon_ecliptic_position(jd);
+    let elongation = (lambda - sun_ecliptic_longitude(jd)).rem_euclid(360.0);
+    elongation / 360.0
 }
 
-/// Calculate Moon illumination percentage
+/// Calculate Moon illumination percentage from the phase angle
 pub fn moon_illumination(jd: f64) -> f64 {
-    let phase = moon_phase(jd);
-    let angle = phase * 2.0 * PI;
-    (1.0 - angle.cos()) / 2.0 * 100.0
+    let i = moon_phase_angle(jd).to_radians();
+    (1.0 + i.cos()) / 2.0 * 100.0
+}
+
+/// Julian Day of the first instant at or after `jd` when the Moon reaches
+/// `target_phase` (see [`moon_phase`]), e.g. 0.0 for new or 0.5 for full
+pub fn next_moon_phase_jd(jd: f64, target_phase: f64) -> f64 {
+    let mut estimate = jd + (target_phase - moon_phase(jd)).rem_euclid(1.0) * SYNODIC_MONTH;
+
+    // The Moon's elongation rate varies by about 20% over a lunation, so a
+    // few steps at the mean rate converge to well under a minute
+    for _ in 0..6 {
+        let offset = (target_phase - moon_phase(estimate) + 0.5).rem_euclid(1.0) - 0.5;
+        estimate += offset * SYNODIC_MONTH;
+    }
+
+    // The estimate can settle on the occurrence just before `jd`
+    if estimate < jd {
+        return next_moon_phase_jd(estimate + SYNODIC_MONTH / 2.0, target_phase);
+    }
+    estimate
 }
 
 /// Get Moon phase name
@@ -425,16 +1017,44 @@ fn find_sun_altitude_time(
     Some(jd_to_datetime((low + high) / 2.0))
 }
 
+/// Sun altitude at sunrise/sunset: the upper limb on the horizon, with
+/// standard refraction folded in unless it is already applied
+pub(crate) fn sunrise_altitude(corrections: &AltitudeCorrections) -> f64 {
+    if corrections.refraction {
+        -SUN_SEMIDIAMETER
+    } else {
+        -0.833
+    }
+}
+
+/// The location without refraction and horizon dip, for twilight limits
+/// which are defined on the geometric altitude
+pub(crate) fn geometric_location(location: &ObserverLocation) -> ObserverLocation {
+    ObserverLocation {
+        corrections: AltitudeCorrections {
+            refraction: false,
+            horizon_dip: false,
+            ..location.corrections.clone()
+        },
+        ..location.clone()
+    }
+}
+
 /// Calculate twilight times for a date
 pub fn calculate_twilight(location: &ObserverLocation, date: NaiveDate) -> TwilightTimes {
-    let sunrise = find_sun_altitude_time(location, date, -0.833, true);
-    let sunset = find_sun_altitude_time(location, date, -0.833, false);
-    let civil_dawn = find_sun_altitude_time(location, date, -6.0, true);
-    let civil_dusk = find_sun_altitude_time(location, date, -6.0, false);
-    let nautical_dawn = find_sun_altitude_time(location, date, -12.0, true);
-    let nautical_dusk = find_sun_altitude_time(location, date, -12.0, false);
-    let astronomical_dawn = find_sun_altitude_time(location, date, -18.0, true);
-    let astronomical_dusk = find_sun_altitude_time(location, date, -18.0, false);
+    // Sunrise and sunset use the observed altitude; twilight limits are
+    // defined on the geometric altitude
+    let horizon = sunrise_altitude(&location.corrections);
+    let geometric = geometric_location(location);
+
+    let sunrise = find_sun_altitude_time(location, date, horizon, true);
+    let sunset = find_sun_altitude_time(location, date, horizon, false);
+    let civil_dawn = find_sun_altitude_time(&geometric, date, -6.0, true);
+    let civil_dusk = find_sun_altitude_time(&geometric, date, -6.0, false);
+    let nautical_dawn = find_sun_altitude_time(&geometric, date, -12.0, true);
+    let nautical_dusk = find_sun_altitude_time(&geometric, date, -12.0, false);
+    let astronomical_dawn = find_sun_altitude_time(&geometric, date, -18.0, true);
+    let astronomical_dusk = find_sun_altitude_time(&geometric, date, -18.0, false);
 
     // Check for polar day/night
     let jd_noon = datetime_to_jd(DateTime::from_naive_utc_and_offset(
@@ -444,8 +1064,8 @@ pub fn calculate_twilight(location: &ObserverLocation, date: NaiveDate) -> Twili
     let noon_alt = sun_altitude(location, jd_noon);
     let midnight_alt = sun_altitude(location, jd_noon - 0.5);
 
-    let is_polar_day = midnight_alt > -0.833;
-    let is_polar_night = noon_alt < -0.833;
+    let is_polar_day = midnight_alt > horizon;
+    let is_polar_night = noon_alt < horizon;
 
     TwilightTimes {
         date: date.format("%Y-%m-%d").to_string(),
@@ -481,33 +1101,35 @@ pub fn calculate_visibility_window(
         Utc,
     ));
 
+    // Sample every 10 minutes
+    let altitudes = altitude_curve(ra, dec, location, jd_start, 1.0 / 144.0, 145);
+    let sample_time = |i: usize| jd_to_datetime(jd_start + i as f64 / 144.0);
+
     let mut start_time: Option<DateTime<Utc>> = None;
     let mut end_time: Option<DateTime<Utc>> = None;
     let mut max_altitude = -90.0;
-    let mut max_altitude_time = jd_to_datetime(jd_start);
+    let mut max_index = 0;
     let mut was_visible = false;
 
-    // Sample every 10 minutes
-    for i in 0..=144 {
-        let jd = jd_start + (i as f64) / 144.0;
-        let (alt, _) = ra_dec_to_alt_az(ra, dec, location.latitude, location.longitude, jd);
+    for (i, &alt) in altitudes.iter().enumerate() {
         let is_visible = alt >= min_altitude;
 
         if alt > max_altitude {
             max_altitude = alt;
-            max_altitude_time = jd_to_datetime(jd);
+            max_index = i;
         }
 
         if is_visible && !was_visible && start_time.is_none() {
-            start_time = Some(jd_to_datetime(jd));
+            start_time = Some(sample_time(i));
         }
 
         if !is_visible && was_visible && end_time.is_none() {
-            end_time = Some(jd_to_datetime(jd));
+            end_time = Some(sample_time(i));
         }
 
         was_visible = is_visible;
     }
+    let max_altitude_time = sample_time(max_index);
 
     // Handle case where target is visible at end of day
     if was_visible && end_time.is_none() {
@@ -529,6 +1151,94 @@ pub fn calculate_visibility_window(
     }
 }
 
+/// Step between samples when searching for rise and set, in days (5 min)
+const RISE_SET_STEP: f64 = 5.0 / 1440.0;
+
+/// Rise, set and transit of a target over the 24 hours from local noon
+/// on `date`, so the times belong to the night starting that day. The
+/// target is up when its observed altitude is at least `min_altitude` and
+/// above the horizon profile of `site`, if given, at its azimuth.
+pub fn calculate_target_rise_set(
+    coords: &Coordinates,
+    location: &ObserverLocation,
+    date: NaiveDate,
+    min_altitude: f64,
+    site: Option<&ObservingSite>,
+) -> RiseSetTimes {
+    let ra = coords.ra_to_decimal();
+    let dec = coords.dec_to_decimal();
+    let jd_start = datetime_to_jd(dark_calendar::night_start(location, date));
+
+    // Observed altitude, and its height above the limit at the target's
+    // azimuth
+    let sample = |jd: f64| {
+        let (altitude, azimuth) = observed_alt_az(ra, dec, location, jd);
+        let limit = site.map_or(min_altitude, |site| {
+            min_altitude.max(site.horizon_altitude(azimuth))
+        });
+        (altitude, altitude - limit)
+    };
+    // Crossing between two samples, by bisection to about a second
+    let crossing = |mut low: f64, mut high: f64, rising: bool| {
+        for _ in 0..20 {
+            let mid = (low + high) / 2.0;
+            if (sample(mid).1 >= 0.0) == rising {
+                high = mid;
+            } else {
+                low = mid;
+            }
+        }
+        jd_to_datetime((low + high) / 2.0)
+    };
+
+    let steps = (1.0 / RISE_SET_STEP).round() as usize;
+    let samples: Vec<(f64, f64, f64)> = (0..=steps)
+        .map(|i| {
+            let jd = jd_start + i as f64 * RISE_SET_STEP;
+            let (altitude, margin) = sample(jd);
+            (jd, altitude, margin)
+        })
+        .collect();
+
+    let mut rise = None;
+    let mut set = None;
+    for pair in samples.windows(2) {
+        let ((jd0, _, m0), (jd1, _, m1)) = (pair[0], pair[1]);
+        if rise.is_none() && m0 < 0.0 && m1 >= 0.0 {
+            rise = Some(crossing(jd0, jd1, true));
+        }
+        if set.is_none() && m0 >= 0.0 && m1 < 0.0 {
+            set = Some(crossing(jd0, jd1, false));
+        }
+    }
+
+    // Transit: refine the highest sample by ternary search on altitude
+    let highest = samples
+        .iter()
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+        .map_or(jd_start, |s| s.0);
+    let (mut low, mut high) = (highest - RISE_SET_STEP, highest + RISE_SET_STEP);
+    for _ in 0..40 {
+        let a = low + (high - low) / 3.0;
+        let b = high - (high - low) / 3.0;
+        if sample(a).0 < sample(b).0 {
+            low = a;
+        } else {
+            high = b;
+        }
+    }
+    let transit = (low + high) / 2.0;
+
+    RiseSetTimes {
+        rise,
+        set,
+        transit: jd_to_datetime(transit),
+        transit_altitude: sample(transit).0,
+        always_up: samples.iter().all(|s| s.2 >= 0.0),
+        never_up: samples.iter().all(|s| s.2 < 0.0),
+    }
+}
+
 /// Calculate observation quality score
 pub fn calculate_observation_quality(
     coords: &Coordinates,
@@ -539,7 +1249,7 @@ pub fn calculate_observation_quality(
     let ra = coords.ra_to_decimal();
     let dec = coords.dec_to_decimal();
 
-    let (target_alt, _) = ra_dec_to_alt_az(ra, dec, location.latitude, location.longitude, jd);
+    let (target_alt, _) = observed_alt_az(ra, dec, location, jd);
     let sun_alt = sun_altitude(location, jd);
     let (moon_ra, moon_dec, _) = moon_position(jd);
     let moon_illum = moon_illumination(jd);
@@ -600,17 +1310,33 @@ pub fn calculate_observation_quality(
         recommendations.push("Bright Moon nearby, consider imaging narrowband".to_string());
     }
 
-    let score = altitude_score + twilight_score + moon_score;
+    let sky_factor = sky_brightness_factor(location.sky_brightness);
+    if sky_factor < 0.75 {
+        recommendations
+            .push("Bright sky at this site, consider narrowband or longer integration".to_string());
+    }
+
+    let score = (altitude_score + twilight_score + moon_score) * sky_factor;
 
     ObservationQuality {
         score,
         altitude_score,
         moon_score,
         twilight_score,
+        sky_factor,
         recommendations,
     }
 }
 
+/// Score multiplier for zenith sky brightness: 1.0 at a pristine site
+/// (SQM 21.9, Bortle 1) down to 0.5 in an inner city (SQM 17.5, Bortle 9)
+pub fn sky_brightness_factor(sqm: Option<f64>) -> f64 {
+    match sqm {
+        Some(sqm) => 0.5 + 0.5 * ((sqm - 17.5) / (21.9 - 17.5)).clamp(0.0, 1.0),
+        None => 1.0,
+    }
+}
+
 /// Batch calculate coordinates
 pub fn batch_calculate_positions(
     targets: &[(String, Coordinates)],
@@ -625,7 +1351,7 @@ pub fn batch_calculate_positions(
         .map(|(id, coords)| {
             let ra = coords.ra_to_decimal();
             let dec = coords.dec_to_decimal();
-            let (alt, az) = ra_dec_to_alt_az(ra, dec, location.latitude, location.longitude, jd);
+            let (alt, az) = observed_alt_az(ra, dec, location, jd);
             let ha = hour_angle(ra, location.longitude, jd);
 
             BatchCoordinateResult {
@@ -663,7 +1389,7 @@ pub fn find_optimal_observation_time(
     let mut current = dark_start;
     while current < dark_end {
         let jd = datetime_to_jd(current);
-        let (alt, _) = ra_dec_to_alt_az(ra, dec, location.latitude, location.longitude, jd);
+        let (alt, _) = observed_alt_az(ra, dec, location, jd);
 
         if alt >= min_altitude {
             let quality = calculate_observation_quality(coords, location, current);
@@ -687,16 +1413,8 @@ pub fn get_moon_phase_info(datetime: DateTime<Utc>) -> MoonPhaseInfo {
     let phase_name = moon_phase_name(phase);
     let age_days = phase * SYNODIC_MONTH;
 
-    // Calculate next new and full moon
-    let days_to_new = (1.0 - phase) * SYNODIC_MONTH;
-    let days_to_full = if phase < 0.5 {
-        (0.5 - phase) * SYNODIC_MONTH
-    } else {
-        (1.5 - phase) * SYNODIC_MONTH
-    };
-
-    let next_new_moon = datetime + Duration::days(days_to_new as i64);
-    let next_full_moon = datetime + Duration::days(days_to_full as i64);
+    let next_new_moon = jd_to_datetime(next_moon_phase_jd(jd, 0.0));
+    let next_full_moon = jd_to_datetime(next_moon_phase_jd(jd, 0.5));
 
     MoonPhaseInfo {
         phase,
@@ -718,6 +1436,8 @@ mod tests {
             longitude: -74.0,
             elevation: 0.0,
             timezone_offset: -5,
+            sky_brightness: None,
+            corrections: AltitudeCorrections::default(),
         }
     }
 
@@ -733,15 +1453,15 @@ mod tests {
     fn test_sun_position() {
         let jd = datetime_to_jd(Utc::now());
         let (ra, dec) = sun_position(jd);
-        assert!(ra >= 0.0 && ra < 24.0);
-        assert!(dec >= -23.5 && dec <= 23.5);
+        assert!((0.0..24.0).contains(&ra));
+        assert!((-23.5..=23.5).contains(&dec));
     }
 
     #[test]
     fn test_moon_phase() {
         let jd = datetime_to_jd(Utc::now());
         let phase = moon_phase(jd);
-        assert!(phase >= 0.0 && phase <= 1.0);
+        assert!((0.0..=1.0).contains(&phase));
     }
 
     #[test]
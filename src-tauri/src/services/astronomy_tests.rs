@@ -11,9 +11,29 @@ mod tests {
             longitude: -74.0060,
             elevation: 10.0,
             timezone_offset: -5,
+            sky_brightness: None,
+            corrections: Default::default(),
         }
     }
 
+    #[test]
+    fn test_observer_location_ranges() {
+        use validator::Validate;
+
+        assert!(test_location().validate().is_ok());
+
+        let location = ObserverLocation {
+            latitude: 120.0,
+            timezone_offset: 20,
+            sky_brightness: Some(30.0),
+            ..test_location()
+        };
+        let errors = location.validate().unwrap_err();
+        let mut fields: Vec<_> = errors.field_errors().into_keys().collect();
+        fields.sort();
+        assert_eq!(fields, ["latitude", "sky_brightness", "timezone_offset"]);
+    }
+
     fn test_coordinates() -> crate::models::Coordinates {
         // M31 Andromeda Galaxy
         crate::models::Coordinates::new(0, 42, 44.3, 41, 16, 9.0, false)
@@ -57,14 +77,14 @@ mod tests {
     fn test_gmst_range() {
         let jd = datetime_to_jd(Utc::now());
         let gmst_val = gmst(jd);
-        assert!(gmst_val >= 0.0 && gmst_val < 360.0);
+        assert!((0.0..360.0).contains(&gmst_val));
     }
 
     #[test]
     fn test_lst_range() {
         let jd = datetime_to_jd(Utc::now());
         let lst_val = lst(jd, -74.0);
-        assert!(lst_val >= 0.0 && lst_val < 360.0);
+        assert!((0.0..360.0).contains(&lst_val));
     }
 
     // ============================================================================
@@ -82,14 +102,14 @@ mod tests {
         // Polaris should always be above horizon at this latitude
         assert!(alt > 0.0);
         // Azimuth should be valid
-        assert!(az >= 0.0 && az < 360.0);
+        assert!((0.0..360.0).contains(&az));
     }
 
     #[test]
     fn test_hour_angle() {
         let jd = datetime_to_jd(Utc::now());
         let ha = hour_angle(12.0, -74.0, jd);
-        assert!(ha >= -180.0 && ha <= 180.0);
+        assert!((-180.0..=180.0).contains(&ha));
     }
 
     #[test]
@@ -121,8 +141,8 @@ mod tests {
         let jd = datetime_to_jd(Utc::now());
         let (ra, dec) = sun_position(jd);
 
-        assert!(ra >= 0.0 && ra < 24.0);
-        assert!(dec >= -23.5 && dec <= 23.5);
+        assert!((0.0..24.0).contains(&ra));
+        assert!((-23.5..=23.5).contains(&dec));
     }
 
     #[test]
@@ -131,7 +151,7 @@ mod tests {
         let jd = datetime_to_jd(Utc::now());
         let alt = sun_altitude(&location, jd);
 
-        assert!(alt >= -90.0 && alt <= 90.0);
+        assert!((-90.0..=90.0).contains(&alt));
     }
 
     // ============================================================================
@@ -143,8 +163,8 @@ mod tests {
         let jd = datetime_to_jd(Utc::now());
         let (ra, dec, distance) = moon_position(jd);
 
-        assert!(ra >= 0.0 && ra < 24.0);
-        assert!(dec >= -30.0 && dec <= 30.0);
+        assert!((0.0..24.0).contains(&ra));
+        assert!((-30.0..=30.0).contains(&dec));
         assert!(distance > 350000.0 && distance < 420000.0);
     }
 
@@ -153,7 +173,7 @@ mod tests {
         let jd = datetime_to_jd(Utc::now());
         let phase = moon_phase(jd);
 
-        assert!(phase >= 0.0 && phase <= 1.0);
+        assert!((0.0..=1.0).contains(&phase));
     }
 
     #[test]
@@ -161,7 +181,7 @@ mod tests {
         let jd = datetime_to_jd(Utc::now());
         let illum = moon_illumination(jd);
 
-        assert!(illum >= 0.0 && illum <= 100.0);
+        assert!((0.0..=100.0).contains(&illum));
     }
 
     #[test]
@@ -172,6 +192,113 @@ mod tests {
         assert_eq!(moon_phase_name(0.75), "Last Quarter");
     }
 
+    #[test]
+    fn test_moon_position_meeus_example() {
+        // Meeus example 47.a: 1992 April 12, 0h TD
+        let jd = 2448724.5;
+        let (ra, dec, distance) = moon_position(jd);
+
+        assert!((ra * 15.0 - 134.688470).abs() < 0.001);
+        assert!((dec - 13.768368).abs() < 0.001);
+        assert!((distance - 368409.7).abs() < 1.0);
+    }
+
+    #[test]
+    fn test_moon_illumination_meeus_example() {
+        // Meeus example 48.a: illuminated fraction 0.6786
+        let illum = moon_illumination(2448724.5);
+        assert!((illum - 67.86).abs() < 0.1);
+    }
+
+    #[test]
+    fn test_next_moon_phases() {
+        // New Moon 2024-01-11 11:57 UTC, Full Moon 2024-01-25 17:54 UTC
+        let dt = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+        let info = get_moon_phase_info(dt);
+
+        let new_moon = Utc.with_ymd_and_hms(2024, 1, 11, 11, 57, 0).unwrap();
+        let full_moon = Utc.with_ymd_and_hms(2024, 1, 25, 17, 54, 0).unwrap();
+        assert!((info.next_new_moon - new_moon).num_minutes().abs() <= 10);
+        assert!((info.next_full_moon - full_moon).num_minutes().abs() <= 10);
+    }
+
+    #[test]
+    fn test_next_moon_phase_is_after_start() {
+        // Starting just after a New Moon skips to the following lunation
+        let jd = datetime_to_jd(Utc.with_ymd_and_hms(2024, 1, 11, 12, 30, 0).unwrap());
+        let next = next_moon_phase_jd(jd, 0.0);
+        assert!(next > jd + 29.0 && next < jd + 30.0);
+    }
+
+    // ============================================================================
+    // Refraction and Horizon Dip Tests
+    // ============================================================================
+
+    #[test]
+    fn test_refraction() {
+        // About 29' at the horizon, vanishing at the zenith
+        assert!((refraction(0.0, 1010.0, 10.0) - 0.483).abs() < 0.005);
+        assert!(refraction(90.0, 1010.0, 10.0).abs() < 0.001);
+
+        // Bennett, from the apparent altitude, is the inverse of Saemundsson
+        let apparent = 10.0 + refraction(10.0, 1010.0, 10.0);
+        let true_alt = apparent - refraction_from_apparent(apparent, 1010.0, 10.0);
+        assert!((true_alt - 10.0).abs() < 0.01);
+
+        // Thinner, warmer air bends less
+        assert!(refraction(5.0, 800.0, 30.0) < refraction(5.0, 1010.0, 10.0));
+    }
+
+    #[test]
+    fn test_horizon_dip() {
+        assert_eq!(horizon_dip(0.0), 0.0);
+        assert!((horizon_dip(100.0) - 0.293).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_observed_alt_az_corrections() {
+        let jd = datetime_to_jd(Utc.with_ymd_and_hms(2024, 10, 15, 4, 0, 0).unwrap());
+        let mut location = test_location();
+        let (geometric, az) =
+            ra_dec_to_alt_az(5.5, 20.0, location.latitude, location.longitude, jd);
+
+        // Disabled by default
+        let (observed, observed_az) = observed_alt_az(5.5, 20.0, &location, jd);
+        assert_eq!(observed, geometric);
+        assert_eq!(observed_az, az);
+
+        location.elevation = 400.0;
+        location.corrections.refraction = true;
+        location.corrections.horizon_dip = true;
+        let (observed, _) = observed_alt_az(5.5, 20.0, &location, jd);
+        let expected = geometric + refraction(geometric, 1010.0, 10.0) + horizon_dip(400.0);
+        assert!((observed - expected).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_twilight_with_corrections() {
+        let date = NaiveDate::from_ymd_opt(2024, 3, 21).unwrap();
+        let plain = calculate_twilight(&test_location(), date);
+
+        let mut location = test_location();
+        location.corrections.refraction = true;
+        let refracted = calculate_twilight(&location, date);
+
+        // Refraction replaces the standard 34' allowance, so sunrise barely moves
+        let shift = (refracted.sunrise.unwrap() - plain.sunrise.unwrap()).num_seconds();
+        assert!(shift.abs() < 60);
+        // Twilight limits stay geometric
+        assert_eq!(refracted.civil_dawn, plain.civil_dawn);
+
+        // A lowered horizon brings sunrise earlier and sunset later
+        location.elevation = 2000.0;
+        location.corrections.horizon_dip = true;
+        let high = calculate_twilight(&location, date);
+        assert!(high.sunrise.unwrap() < refracted.sunrise.unwrap());
+        assert!(high.sunset.unwrap() > refracted.sunset.unwrap());
+        assert_eq!(high.astronomical_dusk, plain.astronomical_dusk);
+    }
+
     // ============================================================================
     // Twilight Tests
     // ============================================================================
@@ -226,6 +353,24 @@ mod tests {
         assert!(window.duration_hours > 0.0);
     }
 
+    #[test]
+    fn test_altitude_curve_matches_point_samples() {
+        let mut location = test_location();
+        location.corrections.refraction = true;
+        let coords = test_coordinates();
+        let (ra, dec) = (coords.ra_to_decimal(), coords.dec_to_decimal());
+        let jd_start = datetime_to_jd(Utc.with_ymd_and_hms(2024, 10, 15, 0, 0, 0).unwrap());
+
+        // Not a whole number of lanes
+        let curve = altitude_curve(ra, dec, &location, jd_start, 1.0 / 288.0, 301);
+        assert_eq!(curve.len(), 301);
+        for (i, alt) in curve.iter().enumerate() {
+            let (expected, _) = observed_alt_az(ra, dec, &location, jd_start + i as f64 / 288.0);
+            assert!((alt - expected).abs() < 1e-5, "sample {}", i);
+        }
+        assert!(altitude_curve(ra, dec, &location, jd_start, 0.01, 0).is_empty());
+    }
+
     #[test]
     fn test_visibility_window_never_visible() {
         let location = test_location();
@@ -257,6 +402,29 @@ mod tests {
         assert!(quality.twilight_score >= 0.0);
     }
 
+    #[test]
+    fn test_observation_quality_sky_brightness() {
+        let dt = Utc.with_ymd_and_hms(2024, 10, 1, 4, 0, 0).unwrap();
+        let coords = test_coordinates();
+        let backyard = ObserverLocation {
+            sky_brightness: Some(18.5),
+            ..test_location()
+        };
+        let dark_site = ObserverLocation {
+            sky_brightness: Some(21.7),
+            ..test_location()
+        };
+
+        let unknown = calculate_observation_quality(&coords, &test_location(), dt);
+        let bright = calculate_observation_quality(&coords, &backyard, dt);
+        let dark = calculate_observation_quality(&coords, &dark_site, dt);
+
+        assert_eq!(unknown.sky_factor, 1.0);
+        assert!(dark.score > bright.score);
+        assert!(bright.sky_factor < 0.75 && dark.sky_factor > 0.9);
+        assert!((dark.altitude_score - bright.altitude_score).abs() < 1e-9);
+    }
+
     // ============================================================================
     // Batch Calculation Tests
     // ============================================================================
@@ -310,4 +478,138 @@ mod tests {
         // Should find an optimal time for M31 in October
         assert!(optimal.is_some());
     }
+
+    // ============================================================================
+    // Precession Tests
+    // ============================================================================
+
+    fn theta_persei() -> crate::models::Coordinates {
+        crate::models::Coordinates::new(2, 44, 11.986, 49, 13, 42.48, false)
+    }
+
+    fn assert_close(coords: &crate::models::Coordinates, ra: f64, dec: f64, arcsec: f64) {
+        let d_ra = (coords.ra_to_decimal() - ra) * 15.0 * 3600.0;
+        let d_dec = (coords.dec_to_decimal() - dec) * 3600.0;
+        assert!(d_ra.abs() < arcsec, "RA off by {:.2}\"", d_ra);
+        assert!(d_dec.abs() < arcsec, "Dec off by {:.2}\"", d_dec);
+    }
+
+    #[test]
+    fn test_precess_coordinates() {
+        // Meeus example 21.b without proper motion
+        let jd = 2462088.69;
+        let precessed = precess_coordinates(&theta_persei(), 2451545.0, jd);
+
+        assert_eq!(precessed.epoch, crate::models::CoordinateEpoch::MeanOfDate);
+        assert_close(
+            &precessed,
+            2.0 + 46.0 / 60.0 + 10.343 / 3600.0,
+            49.0 + 20.0 / 60.0 + 57.12 / 3600.0,
+            1.0,
+        );
+    }
+
+    #[test]
+    fn test_apparent_coordinates() {
+        // Meeus example 23.a without proper motion
+        let jd = 2462088.69;
+        let apparent = apparent_coordinates(&theta_persei(), jd);
+
+        assert_close(
+            &apparent,
+            2.0 + 46.0 / 60.0 + 13.40 / 3600.0,
+            49.0 + 21.0 / 60.0 + 10.03 / 3600.0,
+            2.0,
+        );
+
+        let back = mean_coordinates_j2000(&apparent, jd);
+        assert_eq!(back.epoch, crate::models::CoordinateEpoch::J2000);
+        let original = theta_persei();
+        assert_close(
+            &back,
+            original.ra_to_decimal(),
+            original.dec_to_decimal(),
+            0.5,
+        );
+    }
+
+    #[test]
+    fn test_mean_of_date_round_trip() {
+        // Precessed coordinates still need nutation and aberration to be
+        // apparent, and have none to remove on the way back to J2000
+        let jd = 2462088.69;
+        let original = theta_persei();
+        let precessed = precess_coordinates(&original, 2451545.0, jd);
+
+        let apparent = apparent_coordinates(&precessed, jd);
+        let direct = apparent_coordinates(&original, jd);
+        assert_eq!(apparent.epoch, crate::models::CoordinateEpoch::JNow);
+        assert_close(
+            &apparent,
+            direct.ra_to_decimal(),
+            direct.dec_to_decimal(),
+            0.2,
+        );
+
+        let back = mean_coordinates_j2000(&precessed, jd);
+        assert_close(
+            &back,
+            original.ra_to_decimal(),
+            original.dec_to_decimal(),
+            0.2,
+        );
+
+        let mean =
+            convert_coordinates_epoch(&apparent, crate::models::CoordinateEpoch::MeanOfDate, jd);
+        assert_eq!(mean.epoch, crate::models::CoordinateEpoch::MeanOfDate);
+        assert_close(
+            &mean,
+            precessed.ra_to_decimal(),
+            precessed.dec_to_decimal(),
+            0.5,
+        );
+    }
+
+    #[test]
+    fn test_epoch_year_to_jd() {
+        assert_eq!(epoch_year_to_jd(2000.0), 2451545.0);
+        assert!((epoch_year_to_jd(2050.0) - 2469807.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_target_rise_set() {
+        use crate::models::{Coordinates, HorizonPoint, ObservingSite};
+
+        let location = test_location();
+        let date = NaiveDate::from_ymd_opt(2024, 10, 15).unwrap();
+        let m42 = Coordinates::new(5, 35, 17.3, 5, 23, 28.0, true);
+
+        let times = calculate_target_rise_set(&m42, &location, date, 0.0, None);
+        let (rise, set) = (times.rise.unwrap(), times.set.unwrap());
+        assert!(!times.always_up && !times.never_up);
+        // M42 rises late in the evening and transits before dawn
+        assert!(rise < times.transit && times.transit < set);
+        assert!((times.transit_altitude - (90.0 - 40.7128 - 5.391)).abs() < 0.1);
+        let rise_margin = observed_alt_az(5.588, -5.391, &location, datetime_to_jd(rise)).0;
+        assert!(rise_margin.abs() < 0.05);
+
+        // A horizon profile delays rise and brings set forward
+        let site = ObservingSite {
+            horizon: vec![HorizonPoint {
+                azimuth: 0.0,
+                altitude: 15.0,
+            }],
+            ..Default::default()
+        };
+        let blocked = calculate_target_rise_set(&m42, &location, date, 0.0, Some(&site));
+        assert!(blocked.rise.unwrap() > rise);
+        assert!(blocked.set.unwrap() < set);
+        assert_eq!(blocked.transit, times.transit);
+
+        let polaris = Coordinates::new(2, 31, 49.0, 89, 15, 51.0, false);
+        assert!(calculate_target_rise_set(&polaris, &location, date, 0.0, None).always_up);
+        let south = Coordinates::new(0, 0, 0.0, 80, 0, 0.0, true);
+        let times = calculate_target_rise_set(&south, &location, date, 0.0, None);
+        assert!(times.never_up && times.rise.is_none());
+    }
 }
@@ -0,0 +1,286 @@
+//! Backend auto-save of open sequence sessions
+//!
+//! A background task checks the sessions every second. A session with
+//! edits since its last snapshot is written to its autosave file once the
+//! edits have waited for the auto-save interval, or straight away when
+//! enough edits piled up. Sessions without new edits are skipped, and the
+//! autosave file goes away once the session is saved or closed.
+//!
+//! Snapshots use the same files as the `auto_save_sequence` command, so
+//! `load_auto_save` recovers them.
+
+use chrono::{DateTime, Duration, Utc};
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use crate::models::AppSettings;
+use crate::services::sequence_session::SessionInfo;
+use crate::services::{file_service, serializer, settings_service};
+use crate::state::{AppState, SharedState};
+
+/// How often the sessions are checked
+const CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(1);
+
+/// When snapshots are due
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AutoSavePolicy {
+    pub enabled: bool,
+    /// Longest time edits wait for a snapshot
+    pub interval_seconds: u32,
+    /// Unsaved edits that trigger a snapshot right away, 0 for none
+    pub after_edits: u32,
+}
+
+impl AutoSavePolicy {
+    pub fn from_settings(settings: &AppSettings) -> Self {
+        Self {
+            enabled: settings.auto_save_enabled,
+            interval_seconds: settings.auto_save_interval.max(1),
+            after_edits: settings.auto_save_after_edits,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct SnapshotRecord {
+    /// Revision of the last snapshot
+    revision: u64,
+    saved_at: Option<DateTime<Utc>>,
+    path: Option<PathBuf>,
+    /// When edits newer than the snapshot were first seen
+    pending_since: Option<DateTime<Utc>>,
+}
+
+/// Auto-save state of one session
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionAutoSave {
+    pub session_id: String,
+    /// Revision of the last snapshot
+    pub revision: u64,
+    pub saved_at: Option<DateTime<Utc>>,
+    /// Autosave file of the last snapshot
+    pub path: Option<String>,
+    /// Whether edits are waiting for a snapshot
+    pub pending: bool,
+}
+
+/// Result of [`AutoSaveState::status`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AutoSaveStatus {
+    pub enabled: bool,
+    pub interval_seconds: u32,
+    pub after_edits: u32,
+    /// Time of the last snapshot of any session
+    pub last_save_at: Option<DateTime<Utc>>,
+    /// Error of the last failed snapshot, cleared by the next success
+    pub last_error: Option<String>,
+    pub sessions: Vec<SessionAutoSave>,
+}
+
+/// Snapshot bookkeeping, part of the [`AppState`](crate::state::AppState)
+#[derive(Default)]
+pub struct AutoSaveState {
+    records: RwLock<HashMap<String, SnapshotRecord>>,
+    last_save_at: RwLock<Option<DateTime<Utc>>>,
+    last_error: RwLock<Option<String>>,
+}
+
+impl AutoSaveState {
+    /// Sessions due for a snapshot at `now`. Also forgets sessions that are
+    /// closed or saved, returning the autosave files they leave behind.
+    pub fn plan(
+        &self,
+        sessions: &[SessionInfo],
+        policy: &AutoSavePolicy,
+        now: DateTime<Utc>,
+    ) -> (Vec<String>, Vec<PathBuf>) {
+        let mut records = self.records.write();
+        let mut stale = Vec::new();
+        records.retain(|id, record| {
+            let open = sessions.iter().any(|s| &s.id == id && s.dirty);
+            if !open {
+                stale.extend(record.path.take());
+            }
+            open
+        });
+
+        let interval = Duration::seconds(i64::from(policy.interval_seconds));
+        let mut due = Vec::new();
+        for session in sessions.iter().filter(|s| s.dirty) {
+            let record = records.entry(session.id.clone()).or_default();
+            let edits = session.revision.saturating_sub(record.revision);
+            if edits == 0 {
+                record.pending_since = None;
+                continue;
+            }
+            let pending_since = *record.pending_since.get_or_insert(now);
+            let enough_edits = policy.after_edits > 0 && edits >= u64::from(policy.after_edits);
+            if enough_edits || now - pending_since >= interval {
+                due.push(session.id.clone());
+            }
+        }
+        (due, stale)
+    }
+
+    /// Record a snapshot of `revision`
+    pub fn saved(&self, id: &str, revision: u64, path: PathBuf, now: DateTime<Utc>) {
+        let mut records = self.records.write();
+        let record = records.entry(id.to_string()).or_default();
+        record.revision = revision;
+        record.saved_at = Some(now);
+        record.path = Some(path);
+        record.pending_since = None;
+        *self.last_save_at.write() = Some(now);
+        *self.last_error.write() = None;
+    }
+
+    /// Record a failed snapshot; the session is tried again on the next
+    /// check
+    pub fn failed(&self, error: String) {
+        *self.last_error.write() = Some(error);
+    }
+
+    pub fn status(&self, policy: &AutoSavePolicy) -> AutoSaveStatus {
+        let mut sessions: Vec<SessionAutoSave> = self
+            .records
+            .read()
+            .iter()
+            .map(|(id, record)| SessionAutoSave {
+                session_id: id.clone(),
+                revision: record.revision,
+                saved_at: record.saved_at,
+                path: record.path.as_ref().map(|p| p.display().to_string()),
+                pending: record.pending_since.is_some(),
+            })
+            .collect();
+        sessions.sort_by(|a, b| a.session_id.cmp(&b.session_id));
+
+        AutoSaveStatus {
+            enabled: policy.enabled,
+            interval_seconds: policy.interval_seconds,
+            after_edits: policy.after_edits,
+            last_save_at: *self.last_save_at.read(),
+            last_error: self.last_error.read().clone(),
+            sessions,
+        }
+    }
+}
+
+/// Write a session's current sequence to its autosave file
+async fn snapshot(state: &AppState, id: &str) -> Result<(), String> {
+    let session = state.sessions.session(id)?;
+    let path = file_service::create_auto_save_path(&session.sequence.id);
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent)
+            .await
+            .map_err(|e| format!("Failed to create autosave directory: {}", e))?;
+    }
+    let contents =
+        serializer::serialize_simple_sequence_json(&session.sequence).map_err(|e| e.to_string())?;
+    file_service::write_file(&path, &contents)
+        .await
+        .map_err(|e| e.to_string())?;
+    state
+        .auto_save
+        .saved(id, session.revision, path, Utc::now());
+    Ok(())
+}
+
+/// Snapshot the sessions that are due
+pub async fn check_sessions(state: &AppState) {
+    let policy = AutoSavePolicy::from_settings(&settings_service::get_settings(state));
+    if !policy.enabled {
+        return;
+    }
+
+    let (due, stale) = state
+        .auto_save
+        .plan(&state.sessions.list(), &policy, Utc::now());
+    for path in stale {
+        if let Err(e) = file_service::delete_file(&path).await {
+            log::debug!("Failed to remove autosave {}: {}", path.display(), e);
+        }
+    }
+    for id in due {
+        if let Err(e) = snapshot(state, &id).await {
+            log::warn!("Failed to auto-save session {}: {}", id, e);
+            state.auto_save.failed(e);
+        }
+    }
+}
+
+/// Start the auto-save task; it runs for the life of the app
+pub fn start(state: SharedState) {
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(CHECK_INTERVAL);
+        loop {
+            interval.tick().await;
+            check_sessions(&state).await;
+        }
+    });
+}
+
+/// Auto-save settings and the snapshots taken so far
+pub fn get_auto_save_status(state: &AppState) -> AutoSaveStatus {
+    let policy = AutoSavePolicy::from_settings(&settings_service::get_settings(state));
+    state.auto_save.status(&policy)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session(id: &str, revision: u64, dirty: bool) -> SessionInfo {
+        SessionInfo {
+            id: id.to_string(),
+            path: None,
+            title: "Plan".to_string(),
+            target_count: 0,
+            dirty,
+            revision,
+            opened_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_plan_waits_for_interval_or_edits() {
+        let state = AutoSaveState::default();
+        let policy = AutoSavePolicy {
+            enabled: true,
+            interval_seconds: 60,
+            after_edits: 5,
+        };
+        let start = Utc::now();
+
+        // Clean sessions are never due
+        let (due, _) = state.plan(&[session("clean", 0, false)], &policy, start);
+        assert!(due.is_empty());
+
+        // Edits wait for the interval
+        let (due, _) = state.plan(&[session("a", 2, true)], &policy, start);
+        assert!(due.is_empty());
+        let later = start + Duration::seconds(60);
+        let (due, _) = state.plan(&[session("a", 3, true)], &policy, later);
+        assert_eq!(due, ["a"]);
+
+        // Unchanged since the snapshot, so skipped
+        state.saved("a", 3, PathBuf::from("a.autosave.json"), later);
+        let much_later = later + Duration::seconds(600);
+        let (due, _) = state.plan(&[session("a", 3, true)], &policy, much_later);
+        assert!(due.is_empty());
+
+        // Enough edits don't wait
+        let (due, _) = state.plan(&[session("a", 8, true)], &policy, much_later);
+        assert_eq!(due, ["a"]);
+
+        // A saved session leaves its autosave behind
+        let (due, stale) = state.plan(&[session("a", 8, false)], &policy, much_later);
+        assert!(due.is_empty());
+        assert_eq!(stale, [PathBuf::from("a.autosave.json")]);
+        assert!(state.status(&policy).sessions.is_empty());
+    }
+}
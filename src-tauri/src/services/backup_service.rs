@@ -1,12 +1,17 @@
 //! Backup and recovery service
 
-use chrono::{DateTime, Duration, Utc};
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
 use tokio::fs;
 
-use crate::models::SimpleSequence;
-use crate::services::file_service;
+use crate::error::{AppError, AppResult};
+use crate::models::{BackupRetentionPolicy, EditorSequence, EditorSequenceItem, SimpleSequence};
+use crate::services::edit_journal::{self, JournalOp};
+use crate::services::{file_service, settings_service, validator};
+use crate::state::AppState;
 
 /// Backup metadata
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -19,6 +24,69 @@ pub struct BackupMetadata {
     pub file_path: String,
     pub file_size: u64,
     pub backup_type: BackupType,
+    #[serde(default)]
+    pub sequence_kind: BackupSequenceKind,
+}
+
+/// Which sequence model a backup holds
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum BackupSequenceKind {
+    #[default]
+    Simple,
+    Editor,
+}
+
+/// Target or top-level target item in a backup
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupTargetPreview {
+    pub id: String,
+    pub name: String,
+    pub exposure_count: usize,
+}
+
+/// Summary of a backup's contents
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupPreview {
+    pub metadata: BackupMetadata,
+    pub title: String,
+    /// Last modification time of the backup file
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub modified_at: Option<DateTime<Utc>>,
+    pub target_count: usize,
+    pub exposure_count: usize,
+    /// Total number of editor items, zero for simple sequences
+    pub item_count: usize,
+    pub targets: Vec<BackupTargetPreview>,
+}
+
+/// Backup disk usage for one sequence
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SequenceBackupStatistics {
+    pub sequence_id: String,
+    pub sequence_title: String,
+    pub count: usize,
+    pub size: u64,
+    pub newest: DateTime<Utc>,
+}
+
+/// Backup disk usage
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupStatistics {
+    pub backup_count: usize,
+    /// Bytes used by backups and their metadata files
+    pub backup_size: u64,
+    pub crash_recovery_count: usize,
+    pub crash_recovery_size: u64,
+    pub total_size: u64,
+    pub oldest: Option<DateTime<Utc>>,
+    pub newest: Option<DateTime<Utc>>,
+    /// Per sequence, largest first
+    pub sequences: Vec<SequenceBackupStatistics>,
 }
 
 /// Backup type
@@ -58,29 +126,70 @@ pub async fn ensure_backup_directories() -> Result<(), String> {
 pub async fn create_backup(
     sequence: &SimpleSequence,
     backup_type: BackupType,
+) -> Result<BackupMetadata, String> {
+    let content = serde_json::to_string_pretty(sequence)
+        .map_err(|e| format!("Failed to serialize sequence: {}", e))?;
+
+    write_backup(
+        &sequence.id,
+        &sequence.title,
+        &content,
+        backup_type,
+        BackupSequenceKind::Simple,
+    )
+    .await
+}
+
+/// Create backup of editor sequence
+pub async fn create_editor_backup(
+    sequence: &EditorSequence,
+    backup_type: BackupType,
+) -> Result<BackupMetadata, String> {
+    let content = serde_json::to_string_pretty(sequence)
+        .map_err(|e| format!("Failed to serialize sequence: {}", e))?;
+
+    write_backup(
+        &sequence.id,
+        &sequence.title,
+        &content,
+        backup_type,
+        BackupSequenceKind::Editor,
+    )
+    .await
+}
+
+async fn write_backup(
+    sequence_id: &str,
+    sequence_title: &str,
+    content: &str,
+    backup_type: BackupType,
+    sequence_kind: BackupSequenceKind,
 ) -> Result<BackupMetadata, String> {
     ensure_backup_directories().await?;
 
     let id = uuid::Uuid::new_v4().to_string();
     let now = Utc::now();
-    let filename = format!("{}_{}.json", sequence.id, now.format("%Y%m%d_%H%M%S"));
+    let filename = format!(
+        "{}_{}_{}.json",
+        sequence_id,
+        now.format("%Y%m%d_%H%M%S"),
+        &id[..8]
+    );
     let path = get_backups_directory().join(&filename);
 
-    let content = serde_json::to_string_pretty(sequence)
-        .map_err(|e| format!("Failed to serialize sequence: {}", e))?;
-
-    fs::write(&path, &content)
+    fs::write(&path, content)
         .await
         .map_err(|e| format!("Failed to write backup: {}", e))?;
 
     let metadata = BackupMetadata {
         id,
-        sequence_id: sequence.id.clone(),
-        sequence_title: sequence.title.clone(),
+        sequence_id: sequence_id.to_string(),
+        sequence_title: sequence_title.to_string(),
         created_at: now,
         file_path: path.display().to_string(),
         file_size: content.len() as u64,
         backup_type,
+        sequence_kind,
     };
 
     // Save metadata
@@ -126,35 +235,182 @@ pub async fn list_backups(sequence_id: Option<&str>) -> Result<Vec<BackupMetadat
     }
 
     // Sort by creation time (newest first)
-    backups.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+    backups.sort_by_key(|b| std::cmp::Reverse(b.created_at));
 
     Ok(backups)
 }
 
 /// Restore backup
-pub async fn restore_backup(backup_id: &str) -> Result<SimpleSequence, String> {
-    let backups = list_backups(None).await?;
+pub async fn restore_backup(backup_id: &str) -> AppResult<SimpleSequence> {
+    let content = read_backup(backup_id, BackupSequenceKind::Simple).await?;
+    serde_json::from_str(&content)
+        .map_err(|e| AppError::Parse(format!("Failed to parse backup: {}", e)))
+}
 
-    let backup = backups
-        .iter()
+/// Restore editor sequence backup
+pub async fn restore_editor_backup(backup_id: &str) -> AppResult<EditorSequence> {
+    let content = read_backup(backup_id, BackupSequenceKind::Editor).await?;
+    serde_json::from_str(&content)
+        .map_err(|e| AppError::Parse(format!("Failed to parse backup: {}", e)))
+}
+
+async fn find_backup(backup_id: &str) -> AppResult<BackupMetadata> {
+    list_backups(None)
+        .await
+        .map_err(AppError::Io)?
+        .into_iter()
         .find(|b| b.id == backup_id)
-        .ok_or_else(|| "Backup not found".to_string())?;
+        .ok_or_else(|| AppError::NotFound(format!("Backup not found: {}", backup_id)))
+}
+
+async fn read_backup(backup_id: &str, kind: BackupSequenceKind) -> AppResult<String> {
+    let backup = find_backup(backup_id).await?;
+
+    if backup.sequence_kind != kind {
+        return Err(AppError::InvalidInput(match kind {
+            BackupSequenceKind::Simple => "Backup is not a simple sequence backup".to_string(),
+            BackupSequenceKind::Editor => "Backup is not an editor sequence backup".to_string(),
+        }));
+    }
 
-    let content = fs::read_to_string(&backup.file_path)
+    fs::read_to_string(&backup.file_path)
         .await
-        .map_err(|e| format!("Failed to read backup: {}", e))?;
+        .map_err(|e| AppError::Io(format!("Failed to read backup: {}", e)))
+}
+
+/// Summarize a backup without restoring it
+pub async fn preview_backup(backup_id: &str) -> AppResult<BackupPreview> {
+    let backup = find_backup(backup_id).await?;
+    let content = read_backup(backup_id, backup.sequence_kind).await?;
+    let modified_at = fs::metadata(&backup.file_path)
+        .await
+        .ok()
+        .and_then(|m| m.modified().ok())
+        .map(DateTime::<Utc>::from);
+
+    let mut preview = match backup.sequence_kind {
+        BackupSequenceKind::Simple => {
+            let sequence: SimpleSequence = serde_json::from_str(&content)
+                .map_err(|e| AppError::Parse(format!("Failed to parse backup: {}", e)))?;
+            preview_simple_sequence(&sequence, backup)
+        }
+        BackupSequenceKind::Editor => {
+            let sequence: EditorSequence = serde_json::from_str(&content)
+                .map_err(|e| AppError::Parse(format!("Failed to parse backup: {}", e)))?;
+            preview_editor_sequence(&sequence, backup)
+        }
+    };
+    preview.modified_at = modified_at;
 
-    serde_json::from_str(&content).map_err(|e| format!("Failed to parse backup: {}", e))
+    Ok(preview)
 }
 
-/// Delete backup
-pub async fn delete_backup(backup_id: &str) -> Result<(), String> {
-    let backups = list_backups(None).await?;
+fn preview_simple_sequence(sequence: &SimpleSequence, metadata: BackupMetadata) -> BackupPreview {
+    let targets: Vec<BackupTargetPreview> = sequence
+        .targets
+        .iter()
+        .map(|t| BackupTargetPreview {
+            id: t.id.clone(),
+            name: t.target_name.clone(),
+            exposure_count: t.exposures.len(),
+        })
+        .collect();
+
+    BackupPreview {
+        metadata,
+        title: sequence.title.clone(),
+        modified_at: None,
+        target_count: targets.len(),
+        exposure_count: targets.iter().map(|t| t.exposure_count).sum(),
+        item_count: 0,
+        targets,
+    }
+}
+
+fn preview_editor_sequence(sequence: &EditorSequence, metadata: BackupMetadata) -> BackupPreview {
+    fn count(items: &[EditorSequenceItem], matches: &dyn Fn(&EditorSequenceItem) -> bool) -> usize {
+        items
+            .iter()
+            .map(|item| {
+                usize::from(matches(item)) + item.items.as_deref().map_or(0, |c| count(c, matches))
+            })
+            .sum()
+    }
+    let is_exposure = |item: &EditorSequenceItem| {
+        validator::get_short_type_name(&item.item_type) == "TakeExposure"
+    };
 
-    let backup = backups
+    let targets: Vec<BackupTargetPreview> = sequence
+        .target_items
         .iter()
-        .find(|b| b.id == backup_id)
-        .ok_or_else(|| "Backup not found".to_string())?;
+        .map(|item| BackupTargetPreview {
+            id: item.id.clone(),
+            name: item.name.clone(),
+            exposure_count: count(std::slice::from_ref(item), &is_exposure),
+        })
+        .collect();
+    let item_count = [
+        &sequence.start_items,
+        &sequence.target_items,
+        &sequence.end_items,
+    ]
+    .iter()
+    .map(|items| count(items, &|_| true))
+    .sum();
+
+    BackupPreview {
+        metadata,
+        title: sequence.title.clone(),
+        modified_at: None,
+        target_count: targets.len(),
+        exposure_count: targets.iter().map(|t| t.exposure_count).sum(),
+        item_count,
+        targets,
+    }
+}
+
+/// Restore only the given targets of a simple sequence backup into
+/// `sequence`. Targets that still exist are replaced in place, the others
+/// are appended.
+pub async fn restore_backup_partial(
+    backup_id: &str,
+    sequence: SimpleSequence,
+    target_ids: &[String],
+) -> AppResult<SimpleSequence> {
+    let backup = restore_backup(backup_id).await?;
+    merge_backup_targets(sequence, &backup, target_ids)
+}
+
+/// Merge selected targets of `backup` into `sequence`
+pub fn merge_backup_targets(
+    mut sequence: SimpleSequence,
+    backup: &SimpleSequence,
+    target_ids: &[String],
+) -> AppResult<SimpleSequence> {
+    for target_id in target_ids {
+        let target = backup
+            .targets
+            .iter()
+            .find(|t| &t.id == target_id)
+            .ok_or_else(|| {
+                AppError::NotFound(format!("Target not found in backup: {}", target_id))
+            })?;
+
+        match sequence.targets.iter_mut().find(|t| &t.id == target_id) {
+            Some(existing) => *existing = target.clone(),
+            None => sequence.targets.push(target.clone()),
+        }
+    }
+
+    if !target_ids.is_empty() {
+        sequence.is_dirty = true;
+    }
+    Ok(sequence)
+}
+
+/// Delete backup
+pub async fn delete_backup(backup_id: &str) -> AppResult<()> {
+    let backup = find_backup(backup_id).await?;
 
     let path = PathBuf::from(&backup.file_path);
     let meta_path = path.with_extension("meta.json");
@@ -162,13 +418,13 @@ pub async fn delete_backup(backup_id: &str) -> Result<(), String> {
     if path.exists() {
         fs::remove_file(&path)
             .await
-            .map_err(|e| format!("Failed to delete backup: {}", e))?;
+            .map_err(|e| AppError::Io(format!("Failed to delete backup: {}", e)))?;
     }
 
     if meta_path.exists() {
         fs::remove_file(&meta_path)
             .await
-            .map_err(|e| format!("Failed to delete metadata: {}", e))?;
+            .map_err(|e| AppError::Io(format!("Failed to delete metadata: {}", e)))?;
     }
 
     Ok(())
@@ -208,7 +464,183 @@ pub async fn clean_old_backups(max_age_days: i64, max_count: usize) -> Result<us
     Ok(deleted)
 }
 
-/// Save crash recovery data
+/// Backups that `policy` does not keep. Backups are grouped by sequence;
+/// within a group the newest backups, the newest backup of each of the
+/// latest `keep_daily` days and of each of the latest `keep_weekly` ISO
+/// weeks are kept.
+pub fn select_backups_to_prune(
+    backups: &[BackupMetadata],
+    policy: &BackupRetentionPolicy,
+) -> Vec<String> {
+    let mut by_sequence: HashMap<&str, Vec<&BackupMetadata>> = HashMap::new();
+    for backup in backups {
+        by_sequence
+            .entry(backup.sequence_id.as_str())
+            .or_default()
+            .push(backup);
+    }
+
+    let mut pruned = Vec::new();
+    for group in by_sequence.values_mut() {
+        group.sort_by_key(|b| std::cmp::Reverse(b.created_at));
+
+        let mut days: HashSet<NaiveDate> = HashSet::new();
+        let mut weeks: HashSet<(i32, u32)> = HashSet::new();
+        for (index, backup) in group.iter().enumerate() {
+            let date = backup.created_at.date_naive();
+            let week = date.iso_week();
+
+            let mut keep = index < policy.keep_last;
+            if days.len() < policy.keep_daily && days.insert(date) {
+                keep = true;
+            }
+            if weeks.len() < policy.keep_weekly && weeks.insert((week.year(), week.week())) {
+                keep = true;
+            }
+
+            if !keep {
+                pruned.push(backup.id.clone());
+            }
+        }
+    }
+
+    pruned
+}
+
+/// Delete the backups that the retention policy does not keep, optionally
+/// only for one sequence. Returns the number of deleted backups.
+pub async fn apply_retention_policy(
+    sequence_id: Option<&str>,
+    policy: &BackupRetentionPolicy,
+) -> Result<usize, String> {
+    let backups = list_backups(sequence_id).await?;
+
+    let mut deleted = 0;
+    for id in select_backups_to_prune(&backups, policy) {
+        if let Err(e) = delete_backup(&id).await {
+            log::warn!("Failed to prune backup {}: {}", id, e);
+        } else {
+            deleted += 1;
+        }
+    }
+
+    Ok(deleted)
+}
+
+/// Back up the simple sequence stored at `path` before it is overwritten,
+/// if backup-on-save is enabled, and prune that sequence's backups
+pub async fn backup_simple_file_before_save(
+    state: &AppState,
+    path: &Path,
+) -> Result<Option<BackupMetadata>, String> {
+    if !settings_service::get_backup_on_save(state) || !path.exists() {
+        return Ok(None);
+    }
+
+    let previous = file_service::load_simple_sequence(path)
+        .await
+        .map_err(|e| e.to_string())?;
+    let backup = create_backup(&previous, BackupType::BeforeSave).await?;
+    apply_retention_policy(
+        Some(&backup.sequence_id),
+        &settings_service::get_backup_retention(state),
+    )
+    .await?;
+
+    Ok(Some(backup))
+}
+
+/// Editor sequence counterpart of [`backup_simple_file_before_save`]
+pub async fn backup_editor_file_before_save(
+    state: &AppState,
+    path: &Path,
+) -> Result<Option<BackupMetadata>, String> {
+    if !settings_service::get_backup_on_save(state) || !path.exists() {
+        return Ok(None);
+    }
+
+    let previous = file_service::load_editor_sequence(path)
+        .await
+        .map_err(|e| e.to_string())?;
+    let backup = create_editor_backup(&previous, BackupType::BeforeSave).await?;
+    apply_retention_policy(
+        Some(&backup.sequence_id),
+        &settings_service::get_backup_retention(state),
+    )
+    .await?;
+
+    Ok(Some(backup))
+}
+
+/// Number of files and bytes used by a directory's direct children
+async fn directory_usage(dir: &Path) -> Result<(usize, u64), String> {
+    if !dir.exists() {
+        return Ok((0, 0));
+    }
+
+    let mut count = 0;
+    let mut size = 0;
+    let mut entries = fs::read_dir(dir)
+        .await
+        .map_err(|e| format!("Failed to read directory: {}", e))?;
+
+    while let Some(entry) = entries.next_entry().await.map_err(|e| e.to_string())? {
+        if let Ok(metadata) = entry.metadata().await {
+            if metadata.is_file() {
+                count += 1;
+                size += metadata.len();
+            }
+        }
+    }
+
+    Ok((count, size))
+}
+
+/// Summarize backup disk usage
+pub async fn get_backup_statistics() -> Result<BackupStatistics, String> {
+    let backups = list_backups(None).await?;
+    let (_, backup_size) = directory_usage(&get_backups_directory()).await?;
+    // Journals count towards the size but not the number of recoverable sequences
+    let (_, crash_recovery_size) = directory_usage(&get_crash_recovery_directory()).await?;
+    let crash_recovery_count = list_crash_recovery().await?.len();
+
+    let mut sequences: HashMap<&str, SequenceBackupStatistics> = HashMap::new();
+    for backup in &backups {
+        // Backups are listed newest first, so the first one sets the title
+        let stats = sequences
+            .entry(backup.sequence_id.as_str())
+            .or_insert_with(|| SequenceBackupStatistics {
+                sequence_id: backup.sequence_id.clone(),
+                sequence_title: backup.sequence_title.clone(),
+                count: 0,
+                size: 0,
+                newest: backup.created_at,
+            });
+        stats.count += 1;
+        stats.size += backup.file_size;
+    }
+    let mut sequences: Vec<SequenceBackupStatistics> = sequences.into_values().collect();
+    sequences.sort_by_key(|s| std::cmp::Reverse(s.size));
+
+    Ok(BackupStatistics {
+        backup_count: backups.len(),
+        backup_size,
+        crash_recovery_count,
+        crash_recovery_size,
+        total_size: backup_size + crash_recovery_size,
+        oldest: backups.last().map(|b| b.created_at),
+        newest: backups.first().map(|b| b.created_at),
+        sequences,
+    })
+}
+
+/// Journal file of a sequence's crash recovery data
+pub fn get_crash_journal_path(sequence_id: &str) -> PathBuf {
+    edit_journal::journal_path(&get_crash_recovery_directory(), sequence_id)
+}
+
+/// Save crash recovery data. The snapshot includes every journaled edit,
+/// so the journal starts over.
 pub async fn save_crash_recovery(sequence: &SimpleSequence) -> Result<String, String> {
     ensure_backup_directories().await?;
 
@@ -220,11 +652,19 @@ pub async fn save_crash_recovery(sequence: &SimpleSequence) -> Result<String, St
     fs::write(&path, content)
         .await
         .map_err(|e| format!("Failed to write crash recovery: {}", e))?;
+    edit_journal::clear(&get_crash_journal_path(&sequence.id)).await?;
 
     Ok(path.display().to_string())
 }
 
-/// Load crash recovery data
+/// Record edits made since the last crash recovery snapshot
+pub async fn journal_edit(sequence_id: &str, ops: &[JournalOp]) -> Result<(), String> {
+    ensure_backup_directories().await?;
+    edit_journal::append(&get_crash_journal_path(sequence_id), ops).await
+}
+
+/// Load crash recovery data, replaying journaled edits on top of the
+/// snapshot. Edits that no longer apply are dropped.
 pub async fn load_crash_recovery(sequence_id: &str) -> Result<Option<SimpleSequence>, String> {
     let path = get_crash_recovery_directory().join(format!("{}.json", sequence_id));
 
@@ -236,7 +676,20 @@ pub async fn load_crash_recovery(sequence_id: &str) -> Result<Option<SimpleSeque
         .await
         .map_err(|e| format!("Failed to read crash recovery: {}", e))?;
 
-    let sequence = serde_json::from_str(&content)
+    let snapshot: Value = serde_json::from_str(&content)
+        .map_err(|e| format!("Failed to parse crash recovery: {}", e))?;
+    let entries = edit_journal::read(&get_crash_journal_path(sequence_id)).await?;
+
+    let mut replayed = snapshot.clone();
+    let applied = edit_journal::replay(&mut replayed, &entries);
+    if applied > 0 {
+        match serde_json::from_value(replayed) {
+            Ok(sequence) => return Ok(Some(sequence)),
+            Err(e) => log::warn!("Discarding journal of {}: {}", sequence_id, e),
+        }
+    }
+
+    let sequence = serde_json::from_value(snapshot)
         .map_err(|e| format!("Failed to parse crash recovery: {}", e))?;
 
     Ok(Some(sequence))
@@ -251,6 +704,7 @@ pub async fn clear_crash_recovery(sequence_id: &str) -> Result<(), String> {
             .await
             .map_err(|e| format!("Failed to delete crash recovery: {}", e))?;
     }
+    edit_journal::clear(&get_crash_journal_path(sequence_id)).await?;
 
     Ok(())
 }
@@ -279,3 +733,103 @@ pub async fn list_crash_recovery() -> Result<Vec<String>, String> {
 
     Ok(ids)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::SimpleTarget;
+    use chrono::TimeZone;
+
+    fn backup(id: &str, sequence_id: &str, created_at: DateTime<Utc>) -> BackupMetadata {
+        BackupMetadata {
+            id: id.to_string(),
+            sequence_id: sequence_id.to_string(),
+            sequence_title: String::new(),
+            created_at,
+            file_path: String::new(),
+            file_size: 0,
+            backup_type: BackupType::Auto,
+            sequence_kind: BackupSequenceKind::Simple,
+        }
+    }
+
+    #[test]
+    fn test_select_backups_to_prune() {
+        // Monday 2025-03-03 12:00, then backups going back in time
+        let start = Utc.with_ymd_and_hms(2025, 3, 3, 12, 0, 0).unwrap();
+        let at = |hours: i64| start - Duration::hours(hours);
+        let backups = vec![
+            backup("now", "a", at(0)),
+            backup("earlier", "a", at(1)),
+            backup("yesterday", "a", at(24)),
+            backup("yesterday-early", "a", at(25)),
+            backup("last-week", "a", at(24 * 7)),
+            backup("two-weeks", "a", at(24 * 14)),
+            backup("other", "b", at(24 * 30)),
+        ];
+
+        let policy = BackupRetentionPolicy {
+            keep_last: 1,
+            keep_daily: 2,
+            keep_weekly: 2,
+        };
+        let mut pruned = select_backups_to_prune(&backups, &policy);
+        pruned.sort();
+        // "now" is the newest of its day and ISO week, "yesterday" the
+        // newest of Sunday and of the previous week
+        assert_eq!(
+            pruned,
+            vec!["earlier", "last-week", "two-weeks", "yesterday-early"]
+        );
+
+        let keep_nothing = BackupRetentionPolicy {
+            keep_last: 0,
+            keep_daily: 0,
+            keep_weekly: 0,
+        };
+        assert_eq!(
+            select_backups_to_prune(&backups, &keep_nothing).len(),
+            backups.len()
+        );
+    }
+
+    #[test]
+    fn test_merge_backup_targets() {
+        let mut saved = SimpleSequence::new("Saved");
+        saved.targets[0].target_name = "M31 (old)".to_string();
+        saved.targets.push(SimpleTarget {
+            target_name: "M42".to_string(),
+            ..Default::default()
+        });
+        let kept_id = saved.targets[0].id.clone();
+        let added_id = saved.targets[1].id.clone();
+
+        let mut current = saved.clone();
+        current.targets[0].target_name = "M31".to_string();
+        current.targets.truncate(1);
+        current.targets.push(SimpleTarget {
+            target_name: "M81".to_string(),
+            ..Default::default()
+        });
+        current.is_dirty = false;
+
+        let merged =
+            merge_backup_targets(current.clone(), &saved, &[kept_id, added_id.clone()]).unwrap();
+        let names: Vec<&str> = merged
+            .targets
+            .iter()
+            .map(|t| t.target_name.as_str())
+            .collect();
+        assert_eq!(names, vec!["M31 (old)", "M81", "M42"]);
+        assert!(merged.is_dirty);
+
+        assert_eq!(
+            merge_backup_targets(current, &saved, &["missing".to_string()]).unwrap_err(),
+            AppError::NotFound("Target not found in backup: missing".to_string())
+        );
+
+        let preview = preview_simple_sequence(&saved, backup("id", "a", Utc::now()));
+        assert_eq!(preview.target_count, 2);
+        assert_eq!(preview.targets[1].id, added_id);
+    }
+}
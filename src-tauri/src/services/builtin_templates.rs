@@ -0,0 +1,375 @@
+//! Built-in starter templates
+//!
+//! Built in code rather than shipped as files so they follow model
+//! changes. They are listed alongside user templates with `is_builtin`
+//! set and are never written to disk; users derive their own copies with
+//! `template_service::clone_builtin_template`.
+
+use chrono::{TimeZone, Utc};
+use serde_json::{json, Value};
+
+use crate::models::{
+    FilterInfo, ImageType, SimpleExposure, SimpleSequence, SimpleTarget, StartOptions,
+};
+use crate::services::template_service::{
+    ExposureSetTemplate, SimpleSequenceTemplate, TargetTemplate, TemplateMetadata,
+    TemplateVariable, TemplateVariableType,
+};
+
+/// Prefix of all built-in template IDs
+pub const BUILTIN_ID_PREFIX: &str = "builtin-";
+
+fn metadata(
+    id: &str,
+    name: &str,
+    description: &str,
+    category: &str,
+    tags: &[&str],
+) -> TemplateMetadata {
+    let created = Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap();
+    TemplateMetadata {
+        id: format!("{}{}", BUILTIN_ID_PREFIX, id),
+        name: name.to_string(),
+        description: description.to_string(),
+        category: category.to_string(),
+        created_at: created,
+        updated_at: created,
+        tags: tags.iter().map(|t| t.to_string()).collect(),
+        is_builtin: true,
+    }
+}
+
+fn variable(
+    name: &str,
+    var_type: TemplateVariableType,
+    default: Value,
+    description: &str,
+) -> TemplateVariable {
+    TemplateVariable {
+        name: name.to_string(),
+        var_type,
+        default: Some(default),
+        description: Some(description.to_string()),
+    }
+}
+
+fn exposure_time_variable(default: f64) -> TemplateVariable {
+    variable(
+        "exposure_time",
+        TemplateVariableType::Number,
+        json!(default),
+        "Exposure time per frame (seconds)",
+    )
+}
+
+fn count_variable(default: i32) -> TemplateVariable {
+    variable(
+        "count",
+        TemplateVariableType::Integer,
+        json!(default),
+        "Frames per filter",
+    )
+}
+
+fn filter(name: &str, position: i32) -> Option<FilterInfo> {
+    Some(FilterInfo {
+        name: name.to_string(),
+        position,
+        ..Default::default()
+    })
+}
+
+/// Exposure as template JSON, with time and count given as placeholders
+fn exposure_json(exposure: SimpleExposure, time: &str, count: &str) -> Value {
+    let mut value = serde_json::to_value(exposure).expect("exposure serializes");
+    value["exposureTime"] = json!(time);
+    value["totalCount"] = json!(count);
+    value
+}
+
+fn lrgb_exposures() -> Vec<Value> {
+    ["L", "R", "G", "B"]
+        .iter()
+        .enumerate()
+        .map(|(position, name)| {
+            let exposure = SimpleExposure {
+                filter: filter(name, position as i32),
+                dither: true,
+                dither_every: 3,
+                ..Default::default()
+            };
+            exposure_json(exposure, "{{exposure_time}}", "{{count}}")
+        })
+        .collect()
+}
+
+fn sho_exposures() -> Vec<Value> {
+    [("SII", 6), ("Ha", 4), ("OIII", 5)]
+        .iter()
+        .map(|(name, position)| {
+            let exposure = SimpleExposure {
+                filter: filter(name, *position),
+                dither: true,
+                dither_every: 2,
+                ..Default::default()
+            };
+            exposure_json(exposure, "{{exposure_time}}", "{{count}}")
+        })
+        .collect()
+}
+
+fn eaa_exposures() -> Vec<Value> {
+    vec![exposure_json(
+        SimpleExposure::default(),
+        "{{exposure_time}}",
+        "{{count}}",
+    )]
+}
+
+fn calibration_exposures() -> Vec<SimpleExposure> {
+    vec![
+        SimpleExposure {
+            image_type: ImageType::Flat,
+            exposure_time: 2.0,
+            total_count: 25,
+            ..Default::default()
+        },
+        SimpleExposure {
+            image_type: ImageType::Dark,
+            exposure_time: 120.0,
+            total_count: 25,
+            ..Default::default()
+        },
+        SimpleExposure {
+            image_type: ImageType::Bias,
+            exposure_time: 0.001,
+            total_count: 50,
+            ..Default::default()
+        },
+    ]
+}
+
+/// Built-in exposure set templates
+pub fn exposure_templates() -> Vec<ExposureSetTemplate> {
+    let calibration = calibration_exposures();
+    vec![
+        ExposureSetTemplate {
+            metadata: metadata(
+                "exposures-lrgb",
+                "Broadband LRGB",
+                "Luminance, red, green and blue with dithering every third frame",
+                "exposure",
+                &["broadband", "lrgb"],
+            ),
+            variables: vec![exposure_time_variable(120.0), count_variable(20)],
+            exposures: lrgb_exposures(),
+        },
+        ExposureSetTemplate {
+            metadata: metadata(
+                "exposures-sho",
+                "Narrowband SHO",
+                "SII, Ha and OIII for the Hubble palette",
+                "exposure",
+                &["narrowband", "sho"],
+            ),
+            variables: vec![exposure_time_variable(300.0), count_variable(15)],
+            exposures: sho_exposures(),
+        },
+        ExposureSetTemplate {
+            metadata: metadata(
+                "exposures-eaa",
+                "EAA quick look",
+                "Short unfiltered frames for live stacking",
+                "exposure",
+                &["eaa"],
+            ),
+            variables: vec![exposure_time_variable(10.0), count_variable(30)],
+            exposures: eaa_exposures(),
+        },
+        ExposureSetTemplate {
+            metadata: metadata(
+                "exposures-calibration",
+                "Calibration frames",
+                "Flats, darks and bias frames",
+                "exposure",
+                &["calibration"],
+            ),
+            variables: Vec::new(),
+            exposures: calibration
+                .into_iter()
+                .map(|e| serde_json::to_value(e).expect("exposure serializes"))
+                .collect(),
+        },
+    ]
+}
+
+/// Target as template JSON with a `{{target_name}}` placeholder
+fn target_json(target: SimpleTarget, exposures: Vec<Value>) -> Value {
+    let mut value = serde_json::to_value(target).expect("target serializes");
+    value["name"] = json!("{{target_name}}");
+    value["targetName"] = json!("{{target_name}}");
+    value["exposures"] = Value::Array(exposures);
+    value
+}
+
+fn target_name_variable() -> TemplateVariable {
+    variable(
+        "target_name",
+        TemplateVariableType::String,
+        json!("Target"),
+        "Target name",
+    )
+}
+
+/// Built-in target templates
+pub fn target_templates() -> Vec<TargetTemplate> {
+    vec![
+        TargetTemplate {
+            metadata: metadata(
+                "target-lrgb",
+                "Broadband LRGB target",
+                "Galaxy or cluster imaged through LRGB filters",
+                "target",
+                &["broadband", "lrgb"],
+            ),
+            variables: vec![
+                target_name_variable(),
+                exposure_time_variable(120.0),
+                count_variable(20),
+            ],
+            target: target_json(
+                SimpleTarget {
+                    auto_focus_on_filter_change: true,
+                    ..Default::default()
+                },
+                lrgb_exposures(),
+            ),
+        },
+        TargetTemplate {
+            metadata: metadata(
+                "target-sho",
+                "Narrowband SHO target",
+                "Emission nebula imaged through SII, Ha and OIII filters",
+                "target",
+                &["narrowband", "sho"],
+            ),
+            variables: vec![
+                target_name_variable(),
+                exposure_time_variable(300.0),
+                count_variable(15),
+            ],
+            target: target_json(
+                SimpleTarget {
+                    auto_focus_on_filter_change: true,
+                    auto_focus_after_temperature_change: true,
+                    ..Default::default()
+                },
+                sho_exposures(),
+            ),
+        },
+        TargetTemplate {
+            metadata: metadata(
+                "target-eaa",
+                "EAA quick look target",
+                "Slew and center, then stream short frames without guiding",
+                "target",
+                &["eaa"],
+            ),
+            variables: vec![
+                target_name_variable(),
+                exposure_time_variable(10.0),
+                count_variable(30),
+            ],
+            target: target_json(
+                SimpleTarget {
+                    start_guiding: false,
+                    auto_focus_on_start: false,
+                    ..Default::default()
+                },
+                eaa_exposures(),
+            ),
+        },
+    ]
+}
+
+/// Target taking calibration frames: no slew, centering, guiding or
+/// autofocus
+pub fn calibration_target(name: &str, exposures: Vec<SimpleExposure>) -> SimpleTarget {
+    SimpleTarget {
+        name: name.to_string(),
+        target_name: name.to_string(),
+        slew_to_target: false,
+        center_target: false,
+        start_guiding: false,
+        auto_focus_on_start: false,
+        exposures,
+        ..Default::default()
+    }
+}
+
+/// Built-in simple sequence templates
+pub fn simple_sequence_templates() -> Vec<SimpleSequenceTemplate> {
+    let target = calibration_target("Calibration", calibration_exposures());
+    let mut sequence = SimpleSequence::new("Calibration night");
+    sequence.start_options = StartOptions {
+        unpark_mount_at_sequence_start: false,
+        do_meridian_flip: false,
+        ..Default::default()
+    };
+    sequence.end_options.park_mount_at_sequence_end = true;
+    sequence.selected_target_id = Some(target.id.clone());
+    sequence.active_target_id = Some(target.id.clone());
+    sequence.targets = vec![target];
+
+    vec![SimpleSequenceTemplate {
+        metadata: metadata(
+            "sequence-calibration",
+            "Calibration night",
+            "Cooled flats, darks and bias with the mount parked",
+            "calibration",
+            &["calibration"],
+        ),
+        sequence,
+    }]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::template_service::{
+        instantiate_exposure_template, instantiate_target_template,
+    };
+    use std::collections::{HashMap, HashSet};
+
+    #[test]
+    fn test_builtin_templates_instantiate_with_defaults() {
+        let values = HashMap::new();
+        for template in exposure_templates() {
+            let exposures = instantiate_exposure_template(&template, &values).unwrap();
+            assert!(!exposures.is_empty(), "{}", template.metadata.name);
+            assert!(exposures.iter().all(|e| e.validate().is_empty()));
+        }
+        for template in target_templates() {
+            let target = instantiate_target_template(&template, &values).unwrap();
+            assert_eq!(target.target_name, "Target");
+            assert!(target.validate().is_empty(), "{}", template.metadata.name);
+        }
+        for template in simple_sequence_templates() {
+            assert!(template.sequence.validate().is_empty());
+        }
+    }
+
+    #[test]
+    fn test_builtin_template_ids_are_unique() {
+        let ids: Vec<String> = exposure_templates()
+            .into_iter()
+            .map(|t| t.metadata)
+            .chain(target_templates().into_iter().map(|t| t.metadata))
+            .chain(simple_sequence_templates().into_iter().map(|t| t.metadata))
+            .inspect(|m| assert!(m.is_builtin && m.id.starts_with(BUILTIN_ID_PREFIX)))
+            .map(|m| m.id)
+            .collect();
+        let unique: HashSet<&String> = ids.iter().collect();
+        assert_eq!(unique.len(), ids.len());
+    }
+}
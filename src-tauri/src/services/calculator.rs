@@ -1,21 +1,66 @@
 //! Calculation services for astronomy and sequence timing
 
 use crate::models::*;
+use crate::services::astronomy;
 use chrono::{DateTime, Duration, Utc};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
 
 /// Calculate total runtime for a simple sequence
 pub fn calculate_sequence_runtime(sequence: &SimpleSequence) -> f64 {
     sequence.total_runtime()
 }
 
-/// Calculate ETA for all targets in a sequence
-pub fn calculate_sequence_etas(sequence: &mut SimpleSequence) {
-    sequence.calculate_etas();
+/// Calculate ETAs for all targets in a sequence and their exposure rows,
+/// including the dither, filter change and autofocus overheads when given
+pub fn calculate_sequence_etas(sequence: &mut SimpleSequence, overheads: Option<&OverheadProfile>) {
+    sequence.calculate_etas(overheads);
 }
 
-/// Calculate exposure runtime
-pub fn calculate_exposure_runtime(exposure: &SimpleExposure, download_time: f64) -> f64 {
-    exposure.runtime(download_time)
+/// Calculate exposure runtime, including dither settling when overheads are given
+pub fn calculate_exposure_runtime(
+    exposure: &SimpleExposure,
+    download_time: f64,
+    overheads: Option<&OverheadProfile>,
+) -> f64 {
+    exposure.runtime(download_time) + overheads.map_or(0.0, |o| dither_overhead(exposure, o))
+}
+
+/// Dither settle time spent while taking the remaining exposures
+pub fn dither_overhead(exposure: &SimpleExposure, overheads: &OverheadProfile) -> f64 {
+    exposure.dither_count() as f64 * overheads.dither_settle_time
+}
+
+/// Autofocus time of a target: one run at start and one per filter change
+/// in execution order when enabled, each with the duration of the filter
+/// in use
+pub fn autofocus_overhead(target: &SimpleTarget, overheads: &OverheadProfile) -> f64 {
+    let plan = target.execution_plan();
+    let mut filters = plan.iter().map(|step| {
+        target.exposures[step.exposure_index]
+            .filter
+            .as_ref()
+            .map(|f| f.name.as_str())
+    });
+    let Some(first) = filters.next() else {
+        return 0.0;
+    };
+
+    let mut total = if target.auto_focus_on_start {
+        overheads.autofocus_time(first)
+    } else {
+        0.0
+    };
+    if target.auto_focus_on_filter_change {
+        let mut current = first;
+        for filter in filters {
+            if filter != current {
+                total += overheads.autofocus_time(filter);
+                current = filter;
+            }
+        }
+    }
+    total
 }
 
 /// Calculate target runtime
@@ -23,6 +68,30 @@ pub fn calculate_target_runtime(target: &SimpleTarget, download_time: f64) -> f6
     target.runtime(download_time)
 }
 
+/// Calculate target runtime using per-binning download times from an equipment profile
+pub fn calculate_target_runtime_with_profile(
+    target: &SimpleTarget,
+    profile: &EquipmentProfile,
+) -> f64 {
+    target
+        .exposures
+        .iter()
+        .map(|e| e.runtime(profile.download_time(&e.binning)))
+        .sum()
+}
+
+/// Calculate sequence runtime using per-binning download times from an equipment profile
+pub fn calculate_sequence_runtime_with_profile(
+    sequence: &SimpleSequence,
+    profile: &EquipmentProfile,
+) -> f64 {
+    sequence
+        .targets
+        .iter()
+        .map(|t| calculate_target_runtime_with_profile(t, profile))
+        .sum()
+}
+
 /// Format duration in human-readable format
 pub fn format_duration(seconds: f64) -> String {
     if seconds < 0.0 {
@@ -61,6 +130,89 @@ pub fn angular_separation(coord1: &Coordinates, coord2: &Coordinates) -> f64 {
     coordinates::angular_separation(coord1, coord2)
 }
 
+/// Angular separation in degrees, 0 where rounding pushes the cosine
+/// just past 1 for identical positions
+fn separation_degrees(coord1: &Coordinates, coord2: &Coordinates) -> f64 {
+    let degrees = angular_separation(coord1, coord2);
+    if degrees.is_nan() {
+        0.0
+    } else {
+        degrees
+    }
+}
+
+/// Separations in degrees between every pair of positions, row by row;
+/// symmetric with a zero diagonal
+pub fn separation_matrix(coordinates: &[&Coordinates]) -> Vec<Vec<f64>> {
+    coordinates
+        .par_iter()
+        .enumerate()
+        .map(|(i, from)| {
+            coordinates
+                .iter()
+                .enumerate()
+                .map(|(j, to)| {
+                    if i == j {
+                        0.0
+                    } else {
+                        separation_degrees(from, to)
+                    }
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Separations between the targets of a sequence
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SeparationMatrix {
+    /// Target ids, in the order of the rows and columns
+    pub target_ids: Vec<String>,
+    /// Separations in degrees
+    pub separations: Vec<Vec<f64>>,
+}
+
+/// Separation matrix of `targets`
+pub fn calculate_separation_matrix(targets: &[SimpleTarget]) -> SeparationMatrix {
+    let coordinates: Vec<&Coordinates> = targets.iter().map(|t| &t.coordinates).collect();
+    SeparationMatrix {
+        target_ids: targets.iter().map(|t| t.id.clone()).collect(),
+        separations: separation_matrix(&coordinates),
+    }
+}
+
+/// A target near a position
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NearbyTarget {
+    pub target_id: String,
+    pub target_name: String,
+    /// Separation from the position in degrees
+    pub separation: f64,
+}
+
+/// Targets within `radius` degrees of a position, nearest first
+pub fn find_targets_near(
+    coordinates: &Coordinates,
+    targets: &[SimpleTarget],
+    radius: f64,
+) -> Vec<NearbyTarget> {
+    let mut nearby: Vec<NearbyTarget> = targets
+        .par_iter()
+        .filter_map(|target| {
+            let separation = separation_degrees(coordinates, &target.coordinates);
+            (separation <= radius).then(|| NearbyTarget {
+                target_id: target.id.clone(),
+                target_name: target.target_name.clone(),
+                separation,
+            })
+        })
+        .collect();
+    nearby.sort_by(|a, b| a.separation.total_cmp(&b.separation));
+    nearby
+}
+
 /// Convert RA from HMS to decimal hours
 pub fn ra_to_decimal(hours: i32, minutes: i32, seconds: f64) -> f64 {
     hours as f64 + minutes as f64 / 60.0 + seconds / 3600.0
@@ -178,16 +330,12 @@ pub fn is_above_horizon(
 
 /// Calculate moon phase (0 = new, 0.5 = full, 1 = new)
 pub fn calculate_moon_phase(datetime: DateTime<Utc>) -> f64 {
-    let jd = datetime_to_julian_day(datetime);
-    let days_since_new = (jd - 2451550.1) % 29.530588853;
-    days_since_new / 29.530588853
+    astronomy::moon_phase(astronomy::datetime_to_jd(datetime))
 }
 
 /// Calculate moon illumination percentage
 pub fn calculate_moon_illumination(datetime: DateTime<Utc>) -> f64 {
-    let phase = calculate_moon_phase(datetime);
-    let angle = phase * 2.0 * std::f64::consts::PI;
-    (1.0 - angle.cos()) / 2.0 * 100.0
+    astronomy::moon_illumination(astronomy::datetime_to_jd(datetime))
 }
 
 #[cfg(test)]
@@ -213,6 +361,36 @@ mod tests {
         assert!((decimal - 12.5).abs() < 0.001);
     }
 
+    #[test]
+    fn test_separation_matrix_and_nearby_targets() {
+        let target = |id: &str, coordinates: Coordinates| SimpleTarget {
+            id: id.to_string(),
+            target_name: id.to_string(),
+            coordinates,
+            ..Default::default()
+        };
+        let targets = vec![
+            target("M31", Coordinates::new(0, 42, 44.3, 41, 16, 9.0, false)),
+            target("M42", Coordinates::new(5, 35, 17.3, 5, 23, 28.0, true)),
+            target("M32", Coordinates::new(0, 42, 41.8, 40, 51, 55.0, false)),
+        ];
+
+        let matrix = calculate_separation_matrix(&targets);
+        assert_eq!(matrix.target_ids, ["M31", "M42", "M32"]);
+        for (i, row) in matrix.separations.iter().enumerate() {
+            assert_eq!(row[i], 0.0);
+            for (j, separation) in row.iter().enumerate() {
+                assert_eq!(*separation, matrix.separations[j][i]);
+            }
+        }
+        assert!((matrix.separations[0][2] - 0.4).abs() < 0.05);
+
+        let nearby = find_targets_near(&targets[0].coordinates, &targets, 1.0);
+        let ids: Vec<&str> = nearby.iter().map(|n| n.target_id.as_str()).collect();
+        assert_eq!(ids, ["M31", "M32"]);
+        assert!(find_targets_near(&targets[1].coordinates, &targets, 1.0).len() == 1);
+    }
+
     #[test]
     fn test_dec_conversion() {
         let (d, m, s, neg) = decimal_to_dec(-45.5);
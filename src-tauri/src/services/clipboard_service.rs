@@ -1,18 +1,22 @@
 //! Clipboard service for copy/paste operations
 
-use once_cell::sync::Lazy;
+use chrono::{DateTime, Utc};
 use parking_lot::RwLock;
 use serde::{Deserialize, Serialize};
-use std::sync::Arc;
+use std::path::PathBuf;
+use tokio::fs;
 
+use crate::error::{AppError, AppResult};
 use crate::models::{EditorSequenceItem, SimpleExposure, SimpleTarget};
+use crate::services::nina_type_registry::NinaTypeSchema;
+use crate::services::{file_service, nina_serializer};
 
 /// Clipboard content types
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type", content = "data")]
 pub enum ClipboardContent {
     /// Single target
-    Target(SimpleTarget),
+    Target(Box<SimpleTarget>),
     /// Multiple targets
     Targets(Vec<SimpleTarget>),
     /// Single exposure
@@ -29,167 +33,342 @@ pub enum ClipboardContent {
     Json(String),
 }
 
-/// Internal clipboard storage
-static CLIPBOARD: Lazy<Arc<RwLock<Option<ClipboardContent>>>> =
-    Lazy::new(|| Arc::new(RwLock::new(None)));
-
-/// Copy content to internal clipboard
-pub fn copy_to_clipboard(content: ClipboardContent) {
-    *CLIPBOARD.write() = Some(content);
-}
+impl ClipboardContent {
+    /// Content type name, as accepted by [`ClipboardState::has_content_type`]
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            ClipboardContent::Target(_) => "target",
+            ClipboardContent::Targets(_) => "targets",
+            ClipboardContent::Exposure(_) => "exposure",
+            ClipboardContent::Exposures(_) => "exposures",
+            ClipboardContent::SequenceItem(_) => "sequence_item",
+            ClipboardContent::SequenceItems(_) => "sequence_items",
+            ClipboardContent::Text(_) => "text",
+            ClipboardContent::Json(_) => "json",
+        }
+    }
 
-/// Get content from internal clipboard
-pub fn get_clipboard_content() -> Option<ClipboardContent> {
-    CLIPBOARD.read().clone()
+    /// Number of targets, exposures or items held
+    pub fn item_count(&self) -> usize {
+        match self {
+            ClipboardContent::Targets(targets) => targets.len(),
+            ClipboardContent::Exposures(exposures) => exposures.len(),
+            ClipboardContent::SequenceItems(items) => items.len(),
+            _ => 1,
+        }
+    }
 }
 
-/// Clear internal clipboard
-pub fn clear_clipboard() {
-    *CLIPBOARD.write() = None;
+/// Named clipboard slot, persisted across restarts
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClipboardSlot {
+    pub name: String,
+    pub content: ClipboardContent,
+    pub saved_at: DateTime<Utc>,
 }
 
-/// Check if clipboard has content
-pub fn has_clipboard_content() -> bool {
-    CLIPBOARD.read().is_some()
+/// Slot summary for listing
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClipboardSlotInfo {
+    pub name: String,
+    pub content_type: String,
+    pub item_count: usize,
+    pub saved_at: DateTime<Utc>,
 }
 
-/// Check if clipboard has specific content type
-pub fn has_clipboard_content_type(content_type: &str) -> bool {
-    if let Some(content) = CLIPBOARD.read().as_ref() {
-        matches!(
-            (content_type, content),
-            ("target", ClipboardContent::Target(_))
-                | ("targets", ClipboardContent::Targets(_))
-                | ("exposure", ClipboardContent::Exposure(_))
-                | ("exposures", ClipboardContent::Exposures(_))
-                | ("sequence_item", ClipboardContent::SequenceItem(_))
-                | ("sequence_items", ClipboardContent::SequenceItems(_))
-                | ("text", ClipboardContent::Text(_))
-                | ("json", ClipboardContent::Json(_))
-        )
-    } else {
-        false
+impl From<&ClipboardSlot> for ClipboardSlotInfo {
+    fn from(slot: &ClipboardSlot) -> Self {
+        Self {
+            name: slot.name.clone(),
+            content_type: slot.content.type_name().to_string(),
+            item_count: slot.content.item_count(),
+            saved_at: slot.saved_at,
+        }
     }
 }
 
-/// Copy target to clipboard
-pub fn copy_target(target: SimpleTarget) {
-    copy_to_clipboard(ClipboardContent::Target(target));
+/// Internal clipboard and named slots
+#[derive(Default)]
+pub struct ClipboardState {
+    content: RwLock<Option<ClipboardContent>>,
+    /// Named slots, loaded from disk on first use
+    slots: RwLock<Option<Vec<ClipboardSlot>>>,
+    /// NINA fragment last written to the system clipboard
+    system_fragment: RwLock<Option<String>>,
 }
 
-/// Copy multiple targets to clipboard
-pub fn copy_targets(targets: Vec<SimpleTarget>) {
-    copy_to_clipboard(ClipboardContent::Targets(targets));
+/// Copy of a target to paste: new ids and no progress
+fn fresh_target(mut target: SimpleTarget) -> SimpleTarget {
+    target.id = uuid::Uuid::new_v4().to_string();
+    target.name = format!("{} (Copy)", target.name);
+    target.target_name = format!("{} (Copy)", target.target_name);
+    target.exposures = target.exposures.into_iter().map(fresh_exposure).collect();
+    target.status = crate::models::SequenceEntityStatus::Created;
+    target
 }
 
-/// Copy exposure to clipboard
-pub fn copy_exposure(exposure: SimpleExposure) {
-    copy_to_clipboard(ClipboardContent::Exposure(exposure));
+/// Copy of an exposure to paste: a new id and no progress
+fn fresh_exposure(mut exposure: SimpleExposure) -> SimpleExposure {
+    exposure.id = uuid::Uuid::new_v4().to_string();
+    exposure.progress_count = 0;
+    exposure.status = crate::models::SequenceEntityStatus::Created;
+    exposure
 }
 
-/// Copy multiple exposures to clipboard
-pub fn copy_exposures(exposures: Vec<SimpleExposure>) {
-    copy_to_clipboard(ClipboardContent::Exposures(exposures));
-}
+impl ClipboardState {
+    /// Copy content to internal clipboard
+    pub fn copy(&self, content: ClipboardContent) {
+        *self.content.write() = Some(content);
+    }
+
+    /// Get content from internal clipboard
+    pub fn content(&self) -> Option<ClipboardContent> {
+        self.content.read().clone()
+    }
+
+    /// Clear internal clipboard
+    pub fn clear(&self) {
+        *self.content.write() = None;
+        *self.system_fragment.write() = None;
+    }
+
+    /// Check if clipboard has content
+    pub fn has_content(&self) -> bool {
+        self.content.read().is_some()
+    }
+
+    /// Check if clipboard has specific content type
+    pub fn has_content_type(&self, content_type: &str) -> bool {
+        self.content
+            .read()
+            .as_ref()
+            .is_some_and(|content| content.type_name() == content_type)
+    }
 
-/// Paste target from clipboard
-pub fn paste_target() -> Option<SimpleTarget> {
-    match get_clipboard_content()? {
-        ClipboardContent::Target(mut target) => {
-            // Generate new ID for pasted target
-            target.id = uuid::Uuid::new_v4().to_string();
-            target.name = format!("{} (Copy)", target.name);
-            target.target_name = format!("{} (Copy)", target.target_name);
-            // Reset progress
-            for exp in &mut target.exposures {
-                exp.id = uuid::Uuid::new_v4().to_string();
-                exp.progress_count = 0;
-                exp.status = crate::models::SequenceEntityStatus::Created;
+    /// Copy target to clipboard
+    pub fn copy_target(&self, target: SimpleTarget) {
+        self.copy(ClipboardContent::Target(Box::new(target)));
+    }
+
+    /// Copy multiple targets to clipboard
+    pub fn copy_targets(&self, targets: Vec<SimpleTarget>) {
+        self.copy(ClipboardContent::Targets(targets));
+    }
+
+    /// Copy exposure to clipboard
+    pub fn copy_exposure(&self, exposure: SimpleExposure) {
+        self.copy(ClipboardContent::Exposure(exposure));
+    }
+
+    /// Copy multiple exposures to clipboard
+    pub fn copy_exposures(&self, exposures: Vec<SimpleExposure>) {
+        self.copy(ClipboardContent::Exposures(exposures));
+    }
+
+    /// Paste target from clipboard
+    pub fn paste_target(&self) -> Option<SimpleTarget> {
+        match self.content()? {
+            ClipboardContent::Target(target) => Some(fresh_target(*target)),
+            _ => None,
+        }
+    }
+
+    /// Paste targets from clipboard
+    pub fn paste_targets(&self) -> Option<Vec<SimpleTarget>> {
+        match self.content()? {
+            ClipboardContent::Targets(targets) => {
+                Some(targets.into_iter().map(fresh_target).collect())
             }
-            target.status = crate::models::SequenceEntityStatus::Created;
-            Some(target)
+            ClipboardContent::Target(target) => Some(vec![fresh_target(*target)]),
+            _ => None,
+        }
+    }
+
+    /// Paste exposure from clipboard
+    pub fn paste_exposure(&self) -> Option<SimpleExposure> {
+        match self.content()? {
+            ClipboardContent::Exposure(exposure) => Some(fresh_exposure(exposure)),
+            _ => None,
         }
-        _ => None,
     }
-}
 
-/// Paste targets from clipboard
-pub fn paste_targets() -> Option<Vec<SimpleTarget>> {
-    match get_clipboard_content()? {
-        ClipboardContent::Targets(targets) => Some(
-            targets
-                .into_iter()
-                .map(|mut target| {
-                    target.id = uuid::Uuid::new_v4().to_string();
-                    target.name = format!("{} (Copy)", target.name);
-                    target.target_name = format!("{} (Copy)", target.target_name);
-                    for exp in &mut target.exposures {
-                        exp.id = uuid::Uuid::new_v4().to_string();
-                        exp.progress_count = 0;
-                        exp.status = crate::models::SequenceEntityStatus::Created;
-                    }
-                    target.status = crate::models::SequenceEntityStatus::Created;
-                    target
-                })
-                .collect(),
-        ),
-        ClipboardContent::Target(mut target) => {
-            target.id = uuid::Uuid::new_v4().to_string();
-            target.name = format!("{} (Copy)", target.name);
-            target.target_name = format!("{} (Copy)", target.target_name);
-            for exp in &mut target.exposures {
-                exp.id = uuid::Uuid::new_v4().to_string();
-                exp.progress_count = 0;
-                exp.status = crate::models::SequenceEntityStatus::Created;
+    /// Paste exposures from clipboard
+    pub fn paste_exposures(&self) -> Option<Vec<SimpleExposure>> {
+        match self.content()? {
+            ClipboardContent::Exposures(exposures) => {
+                Some(exposures.into_iter().map(fresh_exposure).collect())
             }
-            target.status = crate::models::SequenceEntityStatus::Created;
-            Some(vec![target])
+            ClipboardContent::Exposure(exposure) => Some(vec![fresh_exposure(exposure)]),
+            _ => None,
         }
-        _ => None,
     }
-}
 
-/// Paste exposure from clipboard
-pub fn paste_exposure() -> Option<SimpleExposure> {
-    match get_clipboard_content()? {
-        ClipboardContent::Exposure(mut exposure) => {
-            exposure.id = uuid::Uuid::new_v4().to_string();
-            exposure.progress_count = 0;
-            exposure.status = crate::models::SequenceEntityStatus::Created;
-            Some(exposure)
+    /// Remember the fragment written to the system clipboard so pasting it
+    /// back uses the internal copy, which keeps all editor fields
+    pub fn remember_system_fragment(&self, fragment: String) {
+        *self.system_fragment.write() = Some(fragment);
+    }
+
+    /// Sequence items to paste. NINA items on the system clipboard that were
+    /// copied in another application take precedence over the internal
+    /// clipboard.
+    pub fn sequence_content(
+        &self,
+        system_text: Option<&str>,
+        plugins: &[NinaTypeSchema],
+    ) -> Option<ClipboardContent> {
+        let foreign = system_text
+            .filter(|text| self.system_fragment.read().as_deref() != Some(*text))
+            .and_then(|text| nina_serializer::import_items_fragment(text, plugins).ok());
+        if let Some(mut items) = foreign {
+            return Some(if items.len() == 1 {
+                ClipboardContent::SequenceItem(items.remove(0))
+            } else {
+                ClipboardContent::SequenceItems(items)
+            });
+        }
+        self.content()
+    }
+
+    /// Load the slots, from memory or from the slots file
+    async fn load_slots(&self) -> Vec<ClipboardSlot> {
+        if let Some(slots) = self.slots.read().clone() {
+            return slots;
+        }
+
+        let slots: Vec<ClipboardSlot> = match fs::read_to_string(slots_path()).await {
+            Ok(content) => serde_json::from_str(&content)
+                .map_err(|e| log::warn!("Ignoring unreadable clipboard slots: {}", e))
+                .unwrap_or_default(),
+            Err(_) => Vec::new(),
+        };
+        *self.slots.write() = Some(slots.clone());
+        slots
+    }
+
+    async fn save_slots(&self, slots: Vec<ClipboardSlot>) -> AppResult<()> {
+        let path = slots_path();
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .await
+                .map_err(|e| AppError::Io(format!("Failed to create app data directory: {}", e)))?;
         }
-        _ => None,
+        let content = serde_json::to_string_pretty(&slots).map_err(|e| {
+            AppError::Internal(format!("Failed to serialize clipboard slots: {}", e))
+        })?;
+        fs::write(&path, content)
+            .await
+            .map_err(|e| AppError::Io(format!("Failed to write clipboard slots: {}", e)))?;
+
+        *self.slots.write() = Some(slots);
+        Ok(())
+    }
+
+    /// Store `content`, or the current clipboard content, in the named slot
+    pub async fn copy_to_slot(
+        &self,
+        name: &str,
+        content: Option<ClipboardContent>,
+    ) -> AppResult<ClipboardSlotInfo> {
+        let name = slot_name(name)?;
+        let content = content
+            .or_else(|| self.content())
+            .ok_or_else(|| AppError::InvalidInput("Clipboard is empty".to_string()))?;
+        let slot = ClipboardSlot {
+            name,
+            content,
+            saved_at: Utc::now(),
+        };
+        let info = ClipboardSlotInfo::from(&slot);
+
+        let mut slots = self.load_slots().await;
+        upsert_slot(&mut slots, slot);
+        self.save_slots(slots).await?;
+        Ok(info)
+    }
+
+    /// Load the named slot into the clipboard and return its content. The
+    /// regular paste functions then hand out copies with fresh IDs.
+    pub async fn paste_from_slot(&self, name: &str) -> AppResult<ClipboardContent> {
+        let name = slot_name(name)?;
+        let content = self
+            .load_slots()
+            .await
+            .into_iter()
+            .find(|s| s.name == name)
+            .map(|s| s.content)
+            .ok_or_else(|| AppError::NotFound(format!("Clipboard slot not found: {}", name)))?;
+        self.copy(content.clone());
+        Ok(content)
+    }
+
+    /// List the named slots
+    pub async fn list_slots(&self) -> Vec<ClipboardSlotInfo> {
+        self.load_slots()
+            .await
+            .iter()
+            .map(ClipboardSlotInfo::from)
+            .collect()
+    }
+
+    /// Delete the named slot
+    pub async fn delete_slot(&self, name: &str) -> AppResult<()> {
+        let name = slot_name(name)?;
+        let mut slots = self.load_slots().await;
+        let count = slots.len();
+        slots.retain(|s| s.name != name);
+        if slots.len() == count {
+            return Err(AppError::NotFound(format!(
+                "Clipboard slot not found: {}",
+                name
+            )));
+        }
+        self.save_slots(slots).await
+    }
+
+    /// Serialize clipboard content to JSON for system clipboard
+    pub fn serialize_content(&self) -> Option<String> {
+        let content = self.content()?;
+        serde_json::to_string(&content).ok()
     }
 }
 
-/// Paste exposures from clipboard
-pub fn paste_exposures() -> Option<Vec<SimpleExposure>> {
-    match get_clipboard_content()? {
-        ClipboardContent::Exposures(exposures) => Some(
-            exposures
-                .into_iter()
-                .map(|mut exp| {
-                    exp.id = uuid::Uuid::new_v4().to_string();
-                    exp.progress_count = 0;
-                    exp.status = crate::models::SequenceEntityStatus::Created;
-                    exp
-                })
-                .collect(),
-        ),
-        ClipboardContent::Exposure(mut exposure) => {
-            exposure.id = uuid::Uuid::new_v4().to_string();
-            exposure.progress_count = 0;
-            exposure.status = crate::models::SequenceEntityStatus::Created;
-            Some(vec![exposure])
+/// NINA JSON fragment of copied sequence items, for the system clipboard
+pub fn nina_fragment(content: &ClipboardContent, plugins: &[NinaTypeSchema]) -> Option<String> {
+    let fragment = match content {
+        ClipboardContent::SequenceItem(item) => {
+            nina_serializer::export_items_fragment(std::slice::from_ref(item), plugins)
         }
-        _ => None,
+        ClipboardContent::SequenceItems(items) => {
+            nina_serializer::export_items_fragment(items, plugins)
+        }
+        _ => return None,
+    };
+    fragment.ok()
+}
+
+fn slots_path() -> PathBuf {
+    file_service::get_app_data_directory().join("clipboard_slots.json")
+}
+
+/// Insert `slot`, replacing a slot with the same name
+fn upsert_slot(slots: &mut Vec<ClipboardSlot>, slot: ClipboardSlot) {
+    match slots.iter_mut().find(|s| s.name == slot.name) {
+        Some(existing) => *existing = slot,
+        None => slots.push(slot),
     }
 }
 
-/// Serialize clipboard content to JSON for system clipboard
-pub fn serialize_clipboard_content() -> Option<String> {
-    let content = get_clipboard_content()?;
-    serde_json::to_string(&content).ok()
+fn slot_name(name: &str) -> AppResult<String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(AppError::InvalidInput(
+            "Clipboard slot name is required".to_string(),
+        ));
+    }
+    Ok(name.to_string())
 }
 
 /// Deserialize clipboard content from JSON
@@ -202,66 +381,130 @@ mod tests {
     use super::*;
     use crate::models::*;
 
+    fn clipboard() -> ClipboardState {
+        ClipboardState::default()
+    }
+
     fn create_test_target() -> SimpleTarget {
-        let mut target = SimpleTarget::default();
-        target.name = "Test Target".to_string();
-        target.target_name = "M31".to_string();
-        target
+        SimpleTarget {
+            name: "Test Target".to_string(),
+            target_name: "M31".to_string(),
+            ..Default::default()
+        }
     }
 
     fn create_test_exposure() -> SimpleExposure {
-        let mut exp = SimpleExposure::default();
-        exp.exposure_time = 60.0;
-        exp.total_count = 10;
-        exp
+        SimpleExposure {
+            exposure_time: 60.0,
+            total_count: 10,
+            ..Default::default()
+        }
     }
 
     #[test]
     fn test_copy_paste_target() {
+        let clipboard = clipboard();
         let target = create_test_target();
         let original_id = target.id.clone();
 
-        copy_target(target);
-        assert!(has_clipboard_content());
-        assert!(has_clipboard_content_type("target"));
+        clipboard.copy_target(target);
+        assert!(clipboard.has_content());
+        assert!(clipboard.has_content_type("target"));
 
-        let pasted = paste_target().unwrap();
+        let pasted = clipboard.paste_target().unwrap();
         assert_ne!(pasted.id, original_id);
         assert!(pasted.name.contains("Copy"));
     }
 
     #[test]
     fn test_copy_paste_exposure() {
+        let clipboard = clipboard();
         let exposure = create_test_exposure();
         let original_id = exposure.id.clone();
 
-        copy_exposure(exposure);
-        assert!(has_clipboard_content());
+        clipboard.copy_exposure(exposure);
+        assert!(clipboard.has_content());
 
-        let pasted = paste_exposure().unwrap();
+        let pasted = clipboard.paste_exposure().unwrap();
         assert_ne!(pasted.id, original_id);
         assert_eq!(pasted.progress_count, 0);
     }
 
+    #[test]
+    fn test_paste_foreign_nina_fragment() {
+        let clipboard = clipboard();
+        let fragment = r#"{
+            "$type": "NINA.Sequencer.SequenceItem.Utility.WaitForTimeSpan, NINA.Sequencer",
+            "Name": "Wait for Time Span",
+            "Time": 60
+        }"#;
+
+        match clipboard.sequence_content(Some(fragment), &[]) {
+            Some(ClipboardContent::SequenceItem(item)) => {
+                assert!(item.item_type.contains("WaitForTimeSpan"));
+                assert_eq!(item.data.get("time"), Some(&serde_json::json!(60)));
+            }
+            other => panic!("unexpected clipboard content: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_upsert_slot() {
+        let slot = |name: &str, content: ClipboardContent| ClipboardSlot {
+            name: name.to_string(),
+            content,
+            saved_at: Utc::now(),
+        };
+        let mut slots = Vec::new();
+        upsert_slot(
+            &mut slots,
+            slot("LRGB", ClipboardContent::Exposure(create_test_exposure())),
+        );
+        upsert_slot(
+            &mut slots,
+            slot(
+                "LRGB",
+                ClipboardContent::Exposures(vec![create_test_exposure(); 4]),
+            ),
+        );
+        upsert_slot(&mut slots, slot("Note", ClipboardContent::Text("x".into())));
+
+        let infos: Vec<ClipboardSlotInfo> = slots.iter().map(ClipboardSlotInfo::from).collect();
+        assert_eq!(infos.len(), 2);
+        assert_eq!(infos[0].content_type, "exposures");
+        assert_eq!(infos[0].item_count, 4);
+        assert!(slot_name("  ").is_err());
+    }
+
+    #[test]
+    fn test_states_are_isolated() {
+        let (first, second) = (clipboard(), clipboard());
+        first.copy_target(create_test_target());
+        assert!(first.has_content());
+        assert!(!second.has_content());
+    }
+
     #[test]
     fn test_clear_clipboard() {
-        copy_target(create_test_target());
-        assert!(has_clipboard_content());
+        let clipboard = clipboard();
+        clipboard.copy_target(create_test_target());
+        assert!(clipboard.has_content());
 
-        clear_clipboard();
-        assert!(!has_clipboard_content());
+        clipboard.clear();
+        assert!(!clipboard.has_content());
     }
 
     #[test]
     fn test_serialize_deserialize() {
-        copy_target(create_test_target());
+        let clipboard = clipboard();
+        clipboard.copy_target(create_test_target());
 
-        let json = serialize_clipboard_content().unwrap();
-        clear_clipboard();
+        let json = clipboard.serialize_content().unwrap();
+        clipboard.clear();
 
         let content = deserialize_clipboard_content(&json).unwrap();
-        copy_to_clipboard(content);
+        clipboard.copy(content);
 
-        assert!(has_clipboard_content());
+        assert!(clipboard.has_content());
     }
 }
@@ -0,0 +1,171 @@
+//! Shared CSV reading and writing
+//!
+//! Follows RFC 4180: fields may be quoted, and quoted fields may contain
+//! delimiters, doubled quotes and line breaks. Readers also accept a UTF-8
+//! byte order mark and detect `,`, `;` and tab delimiters.
+
+use csv::{ReaderBuilder, Trim};
+
+/// Delimiters recognized by [`detect_delimiter`], in order of preference
+pub const CANDIDATE_DELIMITERS: [char; 3] = [',', ';', '\t'];
+
+/// Records looked at when detecting the delimiter
+const DETECTION_SAMPLE: usize = 10;
+
+/// Record read from CSV content
+#[derive(Debug, Clone, PartialEq)]
+pub struct CsvRecord {
+    /// 1-based line on which the record starts
+    pub line: usize,
+    pub fields: Vec<String>,
+}
+
+/// Remove a leading UTF-8 byte order mark
+pub fn strip_bom(content: &str) -> &str {
+    content.strip_prefix('\u{feff}').unwrap_or(content)
+}
+
+fn delimiter_byte(delimiter: char) -> Result<u8, String> {
+    u8::try_from(delimiter)
+        .ok()
+        .filter(|b| b.is_ascii() && *b != b'"')
+        .ok_or_else(|| format!("Unsupported CSV delimiter: {:?}", delimiter))
+}
+
+fn parse(content: &str, delimiter: u8) -> csv::StringRecordsIntoIter<&[u8]> {
+    ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .trim(Trim::All)
+        .delimiter(delimiter)
+        .from_reader(strip_bom(content).as_bytes())
+        .into_records()
+}
+
+/// Guess the delimiter: the candidate that splits the first record into
+/// the most fields while most sampled records agree on that count.
+/// Falls back to `,`.
+pub fn detect_delimiter(content: &str) -> char {
+    let mut best = (',', 1);
+    for candidate in CANDIDATE_DELIMITERS {
+        let counts: Vec<usize> = parse(content, candidate as u8)
+            .filter_map(|record| record.ok())
+            .filter(|record| record.iter().any(|f| !f.is_empty()))
+            .take(DETECTION_SAMPLE)
+            .map(|record| record.len())
+            .collect();
+        let Some(&first) = counts.first() else {
+            continue;
+        };
+        let consistent = counts.iter().filter(|&&n| n == first).count() * 2 > counts.len();
+        if consistent && first > best.1 {
+            best = (candidate, first);
+        }
+    }
+    best.0
+}
+
+/// Read all non-blank records. Without a delimiter it is detected.
+pub fn read_records(content: &str, delimiter: Option<char>) -> Result<Vec<CsvRecord>, String> {
+    let delimiter = delimiter.unwrap_or_else(|| detect_delimiter(content));
+    let mut records = Vec::new();
+
+    for record in parse(content, delimiter_byte(delimiter)?) {
+        let record = record.map_err(|e| format!("Invalid CSV: {}", e))?;
+        if record.iter().all(|f| f.is_empty()) {
+            continue;
+        }
+        records.push(CsvRecord {
+            line: record.position().map_or(0, |p| p.line() as usize),
+            fields: record.iter().map(str::to_string).collect(),
+        });
+    }
+
+    Ok(records)
+}
+
+/// Quote a field if it contains the delimiter, quotes or line breaks
+pub fn escape_field(value: &str, delimiter: char) -> String {
+    if value.contains([delimiter, '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Format one record, including the trailing newline
+pub fn write_record<S: AsRef<str>>(fields: &[S], delimiter: char) -> String {
+    let mut line = fields
+        .iter()
+        .map(|f| escape_field(f.as_ref(), delimiter))
+        .collect::<Vec<_>>()
+        .join(&delimiter.to_string());
+    line.push('\n');
+    line
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fields(records: &[CsvRecord]) -> Vec<Vec<&str>> {
+        records
+            .iter()
+            .map(|r| r.fields.iter().map(String::as_str).collect())
+            .collect()
+    }
+
+    #[test]
+    fn test_quoted_fields() {
+        let content = "name,notes\n\"NGC 7000, North America Nebula\",\"Say \"\"hi\"\"\nnext line\"\nM31,plain\n";
+        let records = read_records(content, None).unwrap();
+        assert_eq!(
+            fields(&records),
+            vec![
+                vec!["name", "notes"],
+                vec!["NGC 7000, North America Nebula", "Say \"hi\"\nnext line"],
+                vec!["M31", "plain"],
+            ]
+        );
+        assert_eq!(records[2].line, 4);
+    }
+
+    #[test]
+    fn test_bom_and_blank_lines() {
+        let records = read_records("\u{feff}name,ra\r\n\r\nM31,00:42:44\r\n", None).unwrap();
+        assert_eq!(
+            fields(&records),
+            vec![vec!["name", "ra"], vec!["M31", "00:42:44"]]
+        );
+    }
+
+    #[test]
+    fn test_detect_delimiter() {
+        assert_eq!(detect_delimiter("name;ra;dec\nM31;00:42:44;+41:16:09"), ';');
+        assert_eq!(
+            detect_delimiter("name\tra\tdec\nM31\t00:42:44\t+41:16:09"),
+            '\t'
+        );
+        assert_eq!(detect_delimiter("name,ra,dec\n\"A; B\",1,2"), ',');
+        // Decimal commas don't outvote the real delimiter
+        assert_eq!(
+            detect_delimiter("ra;dec;pa\n10,5;41,2;0,0\n11,5;42,2;0,0"),
+            ';'
+        );
+        assert_eq!(detect_delimiter("single"), ',');
+    }
+
+    #[test]
+    fn test_write_round_trip() {
+        let row = [
+            "NGC 7000, North America Nebula",
+            "a \"quote\"",
+            "two\nlines",
+        ];
+        let written = write_record(&row, ',');
+        let records = read_records(&written, Some(',')).unwrap();
+        assert_eq!(fields(&records), vec![row.to_vec()]);
+
+        assert_eq!(write_record(&["1,5", "2"], ';'), "1,5;2\n");
+    }
+}
@@ -0,0 +1,400 @@
+//! Monthly darkness calendar
+//!
+//! One entry per night of a month with the astronomical darkness, moon
+//! rise and set, illumination and an imaging grade, so a month view takes
+//! a single call instead of a twilight request per day. Each night runs
+//! from local noon to the next local noon and is sampled every few
+//! minutes, which also covers nights without a dusk or dawn near the
+//! poles.
+//!
+//! [`night_twilight`] finds the twilight times of a night over the same
+//! noon-to-noon window, so services planning a night pair the evening's
+//! dusk with the next morning's dawn at any longitude.
+
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::services::astronomy::{
+    datetime_to_jd, geometric_location, jd_to_datetime, moon_illumination, moon_phase,
+    moon_phase_name, moon_position, ra_dec_to_alt_az, sun_altitude, sunrise_altitude,
+    ObserverLocation, TwilightTimes,
+};
+
+/// Sampling step through the night
+const SAMPLE_MINUTES: i64 = 5;
+
+/// Geocentric Moon altitude at rise and set, allowing for parallax,
+/// semidiameter and refraction (Meeus 15)
+const MOON_RISE_ALTITUDE: f64 = 0.125;
+
+/// Sun altitude at the end of astronomical twilight
+const ASTRONOMICAL_DARK_ALTITUDE: f64 = -18.0;
+
+/// Sampling step when bracketing twilight crossings
+const TWILIGHT_SCAN_MINUTES: i64 = 10;
+
+/// Imaging quality of a night
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ImagingGrade {
+    Excellent,
+    Good,
+    Fair,
+    Poor,
+    /// No astronomical darkness
+    NoDarkness,
+}
+
+impl ImagingGrade {
+    /// Calendar color of the grade
+    pub fn color(&self) -> &'static str {
+        match self {
+            ImagingGrade::Excellent => "#2e7d32",
+            ImagingGrade::Good => "#7cb342",
+            ImagingGrade::Fair => "#fbc02d",
+            ImagingGrade::Poor => "#e64a19",
+            ImagingGrade::NoDarkness => "#9e9e9e",
+        }
+    }
+
+    /// Grade from the hours of darkness and the moonless equivalent of them
+    fn from_darkness(dark_hours: f64, effective_dark_hours: f64) -> Self {
+        if dark_hours < 0.1 {
+            return ImagingGrade::NoDarkness;
+        }
+        match effective_dark_hours / dark_hours {
+            ratio if ratio >= 0.85 => ImagingGrade::Excellent,
+            ratio if ratio >= 0.6 => ImagingGrade::Good,
+            ratio if ratio >= 0.35 => ImagingGrade::Fair,
+            _ => ImagingGrade::Poor,
+        }
+    }
+}
+
+/// One night of the calendar, starting on `date`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DarkCalendarDay {
+    pub date: String,
+    /// Start of the first astronomical darkness of the night
+    pub astronomical_dusk: Option<DateTime<Utc>>,
+    /// End of the last astronomical darkness of the night
+    pub astronomical_dawn: Option<DateTime<Utc>>,
+    pub dark_hours: f64,
+    /// Astronomical darkness with the Moon below the horizon
+    pub moon_free_dark_hours: f64,
+    pub moon_rise: Option<DateTime<Utc>>,
+    pub moon_set: Option<DateTime<Utc>>,
+    /// Percent, at the middle of the night
+    pub moon_illumination: f64,
+    pub moon_phase_name: String,
+    pub grade: ImagingGrade,
+    /// Color of the grade for the calendar cell
+    pub color: String,
+}
+
+/// Darkness calendar of one month
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DarkCalendar {
+    /// `YYYY-MM`
+    pub month: String,
+    pub days: Vec<DarkCalendarDay>,
+    pub total_dark_hours: f64,
+    pub total_moon_free_dark_hours: f64,
+}
+
+fn moon_altitude(location: &ObserverLocation, jd: f64) -> f64 {
+    let (ra, dec, _) = moon_position(jd);
+    ra_dec_to_alt_az(ra, dec, location.latitude, location.longitude, jd).0
+}
+
+/// Time between `before` and `after` where the Moon crosses the rise
+/// altitude
+fn moon_crossing(location: &ObserverLocation, before: f64, after: f64, rising: bool) -> f64 {
+    let (mut low, mut high) = (before, after);
+    for _ in 0..20 {
+        let mid = (low + high) / 2.0;
+        let above = moon_altitude(location, mid) >= MOON_RISE_ALTITUDE;
+        if above == rising {
+            high = mid;
+        } else {
+            low = mid;
+        }
+    }
+    (low + high) / 2.0
+}
+
+fn round_hours(hours: f64) -> f64 {
+    (hours * 100.0).round() / 100.0
+}
+
+/// Local noon on `date`, where the night starting that day begins
+pub fn night_start(location: &ObserverLocation, date: NaiveDate) -> DateTime<Utc> {
+    DateTime::<Utc>::from_naive_utc_and_offset(date.and_hms_opt(12, 0, 0).unwrap(), Utc)
+        - Duration::hours(location.timezone_offset as i64)
+}
+
+/// First time the Sun sets below `limit` and last time it rises above it,
+/// bracketed by the `altitudes` sampled at `jds` and refined by bisection
+fn night_crossings(
+    altitude: impl Fn(f64) -> f64,
+    jds: &[f64],
+    altitudes: &[f64],
+    limit: f64,
+) -> (Option<DateTime<Utc>>, Option<DateTime<Utc>>) {
+    let refine = |before: f64, after: f64, setting: bool| {
+        let (mut low, mut high) = (before, after);
+        for _ in 0..16 {
+            let mid = (low + high) / 2.0;
+            if (altitude(mid) < limit) == setting {
+                high = mid;
+            } else {
+                low = mid;
+            }
+        }
+        jd_to_datetime((low + high) / 2.0)
+    };
+
+    let mut setting = None;
+    let mut rising = None;
+    for i in 1..jds.len() {
+        let was_below = altitudes[i - 1] < limit;
+        let below = altitudes[i] < limit;
+        if !was_below && below && setting.is_none() {
+            setting = Some(refine(jds[i - 1], jds[i], true));
+        } else if was_below && !below {
+            rising = Some(refine(jds[i - 1], jds[i], false));
+        }
+    }
+    (setting, rising)
+}
+
+/// Twilight times of the night starting on `date`: sunset and dusks that
+/// evening, dawns and sunrise the next morning. Unlike
+/// [`calculate_twilight`](crate::services::astronomy::calculate_twilight),
+/// which searches a UTC day, the Sun is followed from local noon to the
+/// next local noon.
+pub fn night_twilight(location: &ObserverLocation, date: NaiveDate) -> TwilightTimes {
+    let horizon = sunrise_altitude(&location.corrections);
+    let geometric = geometric_location(location);
+    let start = datetime_to_jd(night_start(location, date));
+    let jds: Vec<f64> = (0..=24 * 60 / TWILIGHT_SCAN_MINUTES)
+        .map(|step| start + (step * TWILIGHT_SCAN_MINUTES) as f64 / 1440.0)
+        .collect();
+    let observed: Vec<f64> = jds.iter().map(|&jd| sun_altitude(location, jd)).collect();
+    let geometric_altitudes: Vec<f64> =
+        jds.iter().map(|&jd| sun_altitude(&geometric, jd)).collect();
+
+    let (sunset, sunrise) =
+        night_crossings(|jd| sun_altitude(location, jd), &jds, &observed, horizon);
+    let twilight = |limit: f64| {
+        night_crossings(
+            |jd| sun_altitude(&geometric, jd),
+            &jds,
+            &geometric_altitudes,
+            limit,
+        )
+    };
+    let (civil_dusk, civil_dawn) = twilight(-6.0);
+    let (nautical_dusk, nautical_dawn) = twilight(-12.0);
+    let (astronomical_dusk, astronomical_dawn) = twilight(ASTRONOMICAL_DARK_ALTITUDE);
+
+    TwilightTimes {
+        date: date.format("%Y-%m-%d").to_string(),
+        sunrise,
+        sunset,
+        civil_dawn,
+        civil_dusk,
+        nautical_dawn,
+        nautical_dusk,
+        astronomical_dawn,
+        astronomical_dusk,
+        is_polar_day: observed.iter().all(|&alt| alt > horizon),
+        is_polar_night: observed.iter().all(|&alt| alt < horizon),
+    }
+}
+
+/// Astronomical darkness of the night starting on `date`, from dusk to
+/// dawn. `None` when the Sun does not get 18° below the horizon.
+pub fn astronomical_night(
+    location: &ObserverLocation,
+    date: NaiveDate,
+) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+    let twilight = night_twilight(location, date);
+    match (twilight.astronomical_dusk, twilight.astronomical_dawn) {
+        (Some(dusk), Some(dawn)) if dawn > dusk => Some((dusk, dawn)),
+        _ => None,
+    }
+}
+
+/// Calendar entry for the night starting on `date`
+pub fn dark_calendar_day(location: &ObserverLocation, date: NaiveDate) -> DarkCalendarDay {
+    let local_noon = night_start(location, date);
+    let step_hours = SAMPLE_MINUTES as f64 / 60.0;
+    let steps = 24 * 60 / SAMPLE_MINUTES;
+
+    let mut dark_hours = 0.0;
+    let mut moon_free_dark_hours = 0.0;
+    let mut astronomical_dusk = None;
+    let mut astronomical_dawn = None;
+    let mut moon_rise = None;
+    let mut moon_set = None;
+
+    let mut previous: Option<(f64, bool, bool)> = None;
+    for step in 0..=steps {
+        let time = local_noon + Duration::minutes(step * SAMPLE_MINUTES);
+        let jd = datetime_to_jd(time);
+        let dark = sun_altitude(location, jd) < ASTRONOMICAL_DARK_ALTITUDE;
+        let moon_up = moon_altitude(location, jd) >= MOON_RISE_ALTITUDE;
+
+        if let Some((previous_jd, was_dark, moon_was_up)) = previous {
+            // Count each interval by its end sample
+            if dark {
+                dark_hours += step_hours;
+                if !moon_up {
+                    moon_free_dark_hours += step_hours;
+                }
+            }
+            if dark && !was_dark && astronomical_dusk.is_none() {
+                astronomical_dusk = Some(time);
+            }
+            if !dark && was_dark {
+                astronomical_dawn = Some(time);
+            }
+            if moon_up && !moon_was_up && moon_rise.is_none() {
+                moon_rise = Some(moon_crossing(location, previous_jd, jd, true));
+            }
+            if !moon_up && moon_was_up && moon_set.is_none() {
+                moon_set = Some(moon_crossing(location, previous_jd, jd, false));
+            }
+        }
+        previous = Some((jd, dark, moon_up));
+    }
+
+    let midnight = datetime_to_jd(local_noon + Duration::hours(12));
+    let illumination = moon_illumination(midnight);
+    let moonlit_hours = dark_hours - moon_free_dark_hours;
+    let effective_dark_hours = moon_free_dark_hours + moonlit_hours * (1.0 - illumination / 100.0);
+    let grade = ImagingGrade::from_darkness(dark_hours, effective_dark_hours);
+
+    DarkCalendarDay {
+        date: date.format("%Y-%m-%d").to_string(),
+        astronomical_dusk,
+        astronomical_dawn,
+        dark_hours: round_hours(dark_hours),
+        moon_free_dark_hours: round_hours(moon_free_dark_hours),
+        moon_rise: moon_rise.map(jd_to_datetime),
+        moon_set: moon_set.map(jd_to_datetime),
+        moon_illumination: illumination,
+        moon_phase_name: moon_phase_name(moon_phase(midnight)),
+        grade,
+        color: grade.color().to_string(),
+    }
+}
+
+/// Darkness calendar for every night of a month
+pub fn generate_dark_calendar(
+    location: &ObserverLocation,
+    year: i32,
+    month: u32,
+) -> Result<DarkCalendar, String> {
+    let first = NaiveDate::from_ymd_opt(year, month, 1)
+        .ok_or_else(|| format!("Invalid month: {}-{:02}", year, month))?;
+
+    let days: Vec<DarkCalendarDay> = first
+        .iter_days()
+        .take_while(|date| date.month() == month)
+        .map(|date| dark_calendar_day(location, date))
+        .collect();
+
+    Ok(DarkCalendar {
+        month: first.format("%Y-%m").to_string(),
+        total_dark_hours: round_hours(days.iter().map(|d| d.dark_hours).sum()),
+        total_moon_free_dark_hours: round_hours(days.iter().map(|d| d.moon_free_dark_hours).sum()),
+        days,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn munich() -> ObserverLocation {
+        ObserverLocation {
+            latitude: 48.14,
+            longitude: 11.58,
+            elevation: 520.0,
+            timezone_offset: 2,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_generate_dark_calendar() {
+        // New moon on 2024-10-02, full moon on 2024-10-17
+        let calendar = generate_dark_calendar(&munich(), 2024, 10).unwrap();
+        assert_eq!(calendar.month, "2024-10");
+        assert_eq!(calendar.days.len(), 31);
+
+        let new_moon = &calendar.days[1];
+        assert!(new_moon.dark_hours > 8.5 && new_moon.dark_hours < 11.0);
+        assert!(new_moon.moon_illumination < 5.0);
+        assert_eq!(new_moon.grade, ImagingGrade::Excellent);
+        assert!(new_moon.astronomical_dusk < new_moon.astronomical_dawn);
+
+        let full_moon = &calendar.days[16];
+        assert!(full_moon.moon_illumination > 95.0);
+        assert_eq!(full_moon.grade, ImagingGrade::Poor);
+        assert!(full_moon.moon_free_dark_hours < 1.0);
+        assert!(full_moon.moon_rise.is_some());
+
+        assert!(generate_dark_calendar(&munich(), 2024, 13).is_err());
+    }
+
+    #[test]
+    fn test_no_darkness_in_summer_at_high_latitude() {
+        let location = ObserverLocation {
+            latitude: 60.0,
+            longitude: 10.0,
+            ..Default::default()
+        };
+        let day = dark_calendar_day(&location, NaiveDate::from_ymd_opt(2024, 6, 21).unwrap());
+        assert_eq!(day.dark_hours, 0.0);
+        assert_eq!(day.grade, ImagingGrade::NoDarkness);
+        assert_eq!(day.color, "#9e9e9e");
+        assert!(
+            astronomical_night(&location, NaiveDate::from_ymd_opt(2024, 6, 21).unwrap()).is_none()
+        );
+    }
+
+    #[test]
+    fn test_night_twilight_west_of_greenwich() {
+        use chrono::TimeZone;
+
+        // New York's dusk falls after midnight UTC, on the next UTC day
+        let new_york = ObserverLocation {
+            latitude: 40.7128,
+            longitude: -74.006,
+            elevation: 10.0,
+            timezone_offset: -5,
+            ..Default::default()
+        };
+        let date = NaiveDate::from_ymd_opt(2024, 10, 1).unwrap();
+        let twilight = night_twilight(&new_york, date);
+
+        let dusk = twilight.astronomical_dusk.unwrap();
+        let dawn = twilight.astronomical_dawn.unwrap();
+        assert!(dusk > Utc.with_ymd_and_hms(2024, 10, 2, 0, 0, 0).unwrap());
+        assert!(dusk < Utc.with_ymd_and_hms(2024, 10, 2, 0, 30, 0).unwrap());
+        assert!(dawn > Utc.with_ymd_and_hms(2024, 10, 2, 9, 20, 0).unwrap());
+        assert!(dawn < Utc.with_ymd_and_hms(2024, 10, 2, 10, 0, 0).unwrap());
+        assert!(twilight.sunset.unwrap() < twilight.civil_dusk.unwrap());
+        assert!(twilight.nautical_dusk.unwrap() < dusk);
+        assert!(twilight.nautical_dawn.unwrap() > dawn);
+        assert!(twilight.sunrise.unwrap() > twilight.civil_dawn.unwrap());
+        assert!(!twilight.is_polar_day && !twilight.is_polar_night);
+
+        assert_eq!(astronomical_night(&new_york, date), Some((dusk, dawn)));
+    }
+}
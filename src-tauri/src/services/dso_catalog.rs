@@ -0,0 +1,1152 @@
+//! Built-in deep-sky object catalog
+//!
+//! The Messier objects and popular NGC/IC imaging targets with J2000
+//! positions, apparent size and visual magnitude. Small enough to ship in
+//! code and search without a network lookup; used to resolve object names
+//! and to pick targets for a night.
+
+use serde::{Deserialize, Serialize};
+
+use crate::models::Coordinates;
+
+/// Kind of deep-sky object
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DsoType {
+    Galaxy,
+    GlobularCluster,
+    OpenCluster,
+    EmissionNebula,
+    ReflectionNebula,
+    PlanetaryNebula,
+    SupernovaRemnant,
+    DarkNebula,
+    /// Star clouds, asterisms and double stars
+    Other,
+}
+
+impl DsoType {
+    pub fn label(&self) -> &'static str {
+        match self {
+            DsoType::Galaxy => "Galaxy",
+            DsoType::GlobularCluster => "Globular Cluster",
+            DsoType::OpenCluster => "Open Cluster",
+            DsoType::EmissionNebula => "Emission Nebula",
+            DsoType::ReflectionNebula => "Reflection Nebula",
+            DsoType::PlanetaryNebula => "Planetary Nebula",
+            DsoType::SupernovaRemnant => "Supernova Remnant",
+            DsoType::DarkNebula => "Dark Nebula",
+            DsoType::Other => "Other",
+        }
+    }
+}
+
+/// Catalog entry
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeepSkyObject {
+    /// Catalog designation, e.g. `M31` or `NGC 7000`
+    pub id: &'static str,
+    pub common_name: Option<&'static str>,
+    pub object_type: DsoType,
+    /// J2000 right ascension in hours
+    pub ra_hours: f64,
+    /// J2000 declination in degrees
+    pub dec_degrees: f64,
+    /// Major axis in arcminutes
+    pub size_arcmin: f64,
+    /// Visual magnitude, when defined for the object
+    pub magnitude: Option<f64>,
+}
+
+impl DeepSkyObject {
+    /// Catalog prefix of the designation (`M`, `NGC`, `IC`)
+    pub fn catalog(&self) -> &'static str {
+        let end = self
+            .id
+            .find(|c: char| !c.is_ascii_alphabetic())
+            .unwrap_or(self.id.len());
+        &self.id[..end]
+    }
+
+    /// Designation followed by the common name, if any
+    pub fn display_name(&self) -> String {
+        match self.common_name {
+            Some(name) => format!("{} {}", self.id, name),
+            None => self.id.to_string(),
+        }
+    }
+
+    pub fn coordinates(&self) -> Coordinates {
+        Coordinates::from_decimal(self.ra_hours, self.dec_degrees)
+    }
+}
+
+const fn dso(
+    id: &'static str,
+    common_name: Option<&'static str>,
+    object_type: DsoType,
+    ra_hours: f64,
+    dec_degrees: f64,
+    size_arcmin: f64,
+    magnitude: Option<f64>,
+) -> DeepSkyObject {
+    DeepSkyObject {
+        id,
+        common_name,
+        object_type,
+        ra_hours,
+        dec_degrees,
+        size_arcmin,
+        magnitude,
+    }
+}
+
+use DsoType::{
+    DarkNebula, EmissionNebula, Galaxy, GlobularCluster, OpenCluster, Other, PlanetaryNebula,
+    ReflectionNebula, SupernovaRemnant,
+};
+
+/// All catalog objects
+pub const DSO_CATALOG: &[DeepSkyObject] = &[
+    dso(
+        "M1",
+        Some("Crab Nebula"),
+        SupernovaRemnant,
+        5.5750,
+        22.0167,
+        6.0,
+        Some(8.4),
+    ),
+    dso(
+        "M2",
+        None,
+        GlobularCluster,
+        21.5583,
+        -0.8167,
+        16.0,
+        Some(6.5),
+    ),
+    dso(
+        "M3",
+        None,
+        GlobularCluster,
+        13.7033,
+        28.3833,
+        18.0,
+        Some(6.2),
+    ),
+    dso(
+        "M4",
+        None,
+        GlobularCluster,
+        16.3933,
+        -26.5333,
+        36.0,
+        Some(5.6),
+    ),
+    dso(
+        "M5",
+        None,
+        GlobularCluster,
+        15.3100,
+        2.0833,
+        23.0,
+        Some(5.6),
+    ),
+    dso(
+        "M6",
+        Some("Butterfly Cluster"),
+        OpenCluster,
+        17.6683,
+        -32.2167,
+        25.0,
+        Some(4.2),
+    ),
+    dso(
+        "M7",
+        Some("Ptolemy Cluster"),
+        OpenCluster,
+        17.8983,
+        -34.8167,
+        80.0,
+        Some(3.3),
+    ),
+    dso(
+        "M8",
+        Some("Lagoon Nebula"),
+        EmissionNebula,
+        18.0633,
+        -24.3833,
+        90.0,
+        Some(6.0),
+    ),
+    dso(
+        "M9",
+        None,
+        GlobularCluster,
+        17.3200,
+        -18.5167,
+        12.0,
+        Some(7.7),
+    ),
+    dso(
+        "M10",
+        None,
+        GlobularCluster,
+        16.9517,
+        -4.1000,
+        20.0,
+        Some(6.6),
+    ),
+    dso(
+        "M11",
+        Some("Wild Duck Cluster"),
+        OpenCluster,
+        18.8517,
+        -6.2667,
+        14.0,
+        Some(5.8),
+    ),
+    dso(
+        "M12",
+        None,
+        GlobularCluster,
+        16.7867,
+        -1.9500,
+        16.0,
+        Some(6.7),
+    ),
+    dso(
+        "M13",
+        Some("Hercules Cluster"),
+        GlobularCluster,
+        16.6950,
+        36.4667,
+        20.0,
+        Some(5.8),
+    ),
+    dso(
+        "M14",
+        None,
+        GlobularCluster,
+        17.6267,
+        -3.2500,
+        11.0,
+        Some(7.6),
+    ),
+    dso(
+        "M15",
+        None,
+        GlobularCluster,
+        21.5000,
+        12.1667,
+        18.0,
+        Some(6.2),
+    ),
+    dso(
+        "M16",
+        Some("Eagle Nebula"),
+        EmissionNebula,
+        18.3133,
+        -13.7833,
+        35.0,
+        Some(6.0),
+    ),
+    dso(
+        "M17",
+        Some("Omega Nebula"),
+        EmissionNebula,
+        18.3467,
+        -16.1833,
+        20.0,
+        Some(6.0),
+    ),
+    dso("M18", None, OpenCluster, 18.3317, -17.1333, 9.0, Some(7.5)),
+    dso(
+        "M19",
+        None,
+        GlobularCluster,
+        17.0433,
+        -26.2667,
+        17.0,
+        Some(6.8),
+    ),
+    dso(
+        "M20",
+        Some("Trifid Nebula"),
+        EmissionNebula,
+        18.0433,
+        -23.0333,
+        28.0,
+        Some(6.3),
+    ),
+    dso("M21", None, OpenCluster, 18.0767, -22.5000, 13.0, Some(6.5)),
+    dso(
+        "M22",
+        None,
+        GlobularCluster,
+        18.6067,
+        -23.9000,
+        32.0,
+        Some(5.1),
+    ),
+    dso("M23", None, OpenCluster, 17.9467, -19.0167, 27.0, Some(6.9)),
+    dso(
+        "M24",
+        Some("Sagittarius Star Cloud"),
+        Other,
+        18.2817,
+        -18.4833,
+        90.0,
+        Some(4.6),
+    ),
+    dso("M25", None, OpenCluster, 18.5267, -19.2500, 32.0, Some(4.6)),
+    dso("M26", None, OpenCluster, 18.7533, -9.4000, 15.0, Some(8.0)),
+    dso(
+        "M27",
+        Some("Dumbbell Nebula"),
+        PlanetaryNebula,
+        19.9933,
+        22.7167,
+        8.0,
+        Some(7.5),
+    ),
+    dso(
+        "M28",
+        None,
+        GlobularCluster,
+        18.4083,
+        -24.8667,
+        11.0,
+        Some(6.8),
+    ),
+    dso("M29", None, OpenCluster, 20.3983, 38.5333, 7.0, Some(7.1)),
+    dso(
+        "M30",
+        None,
+        GlobularCluster,
+        21.6733,
+        -23.1833,
+        12.0,
+        Some(7.2),
+    ),
+    dso(
+        "M31",
+        Some("Andromeda Galaxy"),
+        Galaxy,
+        0.7117,
+        41.2667,
+        190.0,
+        Some(3.4),
+    ),
+    dso("M32", None, Galaxy, 0.7117, 40.8667, 8.0, Some(8.1)),
+    dso(
+        "M33",
+        Some("Triangulum Galaxy"),
+        Galaxy,
+        1.5650,
+        30.6500,
+        70.0,
+        Some(5.7),
+    ),
+    dso("M34", None, OpenCluster, 2.7000, 42.7833, 35.0, Some(5.5)),
+    dso("M35", None, OpenCluster, 6.1483, 24.3333, 28.0, Some(5.3)),
+    dso("M36", None, OpenCluster, 5.6017, 34.1333, 12.0, Some(6.3)),
+    dso("M37", None, OpenCluster, 5.8733, 32.5500, 24.0, Some(6.2)),
+    dso("M38", None, OpenCluster, 5.4783, 35.8333, 21.0, Some(7.4)),
+    dso("M39", None, OpenCluster, 21.5367, 48.4333, 32.0, Some(4.6)),
+    dso(
+        "M40",
+        Some("Winnecke 4"),
+        Other,
+        12.3733,
+        58.0833,
+        1.0,
+        Some(8.4),
+    ),
+    dso("M41", None, OpenCluster, 6.7667, -20.7333, 38.0, Some(4.5)),
+    dso(
+        "M42",
+        Some("Orion Nebula"),
+        EmissionNebula,
+        5.5900,
+        -5.4500,
+        85.0,
+        Some(4.0),
+    ),
+    dso(
+        "M43",
+        Some("De Mairan's Nebula"),
+        EmissionNebula,
+        5.5933,
+        -5.2667,
+        20.0,
+        Some(9.0),
+    ),
+    dso(
+        "M44",
+        Some("Beehive Cluster"),
+        OpenCluster,
+        8.6683,
+        19.9833,
+        95.0,
+        Some(3.7),
+    ),
+    dso(
+        "M45",
+        Some("Pleiades"),
+        OpenCluster,
+        3.7833,
+        24.1167,
+        110.0,
+        Some(1.6),
+    ),
+    dso("M46", None, OpenCluster, 7.6967, -14.8167, 27.0, Some(6.1)),
+    dso("M47", None, OpenCluster, 7.6100, -14.5000, 30.0, Some(4.2)),
+    dso("M48", None, OpenCluster, 8.2300, -5.8000, 54.0, Some(5.5)),
+    dso("M49", None, Galaxy, 12.4967, 8.0000, 10.0, Some(8.4)),
+    dso("M50", None, OpenCluster, 7.0533, -8.3333, 16.0, Some(5.9)),
+    dso(
+        "M51",
+        Some("Whirlpool Galaxy"),
+        Galaxy,
+        13.4983,
+        47.2000,
+        11.0,
+        Some(8.4),
+    ),
+    dso("M52", None, OpenCluster, 23.4033, 61.5833, 13.0, Some(7.3)),
+    dso(
+        "M53",
+        None,
+        GlobularCluster,
+        13.2150,
+        18.1667,
+        13.0,
+        Some(7.6),
+    ),
+    dso(
+        "M54",
+        None,
+        GlobularCluster,
+        18.9183,
+        -30.4833,
+        12.0,
+        Some(7.6),
+    ),
+    dso(
+        "M55",
+        None,
+        GlobularCluster,
+        19.6667,
+        -30.9667,
+        19.0,
+        Some(6.3),
+    ),
+    dso(
+        "M56",
+        None,
+        GlobularCluster,
+        19.2767,
+        30.1833,
+        9.0,
+        Some(8.3),
+    ),
+    dso(
+        "M57",
+        Some("Ring Nebula"),
+        PlanetaryNebula,
+        18.8933,
+        33.0333,
+        1.4,
+        Some(8.8),
+    ),
+    dso("M58", None, Galaxy, 12.6283, 11.8167, 6.0, Some(9.7)),
+    dso("M59", None, Galaxy, 12.7000, 11.6500, 5.0, Some(9.6)),
+    dso("M60", None, Galaxy, 12.7283, 11.5500, 7.0, Some(8.8)),
+    dso("M61", None, Galaxy, 12.3650, 4.4667, 6.0, Some(9.7)),
+    dso(
+        "M62",
+        None,
+        GlobularCluster,
+        17.0200,
+        -30.1167,
+        15.0,
+        Some(6.5),
+    ),
+    dso(
+        "M63",
+        Some("Sunflower Galaxy"),
+        Galaxy,
+        13.2633,
+        42.0333,
+        13.0,
+        Some(8.6),
+    ),
+    dso(
+        "M64",
+        Some("Black Eye Galaxy"),
+        Galaxy,
+        12.9450,
+        21.6833,
+        10.0,
+        Some(8.5),
+    ),
+    dso("M65", None, Galaxy, 11.3150, 13.0833, 9.0, Some(9.3)),
+    dso("M66", None, Galaxy, 11.3367, 12.9833, 9.0, Some(8.9)),
+    dso("M67", None, OpenCluster, 8.8550, 11.8167, 30.0, Some(6.1)),
+    dso(
+        "M68",
+        None,
+        GlobularCluster,
+        12.6583,
+        -26.7500,
+        12.0,
+        Some(7.8),
+    ),
+    dso(
+        "M69",
+        None,
+        GlobularCluster,
+        18.5233,
+        -32.3500,
+        10.0,
+        Some(7.6),
+    ),
+    dso(
+        "M70",
+        None,
+        GlobularCluster,
+        18.7200,
+        -32.3000,
+        8.0,
+        Some(7.9),
+    ),
+    dso(
+        "M71",
+        None,
+        GlobularCluster,
+        19.8967,
+        18.7833,
+        7.0,
+        Some(8.2),
+    ),
+    dso(
+        "M72",
+        None,
+        GlobularCluster,
+        20.8917,
+        -12.5333,
+        7.0,
+        Some(9.3),
+    ),
+    dso("M73", None, Other, 20.9833, -12.6333, 3.0, Some(9.0)),
+    dso(
+        "M74",
+        Some("Phantom Galaxy"),
+        Galaxy,
+        1.6117,
+        15.7833,
+        10.0,
+        Some(9.4),
+    ),
+    dso(
+        "M75",
+        None,
+        GlobularCluster,
+        20.1017,
+        -21.9167,
+        7.0,
+        Some(8.5),
+    ),
+    dso(
+        "M76",
+        Some("Little Dumbbell Nebula"),
+        PlanetaryNebula,
+        1.7050,
+        51.5667,
+        3.0,
+        Some(10.1),
+    ),
+    dso("M77", None, Galaxy, 2.7117, -0.0167, 7.0, Some(8.9)),
+    dso(
+        "M78",
+        None,
+        ReflectionNebula,
+        5.7800,
+        0.0500,
+        8.0,
+        Some(8.3),
+    ),
+    dso(
+        "M79",
+        None,
+        GlobularCluster,
+        5.4083,
+        -24.5500,
+        10.0,
+        Some(7.7),
+    ),
+    dso(
+        "M80",
+        None,
+        GlobularCluster,
+        16.2833,
+        -22.9833,
+        10.0,
+        Some(7.3),
+    ),
+    dso(
+        "M81",
+        Some("Bode's Galaxy"),
+        Galaxy,
+        9.9267,
+        69.0667,
+        27.0,
+        Some(6.9),
+    ),
+    dso(
+        "M82",
+        Some("Cigar Galaxy"),
+        Galaxy,
+        9.9300,
+        69.6833,
+        11.0,
+        Some(8.4),
+    ),
+    dso(
+        "M83",
+        Some("Southern Pinwheel Galaxy"),
+        Galaxy,
+        13.6167,
+        -29.8667,
+        13.0,
+        Some(7.5),
+    ),
+    dso("M84", None, Galaxy, 12.4183, 12.8833, 6.0, Some(9.1)),
+    dso("M85", None, Galaxy, 12.4233, 18.1833, 7.0, Some(9.1)),
+    dso("M86", None, Galaxy, 12.4367, 12.9500, 9.0, Some(8.9)),
+    dso(
+        "M87",
+        Some("Virgo A"),
+        Galaxy,
+        12.5133,
+        12.3833,
+        8.0,
+        Some(8.6),
+    ),
+    dso("M88", None, Galaxy, 12.5333, 14.4167, 7.0, Some(9.6)),
+    dso("M89", None, Galaxy, 12.5950, 12.5500, 5.0, Some(9.8)),
+    dso("M90", None, Galaxy, 12.6133, 13.1667, 10.0, Some(9.5)),
+    dso("M91", None, Galaxy, 12.5900, 14.5000, 5.0, Some(10.2)),
+    dso(
+        "M92",
+        None,
+        GlobularCluster,
+        17.2850,
+        43.1333,
+        14.0,
+        Some(6.4),
+    ),
+    dso("M93", None, OpenCluster, 7.7433, -23.8667, 22.0, Some(6.0)),
+    dso("M94", None, Galaxy, 12.8483, 41.1167, 11.0, Some(8.2)),
+    dso("M95", None, Galaxy, 10.7333, 11.7000, 7.0, Some(9.7)),
+    dso("M96", None, Galaxy, 10.7800, 11.8167, 8.0, Some(9.2)),
+    dso(
+        "M97",
+        Some("Owl Nebula"),
+        PlanetaryNebula,
+        11.2467,
+        55.0167,
+        3.4,
+        Some(9.9),
+    ),
+    dso("M98", None, Galaxy, 12.2300, 14.9000, 10.0, Some(10.1)),
+    dso("M99", None, Galaxy, 12.3133, 14.4167, 5.0, Some(9.9)),
+    dso("M100", None, Galaxy, 12.3817, 15.8167, 7.0, Some(9.3)),
+    dso(
+        "M101",
+        Some("Pinwheel Galaxy"),
+        Galaxy,
+        14.0533,
+        54.3500,
+        29.0,
+        Some(7.9),
+    ),
+    dso(
+        "M102",
+        Some("Spindle Galaxy"),
+        Galaxy,
+        15.1083,
+        55.7667,
+        5.0,
+        Some(9.9),
+    ),
+    dso("M103", None, OpenCluster, 1.5533, 60.7000, 6.0, Some(7.4)),
+    dso(
+        "M104",
+        Some("Sombrero Galaxy"),
+        Galaxy,
+        12.6667,
+        -11.6167,
+        9.0,
+        Some(8.0),
+    ),
+    dso("M105", None, Galaxy, 10.7967, 12.5833, 5.0, Some(9.3)),
+    dso("M106", None, Galaxy, 12.3167, 47.3000, 19.0, Some(8.4)),
+    dso(
+        "M107",
+        None,
+        GlobularCluster,
+        16.5417,
+        -13.0500,
+        13.0,
+        Some(7.9),
+    ),
+    dso("M108", None, Galaxy, 11.1917, 55.6667, 8.0, Some(10.0)),
+    dso("M109", None, Galaxy, 11.9600, 53.3833, 8.0, Some(9.8)),
+    dso("M110", None, Galaxy, 0.6733, 41.6833, 22.0, Some(8.5)),
+    dso(
+        "NGC 253",
+        Some("Sculptor Galaxy"),
+        Galaxy,
+        0.7933,
+        -25.2833,
+        27.0,
+        Some(7.1),
+    ),
+    dso(
+        "NGC 281",
+        Some("Pacman Nebula"),
+        EmissionNebula,
+        0.8800,
+        56.6167,
+        35.0,
+        Some(7.4),
+    ),
+    dso(
+        "NGC 869",
+        Some("Double Cluster"),
+        OpenCluster,
+        2.3167,
+        57.1500,
+        30.0,
+        Some(5.3),
+    ),
+    dso("NGC 891", None, Galaxy, 2.3767, 42.3500, 13.0, Some(9.9)),
+    dso(
+        "NGC 1499",
+        Some("California Nebula"),
+        EmissionNebula,
+        4.0533,
+        36.4167,
+        145.0,
+        Some(6.0),
+    ),
+    dso(
+        "NGC 1977",
+        Some("Running Man Nebula"),
+        ReflectionNebula,
+        5.5883,
+        -4.8333,
+        20.0,
+        Some(7.0),
+    ),
+    dso(
+        "NGC 2024",
+        Some("Flame Nebula"),
+        EmissionNebula,
+        5.6983,
+        -1.8500,
+        30.0,
+        Some(10.0),
+    ),
+    dso(
+        "NGC 2174",
+        Some("Monkey Head Nebula"),
+        EmissionNebula,
+        6.1617,
+        20.5000,
+        40.0,
+        Some(6.8),
+    ),
+    dso(
+        "NGC 2237",
+        Some("Rosette Nebula"),
+        EmissionNebula,
+        6.5633,
+        4.9833,
+        80.0,
+        Some(9.0),
+    ),
+    dso(
+        "NGC 2264",
+        Some("Cone Nebula"),
+        EmissionNebula,
+        6.6833,
+        9.8833,
+        40.0,
+        Some(3.9),
+    ),
+    dso(
+        "NGC 2359",
+        Some("Thor's Helmet"),
+        EmissionNebula,
+        7.3083,
+        -13.2167,
+        10.0,
+        Some(11.5),
+    ),
+    dso("NGC 2403", None, Galaxy, 7.6150, 65.6000, 22.0, Some(8.4)),
+    dso(
+        "NGC 3372",
+        Some("Carina Nebula"),
+        EmissionNebula,
+        10.7517,
+        -59.8667,
+        120.0,
+        Some(1.0),
+    ),
+    dso(
+        "NGC 4565",
+        Some("Needle Galaxy"),
+        Galaxy,
+        12.6050,
+        25.9833,
+        16.0,
+        Some(9.6),
+    ),
+    dso(
+        "NGC 4631",
+        Some("Whale Galaxy"),
+        Galaxy,
+        12.7017,
+        32.5333,
+        15.0,
+        Some(9.2),
+    ),
+    dso(
+        "NGC 5128",
+        Some("Centaurus A"),
+        Galaxy,
+        13.4250,
+        -43.0167,
+        26.0,
+        Some(6.8),
+    ),
+    dso(
+        "NGC 6543",
+        Some("Cat's Eye Nebula"),
+        PlanetaryNebula,
+        17.9767,
+        66.6333,
+        0.4,
+        Some(8.1),
+    ),
+    dso(
+        "NGC 6888",
+        Some("Crescent Nebula"),
+        EmissionNebula,
+        20.2000,
+        38.3500,
+        18.0,
+        Some(7.4),
+    ),
+    dso(
+        "NGC 6946",
+        Some("Fireworks Galaxy"),
+        Galaxy,
+        20.5817,
+        60.1500,
+        11.0,
+        Some(8.9),
+    ),
+    dso(
+        "NGC 6960",
+        Some("Western Veil Nebula"),
+        SupernovaRemnant,
+        20.7600,
+        30.7167,
+        70.0,
+        Some(7.0),
+    ),
+    dso(
+        "NGC 6992",
+        Some("Eastern Veil Nebula"),
+        SupernovaRemnant,
+        20.9400,
+        31.7167,
+        60.0,
+        Some(7.0),
+    ),
+    dso(
+        "NGC 7000",
+        Some("North America Nebula"),
+        EmissionNebula,
+        20.9883,
+        44.5167,
+        120.0,
+        Some(4.0),
+    ),
+    dso(
+        "NGC 7023",
+        Some("Iris Nebula"),
+        ReflectionNebula,
+        21.0267,
+        68.1667,
+        18.0,
+        Some(6.8),
+    ),
+    dso(
+        "NGC 7293",
+        Some("Helix Nebula"),
+        PlanetaryNebula,
+        22.4933,
+        -20.8333,
+        25.0,
+        Some(7.6),
+    ),
+    dso("NGC 7331", None, Galaxy, 22.6183, 34.4167, 10.0, Some(9.5)),
+    dso(
+        "NGC 7635",
+        Some("Bubble Nebula"),
+        EmissionNebula,
+        23.3450,
+        61.2000,
+        15.0,
+        Some(10.0),
+    ),
+    dso(
+        "IC 405",
+        Some("Flaming Star Nebula"),
+        EmissionNebula,
+        5.2700,
+        34.2667,
+        37.0,
+        Some(6.0),
+    ),
+    dso(
+        "IC 410",
+        Some("Tadpoles Nebula"),
+        EmissionNebula,
+        5.3767,
+        33.5167,
+        40.0,
+        Some(7.5),
+    ),
+    dso(
+        "IC 434",
+        Some("Horsehead Nebula"),
+        DarkNebula,
+        5.6817,
+        -2.4667,
+        60.0,
+        None,
+    ),
+    dso(
+        "IC 1396",
+        Some("Elephant's Trunk Nebula"),
+        EmissionNebula,
+        21.6517,
+        57.5000,
+        170.0,
+        Some(3.5),
+    ),
+    dso(
+        "IC 1805",
+        Some("Heart Nebula"),
+        EmissionNebula,
+        2.5450,
+        61.4500,
+        150.0,
+        Some(6.5),
+    ),
+    dso(
+        "IC 1848",
+        Some("Soul Nebula"),
+        EmissionNebula,
+        2.8533,
+        60.4333,
+        150.0,
+        Some(6.5),
+    ),
+    dso(
+        "IC 5070",
+        Some("Pelican Nebula"),
+        EmissionNebula,
+        20.8467,
+        44.3500,
+        60.0,
+        Some(8.0),
+    ),
+    dso(
+        "IC 5146",
+        Some("Cocoon Nebula"),
+        EmissionNebula,
+        21.8917,
+        47.2667,
+        12.0,
+        Some(7.2),
+    ),
+];
+
+/// Catalog search criteria; empty criteria match every object
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct CatalogFilter {
+    pub object_types: Vec<DsoType>,
+    /// Catalog prefixes such as `M` or `NGC`
+    pub catalogs: Vec<String>,
+    /// Faintest visual magnitude; objects without a magnitude always pass
+    pub max_magnitude: Option<f64>,
+    /// Smallest major axis in arcminutes
+    pub min_size: Option<f64>,
+    /// Largest major axis in arcminutes
+    pub max_size: Option<f64>,
+    /// Text matched against the designation and common name
+    pub query: Option<String>,
+    /// Largest angular distance from the galactic plane (|b|) in degrees
+    pub max_galactic_latitude: Option<f64>,
+}
+
+impl CatalogFilter {
+    pub fn matches(&self, object: &DeepSkyObject) -> bool {
+        if !self.object_types.is_empty() && !self.object_types.contains(&object.object_type) {
+            return false;
+        }
+        if !self.catalogs.is_empty()
+            && !self
+                .catalogs
+                .iter()
+                .any(|c| c.eq_ignore_ascii_case(object.catalog()))
+        {
+            return false;
+        }
+        if let (Some(max), Some(magnitude)) = (self.max_magnitude, object.magnitude) {
+            if magnitude > max {
+                return false;
+            }
+        }
+        if self.min_size.is_some_and(|min| object.size_arcmin < min)
+            || self.max_size.is_some_and(|max| object.size_arcmin > max)
+        {
+            return false;
+        }
+        if let Some(max) = self.max_galactic_latitude {
+            if object.coordinates().to_galactic().latitude.abs() > max {
+                return false;
+            }
+        }
+        match self.query.as_deref().map(normalize_name) {
+            Some(query) if !query.is_empty() => {
+                normalize_name(object.id).contains(&query)
+                    || object
+                        .common_name
+                        .is_some_and(|name| normalize_name(name).contains(&query))
+            }
+            _ => true,
+        }
+    }
+}
+
+/// Lowercase alphanumerics only, so `M 31`, `m31` and `M-31` compare equal
+fn normalize_name(name: &str) -> String {
+    name.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(|c| c.to_lowercase())
+        .collect()
+}
+
+/// Objects matching the filter, in catalog order
+pub fn search_catalog(filter: &CatalogFilter) -> Vec<&'static DeepSkyObject> {
+    DSO_CATALOG.iter().filter(|o| filter.matches(o)).collect()
+}
+
+/// Look up an object by designation or common name
+pub fn find_object(name: &str) -> Option<&'static DeepSkyObject> {
+    let name = normalize_name(name);
+    if name.is_empty() {
+        return None;
+    }
+    DSO_CATALOG.iter().find(|o| {
+        normalize_name(o.id) == name || o.common_name.is_some_and(|n| normalize_name(n) == name)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_object() {
+        let m31 = find_object("m 31").unwrap();
+        assert_eq!(m31.id, "M31");
+        assert_eq!(m31.catalog(), "M");
+        assert_eq!(m31.display_name(), "M31 Andromeda Galaxy");
+        assert_eq!(find_object("North America Nebula").unwrap().id, "NGC 7000");
+        assert_eq!(find_object("ngc7000").unwrap().catalog(), "NGC");
+        assert!(find_object("M 3").is_some_and(|o| o.id == "M3"));
+        assert!(find_object("").is_none());
+        assert!(find_object("Nothing").is_none());
+
+        let coords = find_object("M42").unwrap().coordinates();
+        assert_eq!(coords.ra_hours, 5);
+        assert_eq!(coords.ra_minutes, 35);
+        assert!(coords.negative_dec);
+    }
+
+    #[test]
+    fn test_catalog_ids_are_unique() {
+        for (i, object) in DSO_CATALOG.iter().enumerate() {
+            assert!(
+                DSO_CATALOG[..i].iter().all(|o| o.id != object.id),
+                "{} listed twice",
+                object.id
+            );
+            assert!((0.0..24.0).contains(&object.ra_hours));
+            assert!((-90.0..=90.0).contains(&object.dec_degrees));
+        }
+        assert_eq!(
+            DSO_CATALOG.iter().filter(|o| o.catalog() == "M").count(),
+            110
+        );
+    }
+
+    #[test]
+    fn test_search_catalog() {
+        let filter = CatalogFilter {
+            object_types: vec![DsoType::PlanetaryNebula],
+            catalogs: vec!["m".to_string()],
+            ..Default::default()
+        };
+        let ids: Vec<&str> = search_catalog(&filter).iter().map(|o| o.id).collect();
+        assert_eq!(ids, ["M27", "M57", "M76", "M97"]);
+
+        let bright_large = CatalogFilter {
+            max_magnitude: Some(4.0),
+            min_size: Some(60.0),
+            ..Default::default()
+        };
+        assert!(search_catalog(&bright_large)
+            .iter()
+            .all(|o| o.size_arcmin >= 60.0 && o.magnitude.unwrap_or(0.0) <= 4.0));
+
+        let query = CatalogFilter {
+            query: Some("veil".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(search_catalog(&query).len(), 2);
+
+        let milky_way = CatalogFilter {
+            max_galactic_latitude: Some(10.0),
+            ..Default::default()
+        };
+        let ids: Vec<&str> = search_catalog(&milky_way).iter().map(|o| o.id).collect();
+        assert!(ids.contains(&"M8") && !ids.contains(&"M31"));
+        assert_eq!(
+            search_catalog(&CatalogFilter::default()).len(),
+            DSO_CATALOG.len()
+        );
+    }
+}
@@ -0,0 +1,310 @@
+//! Edit journal for crash recovery
+//!
+//! Between autosaves the frontend appends compact edit operations to a
+//! per-sequence journal file. Operations address the sequence's JSON form
+//! with JSON pointers (RFC 6901), so any edit can be recorded without a
+//! dedicated operation type. Recovery replays the journal on top of the
+//! last crash recovery snapshot.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use tokio::fs::{self, OpenOptions};
+use tokio::io::AsyncWriteExt;
+
+/// Edit operation on a sequence's JSON form
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "camelCase")]
+pub enum JournalOp {
+    /// Set an object field or replace an array element. An empty path
+    /// replaces the whole sequence; `-` as the last token appends.
+    Set { path: String, value: Value },
+    /// Insert into the array at `path` before `index`
+    Insert {
+        path: String,
+        index: usize,
+        value: Value,
+    },
+    /// Remove an object field or array element
+    Remove { path: String },
+    /// Move an array element within the array at `path`
+    Move {
+        path: String,
+        from: usize,
+        to: usize,
+    },
+}
+
+/// Journal line
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JournalEntry {
+    pub timestamp: DateTime<Utc>,
+    #[serde(flatten)]
+    pub op: JournalOp,
+}
+
+/// Journal file of a sequence, next to its crash recovery snapshot
+pub fn journal_path(directory: &Path, sequence_id: &str) -> PathBuf {
+    directory.join(format!("{}.journal", sequence_id))
+}
+
+/// Append operations to a journal, one JSON object per line
+pub async fn append(path: &Path, ops: &[JournalOp]) -> Result<(), String> {
+    if ops.is_empty() {
+        return Ok(());
+    }
+
+    let now = Utc::now();
+    let mut lines = String::new();
+    for op in ops {
+        let entry = JournalEntry {
+            timestamp: now,
+            op: op.clone(),
+        };
+        let line = serde_json::to_string(&entry)
+            .map_err(|e| format!("Failed to serialize journal entry: {}", e))?;
+        lines.push_str(&line);
+        lines.push('\n');
+    }
+
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .await
+        .map_err(|e| format!("Failed to open journal: {}", e))?;
+    file.write_all(lines.as_bytes())
+        .await
+        .map_err(|e| format!("Failed to write journal: {}", e))?;
+    file.flush()
+        .await
+        .map_err(|e| format!("Failed to write journal: {}", e))
+}
+
+/// Parse journal contents. A line that does not parse, typically one cut
+/// short by the crash, ends the journal.
+pub fn parse_journal(content: &str) -> Vec<JournalEntry> {
+    let mut entries = Vec::new();
+    for line in content.lines().filter(|l| !l.trim().is_empty()) {
+        match serde_json::from_str(line) {
+            Ok(entry) => entries.push(entry),
+            Err(e) => {
+                log::warn!("Journal truncated at unreadable entry: {}", e);
+                break;
+            }
+        }
+    }
+    entries
+}
+
+/// Read a journal, returning no entries when it does not exist
+pub async fn read(path: &Path) -> Result<Vec<JournalEntry>, String> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let content = fs::read_to_string(path)
+        .await
+        .map_err(|e| format!("Failed to read journal: {}", e))?;
+    Ok(parse_journal(&content))
+}
+
+/// Delete a journal
+pub async fn clear(path: &Path) -> Result<(), String> {
+    if path.exists() {
+        fs::remove_file(path)
+            .await
+            .map_err(|e| format!("Failed to delete journal: {}", e))?;
+    }
+    Ok(())
+}
+
+/// Split a JSON pointer into its parent pointer and unescaped last token
+fn split_pointer(path: &str) -> Result<(&str, String), String> {
+    let index = path
+        .rfind('/')
+        .ok_or_else(|| format!("Invalid JSON pointer: {}", path))?;
+    let token = path[index + 1..].replace("~1", "/").replace("~0", "~");
+    Ok((&path[..index], token))
+}
+
+fn lookup<'a>(root: &'a mut Value, path: &str) -> Result<&'a mut Value, String> {
+    root.pointer_mut(path)
+        .ok_or_else(|| format!("Path not found: {}", path))
+}
+
+fn array_at<'a>(root: &'a mut Value, path: &str) -> Result<&'a mut Vec<Value>, String> {
+    lookup(root, path)?
+        .as_array_mut()
+        .ok_or_else(|| format!("Not an array: {}", path))
+}
+
+fn array_index(token: &str, len: usize, allow_end: bool) -> Result<usize, String> {
+    let index = if token == "-" {
+        len
+    } else {
+        token
+            .parse::<usize>()
+            .map_err(|_| format!("Invalid array index: {}", token))?
+    };
+    if index < len || (allow_end && index == len) {
+        Ok(index)
+    } else {
+        Err(format!("Array index out of range: {}", index))
+    }
+}
+
+/// Apply one operation
+pub fn apply_op(root: &mut Value, op: &JournalOp) -> Result<(), String> {
+    match op {
+        JournalOp::Set { path, value } => {
+            if path.is_empty() {
+                *root = value.clone();
+                return Ok(());
+            }
+            let (parent, token) = split_pointer(path)?;
+            match lookup(root, parent)? {
+                Value::Object(map) => {
+                    map.insert(token, value.clone());
+                }
+                Value::Array(items) => {
+                    let index = array_index(&token, items.len(), true)?;
+                    if index == items.len() {
+                        items.push(value.clone());
+                    } else {
+                        items[index] = value.clone();
+                    }
+                }
+                _ => return Err(format!("Cannot set a field of a scalar: {}", parent)),
+            }
+        }
+        JournalOp::Insert { path, index, value } => {
+            let items = array_at(root, path)?;
+            let index = array_index(&index.to_string(), items.len(), true)?;
+            items.insert(index, value.clone());
+        }
+        JournalOp::Remove { path } => {
+            let (parent, token) = split_pointer(path)?;
+            match lookup(root, parent)? {
+                Value::Object(map) => {
+                    map.remove(&token)
+                        .ok_or_else(|| format!("Path not found: {}", path))?;
+                }
+                Value::Array(items) => {
+                    let index = array_index(&token, items.len(), false)?;
+                    items.remove(index);
+                }
+                _ => return Err(format!("Cannot remove a field of a scalar: {}", parent)),
+            }
+        }
+        JournalOp::Move { path, from, to } => {
+            let items = array_at(root, path)?;
+            let from = array_index(&from.to_string(), items.len(), false)?;
+            let to = array_index(&to.to_string(), items.len(), false)?;
+            let item = items.remove(from);
+            items.insert(to, item);
+        }
+    }
+    Ok(())
+}
+
+/// Replay journal entries in order. Replay stops at the first entry that
+/// cannot be applied; returns the number of entries applied.
+pub fn replay(root: &mut Value, entries: &[JournalEntry]) -> usize {
+    for (applied, entry) in entries.iter().enumerate() {
+        if let Err(e) = apply_op(root, &entry.op) {
+            log::warn!("Stopped journal replay at entry {}: {}", applied, e);
+            return applied;
+        }
+    }
+    entries.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn entry(op: JournalOp) -> JournalEntry {
+        JournalEntry {
+            timestamp: Utc::now(),
+            op,
+        }
+    }
+
+    #[test]
+    fn test_apply_ops() {
+        let mut root = json!({
+            "title": "Night",
+            "targets": [{ "name": "M31" }, { "name": "M42" }],
+        });
+
+        let entries = vec![
+            entry(JournalOp::Set {
+                path: "/title".to_string(),
+                value: json!("Night 2"),
+            }),
+            entry(JournalOp::Set {
+                path: "/targets/-".to_string(),
+                value: json!({ "name": "M81" }),
+            }),
+            entry(JournalOp::Insert {
+                path: "/targets".to_string(),
+                index: 0,
+                value: json!({ "name": "M1" }),
+            }),
+            entry(JournalOp::Move {
+                path: "/targets".to_string(),
+                from: 3,
+                to: 1,
+            }),
+            entry(JournalOp::Remove {
+                path: "/targets/2".to_string(),
+            }),
+            entry(JournalOp::Set {
+                path: "/targets/0/a~1b".to_string(),
+                value: json!(1),
+            }),
+        ];
+
+        assert_eq!(replay(&mut root, &entries), entries.len());
+        assert_eq!(
+            root,
+            json!({
+                "title": "Night 2",
+                "targets": [{ "name": "M1", "a/b": 1 }, { "name": "M81" }, { "name": "M42" }],
+            })
+        );
+    }
+
+    #[test]
+    fn test_replay_stops_at_failed_op() {
+        let mut root = json!({ "targets": [] });
+        let entries = vec![
+            entry(JournalOp::Remove {
+                path: "/targets/0".to_string(),
+            }),
+            entry(JournalOp::Set {
+                path: "/title".to_string(),
+                value: json!("Never applied"),
+            }),
+        ];
+
+        assert_eq!(replay(&mut root, &entries), 0);
+        assert_eq!(root, json!({ "targets": [] }));
+    }
+
+    #[test]
+    fn test_parse_journal_stops_at_torn_line() {
+        let line = serde_json::to_string(&entry(JournalOp::Remove {
+            path: "/title".to_string(),
+        }))
+        .unwrap();
+        assert!(line.contains("\"op\":\"remove\""));
+
+        let content = format!("{}\n{}\n{{\"timestamp\":", line, line);
+        assert_eq!(parse_journal(&content).len(), 2);
+    }
+}
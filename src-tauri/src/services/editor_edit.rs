@@ -0,0 +1,535 @@
+//! Editor sequence editing service
+//!
+//! Structural edits on the nested items of an editor sequence, done in
+//! the backend so ids stay unique and the item tree stays consistent.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+
+use crate::error::{AppError, AppResult};
+use crate::models::{EditorSequence, EditorSequenceItem, EditorTrigger, SequenceEntityStatus};
+
+/// Result of [`duplicate_editor_item`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EditorItemDuplicate {
+    pub sequence: EditorSequence,
+    /// Id of the copy
+    pub item_id: String,
+}
+
+fn item_not_found(id: &str) -> AppError {
+    AppError::NotFound(format!("Item {} not found", id))
+}
+
+fn not_a_parent(id: &str) -> AppError {
+    AppError::NotFound(format!("{} is not an area, container or trigger", id))
+}
+
+fn new_id() -> String {
+    uuid::Uuid::new_v4().to_string()
+}
+
+/// Result of [`set_items_status`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ItemStatusResult {
+    pub sequence: EditorSequence,
+    /// Number of items whose status changed
+    pub changed: usize,
+}
+
+/// Item list holding the item with `id`, and its index there
+fn find_item_list_mut<'a>(
+    items: &'a mut Vec<EditorSequenceItem>,
+    id: &str,
+) -> Option<(&'a mut Vec<EditorSequenceItem>, usize)> {
+    if let Some(index) = items.iter().position(|item| item.id == id) {
+        return Some((items, index));
+    }
+    for item in items.iter_mut() {
+        if let Some(children) = item.items.as_mut() {
+            if let Some(found) = find_item_list_mut(children, id) {
+                return Some(found);
+            }
+        }
+        if let Some(found) = find_in_triggers_mut(item.triggers.iter_mut().flatten(), id) {
+            return Some(found);
+        }
+    }
+    None
+}
+
+fn find_in_triggers_mut<'a>(
+    triggers: impl Iterator<Item = &'a mut EditorTrigger>,
+    id: &str,
+) -> Option<(&'a mut Vec<EditorSequenceItem>, usize)> {
+    for trigger in triggers {
+        if let Some(items) = trigger.trigger_items.as_mut() {
+            if let Some(found) = find_item_list_mut(items, id) {
+                return Some(found);
+            }
+        }
+    }
+    None
+}
+
+/// Item list of a sequence holding the item with `id`, searching every
+/// area and the items of global triggers
+fn find_in_sequence_mut<'a>(
+    sequence: &'a mut EditorSequence,
+    id: &str,
+) -> Option<(&'a mut Vec<EditorSequenceItem>, usize)> {
+    let EditorSequence {
+        start_items,
+        target_items,
+        end_items,
+        global_triggers,
+        ..
+    } = sequence;
+    find_item_list_mut(start_items, id)
+        .or_else(|| find_item_list_mut(target_items, id))
+        .or_else(|| find_item_list_mut(end_items, id))
+        .or_else(|| find_in_triggers_mut(global_triggers.iter_mut(), id))
+}
+
+/// Give an item and everything in it fresh ids, recording old to new
+fn regenerate_ids(item: &mut EditorSequenceItem, ids: &mut HashMap<String, String>) {
+    let id = new_id();
+    ids.insert(std::mem::replace(&mut item.id, id.clone()), id);
+    for condition in item.conditions.iter_mut().flatten() {
+        let id = new_id();
+        ids.insert(std::mem::replace(&mut condition.id, id.clone()), id);
+    }
+    for trigger in item.triggers.iter_mut().flatten() {
+        let id = new_id();
+        ids.insert(std::mem::replace(&mut trigger.id, id.clone()), id);
+        for child in trigger.trigger_items.iter_mut().flatten() {
+            regenerate_ids(child, ids);
+        }
+    }
+    for child in item.items.iter_mut().flatten() {
+        regenerate_ids(child, ids);
+    }
+}
+
+/// Point references to old ids inside data at the new ids
+fn remap_value(value: &mut Value, ids: &HashMap<String, String>) {
+    match value {
+        Value::String(s) => {
+            if let Some(id) = ids.get(s.as_str()) {
+                *s = id.clone();
+            }
+        }
+        Value::Array(values) => values.iter_mut().for_each(|v| remap_value(v, ids)),
+        Value::Object(object) => object.values_mut().for_each(|v| remap_value(v, ids)),
+        _ => {}
+    }
+}
+
+/// Fix id references and reset run state in a copied item tree
+fn prepare_copy(item: &mut EditorSequenceItem, ids: &HashMap<String, String>) {
+    if item.status != SequenceEntityStatus::Disabled {
+        item.status = SequenceEntityStatus::Created;
+    }
+    item.data.values_mut().for_each(|v| remap_value(v, ids));
+    for condition in item.conditions.iter_mut().flatten() {
+        condition
+            .data
+            .values_mut()
+            .for_each(|v| remap_value(v, ids));
+        if let Some(completed) = condition
+            .data
+            .iter_mut()
+            .find(|(k, _)| k.eq_ignore_ascii_case("completedIterations"))
+            .map(|(_, v)| v)
+        {
+            *completed = Value::from(0);
+        }
+    }
+    for trigger in item.triggers.iter_mut().flatten() {
+        trigger.data.values_mut().for_each(|v| remap_value(v, ids));
+        for child in trigger.trigger_items.iter_mut().flatten() {
+            prepare_copy(child, ids);
+        }
+    }
+    for child in item.items.iter_mut().flatten() {
+        prepare_copy(child, ids);
+    }
+}
+
+/// "Copy of <name>", numbered when a sibling already has that name
+fn copy_name(name: &str, siblings: &[EditorSequenceItem]) -> String {
+    let base = format!("Copy of {}", name);
+    let taken = |candidate: &str| siblings.iter().any(|s| s.name == candidate);
+    if !taken(&base) {
+        return base;
+    }
+    (2..)
+        .map(|n| format!("{} ({})", base, n))
+        .find(|candidate| !taken(candidate))
+        .unwrap_or(base)
+}
+
+/// Duplicate an item and insert the copy right after it. The copy and
+/// everything in it get fresh ids, and references to the old ids in item
+/// data are pointed at the new ones. With `deep` unset a container is
+/// copied without its items. Run state is reset in the copy; disabled
+/// items stay disabled.
+pub fn duplicate_editor_item(
+    sequence: &mut EditorSequence,
+    item_id: &str,
+    deep: bool,
+) -> AppResult<String> {
+    let (items, index) =
+        find_in_sequence_mut(sequence, item_id).ok_or_else(|| item_not_found(item_id))?;
+
+    let mut copy = items[index].clone();
+    if !deep {
+        if let Some(children) = copy.items.as_mut() {
+            children.clear();
+        }
+    }
+    let mut ids = HashMap::new();
+    regenerate_ids(&mut copy, &mut ids);
+    prepare_copy(&mut copy, &ids);
+    copy.name = copy_name(&copy.name, items);
+
+    let copy_id = copy.id.clone();
+    items.insert(index + 1, copy);
+    Ok(copy_id)
+}
+
+/// Parent ids of the sequence areas, for items at the top level
+pub const START_AREA: &str = "start";
+pub const TARGET_AREA: &str = "target";
+pub const END_AREA: &str = "end";
+
+/// Whether `id` is the item itself or anything inside it
+fn contains_id(item: &EditorSequenceItem, id: &str) -> bool {
+    item.id == id
+        || item.triggers.iter().flatten().any(|trigger| {
+            trigger.id == id
+                || trigger
+                    .trigger_items
+                    .iter()
+                    .flatten()
+                    .any(|child| contains_id(child, id))
+        })
+        || item
+            .items
+            .iter()
+            .flatten()
+            .any(|child| contains_id(child, id))
+}
+
+/// Children of the container or trigger with `id` among `items`
+fn find_children_mut<'a>(
+    items: &'a mut [EditorSequenceItem],
+    id: &str,
+) -> Option<&'a mut Vec<EditorSequenceItem>> {
+    for item in items {
+        if item.id == id {
+            if !item.is_container() {
+                return None;
+            }
+            return Some(item.items.get_or_insert_with(Vec::new));
+        }
+        if let Some(found) = find_trigger_children_mut(item.triggers.iter_mut().flatten(), id) {
+            return Some(found);
+        }
+        if let Some(found) = find_children_mut(item.items.as_deref_mut().unwrap_or_default(), id) {
+            return Some(found);
+        }
+    }
+    None
+}
+
+fn find_trigger_children_mut<'a>(
+    triggers: impl Iterator<Item = &'a mut EditorTrigger>,
+    id: &str,
+) -> Option<&'a mut Vec<EditorSequenceItem>> {
+    for trigger in triggers {
+        if trigger.id == id {
+            return Some(trigger.trigger_items.get_or_insert_with(Vec::new));
+        }
+        let items = trigger.trigger_items.as_deref_mut().unwrap_or_default();
+        if let Some(found) = find_children_mut(items, id) {
+            return Some(found);
+        }
+    }
+    None
+}
+
+/// Children of a parent: an area, a container or a trigger
+fn children_mut<'a>(
+    sequence: &'a mut EditorSequence,
+    parent_id: &str,
+) -> Option<&'a mut Vec<EditorSequenceItem>> {
+    match parent_id {
+        START_AREA => return Some(&mut sequence.start_items),
+        TARGET_AREA => return Some(&mut sequence.target_items),
+        END_AREA => return Some(&mut sequence.end_items),
+        _ => {}
+    }
+    let EditorSequence {
+        start_items,
+        target_items,
+        end_items,
+        global_triggers,
+        ..
+    } = sequence;
+    find_children_mut(start_items, parent_id)
+        .or_else(|| find_children_mut(target_items, parent_id))
+        .or_else(|| find_children_mut(end_items, parent_id))
+        .or_else(|| find_trigger_children_mut(global_triggers.iter_mut(), parent_id))
+}
+
+/// Move an item into a new parent at `index`, counted after the item is
+/// taken out; larger indices append. The parent is an area
+/// ([`START_AREA`], [`TARGET_AREA`], [`END_AREA`]), a container or a
+/// trigger. A container cannot move into itself or anything inside it.
+pub fn move_editor_item(
+    sequence: &mut EditorSequence,
+    item_id: &str,
+    new_parent_id: &str,
+    index: usize,
+) -> AppResult<()> {
+    let (items, position) =
+        find_in_sequence_mut(sequence, item_id).ok_or_else(|| item_not_found(item_id))?;
+    if contains_id(&items[position], new_parent_id) {
+        return Err(AppError::InvalidInput(format!(
+            "Cannot move '{}' into itself or an item inside it",
+            items[position].name
+        )));
+    }
+    if children_mut(sequence, new_parent_id).is_none() {
+        return Err(not_a_parent(new_parent_id));
+    }
+
+    let (items, position) =
+        find_in_sequence_mut(sequence, item_id).ok_or_else(|| item_not_found(item_id))?;
+    let item = items.remove(position);
+    let children =
+        children_mut(sequence, new_parent_id).ok_or_else(|| not_a_parent(new_parent_id))?;
+    let index = index.min(children.len());
+    children.insert(index, item);
+    Ok(())
+}
+
+/// Put the children of a parent in the order of `ordered_ids`, which must
+/// name each child exactly once
+pub fn reorder_items(
+    sequence: &mut EditorSequence,
+    parent_id: &str,
+    ordered_ids: &[String],
+) -> AppResult<()> {
+    let children = children_mut(sequence, parent_id).ok_or_else(|| not_a_parent(parent_id))?;
+
+    let mut reordered = Vec::with_capacity(children.len());
+    for id in ordered_ids {
+        if reordered
+            .iter()
+            .any(|item: &EditorSequenceItem| &item.id == id)
+        {
+            return Err(AppError::InvalidInput(format!(
+                "Item {} is listed twice",
+                id
+            )));
+        }
+        let item = children.iter().find(|item| &item.id == id).ok_or_else(|| {
+            AppError::InvalidInput(format!("Item {} is not in {}", id, parent_id))
+        })?;
+        reordered.push(item.clone());
+    }
+    if reordered.len() != children.len() {
+        return Err(AppError::InvalidInput(format!(
+            "Expected {} item ids, got {}",
+            children.len(),
+            ordered_ids.len()
+        )));
+    }
+    *children = reordered;
+    Ok(())
+}
+
+/// Set the status of each listed item, returning how many changed. All
+/// ids must exist, otherwise nothing is changed. Items inside a container
+/// keep their own status.
+pub fn set_items_status(
+    sequence: &mut EditorSequence,
+    item_ids: &[String],
+    status: SequenceEntityStatus,
+) -> AppResult<usize> {
+    if let Some(missing) = item_ids
+        .iter()
+        .find(|id| find_in_sequence_mut(sequence, id).is_none())
+    {
+        return Err(item_not_found(missing));
+    }
+
+    let mut changed = 0;
+    for id in item_ids {
+        if let Some((items, index)) = find_in_sequence_mut(sequence, id) {
+            if items[index].status != status {
+                items[index].status = status;
+                changed += 1;
+            }
+        }
+    }
+    Ok(changed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn item(id: &str, name: &str, items: Option<Vec<Value>>, data: Value) -> Value {
+        let mut value = json!({
+            "id": id,
+            "type": "NINA.Sequencer.Container.SequentialContainer, NINA.Sequencer",
+            "name": name,
+            "category": "Container",
+            "status": "FINISHED",
+            "data": data,
+        });
+        if let Some(items) = items {
+            value["items"] = json!(items);
+            value["conditions"] = json!([{
+                "id": format!("{}-loop", id),
+                "type": "NINA.Sequencer.Conditions.LoopCondition, NINA.Sequencer",
+                "name": "Loop",
+                "category": "Condition",
+                "data": { "Iterations": 5, "CompletedIterations": 3 },
+            }]);
+        }
+        value
+    }
+
+    fn sequence() -> EditorSequence {
+        let mut sequence = EditorSequence::new("Plan");
+        let tree = item(
+            "outer",
+            "Block",
+            Some(vec![
+                item("inner", "Inner", Some(vec![]), json!({})),
+                item("leaf", "Leaf", None, json!({ "ParentId": "outer" })),
+            ]),
+            json!({}),
+        );
+        sequence.target_items = vec![serde_json::from_value(tree).unwrap()];
+        sequence
+    }
+
+    #[test]
+    fn test_duplicate_deep() {
+        let mut sequence = sequence();
+        let copy_id = duplicate_editor_item(&mut sequence, "outer", true).unwrap();
+
+        assert_eq!(sequence.target_items.len(), 2);
+        let copy = &sequence.target_items[1];
+        assert_eq!(copy.id, copy_id);
+        assert_eq!(copy.name, "Copy of Block");
+        assert_eq!(copy.status, SequenceEntityStatus::Created);
+
+        let original_ids = sequence.target_items[0].get_all_item_ids();
+        let copy_ids = copy.get_all_item_ids();
+        assert_eq!(copy_ids.len(), 3);
+        assert!(copy_ids.iter().all(|id| !original_ids.contains(id)));
+
+        // The reference to the parent follows the copy
+        let leaf = &copy.items.as_ref().unwrap()[1];
+        assert_eq!(leaf.data["ParentId"], json!(copy_id));
+        assert_eq!(leaf.name, "Leaf");
+
+        let loop_condition = &copy.conditions.as_ref().unwrap()[0];
+        assert_ne!(loop_condition.id, "outer-loop");
+        assert_eq!(loop_condition.data["CompletedIterations"], 0);
+
+        // Duplicating again numbers the name
+        duplicate_editor_item(&mut sequence, "outer", true).unwrap();
+        assert_eq!(sequence.target_items[1].name, "Copy of Block (2)");
+    }
+
+    #[test]
+    fn test_duplicate_nested_shallow() {
+        let mut sequence = sequence();
+        let copy_id = duplicate_editor_item(&mut sequence, "inner", false).unwrap();
+
+        let children = sequence.target_items[0].items.as_ref().unwrap();
+        assert_eq!(children.len(), 3);
+        assert_eq!(children[1].id, copy_id);
+        assert!(children[1].items.as_ref().unwrap().is_empty());
+
+        assert!(duplicate_editor_item(&mut sequence, "missing", true).is_err());
+    }
+
+    #[test]
+    fn test_set_items_status() {
+        let mut sequence = sequence();
+        let outer_status = sequence.target_items[0].status;
+        let ids = vec!["inner".to_string(), "leaf".to_string()];
+
+        let changed =
+            set_items_status(&mut sequence, &ids, SequenceEntityStatus::Disabled).unwrap();
+        assert_eq!(changed, 2);
+        let outer = &sequence.target_items[0];
+        assert_eq!(outer.status, outer_status);
+        assert!(outer
+            .items
+            .iter()
+            .flatten()
+            .all(|item| item.status == SequenceEntityStatus::Disabled));
+
+        let ids = vec!["outer".to_string(), "missing".to_string()];
+        assert!(set_items_status(&mut sequence, &ids, SequenceEntityStatus::Disabled).is_err());
+        assert_eq!(sequence.target_items[0].status, outer_status);
+    }
+
+    fn child_ids(items: &[EditorSequenceItem]) -> Vec<&str> {
+        items.iter().map(|item| item.id.as_str()).collect()
+    }
+
+    #[test]
+    fn test_move_editor_item() {
+        let mut sequence = sequence();
+
+        move_editor_item(&mut sequence, "leaf", "inner", 0).unwrap();
+        let outer = &sequence.target_items[0];
+        assert_eq!(child_ids(outer.items.as_ref().unwrap()), ["inner"]);
+        let inner = &outer.items.as_ref().unwrap()[0];
+        assert_eq!(child_ids(inner.items.as_ref().unwrap()), ["leaf"]);
+
+        // Indices past the end append
+        move_editor_item(&mut sequence, "inner", START_AREA, 10).unwrap();
+        assert_eq!(child_ids(&sequence.start_items), ["inner"]);
+        assert!(sequence.target_items[0].items.as_ref().unwrap().is_empty());
+
+        // No moving into itself, a descendant or a plain item
+        let err = move_editor_item(&mut sequence, "inner", "leaf", 0).unwrap_err();
+        assert!(err.to_string().contains("into itself"), "{}", err);
+        assert!(move_editor_item(&mut sequence, "inner", "inner", 0).is_err());
+        assert_eq!(
+            move_editor_item(&mut sequence, "outer", "missing", 0).unwrap_err(),
+            not_a_parent("missing")
+        );
+        assert_eq!(child_ids(&sequence.start_items), ["inner"]);
+    }
+
+    #[test]
+    fn test_reorder_items() {
+        let mut sequence = sequence();
+        let ids = |ids: &[&str]| ids.iter().map(|id| id.to_string()).collect::<Vec<_>>();
+
+        reorder_items(&mut sequence, "outer", &ids(&["leaf", "inner"])).unwrap();
+        let outer = &sequence.target_items[0];
+        assert_eq!(child_ids(outer.items.as_ref().unwrap()), ["leaf", "inner"]);
+
+        assert!(reorder_items(&mut sequence, "outer", &ids(&["leaf"])).is_err());
+        assert!(reorder_items(&mut sequence, "outer", &ids(&["leaf", "leaf"])).is_err());
+        assert!(reorder_items(&mut sequence, "outer", &ids(&["leaf", "outer"])).is_err());
+        reorder_items(&mut sequence, TARGET_AREA, &ids(&["outer"])).unwrap();
+    }
+}
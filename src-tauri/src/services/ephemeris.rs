@@ -0,0 +1,507 @@
+//! Ephemeris service for moving targets
+//!
+//! Computes positions of comets and asteroids from orbital elements or
+//! ephemeris tables, and imports both from common formats:
+//! - MPC one-line orbital elements (CometEls.txt and MPCORB.DAT)
+//! - JPL Horizons observer tables
+
+use chrono::{DateTime, Duration, NaiveDate, NaiveDateTime, Utc};
+
+use crate::models::{
+    Coordinates, EphemerisEntry, MovingTarget, MovingTargetKind, OrbitalElements, SimpleSequence,
+    SimpleTarget,
+};
+use crate::services::astronomy::{
+    datetime_to_jd, sun_distance, sun_ecliptic_longitude, ObserverLocation,
+};
+
+/// Gaussian gravitational constant (rad/day)
+const GAUSS_K: f64 = 0.01720209895;
+/// Obliquity of the ecliptic at J2000 (degrees)
+const OBLIQUITY_J2000: f64 = 23.4392911;
+/// Light time for 1 AU (days)
+const LIGHT_TIME_PER_AU: f64 = 0.0057755183;
+
+// ============================================================================
+// Position Calculation
+// ============================================================================
+
+/// Heliocentric ecliptic (J2000) position in AU
+fn heliocentric_position(elements: &OrbitalElements, jd: f64) -> [f64; 3] {
+    let q = elements.perihelion_distance;
+    let e = elements.eccentricity;
+    let t = jd - elements.perihelion_time;
+
+    let (r, nu) = if (e - 1.0).abs() < 1e-6 {
+        // Parabolic (Barker's equation)
+        let w = 3.0 * GAUSS_K / (2.0f64.sqrt() * q.powf(1.5)) * t;
+        let y = (w / 2.0 + (w * w / 4.0 + 1.0).sqrt()).cbrt();
+        let s = y - 1.0 / y;
+        (q * (1.0 + s * s), 2.0 * s.atan())
+    } else if e < 1.0 {
+        let a = q / (1.0 - e);
+        let m = (GAUSS_K / a.powf(1.5) * t).rem_euclid(2.0 * std::f64::consts::PI);
+        let mut ecc = if e > 0.8 { std::f64::consts::PI } else { m };
+        for _ in 0..50 {
+            let delta = (ecc - e * ecc.sin() - m) / (1.0 - e * ecc.cos());
+            ecc -= delta;
+            if delta.abs() < 1e-12 {
+                break;
+            }
+        }
+        let x = a * (ecc.cos() - e);
+        let y = a * (1.0 - e * e).sqrt() * ecc.sin();
+        ((x * x + y * y).sqrt(), y.atan2(x))
+    } else {
+        let a = q / (e - 1.0);
+        let m = GAUSS_K / a.powf(1.5) * t;
+        let mut h = (2.0 * m / e).asinh();
+        for _ in 0..50 {
+            let delta = (e * h.sinh() - h - m) / (e * h.cosh() - 1.0);
+            h -= delta;
+            if delta.abs() < 1e-12 {
+                break;
+            }
+        }
+        let x = a * (e - h.cosh());
+        let y = a * (e * e - 1.0).sqrt() * h.sinh();
+        ((x * x + y * y).sqrt(), y.atan2(x))
+    };
+
+    let u = nu + elements.arg_perihelion.to_radians();
+    let node = elements.ascending_node.to_radians();
+    let inc = elements.inclination.to_radians();
+
+    [
+        r * (node.cos() * u.cos() - node.sin() * u.sin() * inc.cos()),
+        r * (node.sin() * u.cos() + node.cos() * u.sin() * inc.cos()),
+        r * u.sin() * inc.sin(),
+    ]
+}
+
+/// Heliocentric ecliptic (J2000) position of the Earth in AU
+fn earth_position(jd: f64) -> [f64; 3] {
+    // Sun longitude is referred to the equinox of date; remove general precession
+    let years = (jd - 2451545.0) / 365.25;
+    let longitude = (sun_ecliptic_longitude(jd) - 0.013969713 * years).to_radians();
+    let r = sun_distance(jd);
+    [-r * longitude.cos(), -r * longitude.sin(), 0.0]
+}
+
+/// Geocentric astrometric RA (hours) / Dec (degrees), J2000, corrected
+/// for light time
+pub fn elements_position(elements: &OrbitalElements, jd: f64) -> (f64, f64) {
+    let earth = earth_position(jd);
+    let geocentric = |t: f64| {
+        let p = heliocentric_position(elements, t);
+        [p[0] - earth[0], p[1] - earth[1], p[2] - earth[2]]
+    };
+
+    let mut d = geocentric(jd);
+    let distance = (d[0] * d[0] + d[1] * d[1] + d[2] * d[2]).sqrt();
+    d = geocentric(jd - distance * LIGHT_TIME_PER_AU);
+
+    let eps = OBLIQUITY_J2000.to_radians();
+    let x = d[0];
+    let y = d[1] * eps.cos() - d[2] * eps.sin();
+    let z = d[1] * eps.sin() + d[2] * eps.cos();
+
+    let ra = (y.atan2(x).to_degrees() / 15.0).rem_euclid(24.0);
+    let dec = z.atan2((x * x + y * y).sqrt()).to_degrees();
+    (ra, dec)
+}
+
+/// Linearly interpolate an ephemeris table; None outside its range
+pub fn interpolate_ephemeris(
+    entries: &[EphemerisEntry],
+    time: DateTime<Utc>,
+) -> Option<(f64, f64)> {
+    let idx = entries.partition_point(|e| e.time <= time);
+    if idx == 0 {
+        return None;
+    }
+    let before = &entries[idx - 1];
+    if before.time == time {
+        return Some((before.ra, before.dec));
+    }
+    let after = entries.get(idx)?;
+
+    let span = (after.time - before.time).num_milliseconds() as f64;
+    let frac = (time - before.time).num_milliseconds() as f64 / span;
+
+    let mut d_ra = after.ra - before.ra;
+    if d_ra > 12.0 {
+        d_ra -= 24.0;
+    } else if d_ra < -12.0 {
+        d_ra += 24.0;
+    }
+
+    Some((
+        (before.ra + d_ra * frac).rem_euclid(24.0),
+        before.dec + (after.dec - before.dec) * frac,
+    ))
+}
+
+/// Position of a moving target at the given time
+pub fn moving_target_coordinates(
+    target: &MovingTarget,
+    time: DateTime<Utc>,
+) -> Option<Coordinates> {
+    let (ra, dec) = interpolate_ephemeris(&target.ephemeris, time).or_else(|| {
+        target
+            .elements
+            .as_ref()
+            .map(|elements| elements_position(elements, datetime_to_jd(time)))
+    })?;
+    Some(Coordinates::from_decimal(ra, dec))
+}
+
+/// Coordinates of a target at the given time, following moving targets
+pub fn resolve_target_coordinates(target: &SimpleTarget, time: DateTime<Utc>) -> Coordinates {
+    target
+        .moving_target
+        .as_ref()
+        .and_then(|m| moving_target_coordinates(m, time))
+        .unwrap_or_else(|| target.coordinates.clone())
+}
+
+/// Update the coordinates of all moving targets for the given time,
+/// returning the number of targets updated
+pub fn update_moving_target_coordinates(
+    sequence: &mut SimpleSequence,
+    time: DateTime<Utc>,
+) -> usize {
+    let mut updated = 0;
+    for target in &mut sequence.targets {
+        if let Some(coords) = target
+            .moving_target
+            .as_ref()
+            .and_then(|m| moving_target_coordinates(m, time))
+        {
+            target.coordinates = coords;
+            updated += 1;
+        }
+    }
+    updated
+}
+
+/// Update moving targets to their position at local midnight of the night
+/// starting on `date`
+pub fn update_moving_targets_for_night(
+    sequence: &mut SimpleSequence,
+    location: &ObserverLocation,
+    date: NaiveDate,
+) -> usize {
+    let midnight = DateTime::<Utc>::from_naive_utc_and_offset(
+        date.succ_opt()
+            .unwrap_or(date)
+            .and_hms_opt(0, 0, 0)
+            .unwrap(),
+        Utc,
+    ) - Duration::hours(location.timezone_offset as i64);
+    update_moving_target_coordinates(sequence, midnight)
+}
+
+/// Create a sequence target for a moving target, positioned at `time`
+/// (or at the start of its ephemeris when `time` is not covered)
+pub fn create_moving_target(moving: MovingTarget, time: DateTime<Utc>) -> SimpleTarget {
+    let coordinates = moving_target_coordinates(&moving, time)
+        .or_else(|| {
+            let first = moving.ephemeris.first()?;
+            Some(Coordinates::from_decimal(first.ra, first.dec))
+        })
+        .unwrap_or_default();
+    SimpleTarget {
+        name: moving.designation.clone(),
+        target_name: moving.designation.clone(),
+        coordinates,
+        moving_target: Some(moving),
+        ..Default::default()
+    }
+}
+
+// ============================================================================
+// MPC Orbital Elements
+// ============================================================================
+
+fn column(line: &str, start: usize, end: usize) -> &str {
+    line.get(start - 1..end.min(line.len()))
+        .unwrap_or("")
+        .trim()
+}
+
+fn date_to_jd(year: i32, month: u32, day: f64) -> Option<f64> {
+    let date = NaiveDate::from_ymd_opt(year, month, 1)?;
+    let midnight = DateTime::<Utc>::from_naive_utc_and_offset(date.and_hms_opt(0, 0, 0)?, Utc);
+    Some(datetime_to_jd(midnight) + day - 1.0)
+}
+
+/// Decode an MPC packed date, e.g. "K2555" -> 2025-05-05
+fn unpack_mpc_date(packed: &str) -> Option<f64> {
+    let chars: Vec<char> = packed.chars().collect();
+    if chars.len() != 5 {
+        return None;
+    }
+    let century = match chars[0] {
+        'I' => 1800,
+        'J' => 1900,
+        'K' => 2000,
+        _ => return None,
+    };
+    let year = century + packed.get(1..3)?.parse::<i32>().ok()?;
+    let decode = |c: char| match c {
+        '1'..='9' => Some(c as u32 - '0' as u32),
+        'A'..='V' => Some(c as u32 - 'A' as u32 + 10),
+        _ => None,
+    };
+    date_to_jd(year, decode(chars[3])?, decode(chars[4])? as f64)
+}
+
+/// Parse a line of MPC comet elements (CometEls.txt)
+fn parse_mpc_comet(line: &str) -> Option<MovingTarget> {
+    let year: i32 = column(line, 15, 18).parse().ok()?;
+    let month: u32 = column(line, 20, 21).parse().ok()?;
+    let day: f64 = column(line, 23, 29).parse().ok()?;
+    let perihelion_time = date_to_jd(year, month, day)?;
+
+    let epoch = column(line, 82, 89);
+    let epoch = match (
+        epoch.get(0..4).and_then(|s| s.parse().ok()),
+        epoch.get(4..6).and_then(|s| s.parse().ok()),
+        epoch.get(6..8).and_then(|s| s.parse::<f64>().ok()),
+    ) {
+        (Some(y), Some(m), Some(d)) => date_to_jd(y, m, d)?,
+        _ => perihelion_time,
+    };
+
+    let designation = match column(line, 103, 158) {
+        "" => column(line, 1, 12).to_string(),
+        name => name.to_string(),
+    };
+
+    Some(MovingTarget {
+        designation,
+        kind: MovingTargetKind::Comet,
+        elements: Some(OrbitalElements {
+            epoch,
+            perihelion_distance: column(line, 31, 39).parse().ok()?,
+            eccentricity: column(line, 42, 49).parse().ok()?,
+            arg_perihelion: column(line, 52, 59).parse().ok()?,
+            ascending_node: column(line, 62, 69).parse().ok()?,
+            inclination: column(line, 72, 79).parse().ok()?,
+            perihelion_time,
+        }),
+        ephemeris: Vec::new(),
+    })
+}
+
+/// Parse a line of MPCORB asteroid elements
+fn parse_mpcorb(line: &str) -> Option<MovingTarget> {
+    let epoch = unpack_mpc_date(column(line, 21, 25))?;
+    let mean_anomaly: f64 = column(line, 27, 35).parse().ok()?;
+    let eccentricity: f64 = column(line, 71, 79).parse().ok()?;
+    let semi_major_axis: f64 = column(line, 93, 103).parse().ok()?;
+    let mean_motion = column(line, 81, 91)
+        .parse::<f64>()
+        .unwrap_or_else(|_| GAUSS_K.to_degrees() / semi_major_axis.powf(1.5));
+
+    let designation = match column(line, 167, 194) {
+        "" => column(line, 1, 7).to_string(),
+        name => name.to_string(),
+    };
+
+    Some(MovingTarget {
+        designation,
+        kind: MovingTargetKind::Asteroid,
+        elements: Some(OrbitalElements {
+            epoch,
+            perihelion_distance: semi_major_axis * (1.0 - eccentricity),
+            eccentricity,
+            arg_perihelion: column(line, 38, 46).parse().ok()?,
+            ascending_node: column(line, 49, 57).parse().ok()?,
+            inclination: column(line, 60, 68).parse().ok()?,
+            perihelion_time: epoch - mean_anomaly / mean_motion,
+        }),
+        ephemeris: Vec::new(),
+    })
+}
+
+/// Parse MPC one-line orbital elements (comets or MPCORB asteroids).
+/// Header and unrecognized lines are skipped.
+pub fn parse_mpc_elements(content: &str) -> Result<Vec<MovingTarget>, String> {
+    let targets: Vec<MovingTarget> = content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .filter_map(|line| parse_mpcorb(line).or_else(|| parse_mpc_comet(line)))
+        .collect();
+
+    if targets.is_empty() {
+        return Err("No MPC orbital elements found".to_string());
+    }
+    Ok(targets)
+}
+
+// ============================================================================
+// JPL Horizons Ephemeris
+// ============================================================================
+
+fn parse_horizons_time(date: &str, time: &str) -> Option<DateTime<Utc>> {
+    let text = format!("{} {}", date, time);
+    [
+        "%Y-%b-%d %H:%M:%S%.f",
+        "%Y-%b-%d %H:%M:%S",
+        "%Y-%b-%d %H:%M",
+    ]
+    .iter()
+    .find_map(|fmt| NaiveDateTime::parse_from_str(&text, fmt).ok())
+    .map(|dt| DateTime::<Utc>::from_naive_utc_and_offset(dt, Utc))
+}
+
+fn parse_horizons_row(line: &str) -> Option<EphemerisEntry> {
+    let cleaned = line.replace(',', " ");
+    let mut tokens = cleaned.split_whitespace();
+    let time = parse_horizons_time(tokens.next()?, tokens.next()?)?;
+
+    // Skip solar/lunar presence markers
+    let values: Vec<&str> = tokens.skip_while(|t| t.parse::<f64>().is_err()).collect();
+
+    let (ra, dec) = if values.len() >= 6 && values[3].starts_with(['+', '-']) {
+        let num = |i: usize| values[i].parse::<f64>().ok();
+        let ra = num(0)? + num(1)? / 60.0 + num(2)? / 3600.0;
+        let dec_abs = num(3)?.abs() + num(4)? / 60.0 + num(5)? / 3600.0;
+        (
+            ra,
+            if values[3].starts_with('-') {
+                -dec_abs
+            } else {
+                dec_abs
+            },
+        )
+    } else if values.len() >= 2 {
+        (
+            values[0].parse::<f64>().ok()? / 15.0,
+            values[1].parse().ok()?,
+        )
+    } else {
+        return None;
+    };
+
+    Some(EphemerisEntry { time, ra, dec })
+}
+
+/// Parse a JPL Horizons observer table (astrometric RA/Dec, sexagesimal or
+/// degrees, plain or CSV output)
+pub fn parse_horizons_ephemeris(content: &str) -> Result<MovingTarget, String> {
+    let mut designation = String::new();
+    let mut ephemeris = Vec::new();
+    let mut in_table = false;
+
+    for line in content.lines() {
+        let trimmed = line.trim();
+        if let Some(name) = trimmed.strip_prefix("Target body name:") {
+            designation = name.split('{').next().unwrap_or(name).trim().to_string();
+        } else if trimmed == "$$SOE" {
+            in_table = true;
+        } else if trimmed == "$$EOE" {
+            in_table = false;
+        } else if in_table {
+            ephemeris.push(
+                parse_horizons_row(trimmed)
+                    .ok_or_else(|| format!("Invalid ephemeris row: {}", trimmed))?,
+            );
+        }
+    }
+
+    if ephemeris.is_empty() {
+        return Err("No ephemeris rows found between $$SOE and $$EOE".to_string());
+    }
+    ephemeris.sort_by_key(|e| e.time);
+
+    let kind = if designation.contains("P/") || designation.contains("C/") {
+        MovingTargetKind::Comet
+    } else {
+        MovingTargetKind::Asteroid
+    };
+
+    Ok(MovingTarget {
+        designation: if designation.is_empty() {
+            "Horizons target".to_string()
+        } else {
+            designation
+        },
+        kind,
+        elements: None,
+        ephemeris,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_elements_position_encke() {
+        // Meeus example 33.a: comet Encke, 1990 Oct 6.0
+        let elements = OrbitalElements {
+            epoch: 2448192.5,
+            perihelion_distance: 2.2091404 * (1.0 - 0.8502196),
+            eccentricity: 0.8502196,
+            inclination: 11.94524,
+            arg_perihelion: 186.23352,
+            ascending_node: 334.75006,
+            perihelion_time: 2448193.04502,
+        };
+
+        let (ra, dec) = elements_position(&elements, 2448170.5);
+
+        assert!((ra * 15.0 - 158.558965).abs() < 0.1);
+        assert!((dec - 19.182861).abs() < 0.1);
+    }
+
+    #[test]
+    fn test_parse_mpc_elements() {
+        let content = concat!(
+            "    CJ95O010  1997 03 29.6333  0.916241  0.994928  130.6448  283.2222   89.4320  20240101  -2.0  4.0  C/1995 O1 (Hale-Bopp)\n",
+            "00001    3.34  0.15 K2555 188.70269   73.27343   80.25221   10.58780  0.0794013  0.21424651   2.7660512  0 E2024-V47  7330 125 1801-2024 0.80 M-v 30k MPCLINUX   4000      (1) Ceres              20241101\n",
+        );
+
+        let targets = parse_mpc_elements(content).unwrap();
+
+        assert_eq!(targets.len(), 2);
+        assert_eq!(targets[0].designation, "C/1995 O1 (Hale-Bopp)");
+        assert_eq!(targets[0].kind, MovingTargetKind::Comet);
+        assert_eq!(targets[1].designation, "(1) Ceres");
+        let ceres = targets[1].elements.as_ref().unwrap();
+        assert!((ceres.perihelion_distance - 2.5464).abs() < 0.001);
+        assert!((ceres.inclination - 10.5878).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_parse_horizons_ephemeris_and_interpolate() {
+        let content = "\
+Target body name: 2P/Encke                       {source: JPL#K231/2}
+*******************************************************************
+ Date__(UT)__HR:MN     R.A._____(ICRF)_____DEC
+*******************************************************************
+$$SOE
+ 2024-Oct-01 00:00 *m  23 59 00.00 +10 00 00.0
+ 2024-Oct-02 00:00 Cm  00 01 00.00 +09 00 00.0
+$$EOE
+";
+
+        let target = parse_horizons_ephemeris(content).unwrap();
+
+        assert_eq!(target.designation, "2P/Encke");
+        assert_eq!(target.kind, MovingTargetKind::Comet);
+        assert_eq!(target.ephemeris.len(), 2);
+
+        let noon = Utc.with_ymd_and_hms(2024, 10, 1, 12, 0, 0).unwrap();
+        let (ra, dec) = interpolate_ephemeris(&target.ephemeris, noon).unwrap();
+        assert!(ra.abs() < 1e-6 || (ra - 24.0).abs() < 1e-6);
+        assert!((dec - 9.5).abs() < 1e-6);
+
+        let later = Utc.with_ymd_and_hms(2024, 10, 3, 0, 0, 0).unwrap();
+        assert!(interpolate_ephemeris(&target.ephemeris, later).is_none());
+    }
+}
@@ -5,14 +5,32 @@
 //! - XML
 //! - Stellarium skylist
 //! - APT format
-//! - Voyager format
+//! - Voyager format and RoboTarget JSON
 //! - NINA Target Set
+//! - HTML session report
 
-use chrono::Utc;
+use chrono::{DateTime, Duration, NaiveDate, Timelike, Utc};
 use serde::{Deserialize, Serialize};
-
-use crate::models::simple_sequence::TargetSetExport;
-use crate::models::{Coordinates, SimpleSequence, SimpleTarget};
+use std::fmt::Write;
+use std::path::Path;
+
+use crate::models::coordinates::angular_separation;
+use crate::models::simple_sequence::{metadata_value_to_text, TargetSetExport};
+use crate::models::{
+    CoordinateEpoch, Coordinates, ObservingConstraints, SimpleSequence, SimpleTarget,
+    UnitPreferences,
+};
+use crate::services::astronomy::{
+    convert_coordinates_epoch, datetime_to_jd, get_moon_phase_info, moon_position, observed_alt_az,
+    ObserverLocation,
+};
+use crate::services::calculator::format_duration;
+use crate::services::csv_io::escape_field;
+use crate::services::dark_calendar;
+use crate::services::ephemeris::update_moving_target_coordinates;
+use crate::services::file_service;
+use crate::services::units::{format_clock, format_length, format_number, localize_decimal};
+use crate::services::voyager_robotarget::export_to_robotarget_json;
 
 /// Export options
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -24,6 +42,15 @@ pub struct ExportOptions {
     pub include_progress: bool,
     pub decimal_places: usize,
     pub coordinate_format: CoordinateFormat,
+    /// Epoch to write coordinates in (some mount formats expect JNow)
+    #[serde(default)]
+    pub coordinate_epoch: CoordinateEpoch,
+    /// Date used for JNow conversion and moving target positions (defaults to now)
+    #[serde(default)]
+    pub epoch_date: Option<DateTime<Utc>>,
+    /// Decimal separator and delimiter of CSV exports (US formats if unset)
+    #[serde(default)]
+    pub units: Option<UnitPreferences>,
 }
 
 impl Default for ExportOptions {
@@ -35,6 +62,9 @@ impl Default for ExportOptions {
             include_progress: false,
             decimal_places: 2,
             coordinate_format: CoordinateFormat::Sexagesimal,
+            coordinate_epoch: CoordinateEpoch::J2000,
+            epoch_date: None,
+            units: None,
         }
     }
 }
@@ -49,10 +79,61 @@ pub enum ExportFormat {
     XmlApt,
     Stellarium,
     Voyager,
+    VoyagerRoboTarget,
     NinaTargetSet,
     Json,
 }
 
+impl ExportFormat {
+    pub const ALL: [ExportFormat; 9] = [
+        ExportFormat::Csv,
+        ExportFormat::CsvTelescopius,
+        ExportFormat::Xml,
+        ExportFormat::XmlApt,
+        ExportFormat::Stellarium,
+        ExportFormat::Voyager,
+        ExportFormat::VoyagerRoboTarget,
+        ExportFormat::NinaTargetSet,
+        ExportFormat::Json,
+    ];
+
+    /// Format from a name as given on the command line: the serialized or
+    /// snake_case name in any case, or a short alias such as `nina`
+    pub fn from_name(name: &str) -> Option<ExportFormat> {
+        let name: String = name
+            .chars()
+            .filter(|c| *c != '_' && *c != '-')
+            .collect::<String>()
+            .to_lowercase();
+        match name.as_str() {
+            "nina" => return Some(ExportFormat::NinaTargetSet),
+            "apt" => return Some(ExportFormat::XmlApt),
+            "telescopius" => return Some(ExportFormat::CsvTelescopius),
+            "robotarget" => return Some(ExportFormat::VoyagerRoboTarget),
+            _ => {}
+        }
+        Self::ALL
+            .into_iter()
+            .find(|f| format!("{:?}", f).to_lowercase() == name)
+    }
+
+    /// File name suffix, distinct per format so one export directory can
+    /// hold every format of the same sequence
+    pub fn file_suffix(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => ".csv",
+            ExportFormat::CsvTelescopius => ".telescopius.csv",
+            ExportFormat::Xml => ".xml",
+            ExportFormat::XmlApt => ".apt.xml",
+            ExportFormat::Stellarium => ".skylist.txt",
+            ExportFormat::Voyager => ".voyager.txt",
+            ExportFormat::VoyagerRoboTarget => ".robotarget.json",
+            ExportFormat::NinaTargetSet => ".ninaTargetSet",
+            ExportFormat::Json => ".json",
+        }
+    }
+}
+
 /// Coordinate format for export
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -82,6 +163,10 @@ pub struct ExportResult {
 pub fn export_to_csv(sequence: &SimpleSequence, options: &ExportOptions) -> ExportResult {
     let mut lines = Vec::new();
     let errors: Vec<String> = Vec::new();
+    let units = options.units.unwrap_or_default();
+    let delimiter = units.csv_delimiter;
+    let separator = delimiter.to_string();
+    let number = |value: f64| format_number(value, 1, &units);
 
     // Header
     let mut headers = vec!["Name", "RA", "Dec", "Position Angle"];
@@ -98,33 +183,48 @@ pub fn export_to_csv(sequence: &SimpleSequence, options: &ExportOptions) -> Expo
     if options.include_progress {
         headers.push("Progress");
     }
-    lines.push(headers.join(","));
+    let annotations = AnnotationColumns::for_targets(&sequence.targets);
+    let mut header = headers.join(&separator);
+    for column in annotations.headers() {
+        header.push(delimiter);
+        header.push_str(&escape_field(&column, delimiter));
+    }
+    lines.push(header);
 
     // Data rows
     for target in &sequence.targets {
-        let ra = format_ra(
-            &target.coordinates,
-            options.coordinate_format,
-            options.decimal_places,
+        let ra = localize_decimal(
+            &format_ra(
+                &target.coordinates,
+                options.coordinate_format,
+                options.decimal_places,
+            ),
+            &units,
         );
-        let dec = format_dec(
-            &target.coordinates,
-            options.coordinate_format,
-            options.decimal_places,
+        let dec = localize_decimal(
+            &format_dec(
+                &target.coordinates,
+                options.coordinate_format,
+                options.decimal_places,
+            ),
+            &units,
         );
 
         if options.include_exposures && !target.exposures.is_empty() {
             for exp in &target.exposures {
                 let mut row = vec![
-                    escape_csv(&target.target_name),
-                    ra.clone(),
-                    dec.clone(),
-                    format!("{:.1}", target.position_angle),
-                    format!("{:.1}", exp.exposure_time),
-                    exp.filter
-                        .as_ref()
-                        .map(|f| f.name.clone())
-                        .unwrap_or_default(),
+                    escape_field(&target.target_name, delimiter),
+                    escape_field(&ra, delimiter),
+                    escape_field(&dec, delimiter),
+                    escape_field(&number(target.position_angle), delimiter),
+                    escape_field(&number(exp.exposure_time), delimiter),
+                    escape_field(
+                        &exp.filter
+                            .as_ref()
+                            .map(|f| f.name.clone())
+                            .unwrap_or_default(),
+                        delimiter,
+                    ),
                     format!("{}x{}", exp.binning.x, exp.binning.y),
                     exp.gain.to_string(),
                     exp.offset.to_string(),
@@ -133,14 +233,15 @@ pub fn export_to_csv(sequence: &SimpleSequence, options: &ExportOptions) -> Expo
                 if options.include_progress {
                     row.push(exp.progress_count.to_string());
                 }
-                lines.push(row.join(","));
+                row.extend(annotations.cells(target, delimiter));
+                lines.push(row.join(&separator));
             }
         } else {
             let mut row = vec![
-                escape_csv(&target.target_name),
-                ra,
-                dec,
-                format!("{:.1}", target.position_angle),
+                escape_field(&target.target_name, delimiter),
+                escape_field(&ra, delimiter),
+                escape_field(&dec, delimiter),
+                escape_field(&number(target.position_angle), delimiter),
             ];
             if options.include_exposures {
                 row.extend(vec![
@@ -155,7 +256,8 @@ pub fn export_to_csv(sequence: &SimpleSequence, options: &ExportOptions) -> Expo
             if options.include_progress {
                 row.push("".to_string());
             }
-            lines.push(row.join(","));
+            row.extend(annotations.cells(target, delimiter));
+            lines.push(row.join(&separator));
         }
     }
 
@@ -184,8 +286,8 @@ pub fn export_to_telescopius_csv(
 
         let row = [
             (idx + 1).to_string(),
-            escape_csv(&target.target_name),
-            escape_csv(&target.name),
+            escape_field(&target.target_name, ','),
+            escape_field(&target.name, ','),
             ra,
             dec,
             format!("{:.1}", target.position_angle),
@@ -202,11 +304,62 @@ pub fn export_to_telescopius_csv(
     }
 }
 
-fn escape_csv(s: &str) -> String {
-    if s.contains(',') || s.contains('"') || s.contains('\n') {
-        format!("\"{}\"", s.replace('"', "\"\""))
-    } else {
-        s.to_string()
+/// Notes, tags and metadata columns appended to CSV rows. Columns are only
+/// present when at least one target uses them; each metadata key gets its
+/// own `Meta:<key>` column.
+struct AnnotationColumns {
+    notes: bool,
+    tags: bool,
+    metadata_keys: Vec<String>,
+}
+
+impl AnnotationColumns {
+    fn for_targets(targets: &[SimpleTarget]) -> Self {
+        let mut metadata_keys: Vec<String> = targets
+            .iter()
+            .flat_map(|t| t.metadata.keys().cloned())
+            .collect();
+        metadata_keys.sort();
+        metadata_keys.dedup();
+
+        Self {
+            notes: targets.iter().any(|t| t.notes.is_some()),
+            tags: targets.iter().any(|t| !t.tags.is_empty()),
+            metadata_keys,
+        }
+    }
+
+    fn headers(&self) -> Vec<String> {
+        let mut headers = Vec::new();
+        if self.notes {
+            headers.push("Notes".to_string());
+        }
+        if self.tags {
+            headers.push("Tags".to_string());
+        }
+        headers.extend(self.metadata_keys.iter().map(|k| format!("Meta:{}", k)));
+        headers
+    }
+
+    fn cells(&self, target: &SimpleTarget, delimiter: char) -> Vec<String> {
+        let mut cells = Vec::new();
+        if self.notes {
+            cells.push(escape_field(
+                target.notes.as_deref().unwrap_or_default(),
+                delimiter,
+            ));
+        }
+        if self.tags {
+            cells.push(escape_field(&target.tags.join(";"), delimiter));
+        }
+        cells.extend(self.metadata_keys.iter().map(|key| {
+            target
+                .metadata
+                .get(key)
+                .map(|v| escape_field(&metadata_value_to_text(v), delimiter))
+                .unwrap_or_default()
+        }));
+        cells
     }
 }
 
@@ -215,6 +368,32 @@ fn escape_csv(s: &str) -> String {
 // ============================================================================
 
 /// Export to generic XML
+/// Set constraints as XML element names and values
+fn xml_constraint_values(constraints: &ObservingConstraints) -> Vec<(&'static str, String)> {
+    let numbers = [
+        ("MinAltitude", constraints.min_altitude),
+        ("MaxAltitude", constraints.max_altitude),
+        ("MaxAirmass", constraints.max_airmass),
+        ("MinHourAngle", constraints.min_hour_angle),
+        ("MaxHourAngle", constraints.max_hour_angle),
+        ("MinMoonSeparation", constraints.min_moon_separation),
+        ("MaxMoonIllumination", constraints.max_moon_illumination),
+    ];
+    let times = [
+        ("EarliestStart", constraints.earliest_start),
+        ("LatestStart", constraints.latest_start),
+    ];
+    numbers
+        .into_iter()
+        .filter_map(|(name, value)| value.map(|v| (name, v.to_string())))
+        .chain(
+            times
+                .into_iter()
+                .filter_map(|(name, value)| value.map(|t| (name, t.to_rfc3339()))),
+        )
+        .collect()
+}
+
 pub fn export_to_xml(sequence: &SimpleSequence, options: &ExportOptions) -> ExportResult {
     let mut xml = String::new();
 
@@ -253,6 +432,37 @@ pub fn export_to_xml(sequence: &SimpleSequence, options: &ExportOptions) -> Expo
             target.position_angle
         ));
 
+        if let Some(ref notes) = target.notes {
+            xml.push_str(&format!("      <Notes>{}</Notes>\n", escape_xml(notes)));
+        }
+        if !target.tags.is_empty() {
+            xml.push_str("      <Tags>\n");
+            for tag in &target.tags {
+                xml.push_str(&format!("        <Tag>{}</Tag>\n", escape_xml(tag)));
+            }
+            xml.push_str("      </Tags>\n");
+        }
+        if !target.metadata.is_empty() {
+            let mut keys: Vec<&String> = target.metadata.keys().collect();
+            keys.sort();
+            xml.push_str("      <Metadata>\n");
+            for key in keys {
+                xml.push_str(&format!(
+                    "        <Entry key=\"{}\">{}</Entry>\n",
+                    escape_xml(key),
+                    escape_xml(&metadata_value_to_text(&target.metadata[key]))
+                ));
+            }
+            xml.push_str("      </Metadata>\n");
+        }
+        if !target.constraints.is_empty() {
+            xml.push_str("      <Constraints>\n");
+            for (name, value) in xml_constraint_values(&target.constraints) {
+                xml.push_str(&format!("        <{0}>{1}</{0}>\n", name, value));
+            }
+            xml.push_str("      </Constraints>\n");
+        }
+
         if options.include_settings {
             xml.push_str(&format!(
                 "      <SlewToTarget>{}</SlewToTarget>\n",
@@ -511,12 +721,474 @@ pub fn export_to_json(sequence: &SimpleSequence) -> ExportResult {
     }
 }
 
+// ============================================================================
+// Session Report
+// ============================================================================
+
+/// Session report options
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionReportOptions {
+    pub title: Option<String>,
+    /// Session start (defaults to astronomical dusk)
+    pub start_time: Option<DateTime<Utc>>,
+    pub min_altitude: f64,
+    /// Altitude curve sample interval in minutes
+    pub sample_minutes: u32,
+    /// Clock, number and length formats (US formats if unset)
+    #[serde(default)]
+    pub units: Option<UnitPreferences>,
+}
+
+impl Default for SessionReportOptions {
+    fn default() -> Self {
+        Self {
+            title: None,
+            start_time: None,
+            min_altitude: 20.0,
+            sample_minutes: 10,
+            units: None,
+        }
+    }
+}
+
+/// Scheduled target in a session report
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReportTargetEntry {
+    pub target_id: String,
+    pub target_name: String,
+    pub start_time: DateTime<Utc>,
+    pub end_time: DateTime<Utc>,
+    pub start_altitude: f64,
+    pub end_altitude: f64,
+    pub max_altitude: f64,
+    pub moon_separation: f64,
+}
+
+/// Shaded chart period: (from, to, fill color)
+type ShadingPeriod<'a> = (Option<DateTime<Utc>>, Option<DateTime<Utc>>, &'a str);
+
+const REPORT_COLORS: &[&str] = &[
+    "#4e79a7", "#f28e2b", "#e15759", "#76b7b2", "#59a14f", "#edc948", "#b07aa1", "#ff9da7",
+];
+
+fn altitude_at(coords: &Coordinates, location: &ObserverLocation, time: DateTime<Utc>) -> f64 {
+    observed_alt_az(
+        coords.ra_to_decimal(),
+        coords.dec_to_decimal(),
+        location,
+        datetime_to_jd(time),
+    )
+    .0
+}
+
+fn moon_coordinates(time: DateTime<Utc>) -> Coordinates {
+    let (ra, dec, _) = moon_position(datetime_to_jd(time));
+    Coordinates::from_decimal(ra, dec)
+}
+
+/// Schedule targets back to back from `start` and compute altitudes and moon separation
+pub fn schedule_report_entries(
+    sequence: &SimpleSequence,
+    location: &ObserverLocation,
+    start: DateTime<Utc>,
+) -> Vec<ReportTargetEntry> {
+    let mut entries = Vec::new();
+    let mut current = start;
+
+    for target in &sequence.targets {
+        let runtime = target.runtime(sequence.estimated_download_time);
+        let end = current + Duration::seconds(runtime as i64);
+
+        // Sample the scheduled block for its highest point
+        let steps = ((runtime / 600.0).ceil() as i64).max(1);
+        let max_altitude = (0..=steps)
+            .map(|i| {
+                let t = current + Duration::seconds(runtime as i64 * i / steps);
+                altitude_at(&target.coordinates, location, t)
+            })
+            .fold(f64::MIN, f64::max);
+
+        let mid = current + Duration::seconds(runtime as i64 / 2);
+
+        entries.push(ReportTargetEntry {
+            target_id: target.id.clone(),
+            target_name: target.target_name.clone(),
+            start_time: current,
+            end_time: end,
+            start_altitude: altitude_at(&target.coordinates, location, current),
+            end_altitude: altitude_at(&target.coordinates, location, end),
+            max_altitude,
+            moon_separation: angular_separation(&target.coordinates, &moon_coordinates(mid)),
+        });
+
+        current = end;
+    }
+
+    entries
+}
+
+/// Generate a standalone HTML report for the scheduled night: per-target
+/// times, altitude curves (inline SVG), moon data and twilight boundaries
+pub fn generate_session_report(
+    sequence: &SimpleSequence,
+    location: &ObserverLocation,
+    date: NaiveDate,
+    options: &SessionReportOptions,
+) -> ExportResult {
+    let twilight = dark_calendar::night_twilight(location, date);
+    let local_noon = dark_calendar::night_start(location, date);
+
+    let night_start = twilight.sunset.unwrap_or(local_noon + Duration::hours(6));
+    let night_end = twilight
+        .sunrise
+        .filter(|t| *t > night_start)
+        .unwrap_or(night_start + Duration::hours(12));
+
+    let session_start = options
+        .start_time
+        .or(twilight.astronomical_dusk)
+        .or(twilight.nautical_dusk)
+        .unwrap_or(night_start);
+
+    let entries = schedule_report_entries(sequence, location, session_start);
+    let session_end = entries.last().map(|e| e.end_time).unwrap_or(session_start);
+
+    let chart_start = night_start.min(session_start) - Duration::hours(1);
+    let chart_end = night_end.max(session_end) + Duration::hours(1);
+
+    let units = options.units.unwrap_or_default();
+    let number = |value: f64, decimals: usize| format_number(value, decimals, &units);
+    let local = |t: DateTime<Utc>| {
+        format_clock(
+            &(t + Duration::hours(location.timezone_offset as i64)),
+            false,
+            &units,
+        )
+    };
+    let local_opt = |t: Option<DateTime<Utc>>| t.map(local).unwrap_or_else(|| "-".to_string());
+
+    let title = options
+        .title
+        .clone()
+        .unwrap_or_else(|| format!("{} - {}", sequence.title, date.format("%Y-%m-%d")));
+    let moon = get_moon_phase_info(session_start);
+
+    let mut html = String::new();
+    html.push_str("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n");
+    let _ = writeln!(html, "<title>{}</title>", escape_xml(&title));
+    html.push_str(
+        "<style>\n\
+         body { font-family: sans-serif; margin: 2em; color: #222; }\n\
+         table { border-collapse: collapse; margin-bottom: 1.5em; }\n\
+         th, td { border: 1px solid #ccc; padding: 4px 8px; text-align: left; }\n\
+         th { background: #f0f0f0; }\n\
+         .warn { color: #b00; }\n\
+         svg { border: 1px solid #ccc; }\n\
+         </style>\n</head>\n<body>\n",
+    );
+
+    let _ = writeln!(html, "<h1>{}</h1>", escape_xml(&title));
+    let _ = writeln!(
+        html,
+        "<p>Location: {}&deg;, {}&deg;, {} (UTC{:+}) &middot; {} targets &middot; total runtime {}</p>",
+        number(location.latitude, 4),
+        number(location.longitude, 4),
+        format_length(location.elevation, &units),
+        location.timezone_offset,
+        entries.len(),
+        format_duration((session_end - session_start).num_seconds() as f64)
+    );
+
+    // Twilight
+    html.push_str(
+        "<h2>Twilight</h2>\n<table>\n<tr><th>Event</th><th>Evening</th><th>Morning</th></tr>\n",
+    );
+    for (name, dusk, dawn) in [
+        ("Sunset / Sunrise", twilight.sunset, twilight.sunrise),
+        ("Civil", twilight.civil_dusk, twilight.civil_dawn),
+        ("Nautical", twilight.nautical_dusk, twilight.nautical_dawn),
+        (
+            "Astronomical",
+            twilight.astronomical_dusk,
+            twilight.astronomical_dawn,
+        ),
+    ] {
+        let _ = writeln!(
+            html,
+            "<tr><td>{}</td><td>{}</td><td>{}</td></tr>",
+            name,
+            local_opt(dusk),
+            local_opt(dawn)
+        );
+    }
+    html.push_str("</table>\n");
+
+    // Moon
+    let _ = writeln!(
+        html,
+        "<h2>Moon</h2>\n<p>{} &middot; {}% illuminated &middot; age {} days</p>",
+        escape_xml(&moon.phase_name),
+        number(moon.illumination, 0),
+        number(moon.age_days, 1)
+    );
+
+    // Altitude chart
+    html.push_str("<h2>Altitude</h2>\n");
+    html.push_str(&render_altitude_svg(
+        sequence,
+        location,
+        &entries,
+        (chart_start, chart_end),
+        &[
+            (twilight.sunset, twilight.astronomical_dusk, "#dde3ee"),
+            (twilight.astronomical_dawn, twilight.sunrise, "#dde3ee"),
+            (
+                twilight.astronomical_dusk,
+                twilight.astronomical_dawn,
+                "#b8c2d6",
+            ),
+        ],
+        options,
+        &local,
+    ));
+
+    // Schedule
+    html.push_str(
+        "<h2>Schedule</h2>\n<table>\n<tr><th>Target</th><th>RA</th><th>Dec</th><th>Start</th>\
+         <th>End</th><th>Duration</th><th>Alt start</th><th>Alt end</th><th>Alt max</th>\
+         <th>Moon sep.</th><th>Notes</th></tr>\n",
+    );
+    for (entry, target) in entries.iter().zip(&sequence.targets) {
+        let mut notes = Vec::new();
+        if entry.start_altitude < options.min_altitude || entry.end_altitude < options.min_altitude
+        {
+            notes.push(format!("below {}&deg;", number(options.min_altitude, 0)));
+        }
+        if twilight
+            .astronomical_dawn
+            .is_some_and(|dawn| entry.end_time > dawn)
+        {
+            notes.push("ends after astronomical dawn".to_string());
+        }
+        let _ = writeln!(
+            html,
+            "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td>\
+             <td>{}&deg;</td><td>{}&deg;</td><td>{}&deg;</td><td>{}&deg;</td>\
+             <td class=\"warn\">{}</td></tr>",
+            escape_xml(&entry.target_name),
+            localize_decimal(&target.coordinates.format_ra(), &units),
+            localize_decimal(&target.coordinates.format_dec(), &units),
+            local(entry.start_time),
+            local(entry.end_time),
+            format_duration((entry.end_time - entry.start_time).num_seconds() as f64),
+            number(entry.start_altitude, 1),
+            number(entry.end_altitude, 1),
+            number(entry.max_altitude, 1),
+            number(entry.moon_separation, 1),
+            notes.join(", ")
+        );
+    }
+    html.push_str("</table>\n");
+
+    let _ = writeln!(
+        html,
+        "<p><small>Generated by Cobalt Task Editor on {}</small></p>\n</body>\n</html>",
+        Utc::now().format("%Y-%m-%d %H:%M UTC")
+    );
+
+    ExportResult {
+        success: true,
+        content: html,
+        format: "HTML".to_string(),
+        target_count: entries.len(),
+        errors: vec![],
+    }
+}
+
+/// Render target and moon altitude curves as an SVG chart, with twilight
+/// and night periods shaded
+fn render_altitude_svg(
+    sequence: &SimpleSequence,
+    location: &ObserverLocation,
+    entries: &[ReportTargetEntry],
+    (chart_start, chart_end): (DateTime<Utc>, DateTime<Utc>),
+    shading: &[ShadingPeriod],
+    options: &SessionReportOptions,
+    local: &dyn Fn(DateTime<Utc>) -> String,
+) -> String {
+    const WIDTH: f64 = 800.0;
+    const HEIGHT: f64 = 320.0;
+    const LEFT: f64 = 40.0;
+    const TOP: f64 = 10.0;
+    const PLOT_W: f64 = WIDTH - LEFT - 10.0;
+    const PLOT_H: f64 = HEIGHT - TOP - 30.0;
+
+    let span = (chart_end - chart_start).num_seconds().max(1) as f64;
+    let x = |t: DateTime<Utc>| {
+        LEFT + ((t - chart_start).num_seconds() as f64 / span).clamp(0.0, 1.0) * PLOT_W
+    };
+    let y = |alt: f64| TOP + (90.0 - alt.clamp(0.0, 90.0)) / 90.0 * PLOT_H;
+
+    let step = Duration::minutes(options.sample_minutes.max(1) as i64);
+    let mut times = Vec::new();
+    let mut t = chart_start;
+    while t <= chart_end {
+        times.push(t);
+        t += step;
+    }
+
+    let mut svg = String::new();
+    let _ = writeln!(
+        svg,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" viewBox=\"0 0 {} {}\" font-size=\"10\">",
+        WIDTH, HEIGHT, WIDTH, HEIGHT
+    );
+
+    // Twilight and night shading
+    for (from, to, fill) in shading {
+        if let (Some(from), Some(to)) = (from, to) {
+            let _ = writeln!(
+                svg,
+                "<rect x=\"{:.1}\" y=\"{}\" width=\"{:.1}\" height=\"{}\" fill=\"{}\"/>",
+                x(*from),
+                TOP,
+                (x(*to) - x(*from)).max(0.0),
+                PLOT_H,
+                fill
+            );
+        }
+    }
+
+    // Altitude grid and minimum altitude
+    for alt in [0.0, 30.0, 60.0, 90.0] {
+        let _ = writeln!(
+            svg,
+            "<line x1=\"{}\" y1=\"{:.1}\" x2=\"{}\" y2=\"{:.1}\" stroke=\"#999\" stroke-width=\"0.5\"/>\
+             <text x=\"{}\" y=\"{:.1}\" text-anchor=\"end\">{}&deg;</text>",
+            LEFT,
+            y(alt),
+            LEFT + PLOT_W,
+            y(alt),
+            LEFT - 4.0,
+            y(alt) + 3.0,
+            alt
+        );
+    }
+    let _ = writeln!(
+        svg,
+        "<line x1=\"{}\" y1=\"{:.1}\" x2=\"{}\" y2=\"{:.1}\" stroke=\"#c00\" stroke-dasharray=\"4 3\"/>",
+        LEFT,
+        y(options.min_altitude),
+        LEFT + PLOT_W,
+        y(options.min_altitude)
+    );
+
+    // Hour ticks
+    let mut tick = chart_start + Duration::minutes(60 - chart_start.minute() as i64);
+    while tick <= chart_end {
+        let _ = writeln!(
+            svg,
+            "<text x=\"{:.1}\" y=\"{}\" text-anchor=\"middle\">{}</text>",
+            x(tick),
+            HEIGHT - 12.0,
+            local(tick)
+        );
+        tick += Duration::hours(1);
+    }
+
+    let polyline = |points: &[(f64, f64)]| {
+        points
+            .iter()
+            .map(|(px, py)| format!("{:.1},{:.1}", px, py))
+            .collect::<Vec<_>>()
+            .join(" ")
+    };
+
+    // Moon
+    let moon_points: Vec<(f64, f64)> = times
+        .iter()
+        .map(|t| (x(*t), y(altitude_at(&moon_coordinates(*t), location, *t))))
+        .collect();
+    let _ = writeln!(
+        svg,
+        "<polyline points=\"{}\" fill=\"none\" stroke=\"#888\" stroke-dasharray=\"2 2\"/>",
+        polyline(&moon_points)
+    );
+
+    // Targets: full curve, scheduled block highlighted
+    for (i, (entry, target)) in entries.iter().zip(&sequence.targets).enumerate() {
+        let color = REPORT_COLORS[i % REPORT_COLORS.len()];
+        let curve: Vec<(f64, f64)> = times
+            .iter()
+            .map(|t| (x(*t), y(altitude_at(&target.coordinates, location, *t))))
+            .collect();
+        let block: Vec<(f64, f64)> = std::iter::once(&entry.start_time)
+            .chain(
+                times
+                    .iter()
+                    .filter(|t| **t > entry.start_time && **t < entry.end_time),
+            )
+            .chain([&entry.end_time])
+            .map(|t| (x(*t), y(altitude_at(&target.coordinates, location, *t))))
+            .collect();
+
+        let _ = writeln!(
+            svg,
+            "<polyline points=\"{}\" fill=\"none\" stroke=\"{}\" stroke-opacity=\"0.4\"/>\
+             <polyline points=\"{}\" fill=\"none\" stroke=\"{}\" stroke-width=\"3\"/>\
+             <text x=\"{:.1}\" y=\"{}\" fill=\"{}\">{}</text>",
+            polyline(&curve),
+            color,
+            polyline(&block),
+            color,
+            LEFT + 4.0 + (i % 4) as f64 * 190.0,
+            TOP + 12.0 + (i / 4) as f64 * 12.0,
+            color,
+            escape_xml(&entry.target_name)
+        );
+    }
+
+    svg.push_str("</svg>\n");
+    svg
+}
+
 // ============================================================================
 // Unified Export Function
 // ============================================================================
 
+/// Convert all target coordinates in a sequence to the given epoch
+pub fn convert_sequence_epoch(
+    sequence: &SimpleSequence,
+    epoch: CoordinateEpoch,
+    jd: f64,
+) -> SimpleSequence {
+    let mut converted = sequence.clone();
+    for target in &mut converted.targets {
+        target.coordinates = convert_coordinates_epoch(&target.coordinates, epoch, jd);
+    }
+    converted
+}
+
 /// Export sequence to specified format
 pub fn export_sequence(sequence: &SimpleSequence, options: &ExportOptions) -> ExportResult {
+    let converted;
+    let sequence = if sequence
+        .targets
+        .iter()
+        .any(|t| t.moving_target.is_some() || t.coordinates.epoch != options.coordinate_epoch)
+    {
+        let time = options.epoch_date.unwrap_or_else(Utc::now);
+        let mut updated = sequence.clone();
+        update_moving_target_coordinates(&mut updated, time);
+        converted =
+            convert_sequence_epoch(&updated, options.coordinate_epoch, datetime_to_jd(time));
+        &converted
+    } else {
+        sequence
+    };
+
     match options.format {
         ExportFormat::Csv => export_to_csv(sequence, options),
         ExportFormat::CsvTelescopius => export_to_telescopius_csv(sequence, options),
@@ -524,37 +1196,129 @@ pub fn export_sequence(sequence: &SimpleSequence, options: &ExportOptions) -> Ex
         ExportFormat::XmlApt => export_to_apt_xml(sequence, options),
         ExportFormat::Stellarium => export_to_stellarium(sequence, options),
         ExportFormat::Voyager => export_to_voyager(sequence, options),
+        ExportFormat::VoyagerRoboTarget => export_to_robotarget_json(sequence, options),
         ExportFormat::NinaTargetSet => export_to_nina_target_set(sequence),
         ExportFormat::Json => export_to_json(sequence),
     }
 }
 
+/// Outcome of one format in a multi-format export
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FormatExportResult {
+    pub format: ExportFormat,
+    /// Written file, if the export succeeded
+    pub path: Option<String>,
+    pub success: bool,
+    pub target_count: usize,
+    pub errors: Vec<String>,
+}
+
+/// File name stem for exports of a sequence, from its title with
+/// characters that are invalid in file names replaced
+pub fn export_file_stem(sequence: &SimpleSequence) -> String {
+    let stem: String = sequence
+        .title
+        .trim()
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+    let stem = stem.trim_matches(|c: char| c == '.' || c.is_whitespace());
+    if stem.is_empty() {
+        "sequence".to_string()
+    } else {
+        stem.to_string()
+    }
+}
+
+/// Export a sequence to several formats, writing `<stem><suffix>` files
+/// into `directory`. A failing format doesn't stop the others.
+pub async fn export_sequence_multi(
+    sequence: &SimpleSequence,
+    formats: &[ExportFormat],
+    options: &ExportOptions,
+    directory: &Path,
+) -> Vec<FormatExportResult> {
+    let stem = export_file_stem(sequence);
+    let mut results = Vec::new();
+
+    for &format in formats {
+        if results
+            .iter()
+            .any(|r: &FormatExportResult| r.format == format)
+        {
+            continue;
+        }
+
+        let options = ExportOptions {
+            format,
+            ..options.clone()
+        };
+        let result = export_sequence(sequence, &options);
+        let mut outcome = FormatExportResult {
+            format,
+            path: None,
+            success: false,
+            target_count: result.target_count,
+            errors: result.errors,
+        };
+
+        if result.success {
+            let path = directory.join(format!("{}{}", stem, format.file_suffix()));
+            match file_service::write_file(&path, &result.content).await {
+                Ok(()) => {
+                    outcome.path = Some(path.display().to_string());
+                    outcome.success = true;
+                }
+                Err(e) => outcome.errors.push(e.to_string()),
+            }
+        }
+        results.push(outcome);
+    }
+
+    results
+}
+
 /// Generate CSV content from targets only
 pub fn generate_csv_content(targets: &[SimpleTarget], options: &ExportOptions) -> String {
     let mut lines = Vec::new();
+    let units = options.units.unwrap_or_default();
+    let delimiter = units.csv_delimiter;
 
     // Header
-    lines.push("Name,RA,Dec,Position Angle".to_string());
+    lines.push(["Name", "RA", "Dec", "Position Angle"].join(&delimiter.to_string()));
 
     for target in targets {
-        let ra = format_ra(
-            &target.coordinates,
-            options.coordinate_format,
-            options.decimal_places,
+        let ra = localize_decimal(
+            &format_ra(
+                &target.coordinates,
+                options.coordinate_format,
+                options.decimal_places,
+            ),
+            &units,
         );
-        let dec = format_dec(
-            &target.coordinates,
-            options.coordinate_format,
-            options.decimal_places,
+        let dec = localize_decimal(
+            &format_dec(
+                &target.coordinates,
+                options.coordinate_format,
+                options.decimal_places,
+            ),
+            &units,
         );
 
-        lines.push(format!(
-            "{},{},{},{:.1}",
-            escape_csv(&target.target_name),
-            ra,
-            dec,
-            target.position_angle
-        ));
+        lines.push(
+            [
+                escape_field(&target.target_name, delimiter),
+                escape_field(&ra, delimiter),
+                escape_field(&dec, delimiter),
+                escape_field(&format_number(target.position_angle, 1, &units), delimiter),
+            ]
+            .join(&delimiter.to_string()),
+        );
     }
 
     lines.join("\n")
@@ -3,8 +3,14 @@
 #[cfg(test)]
 mod tests {
     use super::super::export_service::*;
-    use crate::models::common::{BinningMode, ImageType, SequenceEntityStatus, SequenceMode};
-    use crate::models::{Coordinates, SimpleExposure, SimpleSequence, SimpleTarget};
+    use crate::models::common::{
+        BinningMode, ImageType, SequenceEntityStatus, SequenceMode, TargetPriority,
+    };
+    use crate::models::{
+        Coordinates, ObservingConstraints, SimpleExposure, SimpleSequence, SimpleTarget,
+        UnitPreferences,
+    };
+    use chrono::{TimeZone, Utc};
 
     fn create_test_sequence() -> SimpleSequence {
         let mut seq = SimpleSequence::new("Test Sequence".to_string());
@@ -15,6 +21,7 @@ mod tests {
         seq
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn create_test_target(
         name: &str,
         ra_h: i32,
@@ -40,6 +47,10 @@ mod tests {
             center_target: true,
             rotate_target: false,
             start_guiding: true,
+            priority: TargetPriority::Normal,
+            min_integration: None,
+            max_integration: None,
+            constraints: Default::default(),
             auto_focus_on_start: true,
             auto_focus_on_filter_change: false,
             auto_focus_after_set_time: false,
@@ -54,6 +65,10 @@ mod tests {
             estimated_start_time: None,
             estimated_end_time: None,
             estimated_duration: None,
+            moving_target: None,
+            notes: None,
+            tags: Vec::new(),
+            metadata: Default::default(),
         }
     }
 
@@ -72,6 +87,8 @@ mod tests {
             progress_count: 0,
             dither: false,
             dither_every: 1,
+            estimated_start_time: None,
+            estimated_end_time: None,
         }
     }
 
@@ -181,6 +198,347 @@ mod tests {
         assert!(result.content.contains("<ObjectList>"));
     }
 
+    // ============================================================================
+    // Annotation Round-Trip Tests
+    // ============================================================================
+
+    fn annotate(seq: &mut SimpleSequence) {
+        let target = &mut seq.targets[0];
+        target.notes = Some("Needs \"dark\" sky, <mosaic> & flats".to_string());
+        target.tags = vec!["galaxy".to_string(), "autumn".to_string()];
+        target.metadata = [
+            ("Catalog".to_string(), serde_json::json!("NGC 224")),
+            ("magnitude".to_string(), serde_json::json!(3.4)),
+            ("id".to_string(), serde_json::json!("224")),
+            ("panels".to_string(), serde_json::json!([1, 2])),
+        ]
+        .into_iter()
+        .collect();
+    }
+
+    fn assert_annotations(original: &SimpleTarget, imported: &SimpleTarget) {
+        assert_eq!(imported.notes, original.notes);
+        assert_eq!(imported.tags, original.tags);
+        assert_eq!(imported.metadata, original.metadata);
+    }
+
+    #[test]
+    fn test_csv_annotation_round_trip() {
+        let mut seq = create_test_sequence();
+        annotate(&mut seq);
+        let options = ExportOptions {
+            include_exposures: false,
+            ..Default::default()
+        };
+
+        let result = export_to_csv(&seq, &options);
+        assert!(result
+            .content
+            .starts_with("Name,RA,Dec,Position Angle,Notes,Tags,Meta:Catalog,Meta:id"));
+
+        let imported = super::super::import_service::parse_csv_content(&result.content, None);
+        assert_eq!(imported.targets.len(), 2);
+        assert_annotations(&seq.targets[0], &imported.targets[0]);
+        assert_annotations(&seq.targets[1], &imported.targets[1]);
+    }
+
+    #[test]
+    fn test_export_to_csv_with_locale_units() {
+        let seq = create_test_sequence();
+        let options = ExportOptions {
+            include_exposures: false,
+            coordinate_format: CoordinateFormat::Decimal,
+            units: Some(UnitPreferences::for_locale("de-DE")),
+            ..Default::default()
+        };
+
+        let result = export_to_csv(&seq, &options);
+        let lines: Vec<&str> = result.content.lines().collect();
+        assert_eq!(lines[0], "Name;RA;Dec;Position Angle");
+        assert!(lines[1].starts_with("M31;0,7"));
+        assert!(lines[1].ends_with(";0,0"));
+    }
+
+    #[test]
+    fn test_export_file_names() {
+        let mut seq = create_test_sequence();
+        seq.title = " Club night: M31/M42? ".to_string();
+        assert_eq!(export_file_stem(&seq), "Club night_ M31_M42_");
+
+        seq.title = "..".to_string();
+        assert_eq!(export_file_stem(&seq), "sequence");
+
+        let formats = ExportFormat::ALL;
+        let mut suffixes: Vec<&str> = formats.iter().map(|f| f.file_suffix()).collect();
+        suffixes.sort();
+        suffixes.dedup();
+        assert_eq!(suffixes.len(), formats.len());
+    }
+
+    #[test]
+    fn test_export_format_from_name() {
+        assert_eq!(
+            ExportFormat::from_name("csv_telescopius"),
+            Some(ExportFormat::CsvTelescopius)
+        );
+        assert_eq!(
+            ExportFormat::from_name("voyagerRoboTarget"),
+            Some(ExportFormat::VoyagerRoboTarget)
+        );
+        assert_eq!(
+            ExportFormat::from_name("NINA"),
+            Some(ExportFormat::NinaTargetSet)
+        );
+        assert_eq!(ExportFormat::from_name("docx"), None);
+    }
+
+    #[test]
+    fn test_xml_annotation_round_trip() {
+        let mut seq = create_test_sequence();
+        annotate(&mut seq);
+
+        let result = export_to_xml(&seq, &ExportOptions::default());
+        assert!(result.content.contains("<Tag>galaxy</Tag>"));
+
+        let imported = super::super::import_service::parse_xml_content(&result.content);
+        assert_eq!(imported.targets.len(), 2);
+        assert_annotations(&seq.targets[0], &imported.targets[0]);
+        assert_annotations(&seq.targets[1], &imported.targets[1]);
+    }
+
+    #[test]
+    fn test_xml_constraints_round_trip() {
+        let mut seq = create_test_sequence();
+        seq.targets[0].constraints = ObservingConstraints {
+            min_altitude: Some(30.0),
+            max_airmass: Some(2.0),
+            min_hour_angle: Some(-3.5),
+            min_moon_separation: Some(45.0),
+            latest_start: Some(Utc.with_ymd_and_hms(2024, 10, 15, 23, 30, 0).unwrap()),
+            ..Default::default()
+        };
+
+        let result = export_to_xml(&seq, &ExportOptions::default());
+        assert!(result.content.contains("<MinHourAngle>-3.5</MinHourAngle>"));
+
+        let imported = super::super::import_service::parse_xml_content(&result.content);
+        assert_eq!(imported.targets[0].constraints, seq.targets[0].constraints);
+        assert!(imported.targets[1].constraints.is_empty());
+    }
+
+    fn assert_same_position(original: &SimpleTarget, imported: &SimpleTarget) {
+        assert_eq!(imported.target_name, original.target_name);
+        assert!(
+            (imported.coordinates.ra_to_degrees() - original.coordinates.ra_to_degrees()).abs()
+                < 1e-3
+        );
+        assert!(
+            (imported.coordinates.dec_to_decimal() - original.coordinates.dec_to_decimal()).abs()
+                < 1e-3
+        );
+    }
+
+    fn detailed_sequence() -> SimpleSequence {
+        let mut seq = create_test_sequence();
+        seq.targets[0].target_name = "M31 & <Andromeda>".to_string();
+        seq.targets[0].position_angle = 35.0;
+        seq.targets[0].mode = SequenceMode::Rotate;
+        seq.targets[0].delay = 5;
+        seq.targets[0].exposures = vec![
+            SimpleExposure {
+                exposure_time: 300.0,
+                filter: Some(crate::models::common::FilterInfo {
+                    name: "Ha".to_string(),
+                    position: 2,
+                    ..Default::default()
+                }),
+                binning: BinningMode { x: 2, y: 2 },
+                gain: 139,
+                offset: 21,
+                total_count: 20,
+                progress_count: 4,
+                dither: true,
+                dither_every: 3,
+                ..create_test_exposure()
+            },
+            SimpleExposure {
+                image_type: ImageType::Flat,
+                enabled: false,
+                ..create_test_exposure()
+            },
+        ];
+        seq
+    }
+
+    #[test]
+    fn test_nina_legacy_xml_round_trip() {
+        let seq = detailed_sequence();
+        let xml = crate::services::serializer::export_to_xml(&seq).unwrap();
+
+        let imported = super::super::import_service::parse_xml_content(&xml);
+        assert!(imported.success);
+        assert_eq!(imported.source_format, "NINA XML");
+        assert_eq!(imported.targets.len(), 2);
+
+        let (original, target) = (&seq.targets[0], &imported.targets[0]);
+        assert_same_position(original, target);
+        assert_same_position(&seq.targets[1], &imported.targets[1]);
+        assert_eq!(target.position_angle, 35.0);
+        assert_eq!(target.mode, SequenceMode::Rotate);
+        assert_eq!(target.delay, 5);
+        assert_eq!(target.exposures.len(), 2);
+
+        let (exp, imported_exp) = (&original.exposures[0], &target.exposures[0]);
+        assert_eq!(imported_exp.exposure_time, exp.exposure_time);
+        assert_eq!(imported_exp.filter.as_ref().unwrap().name, "Ha");
+        assert_eq!(imported_exp.filter.as_ref().unwrap().position, 2);
+        assert_eq!(imported_exp.binning, exp.binning);
+        assert_eq!(
+            (imported_exp.gain, imported_exp.offset),
+            (exp.gain, exp.offset)
+        );
+        assert_eq!(
+            (imported_exp.total_count, imported_exp.progress_count),
+            (20, 4)
+        );
+        assert!(imported_exp.dither);
+        assert_eq!(imported_exp.dither_every, 3);
+        assert!(!target.exposures[1].enabled);
+        assert_eq!(target.exposures[1].image_type, ImageType::Flat);
+        assert!(target.exposures[1].filter.is_none());
+    }
+
+    #[test]
+    fn test_nina_xml_target_set_import() {
+        let mut seq = detailed_sequence();
+        {
+            let target = &mut seq.targets[0];
+            target.auto_focus_after_set_time = true;
+            target.auto_focus_set_time = 45;
+            target.auto_focus_after_set_exposures = true;
+            target.auto_focus_set_exposures = 12;
+            target.auto_focus_after_temperature_change = true;
+            target.auto_focus_after_temperature_change_amount = 1.5;
+            target.auto_focus_after_hfr_change = true;
+            target.auto_focus_after_hfr_change_amount = 20.0;
+            target.exposures[0].filter.as_mut().unwrap().focus_offset = Some(-35);
+        }
+        let xml = crate::services::serializer::export_to_xml(&seq).unwrap();
+
+        let imported = super::super::import_service::import_nina_xml_target_set(&xml).unwrap();
+        assert!(imported.warnings.is_empty());
+        let sequence = imported.sequence;
+        assert_eq!(sequence.targets.len(), 2);
+        assert_eq!(
+            sequence.selected_target_id.as_deref(),
+            Some(sequence.targets[0].id.as_str())
+        );
+
+        let (original, target) = (&seq.targets[0], &sequence.targets[0]);
+        assert_eq!(target.target_name, original.target_name);
+        assert_eq!(target.auto_focus_on_start, original.auto_focus_on_start);
+        assert_eq!(
+            target.auto_focus_on_filter_change,
+            original.auto_focus_on_filter_change
+        );
+        assert!(target.auto_focus_after_set_time);
+        assert_eq!(target.auto_focus_set_time, 45);
+        assert!(target.auto_focus_after_set_exposures);
+        assert_eq!(target.auto_focus_set_exposures, 12);
+        assert!(target.auto_focus_after_temperature_change);
+        assert_eq!(target.auto_focus_after_temperature_change_amount, 1.5);
+        assert!(target.auto_focus_after_hfr_change);
+        assert_eq!(target.auto_focus_after_hfr_change_amount, 20.0);
+
+        let filter = target.exposures[0].filter.as_ref().unwrap();
+        assert_eq!((filter.name.as_str(), filter.position), ("Ha", 2));
+        assert_eq!(filter.focus_offset, Some(-35));
+        assert_eq!(target.exposures[0].binning, BinningMode { x: 2, y: 2 });
+    }
+
+    #[test]
+    fn test_nina_xml_target_set_written_by_nina() {
+        // NINA 1.x writes the filter's private fields
+        let xml = r#"<?xml version="1.0" encoding="utf-8"?>
+<ArrayOfCaptureSequenceList>
+  <CaptureSequenceList TargetName="NGC 7000" AutoFocusAfterSetExposures="true" AutoFocusSetExposures="8">
+    <Coordinates><RA>314.75</RA><Dec>44.33</Dec><Epoch>J2000</Epoch></Coordinates>
+    <Items>
+      <CaptureSequence>
+        <ExposureTime>300</ExposureTime>
+        <ImageType>LIGHT</ImageType>
+        <FilterType><_name>OIII</_name><_focusOffset>12</_focusOffset><_position>3</_position></FilterType>
+        <Binning><X>1</X><Y>1</Y></Binning>
+        <TotalExposureCount>24</TotalExposureCount>
+      </CaptureSequence>
+    </Items>
+  </CaptureSequenceList>
+  <CaptureSequenceList TargetName="Broken" />
+</ArrayOfCaptureSequenceList>"#;
+
+        let imported = super::super::import_service::import_nina_xml_target_set(xml).unwrap();
+        assert_eq!(imported.sequence.targets.len(), 1);
+        assert_eq!(imported.warnings.len(), 1);
+
+        let target = &imported.sequence.targets[0];
+        assert!(target.auto_focus_after_set_exposures);
+        assert_eq!(target.auto_focus_set_exposures, 8);
+        let exposure = &target.exposures[0];
+        assert_eq!(exposure.total_count, 24);
+        let filter = exposure.filter.as_ref().unwrap();
+        assert_eq!(
+            (filter.name.as_str(), filter.position, filter.focus_offset),
+            ("OIII", 3, Some(12))
+        );
+
+        assert!(super::super::import_service::import_nina_xml_target_set("<Targets/>").is_err());
+    }
+
+    #[test]
+    fn test_generic_xml_exposure_round_trip() {
+        let seq = detailed_sequence();
+        let options = ExportOptions {
+            include_progress: true,
+            ..Default::default()
+        };
+        let result = export_to_xml(&seq, &options);
+
+        let imported = super::super::import_service::parse_xml_content(&result.content);
+        assert_eq!(imported.targets.len(), 2);
+        let target = &imported.targets[0];
+        assert_same_position(&seq.targets[0], target);
+        assert_eq!(target.exposures.len(), 2);
+        assert_eq!(target.exposures[0].binning, BinningMode { x: 2, y: 2 });
+        assert_eq!(target.exposures[0].filter.as_ref().unwrap().name, "Ha");
+        assert_eq!(target.exposures[0].total_count, 20);
+        assert_eq!(target.exposures[0].progress_count, 4);
+        assert_eq!(target.exposures[1].image_type, ImageType::Flat);
+    }
+
+    #[test]
+    fn test_apt_xml_round_trip() {
+        let seq = detailed_sequence();
+        let result = export_to_apt_xml(&seq, &ExportOptions::default());
+
+        let imported = super::super::import_service::parse_xml_content(&result.content);
+        assert_eq!(imported.source_format, "APT");
+        assert_eq!(imported.targets.len(), 2);
+        assert_same_position(&seq.targets[0], &imported.targets[0]);
+        assert_same_position(&seq.targets[1], &imported.targets[1]);
+        assert_eq!(imported.targets[0].position_angle, 35.0);
+    }
+
+    #[test]
+    fn test_json_annotation_round_trip() {
+        let mut seq = create_test_sequence();
+        annotate(&mut seq);
+
+        let result = export_to_json(&seq);
+        let imported: SimpleSequence = serde_json::from_str(&result.content).unwrap();
+        assert_annotations(&seq.targets[0], &imported.targets[0]);
+        assert!(!result.content.contains("\"notes\": null"));
+    }
+
     // ============================================================================
     // Stellarium Export Tests
     // ============================================================================
@@ -398,4 +756,101 @@ mod tests {
         // Should be properly escaped
         assert!(result.content.contains("\"Test, \"\"with\"\" special\""));
     }
+
+    // ============================================================================
+    // Session Report Tests
+    // ============================================================================
+
+    #[test]
+    fn test_generate_session_report() {
+        let seq = create_test_sequence();
+        let location = crate::services::astronomy::ObserverLocation {
+            latitude: 40.0,
+            longitude: -74.0,
+            elevation: 0.0,
+            timezone_offset: -5,
+            sky_brightness: None,
+            corrections: Default::default(),
+        };
+        let date = chrono::NaiveDate::from_ymd_opt(2024, 1, 15).unwrap();
+
+        let result =
+            generate_session_report(&seq, &location, date, &SessionReportOptions::default());
+
+        assert!(result.success);
+        assert_eq!(result.format, "HTML");
+        assert_eq!(result.target_count, 2);
+        assert!(result.content.starts_with("<!DOCTYPE html>"));
+        assert!(result.content.contains("<svg"));
+        assert!(result.content.contains("M31"));
+        assert!(result.content.contains("Astronomical"));
+    }
+
+    #[test]
+    fn test_session_report_twilight_west_of_greenwich() {
+        // June dusk in New York falls on the next UTC day
+        let seq = create_test_sequence();
+        let location = crate::services::astronomy::ObserverLocation {
+            latitude: 40.7,
+            longitude: -74.0,
+            timezone_offset: -5,
+            ..Default::default()
+        };
+        let date = chrono::NaiveDate::from_ymd_opt(2024, 6, 15).unwrap();
+
+        let result =
+            generate_session_report(&seq, &location, date, &SessionReportOptions::default());
+
+        let row = result
+            .content
+            .lines()
+            .find(|l| l.starts_with("<tr><td>Astronomical</td>"))
+            .unwrap();
+        assert!(!row.contains("<td>-</td>"), "{}", row);
+    }
+
+    #[test]
+    fn test_schedule_report_entries_back_to_back() {
+        let seq = create_test_sequence();
+        let location = crate::services::astronomy::ObserverLocation::default();
+        let start = chrono::Utc::now();
+
+        let entries = schedule_report_entries(&seq, &location, start);
+
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].start_time, start);
+        assert_eq!(entries[1].start_time, entries[0].end_time);
+        assert!(entries[0].max_altitude >= entries[0].start_altitude);
+    }
+
+    #[test]
+    fn test_export_sequence_jnow() {
+        let seq = create_test_sequence();
+        let j2000 = export_sequence(
+            &seq,
+            &ExportOptions {
+                format: ExportFormat::Voyager,
+                ..Default::default()
+            },
+        );
+        let jnow = export_sequence(
+            &seq,
+            &ExportOptions {
+                format: ExportFormat::Voyager,
+                coordinate_epoch: crate::models::CoordinateEpoch::JNow,
+                epoch_date: Some(chrono::Utc::now()),
+                ..Default::default()
+            },
+        );
+
+        assert!(j2000.content.contains("RA=00:42:44"));
+        assert!(!jnow.content.contains("RA=00:42:44"));
+
+        let converted =
+            convert_sequence_epoch(&seq, crate::models::CoordinateEpoch::JNow, 2460000.5);
+        assert!(converted
+            .targets
+            .iter()
+            .all(|t| t.coordinates.epoch == crate::models::CoordinateEpoch::JNow));
+    }
 }
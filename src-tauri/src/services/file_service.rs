@@ -19,6 +19,12 @@ pub enum FileError {
     InvalidFormat(String),
     #[error("Permission denied: {0}")]
     PermissionDenied(String),
+    #[error("Failed to save {path}: {reason}. The new contents were kept in {temp_path}")]
+    AtomicSaveFailed {
+        path: String,
+        temp_path: String,
+        reason: String,
+    },
 }
 
 pub type Result<T> = std::result::Result<T, FileError>;
@@ -31,13 +37,92 @@ pub async fn read_file(path: &Path) -> Result<String> {
     Ok(fs::read_to_string(path).await?)
 }
 
+/// Checks contents read back after writing, e.g. by parsing them
+pub type WriteVerifier = fn(&str) -> std::result::Result<(), String>;
+
+/// Temporary file next to `path`, so the final rename stays on one file system
+fn temp_path_for(path: &Path) -> PathBuf {
+    let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("file");
+    path.with_file_name(format!(".{}.{}.tmp", name, uuid::Uuid::new_v4().simple()))
+}
+
+/// Write `contents` to a temporary file, flush it to disk, optionally
+/// verify it and rename it over `path`. Readers see either the old or the
+/// new file, never a partial one.
+fn write_atomic_blocking(path: &Path, contents: &str, verify: Option<WriteVerifier>) -> Result<()> {
+    use std::io::Write;
+
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    let temp_path = temp_path_for(path);
+    let written = std::fs::File::create(&temp_path).and_then(|mut file| {
+        file.write_all(contents.as_bytes())?;
+        file.sync_all()
+    });
+    if let Err(e) = written {
+        // A partial temporary file is useless; the original is untouched
+        let _ = std::fs::remove_file(&temp_path);
+        return Err(e.into());
+    }
+
+    let failed = |reason: String| FileError::AtomicSaveFailed {
+        path: path.display().to_string(),
+        temp_path: temp_path.display().to_string(),
+        reason,
+    };
+
+    if let Some(verify) = verify {
+        let read_back = std::fs::read_to_string(&temp_path)
+            .map_err(|e| failed(format!("verification read failed: {}", e)))?;
+        if read_back != contents {
+            return Err(failed("verification found different contents".to_string()));
+        }
+        verify(&read_back).map_err(|e| failed(format!("verification failed: {}", e)))?;
+    }
+
+    std::fs::rename(&temp_path, path).map_err(|e| failed(e.to_string()))?;
+
+    // Persist the rename itself; not possible for directories on Windows
+    #[cfg(unix)]
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        if let Err(e) = std::fs::File::open(parent).and_then(|dir| dir.sync_all()) {
+            log::warn!("Failed to sync directory {}: {}", parent.display(), e);
+        }
+    }
+
+    Ok(())
+}
+
+/// Atomically replace a file's contents, see [`write_atomic_blocking`]
+pub async fn write_file_atomic(
+    path: &Path,
+    contents: &str,
+    verify: Option<WriteVerifier>,
+) -> Result<()> {
+    let path = path.to_path_buf();
+    let contents = contents.to_string();
+    tokio::task::spawn_blocking(move || write_atomic_blocking(&path, &contents, verify))
+        .await
+        .map_err(|e| FileError::Io(std::io::Error::other(e)))?
+}
+
 /// Write string contents to file
 pub async fn write_file(path: &Path, contents: &str) -> Result<()> {
-    // Create parent directories if they don't exist
-    if let Some(parent) = path.parent() {
-        fs::create_dir_all(parent).await?;
-    }
-    Ok(fs::write(path, contents).await?)
+    write_file_atomic(path, contents, None).await
+}
+
+fn verify_simple_sequence_json(json: &str) -> std::result::Result<(), String> {
+    serializer::deserialize_simple_sequence_json(json)
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+fn verify_editor_sequence_json(json: &str) -> std::result::Result<(), String> {
+    serializer::deserialize_editor_sequence_json(json)
+        .map(|_| ())
+        .map_err(|e| e.to_string())
 }
 
 /// Load simple sequence from file
@@ -63,19 +148,23 @@ pub async fn load_simple_sequence(path: &Path) -> Result<SimpleSequence> {
 pub async fn save_simple_sequence(path: &Path, sequence: &SimpleSequence) -> Result<()> {
     let extension = path.extension().and_then(|e| e.to_str()).unwrap_or("");
 
-    let contents = match extension.to_lowercase().as_str() {
-        "json" => serializer::serialize_simple_sequence_json(sequence)?,
-        "csv" => serializer::export_to_csv(sequence)?,
-        "xml" | "ninatargetset" => serializer::export_to_xml(sequence)?,
-        _ => {
-            return Err(FileError::InvalidFormat(format!(
-                "Unsupported file format: {}",
-                extension
-            )))
-        }
-    };
+    let (contents, verify): (String, Option<WriteVerifier>) =
+        match extension.to_lowercase().as_str() {
+            "json" => (
+                serializer::serialize_simple_sequence_json(sequence)?,
+                Some(verify_simple_sequence_json),
+            ),
+            "csv" => (serializer::export_to_csv(sequence)?, None),
+            "xml" | "ninatargetset" => (serializer::export_to_xml(sequence)?, None),
+            _ => {
+                return Err(FileError::InvalidFormat(format!(
+                    "Unsupported file format: {}",
+                    extension
+                )))
+            }
+        };
 
-    write_file(path, &contents).await
+    write_file_atomic(path, &contents, verify).await
 }
 
 /// Load editor sequence from file
@@ -88,7 +177,7 @@ pub async fn load_editor_sequence(path: &Path) -> Result<EditorSequence> {
 /// Save editor sequence to file
 pub async fn save_editor_sequence(path: &Path, sequence: &EditorSequence) -> Result<()> {
     let contents = serializer::serialize_editor_sequence_json(sequence)?;
-    write_file(path, &contents).await
+    write_file_atomic(path, &contents, Some(verify_editor_sequence_json)).await
 }
 
 /// Import targets from CSV file
@@ -123,6 +212,81 @@ pub async fn get_file_info(path: &Path) -> Result<FileInfo> {
     })
 }
 
+/// Bytes read from the start of a file to find its title
+const TITLE_PREFIX_BYTES: u64 = 64 * 1024;
+
+/// String value of the field `key` of the top-level JSON object in
+/// `prefix`, which may be cut off anywhere after that field
+fn top_level_string(prefix: &[u8], key: &str) -> Option<String> {
+    // Index just past the closing quote of the string literal at `start`
+    let literal_end = |start: usize| {
+        let mut i = start + 1;
+        while i < prefix.len() {
+            match prefix[i] {
+                b'\\' => i += 2,
+                b'"' => return Some(i + 1),
+                _ => i += 1,
+            }
+        }
+        None
+    };
+    let skip_space = |mut i: usize| {
+        while prefix.get(i).is_some_and(u8::is_ascii_whitespace) {
+            i += 1;
+        }
+        i
+    };
+
+    let mut depth = 0;
+    let mut i = 0;
+    while i < prefix.len() {
+        match prefix[i] {
+            b'{' | b'[' => depth += 1,
+            b'}' | b']' => depth -= 1,
+            b'"' => {
+                let end = literal_end(i)?;
+                let colon = skip_space(end);
+                // A string followed by a colon is a key
+                if depth == 1 && prefix.get(colon) == Some(&b':') {
+                    let name: String = serde_json::from_slice(&prefix[i..end]).ok()?;
+                    let value = skip_space(colon + 1);
+                    if name == key && prefix.get(value) == Some(&b'"') {
+                        return serde_json::from_slice(&prefix[value..literal_end(value)?]).ok();
+                    }
+                }
+                i = end;
+                continue;
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    None
+}
+
+/// Title of a sequence file, read from the start of the file without
+/// loading the sequence: `title` of simple and editor sequences or `Name`
+/// of NINA sequences. Files other than JSON have no title to read.
+pub async fn read_sequence_title(path: &Path) -> Result<Option<String>> {
+    use tokio::io::AsyncReadExt;
+
+    let extension = path.extension().and_then(|e| e.to_str()).unwrap_or("");
+    if !extension.eq_ignore_ascii_case("json") {
+        return Ok(None);
+    }
+    if !path.exists() {
+        return Err(FileError::NotFound(path.display().to_string()));
+    }
+
+    let mut prefix = Vec::new();
+    fs::File::open(path)
+        .await?
+        .take(TITLE_PREFIX_BYTES)
+        .read_to_end(&mut prefix)
+        .await?;
+    Ok(top_level_string(&prefix, "title").or_else(|| top_level_string(&prefix, "Name")))
+}
+
 /// File information
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -223,3 +387,163 @@ pub fn get_auto_save_directory() -> PathBuf {
 pub fn create_auto_save_path(sequence_id: &str) -> PathBuf {
     get_auto_save_directory().join(format!("{}.autosave.json", sequence_id))
 }
+
+/// Space on the file system holding a path
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiskSpace {
+    /// Bytes available to the current user
+    pub available_bytes: u64,
+    pub total_bytes: u64,
+}
+
+/// Space on the file system holding `path`. A path that does not exist
+/// yet is measured at its nearest existing ancestor.
+pub fn get_disk_space(path: &Path) -> Result<DiskSpace> {
+    let existing = path
+        .ancestors()
+        .find(|p| !p.as_os_str().is_empty() && p.exists())
+        .ok_or_else(|| FileError::NotFound(path.display().to_string()))?;
+    query_disk_space(existing)
+}
+
+#[cfg(unix)]
+fn query_disk_space(path: &Path) -> Result<DiskSpace> {
+    use std::os::unix::ffi::OsStrExt;
+
+    let c_path = std::ffi::CString::new(path.as_os_str().as_bytes())
+        .map_err(|_| FileError::InvalidFormat(path.display().to_string()))?;
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+    // SAFETY: c_path is a valid C string and stat a writable statvfs
+    if unsafe { libc::statvfs(c_path.as_ptr(), &mut stat) } != 0 {
+        return Err(std::io::Error::last_os_error().into());
+    }
+    let fragment_size = stat.f_frsize as u64;
+    Ok(DiskSpace {
+        available_bytes: stat.f_bavail as u64 * fragment_size,
+        total_bytes: stat.f_blocks as u64 * fragment_size,
+    })
+}
+
+#[cfg(windows)]
+fn query_disk_space(path: &Path) -> Result<DiskSpace> {
+    use std::os::windows::ffi::OsStrExt;
+    use windows_sys::Win32::Storage::FileSystem::GetDiskFreeSpaceExW;
+
+    let wide: Vec<u16> = path.as_os_str().encode_wide().chain(Some(0)).collect();
+    let mut available = 0u64;
+    let mut total = 0u64;
+    // SAFETY: wide is NUL-terminated and the out pointers are valid
+    let ok = unsafe {
+        GetDiskFreeSpaceExW(
+            wide.as_ptr(),
+            &mut available,
+            &mut total,
+            std::ptr::null_mut(),
+        )
+    };
+    if ok == 0 {
+        return Err(std::io::Error::last_os_error().into());
+    }
+    Ok(DiskSpace {
+        available_bytes: available,
+        total_bytes: total,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_directory(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("cobalt-{}-{}", name, uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn leftover_temp_files(dir: &Path) -> Vec<PathBuf> {
+        std::fs::read_dir(dir)
+            .unwrap()
+            .map(|e| e.unwrap().path())
+            .filter(|p| p.extension().is_some_and(|e| e == "tmp"))
+            .collect()
+    }
+
+    #[test]
+    fn test_atomic_write_replaces_file() {
+        let dir = test_directory("atomic");
+        let path = dir.join("sequence.json");
+        std::fs::write(&path, "old").unwrap();
+
+        write_atomic_blocking(&path, "new", None).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "new");
+        assert!(leftover_temp_files(&dir).is_empty());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_failed_verification_keeps_original_and_temp_file() {
+        let dir = test_directory("verify");
+        let path = dir.join("sequence.json");
+        std::fs::write(&path, "old").unwrap();
+
+        let error = write_atomic_blocking(&path, "not json", Some(verify_simple_sequence_json))
+            .unwrap_err();
+        let FileError::AtomicSaveFailed { temp_path, .. } = error else {
+            panic!("unexpected error: {}", error);
+        };
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "old");
+        assert_eq!(std::fs::read_to_string(&temp_path).unwrap(), "not json");
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_read_sequence_title() {
+        let dir = test_directory("title");
+        let simple = dir.join("simple.json");
+        std::fs::write(&simple, r#"{"id":"1","title":"M31 mosaic","targets":[]}"#).unwrap();
+        let nina = dir.join("nina.json");
+        std::fs::write(&nina, r#"{"$type":"Sequence","Name":"Nightly","Items":{}}"#).unwrap();
+        let archive = dir.join("plan.ctes");
+        std::fs::write(&archive, "PK").unwrap();
+
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let title = |path: &Path| runtime.block_on(read_sequence_title(path));
+        assert_eq!(title(&simple).unwrap().as_deref(), Some("M31 mosaic"));
+        assert_eq!(title(&nina).unwrap().as_deref(), Some("Nightly"));
+        assert_eq!(title(&archive).unwrap(), None);
+
+        // Only the start of the file is read, and nested titles are skipped
+        let large = dir.join("large.json");
+        let targets = format!(r#"[{{"title":"nested"}},"{}"]"#, "x".repeat(200_000));
+        std::fs::write(
+            &large,
+            format!(r#"{{"id":"2","targets":{},"title":"Late"}}"#, targets),
+        )
+        .unwrap();
+        assert_eq!(title(&large).unwrap(), None);
+        std::fs::write(
+            &large,
+            format!(
+                r#"{{"id":"2","title":"Big \"plan\"","targets":{}}}"#,
+                targets
+            ),
+        )
+        .unwrap();
+        assert_eq!(title(&large).unwrap().as_deref(), Some("Big \"plan\""));
+        assert!(title(&dir.join("missing.json")).is_err());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_get_disk_space() {
+        let dir = test_directory("space");
+        let space = get_disk_space(&dir.join("not/yet/created")).unwrap();
+        assert!(space.total_bytes > 0);
+        assert!(space.available_bytes <= space.total_bytes);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
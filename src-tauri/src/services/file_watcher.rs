@@ -0,0 +1,410 @@
+//! Watches the open sequence file for external changes
+//!
+//! The watched file is polled; a change is reported when its contents
+//! differ from what the editor last loaded or saved, so the editor's own
+//! saves and touch-only updates don't trigger a reload prompt.
+
+use chrono::{DateTime, Utc};
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use tokio::fs;
+
+use crate::models::{EditorSequence, SimpleSequence};
+use crate::services::{file_service, nina_serializer, nina_type_registry};
+use crate::state::{AppState, SharedState};
+
+/// Event emitted to the frontend when the watched file changes
+pub const FILE_CHANGED_EVENT: &str = "file://changed";
+
+/// How often the watched file is checked
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Format of the watched file, which decides how it is reloaded
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum WatchedFileKind {
+    Simple,
+    Editor,
+    Nina,
+}
+
+/// Kind of external change
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum FileChangeKind {
+    Modified,
+    Removed,
+}
+
+/// External change of the watched file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileChangeEvent {
+    pub path: String,
+    pub kind: FileChangeKind,
+    pub modified_at: Option<DateTime<Utc>>,
+}
+
+/// Receives change events, e.g. to forward them to the frontend
+pub type FileChangeListener = Box<dyn Fn(&FileChangeEvent) + Send + Sync>;
+
+/// State of a file on disk
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileSnapshot {
+    pub modified_at: DateTime<Utc>,
+    pub size: u64,
+    pub hash: u64,
+}
+
+struct WatchedFile {
+    path: PathBuf,
+    kind: WatchedFileKind,
+    /// Contents the editor last loaded or saved; `None` if the file did
+    /// not exist
+    baseline: Option<FileSnapshot>,
+    /// Last state seen by the poller, so each change is reported once
+    last_seen: Option<FileSnapshot>,
+    /// Bumped on every watch call to stop earlier pollers
+    generation: u64,
+}
+
+/// Watched file and change listener, part of the
+/// [`AppState`](crate::state::AppState)
+#[derive(Default)]
+pub struct FileWatchState {
+    watched: RwLock<Option<WatchedFile>>,
+    generation: AtomicU64,
+    listener: RwLock<Option<FileChangeListener>>,
+}
+
+/// Install or remove the change listener
+pub fn set_file_change_listener(state: &AppState, listener: Option<FileChangeListener>) {
+    *state.file_watch.listener.write() = listener;
+}
+
+fn notify(state: &AppState, event: &FileChangeEvent) {
+    if let Some(listener) = state.file_watch.listener.read().as_ref() {
+        listener(event);
+    }
+}
+
+/// Hash of file contents
+pub fn content_hash(content: &[u8]) -> u64 {
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    content.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Current state of a file, `None` if it does not exist
+pub async fn snapshot(path: &Path) -> Result<Option<FileSnapshot>, String> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    let content = fs::read(path)
+        .await
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let metadata = fs::metadata(path)
+        .await
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+
+    Ok(Some(FileSnapshot {
+        modified_at: metadata
+            .modified()
+            .map(DateTime::<Utc>::from)
+            .unwrap_or_else(|_| Utc::now()),
+        size: content.len() as u64,
+        hash: content_hash(&content),
+    }))
+}
+
+/// Compare the file on disk against the editor's baseline
+pub fn detect_change(
+    baseline: Option<&FileSnapshot>,
+    current: Option<&FileSnapshot>,
+) -> Option<FileChangeKind> {
+    match (baseline, current) {
+        (_, None) if baseline.is_some() => Some(FileChangeKind::Removed),
+        (None, Some(_)) => Some(FileChangeKind::Modified),
+        (Some(baseline), Some(current)) if baseline.hash != current.hash => {
+            Some(FileChangeKind::Modified)
+        }
+        _ => None,
+    }
+}
+
+/// Whether the poller needs to look at the contents again
+fn metadata_changed(
+    last_seen: Option<&FileSnapshot>,
+    modified_at: DateTime<Utc>,
+    size: u64,
+) -> bool {
+    last_seen.map_or(true, |s| s.modified_at != modified_at || s.size != size)
+}
+
+async fn poll(state: SharedState, generation: u64) {
+    let mut interval = tokio::time::interval(POLL_INTERVAL);
+    loop {
+        interval.tick().await;
+
+        let (path, last_seen) = match state.file_watch.watched.read().as_ref() {
+            Some(watched) if watched.generation == generation => {
+                (watched.path.clone(), watched.last_seen)
+            }
+            _ => return,
+        };
+
+        // Cheap metadata check before reading the file
+        let unchanged = match fs::metadata(&path).await {
+            Ok(metadata) => {
+                let modified_at = metadata
+                    .modified()
+                    .map(DateTime::<Utc>::from)
+                    .unwrap_or_else(|_| Utc::now());
+                !metadata_changed(last_seen.as_ref(), modified_at, metadata.len())
+            }
+            Err(_) => last_seen.is_none(),
+        };
+        if unchanged {
+            continue;
+        }
+
+        let current = match snapshot(&path).await {
+            Ok(current) => current,
+            Err(e) => {
+                log::warn!("Failed to check {} for changes: {}", path.display(), e);
+                continue;
+            }
+        };
+
+        let change = {
+            let mut watched = state.file_watch.watched.write();
+            let Some(watched) = watched.as_mut().filter(|w| w.generation == generation) else {
+                return;
+            };
+            watched.last_seen = current;
+            detect_change(watched.baseline.as_ref(), current.as_ref())
+        };
+
+        if let Some(kind) = change {
+            notify(
+                &state,
+                &FileChangeEvent {
+                    path: path.display().to_string(),
+                    kind,
+                    modified_at: current.map(|c| c.modified_at),
+                },
+            );
+        }
+    }
+}
+
+/// Watch a sequence file, replacing the previously watched one. The
+/// current contents become the baseline.
+pub async fn watch_file(
+    state: &SharedState,
+    path: &Path,
+    kind: WatchedFileKind,
+) -> Result<(), String> {
+    let baseline = snapshot(path).await?;
+    let generation = state.file_watch.generation.fetch_add(1, Ordering::SeqCst) + 1;
+
+    *state.file_watch.watched.write() = Some(WatchedFile {
+        path: path.to_path_buf(),
+        kind,
+        baseline,
+        last_seen: baseline,
+        generation,
+    });
+    tokio::spawn(poll(state.clone(), generation));
+    Ok(())
+}
+
+/// Stop watching
+pub fn unwatch_file(state: &AppState) {
+    *state.file_watch.watched.write() = None;
+}
+
+fn is_watched(state: &AppState, path: &Path) -> bool {
+    state
+        .file_watch
+        .watched
+        .read()
+        .as_ref()
+        .is_some_and(|watched| watched.path == path)
+}
+
+/// Take the file's current contents as the baseline after the editor
+/// itself wrote it. Does nothing for files that are not watched.
+pub async fn acknowledge_save(state: &AppState, path: &Path) -> Result<(), String> {
+    if !is_watched(state, path) {
+        return Ok(());
+    }
+
+    let current = snapshot(path).await?;
+    if let Some(watched) = state
+        .file_watch
+        .watched
+        .write()
+        .as_mut()
+        .filter(|w| w.path == path)
+    {
+        watched.baseline = current;
+        watched.last_seen = current;
+    }
+    Ok(())
+}
+
+/// Outcome of [`reload_if_changed`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ReloadStatus {
+    /// The file matches the baseline
+    Unchanged,
+    /// The file changed and was reloaded
+    Reloaded,
+    /// The file changed but the editor has unsaved edits; nothing was
+    /// reloaded
+    Conflict,
+    /// The file no longer exists
+    Removed,
+}
+
+/// Reloaded sequence, in the model matching the watched file's kind
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "sequence", rename_all = "camelCase")]
+pub enum ReloadedSequence {
+    Simple(SimpleSequence),
+    Editor(EditorSequence),
+}
+
+/// Result of [`reload_if_changed`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReloadResult {
+    pub status: ReloadStatus,
+    pub modified_at: Option<DateTime<Utc>>,
+    pub sequence: Option<ReloadedSequence>,
+}
+
+async fn load(
+    state: &AppState,
+    path: &Path,
+    kind: WatchedFileKind,
+) -> Result<ReloadedSequence, String> {
+    match kind {
+        WatchedFileKind::Simple => file_service::load_simple_sequence(path)
+            .await
+            .map(ReloadedSequence::Simple)
+            .map_err(|e| e.to_string()),
+        WatchedFileKind::Editor => file_service::load_editor_sequence(path)
+            .await
+            .map(ReloadedSequence::Editor)
+            .map_err(|e| e.to_string()),
+        WatchedFileKind::Nina => {
+            let content = file_service::read_file(path)
+                .await
+                .map_err(|e| e.to_string())?;
+            let stable_ids = state.settings.read().stable_nina_ids;
+            let plugins = nina_type_registry::plugin_types(state);
+            nina_serializer::import_from_nina(&content, stable_ids, &plugins)
+                .map(ReloadedSequence::Editor)
+        }
+    }
+}
+
+/// Reload the watched file if it changed on disk. With unsaved edits in
+/// the editor a change is reported as a conflict unless `force` is set,
+/// in which case the edits are discarded.
+pub async fn reload_if_changed(
+    state: &AppState,
+    path: &Path,
+    has_unsaved_changes: bool,
+    force: bool,
+) -> Result<ReloadResult, String> {
+    let (kind, baseline) = match state.file_watch.watched.read().as_ref() {
+        Some(watched) if watched.path == path => (watched.kind, watched.baseline),
+        _ => return Err(format!("File is not being watched: {}", path.display())),
+    };
+
+    let current = snapshot(path).await?;
+    let modified_at = current.map(|c| c.modified_at);
+    let status = match detect_change(baseline.as_ref(), current.as_ref()) {
+        None => ReloadStatus::Unchanged,
+        Some(FileChangeKind::Removed) => ReloadStatus::Removed,
+        Some(FileChangeKind::Modified) if has_unsaved_changes && !force => ReloadStatus::Conflict,
+        Some(FileChangeKind::Modified) => {
+            let sequence = load(state, path, kind).await?;
+            if let Some(watched) = state
+                .file_watch
+                .watched
+                .write()
+                .as_mut()
+                .filter(|w| w.path == path)
+            {
+                watched.baseline = current;
+                watched.last_seen = current;
+            }
+            return Ok(ReloadResult {
+                status: ReloadStatus::Reloaded,
+                modified_at,
+                sequence: Some(sequence),
+            });
+        }
+    };
+
+    Ok(ReloadResult {
+        status,
+        modified_at,
+        sequence: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(content: &[u8]) -> FileSnapshot {
+        FileSnapshot {
+            modified_at: Utc::now(),
+            size: content.len() as u64,
+            hash: content_hash(content),
+        }
+    }
+
+    #[test]
+    fn test_detect_change() {
+        let original = file(b"{\"title\":\"A\"}");
+        let touched = FileSnapshot {
+            modified_at: original.modified_at + chrono::Duration::seconds(5),
+            ..original
+        };
+        let edited = file(b"{\"title\":\"B\"}");
+
+        assert_eq!(detect_change(Some(&original), Some(&touched)), None);
+        assert_eq!(
+            detect_change(Some(&original), Some(&edited)),
+            Some(FileChangeKind::Modified)
+        );
+        assert_eq!(
+            detect_change(Some(&original), None),
+            Some(FileChangeKind::Removed)
+        );
+        assert_eq!(
+            detect_change(None, Some(&edited)),
+            Some(FileChangeKind::Modified)
+        );
+        assert_eq!(detect_change(None, None), None);
+    }
+
+    #[test]
+    fn test_metadata_changed() {
+        let seen = file(b"abc");
+        assert!(!metadata_changed(Some(&seen), seen.modified_at, seen.size));
+        assert!(metadata_changed(Some(&seen), seen.modified_at, 4));
+        assert!(metadata_changed(None, seen.modified_at, seen.size));
+    }
+}
@@ -0,0 +1,204 @@
+//! Per-filter focus offsets in NINA sequences
+//!
+//! NINA moves the focuser by a filter's focus offset when it switches to
+//! that filter, so a sequence can change filters without refocusing.
+//! Switch Filter items keep their own copy of the filter, offset included.
+//! [`apply_filter_offsets`] writes the offsets of the filter set into
+//! those copies. Filters without an offset are reported when no Autofocus
+//! After Filter Change trigger covers the switch, since the frames after
+//! it would come out soft.
+
+use std::collections::HashSet;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::models::{EditorSequence, EditorSequenceItem, EditorTrigger, FilterInfo};
+use crate::services::validator::get_short_type_name;
+
+/// Result of [`apply_filter_offsets`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FilterOffsetResult {
+    pub sequence: EditorSequence,
+    /// Ids of the Switch Filter items whose offset was changed
+    pub updated_item_ids: Vec<String>,
+    /// Filters switched to without an offset or a refocus
+    pub warnings: Vec<String>,
+}
+
+/// Value under `key` in any letter case, with or without the leading
+/// underscore NINA writes
+fn field<'a>(
+    object: &'a serde_json::Map<String, Value>,
+    key: &str,
+) -> Option<(&'a String, &'a Value)> {
+    object
+        .iter()
+        .find(|(k, _)| k.trim_start_matches('_').eq_ignore_ascii_case(key))
+}
+
+fn refocuses_on_filter_change(triggers: Option<&Vec<EditorTrigger>>) -> bool {
+    triggers
+        .into_iter()
+        .flatten()
+        .any(|t| get_short_type_name(&t.trigger_type) == "AutofocusAfterFilterChange")
+}
+
+struct OffsetWalk<'a> {
+    filters: &'a [FilterInfo],
+    updated: Vec<String>,
+    warnings: Vec<String>,
+    warned: HashSet<String>,
+}
+
+impl OffsetWalk<'_> {
+    fn apply(&mut self, item: &mut EditorSequenceItem, refocused: bool) {
+        let filter = item
+            .data
+            .iter_mut()
+            .find(|(k, _)| k.eq_ignore_ascii_case("Filter"))
+            .and_then(|(_, v)| v.as_object_mut());
+        let Some(filter) = filter else {
+            return;
+        };
+        let Some(name) = field(filter, "name")
+            .and_then(|(_, v)| v.as_str())
+            .map(str::to_string)
+        else {
+            return;
+        };
+
+        let offset = self
+            .filters
+            .iter()
+            .find(|f| f.name.eq_ignore_ascii_case(&name))
+            .and_then(|f| f.focus_offset);
+        match offset {
+            Some(offset) => {
+                let key = field(filter, "focusOffset")
+                    .map(|(k, _)| k.clone())
+                    .unwrap_or_else(|| "_focusOffset".to_string());
+                if filter.get(&key).and_then(Value::as_i64) != Some(i64::from(offset)) {
+                    filter.insert(key, json!(offset));
+                    self.updated.push(item.id.clone());
+                }
+            }
+            None if !refocused && self.warned.insert(name.to_lowercase()) => {
+                self.warnings.push(format!(
+                    "Filter '{}' has no focus offset and no autofocus after filter change; frames after '{}' may be soft",
+                    name, item.name
+                ));
+            }
+            None => {}
+        }
+    }
+
+    fn walk(&mut self, items: &mut [EditorSequenceItem], refocused: bool) {
+        for item in items {
+            let refocused = refocused || refocuses_on_filter_change(item.triggers.as_ref());
+            if get_short_type_name(&item.item_type) == "SwitchFilter" {
+                self.apply(item, refocused);
+            }
+
+            self.walk(item.items.as_deref_mut().unwrap_or_default(), refocused);
+            for trigger in item.triggers.iter_mut().flatten() {
+                self.walk(
+                    trigger.trigger_items.as_deref_mut().unwrap_or_default(),
+                    refocused,
+                );
+            }
+        }
+    }
+}
+
+/// Write the focus offsets of `filters` into the sequence's Switch Filter
+/// items, warning about filters without one that are switched to without
+/// a refocus
+pub fn apply_filter_offsets(
+    mut sequence: EditorSequence,
+    filters: &[FilterInfo],
+) -> FilterOffsetResult {
+    let refocused = refocuses_on_filter_change(Some(&sequence.global_triggers));
+    let mut walk = OffsetWalk {
+        filters,
+        updated: Vec::new(),
+        warnings: Vec::new(),
+        warned: HashSet::new(),
+    };
+    walk.walk(&mut sequence.start_items, refocused);
+    walk.walk(&mut sequence.target_items, refocused);
+    walk.walk(&mut sequence.end_items, refocused);
+
+    FilterOffsetResult {
+        sequence,
+        updated_item_ids: walk.updated,
+        warnings: walk.warnings,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn switch_filter(id: &str, name: &str) -> Value {
+        json!({
+            "id": id,
+            "type": "NINA.Sequencer.SequenceItem.FilterWheel.SwitchFilter, NINA.Sequencer",
+            "name": "Switch Filter",
+            "category": "",
+            "status": "CREATED",
+            "data": { "Filter": { "_name": name, "_position": 0, "_focusOffset": 0 } },
+        })
+    }
+
+    fn filter(name: &str, offset: Option<i32>) -> FilterInfo {
+        FilterInfo {
+            name: name.to_string(),
+            focus_offset: offset,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_apply_filter_offsets() {
+        let container = json!({
+            "id": "dso",
+            "type": "NINA.Sequencer.Container.DeepSkyObjectContainer, NINA.Sequencer",
+            "name": "M42",
+            "category": "",
+            "status": "CREATED",
+            "items": [switch_filter("ha", "Ha"), switch_filter("oiii", "OIII")],
+            "triggers": [],
+        });
+        let mut sequence = EditorSequence::new("Offsets");
+        sequence.target_items = vec![
+            serde_json::from_value(container).unwrap(),
+            serde_json::from_value(switch_filter("l", "l")).unwrap(),
+        ];
+        let filters = [
+            filter("L", Some(0)),
+            filter("Ha", Some(-40)),
+            filter("OIII", None),
+        ];
+
+        let result = apply_filter_offsets(sequence.clone(), &filters);
+        assert_eq!(result.updated_item_ids, ["ha"]);
+        let ha = &result.sequence.target_items[0].items.as_ref().unwrap()[0];
+        assert_eq!(ha.data["Filter"]["_focusOffset"], -40);
+        assert_eq!(result.warnings.len(), 1);
+        assert!(result.warnings[0].contains("OIII"));
+
+        // No warning where autofocus runs after each filter change
+        let trigger: EditorTrigger = serde_json::from_value(json!({
+            "id": "af",
+            "type": "NINA.Sequencer.Trigger.Autofocus.AutofocusAfterFilterChange, NINA.Sequencer",
+            "name": "Autofocus After Filter Change",
+            "category": "",
+        }))
+        .unwrap();
+        sequence.target_items[0].triggers = Some(vec![trigger]);
+        let result = apply_filter_offsets(sequence, &filters);
+        assert!(result.warnings.is_empty());
+    }
+}
@@ -0,0 +1,626 @@
+//! FITS header reading
+//!
+//! Headers are read block by block until the `END` card, so long headers
+//! and extension HDUs are covered without loading image data. String
+//! values follow the FITS quoting rules, including the `CONTINUE`
+//! long-string convention.
+
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufReader, Cursor, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+/// Size of FITS header and data blocks
+pub const FITS_BLOCK_SIZE: usize = 2880;
+
+const CARD_SIZE: usize = 80;
+
+/// Extensions of FITS files picked up by directory scans
+pub const FITS_EXTENSIONS: &[&str] = &["fits", "fit", "fts"];
+
+/// Upper bound on header blocks per HDU, against files without `END`
+const MAX_HEADER_BLOCKS: usize = 1000;
+
+/// HDUs read per file; observation keywords live in the first few
+const MAX_HDUS: usize = 16;
+
+/// Header card
+#[derive(Debug, Clone, PartialEq)]
+pub struct FitsCard {
+    pub key: String,
+    /// Value without quotes or comment
+    pub value: String,
+    pub is_string: bool,
+}
+
+/// FITS header info
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FitsHeaderInfo {
+    pub object_name: Option<String>,
+    /// Degrees
+    pub ra: Option<f64>,
+    /// Degrees
+    pub dec: Option<f64>,
+    pub exposure_time: Option<f64>,
+    pub filter: Option<String>,
+    pub gain: Option<i32>,
+    pub offset: Option<i32>,
+    pub binning_x: Option<i32>,
+    pub binning_y: Option<i32>,
+    pub date_obs: Option<String>,
+    pub telescope: Option<String>,
+    pub instrument: Option<String>,
+    /// `IMAGETYP`, e.g. "Light Frame"
+    #[serde(default)]
+    pub image_type: Option<String>,
+}
+
+/// Parse a quoted string value starting at `'`. Quotes inside are
+/// doubled; trailing spaces are not significant.
+pub fn parse_string(field: &str) -> String {
+    let mut value = String::new();
+    let mut chars = field.chars().skip(1).peekable();
+    while let Some(c) = chars.next() {
+        if c == '\'' {
+            if chars.peek() != Some(&'\'') {
+                break;
+            }
+            chars.next();
+        }
+        value.push(c);
+    }
+    value.trim_end().to_string()
+}
+
+/// Parse one 80-character card. Cards without a value (comments, blank
+/// cards, `END`) only carry their key.
+pub fn parse_card(card: &str) -> FitsCard {
+    let key = card.get(..8).unwrap_or(card).trim().to_string();
+    let field = if key == "CONTINUE" {
+        card.get(8..)
+    } else if card.get(8..10) == Some("= ") {
+        card.get(10..)
+    } else {
+        None
+    };
+
+    let Some(field) = field.map(str::trim_start) else {
+        return FitsCard {
+            key,
+            value: String::new(),
+            is_string: false,
+        };
+    };
+
+    if field.starts_with('\'') {
+        FitsCard {
+            key,
+            value: parse_string(field),
+            is_string: true,
+        }
+    } else {
+        FitsCard {
+            key,
+            value: field.split('/').next().unwrap_or("").trim().to_string(),
+            is_string: false,
+        }
+    }
+}
+
+/// Read header cards up to `END`, joining `CONTINUE` cards onto the long
+/// string they extend. Returns `None` at end of input.
+pub fn read_header<R: Read>(reader: &mut R) -> Result<Option<Vec<FitsCard>>, String> {
+    let mut cards: Vec<FitsCard> = Vec::new();
+    let mut block = vec![0u8; FITS_BLOCK_SIZE];
+
+    for block_index in 0..MAX_HEADER_BLOCKS {
+        if let Err(e) = reader.read_exact(&mut block) {
+            if block_index == 0 && e.kind() == std::io::ErrorKind::UnexpectedEof {
+                return Ok(None);
+            }
+            return Err(format!("Truncated FITS header: {}", e));
+        }
+
+        for raw in block.chunks(CARD_SIZE) {
+            let card = parse_card(&String::from_utf8_lossy(raw));
+            if card.key == "END" {
+                return Ok(Some(cards));
+            }
+            if card.key == "CONTINUE" {
+                if let Some(previous) = cards
+                    .last_mut()
+                    .filter(|c| c.is_string && c.value.ends_with('&'))
+                {
+                    previous.value.pop();
+                    previous.value.push_str(&card.value);
+                    continue;
+                }
+            }
+            cards.push(card);
+        }
+    }
+
+    Err("FITS header has no END card".to_string())
+}
+
+fn card_number(cards: &[FitsCard], key: &str) -> Option<i64> {
+    cards
+        .iter()
+        .find(|c| c.key == key)
+        .and_then(|c| c.value.parse().ok())
+}
+
+/// Size of the data following a header, padded to whole blocks
+pub fn data_size(cards: &[FitsCard]) -> u64 {
+    let axes = card_number(cards, "NAXIS").unwrap_or(0);
+    if axes <= 0 {
+        return 0;
+    }
+    let elements: i64 = (1..=axes)
+        .map(|n| card_number(cards, &format!("NAXIS{}", n)).unwrap_or(0))
+        .product();
+    let bytes_per_element = card_number(cards, "BITPIX").unwrap_or(0).abs() / 8;
+    let groups = card_number(cards, "GCOUNT").unwrap_or(1);
+    let parameters = card_number(cards, "PCOUNT").unwrap_or(0);
+
+    let size = (bytes_per_element * groups * (parameters + elements)).max(0) as u64;
+    size.div_ceil(FITS_BLOCK_SIZE as u64) * FITS_BLOCK_SIZE as u64
+}
+
+/// Read the headers of the primary HDU and its extensions
+pub fn read_hdus<R: Read + Seek>(reader: &mut R) -> Result<Vec<Vec<FitsCard>>, String> {
+    let mut hdus = Vec::new();
+    let primary = read_header(reader)?.ok_or("Empty FITS file")?;
+    let mut skip = data_size(&primary);
+    hdus.push(primary);
+
+    while hdus.len() < MAX_HDUS {
+        if reader.seek(SeekFrom::Current(skip as i64)).is_err() {
+            break;
+        }
+        // A damaged extension doesn't invalidate the primary header
+        let Ok(Some(cards)) = read_header(reader) else {
+            break;
+        };
+        if cards.first().map(|c| c.key.as_str()) != Some("XTENSION") {
+            break;
+        }
+        skip = data_size(&cards);
+        hdus.push(cards);
+    }
+
+    Ok(hdus)
+}
+
+/// Sexagesimal value like `05 35 16.2`, `-05:23:28` or `05h35m16s`
+fn parse_sexagesimal(value: &str) -> Option<f64> {
+    let value = value.trim();
+    let negative = value.starts_with('-');
+    let parts: Vec<f64> = value
+        .trim_start_matches(['+', '-'])
+        .split([' ', ':', 'h', 'm', 's', 'd', '°', '\'', '"'])
+        .filter(|p| !p.is_empty())
+        .map(|p| p.parse().ok())
+        .collect::<Option<_>>()?;
+    if parts.is_empty() || parts.len() > 3 {
+        return None;
+    }
+
+    let magnitude = parts
+        .iter()
+        .zip([1.0, 60.0, 3600.0])
+        .map(|(part, divisor)| part / divisor)
+        .sum::<f64>();
+    Some(if negative { -magnitude } else { magnitude })
+}
+
+/// RA in degrees: plain numbers are degrees, sexagesimal strings hours
+fn parse_ra_value(value: &str) -> Option<f64> {
+    value
+        .parse::<f64>()
+        .ok()
+        .or_else(|| parse_sexagesimal(value).map(|hours| hours * 15.0))
+        .filter(|ra| (0.0..360.0).contains(ra))
+}
+
+fn parse_dec_value(value: &str) -> Option<f64> {
+    value
+        .parse::<f64>()
+        .ok()
+        .or_else(|| parse_sexagesimal(value))
+        .filter(|dec| (-90.0..=90.0).contains(dec))
+}
+
+fn parse_integer(value: &str) -> Option<i32> {
+    value.parse::<f64>().ok().map(|v| v.round() as i32)
+}
+
+/// Collect observation keywords. Earlier HDUs take precedence; `RA`/`DEC`
+/// are preferred over `OBJCTRA`/`OBJCTDEC`.
+pub fn header_info(hdus: &[Vec<FitsCard>]) -> FitsHeaderInfo {
+    let mut info = FitsHeaderInfo::default();
+    let mut objct_ra = None;
+    let mut objct_dec = None;
+
+    fn set<T>(field: &mut Option<T>, value: Option<T>) {
+        if field.is_none() {
+            *field = value;
+        }
+    }
+    let text = |card: &FitsCard| Some(card.value.clone()).filter(|v| !v.is_empty());
+
+    for card in hdus.iter().flatten() {
+        let value = card.value.as_str();
+        match card.key.as_str() {
+            "OBJECT" => set(&mut info.object_name, text(card)),
+            "RA" => set(&mut info.ra, parse_ra_value(value)),
+            "OBJCTRA" => set(&mut objct_ra, parse_ra_value(value)),
+            "DEC" => set(&mut info.dec, parse_dec_value(value)),
+            "OBJCTDEC" => set(&mut objct_dec, parse_dec_value(value)),
+            "EXPTIME" | "EXPOSURE" => set(&mut info.exposure_time, value.parse().ok()),
+            "FILTER" => set(&mut info.filter, text(card)),
+            "GAIN" => set(&mut info.gain, parse_integer(value)),
+            "OFFSET" => set(&mut info.offset, parse_integer(value)),
+            "XBINNING" => set(&mut info.binning_x, parse_integer(value)),
+            "YBINNING" => set(&mut info.binning_y, parse_integer(value)),
+            "DATE-OBS" => set(&mut info.date_obs, text(card)),
+            "TELESCOP" => set(&mut info.telescope, text(card)),
+            "INSTRUME" => set(&mut info.instrument, text(card)),
+            "IMAGETYP" => set(&mut info.image_type, text(card)),
+            _ => {}
+        }
+    }
+
+    set(&mut info.ra, objct_ra);
+    set(&mut info.dec, objct_dec);
+    info
+}
+
+/// Parse FITS headers from file content
+pub fn parse_fits_header(content: &[u8]) -> Result<FitsHeaderInfo, String> {
+    if content.len() < FITS_BLOCK_SIZE {
+        return Err("File too small to be a valid FITS file".to_string());
+    }
+    Ok(header_info(&read_hdus(&mut Cursor::new(content))?))
+}
+
+/// Read the headers of a FITS file without loading its data
+pub fn read_fits_header_file(path: &Path) -> Result<FitsHeaderInfo, String> {
+    let file = File::open(path).map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    let hdus =
+        read_hdus(&mut BufReader::new(file)).map_err(|e| format!("{}: {}", path.display(), e))?;
+    Ok(header_info(&hdus))
+}
+
+// ============================================================================
+// Directory Scan
+// ============================================================================
+
+/// Frames of one object taken through one filter
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FitsFilterStats {
+    pub filter: Option<String>,
+    pub frame_count: usize,
+    /// Seconds
+    pub total_exposure: f64,
+}
+
+/// Light frames of one object
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FitsObjectStats {
+    pub object_name: String,
+    /// Degrees, from the first frame that has coordinates
+    pub ra: Option<f64>,
+    pub dec: Option<f64>,
+    pub frame_count: usize,
+    /// Seconds
+    pub total_exposure: f64,
+    pub filters: Vec<FitsFilterStats>,
+    pub first_observed: Option<String>,
+    pub last_observed: Option<String>,
+}
+
+/// Result of [`scan_fits_directory`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FitsDirectoryScan {
+    pub directory: String,
+    pub scanned_files: usize,
+    pub light_frames: usize,
+    /// Dark, flat and bias frames, which are not counted per object
+    pub calibration_frames: usize,
+    /// Light frames without an `OBJECT` keyword
+    pub unidentified_frames: usize,
+    pub objects: Vec<FitsObjectStats>,
+    pub errors: Vec<String>,
+}
+
+/// Whether `IMAGETYP` names a dark, flat or bias frame
+pub fn is_calibration(image_type: Option<&str>) -> bool {
+    image_type.is_some_and(|t| {
+        let t = t.to_lowercase();
+        ["dark", "flat", "bias", "offset"]
+            .iter()
+            .any(|kind| t.contains(kind))
+    })
+}
+
+/// Files with one of the extensions below `directory`, skipping hidden
+/// entries
+pub fn find_image_files(directory: &Path, extensions: &[&str]) -> Result<Vec<PathBuf>, String> {
+    let mut files = Vec::new();
+    let mut pending = vec![directory.to_path_buf()];
+
+    while let Some(dir) = pending.pop() {
+        let entries = std::fs::read_dir(&dir)
+            .map_err(|e| format!("Failed to read {}: {}", dir.display(), e))?;
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let hidden = path
+                .file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(|n| n.starts_with('.'));
+            if hidden {
+                continue;
+            }
+            if path.is_dir() {
+                pending.push(path);
+                continue;
+            }
+            let extension = path
+                .extension()
+                .and_then(|e| e.to_str())
+                .unwrap_or_default()
+                .to_lowercase();
+            if extensions.contains(&extension.as_str()) {
+                files.push(path);
+            }
+        }
+    }
+
+    files.sort();
+    Ok(files)
+}
+
+/// Aggregate per-object exposure statistics of the FITS files below
+/// `directory`
+pub fn scan_fits_directory(directory: &Path) -> Result<FitsDirectoryScan, String> {
+    let files = find_image_files(directory, FITS_EXTENSIONS)?;
+    let headers: Vec<Result<FitsHeaderInfo, String>> = files
+        .par_iter()
+        .map(|path| read_fits_header_file(path))
+        .collect();
+
+    let mut scan = FitsDirectoryScan {
+        directory: directory.display().to_string(),
+        scanned_files: files.len(),
+        light_frames: 0,
+        calibration_frames: 0,
+        unidentified_frames: 0,
+        objects: Vec::new(),
+        errors: Vec::new(),
+    };
+    let mut objects: BTreeMap<String, FitsObjectStats> = BTreeMap::new();
+
+    for header in headers {
+        let info = match header {
+            Ok(info) => info,
+            Err(e) => {
+                scan.errors.push(e);
+                continue;
+            }
+        };
+        if is_calibration(info.image_type.as_deref()) {
+            scan.calibration_frames += 1;
+            continue;
+        }
+        scan.light_frames += 1;
+        let Some(name) = info.object_name.clone() else {
+            scan.unidentified_frames += 1;
+            continue;
+        };
+
+        let exposure = info.exposure_time.unwrap_or(0.0);
+        let object = objects
+            .entry(name.to_lowercase())
+            .or_insert_with(|| FitsObjectStats {
+                object_name: name,
+                ra: None,
+                dec: None,
+                frame_count: 0,
+                total_exposure: 0.0,
+                filters: Vec::new(),
+                first_observed: None,
+                last_observed: None,
+            });
+        if object.ra.is_none() && object.dec.is_none() {
+            object.ra = info.ra;
+            object.dec = info.dec;
+        }
+        object.frame_count += 1;
+        object.total_exposure += exposure;
+
+        match object.filters.iter_mut().find(|f| f.filter == info.filter) {
+            Some(filter) => {
+                filter.frame_count += 1;
+                filter.total_exposure += exposure;
+            }
+            None => object.filters.push(FitsFilterStats {
+                filter: info.filter.clone(),
+                frame_count: 1,
+                total_exposure: exposure,
+            }),
+        }
+
+        if let Some(date) = info.date_obs {
+            if object.first_observed.as_ref().map_or(true, |d| &date < d) {
+                object.first_observed = Some(date.clone());
+            }
+            if object.last_observed.as_ref().map_or(true, |d| &date > d) {
+                object.last_observed = Some(date);
+            }
+        }
+    }
+
+    scan.objects = objects
+        .into_values()
+        .map(|mut object| {
+            object.filters.sort_by(|a, b| a.filter.cmp(&b.filter));
+            object
+        })
+        .collect();
+    Ok(scan)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn card(text: &str) -> Vec<u8> {
+        format!("{:<80}", text).into_bytes()
+    }
+
+    /// Header of the given cards, terminated and padded to whole blocks
+    fn header(cards: &[&str]) -> Vec<u8> {
+        let mut bytes: Vec<u8> = cards.iter().flat_map(|c| card(c)).collect();
+        bytes.extend(card("END"));
+        bytes.resize(
+            bytes.len().div_ceil(FITS_BLOCK_SIZE) * FITS_BLOCK_SIZE,
+            b' ',
+        );
+        bytes
+    }
+
+    #[test]
+    fn test_parse_card_strings_and_comments() {
+        let card = parse_card("OBJECT  = 'NGC 7000/North America' / target name");
+        assert_eq!(card.value, "NGC 7000/North America");
+        assert!(card.is_string);
+
+        assert_eq!(parse_card("NOTE    = 'It''s dark   '").value, "It's dark");
+        assert_eq!(
+            parse_card("EXPTIME =                300.0 / seconds").value,
+            "300.0"
+        );
+        assert_eq!(parse_card("COMMENT just text").value, "");
+    }
+
+    #[test]
+    fn test_header_spanning_blocks_with_continue() {
+        let mut cards: Vec<String> = (0..40).map(|i| format!("HISTORY step {}", i)).collect();
+        cards.push("OBJECT  = 'M31'".to_string());
+        cards.push("LONGSTRN= 'OGIP 1.0'".to_string());
+        cards.push("NOTES   = 'first part &'".to_string());
+        cards.push("CONTINUE  ' and second'".to_string());
+        cards.push("OBJCTRA = '00 42 44.3'".to_string());
+        cards.push("OBJCTDEC= '+41 16 09'".to_string());
+        let cards: Vec<&str> = cards.iter().map(String::as_str).collect();
+        let bytes = header(&cards);
+        assert!(bytes.len() > FITS_BLOCK_SIZE);
+
+        let hdus = read_hdus(&mut Cursor::new(&bytes)).unwrap();
+        let notes = hdus[0].iter().find(|c| c.key == "NOTES").unwrap();
+        assert_eq!(notes.value, "first part  and second");
+
+        let info = parse_fits_header(&bytes).unwrap();
+        assert_eq!(info.object_name.as_deref(), Some("M31"));
+        assert!((info.ra.unwrap() - 10.6846).abs() < 1e-3);
+        assert!((info.dec.unwrap() - 41.2692).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_multi_hdu_file() {
+        let mut bytes = header(&[
+            "SIMPLE  =                    T",
+            "BITPIX  =                   16",
+            "NAXIS   =                    2",
+            "NAXIS1  =                  100",
+            "NAXIS2  =                  100",
+            "EXTEND  =                    T",
+            "EXPTIME =                  120",
+        ]);
+        bytes.extend(vec![
+            0u8;
+            data_size(&read_hdus(&mut Cursor::new(&bytes)).unwrap()[0])
+                as usize
+        ]);
+        bytes.extend(header(&[
+            "XTENSION= 'IMAGE   '",
+            "BITPIX  =                    8",
+            "NAXIS   =                    0",
+            "OBJECT  = 'M42'",
+            "RA      =               83.822",
+            "DEC     =               -5.391",
+            "EXPTIME =                   30",
+        ]));
+
+        let hdus = read_hdus(&mut Cursor::new(&bytes)).unwrap();
+        assert_eq!(hdus.len(), 2);
+
+        let info = header_info(&hdus);
+        assert_eq!(info.object_name.as_deref(), Some("M42"));
+        assert_eq!(info.ra, Some(83.822));
+        // The primary HDU wins
+        assert_eq!(info.exposure_time, Some(120.0));
+    }
+
+    #[test]
+    fn test_scan_fits_directory() {
+        let dir = std::env::temp_dir().join(format!("cobalt-fits-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(dir.join("night2")).unwrap();
+        let frame = |object: &str, filter: &str, exposure: u32, date: &str, kind: &str| {
+            header(&[
+                &format!("OBJECT  = '{}'", object),
+                &format!("FILTER  = '{}'", filter),
+                &format!("EXPTIME = {:>20}", exposure),
+                &format!("DATE-OBS= '{}'", date),
+                &format!("IMAGETYP= '{}'", kind),
+            ])
+        };
+        std::fs::write(
+            dir.join("m31_1.fits"),
+            frame("M31", "Ha", 300, "2026-09-01T22:00:00", "Light Frame"),
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("night2/m31_2.FIT"),
+            frame("M31", "Ha", 300, "2026-09-02T22:00:00", "LIGHT"),
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("night2/m31_3.fts"),
+            frame("m31", "OIII", 600, "2026-08-30T21:00:00", "LIGHT"),
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("dark.fits"),
+            frame("", "", 300, "2026-09-01T23:00:00", "Dark Frame"),
+        )
+        .unwrap();
+        std::fs::write(dir.join("broken.fits"), b"SIMPLE").unwrap();
+        std::fs::write(dir.join("notes.txt"), b"not a frame").unwrap();
+
+        let scan = scan_fits_directory(&dir).unwrap();
+        assert_eq!(scan.scanned_files, 5);
+        assert_eq!(scan.light_frames, 3);
+        assert_eq!(scan.calibration_frames, 1);
+        assert_eq!(scan.errors.len(), 1);
+        assert_eq!(scan.objects.len(), 1);
+
+        let m31 = &scan.objects[0];
+        assert_eq!(m31.frame_count, 3);
+        assert_eq!(m31.total_exposure, 1200.0);
+        assert_eq!(m31.filters.len(), 2);
+        assert_eq!(m31.filters[0].filter.as_deref(), Some("Ha"));
+        assert_eq!(m31.filters[0].total_exposure, 600.0);
+        assert_eq!(m31.first_observed.as_deref(), Some("2026-08-30T21:00:00"));
+        assert_eq!(m31.last_observed.as_deref(), Some("2026-09-02T22:00:00"));
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,329 @@
+//! Flat frame exposure calculator
+//!
+//! Flats should reach a set mean level, usually half of full scale. The
+//! signal from a flat panel grows linearly with its brightness setting and
+//! the filter's transmission, so the exposure time of each filter follows
+//! from the panel's flux and the camera's system gain:
+//!
+//! `t = (target ADU - bias) × e⁻/ADU / (flux × brightness / max brightness × transmission)`
+//!
+//! When that time falls outside the allowed range the brightness can be
+//! changed per filter, so narrowband filters get a brighter panel rather
+//! than minute-long flats, and luminance a dimmer one rather than exposures
+//! short enough to show shutter gradients. Brightness settings are whole
+//! steps, as flat panels take them.
+
+use serde::{Deserialize, Serialize};
+
+use crate::models::{FilterInfo, ImageType, SimpleExposure, SimpleTarget};
+use crate::services::builtin_templates;
+
+/// Bit depth when none is given
+const DEFAULT_BIT_DEPTH: u32 = 16;
+
+/// A filter to take flats with
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FlatFilter {
+    pub filter: FilterInfo,
+    /// Throughput relative to a clear filter, 0-1
+    pub transmission: f64,
+}
+
+/// Flat panel, camera and flat settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct FlatPanelSettings {
+    /// Mean level the flats should reach in ADU, half of full scale when
+    /// not given
+    pub target_adu: Option<f64>,
+    /// Panel brightness setting
+    pub brightness: f64,
+    /// Highest brightness setting of the panel
+    pub max_brightness: f64,
+    /// Signal at full brightness through a clear filter, in electrons per
+    /// pixel per second
+    pub panel_flux: f64,
+    /// System gain at the flats' gain setting, in electrons per ADU
+    pub electrons_per_adu: f64,
+    /// Bias level in ADU
+    pub bias_adu: f64,
+    /// Bits per pixel of the saved frames, 16 when not given
+    pub bit_depth: Option<u32>,
+    pub gain: i32,
+    pub offset: i32,
+    /// Shortest flat exposure in seconds
+    pub min_exposure: f64,
+    /// Longest flat exposure in seconds
+    pub max_exposure: f64,
+    /// Change the brightness per filter to keep exposures within the limits
+    pub adjust_brightness: bool,
+    /// Flats per filter
+    pub count: i32,
+    /// Filters to take flats with; one unfiltered set when empty
+    pub filters: Vec<FlatFilter>,
+}
+
+impl Default for FlatPanelSettings {
+    fn default() -> Self {
+        Self {
+            target_adu: None,
+            brightness: 50.0,
+            max_brightness: 255.0,
+            panel_flux: 20_000.0,
+            electrons_per_adu: 1.0,
+            bias_adu: 500.0,
+            bit_depth: None,
+            gain: -1,
+            offset: -1,
+            min_exposure: 0.5,
+            max_exposure: 10.0,
+            adjust_brightness: true,
+            count: 25,
+            filters: Vec::new(),
+        }
+    }
+}
+
+impl FlatPanelSettings {
+    fn full_scale(&self) -> f64 {
+        let bits = self.bit_depth.unwrap_or(DEFAULT_BIT_DEPTH).min(32);
+        2f64.powi(bits as i32) - 1.0
+    }
+
+    fn target_level(&self) -> f64 {
+        self.target_adu.unwrap_or((self.full_scale() / 2.0).round())
+    }
+
+    /// Validate the settings
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+
+        let target = self.target_level();
+        if !(target > self.bias_adu && target < self.full_scale()) {
+            errors.push("Target ADU must be above the bias level and below saturation".to_string());
+        }
+        if self.bias_adu.is_nan() || self.bias_adu < 0.0 {
+            errors.push("Bias level cannot be negative".to_string());
+        }
+        if !(self.max_brightness >= 1.0 && self.brightness >= 1.0)
+            || self.brightness > self.max_brightness
+        {
+            errors.push("Brightness must be between 1 and the maximum setting".to_string());
+        }
+        if !(self.panel_flux > 0.0 && self.electrons_per_adu > 0.0) {
+            errors.push("Panel flux and camera gain must be above zero".to_string());
+        }
+        if !(self.min_exposure > 0.0 && self.min_exposure <= self.max_exposure) {
+            errors.push("Exposure limits must be above zero and in order".to_string());
+        }
+        if self.count < 1 {
+            errors.push("At least one flat per filter is required".to_string());
+        }
+        if self
+            .filters
+            .iter()
+            .any(|f| !(f.transmission > 0.0 && f.transmission <= 1.0))
+        {
+            errors.push("Filter transmission must be above 0 and at most 1".to_string());
+        }
+
+        errors
+    }
+}
+
+/// Flat exposure of one filter
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FlatExposure {
+    /// Filter name, empty for unfiltered flats
+    pub filter: String,
+    /// Exposure time in seconds
+    pub exposure_time: f64,
+    /// Panel brightness setting to use
+    pub brightness: f64,
+    /// Mean level the flats reach in ADU
+    pub expected_adu: f64,
+    /// Set when the exposure limits could not be kept
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub warning: Option<String>,
+}
+
+/// Flat exposures and a target taking them
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FlatPlan {
+    pub exposures: Vec<FlatExposure>,
+    /// Calibration target with `count` flats per filter, ready to add to a
+    /// sequence
+    pub target: SimpleTarget,
+}
+
+/// Exposure time and brightness of one filter
+fn flat_exposure(settings: &FlatPanelSettings, name: &str, transmission: f64) -> FlatExposure {
+    let electrons = (settings.target_level() - settings.bias_adu) * settings.electrons_per_adu;
+    // Electrons per second at a brightness setting
+    let rate =
+        |brightness: f64| settings.panel_flux * brightness / settings.max_brightness * transmission;
+
+    let mut brightness = settings.brightness;
+    let mut time = electrons / rate(brightness);
+    if settings.adjust_brightness && time < settings.min_exposure {
+        // Exposure times scale inversely with brightness
+        brightness = (brightness * time / settings.min_exposure).floor().max(1.0);
+        time = electrons / rate(brightness);
+    } else if settings.adjust_brightness && time > settings.max_exposure {
+        brightness = (brightness * time / settings.max_exposure)
+            .ceil()
+            .min(settings.max_brightness);
+        time = electrons / rate(brightness);
+    }
+    let exposure_time = ((time * 100.0).round() / 100.0).max(0.01);
+    let expected_adu =
+        settings.bias_adu + rate(brightness) * exposure_time / settings.electrons_per_adu;
+
+    let warning = if time < settings.min_exposure {
+        Some(format!(
+            "Needs {:.2} s at brightness {}, shorter than the {} s limit",
+            time, brightness, settings.min_exposure
+        ))
+    } else if time > settings.max_exposure {
+        Some(format!(
+            "Needs {:.1} s at brightness {}, longer than the {} s limit",
+            time, brightness, settings.max_exposure
+        ))
+    } else {
+        None
+    };
+
+    FlatExposure {
+        filter: name.to_string(),
+        exposure_time,
+        brightness,
+        expected_adu: expected_adu.round(),
+        warning,
+    }
+}
+
+/// Flat exposure times per filter and a calibration target taking them
+pub fn calculate_flat_exposures(settings: &FlatPanelSettings) -> Result<FlatPlan, String> {
+    let errors = settings.validate();
+    if !errors.is_empty() {
+        return Err(errors.join("; "));
+    }
+
+    let filters: Vec<(Option<&FilterInfo>, f64)> = if settings.filters.is_empty() {
+        vec![(None, 1.0)]
+    } else {
+        settings
+            .filters
+            .iter()
+            .map(|f| (Some(&f.filter), f.transmission))
+            .collect()
+    };
+
+    let mut exposures = Vec::new();
+    let mut frames = Vec::new();
+    for (filter, transmission) in filters {
+        let name = filter.map_or("", |f| f.name.as_str());
+        let exposure = flat_exposure(settings, name, transmission);
+        frames.push(SimpleExposure {
+            image_type: ImageType::Flat,
+            exposure_time: exposure.exposure_time,
+            filter: filter.cloned(),
+            gain: settings.gain,
+            offset: settings.offset,
+            total_count: settings.count,
+            ..Default::default()
+        });
+        exposures.push(exposure);
+    }
+
+    Ok(FlatPlan {
+        exposures,
+        target: builtin_templates::calibration_target("Flats", frames),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filter(name: &str, transmission: f64) -> FlatFilter {
+        FlatFilter {
+            filter: FilterInfo {
+                name: name.to_string(),
+                ..Default::default()
+            },
+            transmission,
+        }
+    }
+
+    #[test]
+    fn test_calculate_flat_exposures() {
+        let settings = FlatPanelSettings {
+            target_adu: Some(30_500.0),
+            brightness: 100.0,
+            max_brightness: 255.0,
+            panel_flux: 25_500.0,
+            electrons_per_adu: 0.5,
+            min_exposure: 1.0,
+            max_exposure: 10.0,
+            filters: vec![filter("R", 0.3), filter("L", 1.0), filter("Ha", 0.02)],
+            ..Default::default()
+        };
+        let plan = calculate_flat_exposures(&settings).unwrap();
+
+        // 15000 e⁻ at 10000 e⁻/s × 0.3
+        let red = &plan.exposures[0];
+        assert_eq!(red.exposure_time, 5.0);
+        assert_eq!(red.brightness, 100.0);
+        assert_eq!(red.expected_adu, 30_500.0);
+        assert!(red.warning.is_none());
+
+        // 1.5 s is within the limits at the given brightness
+        let luminance = &plan.exposures[1];
+        assert_eq!(luminance.brightness, 100.0);
+        assert_eq!(luminance.exposure_time, 1.5);
+
+        // 75 s at 100 is too long; even full brightness needs 29.4 s
+        let ha = &plan.exposures[2];
+        assert_eq!(ha.brightness, 255.0);
+        assert_eq!(ha.exposure_time, 29.41);
+        assert!(ha.warning.is_some());
+
+        let target = &plan.target;
+        assert!(!target.slew_to_target && !target.auto_focus_on_start);
+        assert_eq!(target.exposures.len(), 3);
+        assert_eq!(target.exposures[2].filter.as_ref().unwrap().name, "Ha");
+        assert_eq!(target.exposures[2].image_type, ImageType::Flat);
+        assert_eq!(target.exposures[2].total_count, 25);
+    }
+
+    #[test]
+    fn test_flat_brightness_lowered() {
+        let settings = FlatPanelSettings {
+            brightness: 200.0,
+            panel_flux: 100_000.0,
+            min_exposure: 1.0,
+            ..Default::default()
+        };
+        let plan = calculate_flat_exposures(&settings).unwrap();
+
+        // 32268 e⁻ in 0.41 s at 200; dimming to 82 gives 1.0 s or more
+        let flat = &plan.exposures[0];
+        assert_eq!(flat.filter, "");
+        assert_eq!(flat.brightness, 82.0);
+        assert!(flat.exposure_time >= 1.0);
+        assert!(flat.warning.is_none());
+        assert!(plan.target.exposures[0].filter.is_none());
+
+        let bad = FlatPanelSettings {
+            target_adu: Some(100.0),
+            filters: vec![filter("L", 0.0)],
+            ..Default::default()
+        };
+        assert_eq!(bad.validate().len(), 2);
+        assert!(calculate_flat_exposures(&bad).is_err());
+    }
+}
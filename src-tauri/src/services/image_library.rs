@@ -0,0 +1,391 @@
+//! Acquired integration from an image library
+//!
+//! Scans captured FITS and XISF frames, groups the light frames by
+//! object, filter and exposure time, and subtracts what is already
+//! captured from the exposure counts of a sequence.
+
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use crate::models::common::ImageType;
+use crate::models::{Coordinates, SimpleSequence, SimpleTarget};
+use crate::services::fits_header::{
+    find_image_files, is_calibration, FitsHeaderInfo, FITS_EXTENSIONS,
+};
+use crate::services::import_service::read_image_header_file;
+use crate::services::sequence_edit::separation_arcmin;
+use crate::services::xisf_header::XISF_EXTENSIONS;
+
+/// Default distance within which library frames match a sequence target
+/// whose name differs
+pub const DEFAULT_MATCH_TOLERANCE_ARCMIN: f64 = 10.0;
+
+/// Exposure times closer than this are considered equal (seconds)
+const EXPOSURE_TIME_TOLERANCE: f64 = 0.5;
+
+/// Light frames of one object with the same filter and exposure time
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AcquiredGroup {
+    pub filter: Option<String>,
+    /// Seconds
+    pub exposure_time: f64,
+    pub frame_count: usize,
+}
+
+/// Acquired light frames of one object
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AcquiredTarget {
+    pub object_name: String,
+    /// Degrees, from the first frame that has coordinates
+    pub ra: Option<f64>,
+    pub dec: Option<f64>,
+    pub frame_count: usize,
+    /// Seconds
+    pub total_integration: f64,
+    pub groups: Vec<AcquiredGroup>,
+}
+
+/// Result of [`analyze_image_library`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImageLibraryAnalysis {
+    pub directory: String,
+    pub scanned_files: usize,
+    pub light_frames: usize,
+    pub calibration_frames: usize,
+    /// Light frames without an object name or exposure time
+    pub unidentified_frames: usize,
+    pub targets: Vec<AcquiredTarget>,
+    pub errors: Vec<String>,
+}
+
+/// Exposure count reduced by [`subtract_acquired_from_sequence`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AcquiredAdjustment {
+    pub target_id: String,
+    pub target_name: String,
+    pub exposure_id: String,
+    pub filter: Option<String>,
+    pub exposure_time: f64,
+    /// Frames subtracted from this exposure
+    pub acquired: i32,
+    pub previous_count: i32,
+    pub new_count: i32,
+}
+
+/// Result of [`subtract_acquired_from_sequence`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SubtractAcquiredResult {
+    pub sequence: SimpleSequence,
+    pub adjustments: Vec<AcquiredAdjustment>,
+    /// Library objects that matched no target of the sequence
+    pub unmatched_objects: Vec<String>,
+}
+
+/// Fold frame headers into per-object statistics
+pub fn summarize_frames(
+    directory: &str,
+    headers: Vec<Result<FitsHeaderInfo, String>>,
+) -> ImageLibraryAnalysis {
+    let mut analysis = ImageLibraryAnalysis {
+        directory: directory.to_string(),
+        scanned_files: headers.len(),
+        light_frames: 0,
+        calibration_frames: 0,
+        unidentified_frames: 0,
+        targets: Vec::new(),
+        errors: Vec::new(),
+    };
+    let mut targets: BTreeMap<String, AcquiredTarget> = BTreeMap::new();
+
+    for header in headers {
+        let info = match header {
+            Ok(info) => info,
+            Err(e) => {
+                analysis.errors.push(e);
+                continue;
+            }
+        };
+        if is_calibration(info.image_type.as_deref()) {
+            analysis.calibration_frames += 1;
+            continue;
+        }
+        analysis.light_frames += 1;
+        let (Some(name), Some(exposure_time)) = (info.object_name, info.exposure_time) else {
+            analysis.unidentified_frames += 1;
+            continue;
+        };
+
+        let target = targets
+            .entry(normalize_name(&name))
+            .or_insert_with(|| AcquiredTarget {
+                object_name: name,
+                ra: None,
+                dec: None,
+                frame_count: 0,
+                total_integration: 0.0,
+                groups: Vec::new(),
+            });
+        if target.ra.is_none() && target.dec.is_none() {
+            target.ra = info.ra;
+            target.dec = info.dec;
+        }
+        target.frame_count += 1;
+        target.total_integration += exposure_time;
+
+        let group = target.groups.iter_mut().find(|g| {
+            same_filter(g.filter.as_deref(), info.filter.as_deref())
+                && (g.exposure_time - exposure_time).abs() < EXPOSURE_TIME_TOLERANCE
+        });
+        match group {
+            Some(group) => group.frame_count += 1,
+            None => target.groups.push(AcquiredGroup {
+                filter: info.filter,
+                exposure_time,
+                frame_count: 1,
+            }),
+        }
+    }
+
+    analysis.targets = targets
+        .into_values()
+        .map(|mut target| {
+            target.groups.sort_by(|a, b| {
+                a.filter
+                    .cmp(&b.filter)
+                    .then(a.exposure_time.total_cmp(&b.exposure_time))
+            });
+            target
+        })
+        .collect();
+    analysis
+}
+
+/// Scan the FITS and XISF files below `directory`
+pub fn analyze_image_library(directory: &Path) -> Result<ImageLibraryAnalysis, String> {
+    let extensions: Vec<&str> = FITS_EXTENSIONS
+        .iter()
+        .chain(XISF_EXTENSIONS)
+        .copied()
+        .collect();
+    let files = find_image_files(directory, &extensions)?;
+    let headers = files
+        .par_iter()
+        .map(|path| read_image_header_file(path))
+        .collect();
+    Ok(summarize_frames(&directory.display().to_string(), headers))
+}
+
+/// Object names compare without case and spaces, so "M 31" matches "m31"
+fn normalize_name(name: &str) -> String {
+    name.chars()
+        .filter(|c| !c.is_whitespace())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+fn same_filter(a: Option<&str>, b: Option<&str>) -> bool {
+    match (a, b) {
+        (Some(a), Some(b)) => a.trim().eq_ignore_ascii_case(b.trim()),
+        (None, None) => true,
+        _ => false,
+    }
+}
+
+fn matches_target(acquired: &AcquiredTarget, target: &SimpleTarget, tolerance_arcmin: f64) -> bool {
+    let name = normalize_name(&acquired.object_name);
+    if name == normalize_name(&target.target_name) || name == normalize_name(&target.name) {
+        return true;
+    }
+    match (acquired.ra, acquired.dec) {
+        (Some(ra), Some(dec)) if target.moving_target.is_none() => {
+            let position = SimpleTarget {
+                coordinates: Coordinates::from_decimal(ra / 15.0, dec),
+                ..SimpleTarget::default()
+            };
+            separation_arcmin(&position, target) <= tolerance_arcmin
+        }
+        _ => false,
+    }
+}
+
+/// Reduce the total count of light exposures by the frames already in the
+/// library. Targets match by name, or by position within
+/// `tolerance_arcmin`; exposures match by filter and exposure time.
+pub fn subtract_acquired_from_sequence(
+    sequence: &SimpleSequence,
+    analysis: &ImageLibraryAnalysis,
+    tolerance_arcmin: f64,
+) -> SubtractAcquiredResult {
+    let mut edited = sequence.clone();
+    let mut adjustments = Vec::new();
+    let mut unmatched_objects = Vec::new();
+
+    for acquired in &analysis.targets {
+        let Some(target) = edited
+            .targets
+            .iter_mut()
+            .find(|t| matches_target(acquired, t, tolerance_arcmin))
+        else {
+            unmatched_objects.push(acquired.object_name.clone());
+            continue;
+        };
+
+        for group in &acquired.groups {
+            let mut remaining = group.frame_count as i32;
+            for exposure in target.exposures.iter_mut().filter(|e| {
+                e.image_type == ImageType::Light
+                    && same_filter(
+                        e.filter.as_ref().map(|f| f.name.as_str()),
+                        group.filter.as_deref(),
+                    )
+                    && (e.exposure_time - group.exposure_time).abs() < EXPOSURE_TIME_TOLERANCE
+            }) {
+                if remaining == 0 {
+                    break;
+                }
+                let subtracted = remaining.min(exposure.total_count.max(0));
+                if subtracted == 0 {
+                    continue;
+                }
+                remaining -= subtracted;
+
+                let previous_count = exposure.total_count;
+                exposure.total_count -= subtracted;
+                exposure.progress_count = exposure.progress_count.min(exposure.total_count);
+                adjustments.push(AcquiredAdjustment {
+                    target_id: target.id.clone(),
+                    target_name: target.target_name.clone(),
+                    exposure_id: exposure.id.clone(),
+                    filter: exposure.filter.as_ref().map(|f| f.name.clone()),
+                    exposure_time: exposure.exposure_time,
+                    acquired: subtracted,
+                    previous_count,
+                    new_count: exposure.total_count,
+                });
+            }
+        }
+    }
+
+    if !adjustments.is_empty() {
+        edited.is_dirty = true;
+    }
+
+    SubtractAcquiredResult {
+        sequence: edited,
+        adjustments,
+        unmatched_objects,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::common::FilterInfo;
+    use crate::models::SimpleExposure;
+
+    fn frame(
+        object: &str,
+        filter: Option<&str>,
+        exposure_time: f64,
+    ) -> Result<FitsHeaderInfo, String> {
+        Ok(FitsHeaderInfo {
+            object_name: Some(object.to_string()),
+            filter: filter.map(str::to_string),
+            exposure_time: Some(exposure_time),
+            ra: Some(10.6847),
+            dec: Some(41.269),
+            ..Default::default()
+        })
+    }
+
+    fn exposure(filter: &str, exposure_time: f64, total_count: i32) -> SimpleExposure {
+        SimpleExposure {
+            filter: Some(FilterInfo {
+                name: filter.to_string(),
+                ..Default::default()
+            }),
+            exposure_time,
+            total_count,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_summarize_frames() {
+        let mut headers: Vec<_> = (0..3).map(|_| frame("M31", Some("Ha"), 300.0)).collect();
+        headers.push(frame("m 31", Some("ha"), 300.2));
+        headers.push(frame("M31", Some("OIII"), 600.0));
+        headers.push(Ok(FitsHeaderInfo {
+            image_type: Some("Dark Frame".to_string()),
+            ..Default::default()
+        }));
+        headers.push(Ok(FitsHeaderInfo::default()));
+        headers.push(Err("broken.fits: Truncated FITS header".to_string()));
+
+        let analysis = summarize_frames("/images", headers);
+        assert_eq!(analysis.scanned_files, 8);
+        assert_eq!(analysis.light_frames, 6);
+        assert_eq!(analysis.calibration_frames, 1);
+        assert_eq!(analysis.unidentified_frames, 1);
+        assert_eq!(analysis.errors.len(), 1);
+
+        assert_eq!(analysis.targets.len(), 1);
+        let m31 = &analysis.targets[0];
+        assert_eq!(m31.frame_count, 5);
+        assert!((m31.total_integration - 1800.2).abs() < 1e-9);
+        assert_eq!(m31.groups.len(), 2);
+        assert_eq!(m31.groups[0].filter.as_deref(), Some("Ha"));
+        assert_eq!(m31.groups[0].frame_count, 4);
+    }
+
+    #[test]
+    fn test_subtract_acquired_from_sequence() {
+        let mut sequence = SimpleSequence::new("Plan");
+        sequence.targets = vec![SimpleTarget {
+            target_name: "Andromeda".to_string(),
+            coordinates: Coordinates::from_decimal(10.6847 / 15.0, 41.269),
+            exposures: vec![
+                exposure("Ha", 300.0, 3),
+                exposure("Ha", 300.0, 10),
+                exposure("OIII", 300.0, 10),
+            ],
+            ..Default::default()
+        }];
+        let headers = (0..5)
+            .map(|_| frame("M31", Some("Ha"), 300.0))
+            .chain([frame("M42", Some("Ha"), 300.0)])
+            .collect();
+        let mut analysis = summarize_frames("/images", headers);
+        analysis.targets[1].ra = Some(83.82);
+        analysis.targets[1].dec = Some(-5.39);
+
+        let result = subtract_acquired_from_sequence(&sequence, &analysis, 10.0);
+        let counts: Vec<i32> = result.sequence.targets[0]
+            .exposures
+            .iter()
+            .map(|e| e.total_count)
+            .collect();
+        assert_eq!(counts, vec![0, 8, 10]);
+        assert_eq!(result.adjustments.len(), 2);
+        assert_eq!(result.unmatched_objects, vec!["M42".to_string()]);
+        assert!(result.sequence.is_dirty);
+
+        // Names match regardless of case and spaces, even far from the frames
+        sequence.targets[0].target_name = "m 31".to_string();
+        sequence.targets[0].coordinates = Coordinates::from_decimal(0.0, 0.0);
+        let result = subtract_acquired_from_sequence(&sequence, &analysis, 0.0);
+        assert_eq!(result.adjustments.len(), 2);
+
+        sequence.targets[0].target_name = "Andromeda".to_string();
+        let result = subtract_acquired_from_sequence(&sequence, &analysis, 10.0);
+        assert!(result.adjustments.is_empty());
+        assert!(!result.sequence.is_dirty);
+    }
+}
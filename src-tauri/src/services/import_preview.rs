@@ -0,0 +1,335 @@
+//! Import preview and selective commit
+//!
+//! Imports are parsed into a preview first: every row carries its
+//! validation status and any duplicates among the existing targets or
+//! earlier rows of the same file. The user deselects rows they don't want
+//! and [`commit_import`] returns the targets of the remaining rows.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::error::{AppError, AppResult};
+use crate::models::SimpleTarget;
+use crate::services::import_service::{parse_csv_rows, CsvColumnMapping, ImportResult};
+use crate::services::sequence_edit::separation_arcmin;
+use crate::state::AppState;
+
+/// Default radius within which two targets count as the same object
+pub const DEFAULT_DUPLICATE_RADIUS_ARCMIN: f64 = 1.0;
+
+/// Number of uncommitted previews kept before the oldest is dropped
+const MAX_PENDING_PREVIEWS: usize = 8;
+
+/// Validation status of a preview row
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ImportRowStatus {
+    Valid,
+    /// Parsed, but matches an existing target or an earlier row
+    Duplicate,
+    /// Could not be parsed into a target
+    Invalid,
+}
+
+/// Why a row was flagged as duplicate
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DuplicateReason {
+    /// Same target name, ignoring case
+    Name,
+    /// Within the duplicate radius of the other target
+    Position,
+}
+
+/// Target a preview row duplicates
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DuplicateMatch {
+    pub target_id: String,
+    pub target_name: String,
+    pub reason: DuplicateReason,
+    pub separation_arcmin: f64,
+    /// The match is an earlier row of the same import rather than an
+    /// existing target
+    pub within_import: bool,
+}
+
+/// Parsed row of an import preview
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportPreviewRow {
+    /// Index used in [`ImportSelection::row_indices`]
+    pub index: usize,
+    /// Line number in the source file, if the format is line based
+    pub row_number: Option<usize>,
+    /// Raw fields of the row, if the format is tabular
+    pub fields: Vec<String>,
+    pub target: Option<SimpleTarget>,
+    pub status: ImportRowStatus,
+    pub errors: Vec<String>,
+    pub duplicates: Vec<DuplicateMatch>,
+    /// Suggested selection: valid rows that are not duplicates
+    pub selected: bool,
+}
+
+/// Preview of an import, kept until it is committed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportPreview {
+    pub id: String,
+    pub source_format: String,
+    pub created_at: DateTime<Utc>,
+    pub rows: Vec<ImportPreviewRow>,
+    pub errors: Vec<String>,
+    pub warnings: Vec<String>,
+    pub valid_count: usize,
+    pub duplicate_count: usize,
+    pub invalid_count: usize,
+}
+
+/// Rows of a preview to turn into targets
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportSelection {
+    pub preview_id: String,
+    pub row_indices: Vec<usize>,
+}
+
+/// Result of [`commit_import`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportCommitResult {
+    pub targets: Vec<SimpleTarget>,
+    /// Selected rows that could not be imported
+    pub skipped: Vec<String>,
+}
+
+/// Row input for [`build_preview`]
+#[derive(Debug, Clone)]
+pub struct PreviewRowInput {
+    pub row_number: Option<usize>,
+    pub fields: Vec<String>,
+    pub target: Result<SimpleTarget, String>,
+}
+
+/// Find targets that `target` duplicates by name or position
+pub fn find_duplicates(
+    target: &SimpleTarget,
+    candidates: &[(&SimpleTarget, bool)],
+    radius_arcmin: f64,
+) -> Vec<DuplicateMatch> {
+    let name = target.target_name.trim().to_lowercase();
+
+    candidates
+        .iter()
+        .filter_map(|(candidate, within_import)| {
+            let separation = separation_arcmin(target, candidate);
+            let reason = if !name.is_empty() && candidate.target_name.trim().to_lowercase() == name
+            {
+                DuplicateReason::Name
+            } else if separation <= radius_arcmin {
+                DuplicateReason::Position
+            } else {
+                return None;
+            };
+            Some(DuplicateMatch {
+                target_id: candidate.id.clone(),
+                target_name: candidate.target_name.clone(),
+                reason,
+                separation_arcmin: separation,
+                within_import: *within_import,
+            })
+        })
+        .collect()
+}
+
+/// Build a preview from parsed rows, checking each valid row against the
+/// existing targets and the valid rows before it
+pub fn build_preview(
+    source_format: String,
+    rows: Vec<PreviewRowInput>,
+    errors: Vec<String>,
+    warnings: Vec<String>,
+    existing: &[SimpleTarget],
+    radius_arcmin: f64,
+) -> ImportPreview {
+    let mut preview_rows: Vec<ImportPreviewRow> = Vec::with_capacity(rows.len());
+
+    for (index, row) in rows.into_iter().enumerate() {
+        let preview_row = match row.target {
+            Ok(target) => {
+                let mut candidates: Vec<(&SimpleTarget, bool)> =
+                    existing.iter().map(|t| (t, false)).collect();
+                candidates.extend(
+                    preview_rows
+                        .iter()
+                        .filter_map(|r| r.target.as_ref())
+                        .map(|t| (t, true)),
+                );
+                let duplicates = find_duplicates(&target, &candidates, radius_arcmin);
+                let status = if duplicates.is_empty() {
+                    ImportRowStatus::Valid
+                } else {
+                    ImportRowStatus::Duplicate
+                };
+                ImportPreviewRow {
+                    index,
+                    row_number: row.row_number,
+                    fields: row.fields,
+                    target: Some(target),
+                    status,
+                    errors: vec![],
+                    duplicates,
+                    selected: status == ImportRowStatus::Valid,
+                }
+            }
+            Err(e) => ImportPreviewRow {
+                index,
+                row_number: row.row_number,
+                fields: row.fields,
+                target: None,
+                status: ImportRowStatus::Invalid,
+                errors: vec![e],
+                duplicates: vec![],
+                selected: false,
+            },
+        };
+        preview_rows.push(preview_row);
+    }
+
+    let count = |status| preview_rows.iter().filter(|r| r.status == status).count();
+    ImportPreview {
+        id: uuid::Uuid::new_v4().to_string(),
+        source_format,
+        created_at: Utc::now(),
+        valid_count: count(ImportRowStatus::Valid),
+        duplicate_count: count(ImportRowStatus::Duplicate),
+        invalid_count: count(ImportRowStatus::Invalid),
+        rows: preview_rows,
+        errors,
+        warnings,
+    }
+}
+
+/// Preview CSV content row by row
+pub fn preview_csv(
+    content: &str,
+    mapping: Option<CsvColumnMapping>,
+    existing: &[SimpleTarget],
+    radius_arcmin: f64,
+) -> ImportPreview {
+    let parsed = parse_csv_rows(content, &mapping.unwrap_or_default());
+    let errors = match parsed.error {
+        Some(e) => vec![e],
+        None if parsed.rows.is_empty() => vec!["No data rows in CSV content".to_string()],
+        None => vec![],
+    };
+    let rows = parsed
+        .rows
+        .into_iter()
+        .map(|row| PreviewRowInput {
+            row_number: Some(row.row_number),
+            fields: row.fields,
+            target: row.target,
+        })
+        .collect();
+
+    build_preview(
+        format!("{:?}", parsed.format),
+        rows,
+        errors,
+        vec![],
+        existing,
+        radius_arcmin,
+    )
+}
+
+/// Preview the targets of a finished import. Formats without row-level
+/// results report rows they skipped as warnings.
+pub fn preview_import_result(
+    result: ImportResult,
+    existing: &[SimpleTarget],
+    radius_arcmin: f64,
+) -> ImportPreview {
+    let rows = result
+        .targets
+        .into_iter()
+        .map(|target| PreviewRowInput {
+            row_number: None,
+            fields: vec![],
+            target: Ok(target),
+        })
+        .collect();
+
+    build_preview(
+        result.source_format,
+        rows,
+        result.errors,
+        result.warnings,
+        existing,
+        radius_arcmin,
+    )
+}
+
+/// Keep a preview until it is committed
+pub fn store_preview(state: &AppState, preview: &ImportPreview) {
+    let mut pending = state.import_previews.write();
+    pending.push(preview.clone());
+    if pending.len() > MAX_PENDING_PREVIEWS {
+        let excess = pending.len() - MAX_PENDING_PREVIEWS;
+        pending.drain(..excess);
+    }
+}
+
+/// Discard a pending preview
+pub fn discard_preview(state: &AppState, preview_id: &str) {
+    state.import_previews.write().retain(|p| p.id != preview_id);
+}
+
+/// Targets of the selected rows of a preview, in row order
+pub fn select_rows(preview: &ImportPreview, row_indices: &[usize]) -> ImportCommitResult {
+    let mut indices = row_indices.to_vec();
+    indices.sort_unstable();
+    indices.dedup();
+
+    let mut targets = Vec::new();
+    let mut skipped = Vec::new();
+    for index in indices {
+        match preview.rows.get(index) {
+            Some(ImportPreviewRow {
+                target: Some(target),
+                ..
+            }) => targets.push(target.clone()),
+            Some(row) => skipped.push(format!(
+                "Row {}: {}",
+                row.row_number.unwrap_or(index + 1),
+                row.errors.join("; ")
+            )),
+            None => skipped.push(format!("Row index {} is not in the preview", index)),
+        }
+    }
+
+    ImportCommitResult { targets, skipped }
+}
+
+/// Create the targets of the selected rows of a pending preview. The
+/// preview is consumed.
+pub fn commit_import(
+    state: &AppState,
+    selection: &ImportSelection,
+) -> AppResult<ImportCommitResult> {
+    let preview = {
+        let mut pending = state.import_previews.write();
+        let position = pending
+            .iter()
+            .position(|p| p.id == selection.preview_id)
+            .ok_or_else(|| {
+                AppError::NotFound(format!(
+                    "Import preview not found: {}",
+                    selection.preview_id
+                ))
+            })?;
+        pending.remove(position)
+    };
+    Ok(select_rows(&preview, &selection.row_indices))
+}
@@ -3,15 +3,33 @@
 //! Supports importing targets from:
 //! - CSV (Telescopius, custom formats)
 //! - Stellarium skylist
-//! - APT format
+//! - APT and NINA legacy XML
 //! - Voyager format
-//! - FITS headers
+//! - FITS and XISF headers
+//! - Plate solve results (ASTAP, astrometry.net)
+//!
+//! Sequence Generator Pro files are handled by `sgp_import`.
 
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-
-use crate::models::common::{BinningMode, ImageType, SequenceEntityStatus, SequenceMode};
-use crate::models::{Coordinates, SimpleExposure, SimpleTarget};
+use std::path::Path;
+
+use crate::models::common::{
+    BinningMode, FilterInfo, ImageType, SequenceEntityStatus, SequenceMode, TargetPriority,
+};
+use crate::models::{
+    metadata_value_from_text, Coordinates, ObservingConstraints, SimpleExposure, SimpleSequence,
+    SimpleTarget,
+};
+use crate::services::csv_io;
+use crate::services::fits_header::read_fits_header_file;
+pub use crate::services::fits_header::{parse_fits_header, FitsHeaderInfo};
+use crate::services::sgp_import::import_sgp_sequence;
+use crate::services::voyager_robotarget::{is_robotarget_json, parse_robotarget_json};
+pub use crate::services::xisf_header::parse_xisf_header;
+use crate::services::xisf_header::{read_xisf_header_file, XISF_EXTENSIONS};
+use crate::services::xml_tree::XmlElement;
 
 /// Import result
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -36,6 +54,7 @@ pub struct CsvColumnMapping {
     pub dec_column: Option<String>,
     pub position_angle_column: Option<String>,
     pub notes_column: Option<String>,
+    /// Field delimiter; detected from the content when unset
     pub delimiter: Option<char>,
     pub has_header: bool,
 }
@@ -48,7 +67,7 @@ impl Default for CsvColumnMapping {
             dec_column: Some("dec".to_string()),
             position_angle_column: None,
             notes_column: None,
-            delimiter: Some(','),
+            delimiter: None,
             has_header: true,
         }
     }
@@ -103,13 +122,114 @@ pub fn detect_csv_format(headers: &[String]) -> DetectedCsvFormat {
     DetectedCsvFormat::Unknown
 }
 
+/// Row of a CSV file with its parse outcome
+#[derive(Debug, Clone)]
+pub struct CsvRow {
+    /// 1-based line number in the file
+    pub row_number: usize,
+    pub fields: Vec<String>,
+    pub target: Result<SimpleTarget, String>,
+}
+
+/// CSV content parsed row by row
+#[derive(Debug, Clone)]
+pub struct CsvRows {
+    pub format: DetectedCsvFormat,
+    pub total_rows: usize,
+    pub rows: Vec<CsvRow>,
+    /// Set when the content could not be read as CSV
+    pub error: Option<String>,
+}
+
+/// Parse every data row of CSV content, keeping rows that fail to parse
+pub fn parse_csv_rows(content: &str, mapping: &CsvColumnMapping) -> CsvRows {
+    let (records, error) = match csv_io::read_records(content, mapping.delimiter) {
+        Ok(records) => (records, None),
+        Err(e) => (vec![], Some(e)),
+    };
+
+    // Parse headers (original case is kept for metadata keys)
+    let raw_headers: Vec<String> = match records.first() {
+        Some(record) if mapping.has_header => record.fields.clone(),
+        _ => vec![],
+    };
+    let headers: Vec<String> = raw_headers.iter().map(|s| s.to_lowercase()).collect();
+
+    let format = if mapping.has_header {
+        detect_csv_format(&headers)
+    } else {
+        DetectedCsvFormat::Generic
+    };
+
+    let start_row = if mapping.has_header { 1 } else { 0 };
+    let total_rows = records.len().saturating_sub(start_row);
+
+    let rows = records
+        .into_iter()
+        .skip(start_row)
+        .map(|record| {
+            let target = parse_csv_row(&headers, &raw_headers, &record.fields, &format, mapping);
+            CsvRow {
+                row_number: record.line,
+                fields: record.fields,
+                target,
+            }
+        })
+        .collect();
+
+    CsvRows {
+        format,
+        total_rows,
+        rows,
+        error,
+    }
+}
+
+/// Import targets from `content`, picking the parser by file extension and
+/// falling back to sniffing the content
+pub fn detect_and_import(content: &str, extension: &str) -> Result<ImportResult, String> {
+    match extension.to_lowercase().as_str() {
+        "csv" => Ok(parse_csv_content(content, None)),
+        "skylist" | "sl" => Ok(parse_stellarium_skylist(content)),
+        "xml" => Ok(parse_xml_content(content)),
+        "json" if is_robotarget_json(content) => Ok(parse_robotarget_json(content)),
+        "sgf" => {
+            let result = import_sgp_sequence(content)?;
+            let targets = result.sequence.targets;
+            Ok(ImportResult {
+                success: true,
+                imported_count: targets.len(),
+                total_rows: targets.len(),
+                targets,
+                errors: vec![],
+                warnings: result.warnings,
+                source_format: "SGP".to_string(),
+                skipped_count: 0,
+            })
+        }
+        _ => {
+            // Try to detect by content
+            if is_robotarget_json(content) {
+                Ok(parse_robotarget_json(content))
+            } else if content.trim().starts_with("<?xml") || content.trim().starts_with("<") {
+                Ok(parse_xml_content(content))
+            } else if content.contains("[") && content.contains("RA=") {
+                Ok(parse_voyager_format(content))
+            } else if content.starts_with("#") || content.contains("designation") {
+                Ok(parse_stellarium_skylist(content))
+            } else {
+                // Default to CSV
+                Ok(parse_csv_content(content, None))
+            }
+        }
+    }
+}
+
 /// Parse CSV content with auto-detection
 pub fn parse_csv_content(content: &str, mapping: Option<CsvColumnMapping>) -> ImportResult {
     let mapping = mapping.unwrap_or_default();
-    let delimiter = mapping.delimiter.unwrap_or(',');
 
-    let lines: Vec<&str> = content.lines().collect();
-    if lines.is_empty() {
+    if csv_io::strip_bom(content).trim().is_empty() {
         return ImportResult {
             success: false,
             targets: vec![],
@@ -122,41 +242,17 @@ pub fn parse_csv_content(content: &str, mapping: Option<CsvColumnMapping>) -> Im
         };
     }
 
+    let parsed = parse_csv_rows(content, &mapping);
     let mut targets = Vec::new();
-    let errors: Vec<String> = Vec::new();
+    let errors: Vec<String> = parsed.error.into_iter().collect();
     let mut warnings = Vec::new();
     let mut skipped = 0;
 
-    // Parse headers
-    let headers: Vec<String> = if mapping.has_header {
-        parse_csv_line(lines[0], delimiter)
-            .iter()
-            .map(|s| s.trim().to_lowercase())
-            .collect()
-    } else {
-        vec![]
-    };
-
-    let format = if mapping.has_header {
-        detect_csv_format(&headers)
-    } else {
-        DetectedCsvFormat::Generic
-    };
-
-    let start_row = if mapping.has_header { 1 } else { 0 };
-    let total_rows = lines.len() - start_row;
-
-    for (idx, line) in lines.iter().enumerate().skip(start_row) {
-        if line.trim().is_empty() {
-            continue;
-        }
-
-        let fields = parse_csv_line(line, delimiter);
-
-        match parse_csv_row(&headers, &fields, &format, &mapping) {
+    for row in parsed.rows {
+        match row.target {
             Ok(target) => targets.push(target),
             Err(e) => {
-                warnings.push(format!("Row {}: {}", idx + 1, e));
+                warnings.push(format!("Row {}: {}", row.row_number, e));
                 skipped += 1;
             }
         }
@@ -167,49 +263,17 @@ pub fn parse_csv_content(content: &str, mapping: Option<CsvColumnMapping>) -> Im
         targets,
         errors,
         warnings,
-        source_format: format!("{:?}", format),
-        total_rows,
-        imported_count: total_rows - skipped,
+        source_format: format!("{:?}", parsed.format),
+        total_rows: parsed.total_rows,
+        imported_count: parsed.total_rows - skipped,
         skipped_count: skipped,
     }
 }
 
-/// Parse a single CSV line
-fn parse_csv_line(line: &str, delimiter: char) -> Vec<String> {
-    let mut fields = Vec::new();
-    let mut current = String::new();
-    let mut in_quotes = false;
-    let mut chars = line.chars().peekable();
-
-    while let Some(c) = chars.next() {
-        match c {
-            '"' => {
-                if in_quotes {
-                    if chars.peek() == Some(&'"') {
-                        current.push('"');
-                        chars.next();
-                    } else {
-                        in_quotes = false;
-                    }
-                } else {
-                    in_quotes = true;
-                }
-            }
-            c if c == delimiter && !in_quotes => {
-                fields.push(current.trim().to_string());
-                current = String::new();
-            }
-            _ => current.push(c),
-        }
-    }
-    fields.push(current.trim().to_string());
-
-    fields
-}
-
 /// Parse a CSV row into a target
 fn parse_csv_row(
     headers: &[String],
+    raw_headers: &[String],
     fields: &[String],
     format: &DetectedCsvFormat,
     mapping: &CsvColumnMapping,
@@ -277,6 +341,45 @@ fn parse_csv_row(
         .and_then(|s| s.parse::<f64>().ok())
         .unwrap_or(0.0);
 
+    let priority = get_field("priority")
+        .and_then(|s| TargetPriority::parse(&s))
+        .unwrap_or_default();
+
+    // Annotations match column names exactly so `Meta:` columns never
+    // shadow them
+    let get_exact = |name: &str| -> Option<String> {
+        headers
+            .iter()
+            .position(|h| h == name)
+            .and_then(|i| fields.get(i).cloned())
+            .filter(|s| !s.is_empty())
+    };
+    let notes = mapping
+        .notes_column
+        .as_ref()
+        .and_then(|col| get_exact(&col.to_lowercase()))
+        .or_else(|| get_exact("notes"));
+    let tags = get_exact("tags")
+        .map(|s| parse_tags(&s))
+        .unwrap_or_default();
+    let metadata = raw_headers
+        .iter()
+        .zip(fields)
+        .filter(|(_, value)| !value.is_empty())
+        .filter_map(|(header, value)| {
+            let header = header.trim();
+            if header
+                .get(..5)
+                .is_some_and(|prefix| prefix.eq_ignore_ascii_case("meta:"))
+                && header.len() > 5
+            {
+                Some((header[5..].to_string(), metadata_value_from_text(value)))
+            } else {
+                None
+            }
+        })
+        .collect();
+
     Ok(SimpleTarget {
         id: uuid::Uuid::new_v4().to_string(),
         name: name.clone(),
@@ -292,6 +395,10 @@ fn parse_csv_row(
         center_target: true,
         rotate_target: false,
         start_guiding: true,
+        priority,
+        min_integration: None,
+        max_integration: None,
+        constraints: Default::default(),
         auto_focus_on_start: true,
         auto_focus_on_filter_change: false,
         auto_focus_after_set_time: false,
@@ -306,9 +413,21 @@ fn parse_csv_row(
         estimated_start_time: None,
         estimated_end_time: None,
         estimated_duration: None,
+        moving_target: None,
+        notes,
+        tags,
+        metadata,
     })
 }
 
+/// Split a tag list written as "a;b" or "a, b"
+fn parse_tags(s: &str) -> Vec<String> {
+    s.split([';', ','])
+        .map(|t| t.trim().to_string())
+        .filter(|t| !t.is_empty())
+        .collect()
+}
+
 /// Parse coordinate strings
 fn parse_coordinates(ra_str: &str, dec_str: &str) -> Result<Coordinates, String> {
     let ra = parse_ra(ra_str)?;
@@ -322,6 +441,7 @@ fn parse_coordinates(ra_str: &str, dec_str: &str) -> Result<Coordinates, String>
         dec_minutes: dec.1,
         dec_seconds: dec.2,
         negative_dec: dec.3,
+        epoch: Default::default(),
     })
 }
 
@@ -611,74 +731,389 @@ fn create_target_from_map(data: &HashMap<String, String>) -> Result<SimpleTarget
 // XML Import
 // ============================================================================
 
-/// Parse XML targets (generic)
-pub fn parse_xml_targets(content: &str, format_name: &str) -> ImportResult {
-    let mut targets = Vec::new();
-    let errors: Vec<String> = Vec::new();
-    let mut warnings = Vec::new();
+/// Elements holding one target in generic and APT XML
+const XML_TARGET_ELEMENTS: &[&str] = &["Target", "Object", "DSO"];
 
-    // Simple XML parsing without external dependencies
-    let target_regex =
-        regex_lite::Regex::new(r"<(?:Target|Object|DSO)[^>]*>([\s\S]*?)</(?:Target|Object|DSO)>")
-            .unwrap();
-
-    let name_regex = regex_lite::Regex::new(r"<(?:Name|TargetName)>([^<]+)</").unwrap();
-    let ra_regex = regex_lite::Regex::new(r"<(?:RA|RightAscension)>([^<]+)</").unwrap();
-    let dec_regex = regex_lite::Regex::new(r"<(?:Dec|Declination)>([^<]+)</").unwrap();
-    let pa_regex = regex_lite::Regex::new(r"<(?:PA|PositionAngle)>([^<]+)</").unwrap();
-
-    for cap in target_regex.captures_iter(content) {
-        let target_xml = &cap[1];
-
-        let name = name_regex
-            .captures(target_xml)
-            .map(|c| c[1].to_string())
-            .unwrap_or_else(|| "Unknown".to_string());
-
-        let ra_str = match ra_regex.captures(target_xml) {
-            Some(c) => c[1].to_string(),
-            None => {
-                warnings.push(format!("Target '{}': Missing RA", name));
-                continue;
-            }
+/// Target element of the NINA legacy target set, as written by
+/// `serializer::export_to_xml`
+const NINA_TARGET_ELEMENT: &str = "CaptureSequenceList";
+
+fn xml_number<T: std::str::FromStr>(element: &XmlElement, names: &[&str]) -> Option<T> {
+    element.value(names).and_then(|v| v.parse().ok())
+}
+
+fn xml_bool(element: &XmlElement, names: &[&str]) -> Option<bool> {
+    element
+        .value(names)
+        .map(|v| v.eq_ignore_ascii_case("true") || v == "1")
+}
+
+fn parse_image_type(s: &str) -> Option<ImageType> {
+    match s.trim().to_uppercase().as_str() {
+        "LIGHT" => Some(ImageType::Light),
+        "DARK" => Some(ImageType::Dark),
+        "BIAS" => Some(ImageType::Bias),
+        "FLAT" => Some(ImageType::Flat),
+        "SNAPSHOT" => Some(ImageType::Snapshot),
+        _ => None,
+    }
+}
+
+/// Exposure of the generic XML export: binning is written as `2x2`
+fn parse_xml_exposure(element: &XmlElement) -> SimpleExposure {
+    let mut exposure = create_default_exposure();
+    if let Some(time) = xml_number(element, &["ExposureTime"]) {
+        exposure.exposure_time = time;
+    }
+    if let Some(image_type) = element.value(&["ImageType"]).and_then(parse_image_type) {
+        exposure.image_type = image_type;
+    }
+    exposure.filter = element.value(&["Filter"]).map(|name| FilterInfo {
+        name: name.to_string(),
+        ..Default::default()
+    });
+    if let Some((x, y)) = element
+        .value(&["Binning"])
+        .and_then(|b| b.split_once(['x', 'X']))
+    {
+        if let (Ok(x), Ok(y)) = (x.trim().parse(), y.trim().parse()) {
+            exposure.binning = BinningMode { x, y };
+        }
+    }
+    if let Some(gain) = xml_number(element, &["Gain"]) {
+        exposure.gain = gain;
+    }
+    if let Some(offset) = xml_number(element, &["Offset"]) {
+        exposure.offset = offset;
+    }
+    if let Some(count) = xml_number(element, &["Count"]) {
+        exposure.total_count = count;
+    }
+    if let Some(progress) = xml_number(element, &["Progress"]) {
+        exposure.progress_count = progress;
+    }
+    exposure
+}
+
+/// Observing constraints written by `export_service::export_to_xml`
+fn parse_xml_constraints(element: &XmlElement) -> ObservingConstraints {
+    let time = |name: &str| {
+        element
+            .value(&[name])
+            .and_then(|v| DateTime::parse_from_rfc3339(v).ok())
+            .map(|t| t.with_timezone(&Utc))
+    };
+    ObservingConstraints {
+        min_altitude: xml_number(element, &["MinAltitude"]),
+        max_altitude: xml_number(element, &["MaxAltitude"]),
+        max_airmass: xml_number(element, &["MaxAirmass"]),
+        min_hour_angle: xml_number(element, &["MinHourAngle"]),
+        max_hour_angle: xml_number(element, &["MaxHourAngle"]),
+        min_moon_separation: xml_number(element, &["MinMoonSeparation"]),
+        max_moon_illumination: xml_number(element, &["MaxMoonIllumination"]),
+        earliest_start: time("EarliestStart"),
+        latest_start: time("LatestStart"),
+    }
+}
+
+/// Target of generic or APT XML, with values as child elements or
+/// attributes
+fn parse_xml_target(element: &XmlElement) -> Result<SimpleTarget, String> {
+    let coordinates = element.child(&["Coordinates"]).unwrap_or(element);
+    let ra_str = element
+        .value(&["RA", "RightAscension"])
+        .or_else(|| coordinates.value(&["RA", "RightAscension"]))
+        .ok_or("Missing RA")?;
+    let dec_str = element
+        .value(&["Dec", "Declination"])
+        .or_else(|| coordinates.value(&["Dec", "Declination"]))
+        .ok_or("Missing Dec")?;
+    let coords = parse_coordinates(ra_str, dec_str)?;
+
+    let name = element
+        .value(&["Name", "TargetName"])
+        .unwrap_or("Unknown")
+        .to_string();
+    let position_angle = xml_number(element, &["PA", "PositionAngle"]).unwrap_or(0.0);
+
+    let mut target = create_target_from_coords(name, coords, position_angle);
+    target.notes = element
+        .child(&["Notes"])
+        .map(|notes| notes.text().to_string());
+    target.tags = element
+        .find_all(&["Tag"])
+        .into_iter()
+        .map(|tag| tag.text().to_string())
+        .filter(|t| !t.is_empty())
+        .collect();
+    target.metadata = element
+        .find_all(&["Entry"])
+        .into_iter()
+        .filter_map(|entry| {
+            entry
+                .attribute("key")
+                .map(|key| (key.to_string(), metadata_value_from_text(entry.text())))
+        })
+        .collect();
+
+    if let Some(constraints) = element.child(&["Constraints"]) {
+        target.constraints = parse_xml_constraints(constraints);
+    }
+
+    if let Some(slew) = xml_bool(element, &["SlewToTarget"]) {
+        target.slew_to_target = slew;
+    }
+    if let Some(center) = xml_bool(element, &["CenterTarget"]) {
+        target.center_target = center;
+    }
+    if let Some(guide) = xml_bool(element, &["StartGuiding"]) {
+        target.start_guiding = guide;
+    }
+
+    let exposures: Vec<SimpleExposure> = element
+        .child(&["Exposures"])
+        .map(|list| {
+            list.children_named(&["Exposure"])
+                .map(parse_xml_exposure)
+                .collect()
+        })
+        .unwrap_or_default();
+    if !exposures.is_empty() {
+        target.exposures = exposures;
+    }
+
+    Ok(target)
+}
+
+/// Exposure of the NINA legacy target set
+fn parse_nina_capture_sequence(element: &XmlElement) -> SimpleExposure {
+    let mut exposure = create_default_exposure();
+    if let Some(enabled) = xml_bool(element, &["Enabled"]) {
+        exposure.enabled = enabled;
+    }
+    if let Some(time) = xml_number(element, &["ExposureTime"]) {
+        exposure.exposure_time = time;
+    }
+    if let Some(image_type) = element.value(&["ImageType"]).and_then(parse_image_type) {
+        exposure.image_type = image_type;
+    }
+    exposure.filter = element
+        .child(&["FilterType"])
+        .filter(|filter| filter.attribute("nil") != Some("true"))
+        .and_then(|filter| {
+            // NINA itself writes the filter's private fields
+            filter.value(&["Name", "_name"]).map(|name| FilterInfo {
+                name: name.to_string(),
+                position: xml_number(filter, &["Position", "_position"]).unwrap_or(0),
+                focus_offset: xml_number(filter, &["FocusOffset", "_focusOffset"]),
+                ..Default::default()
+            })
+        });
+    if let Some(binning) = element.child(&["Binning"]) {
+        exposure.binning = BinningMode {
+            x: xml_number(binning, &["X"]).unwrap_or(1),
+            y: xml_number(binning, &["Y"]).unwrap_or(1),
         };
+    }
+    if let Some(gain) = xml_number(element, &["Gain"]) {
+        exposure.gain = gain;
+    }
+    if let Some(offset) = xml_number(element, &["Offset"]) {
+        exposure.offset = offset;
+    }
+    if let Some(count) = xml_number(element, &["TotalExposureCount"]) {
+        exposure.total_count = count;
+    }
+    if let Some(progress) = xml_number(element, &["ProgressExposureCount"]) {
+        exposure.progress_count = progress;
+    }
+    if let Some(dither) = xml_bool(element, &["Dither"]) {
+        exposure.dither = dither;
+    }
+    if let Some(every) = xml_number(element, &["DitherAmount"]) {
+        exposure.dither_every = every;
+    }
+    exposure
+}
 
-        let dec_str = match dec_regex.captures(target_xml) {
-            Some(c) => c[1].to_string(),
-            None => {
-                warnings.push(format!("Target '{}': Missing Dec", name));
-                continue;
-            }
+/// Target of the NINA legacy target set. RA is stored in degrees.
+fn parse_nina_capture_list(element: &XmlElement) -> Result<SimpleTarget, String> {
+    let coordinates = element
+        .child(&["Coordinates"])
+        .ok_or("Missing coordinates")?;
+    let ra_degrees: f64 = xml_number(coordinates, &["RA"]).ok_or("Missing RA")?;
+    let dec: f64 = xml_number(coordinates, &["Dec"]).ok_or("Missing Dec")?;
+    if !(0.0..360.0).contains(&ra_degrees) {
+        return Err(format!("RA out of range: {}", ra_degrees));
+    }
+    if !(-90.0..=90.0).contains(&dec) {
+        return Err(format!("Dec out of range: {}", dec));
+    }
+
+    let name = element
+        .value(&["TargetName"])
+        .unwrap_or("Unknown")
+        .to_string();
+    let position_angle = xml_number(element, &["PositionAngle"]).unwrap_or(0.0);
+    let mut target = create_target_from_coords(
+        name,
+        Coordinates::from_decimal(ra_degrees / 15.0, dec),
+        position_angle,
+    );
+
+    if let Some(mode) = element.value(&["Mode"]) {
+        target.mode = if mode.eq_ignore_ascii_case("rotate") {
+            SequenceMode::Rotate
+        } else {
+            SequenceMode::Standard
         };
+    }
+    if let Some(delay) = xml_number(element, &["Delay"]) {
+        target.delay = delay;
+    }
+    if let Some(slew) = xml_bool(element, &["SlewToTarget"]) {
+        target.slew_to_target = slew;
+    }
+    if let Some(center) = xml_bool(element, &["CenterTarget"]) {
+        target.center_target = center;
+    }
+    if let Some(rotate) = xml_bool(element, &["RotateTarget"]) {
+        target.rotate_target = rotate;
+    }
+    if let Some(guide) = xml_bool(element, &["StartGuiding"]) {
+        target.start_guiding = guide;
+    }
+    if let Some(af) = xml_bool(element, &["AutoFocusOnStart"]) {
+        target.auto_focus_on_start = af;
+    }
+    if let Some(af) = xml_bool(element, &["AutoFocusOnFilterChange"]) {
+        target.auto_focus_on_filter_change = af;
+    }
+    if let Some(af) = xml_bool(element, &["AutoFocusAfterSetTime"]) {
+        target.auto_focus_after_set_time = af;
+    }
+    if let Some(minutes) = xml_number(element, &["AutoFocusSetTime"]) {
+        target.auto_focus_set_time = minutes;
+    }
+    if let Some(af) = xml_bool(element, &["AutoFocusAfterSetExposures"]) {
+        target.auto_focus_after_set_exposures = af;
+    }
+    if let Some(exposures) = xml_number(element, &["AutoFocusSetExposures"]) {
+        target.auto_focus_set_exposures = exposures;
+    }
+    if let Some(af) = xml_bool(element, &["AutoFocusAfterTemperatureChange"]) {
+        target.auto_focus_after_temperature_change = af;
+    }
+    if let Some(amount) = xml_number(element, &["AutoFocusAfterTemperatureChangeAmount"]) {
+        target.auto_focus_after_temperature_change_amount = amount;
+    }
+    if let Some(af) = xml_bool(element, &["AutoFocusAfterHFRChange"]) {
+        target.auto_focus_after_hfr_change = af;
+    }
+    if let Some(amount) = xml_number(element, &["AutoFocusAfterHFRChangeAmount"]) {
+        target.auto_focus_after_hfr_change_amount = amount;
+    }
 
-        let position_angle = pa_regex
-            .captures(target_xml)
-            .and_then(|c| c[1].parse::<f64>().ok())
-            .unwrap_or(0.0);
+    target.exposures = element
+        .find_all(&["CaptureSequence"])
+        .into_iter()
+        .map(parse_nina_capture_sequence)
+        .collect();
+
+    Ok(target)
+}
 
-        match parse_coordinates(&ra_str, &dec_str) {
-            Ok(coords) => {
-                targets.push(create_target_from_coords(name, coords, position_angle));
+/// Parse XML targets: the NINA legacy target set, or `Target`, `Object`
+/// and `DSO` elements anywhere in the document
+pub fn parse_xml_targets(content: &str, format_name: &str) -> ImportResult {
+    let document = match XmlElement::parse(content) {
+        Ok(document) => document,
+        Err(e) => {
+            return ImportResult {
+                success: false,
+                targets: vec![],
+                errors: vec![e],
+                warnings: vec![],
+                source_format: format_name.to_string(),
+                total_rows: 0,
+                imported_count: 0,
+                skipped_count: 0,
             }
+        }
+    };
+
+    let mut elements = document.find_all(&[NINA_TARGET_ELEMENT]);
+    if elements.is_empty() {
+        elements = document.find_all(XML_TARGET_ELEMENTS);
+    }
+
+    let mut targets = Vec::new();
+    let mut warnings = Vec::new();
+    for element in &elements {
+        let result = if element.name.eq_ignore_ascii_case(NINA_TARGET_ELEMENT) {
+            parse_nina_capture_list(element)
+        } else {
+            parse_xml_target(element)
+        };
+        match result {
+            Ok(target) => targets.push(target),
             Err(e) => {
+                let name = element.value(&["Name", "TargetName"]).unwrap_or("Unknown");
                 warnings.push(format!("Target '{}': {}", name, e));
             }
         }
     }
 
+    let total_rows = elements.len();
     let imported_count = targets.len();
-    let total_rows = target_regex.captures_iter(content).count();
 
     ImportResult {
-        success: errors.is_empty(),
+        success: true,
         targets,
-        errors,
+        errors: vec![],
         warnings,
         source_format: format_name.to_string(),
         total_rows,
         imported_count,
-        skipped_count: 0,
+        skipped_count: total_rows - imported_count,
+    }
+}
+
+/// Sequence read from a NINA legacy target set
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NinaTargetSetImport {
+    pub sequence: SimpleSequence,
+    /// Targets that could not be read
+    pub warnings: Vec<String>,
+}
+
+/// Import a NINA legacy XML target set (`ArrayOfCaptureSequenceList`), as
+/// saved by NINA 1.x or `serializer::export_to_xml`. The format has no
+/// title, so the sequence gets a generic one.
+pub fn import_nina_xml_target_set(content: &str) -> Result<NinaTargetSetImport, String> {
+    let document = XmlElement::parse(content)?;
+    let elements = document.find_all(&[NINA_TARGET_ELEMENT]);
+    if elements.is_empty() {
+        return Err("Not a NINA target set: no CaptureSequenceList found".to_string());
     }
+
+    let mut sequence = SimpleSequence::new("NINA Target Set");
+    sequence.targets.clear();
+    let mut warnings = Vec::new();
+    for element in elements {
+        match parse_nina_capture_list(element) {
+            Ok(target) => sequence.targets.push(target),
+            Err(e) => {
+                let name = element.value(&["TargetName"]).unwrap_or("Unknown");
+                warnings.push(format!("Target '{}': {}", name, e));
+            }
+        }
+    }
+    sequence.selected_target_id = sequence.targets.first().map(|t| t.id.clone());
+    sequence.active_target_id = sequence.selected_target_id.clone();
+
+    Ok(NinaTargetSetImport { sequence, warnings })
 }
 
 /// Parse XML content string
@@ -688,7 +1123,10 @@ pub fn parse_xml_content(content: &str) -> ImportResult {
         parse_apt_format(content)
     } else if content.contains("<Voyager") {
         parse_voyager_format(content)
-    } else if content.contains("<NINA") || content.contains("<Sequence") {
+    } else if content.contains("<NINA")
+        || content.contains("<Sequence")
+        || content.contains(NINA_TARGET_ELEMENT)
+    {
         parse_xml_targets(content, "NINA XML")
     } else {
         parse_xml_targets(content, "Generic XML")
@@ -696,85 +1134,32 @@ pub fn parse_xml_content(content: &str) -> ImportResult {
 }
 
 // ============================================================================
-// FITS Header Import
+// FITS / XISF Header Import
 // ============================================================================
 
-/// FITS header info
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct FitsHeaderInfo {
-    pub object_name: Option<String>,
-    pub ra: Option<f64>,
-    pub dec: Option<f64>,
-    pub exposure_time: Option<f64>,
-    pub filter: Option<String>,
-    pub gain: Option<i32>,
-    pub offset: Option<i32>,
-    pub binning_x: Option<i32>,
-    pub binning_y: Option<i32>,
-    pub date_obs: Option<String>,
-    pub telescope: Option<String>,
-    pub instrument: Option<String>,
-}
-
-/// Parse FITS header from content (simplified - header only)
-pub fn parse_fits_header(content: &[u8]) -> Result<FitsHeaderInfo, String> {
-    // FITS headers are 80 characters per line, ASCII
-    if content.len() < 2880 {
-        return Err("File too small to be a valid FITS file".to_string());
-    }
-
-    let header_str = String::from_utf8_lossy(&content[..2880.min(content.len())]);
-    let mut info = FitsHeaderInfo {
-        object_name: None,
-        ra: None,
-        dec: None,
-        exposure_time: None,
-        filter: None,
-        gain: None,
-        offset: None,
-        binning_x: None,
-        binning_y: None,
-        date_obs: None,
-        telescope: None,
-        instrument: None,
-    };
-
-    for i in 0..(header_str.len() / 80) {
-        let line = &header_str[i * 80..(i + 1) * 80];
-        let key = line[..8].trim();
-
-        if line.len() > 10 && &line[8..10] == "= " {
-            let value = line[10..].trim();
-            let value = value.split('/').next().unwrap_or(value).trim();
-            let value = value.trim_matches('\'').trim();
-
-            match key {
-                "OBJECT" => info.object_name = Some(value.to_string()),
-                "RA" | "OBJCTRA" => info.ra = value.parse().ok(),
-                "DEC" | "OBJCTDEC" => info.dec = value.parse().ok(),
-                "EXPTIME" | "EXPOSURE" => info.exposure_time = value.parse().ok(),
-                "FILTER" => info.filter = Some(value.to_string()),
-                "GAIN" => info.gain = value.parse().ok(),
-                "OFFSET" => info.offset = value.parse().ok(),
-                "XBINNING" => info.binning_x = value.parse().ok(),
-                "YBINNING" => info.binning_y = value.parse().ok(),
-                "DATE-OBS" => info.date_obs = Some(value.to_string()),
-                "TELESCOP" => info.telescope = Some(value.to_string()),
-                "INSTRUME" => info.instrument = Some(value.to_string()),
-                _ => {}
-            }
-        }
-
-        if key == "END" {
-            break;
-        }
+/// Parse a FITS or XISF header, telling the formats apart by signature
+pub fn parse_image_header(data: &[u8]) -> Result<FitsHeaderInfo, String> {
+    if data.starts_with(b"XISF") {
+        parse_xisf_header(data)
+    } else {
+        parse_fits_header(data)
     }
+}
 
-    Ok(info)
+/// Read the header of a FITS or XISF file, chosen by extension
+pub fn read_image_header_file(path: &Path) -> Result<FitsHeaderInfo, String> {
+    let is_xisf = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| XISF_EXTENSIONS.contains(&e.to_lowercase().as_str()));
+    if is_xisf {
+        read_xisf_header_file(path)
+    } else {
+        read_fits_header_file(path)
+    }
 }
 
-/// Create target from FITS header
+/// Create target from a FITS or XISF header
 pub fn create_target_from_fits(info: &FitsHeaderInfo) -> Option<SimpleTarget> {
     let name = info.object_name.clone()?;
     let ra = info.ra?;
@@ -815,6 +1200,118 @@ pub fn create_target_from_fits(info: &FitsHeaderInfo) -> Option<SimpleTarget> {
     Some(target)
 }
 
+// ============================================================================
+// Plate Solve Result Import
+// ============================================================================
+
+/// Plate solve result (ASTAP .ini / .wcs, astrometry.net .wcs)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlateSolveResult {
+    /// Solved center RA in degrees
+    pub ra: f64,
+    /// Solved center Dec in degrees
+    pub dec: f64,
+    /// Sky rotation (position angle) in degrees, 0..360
+    pub rotation: f64,
+    /// Pixel scale in arcsec/pixel, when available
+    pub pixel_scale: Option<f64>,
+    pub warnings: Vec<String>,
+}
+
+/// Parse plate solve output. Accepts ASTAP `.ini` result files as well as
+/// WCS headers (ASTAP `.wcs`, astrometry.net `wcs.fits`).
+pub fn parse_platesolve_result(content: &str) -> Result<PlateSolveResult, String> {
+    // WCS files written by astrometry.net are raw 80-column FITS cards without line breaks
+    let records: Vec<&str> = if !content.contains('\n') && content.len() >= 80 {
+        (0..content.len() / 80)
+            .filter_map(|i| content.get(i * 80..(i + 1) * 80))
+            .collect()
+    } else {
+        content.lines().collect()
+    };
+
+    let mut values: HashMap<String, String> = HashMap::new();
+    for record in records {
+        let key = record.get(..8).unwrap_or(record).trim();
+        if key == "END" {
+            break;
+        }
+
+        let Some((key, value)) = record.split_once('=') else {
+            continue;
+        };
+
+        let value = value.trim();
+        let value = if value.starts_with('\'') {
+            value
+                .trim_start_matches('\'')
+                .split('\'')
+                .next()
+                .unwrap_or("")
+        } else {
+            value.split('/').next().unwrap_or(value)
+        };
+
+        values.insert(key.trim().to_uppercase(), value.trim().to_string());
+    }
+
+    let get = |key: &str| values.get(key).and_then(|v| v.parse::<f64>().ok());
+
+    if let Some(solved) = values.get("PLTSOLVD") {
+        if !solved.eq_ignore_ascii_case("T") {
+            let reason = values
+                .get("ERROR")
+                .filter(|e| !e.is_empty())
+                .cloned()
+                .unwrap_or_else(|| "no solution".to_string());
+            return Err(format!("Plate solve failed: {}", reason));
+        }
+    }
+
+    let ra = get("CRVAL1").ok_or("Missing CRVAL1 in plate solve result")?;
+    let dec = get("CRVAL2").ok_or("Missing CRVAL2 in plate solve result")?;
+
+    if !(0.0..360.0).contains(&ra) || !(-90.0..=90.0).contains(&dec) {
+        return Err(format!("Solved coordinates out of range: {}, {}", ra, dec));
+    }
+
+    let mut warnings = Vec::new();
+    if let Some(warning) = values.get("WARNING").filter(|w| !w.is_empty()) {
+        warnings.push(warning.clone());
+    }
+
+    let (rotation, pixel_scale) = match (get("CD1_1"), get("CD1_2"), get("CD2_1"), get("CD2_2")) {
+        (Some(cd11), Some(cd12), Some(cd21), Some(cd22)) => {
+            let rotation = (-cd12).atan2(cd22).to_degrees();
+            let scale = (cd11 * cd11 + cd21 * cd21).sqrt() * 3600.0;
+            (rotation, Some(scale))
+        }
+        _ => {
+            let rotation = get("CROTA2").or_else(|| get("CROTA1")).unwrap_or_else(|| {
+                warnings.push("No rotation in plate solve result, assuming 0".to_string());
+                0.0
+            });
+            (rotation, get("CDELT2").map(|d| d.abs() * 3600.0))
+        }
+    };
+
+    Ok(PlateSolveResult {
+        ra,
+        dec,
+        rotation: rotation.rem_euclid(360.0),
+        pixel_scale,
+        warnings,
+    })
+}
+
+/// Update a target's coordinates and rotation from a plate solve result
+pub fn apply_platesolve_to_target(target: &mut SimpleTarget, result: &PlateSolveResult) {
+    target.coordinates = Coordinates::from_decimal(result.ra / 15.0, result.dec);
+    target.position_angle = result.rotation;
+    target.rotation = result.rotation;
+}
+
 // ============================================================================
 // Helper Functions
 // ============================================================================
@@ -839,6 +1336,10 @@ fn create_target_from_coords(
         center_target: true,
         rotate_target: false,
         start_guiding: true,
+        priority: TargetPriority::Normal,
+        min_integration: None,
+        max_integration: None,
+        constraints: Default::default(),
         auto_focus_on_start: true,
         auto_focus_on_filter_change: false,
         auto_focus_after_set_time: false,
@@ -853,6 +1354,10 @@ fn create_target_from_coords(
         estimated_start_time: None,
         estimated_end_time: None,
         estimated_duration: None,
+        moving_target: None,
+        notes: None,
+        tags: Vec::new(),
+        metadata: Default::default(),
     }
 }
 
@@ -871,6 +1376,8 @@ fn create_default_exposure() -> SimpleExposure {
         progress_count: 0,
         dither: false,
         dither_every: 1,
+        estimated_start_time: None,
+        estimated_end_time: None,
     }
 }
 
@@ -3,6 +3,7 @@
 #[cfg(test)]
 mod tests {
     use super::super::import_service::*;
+    use crate::models::TargetPriority;
 
     // ============================================================================
     // CSV Parsing Tests
@@ -19,6 +20,15 @@ mod tests {
         assert_eq!(result.targets[1].target_name, "M42");
     }
 
+    #[test]
+    fn test_parse_csv_priority() {
+        let csv = "name,ra,dec,priority\nM31,00:42:44,+41:16:09,high\nM42,05:35:16,-05:23:28,";
+        let result = parse_csv_content(csv, None);
+
+        assert_eq!(result.targets[0].priority, TargetPriority::High);
+        assert_eq!(result.targets[1].priority, TargetPriority::Normal);
+    }
+
     #[test]
     fn test_parse_csv_with_quotes() {
         let csv = r#"name,ra,dec
@@ -47,7 +57,7 @@ mod tests {
         let result = parse_csv_content(csv, None);
 
         assert!(!result.success);
-        assert!(result.errors.len() > 0);
+        assert!(!result.errors.is_empty());
     }
 
     #[test]
@@ -94,6 +104,22 @@ mod tests {
         assert_eq!(result.targets.len(), 1);
     }
 
+    #[test]
+    fn test_parse_csv_quoted_delimiter_and_detection() {
+        let csv = "\u{feff}name;ra;dec;notes\n\
+                   \"NGC 7000, North America Nebula\";20:59:17;+44:31:44;\"wide\nfield\"\n\
+                   M31;00:42:44;+41:16:09;";
+        let result = parse_csv_content(csv, None);
+
+        assert!(result.success);
+        assert_eq!(result.targets.len(), 2);
+        assert_eq!(
+            result.targets[0].target_name,
+            "NGC 7000, North America Nebula"
+        );
+        assert_eq!(result.total_rows, 2);
+    }
+
     // ============================================================================
     // CSV Format Detection Tests
     // ============================================================================
@@ -263,6 +289,37 @@ Dec=-05:23:28"#;
         assert!((result.targets[0].position_angle - 45.0).abs() < 0.1);
     }
 
+    #[test]
+    fn test_parse_xml_attributes_cdata_and_namespaces() {
+        let xml = r#"<?xml version="1.0"?>
+<t:Targets xmlns:t="urn:targets">
+    <t:Target Name="NGC 7000" RA="20:59:17" Dec="+44:31:44">
+        <Notes><![CDATA[North America <Nebula> & friends]]></Notes>
+    </t:Target>
+    <Object><Name>Broken</Name><RA>00:42:44</RA></Object>
+</t:Targets>"#;
+
+        let result = parse_xml_targets(xml, "Test");
+
+        assert!(result.success);
+        assert_eq!(result.targets.len(), 1);
+        assert_eq!(result.targets[0].target_name, "NGC 7000");
+        assert_eq!(
+            result.targets[0].notes.as_deref(),
+            Some("North America <Nebula> & friends")
+        );
+        assert_eq!(result.skipped_count, 1);
+        assert!(result.warnings[0].contains("Broken"));
+    }
+
+    #[test]
+    fn test_parse_xml_reports_malformed_document() {
+        let result = parse_xml_targets("<Target><Name>M31</Target>", "Test");
+
+        assert!(!result.success);
+        assert!(!result.errors.is_empty());
+    }
+
     // ============================================================================
     // FITS Header Tests
     // ============================================================================
@@ -293,6 +350,98 @@ Dec=-05:23:28"#;
         assert_eq!(info.object_name, Some("M31".to_string()));
     }
 
+    #[test]
+    fn test_parse_image_header_xisf() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<xisf version="1.0" xmlns="http://www.pixinsight.com/xisf">
+  <Image geometry="100:100:1" sampleFormat="UInt16" location="attachment:4096:20000">
+    <FITSKeyword name="OBJECT" value="'M31'" comment=""/>
+    <FITSKeyword name="RA" value="10.6847" comment=""/>
+    <FITSKeyword name="DEC" value="41.269" comment=""/>
+    <Property id="Instrument:ExposureTime" type="Float32" value="120"/>
+    <Property id="Instrument:Filter:Name" type="String" value="L"/>
+  </Image>
+</xisf>"#;
+        let mut data = b"XISF0100".to_vec();
+        data.extend((xml.len() as u32).to_le_bytes());
+        data.extend([0u8; 4]);
+        data.extend(xml.as_bytes());
+
+        let info = parse_image_header(&data).unwrap();
+        let target = create_target_from_fits(&info).unwrap();
+        assert_eq!(target.target_name, "M31");
+        assert!((target.coordinates.ra_to_degrees() - 10.6847).abs() < 1e-3);
+        assert_eq!(target.exposures[0].exposure_time, 120.0);
+        assert_eq!(target.exposures[0].filter.as_ref().unwrap().name, "L");
+
+        assert!(parse_xisf_header(&data[..20]).is_err());
+    }
+
+    // ============================================================================
+    // Plate Solve Tests
+    // ============================================================================
+
+    #[test]
+    fn test_parse_platesolve_astap_ini() {
+        let ini = "PLTSOLVD=T\nCRVAL1=10.6847\nCRVAL2=41.2690\nCDELT1=-0.0003\nCDELT2=0.0003\nCROTA1=-92.5\nCROTA2=-92.5\nWARNING=\n";
+        let result = parse_platesolve_result(ini).unwrap();
+
+        assert!((result.ra - 10.6847).abs() < 1e-6);
+        assert!((result.dec - 41.269).abs() < 1e-6);
+        assert!((result.rotation - 267.5).abs() < 1e-6);
+        assert!((result.pixel_scale.unwrap() - 1.08).abs() < 1e-6);
+        assert!(result.warnings.is_empty());
+    }
+
+    #[test]
+    fn test_parse_platesolve_failed() {
+        let ini = "PLTSOLVD=F\nERROR=No solution found\n";
+        let result = parse_platesolve_result(ini);
+
+        assert!(result.is_err());
+        assert!(result.unwrap_err().contains("No solution found"));
+    }
+
+    #[test]
+    fn test_parse_platesolve_wcs_cd_matrix() {
+        let cards = [
+            "CRVAL1  =        83.8220833333 / RA  of reference point",
+            "CRVAL2  =        -5.3911111111 / DEC of reference point",
+            "CD1_1   =   -0.000277777777778",
+            "CD1_2   =                  0.0",
+            "CD2_1   =                  0.0",
+            "CD2_2   =    0.000277777777778",
+            "END",
+        ];
+        let wcs: String = cards.iter().map(|c| format!("{:<80}", c)).collect();
+        let result = parse_platesolve_result(&wcs).unwrap();
+
+        assert!((result.ra - 83.8220833333).abs() < 1e-9);
+        assert!((result.dec + 5.3911111111).abs() < 1e-9);
+        assert!(result.rotation.abs() < 1e-6);
+        assert!((result.pixel_scale.unwrap() - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_apply_platesolve_to_target() {
+        let csv = "name,ra,dec\nM31,00:42:44,+41:16:09";
+        let mut target = parse_csv_content(csv, None).targets.remove(0);
+        let result = PlateSolveResult {
+            ra: 15.0,
+            dec: -10.5,
+            rotation: 90.0,
+            pixel_scale: None,
+            warnings: vec![],
+        };
+
+        apply_platesolve_to_target(&mut target, &result);
+
+        assert!((target.coordinates.ra_to_decimal() - 1.0).abs() < 1e-6);
+        assert!((target.coordinates.dec_to_decimal() + 10.5).abs() < 1e-6);
+        assert_eq!(target.rotation, 90.0);
+        assert_eq!(target.position_angle, 90.0);
+    }
+
     // ============================================================================
     // Import Result Tests
     // ============================================================================
@@ -318,4 +467,86 @@ Dec=-05:23:28"#;
 
         assert!(result.success);
     }
+
+    // ============================================================================
+    // Import Preview Tests
+    // ============================================================================
+
+    #[test]
+    fn test_preview_flags_invalid_and_duplicate_rows() {
+        use crate::services::import_preview::*;
+
+        let existing = parse_csv_content("name,ra,dec\nM31,00:42:44,+41:16:09", None).targets;
+        let csv = "name,ra,dec\n\
+                   Andromeda,00:42:45,+41:16:20\n\
+                   Bad,invalid,data\n\
+                   M42,05:35:16,-05:23:28\n\
+                   m42,06:00:00,+10:00:00\n\
+                   M45,03:47:24,+24:07:00";
+        let preview = preview_csv(csv, None, &existing, DEFAULT_DUPLICATE_RADIUS_ARCMIN);
+
+        let statuses: Vec<ImportRowStatus> = preview.rows.iter().map(|r| r.status).collect();
+        assert_eq!(
+            statuses,
+            vec![
+                ImportRowStatus::Duplicate,
+                ImportRowStatus::Invalid,
+                ImportRowStatus::Valid,
+                ImportRowStatus::Duplicate,
+                ImportRowStatus::Valid,
+            ]
+        );
+        assert_eq!(
+            preview.rows[0].duplicates[0].reason,
+            DuplicateReason::Position
+        );
+        assert!(!preview.rows[0].duplicates[0].within_import);
+        assert_eq!(preview.rows[1].row_number, Some(3));
+        assert!(!preview.rows[1].errors.is_empty());
+        assert_eq!(preview.rows[3].duplicates[0].reason, DuplicateReason::Name);
+        assert!(preview.rows[3].duplicates[0].within_import);
+        assert_eq!(
+            (
+                preview.valid_count,
+                preview.duplicate_count,
+                preview.invalid_count
+            ),
+            (2, 2, 1)
+        );
+
+        let suggested: Vec<usize> = preview
+            .rows
+            .iter()
+            .filter(|r| r.selected)
+            .map(|r| r.index)
+            .collect();
+        assert_eq!(suggested, vec![2, 4]);
+    }
+
+    #[test]
+    fn test_commit_import_uses_selection() {
+        use crate::error::ErrorCode;
+        use crate::services::import_preview::*;
+        use crate::state::AppState;
+
+        let state = AppState::default();
+        let csv = "name,ra,dec\nM31,00:42:44,+41:16:09\nBad,invalid,data\nM42,05:35:16,-05:23:28";
+        let preview = preview_csv(csv, None, &[], DEFAULT_DUPLICATE_RADIUS_ARCMIN);
+        store_preview(&state, &preview);
+
+        let selection = ImportSelection {
+            preview_id: preview.id.clone(),
+            row_indices: vec![2, 1, 2],
+        };
+        let result = commit_import(&state, &selection).unwrap();
+        assert_eq!(result.targets.len(), 1);
+        assert_eq!(result.targets[0].target_name, "M42");
+        assert_eq!(result.skipped.len(), 1);
+
+        // A preview can only be committed once
+        assert_eq!(
+            commit_import(&state, &selection).unwrap_err().code(),
+            ErrorCode::NotFound
+        );
+    }
 }
@@ -0,0 +1,546 @@
+//! Background jobs
+//!
+//! Long-running work runs as a job instead of blocking its invoke call:
+//! the job gets an id right away, reports progress while it runs and can
+//! be cancelled. At most [`MAX_CONCURRENT_JOBS`] run at once; later jobs
+//! wait in start order. Finished jobs keep their result until cleared or
+//! pushed out by newer ones.
+//!
+//! Commands called directly can still be cancelled: the caller passes an
+//! operation id of its choosing and cancels it with [`cancel_operation`].
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::sync::Semaphore;
+
+use crate::error::{AppError, AppResult};
+use crate::state::AppState;
+
+/// Jobs running at the same time
+pub const MAX_CONCURRENT_JOBS: usize = 2;
+
+/// Finished jobs kept for status queries
+const MAX_FINISHED_JOBS: usize = 50;
+
+/// Error of work stopped by cancellation
+pub const JOB_CANCELLED: &str = "Cancelled";
+
+/// Lifecycle state of a job
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum JobState {
+    Queued,
+    Running,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+impl JobState {
+    pub fn is_finished(&self) -> bool {
+        matches!(
+            self,
+            JobState::Completed | JobState::Failed | JobState::Cancelled
+        )
+    }
+}
+
+/// Status of a job, also sent as its progress event
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JobStatus {
+    pub id: String,
+    pub kind: String,
+    pub state: JobState,
+    /// 0 to 1
+    pub progress: f64,
+    pub message: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub started_at: Option<DateTime<Utc>>,
+    pub finished_at: Option<DateTime<Utc>>,
+    /// Output of a completed job
+    pub result: Option<Value>,
+    pub error: Option<String>,
+}
+
+/// Receives every status change of one job
+pub type JobListener = Box<dyn Fn(&JobStatus) + Send + Sync>;
+
+struct JobEntry {
+    status: JobStatus,
+    cancelled: Arc<AtomicBool>,
+    listener: Option<Arc<JobListener>>,
+}
+
+type JobMap = RwLock<HashMap<String, JobEntry>>;
+
+/// Jobs and cancellable operations, part of the
+/// [`AppState`](crate::state::AppState)
+pub struct JobQueue {
+    jobs: Arc<JobMap>,
+    /// Cancellation flags of running operations by caller-chosen id
+    operations: RwLock<HashMap<String, Arc<AtomicBool>>>,
+    slots: Arc<Semaphore>,
+}
+
+impl Default for JobQueue {
+    fn default() -> Self {
+        Self {
+            jobs: Arc::default(),
+            operations: RwLock::default(),
+            slots: Arc::new(Semaphore::new(MAX_CONCURRENT_JOBS)),
+        }
+    }
+}
+
+/// Change a job's status and tell its listener
+fn update(jobs: &JobMap, id: &str, change: impl FnOnce(&mut JobStatus)) {
+    let notification = {
+        let mut jobs = jobs.write();
+        let Some(entry) = jobs.get_mut(id) else {
+            return;
+        };
+        change(&mut entry.status);
+        entry
+            .listener
+            .clone()
+            .map(|listener| (listener, entry.status.clone()))
+    };
+    if let Some((listener, status)) = notification {
+        listener(&status);
+    }
+}
+
+/// Drop the oldest finished jobs beyond [`MAX_FINISHED_JOBS`]
+fn prune_finished(jobs: &JobMap) {
+    let mut jobs = jobs.write();
+    let mut finished: Vec<(DateTime<Utc>, String)> = jobs
+        .values()
+        .filter(|entry| entry.status.state.is_finished())
+        .map(|entry| {
+            (
+                entry.status.finished_at.unwrap_or(entry.status.created_at),
+                entry.status.id.clone(),
+            )
+        })
+        .collect();
+    if finished.len() <= MAX_FINISHED_JOBS {
+        return;
+    }
+    finished.sort();
+    for (_, id) in &finished[..finished.len() - MAX_FINISHED_JOBS] {
+        jobs.remove(id);
+    }
+}
+
+/// Progress reporting and cancellation for the work of one job. A
+/// detached handle belongs to no job: it never reports and is never
+/// cancelled, so the same work can also run outside a job.
+#[derive(Clone)]
+pub struct JobHandle {
+    /// Id of the job and the map holding its status
+    job: Option<(String, Arc<JobMap>)>,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl JobHandle {
+    pub fn detached() -> Self {
+        Self {
+            job: None,
+            cancelled: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Fail with [`JOB_CANCELLED`] once the job is cancelled
+    pub fn checkpoint(&self) -> Result<(), String> {
+        if self.is_cancelled() {
+            Err(JOB_CANCELLED.to_string())
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Report `done` of `total` steps. Only whole-percent changes reach
+    /// the listener.
+    pub fn report(&self, done: usize, total: usize, message: Option<String>) {
+        let Some((id, jobs)) = &self.job else {
+            return;
+        };
+        let progress = if total == 0 {
+            1.0
+        } else {
+            (done as f64 / total as f64).min(1.0)
+        };
+        let changed = jobs.read().get(id).is_some_and(|entry| {
+            (entry.status.progress * 100.0).floor() != (progress * 100.0).floor()
+                || (message.is_some() && entry.status.message != message)
+        });
+        if changed {
+            update(jobs, id, |status| {
+                status.progress = progress;
+                if message.is_some() {
+                    status.message = message;
+                }
+            });
+        }
+    }
+}
+
+/// Cancellable run of a directly called command. The id stays registered
+/// until the operation is dropped.
+pub struct Operation<'a> {
+    queue: &'a JobQueue,
+    id: Option<String>,
+    handle: JobHandle,
+}
+
+impl<'a> Operation<'a> {
+    /// Register `id` for cancellation. Without an id the operation cannot
+    /// be cancelled.
+    pub fn begin(state: &'a AppState, id: Option<String>) -> Self {
+        let queue = &state.jobs;
+        let handle = JobHandle::detached();
+        if let Some(id) = &id {
+            queue
+                .operations
+                .write()
+                .insert(id.clone(), handle.cancelled.clone());
+        }
+        Self { queue, id, handle }
+    }
+
+    /// Handle for the operation's work; it reports nowhere
+    pub fn handle(&self) -> &JobHandle {
+        &self.handle
+    }
+
+    /// Fail with [`AppError::Cancelled`] once the operation is cancelled
+    pub fn checkpoint(&self) -> AppResult<()> {
+        if self.handle.is_cancelled() {
+            Err(AppError::Cancelled)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Error for failed work of the operation: [`AppError::Cancelled`] when
+    /// it stopped because the operation was cancelled
+    pub fn error(&self, error: impl Into<AppError>) -> AppError {
+        if self.handle.is_cancelled() {
+            AppError::Cancelled
+        } else {
+            error.into()
+        }
+    }
+}
+
+impl Drop for Operation<'_> {
+    fn drop(&mut self) {
+        if let Some(id) = &self.id {
+            let mut operations = self.queue.operations.write();
+            // A later call may have reused the id
+            if operations
+                .get(id)
+                .is_some_and(|flag| Arc::ptr_eq(flag, &self.handle.cancelled))
+            {
+                operations.remove(id);
+            }
+        }
+    }
+}
+
+/// Ask a running operation to stop at its next checkpoint. Returns false
+/// when no operation has the id.
+pub fn cancel_operation(state: &AppState, id: &str) -> bool {
+    match state.jobs.operations.read().get(id) {
+        Some(flag) => {
+            flag.store(true, Ordering::SeqCst);
+            true
+        }
+        None => false,
+    }
+}
+
+/// Queue `work` as a job of `kind` and return its id. The work runs on
+/// the async runtime once a slot is free; CPU-heavy work should move to
+/// the blocking pool with [`run_blocking`].
+pub fn start_job<F, Fut>(
+    state: &AppState,
+    kind: &str,
+    listener: Option<JobListener>,
+    work: F,
+) -> String
+where
+    F: FnOnce(JobHandle) -> Fut + Send + 'static,
+    Fut: Future<Output = Result<Value, String>> + Send + 'static,
+{
+    let id = uuid::Uuid::new_v4().to_string();
+    let cancelled = Arc::new(AtomicBool::new(false));
+    let status = JobStatus {
+        id: id.clone(),
+        kind: kind.to_string(),
+        state: JobState::Queued,
+        progress: 0.0,
+        message: None,
+        created_at: Utc::now(),
+        started_at: None,
+        finished_at: None,
+        result: None,
+        error: None,
+    };
+    let listener = listener.map(Arc::new);
+    if let Some(listener) = &listener {
+        listener(&status);
+    }
+    let jobs = state.jobs.jobs.clone();
+    jobs.write().insert(
+        id.clone(),
+        JobEntry {
+            status,
+            cancelled: cancelled.clone(),
+            listener,
+        },
+    );
+
+    let handle = JobHandle {
+        job: Some((id.clone(), jobs.clone())),
+        cancelled,
+    };
+    let slots = state.jobs.slots.clone();
+    let job_id = id.clone();
+    tokio::spawn(async move {
+        let Ok(_permit) = slots.acquire_owned().await else {
+            return;
+        };
+        let id = job_id;
+        if handle.is_cancelled() {
+            update(&jobs, &id, |status| {
+                status.state = JobState::Cancelled;
+                status.finished_at = Some(Utc::now());
+            });
+            prune_finished(&jobs);
+            return;
+        }
+
+        update(&jobs, &id, |status| {
+            status.state = JobState::Running;
+            status.started_at = Some(Utc::now());
+        });
+        let cancelled = handle.cancelled.clone();
+        let outcome = work(handle).await;
+        update(&jobs, &id, |status| {
+            status.finished_at = Some(Utc::now());
+            match outcome {
+                Ok(value) => {
+                    status.state = JobState::Completed;
+                    status.progress = 1.0;
+                    status.result = Some(value);
+                }
+                Err(_) if cancelled.load(Ordering::SeqCst) => {
+                    status.state = JobState::Cancelled;
+                }
+                Err(e) => {
+                    status.state = JobState::Failed;
+                    status.error = Some(e);
+                }
+            }
+        });
+        prune_finished(&jobs);
+    });
+
+    id
+}
+
+/// Run blocking work on the blocking thread pool
+pub async fn run_blocking<T, F>(work: F) -> Result<T, String>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T, String> + Send + 'static,
+{
+    tokio::task::spawn_blocking(work)
+        .await
+        .map_err(|e| format!("Job task failed: {}", e))?
+}
+
+pub fn get_job_status(state: &AppState, id: &str) -> Option<JobStatus> {
+    state
+        .jobs
+        .jobs
+        .read()
+        .get(id)
+        .map(|entry| entry.status.clone())
+}
+
+/// All known jobs, newest first, without their results
+pub fn list_jobs(state: &AppState) -> Vec<JobStatus> {
+    let mut jobs: Vec<JobStatus> = state
+        .jobs
+        .jobs
+        .read()
+        .values()
+        .map(|entry| JobStatus {
+            result: None,
+            ..entry.status.clone()
+        })
+        .collect();
+    jobs.sort_by_key(|job| std::cmp::Reverse(job.created_at));
+    jobs
+}
+
+/// Ask a job to stop. Queued jobs never start; running jobs stop at their
+/// next checkpoint.
+pub fn cancel_job(state: &AppState, id: &str) -> AppResult<()> {
+    let jobs = state.jobs.jobs.read();
+    let entry = jobs
+        .get(id)
+        .ok_or_else(|| AppError::NotFound(format!("Job not found: {}", id)))?;
+    if entry.status.state.is_finished() {
+        return Err(AppError::InvalidInput(format!(
+            "Job already finished: {}",
+            id
+        )));
+    }
+    entry.cancelled.store(true, Ordering::SeqCst);
+    Ok(())
+}
+
+/// Forget finished jobs and their results
+pub fn clear_finished_jobs(state: &AppState) -> usize {
+    let mut jobs = state.jobs.jobs.write();
+    let before = jobs.len();
+    jobs.retain(|_, entry| !entry.status.state.is_finished());
+    before - jobs.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+    use std::time::Duration;
+
+    fn wait_until_finished(
+        state: &AppState,
+        runtime: &tokio::runtime::Runtime,
+        id: &str,
+    ) -> JobStatus {
+        runtime.block_on(async {
+            loop {
+                let status = get_job_status(state, id).unwrap();
+                if status.state.is_finished() {
+                    return status;
+                }
+                tokio::time::sleep(Duration::from_millis(5)).await;
+            }
+        })
+    }
+
+    #[test]
+    fn test_job_completes_with_progress() {
+        let state = AppState::default();
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let events: Arc<Mutex<Vec<JobStatus>>> = Arc::new(Mutex::new(Vec::new()));
+        let sink = events.clone();
+
+        let id = runtime.block_on(async {
+            start_job(
+                &state,
+                "count",
+                Some(Box::new(move |status| {
+                    sink.lock().unwrap().push(status.clone())
+                })),
+                |job| async move {
+                    run_blocking(move || {
+                        for i in 0..=10 {
+                            job.checkpoint()?;
+                            job.report(i, 10, None);
+                        }
+                        Ok(Value::from(42))
+                    })
+                    .await
+                },
+            )
+        });
+
+        let status = wait_until_finished(&state, &runtime, &id);
+        assert_eq!(status.state, JobState::Completed);
+        assert_eq!(status.result, Some(Value::from(42)));
+        assert_eq!(status.kind, "count");
+
+        let events = events.lock().unwrap();
+        assert_eq!(events[0].state, JobState::Queued);
+        assert!(events.iter().any(|e| e.progress == 0.5));
+        assert_eq!(events.last().unwrap().state, JobState::Completed);
+        assert!(list_jobs(&state)
+            .iter()
+            .any(|job| job.id == id && job.result.is_none()));
+    }
+
+    #[test]
+    fn test_job_cancellation_and_failure() {
+        let state = AppState::default();
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let id = runtime.block_on(async {
+            start_job(&state, "spin", None, |job| async move {
+                run_blocking(move || loop {
+                    job.checkpoint()?;
+                    std::thread::sleep(Duration::from_millis(1));
+                })
+                .await
+            })
+        });
+        std::thread::sleep(Duration::from_millis(20));
+        cancel_job(&state, &id).unwrap();
+        assert_eq!(
+            wait_until_finished(&state, &runtime, &id).state,
+            JobState::Cancelled
+        );
+        assert!(cancel_job(&state, &id).is_err());
+
+        let failing = runtime.block_on(async {
+            start_job(&state, "fail", None, |_| async {
+                Err("Broken".to_string())
+            })
+        });
+        let status = wait_until_finished(&state, &runtime, &failing);
+        assert_eq!(status.state, JobState::Failed);
+        assert_eq!(status.error.as_deref(), Some("Broken"));
+
+        assert_eq!(
+            cancel_job(&state, "missing"),
+            Err(AppError::NotFound("Job not found: missing".to_string()))
+        );
+        assert!(JobHandle::detached().checkpoint().is_ok());
+    }
+
+    #[test]
+    fn test_operation_cancellation() {
+        let state = AppState::default();
+        let operation = Operation::begin(&state, Some("op-test".to_string()));
+        assert!(operation.handle().checkpoint().is_ok());
+        assert!(cancel_operation(&state, "op-test"));
+        assert_eq!(
+            operation.handle().checkpoint(),
+            Err(JOB_CANCELLED.to_string())
+        );
+        assert_eq!(operation.checkpoint(), Err(AppError::Cancelled));
+        assert_eq!(
+            operation.error(JOB_CANCELLED.to_string()),
+            AppError::Cancelled
+        );
+
+        drop(operation);
+        assert!(!cancel_operation(&state, "op-test"));
+        assert!(Operation::begin(&state, None).handle().checkpoint().is_ok());
+    }
+}
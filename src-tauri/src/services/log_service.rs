@@ -1,14 +1,17 @@
 //! Logging service for operation tracking
 
-use chrono::{DateTime, Utc};
-use once_cell::sync::Lazy;
+use chrono::{DateTime, NaiveDateTime, Utc};
 use parking_lot::RwLock;
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
-use std::sync::Arc;
-use tokio::fs;
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use tokio::fs::{self, OpenOptions};
+use tokio::io::AsyncWriteExt;
 
-use crate::services::file_service;
+use crate::error::{AppError, AppResult, ResultExt};
+use crate::services::zip_writer::ZipWriter;
+use crate::services::{file_service, settings_service};
+use crate::state::AppState;
 
 /// Log entry
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -32,12 +35,84 @@ pub enum LogLevel {
     Error,
 }
 
-/// In-memory log buffer
-static LOG_BUFFER: Lazy<Arc<RwLock<Vec<LogEntry>>>> =
-    Lazy::new(|| Arc::new(RwLock::new(Vec::new())));
-
 const MAX_BUFFER_SIZE: usize = 1000;
 
+/// Event emitted to the frontend for each new log entry
+pub const LOG_ENTRY_EVENT: &str = "log://entry";
+
+/// Callback notified of every new log entry
+pub type LogListener = Box<dyn Fn(&LogEntry) + Send + Sync>;
+
+/// Log buffer and listener, part of the [`AppState`](crate::state::AppState)
+#[derive(Default)]
+pub struct LogState {
+    /// Recent entries, oldest first
+    buffer: RwLock<Vec<LogEntry>>,
+    /// Id of the newest entry already written to the log file
+    last_flushed_id: RwLock<Option<String>>,
+    listener: RwLock<Option<LogListener>>,
+}
+
+/// Size at which the day's log file is rotated
+const MAX_LOG_FILE_SIZE: u64 = 5 * 1024 * 1024;
+
+/// Log files from this many days go into a support bundle
+const SUPPORT_BUNDLE_LOG_DAYS: i64 = 7;
+
+/// Settings fields left out of support bundles
+const REDACTED_SETTINGS: &[&str] = &[
+    "lastDirectory",
+    "recentFiles",
+    "pinnedFiles",
+    "recentFileMetadata",
+    "latitude",
+    "longitude",
+    "elevation",
+    "token",
+];
+
+/// Log export format
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogExportFormat {
+    Jsonl,
+    Csv,
+}
+
+/// Time range of exported logs. Open ends are unbounded.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogTimeRange {
+    #[serde(default)]
+    pub from: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub to: Option<DateTime<Utc>>,
+}
+
+impl LogTimeRange {
+    fn contains(&self, timestamp: DateTime<Utc>) -> bool {
+        self.from.map_or(true, |from| timestamp >= from)
+            && self.to.map_or(true, |to| timestamp <= to)
+    }
+
+    /// Whether a log file for `date` (YYYY-MM-DD) may hold entries in range
+    fn overlaps_day(&self, date: &str) -> bool {
+        let day = |t: DateTime<Utc>| t.format("%Y-%m-%d").to_string();
+        self.from.map_or(true, |from| date >= day(from).as_str())
+            && self.to.map_or(true, |to| date <= day(to).as_str())
+    }
+}
+
+/// Created support bundle
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SupportBundleInfo {
+    pub path: String,
+    /// Archive paths of the included files
+    pub files: Vec<String>,
+    pub size: u64,
+}
+
 /// Get logs directory
 pub fn get_logs_directory() -> PathBuf {
     file_service::get_app_data_directory().join("logs")
@@ -50,14 +125,15 @@ pub fn get_current_log_path() -> PathBuf {
 }
 
 /// Ensure logs directory exists
-pub async fn ensure_logs_directory() -> Result<(), String> {
+pub async fn ensure_logs_directory() -> AppResult<()> {
     fs::create_dir_all(get_logs_directory())
         .await
-        .map_err(|e| format!("Failed to create logs directory: {}", e))
+        .context("Failed to create logs directory")
 }
 
 /// Add log entry
 pub fn log_entry(
+    state: &AppState,
     level: LogLevel,
     category: &str,
     message: &str,
@@ -72,13 +148,19 @@ pub fn log_entry(
         details,
     };
 
-    let mut buffer = LOG_BUFFER.write();
-    buffer.push(entry.clone());
+    {
+        let mut buffer = state.logs.buffer.write();
+        buffer.push(entry.clone());
 
-    // Trim buffer if too large
-    if buffer.len() > MAX_BUFFER_SIZE {
-        let drain_count = buffer.len() - MAX_BUFFER_SIZE;
-        buffer.drain(0..drain_count);
+        // Trim buffer if too large
+        if buffer.len() > MAX_BUFFER_SIZE {
+            let drain_count = buffer.len() - MAX_BUFFER_SIZE;
+            buffer.drain(0..drain_count);
+        }
+    }
+
+    if let Some(listener) = state.logs.listener.read().as_ref() {
+        listener(&entry);
     }
 
     // Also log to standard log
@@ -90,39 +172,49 @@ pub fn log_entry(
     }
 }
 
+/// Set the callback notified of every new entry, replacing any previous one
+pub fn set_log_listener(state: &AppState, listener: Option<LogListener>) {
+    *state.logs.listener.write() = listener;
+}
+
 /// Log debug message
-pub fn log_debug(category: &str, message: &str) {
-    log_entry(LogLevel::Debug, category, message, None);
+pub fn log_debug(state: &AppState, category: &str, message: &str) {
+    log_entry(state, LogLevel::Debug, category, message, None);
 }
 
 /// Log info message
-pub fn log_info(category: &str, message: &str) {
-    log_entry(LogLevel::Info, category, message, None);
+pub fn log_info(state: &AppState, category: &str, message: &str) {
+    log_entry(state, LogLevel::Info, category, message, None);
 }
 
 /// Log warning message
-pub fn log_warning(category: &str, message: &str) {
-    log_entry(LogLevel::Warning, category, message, None);
+pub fn log_warning(state: &AppState, category: &str, message: &str) {
+    log_entry(state, LogLevel::Warning, category, message, None);
 }
 
 /// Log error message
-pub fn log_error(category: &str, message: &str) {
-    log_entry(LogLevel::Error, category, message, None);
+pub fn log_error(state: &AppState, category: &str, message: &str) {
+    log_entry(state, LogLevel::Error, category, message, None);
 }
 
 /// Log with details
 pub fn log_with_details(
+    state: &AppState,
     level: LogLevel,
     category: &str,
     message: &str,
     details: serde_json::Value,
 ) {
-    log_entry(level, category, message, Some(details));
+    log_entry(state, level, category, message, Some(details));
 }
 
 /// Get recent logs from buffer
-pub fn get_recent_logs(count: usize, level_filter: Option<LogLevel>) -> Vec<LogEntry> {
-    let buffer = LOG_BUFFER.read();
+pub fn get_recent_logs(
+    state: &AppState,
+    count: usize,
+    level_filter: Option<LogLevel>,
+) -> Vec<LogEntry> {
+    let buffer = state.logs.buffer.read();
 
     buffer
         .iter()
@@ -140,8 +232,8 @@ pub fn get_recent_logs(count: usize, level_filter: Option<LogLevel>) -> Vec<LogE
 }
 
 /// Get logs by category
-pub fn get_logs_by_category(category: &str, count: usize) -> Vec<LogEntry> {
-    let buffer = LOG_BUFFER.read();
+pub fn get_logs_by_category(state: &AppState, category: &str, count: usize) -> Vec<LogEntry> {
+    let buffer = state.logs.buffer.read();
 
     buffer
         .iter()
@@ -153,17 +245,114 @@ pub fn get_logs_by_category(category: &str, count: usize) -> Vec<LogEntry> {
 }
 
 /// Clear log buffer
-pub fn clear_log_buffer() {
-    LOG_BUFFER.write().clear();
+pub fn clear_log_buffer(state: &AppState) {
+    state.logs.buffer.write().clear();
+}
+
+/// Format an entry as a log file line
+fn format_log_line(entry: &LogEntry) -> String {
+    format!(
+        "[{}] [{}] [{}] {}{}\n",
+        entry.timestamp.format("%Y-%m-%d %H:%M:%S%.3f"),
+        format!("{:?}", entry.level).to_uppercase(),
+        entry.category,
+        entry.message,
+        entry
+            .details
+            .as_ref()
+            .map(|d| format!(" | {}", d))
+            .unwrap_or_default()
+    )
+}
+
+/// Parse a log file line written by [`flush_logs_to_file`]. Entry ids are
+/// not stored in log files, so parsed entries get fresh ones.
+pub fn parse_log_line(line: &str) -> Option<LogEntry> {
+    let mut fields = Vec::with_capacity(3);
+    let mut rest = line.trim_end();
+    for _ in 0..3 {
+        let field = rest.strip_prefix('[')?;
+        let end = field.find(']')?;
+        fields.push(&field[..end]);
+        rest = field[end + 1..]
+            .strip_prefix(' ')
+            .unwrap_or(&field[end + 1..]);
+    }
+
+    let timestamp = NaiveDateTime::parse_from_str(fields[0], "%Y-%m-%d %H:%M:%S%.3f")
+        .ok()?
+        .and_utc();
+    let level = match fields[1] {
+        "DEBUG" => LogLevel::Debug,
+        "INFO" => LogLevel::Info,
+        "WARNING" => LogLevel::Warning,
+        "ERROR" => LogLevel::Error,
+        _ => return None,
+    };
+    let (message, details) = match rest.rsplit_once(" | ") {
+        Some((message, details)) => match serde_json::from_str::<Value>(details) {
+            Ok(details) => (message, Some(details)),
+            Err(_) => (rest, None),
+        },
+        None => (rest, None),
+    };
+
+    Some(LogEntry {
+        id: uuid::Uuid::new_v4().to_string(),
+        timestamp,
+        level,
+        category: fields[2].to_string(),
+        message: message.to_string(),
+        details,
+    })
+}
+
+/// Split a log file stem into its date and rotation part. The live file
+/// of a day has no part and sorts after its rotated parts.
+fn log_file_key(stem: &str) -> (&str, u32) {
+    match stem.split_once('.') {
+        Some((date, part)) => (date, part.parse().unwrap_or(0)),
+        None => (stem, u32::MAX),
+    }
+}
+
+/// Move the day's log file aside once it reaches [`MAX_LOG_FILE_SIZE`]
+async fn rotate_log_file(path: &Path) -> AppResult<()> {
+    let size = match fs::metadata(path).await {
+        Ok(metadata) => metadata.len(),
+        Err(_) => return Ok(()),
+    };
+    if size < MAX_LOG_FILE_SIZE {
+        return Ok(());
+    }
+
+    let stem = path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or_default()
+        .to_string();
+    let rotated = (1..)
+        .map(|part| path.with_file_name(format!("{}.{}.log", stem, part)))
+        .find(|p| !p.exists())
+        .expect("unbounded range yields a free name");
+
+    fs::rename(path, &rotated)
+        .await
+        .context("Failed to rotate log file")
 }
 
-/// Flush logs to file
-pub async fn flush_logs_to_file() -> Result<usize, String> {
+/// Append entries logged since the last flush to the day's log file
+pub async fn flush_logs_to_file(state: &AppState) -> AppResult<usize> {
     ensure_logs_directory().await?;
 
     let entries: Vec<LogEntry> = {
-        let buffer = LOG_BUFFER.read();
-        buffer.clone()
+        let buffer = state.logs.buffer.read();
+        let last_flushed = state.logs.last_flushed_id.read();
+        let start = last_flushed
+            .as_ref()
+            .and_then(|id| buffer.iter().position(|e| &e.id == id))
+            .map_or(0, |index| index + 1);
+        buffer[start..].to_vec()
     };
 
     if entries.is_empty() {
@@ -171,41 +360,32 @@ pub async fn flush_logs_to_file() -> Result<usize, String> {
     }
 
     let path = get_current_log_path();
-    let mut content = String::new();
+    rotate_log_file(&path).await?;
 
-    // Read existing content if file exists
-    if path.exists() {
-        if let Ok(existing) = fs::read_to_string(&path).await {
-            content = existing;
-        }
-    }
-
-    // Append new entries
-    for entry in &entries {
-        let line = format!(
-            "[{}] [{}] [{}] {}{}\n",
-            entry.timestamp.format("%Y-%m-%d %H:%M:%S%.3f"),
-            format!("{:?}", entry.level).to_uppercase(),
-            entry.category,
-            entry.message,
-            entry
-                .details
-                .as_ref()
-                .map(|d| format!(" | {}", d))
-                .unwrap_or_default()
-        );
-        content.push_str(&line);
-    }
-
-    fs::write(&path, content)
+    let content: String = entries.iter().map(format_log_line).collect();
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .await
+        .context("Failed to write logs")?;
+    file.write_all(content.as_bytes())
         .await
-        .map_err(|e| format!("Failed to write logs: {}", e))?;
+        .context("Failed to write logs")?;
+
+    *state.logs.last_flushed_id.write() = entries.last().map(|e| e.id.clone());
 
     Ok(entries.len())
 }
 
 /// Read log file
-pub async fn read_log_file(date: &str) -> Result<String, String> {
+pub async fn read_log_file(date: &str) -> AppResult<String> {
+    if date.is_empty() || date.contains(['/', '\\']) || date.contains("..") {
+        return Err(AppError::InvalidInput(format!(
+            "Invalid log file name: {}",
+            date
+        )));
+    }
     let path = get_logs_directory().join(format!("{}.log", date));
 
     if !path.exists() {
@@ -214,11 +394,11 @@ pub async fn read_log_file(date: &str) -> Result<String, String> {
 
     fs::read_to_string(&path)
         .await
-        .map_err(|e| format!("Failed to read log file: {}", e))
+        .context("Failed to read log file")
 }
 
 /// List available log files
-pub async fn list_log_files() -> Result<Vec<String>, String> {
+pub async fn list_log_files() -> AppResult<Vec<String>> {
     let dir = get_logs_directory();
 
     if !dir.exists() {
@@ -228,9 +408,9 @@ pub async fn list_log_files() -> Result<Vec<String>, String> {
     let mut files = Vec::new();
     let mut entries = fs::read_dir(&dir)
         .await
-        .map_err(|e| format!("Failed to read logs directory: {}", e))?;
+        .context("Failed to read logs directory")?;
 
-    while let Some(entry) = entries.next_entry().await.map_err(|e| e.to_string())? {
+    while let Some(entry) = entries.next_entry().await? {
         let path = entry.path();
         if path.extension().and_then(|e| e.to_str()) == Some("log") {
             if let Some(stem) = path.file_stem().and_then(|s| s.to_str()) {
@@ -240,13 +420,13 @@ pub async fn list_log_files() -> Result<Vec<String>, String> {
     }
 
     // Sort by date (newest first)
-    files.sort_by(|a, b| b.cmp(a));
+    files.sort_by(|a, b| log_file_key(b).cmp(&log_file_key(a)));
 
     Ok(files)
 }
 
 /// Clean old log files
-pub async fn clean_old_logs(max_age_days: i64) -> Result<usize, String> {
+pub async fn clean_old_logs(max_age_days: i64) -> AppResult<usize> {
     let dir = get_logs_directory();
 
     if !dir.exists() {
@@ -259,9 +439,9 @@ pub async fn clean_old_logs(max_age_days: i64) -> Result<usize, String> {
     let mut deleted = 0;
     let mut entries = fs::read_dir(&dir)
         .await
-        .map_err(|e| format!("Failed to read logs directory: {}", e))?;
+        .context("Failed to read logs directory")?;
 
-    while let Some(entry) = entries.next_entry().await.map_err(|e| e.to_string())? {
+    while let Some(entry) = entries.next_entry().await? {
         let path = entry.path();
         if path.extension().and_then(|e| e.to_str()) == Some("log") {
             if let Some(stem) = path.file_stem().and_then(|s| s.to_str()) {
@@ -279,8 +459,241 @@ pub async fn clean_old_logs(max_age_days: i64) -> Result<usize, String> {
     Ok(deleted)
 }
 
+/// Delete the oldest log files until all of them fit in `max_total_size`
+/// bytes. The live file of today is never deleted.
+pub async fn clean_logs_by_size(max_total_size: u64) -> AppResult<usize> {
+    let dir = get_logs_directory();
+    let current = get_current_log_path();
+
+    let mut sizes = Vec::new();
+    for stem in list_log_files().await? {
+        let path = dir.join(format!("{}.log", stem));
+        if let Ok(metadata) = fs::metadata(&path).await {
+            sizes.push((path, metadata.len()));
+        }
+    }
+
+    let mut total: u64 = sizes.iter().map(|(_, size)| size).sum();
+    let mut deleted = 0;
+    // Files are listed newest first
+    for (path, size) in sizes.iter().rev() {
+        if total <= max_total_size {
+            break;
+        }
+        if *path == current {
+            continue;
+        }
+        if let Err(e) = fs::remove_file(path).await {
+            log::warn!("Failed to delete log file {:?}: {}", path, e);
+        } else {
+            total -= size;
+            deleted += 1;
+        }
+    }
+
+    Ok(deleted)
+}
+
+/// Read logged entries in a time range, oldest first. Pending entries are
+/// flushed first so the result is complete.
+pub async fn read_log_entries(state: &AppState, range: &LogTimeRange) -> AppResult<Vec<LogEntry>> {
+    flush_logs_to_file(state).await?;
+
+    let mut stems = list_log_files().await?;
+    stems.retain(|stem| range.overlaps_day(log_file_key(stem).0));
+    stems.reverse();
+
+    let mut entries = Vec::new();
+    for stem in stems {
+        let content = read_log_file(&stem).await?;
+        entries.extend(
+            content
+                .lines()
+                .filter_map(parse_log_line)
+                .filter(|e| range.contains(e.timestamp)),
+        );
+    }
+
+    Ok(entries)
+}
+
+/// Format entries for export
+pub fn format_log_entries(entries: &[LogEntry], format: LogExportFormat) -> AppResult<String> {
+    match format {
+        LogExportFormat::Jsonl => {
+            let mut content = String::new();
+            for entry in entries {
+                let line = serde_json::to_string(entry).map_err(|e| {
+                    AppError::Internal(format!("Failed to serialize log entry: {}", e))
+                })?;
+                content.push_str(&line);
+                content.push('\n');
+            }
+            Ok(content)
+        }
+        LogExportFormat::Csv => {
+            let mut writer = csv::Writer::from_writer(Vec::new());
+            writer
+                .write_record(["timestamp", "level", "category", "message", "details"])
+                .map_err(|e| AppError::Internal(e.to_string()))?;
+            for entry in entries {
+                writer
+                    .write_record([
+                        entry.timestamp.to_rfc3339(),
+                        format!("{:?}", entry.level).to_lowercase(),
+                        entry.category.clone(),
+                        entry.message.clone(),
+                        entry
+                            .details
+                            .as_ref()
+                            .map(|d| d.to_string())
+                            .unwrap_or_default(),
+                    ])
+                    .map_err(|e| AppError::Internal(e.to_string()))?;
+            }
+            let bytes = writer
+                .into_inner()
+                .map_err(|e| AppError::Internal(e.to_string()))?;
+            String::from_utf8(bytes).map_err(|e| AppError::Internal(e.to_string()))
+        }
+    }
+}
+
+/// Export logged entries in a time range to a file
+pub async fn export_logs(
+    state: &AppState,
+    range: &LogTimeRange,
+    format: LogExportFormat,
+    path: &Path,
+) -> AppResult<usize> {
+    let entries = read_log_entries(state, range).await?;
+    let content = format_log_entries(&entries, format)?;
+
+    fs::write(path, content)
+        .await
+        .context("Failed to write log export")?;
+
+    Ok(entries.len())
+}
+
+/// Replace the values of privacy-sensitive fields, at any depth
+fn redact_settings(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            for (key, field) in map.iter_mut() {
+                if REDACTED_SETTINGS.contains(&key.as_str()) {
+                    *field = Value::String("<redacted>".to_string());
+                } else {
+                    redact_settings(field);
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(redact_settings),
+        _ => {}
+    }
+}
+
+/// Newest autosave file, or the one of `sequence_id`
+async fn find_autosave(sequence_id: Option<&str>) -> Option<PathBuf> {
+    if let Some(id) = sequence_id {
+        let path = file_service::create_auto_save_path(id);
+        return path.exists().then_some(path);
+    }
+
+    let mut newest: Option<(std::time::SystemTime, PathBuf)> = None;
+    let mut entries = fs::read_dir(file_service::get_auto_save_directory())
+        .await
+        .ok()?;
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        let Ok(modified) = entry.metadata().await.and_then(|m| m.modified()) else {
+            continue;
+        };
+        if newest.as_ref().map_or(true, |(time, _)| modified > *time) {
+            newest = Some((modified, entry.path()));
+        }
+    }
+    newest.map(|(_, path)| path)
+}
+
+/// Write a zip with recent logs, redacted settings and the current autosave
+/// for attaching to bug reports
+pub async fn create_support_bundle(
+    state: &AppState,
+    path: &Path,
+    sequence_id: Option<&str>,
+) -> AppResult<SupportBundleInfo> {
+    flush_logs_to_file(state).await?;
+
+    let now = Utc::now();
+    let mut zip = ZipWriter::new(now);
+    let mut files = Vec::new();
+
+    let cutoff = (now - chrono::Duration::days(SUPPORT_BUNDLE_LOG_DAYS))
+        .format("%Y-%m-%d")
+        .to_string();
+    for stem in list_log_files().await? {
+        if log_file_key(&stem).0 < cutoff.as_str() {
+            continue;
+        }
+        let name = format!("logs/{}.log", stem);
+        zip.add_file(&name, read_log_file(&stem).await?.as_bytes())?;
+        files.push(name);
+    }
+
+    let mut settings = serde_json::to_value(settings_service::get_settings(state))
+        .map_err(|e| AppError::Internal(format!("Failed to serialize settings: {}", e)))?;
+    redact_settings(&mut settings);
+    let settings =
+        serde_json::to_string_pretty(&settings).map_err(|e| AppError::Internal(e.to_string()))?;
+    zip.add_file("settings.json", settings.as_bytes())?;
+    files.push("settings.json".to_string());
+
+    if let Some(autosave) = find_autosave(sequence_id).await {
+        let content = fs::read(&autosave)
+            .await
+            .context("Failed to read autosave")?;
+        let name = format!(
+            "autosave/{}",
+            autosave
+                .file_name()
+                .and_then(|n| n.to_str())
+                .unwrap_or("autosave.json")
+        );
+        zip.add_file(&name, &content)?;
+        files.push(name);
+    }
+
+    let manifest = serde_json::json!({
+        "appVersion": env!("CARGO_PKG_VERSION"),
+        "os": std::env::consts::OS,
+        "arch": std::env::consts::ARCH,
+        "createdAt": now,
+        "files": files,
+    });
+    let manifest =
+        serde_json::to_string_pretty(&manifest).map_err(|e| AppError::Internal(e.to_string()))?;
+    zip.add_file("manifest.json", manifest.as_bytes())?;
+
+    let bytes = zip.finish()?;
+    fs::write(path, &bytes)
+        .await
+        .context("Failed to write support bundle")?;
+
+    Ok(SupportBundleInfo {
+        path: path.display().to_string(),
+        files,
+        size: bytes.len() as u64,
+    })
+}
+
 /// Log operation for tracking user actions
-pub fn log_operation(operation: &str, target: &str, success: bool, error: Option<&str>) {
+pub fn log_operation(
+    state: &AppState,
+    operation: &str,
+    target: &str,
+    success: bool,
+    error: Option<&str>,
+) {
     let level = if success {
         LogLevel::Info
     } else {
@@ -298,6 +711,7 @@ pub fn log_operation(operation: &str, target: &str, success: bool, error: Option
     };
 
     log_entry(
+        state,
         level,
         "operation",
         &message,
@@ -309,3 +723,128 @@ pub fn log_operation(operation: &str, target: &str, success: bool, error: Option
         })),
     );
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::sync::Arc;
+
+    fn entry(details: Option<Value>) -> LogEntry {
+        LogEntry {
+            id: "id".to_string(),
+            timestamp: NaiveDateTime::parse_from_str(
+                "2025-03-01 21:15:02.250",
+                "%Y-%m-%d %H:%M:%S%.3f",
+            )
+            .unwrap()
+            .and_utc(),
+            level: LogLevel::Warning,
+            category: "file".to_string(),
+            message: "Saved | with pipe".to_string(),
+            details,
+        }
+    }
+
+    #[test]
+    fn test_log_listener_receives_entries() {
+        let state = AppState::default();
+        let received = Arc::new(RwLock::new(Vec::new()));
+        let sink = received.clone();
+        set_log_listener(
+            &state,
+            Some(Box::new(move |entry: &LogEntry| {
+                if entry.category == "listener-test" {
+                    sink.write().push(entry.details.clone());
+                }
+            })),
+        );
+
+        log_with_details(
+            &state,
+            LogLevel::Info,
+            "listener-test",
+            "Exposure done",
+            json!({ "frame": 3 }),
+        );
+        set_log_listener(&state, None);
+        log_info(&state, "listener-test", "Not delivered");
+
+        assert_eq!(*received.read(), vec![Some(json!({ "frame": 3 }))]);
+    }
+
+    #[test]
+    fn test_log_line_round_trip() {
+        for details in [None, Some(json!({ "path": "a.json" }))] {
+            let original = entry(details);
+            let parsed = parse_log_line(&format_log_line(&original)).unwrap();
+            assert_eq!(parsed.timestamp, original.timestamp);
+            assert_eq!(parsed.level, original.level);
+            assert_eq!(parsed.category, original.category);
+            assert_eq!(parsed.message, original.message);
+            assert_eq!(parsed.details, original.details);
+        }
+        assert!(parse_log_line("not a log line").is_none());
+    }
+
+    #[test]
+    fn test_format_log_entries() {
+        let entries = vec![entry(Some(json!({ "n": 1 })))];
+
+        let jsonl = format_log_entries(&entries, LogExportFormat::Jsonl).unwrap();
+        assert_eq!(jsonl.lines().count(), 1);
+        let parsed: LogEntry = serde_json::from_str(jsonl.trim()).unwrap();
+        assert_eq!(parsed.message, "Saved | with pipe");
+
+        let csv = format_log_entries(&entries, LogExportFormat::Csv).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[0], "timestamp,level,category,message,details");
+        assert!(lines[1].contains(",warning,file,Saved | with pipe,"));
+    }
+
+    #[test]
+    fn test_log_file_order_and_range() {
+        let mut stems = vec!["2025-03-01.1", "2025-03-02", "2025-03-01", "2025-03-01.2"];
+        stems.sort_by(|a, b| log_file_key(b).cmp(&log_file_key(a)));
+        assert_eq!(
+            stems,
+            vec!["2025-03-02", "2025-03-01", "2025-03-01.2", "2025-03-01.1"]
+        );
+
+        let range = LogTimeRange {
+            from: Some(entry(None).timestamp),
+            to: None,
+        };
+        assert!(range.overlaps_day("2025-03-01"));
+        assert!(!range.overlaps_day("2025-02-28"));
+        assert!(!range.contains(entry(None).timestamp - chrono::Duration::seconds(1)));
+    }
+
+    #[test]
+    fn test_redact_settings() {
+        let mut settings = serde_json::to_value(crate::models::AppSettings {
+            recent_files: vec!["/home/me/m31.json".to_string()],
+            remote_api: crate::models::RemoteApiSettings {
+                token: "secret".to_string(),
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .unwrap();
+        settings["observingSites"] = json!([{ "name": "Home", "latitude": 51.5 }]);
+
+        redact_settings(&mut settings);
+
+        assert_eq!(settings["recentFiles"], json!("<redacted>"));
+        assert_eq!(settings["pinnedFiles"], json!("<redacted>"));
+        assert_eq!(settings["recentFileMetadata"], json!("<redacted>"));
+        assert_eq!(settings["remoteApi"]["token"], json!("<redacted>"));
+        assert_eq!(settings["remoteApi"]["port"], json!(8765));
+        assert_eq!(
+            settings["observingSites"][0]["latitude"],
+            json!("<redacted>")
+        );
+        assert_eq!(settings["observingSites"][0]["name"], json!("Home"));
+        assert_eq!(settings["theme"], json!("system"));
+    }
+}
@@ -3,20 +3,74 @@
 //! This module contains all the business logic for sequence processing,
 //! serialization, validation, and file operations.
 
+pub mod alpaca;
+pub mod altitude_curve;
 pub mod astronomy;
+pub mod auto_save;
 pub mod backup_service;
+pub mod builtin_templates;
 pub mod calculator;
 pub mod clipboard_service;
+pub mod csv_io;
+pub mod dark_calendar;
+pub mod dso_catalog;
+pub mod edit_journal;
+pub mod editor_edit;
+pub mod ephemeris;
 pub mod export_service;
 pub mod file_service;
+pub mod file_watcher;
+pub mod filter_offsets;
+pub mod fits_header;
+pub mod flat_calculator;
+pub mod image_library;
+pub mod import_preview;
 pub mod import_service;
+pub mod job_queue;
 pub mod log_service;
+pub mod night_split;
+pub mod nina_remote;
 pub mod nina_serializer;
+pub mod nina_type_registry;
+pub mod observing_constraints;
+pub mod path_guard;
+pub mod phd2;
+pub mod remote_api;
+pub mod runtime_uncertainty;
+pub mod sampling;
+pub mod satellite;
+pub mod sequence_archive;
+pub mod sequence_convert;
+pub mod sequence_edit;
+pub mod sequence_format;
+pub mod sequence_library;
+pub mod sequence_normalize;
 pub mod sequence_optimizer;
+pub mod sequence_progress;
+pub mod sequence_search;
+pub mod sequence_session;
+pub mod sequence_statistics;
 pub mod serializer;
 pub mod settings_service;
+pub mod sgp_import;
+pub mod simulator;
+pub mod slew_calibration;
+pub mod slew_route;
+pub mod stable_id;
+pub mod target_recommendation;
+pub mod target_season;
+pub mod target_sync;
+pub mod template_bundle;
 pub mod template_service;
+pub mod timeline;
+pub mod units;
 pub mod validator;
+pub mod voyager_robotarget;
+pub mod weather;
+pub mod xisf_header;
+pub mod xml_tree;
+pub mod zip_reader;
+pub mod zip_writer;
 
 #[cfg(test)]
 mod astronomy_tests;
@@ -46,13 +100,16 @@ pub use serializer::{
     serialize_simple_sequence_json, SerializerError,
 };
 pub use settings_service::{
-    add_recent_file, clear_recent_files, get_estimated_download_time, get_language,
-    get_last_directory, get_recent_files, get_settings, get_theme, get_window_state, load_settings,
-    remove_recent_file, save_settings, save_window_state, set_estimated_download_time,
-    set_language, set_last_directory, set_theme, update_settings,
+    add_recent_file, clear_recent_files, delete_equipment_profile, delete_filter_set,
+    get_active_equipment_profile, get_active_filter_set, get_equipment_profile,
+    get_estimated_download_time, get_language, get_last_directory, get_recent_files, get_settings,
+    get_theme, get_window_state, list_equipment_profiles, list_filter_sets, list_filters,
+    load_settings, remove_recent_file, save_equipment_profile, save_filter_set, save_settings,
+    save_window_state, set_active_equipment_profile, set_active_filter_set,
+    set_estimated_download_time, set_language, set_last_directory, set_theme, update_settings,
 };
 pub use validator::{
     get_short_type_name, get_type_category, is_container_type, validate_coordinates,
-    validate_editor_sequence, validate_nina_json, validate_simple_exposure,
-    validate_simple_sequence, validate_simple_target,
+    validate_editor_sequence, validate_nina_json, validate_sequence_filters,
+    validate_simple_exposure, validate_simple_sequence, validate_simple_target,
 };
@@ -0,0 +1,442 @@
+//! Multi-night sequence partitioning
+//!
+//! Spreads the remaining frames of a sequence over a range of nights. Each
+//! night's astronomical darkness is cut into slots and targets claim the
+//! slots in which they meet their constraints, higher-priority and less
+//! often observable targets first, so targets sharing a part of the sky
+//! cannot both count on the same hours. Claimed time becomes whole frames,
+//! split between a target's exposure rows by their remaining counts.
+
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::models::{SimpleSequence, SimpleTarget};
+use crate::services::astronomy::ObserverLocation;
+use crate::services::dark_calendar::astronomical_night;
+use crate::services::job_queue::JobHandle;
+use crate::services::observing_constraints::constraints_met_at;
+use crate::services::sequence_optimizer::DEFAULT_MIN_ALTITUDE;
+
+/// Length of the slots a night is cut into
+const SLOT_MINUTES: i64 = 5;
+
+/// Longest date range that can be split
+const MAX_NIGHTS: i64 = 62;
+
+/// One night's share of the sequence
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NightPlan {
+    pub date: String,
+    pub dark_start: DateTime<Utc>,
+    pub dark_end: DateTime<Utc>,
+    pub dark_hours: f64,
+    pub scheduled_hours: f64,
+    /// Targets in the order they are first observable, with their counts
+    /// set so only the night's frames remain
+    pub sequence: SimpleSequence,
+}
+
+/// Frames that did not fit into any night
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LeftoverTarget {
+    pub target_id: String,
+    pub target_name: String,
+    pub remaining_frames: i32,
+    pub remaining_hours: f64,
+    pub reason: String,
+}
+
+/// Result of splitting a sequence across nights
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NightSplitResult {
+    pub nights: Vec<NightPlan>,
+    pub leftovers: Vec<LeftoverTarget>,
+    pub warnings: Vec<String>,
+}
+
+/// Exposure rows of a target still to be spread
+struct TargetWork<'a> {
+    target: &'a SimpleTarget,
+    /// Exposure index, seconds per frame and frames left to place
+    rows: Vec<(usize, f64, i32)>,
+    /// Frames placed on earlier nights, per exposure
+    placed: Vec<i32>,
+    observable_nights: usize,
+}
+
+impl TargetWork<'_> {
+    fn remaining_frames(&self) -> i32 {
+        self.rows.iter().map(|r| r.2).sum()
+    }
+
+    fn remaining_seconds(&self) -> f64 {
+        self.rows.iter().map(|r| r.2 as f64 * r.1).sum()
+    }
+}
+
+/// Whole frames per row fitting into `seconds`: every row gets the same
+/// share of its remaining frames, then single frames top up the rows
+/// furthest behind while they fit
+fn frames_for(rows: &[(usize, f64, i32)], seconds: f64) -> Vec<i32> {
+    let total: f64 = rows.iter().map(|r| r.2 as f64 * r.1).sum();
+    if total <= 0.0 || seconds <= 0.0 {
+        return vec![0; rows.len()];
+    }
+    let share = (seconds / total).min(1.0);
+    let mut frames: Vec<i32> = rows
+        .iter()
+        .map(|r| (r.2 as f64 * share).floor() as i32)
+        .collect();
+    let mut spent: f64 = rows.iter().zip(&frames).map(|(r, f)| *f as f64 * r.1).sum();
+
+    loop {
+        let next = (0..rows.len())
+            .filter(|&i| frames[i] < rows[i].2 && spent + rows[i].1 <= seconds)
+            .min_by(|&a, &b| {
+                let behind = |i: usize| frames[i] as f64 / rows[i].2 as f64;
+                behind(a).total_cmp(&behind(b))
+            });
+        let Some(i) = next else {
+            break;
+        };
+        frames[i] += 1;
+        spent += rows[i].1;
+    }
+    frames
+}
+
+/// Copy of `target` with only `frames` left to take, counting frames
+/// placed on earlier nights as done
+fn night_target(work: &TargetWork, frames: &[i32]) -> SimpleTarget {
+    let mut target = work.target.clone();
+    for ((row, tonight), placed) in work.rows.iter().zip(frames).zip(&work.placed) {
+        let exposure = &mut target.exposures[row.0];
+        exposure.progress_count += placed;
+        exposure.total_count = exposure.progress_count + tonight;
+    }
+    target
+}
+
+/// Partition the remaining frames of `sequence` over the nights from
+/// `start` to `end`, producing one sequence per night that gets any
+/// frames and a report of the frames that fit nowhere
+pub fn split_sequence_across_nights(
+    sequence: &SimpleSequence,
+    location: &ObserverLocation,
+    start: NaiveDate,
+    end: NaiveDate,
+    job: &JobHandle,
+) -> Result<NightSplitResult, String> {
+    let night_count = (end - start).num_days() + 1;
+    if night_count < 1 {
+        return Err("End date is before start date".to_string());
+    }
+    if night_count > MAX_NIGHTS {
+        return Err(format!("At most {} nights can be planned", MAX_NIGHTS));
+    }
+
+    let download_time = sequence.estimated_download_time;
+    let step = Duration::minutes(SLOT_MINUTES);
+    let step_seconds = step.num_seconds() as f64;
+    let mut warnings = Vec::new();
+    let mut nights = Vec::new();
+
+    let mut work: Vec<TargetWork> = sequence
+        .targets
+        .iter()
+        .map(|target| {
+            let rows: Vec<(usize, f64, i32)> = target
+                .exposures
+                .iter()
+                .enumerate()
+                .filter(|(_, e)| e.enabled && e.remaining() > 0)
+                .map(|(index, e)| (index, e.exposure_time + download_time, e.remaining()))
+                .filter(|row| row.1 > 0.0)
+                .collect();
+            TargetWork {
+                target,
+                placed: vec![0; rows.len()],
+                rows,
+                observable_nights: 0,
+            }
+        })
+        .filter(|w| !w.rows.is_empty())
+        .collect();
+
+    for (night_index, date) in start.iter_days().take(night_count as usize).enumerate() {
+        job.checkpoint()?;
+        job.report(
+            night_index,
+            night_count as usize,
+            Some(date.format("%Y-%m-%d").to_string()),
+        );
+        let Some((dusk, dawn)) = astronomical_night(location, date) else {
+            warnings.push(format!("No astronomical darkness on the night of {}", date));
+            continue;
+        };
+        let slots = ((dawn - dusk).num_seconds() as f64 / step_seconds).floor() as usize;
+
+        // Slots in which each target meets its constraints
+        let usable: Vec<Vec<bool>> = work
+            .par_iter()
+            .map(|w| {
+                if w.remaining_frames() == 0 {
+                    return Vec::new();
+                }
+                (0..slots)
+                    .map(|slot| {
+                        let mid = dusk + step * slot as i32 + step / 2;
+                        constraints_met_at(w.target, location, mid, DEFAULT_MIN_ALTITUDE)
+                    })
+                    .collect()
+            })
+            .collect();
+
+        let mut order: Vec<usize> = (0..work.len())
+            .filter(|&i| usable[i].contains(&true))
+            .collect();
+        for &i in &order {
+            work[i].observable_nights += 1;
+        }
+        order.sort_by_key(|&i| {
+            (
+                std::cmp::Reverse(work[i].target.priority),
+                usable[i].iter().filter(|&&u| u).count(),
+            )
+        });
+
+        let mut free = vec![true; slots];
+        // (first slot, target index, frames per row)
+        let mut placed: Vec<(usize, usize, Vec<i32>)> = Vec::new();
+        let mut scheduled_seconds = 0.0;
+
+        for i in order {
+            let delay = work[i].target.delay as f64;
+            let needed = work[i].remaining_seconds() + delay;
+            let claimable: Vec<usize> = (0..slots).filter(|&s| free[s] && usable[i][s]).collect();
+            let claimed =
+                &claimable[..claimable.len().min((needed / step_seconds).ceil() as usize)];
+
+            let frames = frames_for(&work[i].rows, claimed.len() as f64 * step_seconds - delay);
+            if frames.iter().all(|&f| f == 0) {
+                continue;
+            }
+            for &slot in claimed {
+                free[slot] = false;
+            }
+
+            scheduled_seconds += delay;
+            for (row, &tonight) in work[i].rows.iter().zip(&frames) {
+                scheduled_seconds += tonight as f64 * row.1;
+            }
+            placed.push((claimed[0], i, frames));
+        }
+
+        if placed.is_empty() {
+            continue;
+        }
+        placed.sort_by_key(|p| p.0);
+
+        let mut night = SimpleSequence::new(format!("{} ({})", sequence.title, date));
+        night.start_options = sequence.start_options.clone();
+        night.end_options = sequence.end_options.clone();
+        night.estimated_download_time = download_time;
+        night.targets = placed
+            .iter()
+            .map(|(_, i, frames)| night_target(&work[*i], frames))
+            .collect();
+        night.selected_target_id = night.targets.first().map(|t| t.id.clone());
+        night.active_target_id = night.selected_target_id.clone();
+
+        for (_, i, frames) in &placed {
+            let w = &mut work[*i];
+            for ((row, done), tonight) in w.rows.iter_mut().zip(&mut w.placed).zip(frames) {
+                row.2 -= tonight;
+                *done += tonight;
+            }
+        }
+
+        nights.push(NightPlan {
+            date: date.format("%Y-%m-%d").to_string(),
+            dark_start: dusk,
+            dark_end: dawn,
+            dark_hours: round_hours((dawn - dusk).num_seconds() as f64 / 3600.0),
+            scheduled_hours: round_hours(scheduled_seconds / 3600.0),
+            sequence: night,
+        });
+    }
+
+    let leftovers = work
+        .iter()
+        .filter(|w| w.remaining_frames() > 0)
+        .map(|w| LeftoverTarget {
+            target_id: w.target.id.clone(),
+            target_name: w.target.target_name.clone(),
+            remaining_frames: w.remaining_frames(),
+            remaining_hours: round_hours(w.remaining_seconds() / 3600.0),
+            reason: if w.observable_nights == 0 {
+                "Never within its constraints during darkness in the date range".to_string()
+            } else {
+                format!(
+                    "Not enough free dark time on the {} night(s) it is observable",
+                    w.observable_nights
+                )
+            },
+        })
+        .collect();
+
+    Ok(NightSplitResult {
+        nights,
+        leftovers,
+        warnings,
+    })
+}
+
+fn round_hours(hours: f64) -> f64 {
+    (hours * 100.0).round() / 100.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Coordinates, SimpleExposure};
+
+    fn new_york() -> ObserverLocation {
+        ObserverLocation {
+            latitude: 40.7128,
+            longitude: -74.0060,
+            elevation: 10.0,
+            timezone_offset: -5,
+            ..Default::default()
+        }
+    }
+
+    fn target(name: &str, ra: f64, dec: f64, frames: i32) -> SimpleTarget {
+        SimpleTarget {
+            name: name.to_string(),
+            target_name: name.to_string(),
+            coordinates: Coordinates::from_decimal(ra, dec),
+            exposures: vec![
+                SimpleExposure {
+                    exposure_time: 300.0,
+                    total_count: frames,
+                    ..Default::default()
+                },
+                SimpleExposure {
+                    exposure_time: 300.0,
+                    total_count: frames,
+                    progress_count: 2,
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        }
+    }
+
+    fn october(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 10, day).unwrap()
+    }
+
+    #[test]
+    fn test_split_across_nights() {
+        let mut sequence = SimpleSequence::new("Autumn");
+        sequence.estimated_download_time = 0.0;
+        // M31 for 40 hours, far more than one night; Omega Centauri never
+        // rises above 20° from New York
+        sequence.targets = vec![
+            target("M31", 0.71, 41.27, 240),
+            target("Omega Centauri", 13.45, -47.48, 10),
+        ];
+
+        let result = split_sequence_across_nights(
+            &sequence,
+            &new_york(),
+            october(1),
+            october(3),
+            &JobHandle::detached(),
+        )
+        .unwrap();
+        assert_eq!(result.nights.len(), 3);
+
+        let mut placed = 0;
+        for night in &result.nights {
+            assert!(night.scheduled_hours <= night.dark_hours);
+            assert_eq!(night.sequence.targets.len(), 1);
+            let m31 = &night.sequence.targets[0];
+            assert_eq!(m31.id, sequence.targets[0].id);
+            // Both rows advance together
+            let counts: Vec<i32> = m31.exposures.iter().map(|e| e.remaining()).collect();
+            assert!((counts[0] - counts[1]).abs() <= 1);
+            assert_eq!(m31.exposures[0].progress_count, placed);
+            placed += counts[0];
+        }
+
+        assert_eq!(result.leftovers.len(), 2);
+        let m31 = &result.leftovers[0];
+        assert!(m31.remaining_frames > 0);
+        assert!(m31.reason.contains("3 night"));
+        assert!(result.leftovers[1].reason.starts_with("Never"));
+    }
+
+    #[test]
+    fn test_split_shares_the_same_sky() {
+        // Two targets in the same spot compete for the same slots
+        let mut sequence = SimpleSequence::new("Shared");
+        sequence.estimated_download_time = 0.0;
+        sequence.targets = vec![target("A", 0.71, 41.27, 60), target("B", 0.75, 41.0, 60)];
+
+        let result = split_sequence_across_nights(
+            &sequence,
+            &new_york(),
+            october(1),
+            october(1),
+            &JobHandle::detached(),
+        )
+        .unwrap();
+        let night = &result.nights[0];
+        let frames: i32 = night
+            .sequence
+            .targets
+            .iter()
+            .flat_map(|t| &t.exposures)
+            .map(|e| e.remaining())
+            .sum();
+        assert!(frames as f64 * 300.0 / 3600.0 <= night.dark_hours);
+    }
+
+    #[test]
+    fn test_split_rejects_bad_ranges() {
+        let sequence = SimpleSequence::default();
+        assert!(split_sequence_across_nights(
+            &sequence,
+            &new_york(),
+            october(3),
+            october(1),
+            &JobHandle::detached()
+        )
+        .is_err());
+        let far = NaiveDate::from_ymd_opt(2025, 10, 1).unwrap();
+        assert!(split_sequence_across_nights(
+            &sequence,
+            &new_york(),
+            october(1),
+            far,
+            &JobHandle::detached()
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_frames_for() {
+        let rows = [(0, 100.0, 10), (1, 50.0, 10)];
+        assert_eq!(frames_for(&rows, 1500.0), [10, 10]);
+        assert_eq!(frames_for(&rows, 750.0), [5, 5]);
+        assert_eq!(frames_for(&rows, 0.0), [0, 0]);
+        // Four of each take 600 s; the last 50 s buys one short frame
+        assert_eq!(frames_for(&rows, 650.0), [4, 5]);
+    }
+}
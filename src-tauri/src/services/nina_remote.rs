@@ -0,0 +1,390 @@
+//! Client for NINA's Advanced API plugin
+//!
+//! Connects to a running NINA instance with the Advanced API web plugin
+//! enabled, to push the edited sequence into NINA, pull the sequence NINA
+//! has loaded and follow the equipment state. While connected the
+//! equipment is polled and every update is passed to the status listener.
+//!
+//! The plugin wraps every answer as
+//! `{"Response": ..., "Error": "", "StatusCode": 200, "Success": true}`.
+
+use chrono::{DateTime, Utc};
+use once_cell::sync::Lazy;
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use crate::models::EditorSequence;
+use crate::services::{nina_serializer, nina_type_registry};
+use crate::state::{AppState, SharedState};
+
+/// Default port of the Advanced API plugin
+pub const DEFAULT_NINA_API_PORT: u16 = 1888;
+
+/// Event emitted to the frontend with each equipment update
+pub const NINA_EQUIPMENT_EVENT: &str = "nina://equipment";
+
+/// Devices whose state is reported, by their API names
+pub const EQUIPMENT_DEVICES: [&str; 9] = [
+    "camera",
+    "mount",
+    "focuser",
+    "filterwheel",
+    "guider",
+    "rotator",
+    "dome",
+    "weather",
+    "safetymonitor",
+];
+
+/// How often the equipment is polled while connected
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Connected NINA instance
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NinaConnection {
+    /// API root, e.g. `http://observatory:1888/v2/api`
+    pub base_url: String,
+    /// Advanced API plugin version
+    pub version: Option<String>,
+    pub connected_at: DateTime<Utc>,
+}
+
+/// State of one device
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeviceStatus {
+    pub device: String,
+    pub connected: bool,
+    pub name: Option<String>,
+    /// Why the state could not be read
+    pub error: Option<String>,
+    /// Full device info as reported by NINA
+    pub info: Value,
+}
+
+/// State of all reported devices
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NinaEquipmentStatus {
+    pub base_url: String,
+    pub devices: Vec<DeviceStatus>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Receives equipment updates, e.g. to forward them to the frontend
+pub type EquipmentListener = Box<dyn Fn(&NinaEquipmentStatus) + Send + Sync>;
+
+struct ConnectionState {
+    connection: NinaConnection,
+    /// Bumped on every connect to stop earlier pollers
+    generation: u64,
+}
+
+/// Connection and equipment listener, part of the
+/// [`AppState`](crate::state::AppState)
+#[derive(Default)]
+pub struct NinaRemoteState {
+    connection: RwLock<Option<ConnectionState>>,
+    generation: AtomicU64,
+    listener: RwLock<Option<EquipmentListener>>,
+}
+
+static CLIENT: Lazy<reqwest::Client> = Lazy::new(|| {
+    reqwest::Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .build()
+        .unwrap_or_default()
+});
+
+/// Install or remove the equipment listener
+pub fn set_equipment_listener(state: &AppState, listener: Option<EquipmentListener>) {
+    *state.nina.listener.write() = listener;
+}
+
+fn notify(state: &AppState, status: &NinaEquipmentStatus) {
+    if let Some(listener) = state.nina.listener.read().as_ref() {
+        listener(status);
+    }
+}
+
+/// API root for a host given as a name, `host:port` or a full URL
+pub fn base_url(host: &str, port: Option<u16>) -> Result<String, String> {
+    let host = host.trim().trim_end_matches('/');
+    if host.is_empty() {
+        return Err("NINA host is empty".to_string());
+    }
+
+    let (scheme, rest) = host.split_once("://").unwrap_or(("http", host));
+    let (authority, path) = rest.split_once('/').unwrap_or((rest, ""));
+    let has_port = authority
+        .rsplit_once(':')
+        .is_some_and(|(_, p)| p.parse::<u16>().is_ok());
+    let authority = if has_port {
+        authority.to_string()
+    } else {
+        format!("{}:{}", authority, port.unwrap_or(DEFAULT_NINA_API_PORT))
+    };
+    let path = if path.is_empty() { "v2/api" } else { path };
+    Ok(format!("{}://{}/{}", scheme, authority, path))
+}
+
+/// Unwrap the plugin's response envelope
+pub fn unwrap_response(body: &str) -> Result<Value, String> {
+    #[derive(Deserialize)]
+    #[serde(rename_all = "PascalCase")]
+    struct Envelope {
+        #[serde(default)]
+        response: Value,
+        #[serde(default)]
+        error: String,
+        success: Option<bool>,
+    }
+
+    let envelope: Envelope =
+        serde_json::from_str(body).map_err(|e| format!("Unexpected response from NINA: {}", e))?;
+    if envelope.success == Some(false) || !envelope.error.is_empty() {
+        let error = if envelope.error.is_empty() {
+            "NINA reported a failure".to_string()
+        } else {
+            envelope.error
+        };
+        return Err(error);
+    }
+    Ok(envelope.response)
+}
+
+async fn send(request: reqwest::RequestBuilder, url: &str) -> Result<Value, String> {
+    let response = request
+        .send()
+        .await
+        .map_err(|e| format!("Failed to reach NINA at {}: {}", url, e))?;
+    let status = response.status();
+    let body = response
+        .text()
+        .await
+        .map_err(|e| format!("Failed to read NINA response: {}", e))?;
+
+    // Failures usually come with an envelope explaining them
+    match unwrap_response(&body) {
+        Err(_) if !status.is_success() => Err(format!("NINA returned HTTP {}", status)),
+        result => result,
+    }
+}
+
+async fn get(base_url: &str, path: &str) -> Result<Value, String> {
+    let url = format!("{}/{}", base_url, path);
+    send(CLIENT.get(&url), &url).await
+}
+
+fn connected_base_url(state: &AppState) -> Result<String, String> {
+    state
+        .nina
+        .connection
+        .read()
+        .as_ref()
+        .map(|current| current.connection.base_url.clone())
+        .ok_or_else(|| "Not connected to NINA".to_string())
+}
+
+/// Connect to NINA, replacing an earlier connection, and start polling
+/// the equipment
+pub async fn connect(
+    state: &SharedState,
+    host: &str,
+    port: Option<u16>,
+) -> Result<NinaConnection, String> {
+    let base_url = base_url(host, port)?;
+    let version = get(&base_url, "version").await?;
+    let connection = NinaConnection {
+        base_url,
+        version: match version {
+            Value::String(version) => Some(version),
+            Value::Null => None,
+            other => Some(other.to_string()),
+        },
+        connected_at: Utc::now(),
+    };
+
+    let generation = state.nina.generation.fetch_add(1, Ordering::SeqCst) + 1;
+    *state.nina.connection.write() = Some(ConnectionState {
+        connection: connection.clone(),
+        generation,
+    });
+    tokio::spawn(poll(state.clone(), generation));
+    log::info!("Connected to NINA at {}", connection.base_url);
+    Ok(connection)
+}
+
+/// Forget the connection and stop polling
+pub fn disconnect(state: &AppState) {
+    if let Some(previous) = state.nina.connection.write().take() {
+        log::info!("Disconnected from NINA at {}", previous.connection.base_url);
+    }
+}
+
+/// Current connection, if any
+pub fn connection(state: &AppState) -> Option<NinaConnection> {
+    state
+        .nina
+        .connection
+        .read()
+        .as_ref()
+        .map(|current| current.connection.clone())
+}
+
+/// Load `sequence` into NINA's advanced sequencer
+pub async fn push_sequence(state: &AppState, sequence: &EditorSequence) -> Result<(), String> {
+    let base_url = connected_base_url(state)?;
+    let json = nina_serializer::export_to_nina(sequence, &nina_type_registry::plugin_types(state))?;
+    let url = format!("{}/sequence/load", base_url);
+    let request = CLIENT
+        .post(&url)
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .body(json);
+    send(request, &url).await?;
+    Ok(())
+}
+
+/// Fetch the sequence loaded in NINA
+pub async fn pull_current_sequence(state: &AppState) -> Result<EditorSequence, String> {
+    let base_url = connected_base_url(state)?;
+    let json = match get(&base_url, "sequence/json").await? {
+        Value::String(json) => json,
+        Value::Null => return Err("NINA has no sequence loaded".to_string()),
+        other => other.to_string(),
+    };
+    let stable_ids = state.settings.read().stable_nina_ids;
+    let plugins = nina_type_registry::plugin_types(state);
+    nina_serializer::import_from_nina(&json, stable_ids, &plugins)
+        .map_err(|e| format!("Failed to read NINA's sequence: {}", e))
+}
+
+/// Device state from an equipment info response
+pub fn device_status(device: &str, info: Result<Value, String>) -> DeviceStatus {
+    match info {
+        Ok(info) => DeviceStatus {
+            device: device.to_string(),
+            connected: info
+                .get("Connected")
+                .and_then(Value::as_bool)
+                .unwrap_or(false),
+            name: ["DisplayName", "Name"]
+                .iter()
+                .filter_map(|key| info.get(*key).and_then(Value::as_str))
+                .find(|name| !name.is_empty())
+                .map(str::to_string),
+            error: None,
+            info,
+        },
+        Err(error) => DeviceStatus {
+            device: device.to_string(),
+            connected: false,
+            name: None,
+            error: Some(error),
+            info: Value::Null,
+        },
+    }
+}
+
+/// Read the state of all reported devices
+pub async fn get_equipment_status(state: &AppState) -> Result<NinaEquipmentStatus, String> {
+    let base_url = connected_base_url(state)?;
+    fetch_equipment_status(&base_url).await
+}
+
+async fn fetch_equipment_status(base_url: &str) -> Result<NinaEquipmentStatus, String> {
+    let requests = EQUIPMENT_DEVICES.iter().map(|device| async move {
+        let info = get(base_url, &format!("equipment/{}/info", device)).await;
+        device_status(device, info)
+    });
+    let devices = futures::future::join_all(requests).await;
+
+    // Every device failing means NINA itself is unreachable
+    if devices.iter().all(|d| d.error.is_some()) {
+        return Err(devices[0].error.clone().unwrap_or_default());
+    }
+
+    Ok(NinaEquipmentStatus {
+        base_url: base_url.to_string(),
+        devices,
+        updated_at: Utc::now(),
+    })
+}
+
+async fn poll(state: SharedState, generation: u64) {
+    loop {
+        tokio::time::sleep(POLL_INTERVAL).await;
+
+        let base_url = match state.nina.connection.read().as_ref() {
+            Some(current) if current.generation == generation => {
+                current.connection.base_url.clone()
+            }
+            _ => return,
+        };
+        if state.nina.listener.read().is_none() {
+            continue;
+        }
+
+        match fetch_equipment_status(&base_url).await {
+            Ok(status) => notify(&state, &status),
+            Err(e) => log::debug!("Failed to poll NINA equipment: {}", e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_base_url() {
+        assert_eq!(
+            base_url("observatory", None).unwrap(),
+            "http://observatory:1888/v2/api"
+        );
+        assert_eq!(
+            base_url("192.168.1.20:2000", Some(1888)).unwrap(),
+            "http://192.168.1.20:2000/v2/api"
+        );
+        assert_eq!(
+            base_url("https://nina.local/v2/api/", Some(443)).unwrap(),
+            "https://nina.local:443/v2/api"
+        );
+        assert!(base_url("  ", None).is_err());
+    }
+
+    #[test]
+    fn test_unwrap_response() {
+        let ok =
+            r#"{"Response":"2.1.0.0","Error":"","StatusCode":200,"Success":true,"Type":"API"}"#;
+        assert_eq!(unwrap_response(ok).unwrap(), Value::from("2.1.0.0"));
+
+        let failed =
+            r#"{"Response":"","Error":"Camera not connected","StatusCode":409,"Success":false}"#;
+        assert_eq!(unwrap_response(failed).unwrap_err(), "Camera not connected");
+        assert!(unwrap_response("<html>").is_err());
+    }
+
+    #[test]
+    fn test_device_status() {
+        let info = serde_json::json!({
+            "Connected": true,
+            "Name": "ZWO ASI2600MM Pro",
+            "DisplayName": "",
+            "Temperature": -10.0,
+        });
+        let status = device_status("camera", Ok(info));
+        assert!(status.connected);
+        assert_eq!(status.name.as_deref(), Some("ZWO ASI2600MM Pro"));
+
+        let status = device_status("mount", Err("timeout".to_string()));
+        assert!(!status.connected);
+        assert_eq!(status.error.as_deref(), Some("timeout"));
+    }
+}
@@ -1,27 +1,45 @@
 //! NINA sequence format serializer
 //!
 //! Handles conversion between editor format and NINA JSON format
+//!
+//! Exported `$id`s are numbered from 0 on every export, so unchanged
+//! sequences export identically. Imported entities get new random ids,
+//! or with the `stable_nina_ids` setting ids derived from their position,
+//! which stay the same each time a file is imported.
 
 use serde_json::{json, Value};
+use std::cell::Cell;
 use std::collections::HashMap;
-use std::sync::atomic::{AtomicU32, Ordering};
 
 use crate::models::{EditorCondition, EditorSequence, EditorSequenceItem, EditorTrigger};
+use crate::services::nina_type_registry::{self, NinaTypeSchema};
+use crate::services::stable_id;
 
-static NINA_ID_COUNTER: AtomicU32 = AtomicU32::new(0);
+thread_local! {
+    static NINA_ID_COUNTER: Cell<u32> = const { Cell::new(0) };
+}
 
 /// Reset NINA ID counter
 pub fn reset_nina_ids() {
-    NINA_ID_COUNTER.store(0, Ordering::SeqCst);
+    NINA_ID_COUNTER.with(|counter| counter.set(0));
 }
 
 /// Get next NINA ID
 fn next_nina_id() -> String {
-    NINA_ID_COUNTER.fetch_add(1, Ordering::SeqCst).to_string()
+    NINA_ID_COUNTER
+        .with(|counter| {
+            let id = counter.get();
+            counter.set(id + 1);
+            id
+        })
+        .to_string()
 }
 
 /// Export editor sequence to NINA JSON format
-pub fn export_to_nina(sequence: &EditorSequence) -> Result<String, String> {
+pub fn export_to_nina(
+    sequence: &EditorSequence,
+    plugins: &[NinaTypeSchema],
+) -> Result<String, String> {
     reset_nina_ids();
 
     let root_id = next_nina_id();
@@ -32,6 +50,7 @@ pub fn export_to_nina(sequence: &EditorSequence) -> Result<String, String> {
         "Start Area",
         "NINA.Sequencer.Container.StartAreaContainer, NINA.Sequencer",
         &root_id,
+        plugins,
     );
 
     let target_container = create_area_container(
@@ -39,6 +58,7 @@ pub fn export_to_nina(sequence: &EditorSequence) -> Result<String, String> {
         "Target Area",
         "NINA.Sequencer.Container.TargetAreaContainer, NINA.Sequencer",
         &root_id,
+        plugins,
     );
 
     let end_container = create_area_container(
@@ -46,6 +66,7 @@ pub fn export_to_nina(sequence: &EditorSequence) -> Result<String, String> {
         "End Area",
         "NINA.Sequencer.Container.EndAreaContainer, NINA.Sequencer",
         &root_id,
+        plugins,
     );
 
     // Create root container
@@ -68,19 +89,90 @@ pub fn export_to_nina(sequence: &EditorSequence) -> Result<String, String> {
             "$type": "System.Collections.ObjectModel.ObservableCollection`1[[NINA.Sequencer.Conditions.ISequenceCondition, NINA.Sequencer]], System.ObjectModel",
             "$values": []
         },
-        "Triggers": create_triggers_collection(&sequence.global_triggers, &root_id),
+        "Triggers": create_triggers_collection(&sequence.global_triggers, &root_id, plugins),
         "Parent": null
     });
 
     serde_json::to_string_pretty(&root).map_err(|e| format!("Failed to serialize NINA JSON: {}", e))
 }
 
+/// Export items as a NINA JSON fragment for the system clipboard: a single
+/// item object, or an array of them. Parents are left null since the items
+/// are detached from any container.
+pub fn export_items_fragment(
+    items: &[EditorSequenceItem],
+    plugins: &[NinaTypeSchema],
+) -> Result<String, String> {
+    reset_nina_ids();
+
+    let mut values: Vec<Value> = items
+        .iter()
+        .map(|item| {
+            let mut value = create_nina_item(item, "", plugins);
+            value["Parent"] = Value::Null;
+            value
+        })
+        .collect();
+    let fragment = if values.len() == 1 {
+        values.remove(0)
+    } else {
+        Value::Array(values)
+    };
+
+    serde_json::to_string_pretty(&fragment)
+        .map_err(|e| format!("Failed to serialize NINA JSON: {}", e))
+}
+
+/// Import items from a NINA JSON fragment: a single item or container, an
+/// array of items, an item collection (`$values`) or a whole sequence,
+/// whose area items are returned in order
+pub fn import_items_fragment(
+    json_str: &str,
+    plugins: &[NinaTypeSchema],
+) -> Result<Vec<EditorSequenceItem>, String> {
+    let data: Value =
+        serde_json::from_str(json_str).map_err(|e| format!("Failed to parse NINA JSON: {}", e))?;
+
+    let items = match &data {
+        Value::Array(values) => values
+            .iter()
+            .filter_map(|v| import_item(v, plugins))
+            .collect(),
+        Value::Object(obj) => match obj.get("$type").and_then(|v| v.as_str()) {
+            Some(type_str) if type_str.contains("SequenceRootContainer") => {
+                let sequence = import_root_container(&data, plugins)?;
+                sequence
+                    .start_items
+                    .into_iter()
+                    .chain(sequence.target_items)
+                    .chain(sequence.end_items)
+                    .collect()
+            }
+            Some(_) if obj.contains_key("$values") => import_container_items(
+                &json!({
+                    "Items": data
+                }),
+                plugins,
+            )?,
+            Some(_) => import_item(&data, plugins).into_iter().collect(),
+            None => Vec::new(),
+        },
+        _ => Vec::new(),
+    };
+
+    if items.is_empty() {
+        return Err("No NINA sequence items found".to_string());
+    }
+    Ok(items)
+}
+
 /// Create area container
 fn create_area_container(
     items: &[EditorSequenceItem],
     name: &str,
     type_name: &str,
     parent_id: &str,
+    plugins: &[NinaTypeSchema],
 ) -> Value {
     let container_id = next_nina_id();
 
@@ -95,7 +187,7 @@ fn create_area_container(
         "Items": {
             "$id": next_nina_id(),
             "$type": "System.Collections.ObjectModel.ObservableCollection`1[[NINA.Sequencer.SequenceItem.ISequenceItem, NINA.Sequencer]], System.ObjectModel",
-            "$values": items.iter().map(|item| create_nina_item(item, &container_id)).collect::<Vec<_>>()
+            "$values": items.iter().map(|item| create_nina_item(item, &container_id, plugins)).collect::<Vec<_>>()
         },
         "Conditions": {
             "$id": next_nina_id(),
@@ -114,11 +206,13 @@ fn create_area_container(
 }
 
 /// Create NINA item from editor item
-fn create_nina_item(item: &EditorSequenceItem, parent_id: &str) -> Value {
+fn create_nina_item(
+    item: &EditorSequenceItem,
+    parent_id: &str,
+    plugins: &[NinaTypeSchema],
+) -> Value {
     let item_id = next_nina_id();
-    let is_container = item.item_type.contains("Container")
-        || item.item_type.contains("SmartExposure")
-        || item.item_type.contains("InstructionSet");
+    let is_container = nina_type_registry::is_container_type(&item.item_type, plugins);
 
     let mut nina_item = json!({
         "$id": item_id,
@@ -156,7 +250,7 @@ fn create_nina_item(item: &EditorSequenceItem, parent_id: &str) -> Value {
                 .map(|items| {
                     items
                         .iter()
-                        .map(|i| create_nina_item(i, &item_id))
+                        .map(|i| create_nina_item(i, &item_id, plugins))
                         .collect::<Vec<_>>()
                 })
                 .unwrap_or_default();
@@ -192,7 +286,7 @@ fn create_nina_item(item: &EditorSequenceItem, parent_id: &str) -> Value {
                 .map(|trigs| {
                     trigs
                         .iter()
-                        .map(|t| create_nina_trigger(t, &item_id))
+                        .map(|t| create_nina_trigger(t, &item_id, plugins))
                         .collect::<Vec<_>>()
                 })
                 .unwrap_or_default();
@@ -233,7 +327,11 @@ fn create_nina_condition(condition: &EditorCondition, parent_id: &str) -> Value
 }
 
 /// Create NINA trigger
-fn create_nina_trigger(trigger: &EditorTrigger, parent_id: &str) -> Value {
+fn create_nina_trigger(
+    trigger: &EditorTrigger,
+    parent_id: &str,
+    plugins: &[NinaTypeSchema],
+) -> Value {
     let trigger_id = next_nina_id();
 
     let mut nina_trigger = json!({
@@ -258,7 +356,7 @@ fn create_nina_trigger(trigger: &EditorTrigger, parent_id: &str) -> Value {
         if let Some(obj) = nina_trigger.as_object_mut() {
             let trigger_items: Vec<Value> = items
                 .iter()
-                .map(|item| create_nina_item(item, &trigger_id))
+                .map(|item| create_nina_item(item, &trigger_id, plugins))
                 .collect();
 
             obj.insert("TriggerItems".to_string(), json!({
@@ -273,10 +371,14 @@ fn create_nina_trigger(trigger: &EditorTrigger, parent_id: &str) -> Value {
 }
 
 /// Create triggers collection
-fn create_triggers_collection(triggers: &[EditorTrigger], parent_id: &str) -> Value {
+fn create_triggers_collection(
+    triggers: &[EditorTrigger],
+    parent_id: &str,
+    plugins: &[NinaTypeSchema],
+) -> Value {
     let trigger_values: Vec<Value> = triggers
         .iter()
-        .map(|t| create_nina_trigger(t, parent_id))
+        .map(|t| create_nina_trigger(t, parent_id, plugins))
         .collect();
 
     json!({
@@ -303,8 +405,14 @@ fn to_pascal_case(s: &str) -> String {
     result
 }
 
-/// Import NINA JSON to editor sequence
-pub fn import_from_nina(json_str: &str) -> Result<EditorSequence, String> {
+/// Import NINA JSON to editor sequence, giving the entities ids derived
+/// from their position when `stable_ids` (the `stable_nina_ids` setting)
+/// is set
+pub fn import_from_nina(
+    json_str: &str,
+    stable_ids: bool,
+    plugins: &[NinaTypeSchema],
+) -> Result<EditorSequence, String> {
     let data: Value =
         serde_json::from_str(json_str).map_err(|e| format!("Failed to parse NINA JSON: {}", e))?;
 
@@ -314,17 +422,60 @@ pub fn import_from_nina(json_str: &str) -> Result<EditorSequence, String> {
         .and_then(|v| v.as_str())
         .ok_or("Missing $type field")?;
 
-    if type_str.contains("SequenceRootContainer") {
-        import_root_container(&data)
+    let mut sequence = if type_str.contains("SequenceRootContainer") {
+        import_root_container(&data, plugins)?
     } else if type_str.contains("Container") {
-        import_template(&data)
+        import_template(&data, plugins)?
     } else {
-        Err("Unknown NINA format".to_string())
+        return Err("Unknown NINA format".to_string());
+    };
+    if stable_ids {
+        assign_stable_ids(&mut sequence, json_str);
+    }
+    Ok(sequence)
+}
+
+/// Replace the ids of a sequence with ids derived from each entity's place
+/// and type, and the sequence's own id with one derived from `content`,
+/// the JSON it was read from. Importing the same file again, or a file
+/// exported from the result, gives the same ids, while different files
+/// sharing a title keep apart.
+pub fn assign_stable_ids(sequence: &mut EditorSequence, content: &str) {
+    fn assign_items(items: &mut [EditorSequenceItem], path: &str) {
+        for (index, item) in items.iter_mut().enumerate() {
+            let path = format!("{}/{}:{}", path, index, item.item_type);
+            item.id = stable_id::stable_id(&path).to_string();
+            for (index, condition) in item.conditions.iter_mut().flatten().enumerate() {
+                let seed = format!("{}/c{}:{}", path, index, condition.condition_type);
+                condition.id = stable_id::stable_id(&seed).to_string();
+            }
+            assign_triggers(item.triggers.iter_mut().flatten(), &path);
+            assign_items(item.items.as_deref_mut().unwrap_or_default(), &path);
+        }
     }
+    fn assign_triggers<'a>(triggers: impl Iterator<Item = &'a mut EditorTrigger>, path: &str) {
+        for (index, trigger) in triggers.enumerate() {
+            let path = format!("{}/t{}:{}", path, index, trigger.trigger_type);
+            trigger.id = stable_id::stable_id(&path).to_string();
+            assign_items(
+                trigger.trigger_items.as_deref_mut().unwrap_or_default(),
+                &path,
+            );
+        }
+    }
+
+    sequence.id = stable_id::stable_id(&format!("sequence:{}", content)).to_string();
+    assign_items(&mut sequence.start_items, "start");
+    assign_items(&mut sequence.target_items, "target");
+    assign_items(&mut sequence.end_items, "end");
+    assign_triggers(sequence.global_triggers.iter_mut(), "global");
 }
 
 /// Import root container
-fn import_root_container(data: &Value) -> Result<EditorSequence, String> {
+fn import_root_container(
+    data: &Value,
+    plugins: &[NinaTypeSchema],
+) -> Result<EditorSequence, String> {
     let title = data
         .get("SequenceTitle")
         .or_else(|| data.get("Name"))
@@ -344,7 +495,7 @@ fn import_root_container(data: &Value) -> Result<EditorSequence, String> {
 
     for item in items {
         let item_type = item.get("$type").and_then(|v| v.as_str()).unwrap_or("");
-        let imported_items = import_container_items(item)?;
+        let imported_items = import_container_items(item, plugins)?;
 
         if item_type.contains("StartAreaContainer") {
             start_items = imported_items;
@@ -360,7 +511,11 @@ fn import_root_container(data: &Value) -> Result<EditorSequence, String> {
         .get("Triggers")
         .and_then(|v| v.get("$values"))
         .and_then(|v| v.as_array())
-        .map(|arr| arr.iter().filter_map(import_trigger).collect())
+        .map(|arr| {
+            arr.iter()
+                .filter_map(|v| import_trigger(v, plugins))
+                .collect()
+        })
         .unwrap_or_default();
 
     Ok(EditorSequence {
@@ -374,14 +529,14 @@ fn import_root_container(data: &Value) -> Result<EditorSequence, String> {
 }
 
 /// Import template (single container)
-fn import_template(data: &Value) -> Result<EditorSequence, String> {
+fn import_template(data: &Value, plugins: &[NinaTypeSchema]) -> Result<EditorSequence, String> {
     let title = data
         .get("Name")
         .and_then(|v| v.as_str())
         .unwrap_or("Imported Template")
         .to_string();
 
-    let items = import_container_items(data)?;
+    let items = import_container_items(data, plugins)?;
 
     Ok(EditorSequence {
         id: uuid::Uuid::new_v4().to_string(),
@@ -394,20 +549,23 @@ fn import_template(data: &Value) -> Result<EditorSequence, String> {
 }
 
 /// Import container items
-fn import_container_items(container: &Value) -> Result<Vec<EditorSequenceItem>, String> {
+fn import_container_items(
+    container: &Value,
+    plugins: &[NinaTypeSchema],
+) -> Result<Vec<EditorSequenceItem>, String> {
     let items = container
         .get("Items")
         .and_then(|v| v.get("$values"))
         .and_then(|v| v.as_array());
 
     match items {
-        Some(arr) => Ok(arr.iter().filter_map(import_item).collect()),
+        Some(arr) => Ok(arr.iter().filter_map(|v| import_item(v, plugins)).collect()),
         None => Ok(Vec::new()),
     }
 }
 
 /// Import single item
-fn import_item(data: &Value) -> Option<EditorSequenceItem> {
+fn import_item(data: &Value, plugins: &[NinaTypeSchema]) -> Option<EditorSequenceItem> {
     let item_type = data.get("$type")?.as_str()?.to_string();
     let name = data
         .get("Name")
@@ -416,19 +574,17 @@ fn import_item(data: &Value) -> Option<EditorSequenceItem> {
         .to_string();
 
     // Extract category from type
-    let category = extract_category(&item_type);
+    let category = extract_category(&item_type, plugins);
 
     // Check if container
-    let is_container = item_type.contains("Container")
-        || item_type.contains("SmartExposure")
-        || item_type.contains("InstructionSet");
+    let is_container = nina_type_registry::is_container_type(&item_type, plugins);
 
     // Import nested items if container
     let items = if is_container {
         data.get("Items")
             .and_then(|v| v.get("$values"))
             .and_then(|v| v.as_array())
-            .map(|arr| arr.iter().filter_map(import_item).collect())
+            .map(|arr| arr.iter().filter_map(|v| import_item(v, plugins)).collect())
     } else {
         None
     };
@@ -438,14 +594,22 @@ fn import_item(data: &Value) -> Option<EditorSequenceItem> {
         .get("Conditions")
         .and_then(|v| v.get("$values"))
         .and_then(|v| v.as_array())
-        .map(|arr| arr.iter().filter_map(import_condition).collect());
+        .map(|arr| {
+            arr.iter()
+                .filter_map(|v| import_condition(v, plugins))
+                .collect()
+        });
 
     // Import triggers
     let triggers = data
         .get("Triggers")
         .and_then(|v| v.get("$values"))
         .and_then(|v| v.as_array())
-        .map(|arr| arr.iter().filter_map(import_trigger).collect());
+        .map(|arr| {
+            arr.iter()
+                .filter_map(|v| import_trigger(v, plugins))
+                .collect()
+        });
 
     // Extract data fields
     let mut item_data = HashMap::new();
@@ -486,7 +650,7 @@ fn import_item(data: &Value) -> Option<EditorSequenceItem> {
 }
 
 /// Import condition
-fn import_condition(data: &Value) -> Option<EditorCondition> {
+fn import_condition(data: &Value, plugins: &[NinaTypeSchema]) -> Option<EditorCondition> {
     let condition_type = data.get("$type")?.as_str()?.to_string();
     let name = data
         .get("Name")
@@ -494,7 +658,7 @@ fn import_condition(data: &Value) -> Option<EditorCondition> {
         .unwrap_or("Unknown")
         .to_string();
 
-    let category = extract_category(&condition_type);
+    let category = extract_category(&condition_type, plugins);
 
     let mut condition_data = HashMap::new();
     if let Some(obj) = data.as_object() {
@@ -518,7 +682,7 @@ fn import_condition(data: &Value) -> Option<EditorCondition> {
 }
 
 /// Import trigger
-fn import_trigger(data: &Value) -> Option<EditorTrigger> {
+fn import_trigger(data: &Value, plugins: &[NinaTypeSchema]) -> Option<EditorTrigger> {
     let trigger_type = data.get("$type")?.as_str()?.to_string();
     let name = data
         .get("Name")
@@ -526,7 +690,7 @@ fn import_trigger(data: &Value) -> Option<EditorTrigger> {
         .unwrap_or("Unknown")
         .to_string();
 
-    let category = extract_category(&trigger_type);
+    let category = extract_category(&trigger_type, plugins);
 
     let mut trigger_data = HashMap::new();
     if let Some(obj) = data.as_object() {
@@ -544,7 +708,7 @@ fn import_trigger(data: &Value) -> Option<EditorTrigger> {
         .get("TriggerItems")
         .and_then(|v| v.get("$values"))
         .and_then(|v| v.as_array())
-        .map(|arr| arr.iter().filter_map(import_item).collect());
+        .map(|arr| arr.iter().filter_map(|v| import_item(v, plugins)).collect());
 
     Some(EditorTrigger {
         id: uuid::Uuid::new_v4().to_string(),
@@ -558,7 +722,11 @@ fn import_trigger(data: &Value) -> Option<EditorTrigger> {
 }
 
 /// Extract category from NINA type string
-fn extract_category(type_str: &str) -> String {
+fn extract_category(type_str: &str, plugins: &[NinaTypeSchema]) -> String {
+    if let Some(plugin) = nina_type_registry::find_in(plugins, type_str) {
+        return plugin.category.clone();
+    }
+
     // Extract from "NINA.Sequencer.SequenceItem.Camera.CoolCamera, NINA.Sequencer"
     let parts: Vec<&str> = type_str.split('.').collect();
     if parts.len() >= 4 {
@@ -652,7 +820,7 @@ mod tests {
     #[test]
     fn test_export_to_nina() {
         let sequence = create_test_sequence();
-        let json = export_to_nina(&sequence).unwrap();
+        let json = export_to_nina(&sequence, &[]).unwrap();
 
         assert!(json.contains("SequenceRootContainer"));
         assert!(json.contains("Test Sequence"));
@@ -677,20 +845,70 @@ mod tests {
             "Triggers": { "$values": [] }
         }"#;
 
-        let sequence = import_from_nina(nina_json).unwrap();
+        let sequence = import_from_nina(nina_json, false, &[]).unwrap();
         assert_eq!(sequence.title, "Test");
     }
 
     #[test]
     fn test_roundtrip() {
         let original = create_test_sequence();
-        let json = export_to_nina(&original).unwrap();
-        let imported = import_from_nina(&json).unwrap();
+        let json = export_to_nina(&original, &[]).unwrap();
+        let imported = import_from_nina(&json, false, &[]).unwrap();
 
         assert_eq!(imported.title, original.title);
         assert_eq!(imported.start_items.len(), original.start_items.len());
     }
 
+    #[test]
+    fn test_stable_ids_survive_roundtrip() {
+        let json = export_to_nina(&create_test_sequence(), &[]).unwrap();
+        assert_eq!(export_to_nina(&create_test_sequence(), &[]).unwrap(), json);
+
+        let first = import_from_nina(&json, true, &[]).unwrap();
+        let second = import_from_nina(&json, true, &[]).unwrap();
+        assert_eq!(first.id, second.id);
+        assert_eq!(first.start_items[0].id, second.start_items[0].id);
+
+        let reimported =
+            import_from_nina(&export_to_nina(&first, &[]).unwrap(), true, &[]).unwrap();
+        assert_eq!(reimported.start_items[0].id, first.start_items[0].id);
+
+        let random = import_from_nina(&json, false, &[]).unwrap();
+        assert_ne!(random.start_items[0].id, first.start_items[0].id);
+    }
+
+    #[test]
+    fn test_stable_sequence_id_differs_between_files_with_same_title() {
+        let original = create_test_sequence();
+        let mut other = create_test_sequence();
+        other.end_items = other.start_items.clone();
+
+        let first = import_from_nina(&export_to_nina(&original, &[]).unwrap(), true, &[]).unwrap();
+        let second = import_from_nina(&export_to_nina(&other, &[]).unwrap(), true, &[]).unwrap();
+        assert_eq!(first.title, second.title);
+        assert_ne!(first.id, second.id);
+    }
+
+    #[test]
+    fn test_items_fragment_roundtrip() {
+        let items = create_test_sequence().start_items;
+        let json = export_items_fragment(&items, &[]).unwrap();
+        assert!(json.contains("\"Parent\": null"));
+
+        let imported = import_items_fragment(&json, &[]).unwrap();
+        assert_eq!(imported.len(), 1);
+        assert_eq!(imported[0].item_type, items[0].item_type);
+
+        let pair = vec![items[0].clone(), items[0].clone()];
+        let json = export_items_fragment(&pair, &[]).unwrap();
+        assert_eq!(import_items_fragment(&json, &[]).unwrap().len(), 2);
+
+        let sequence = export_to_nina(&create_test_sequence(), &[]).unwrap();
+        assert_eq!(import_items_fragment(&sequence, &[]).unwrap().len(), 1);
+        assert!(import_items_fragment("not json", &[]).is_err());
+        assert!(import_items_fragment("{}", &[]).is_err());
+    }
+
     #[test]
     fn test_validate_nina_json_valid() {
         let json = r#"{ "$type": "NINA.Sequencer.Container.SequenceRootContainer, NINA.Sequencer", "Items": { "$values": [] } }"#;
@@ -0,0 +1,1209 @@
+//! Registry of NINA sequencer types
+//!
+//! Catalog of the sequence items, conditions and triggers shipped with
+//! NINA, with the data fields each one expects. Field names use NINA's
+//! PascalCase, the same keys the editor stores in item data.
+//!
+//! Type strings are compared by their type path, without the assembly
+//! suffix. Only types in the `NINA.Sequencer` namespace are checked;
+//! plugin types are always accepted.
+//!
+//! Third-party plugin types are described in `plugin_types.json` in the
+//! app data directory, a JSON array of [`NinaTypeSchema`]s. The file is
+//! loaded at startup and can be reloaded after editing. Lookups that may
+//! hit a plugin type take the loaded definitions as a slice.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::fs;
+
+use crate::services::file_service;
+use crate::state::AppState;
+
+const CORE_NAMESPACE: &str = "NINA.Sequencer.";
+const CORE_ASSEMBLY: &str = "NINA.Sequencer";
+
+/// Kind of a NINA type
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum NinaTypeKind {
+    Container,
+    #[default]
+    Item,
+    Condition,
+    Trigger,
+}
+
+/// Value type of a data field
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum NinaFieldType {
+    Number,
+    Integer,
+    Boolean,
+    Text,
+    Object,
+}
+
+/// Data field of a NINA type
+#[derive(Debug, Clone, Copy)]
+pub struct NinaFieldDef {
+    pub name: &'static str,
+    pub field_type: NinaFieldType,
+    /// Default value as JSON
+    pub default: &'static str,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+}
+
+impl NinaFieldDef {
+    const fn new(name: &'static str, field_type: NinaFieldType, default: &'static str) -> Self {
+        Self {
+            name,
+            field_type,
+            default,
+            min: None,
+            max: None,
+        }
+    }
+
+    const fn min(mut self, min: f64) -> Self {
+        self.min = Some(min);
+        self
+    }
+
+    const fn range(mut self, min: f64, max: f64) -> Self {
+        self.min = Some(min);
+        self.max = Some(max);
+        self
+    }
+}
+
+const fn number(name: &'static str, default: &'static str) -> NinaFieldDef {
+    NinaFieldDef::new(name, NinaFieldType::Number, default)
+}
+
+const fn integer(name: &'static str, default: &'static str) -> NinaFieldDef {
+    NinaFieldDef::new(name, NinaFieldType::Integer, default)
+}
+
+const fn boolean(name: &'static str, default: &'static str) -> NinaFieldDef {
+    NinaFieldDef::new(name, NinaFieldType::Boolean, default)
+}
+
+const fn text(name: &'static str, default: &'static str) -> NinaFieldDef {
+    NinaFieldDef::new(name, NinaFieldType::Text, default)
+}
+
+const fn object(name: &'static str, default: &'static str) -> NinaFieldDef {
+    NinaFieldDef::new(name, NinaFieldType::Object, default)
+}
+
+/// NINA type definition
+#[derive(Debug, Clone, Copy)]
+pub struct NinaTypeDef {
+    /// Type path without the assembly
+    pub type_path: &'static str,
+    pub name: &'static str,
+    pub category: &'static str,
+    pub kind: NinaTypeKind,
+    pub description: &'static str,
+    pub fields: &'static [NinaFieldDef],
+}
+
+const fn def(
+    type_path: &'static str,
+    name: &'static str,
+    category: &'static str,
+    kind: NinaTypeKind,
+    description: &'static str,
+    fields: &'static [NinaFieldDef],
+) -> NinaTypeDef {
+    NinaTypeDef {
+        type_path,
+        name,
+        category,
+        kind,
+        description,
+        fields,
+    }
+}
+
+use NinaTypeKind::{Condition, Container, Item, Trigger};
+
+const IS_EXPANDED: NinaFieldDef = boolean("IsExpanded", "true");
+const INHERITED: NinaFieldDef = boolean("Inherited", "true");
+const EXPOSURE_TIME: NinaFieldDef = number("ExposureTime", "60").min(0.0);
+const GAIN: NinaFieldDef = integer("Gain", "-1").min(-1.0);
+const OFFSET: NinaFieldDef = integer("Offset", "-1").min(-1.0);
+const IMAGE_TYPE: NinaFieldDef = text("ImageType", "\"LIGHT\"");
+const EXPOSURE_COUNT: NinaFieldDef = integer("ExposureCount", "0").min(0.0);
+const BINNING: NinaFieldDef = object("Binning", r#"{"X":1,"Y":1}"#);
+const HOURS: NinaFieldDef = integer("Hours", "0").range(0.0, 23.0);
+const MINUTES: NinaFieldDef = integer("Minutes", "0").range(0.0, 59.0);
+const SECONDS: NinaFieldDef = integer("Seconds", "0").range(0.0, 59.0);
+const TARGET_ALTITUDE: NinaFieldDef = number("TargetAltitude", "30").range(-90.0, 90.0);
+const COMPARATOR: NinaFieldDef = text("Comparator", "\">=\"");
+const HORIZON_OFFSET: NinaFieldDef = number("Offset", "0").range(-90.0, 90.0);
+
+/// All known NINA types
+pub const NINA_TYPES: &[NinaTypeDef] = &[
+    // Containers
+    def(
+        "NINA.Sequencer.Container.SequentialContainer",
+        "Sequential Container",
+        "Container",
+        Container,
+        "Execute items in sequence",
+        &[IS_EXPANDED],
+    ),
+    def(
+        "NINA.Sequencer.Container.ParallelContainer",
+        "Parallel Container",
+        "Container",
+        Container,
+        "Execute items in parallel",
+        &[IS_EXPANDED],
+    ),
+    def(
+        "NINA.Sequencer.Container.DeepSkyObjectContainer",
+        "Deep Sky Object",
+        "Container",
+        Container,
+        "Container for a deep sky object target",
+        &[
+            IS_EXPANDED,
+            object(
+                "Target",
+                r#"{"TargetName":"","Rotation":0,"InputCoordinates":{"RAHours":0,"RAMinutes":0,"RASeconds":0,"DecDegrees":0,"DecMinutes":0,"DecSeconds":0}}"#,
+            ),
+        ],
+    ),
+    // Camera
+    def(
+        "NINA.Sequencer.SequenceItem.Camera.CoolCamera",
+        "Cool Camera",
+        "Camera",
+        Item,
+        "Cool the camera to a target temperature",
+        &[
+            number("Temperature", "-10").range(-60.0, 40.0),
+            number("Duration", "0").min(0.0),
+        ],
+    ),
+    def(
+        "NINA.Sequencer.SequenceItem.Camera.WarmCamera",
+        "Warm Camera",
+        "Camera",
+        Item,
+        "Warm the camera back to ambient temperature",
+        &[number("Duration", "0").min(0.0)],
+    ),
+    def(
+        "NINA.Sequencer.SequenceItem.Camera.SetReadoutMode",
+        "Set Readout Mode",
+        "Camera",
+        Item,
+        "Set camera readout mode",
+        &[integer("Mode", "0").min(0.0)],
+    ),
+    def(
+        "NINA.Sequencer.SequenceItem.Camera.DewHeater",
+        "Dew Heater",
+        "Camera",
+        Item,
+        "Toggle dew heater on/off",
+        &[boolean("OnOff", "true")],
+    ),
+    def(
+        "NINA.Sequencer.SequenceItem.Camera.SetUSBLimit",
+        "Set USB Limit",
+        "Camera",
+        Item,
+        "Set camera USB bandwidth limit",
+        &[integer("USBLimit", "40").range(0.0, 100.0)],
+    ),
+    // Imaging
+    def(
+        "NINA.Sequencer.SequenceItem.Imaging.TakeExposure",
+        "Take Exposure",
+        "Imaging",
+        Item,
+        "Take a single exposure",
+        &[
+            EXPOSURE_TIME,
+            GAIN,
+            OFFSET,
+            IMAGE_TYPE,
+            EXPOSURE_COUNT,
+            BINNING,
+        ],
+    ),
+    def(
+        "NINA.Sequencer.SequenceItem.Imaging.TakeManyExposures",
+        "Take Many Exposures",
+        "Imaging",
+        Item,
+        "Take multiple exposures",
+        &[
+            EXPOSURE_TIME,
+            GAIN,
+            OFFSET,
+            IMAGE_TYPE,
+            EXPOSURE_COUNT,
+            integer("TotalExposureCount", "10").min(1.0),
+            BINNING,
+        ],
+    ),
+    def(
+        "NINA.Sequencer.SequenceItem.Imaging.TakeSubframeExposure",
+        "Take Subframe Exposure",
+        "Imaging",
+        Item,
+        "Take an exposure of a centered region of the sensor",
+        &[
+            EXPOSURE_TIME,
+            GAIN,
+            OFFSET,
+            IMAGE_TYPE,
+            EXPOSURE_COUNT,
+            BINNING,
+            number("ROI", "1").range(0.0, 1.0),
+        ],
+    ),
+    def(
+        "NINA.Sequencer.SequenceItem.Imaging.SmartExposure",
+        "Smart Exposure",
+        "Imaging",
+        Container,
+        "Combined filter switch, exposure and dither",
+        &[IS_EXPANDED],
+    ),
+    // Telescope
+    def(
+        "NINA.Sequencer.SequenceItem.Telescope.SlewScopeToRaDec",
+        "Slew to RA/Dec",
+        "Telescope",
+        Item,
+        "Slew telescope to RA/Dec coordinates",
+        &[INHERITED],
+    ),
+    def(
+        "NINA.Sequencer.SequenceItem.Telescope.SlewScopeToAltAz",
+        "Slew to Alt/Az",
+        "Telescope",
+        Item,
+        "Slew telescope to altitude/azimuth",
+        &[
+            number("Altitude", "45").range(-90.0, 90.0),
+            number("Azimuth", "180").range(0.0, 360.0),
+        ],
+    ),
+    def(
+        "NINA.Sequencer.SequenceItem.Telescope.ParkScope",
+        "Park Scope",
+        "Telescope",
+        Item,
+        "Park the telescope",
+        &[],
+    ),
+    def(
+        "NINA.Sequencer.SequenceItem.Telescope.UnparkScope",
+        "Unpark Scope",
+        "Telescope",
+        Item,
+        "Unpark the telescope",
+        &[],
+    ),
+    def(
+        "NINA.Sequencer.SequenceItem.Telescope.FindHome",
+        "Find Home",
+        "Telescope",
+        Item,
+        "Find home position",
+        &[],
+    ),
+    def(
+        "NINA.Sequencer.SequenceItem.Telescope.SetTracking",
+        "Set Tracking",
+        "Telescope",
+        Item,
+        "Set telescope tracking mode",
+        &[integer("TrackingMode", "0").range(0.0, 5.0)],
+    ),
+    // Focuser
+    def(
+        "NINA.Sequencer.SequenceItem.Focuser.MoveFocuserAbsolute",
+        "Move Focuser (Absolute)",
+        "Focuser",
+        Item,
+        "Move focuser to absolute position",
+        &[integer("Position", "5000").min(0.0)],
+    ),
+    def(
+        "NINA.Sequencer.SequenceItem.Focuser.MoveFocuserRelative",
+        "Move Focuser (Relative)",
+        "Focuser",
+        Item,
+        "Move focuser by relative amount",
+        &[integer("RelativePosition", "100")],
+    ),
+    def(
+        "NINA.Sequencer.SequenceItem.Focuser.MoveFocuserByTemperature",
+        "Move Focuser by Temperature",
+        "Focuser",
+        Item,
+        "Adjust focuser based on temperature",
+        &[number("Slope", "0"), number("Intercept", "0")],
+    ),
+    // Filter wheel
+    def(
+        "NINA.Sequencer.SequenceItem.FilterWheel.SwitchFilter",
+        "Switch Filter",
+        "Filter Wheel",
+        Item,
+        "Switch to a specific filter",
+        &[object("Filter", "null")],
+    ),
+    // Guider
+    def(
+        "NINA.Sequencer.SequenceItem.Guider.StartGuiding",
+        "Start Guiding",
+        "Guider",
+        Item,
+        "Start autoguiding",
+        &[boolean("ForceCalibration", "false")],
+    ),
+    def(
+        "NINA.Sequencer.SequenceItem.Guider.StopGuiding",
+        "Stop Guiding",
+        "Guider",
+        Item,
+        "Stop autoguiding",
+        &[],
+    ),
+    def(
+        "NINA.Sequencer.SequenceItem.Guider.Dither",
+        "Dither",
+        "Guider",
+        Item,
+        "Perform a dither",
+        &[],
+    ),
+    // Autofocus
+    def(
+        "NINA.Sequencer.SequenceItem.Autofocus.RunAutofocus",
+        "Run Autofocus",
+        "Autofocus",
+        Item,
+        "Run autofocus routine",
+        &[],
+    ),
+    // Plate solving
+    def(
+        "NINA.Sequencer.SequenceItem.Platesolving.Center",
+        "Center",
+        "Platesolving",
+        Item,
+        "Center on target using plate solving",
+        &[INHERITED],
+    ),
+    def(
+        "NINA.Sequencer.SequenceItem.Platesolving.CenterAndRotate",
+        "Center and Rotate",
+        "Platesolving",
+        Item,
+        "Center and rotate to target position angle",
+        &[INHERITED, number("Rotation", "0").range(0.0, 360.0)],
+    ),
+    def(
+        "NINA.Sequencer.SequenceItem.Platesolving.SolveAndSync",
+        "Solve and Sync",
+        "Platesolving",
+        Item,
+        "Plate solve and sync the mount",
+        &[],
+    ),
+    // Rotator
+    def(
+        "NINA.Sequencer.SequenceItem.Rotator.MoveRotatorAbsolute",
+        "Move Rotator (Absolute)",
+        "Rotator",
+        Item,
+        "Move rotator to absolute position",
+        &[number("Position", "0").range(0.0, 360.0)],
+    ),
+    def(
+        "NINA.Sequencer.SequenceItem.Rotator.MoveRotatorRelative",
+        "Move Rotator (Relative)",
+        "Rotator",
+        Item,
+        "Move rotator by relative amount",
+        &[number("RelativePosition", "0").range(-360.0, 360.0)],
+    ),
+    def(
+        "NINA.Sequencer.SequenceItem.Rotator.MoveRotatorMechanical",
+        "Move Rotator (Mechanical)",
+        "Rotator",
+        Item,
+        "Move rotator to mechanical position",
+        &[number("MechanicalPosition", "0").range(0.0, 360.0)],
+    ),
+    // Dome
+    def(
+        "NINA.Sequencer.SequenceItem.Dome.OpenDomeShutter",
+        "Open Dome Shutter",
+        "Dome",
+        Item,
+        "Open the dome shutter",
+        &[],
+    ),
+    def(
+        "NINA.Sequencer.SequenceItem.Dome.CloseDomeShutter",
+        "Close Dome Shutter",
+        "Dome",
+        Item,
+        "Close the dome shutter",
+        &[],
+    ),
+    def(
+        "NINA.Sequencer.SequenceItem.Dome.ParkDome",
+        "Park Dome",
+        "Dome",
+        Item,
+        "Park the dome",
+        &[],
+    ),
+    def(
+        "NINA.Sequencer.SequenceItem.Dome.SynchronizeDome",
+        "Synchronize Dome",
+        "Dome",
+        Item,
+        "Synchronize dome with telescope",
+        &[],
+    ),
+    def(
+        "NINA.Sequencer.SequenceItem.Dome.EnableDomeSynchronization",
+        "Enable Dome Sync",
+        "Dome",
+        Item,
+        "Enable dome synchronization",
+        &[],
+    ),
+    def(
+        "NINA.Sequencer.SequenceItem.Dome.DisableDomeSynchronization",
+        "Disable Dome Sync",
+        "Dome",
+        Item,
+        "Disable dome synchronization",
+        &[],
+    ),
+    def(
+        "NINA.Sequencer.SequenceItem.Dome.SlewDomeAbsolute",
+        "Slew Dome",
+        "Dome",
+        Item,
+        "Slew dome to azimuth",
+        &[number("Azimuth", "0").range(0.0, 360.0)],
+    ),
+    // Flat device
+    def(
+        "NINA.Sequencer.SequenceItem.FlatDevice.SetBrightness",
+        "Set Brightness",
+        "Flat Device",
+        Item,
+        "Set flat panel brightness",
+        &[integer("Brightness", "50").min(0.0)],
+    ),
+    def(
+        "NINA.Sequencer.SequenceItem.FlatDevice.ToggleLight",
+        "Toggle Light",
+        "Flat Device",
+        Item,
+        "Toggle flat panel light",
+        &[boolean("OnOff", "true")],
+    ),
+    def(
+        "NINA.Sequencer.SequenceItem.FlatDevice.OpenCover",
+        "Open Cover",
+        "Flat Device",
+        Item,
+        "Open flat panel cover",
+        &[],
+    ),
+    def(
+        "NINA.Sequencer.SequenceItem.FlatDevice.CloseCover",
+        "Close Cover",
+        "Flat Device",
+        Item,
+        "Close flat panel cover",
+        &[],
+    ),
+    def(
+        "NINA.Sequencer.SequenceItem.FlatDevice.SkyFlat",
+        "Sky Flats",
+        "Flat Device",
+        Container,
+        "Take twilight sky flats",
+        &[IS_EXPANDED],
+    ),
+    def(
+        "NINA.Sequencer.SequenceItem.FlatDevice.TrainedFlatExposure",
+        "Trained Flat Exposure",
+        "Flat Device",
+        Container,
+        "Take flats with trained panel settings",
+        &[IS_EXPANDED],
+    ),
+    def(
+        "NINA.Sequencer.SequenceItem.FlatDevice.TrainedDarkFlatExposure",
+        "Trained Dark Flat Exposure",
+        "Flat Device",
+        Container,
+        "Take dark flats matching trained flat settings",
+        &[IS_EXPANDED],
+    ),
+    // Safety monitor
+    def(
+        "NINA.Sequencer.SequenceItem.SafetyMonitor.WaitUntilSafe",
+        "Wait Until Safe",
+        "Safety Monitor",
+        Item,
+        "Wait until safety monitor reports safe",
+        &[],
+    ),
+    // Switch
+    def(
+        "NINA.Sequencer.SequenceItem.Switch.SetSwitchValue",
+        "Set Switch Value",
+        "Switch",
+        Item,
+        "Set a switch value",
+        &[integer("SwitchIndex", "0").min(0.0), number("Value", "0")],
+    ),
+    // Utility
+    def(
+        "NINA.Sequencer.SequenceItem.Utility.Annotation",
+        "Annotation",
+        "Utility",
+        Item,
+        "Add a comment/annotation",
+        &[text("Text", "\"\"")],
+    ),
+    def(
+        "NINA.Sequencer.SequenceItem.Utility.MessageBox",
+        "Message Box",
+        "Utility",
+        Item,
+        "Show a message box",
+        &[text("Text", "\"\"")],
+    ),
+    def(
+        "NINA.Sequencer.SequenceItem.Utility.ExternalScript",
+        "External Script",
+        "Utility",
+        Item,
+        "Run an external script",
+        &[text("Script", "\"\"")],
+    ),
+    def(
+        "NINA.Sequencer.SequenceItem.Utility.WaitForTime",
+        "Wait For Time",
+        "Utility",
+        Item,
+        "Wait until a specific time",
+        &[HOURS, MINUTES, SECONDS],
+    ),
+    def(
+        "NINA.Sequencer.SequenceItem.Utility.WaitForTimeSpan",
+        "Wait For Duration",
+        "Utility",
+        Item,
+        "Wait for a duration",
+        &[number("Time", "60").min(0.0)],
+    ),
+    def(
+        "NINA.Sequencer.SequenceItem.Utility.WaitForAltitude",
+        "Wait For Altitude",
+        "Utility",
+        Item,
+        "Wait for target altitude",
+        &[TARGET_ALTITUDE, COMPARATOR],
+    ),
+    def(
+        "NINA.Sequencer.SequenceItem.Utility.WaitForMoonAltitude",
+        "Wait For Moon Altitude",
+        "Utility",
+        Item,
+        "Wait for moon altitude",
+        &[
+            number("TargetAltitude", "0").range(-90.0, 90.0),
+            text("Comparator", "\"<=\""),
+        ],
+    ),
+    def(
+        "NINA.Sequencer.SequenceItem.Utility.WaitForSunAltitude",
+        "Wait For Sun Altitude",
+        "Utility",
+        Item,
+        "Wait for sun altitude",
+        &[
+            number("TargetAltitude", "-12").range(-90.0, 90.0),
+            text("Comparator", "\"<=\""),
+        ],
+    ),
+    def(
+        "NINA.Sequencer.SequenceItem.Utility.WaitUntilAboveHorizon",
+        "Wait Until Above Horizon",
+        "Utility",
+        Item,
+        "Wait until target is above horizon",
+        &[HORIZON_OFFSET],
+    ),
+    def(
+        "NINA.Sequencer.SequenceItem.Utility.SaveSequence",
+        "Save Sequence",
+        "Utility",
+        Item,
+        "Save the sequence to file",
+        &[text("FilePath", "\"\"")],
+    ),
+    // Equipment
+    def(
+        "NINA.Sequencer.SequenceItem.Connect.ConnectEquipment",
+        "Connect Equipment",
+        "Connect",
+        Item,
+        "Connect all equipment",
+        &[],
+    ),
+    def(
+        "NINA.Sequencer.SequenceItem.Connect.DisconnectEquipment",
+        "Disconnect Equipment",
+        "Connect",
+        Item,
+        "Disconnect all equipment",
+        &[],
+    ),
+    // Conditions
+    def(
+        "NINA.Sequencer.Conditions.LoopCondition",
+        "Loop",
+        "Condition",
+        Condition,
+        "Repeat for a number of iterations",
+        &[
+            integer("Iterations", "1").min(0.0),
+            integer("CompletedIterations", "0").min(0.0),
+        ],
+    ),
+    def(
+        "NINA.Sequencer.Conditions.TimeCondition",
+        "Loop Until Time",
+        "Condition",
+        Condition,
+        "Loop until a specific time",
+        &[HOURS, MINUTES, SECONDS],
+    ),
+    def(
+        "NINA.Sequencer.Conditions.TimeSpanCondition",
+        "Loop For Duration",
+        "Condition",
+        Condition,
+        "Loop for a duration",
+        &[integer("Hours", "0").min(0.0), MINUTES, SECONDS],
+    ),
+    def(
+        "NINA.Sequencer.Conditions.AltitudeCondition",
+        "Loop While Altitude",
+        "Condition",
+        Condition,
+        "Loop while altitude condition is met",
+        &[TARGET_ALTITUDE, COMPARATOR],
+    ),
+    def(
+        "NINA.Sequencer.Conditions.AboveHorizonCondition",
+        "Loop While Above Horizon",
+        "Condition",
+        Condition,
+        "Loop while target is above horizon",
+        &[HORIZON_OFFSET],
+    ),
+    def(
+        "NINA.Sequencer.Conditions.MoonAltitudeCondition",
+        "Loop While Moon Altitude",
+        "Condition",
+        Condition,
+        "Loop while moon altitude condition is met",
+        &[
+            number("TargetAltitude", "0").range(-90.0, 90.0),
+            text("Comparator", "\"<=\""),
+        ],
+    ),
+    def(
+        "NINA.Sequencer.Conditions.SunAltitudeCondition",
+        "Loop While Sun Altitude",
+        "Condition",
+        Condition,
+        "Loop while sun altitude condition is met",
+        &[
+            number("TargetAltitude", "-12").range(-90.0, 90.0),
+            text("Comparator", "\"<=\""),
+        ],
+    ),
+    def(
+        "NINA.Sequencer.Conditions.MoonIlluminationCondition",
+        "Loop While Moon Illumination",
+        "Condition",
+        Condition,
+        "Loop while moon illumination condition is met",
+        &[
+            number("TargetIllumination", "50").range(0.0, 100.0),
+            text("Comparator", "\"<=\""),
+        ],
+    ),
+    def(
+        "NINA.Sequencer.Conditions.SafetyMonitorCondition",
+        "Loop While Safe",
+        "Condition",
+        Condition,
+        "Loop while safety monitor reports safe",
+        &[],
+    ),
+    // Triggers
+    def(
+        "NINA.Sequencer.Trigger.MeridianFlip.MeridianFlipTrigger",
+        "Meridian Flip",
+        "Trigger",
+        Trigger,
+        "Trigger meridian flip when needed",
+        &[],
+    ),
+    def(
+        "NINA.Sequencer.Trigger.Guider.DitherAfterExposures",
+        "Dither After Exposures",
+        "Trigger",
+        Trigger,
+        "Dither after a number of exposures",
+        &[integer("AfterExposures", "1").min(1.0)],
+    ),
+    def(
+        "NINA.Sequencer.Trigger.Guider.RestoreGuiding",
+        "Restore Guiding",
+        "Trigger",
+        Trigger,
+        "Restore guiding if stopped",
+        &[],
+    ),
+    def(
+        "NINA.Sequencer.Trigger.Autofocus.AutofocusAfterExposures",
+        "Autofocus After Exposures",
+        "Trigger",
+        Trigger,
+        "Run autofocus after a number of exposures",
+        &[integer("AfterExposures", "10").min(1.0)],
+    ),
+    def(
+        "NINA.Sequencer.Trigger.Autofocus.AutofocusAfterFilterChange",
+        "Autofocus After Filter Change",
+        "Trigger",
+        Trigger,
+        "Run autofocus after filter change",
+        &[],
+    ),
+    def(
+        "NINA.Sequencer.Trigger.Autofocus.AutofocusAfterHFRIncreaseTrigger",
+        "Autofocus After HFR Increase",
+        "Trigger",
+        Trigger,
+        "Run autofocus when HFR increases",
+        &[
+            number("Amount", "10").min(0.0),
+            integer("SampleSize", "10").min(1.0),
+        ],
+    ),
+    def(
+        "NINA.Sequencer.Trigger.Autofocus.AutofocusAfterTemperatureChangeTrigger",
+        "Autofocus After Temperature Change",
+        "Trigger",
+        Trigger,
+        "Run autofocus when temperature changes",
+        &[number("Amount", "2").min(0.0)],
+    ),
+    def(
+        "NINA.Sequencer.Trigger.Autofocus.AutofocusAfterTimeTrigger",
+        "Autofocus After Time",
+        "Trigger",
+        Trigger,
+        "Run autofocus after time interval",
+        &[number("Amount", "60").min(0.0)],
+    ),
+    def(
+        "NINA.Sequencer.Trigger.Platesolving.CenterAfterDriftTrigger",
+        "Center After Drift",
+        "Trigger",
+        Trigger,
+        "Re-center when drift exceeds threshold",
+        &[number("DistanceArcMinutes", "5").min(0.0)],
+    ),
+];
+
+/// Serializable field schema
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NinaFieldSchema {
+    pub name: String,
+    pub field_type: NinaFieldType,
+    #[serde(default)]
+    pub default: Value,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max: Option<f64>,
+}
+
+/// Serializable type schema
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NinaTypeSchema {
+    /// Full type string, e.g. `NINA.Sequencer.Conditions.LoopCondition, NINA.Sequencer`
+    #[serde(alias = "type")]
+    pub type_name: String,
+    pub name: String,
+    #[serde(default = "default_plugin_category")]
+    pub category: String,
+    #[serde(default)]
+    pub kind: NinaTypeKind,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub fields: Vec<NinaFieldSchema>,
+}
+
+fn default_plugin_category() -> String {
+    "Plugin".to_string()
+}
+
+impl NinaTypeSchema {
+    /// Item data filled with the field defaults
+    pub fn default_data(&self) -> HashMap<String, Value> {
+        self.fields
+            .iter()
+            .map(|f| (f.name.clone(), f.default.clone()))
+            .collect()
+    }
+}
+
+impl NinaTypeDef {
+    /// Full type string including the assembly
+    pub fn type_name(&self) -> String {
+        format!("{}, {}", self.type_path, CORE_ASSEMBLY)
+    }
+
+    /// Look up a field by name, ignoring case
+    pub fn field(&self, name: &str) -> Option<&'static NinaFieldDef> {
+        self.fields
+            .iter()
+            .find(|f| f.name.eq_ignore_ascii_case(name))
+    }
+
+    pub fn schema(&self) -> NinaTypeSchema {
+        NinaTypeSchema {
+            type_name: self.type_name(),
+            name: self.name.to_string(),
+            category: self.category.to_string(),
+            kind: self.kind,
+            description: self.description.to_string(),
+            fields: self
+                .fields
+                .iter()
+                .map(|f| NinaFieldSchema {
+                    name: f.name.to_string(),
+                    field_type: f.field_type,
+                    default: serde_json::from_str(f.default).unwrap_or(Value::Null),
+                    min: f.min,
+                    max: f.max,
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Type path without the assembly, e.g. `NINA.Sequencer.Conditions.LoopCondition`
+pub fn type_path(full_type: &str) -> &str {
+    full_type.split(',').next().unwrap_or(full_type).trim()
+}
+
+/// Find a type by its full type string or type path
+pub fn find_type(full_type: &str) -> Option<&'static NinaTypeDef> {
+    let path = type_path(full_type);
+    NINA_TYPES.iter().find(|t| t.type_path == path)
+}
+
+/// Schema of a built-in type or one of the `plugins`
+pub fn get_schema(full_type: &str, plugins: &[NinaTypeSchema]) -> Option<NinaTypeSchema> {
+    find_type(full_type)
+        .map(NinaTypeDef::schema)
+        .or_else(|| find_in(plugins, full_type).cloned())
+}
+
+/// List type schemas, optionally limited to a category. Categories match
+/// without regard to case or spaces, so `FilterWheel` finds `Filter Wheel`.
+pub fn list_types(category: Option<&str>, plugins: &[NinaTypeSchema]) -> Vec<NinaTypeSchema> {
+    let normalize = |s: &str| s.replace(' ', "").to_lowercase();
+    let category = category.map(normalize).filter(|c| !c.is_empty());
+    NINA_TYPES
+        .iter()
+        .map(NinaTypeDef::schema)
+        .chain(plugins.iter().cloned())
+        .filter(|t| {
+            category
+                .as_ref()
+                .map_or(true, |c| normalize(&t.category) == *c)
+        })
+        .collect()
+}
+
+/// Check if a type string represents a container. Built-in types and the
+/// `plugins` use their kind; other types fall back to the name.
+pub fn is_container_type(full_type: &str, plugins: &[NinaTypeSchema]) -> bool {
+    let kind = find_type(full_type)
+        .map(|def| def.kind)
+        .or_else(|| find_in(plugins, full_type).map(|t| t.kind));
+    match kind {
+        Some(kind) => kind == Container,
+        None => {
+            full_type.contains("Container")
+                || full_type.contains("SmartExposure")
+                || full_type.contains("InstructionSet")
+        }
+    }
+}
+
+fn is_known(full_type: &str, kinds: &[NinaTypeKind]) -> bool {
+    if !type_path(full_type).starts_with(CORE_NAMESPACE) {
+        return true;
+    }
+    find_type(full_type).is_some_and(|t| kinds.contains(&t.kind))
+}
+
+/// Check a sequence item type against the registry
+pub fn is_known_item_type(full_type: &str) -> bool {
+    is_known(full_type, &[Container, Item])
+}
+
+/// Check a condition type against the registry
+pub fn is_known_condition_type(full_type: &str) -> bool {
+    is_known(full_type, &[Condition])
+}
+
+/// Check a trigger type against the registry
+pub fn is_known_trigger_type(full_type: &str) -> bool {
+    is_known(full_type, &[Trigger])
+}
+
+// ============================================================================
+// Plugin types
+// ============================================================================
+
+/// Loaded plugin type definitions, part of the
+/// [`AppState`](crate::state::AppState)
+#[derive(Default)]
+pub struct PluginTypes {
+    types: RwLock<Vec<NinaTypeSchema>>,
+}
+
+/// Get the plugin type definitions file path
+pub fn get_plugin_types_path() -> PathBuf {
+    file_service::get_app_data_directory().join("plugin_types.json")
+}
+
+/// Parse plugin type definitions. A later definition of the same type
+/// replaces an earlier one.
+pub fn parse_plugin_types(json: &str) -> Result<Vec<NinaTypeSchema>, String> {
+    let definitions: Vec<NinaTypeSchema> =
+        serde_json::from_str(json).map_err(|e| format!("Failed to parse plugin types: {}", e))?;
+
+    let mut types: Vec<NinaTypeSchema> = Vec::with_capacity(definitions.len());
+    for definition in definitions {
+        let path = type_path(&definition.type_name);
+        if path.is_empty() {
+            return Err(format!(
+                "Plugin type '{}' has no type name",
+                definition.name
+            ));
+        }
+        if find_type(path).is_some() {
+            return Err(format!("Plugin type {} is a built-in NINA type", path));
+        }
+        types.retain(|t| type_path(&t.type_name) != path);
+        types.push(definition);
+    }
+    Ok(types)
+}
+
+/// Load plugin type definitions from disk. A missing file clears them.
+pub async fn load_plugin_types(state: &AppState) -> Result<usize, String> {
+    let path = get_plugin_types_path();
+    let types = if path.exists() {
+        let contents = fs::read_to_string(&path)
+            .await
+            .map_err(|e| format!("Failed to read plugin types: {}", e))?;
+        parse_plugin_types(&contents)?
+    } else {
+        Vec::new()
+    };
+
+    let count = types.len();
+    set_plugin_types(state, types);
+    Ok(count)
+}
+
+/// Replace the loaded plugin type definitions
+pub fn set_plugin_types(state: &AppState, types: Vec<NinaTypeSchema>) {
+    *state.plugin_types.types.write() = types;
+}
+
+/// Loaded plugin type definitions
+pub fn plugin_types(state: &AppState) -> Vec<NinaTypeSchema> {
+    state.plugin_types.types.read().clone()
+}
+
+/// Find a type among `plugins` by its full type string or type path
+pub fn find_in<'a>(plugins: &'a [NinaTypeSchema], full_type: &str) -> Option<&'a NinaTypeSchema> {
+    let path = type_path(full_type);
+    plugins.iter().find(|t| type_path(&t.type_name) == path)
+}
+
+/// Find a loaded plugin type by its full type string or type path
+pub fn find_plugin_type(state: &AppState, full_type: &str) -> Option<NinaTypeSchema> {
+    find_in(&state.plugin_types.types.read(), full_type).cloned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_registry_lookup() {
+        assert!(is_known_item_type(
+            "NINA.Sequencer.SequenceItem.Imaging.SmartExposure, NINA.Sequencer"
+        ));
+        assert!(!is_known_item_type(
+            "NINA.Sequencer.SequenceItem.Imaging.TakeExposures, NINA.Sequencer"
+        ));
+        // Plugin types are not checked
+        assert!(is_known_item_type(
+            "DaleGhent.NINA.GroundStation.SendToPushover.SendToPushover, GroundStation"
+        ));
+        assert!(is_known_condition_type(
+            "NINA.Sequencer.Conditions.LoopCondition, NINA.Sequencer"
+        ));
+        assert!(!is_known_trigger_type(
+            "NINA.Sequencer.Conditions.LoopCondition, NINA.Sequencer"
+        ));
+
+        assert!(is_container_type(
+            "NINA.Sequencer.SequenceItem.FlatDevice.SkyFlat, NINA.Sequencer",
+            &[],
+        ));
+        assert!(is_container_type(
+            "Plugin.Sequencer.Container.LoopingContainer, Plugin",
+            &[],
+        ));
+        assert!(!is_container_type(
+            "NINA.Sequencer.SequenceItem.Camera.CoolCamera, NINA.Sequencer",
+            &[],
+        ));
+    }
+
+    #[test]
+    fn test_registry_defaults_are_valid() {
+        for def in NINA_TYPES {
+            for field in def.fields {
+                let value: Value = serde_json::from_str(field.default)
+                    .unwrap_or_else(|e| panic!("{}.{}: {}", def.type_path, field.name, e));
+                if let Some(n) = value.as_f64() {
+                    assert!(field.min.map_or(true, |min| n >= min), "{}", field.name);
+                    assert!(field.max.map_or(true, |max| n <= max), "{}", field.name);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_schema_and_category_listing() {
+        let schema = get_schema(
+            "NINA.Sequencer.SequenceItem.Imaging.TakeExposure, NINA.Sequencer",
+            &[],
+        )
+        .unwrap();
+        assert_eq!(schema.category, "Imaging");
+        let data = schema.default_data();
+        assert_eq!(data["ExposureTime"], 60);
+        assert_eq!(data["Binning"]["X"], 1);
+
+        let filter_wheel = list_types(Some("FilterWheel"), &[]);
+        assert_eq!(filter_wheel.len(), 1);
+        assert_eq!(filter_wheel[0].name, "Switch Filter");
+        assert!(list_types(Some("condition"), &[])
+            .iter()
+            .all(|t| t.kind == Condition));
+        let builtin = list_types(None, &[])
+            .into_iter()
+            .filter(|t| t.type_name.starts_with(CORE_NAMESPACE))
+            .count();
+        assert_eq!(builtin, NINA_TYPES.len());
+    }
+
+    #[test]
+    fn test_plugin_types() {
+        let json = r#"[
+            {
+                "type": "DaleGhent.NINA.GroundStation.SendToPushover.SendToPushover, DaleGhent.NINA.GroundStation",
+                "name": "Send to Pushover",
+                "category": "Ground Station",
+                "fields": [
+                    { "name": "Priority", "fieldType": "integer", "default": 0, "min": -2, "max": 2 }
+                ]
+            },
+            {
+                "typeName": "Plugin.Sequencer.LoopingSet, Plugin",
+                "name": "Looping Set",
+                "kind": "container"
+            }
+        ]"#;
+        let types = parse_plugin_types(json).unwrap();
+        assert_eq!(types.len(), 2);
+        assert_eq!(types[1].category, "Plugin");
+        assert_eq!(types[0].kind, Item);
+
+        let clash =
+            r#"[{ "type": "NINA.Sequencer.SequenceItem.Guider.Dither", "name": "Dither" }]"#;
+        assert!(parse_plugin_types(clash).is_err());
+
+        let state = AppState::default();
+        set_plugin_types(&state, types);
+        let plugins = plugin_types(&state);
+        let schema = get_schema(
+            "DaleGhent.NINA.GroundStation.SendToPushover.SendToPushover, DaleGhent.NINA.GroundStation",
+            &plugins,
+        )
+        .unwrap();
+        assert_eq!(schema.fields[0].max, Some(2.0));
+        assert!(is_container_type(
+            "Plugin.Sequencer.LoopingSet, Plugin",
+            &plugins
+        ));
+        assert!(!is_container_type(
+            "Plugin.Sequencer.LoopingSet, Plugin",
+            &[]
+        ));
+        assert_eq!(list_types(Some("groundstation"), &plugins).len(), 1);
+        assert!(find_plugin_type(&state, "Plugin.Sequencer.LoopingSet").is_some());
+    }
+}
@@ -0,0 +1,537 @@
+//! Observing constraint evaluation
+//!
+//! Applies a target's [`ObservingConstraints`] to its visibility: the
+//! scheduler, conflict detection and date validation use the constrained
+//! window instead of the plain altitude window. [`calculate_imaging_window`]
+//! further intersects the constraints with astronomical darkness and a
+//! meridian flip exclusion, giving the segments of a night that can
+//! actually be imaged.
+
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::models::coordinates::angular_separation;
+use crate::models::{Coordinates, ObservingConstraints, SimpleTarget};
+use crate::services::astronomy::{
+    air_mass, calculate_visibility_window, datetime_to_jd, hour_angle, jd_to_datetime,
+    moon_illumination, moon_position, observed_alt_az, sun_altitude, ObserverLocation,
+    VisibilityWindow,
+};
+use crate::services::dark_calendar;
+use crate::services::sequence_optimizer::DEFAULT_MIN_ALTITUDE;
+
+/// Samples per day, matching `calculate_visibility_window`
+const SAMPLES_PER_DAY: usize = 144;
+
+/// Samples per night for imaging windows (every 5 minutes)
+const IMAGING_SAMPLES_PER_DAY: usize = 288;
+
+/// Constraint that can keep a target from being imaged
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ConstraintKind {
+    MinAltitude,
+    MaxAltitude,
+    MaxAirmass,
+    HourAngle,
+    MoonSeparation,
+    MoonIllumination,
+    TimeWindow,
+}
+
+impl ConstraintKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            ConstraintKind::MinAltitude => "minimum altitude",
+            ConstraintKind::MaxAltitude => "maximum altitude",
+            ConstraintKind::MaxAirmass => "maximum airmass",
+            ConstraintKind::HourAngle => "hour angle range",
+            ConstraintKind::MoonSeparation => "minimum moon separation",
+            ConstraintKind::MoonIllumination => "maximum moon illumination",
+            ConstraintKind::TimeWindow => "time window",
+        }
+    }
+}
+
+/// Sky conditions of a target at one instant
+#[derive(Debug, Clone, Copy)]
+struct SkyState {
+    time: DateTime<Utc>,
+    altitude: f64,
+    /// Hours
+    hour_angle: f64,
+    /// Only computed when a moon constraint is set
+    moon_separation: Option<f64>,
+    moon_illumination: Option<f64>,
+}
+
+fn has_moon_constraint(constraints: &ObservingConstraints) -> bool {
+    constraints.min_moon_separation.is_some() || constraints.max_moon_illumination.is_some()
+}
+
+fn sky_state(
+    coords: &Coordinates,
+    location: &ObserverLocation,
+    time: DateTime<Utc>,
+    with_moon: bool,
+) -> SkyState {
+    let jd = datetime_to_jd(time);
+    let ra = coords.ra_to_decimal();
+    let (altitude, _) = observed_alt_az(ra, coords.dec_to_decimal(), location, jd);
+    let (moon_separation, moon_illumination) = if with_moon {
+        let (moon_ra, moon_dec, _) = moon_position(jd);
+        let moon = Coordinates::from_decimal(moon_ra, moon_dec);
+        (
+            Some(angular_separation(coords, &moon)),
+            Some(moon_illumination(jd)),
+        )
+    } else {
+        (None, None)
+    };
+    SkyState {
+        time,
+        altitude,
+        hour_angle: hour_angle(ra, location.longitude, jd) / 15.0,
+        moon_separation,
+        moon_illumination,
+    }
+}
+
+/// Constraints violated in `state`. The minimum altitude falls back to
+/// `default_min_altitude`; the latest start is not checked here since it
+/// only limits when imaging may begin.
+fn violations(
+    constraints: &ObservingConstraints,
+    state: &SkyState,
+    default_min_altitude: f64,
+) -> Vec<ConstraintKind> {
+    let mut failed = Vec::new();
+    let min_altitude = constraints.min_altitude.unwrap_or(default_min_altitude);
+
+    if state.altitude < min_altitude {
+        failed.push(ConstraintKind::MinAltitude);
+    }
+    if constraints
+        .max_altitude
+        .is_some_and(|max| state.altitude > max)
+    {
+        failed.push(ConstraintKind::MaxAltitude);
+    }
+    if let Some(max) = constraints.max_airmass {
+        if air_mass(state.altitude).map_or(true, |airmass| airmass > max) {
+            failed.push(ConstraintKind::MaxAirmass);
+        }
+    }
+    if constraints
+        .min_hour_angle
+        .is_some_and(|min| state.hour_angle < min)
+        || constraints
+            .max_hour_angle
+            .is_some_and(|max| state.hour_angle > max)
+    {
+        failed.push(ConstraintKind::HourAngle);
+    }
+    if let (Some(min), Some(separation)) = (constraints.min_moon_separation, state.moon_separation)
+    {
+        if separation < min {
+            failed.push(ConstraintKind::MoonSeparation);
+        }
+    }
+    if let (Some(max), Some(illumination)) =
+        (constraints.max_moon_illumination, state.moon_illumination)
+    {
+        if illumination > max {
+            failed.push(ConstraintKind::MoonIllumination);
+        }
+    }
+    if constraints
+        .earliest_start
+        .is_some_and(|earliest| state.time < earliest)
+    {
+        failed.push(ConstraintKind::TimeWindow);
+    }
+
+    failed
+}
+
+fn day_samples(
+    target: &SimpleTarget,
+    location: &ObserverLocation,
+    date: NaiveDate,
+) -> Vec<SkyState> {
+    let with_moon = has_moon_constraint(&target.constraints);
+    let jd_start = datetime_to_jd(date.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc());
+    (0..=SAMPLES_PER_DAY)
+        .map(|i| {
+            let time = jd_to_datetime(jd_start + i as f64 / SAMPLES_PER_DAY as f64);
+            sky_state(&target.coordinates, location, time, with_moon)
+        })
+        .collect()
+}
+
+/// Whether the target meets its constraints at `time`
+pub fn constraints_met_at(
+    target: &SimpleTarget,
+    location: &ObserverLocation,
+    time: DateTime<Utc>,
+    default_min_altitude: f64,
+) -> bool {
+    let with_moon = has_moon_constraint(&target.constraints);
+    let state = sky_state(&target.coordinates, location, time, with_moon);
+    violations(&target.constraints, &state, default_min_altitude).is_empty()
+}
+
+/// Visibility window of a target on `date`, narrowed to the first stretch
+/// in which all of its constraints are met. Without constraints this is
+/// the plain window above `default_min_altitude`.
+pub fn constrained_visibility_window(
+    target: &SimpleTarget,
+    location: &ObserverLocation,
+    date: NaiveDate,
+    default_min_altitude: f64,
+) -> VisibilityWindow {
+    if target.constraints.is_empty() {
+        return calculate_visibility_window(
+            &target.coordinates,
+            location,
+            date,
+            default_min_altitude,
+        );
+    }
+
+    let samples = day_samples(target, location, date);
+    let mut start_time: Option<DateTime<Utc>> = None;
+    let mut end_time: Option<DateTime<Utc>> = None;
+    let mut max_altitude = -90.0;
+    let mut max_altitude_time = samples[0].time;
+
+    for state in &samples {
+        if state.altitude > max_altitude {
+            max_altitude = state.altitude;
+            max_altitude_time = state.time;
+        }
+        if end_time.is_some() {
+            continue;
+        }
+        let met = violations(&target.constraints, state, default_min_altitude).is_empty();
+        match (met, start_time) {
+            (true, None) => start_time = Some(state.time),
+            (false, Some(_)) => end_time = Some(state.time),
+            _ => {}
+        }
+    }
+
+    let start_time = start_time.filter(|start| {
+        target
+            .constraints
+            .latest_start
+            .map_or(true, |latest| *start <= latest)
+    });
+    let end_time = end_time.or_else(|| start_time.and(samples.last().map(|s| s.time)));
+
+    let duration_hours = match (start_time, end_time) {
+        (Some(s), Some(e)) => (e - s).num_minutes() as f64 / 60.0,
+        _ => 0.0,
+    };
+
+    VisibilityWindow {
+        start_time: start_time.unwrap_or(samples[0].time),
+        end_time: end_time
+            .filter(|_| start_time.is_some())
+            .unwrap_or(samples[0].time),
+        max_altitude,
+        max_altitude_time,
+        duration_hours,
+        is_visible: start_time.is_some(),
+    }
+}
+
+/// Constraints that alone keep a target from being imaged on `date`
+/// although it rises above `default_min_altitude`
+pub fn blocking_constraints(
+    target: &SimpleTarget,
+    location: &ObserverLocation,
+    date: NaiveDate,
+    default_min_altitude: f64,
+) -> Vec<ConstraintKind> {
+    if target.constraints.is_empty() {
+        return Vec::new();
+    }
+
+    let unconstrained = ObservingConstraints::default();
+    let samples: Vec<(SkyState, Vec<ConstraintKind>)> = day_samples(target, location, date)
+        .into_iter()
+        .filter(|state| violations(&unconstrained, state, default_min_altitude).is_empty())
+        .map(|state| {
+            let mut failed = violations(&target.constraints, &state, default_min_altitude);
+            if target
+                .constraints
+                .latest_start
+                .is_some_and(|latest| state.time > latest)
+            {
+                failed.push(ConstraintKind::TimeWindow);
+            }
+            (state, failed)
+        })
+        .collect();
+    if samples.is_empty() {
+        return Vec::new();
+    }
+
+    let mut blocking: Vec<ConstraintKind> = Vec::new();
+    for (_, failed) in &samples {
+        for kind in failed {
+            if !blocking.contains(kind) && samples.iter().all(|(_, failed)| failed.contains(kind)) {
+                blocking.push(*kind);
+            }
+        }
+    }
+    blocking
+}
+
+/// Options of [`calculate_imaging_window`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ImagingWindowOptions {
+    /// Sun altitude in degrees below which the sky counts as dark
+    pub max_sun_altitude: f64,
+    /// Minimum target altitude when the constraints set none
+    pub default_min_altitude: f64,
+    /// Minutes before the meridian kept free for a meridian flip
+    pub flip_minutes_before: f64,
+    /// Minutes after the meridian kept free for a meridian flip
+    pub flip_minutes_after: f64,
+}
+
+impl Default for ImagingWindowOptions {
+    fn default() -> Self {
+        Self {
+            max_sun_altitude: -18.0,
+            default_min_altitude: DEFAULT_MIN_ALTITUDE,
+            flip_minutes_before: 0.0,
+            flip_minutes_after: 0.0,
+        }
+    }
+}
+
+/// Stretch of a night in which a target can be imaged
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImagingSegment {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    pub duration_hours: f64,
+}
+
+/// Usable imaging time of a target over one night
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImagingWindow {
+    /// In time order; more than one when a constraint or the meridian
+    /// flip interrupts the night
+    pub segments: Vec<ImagingSegment>,
+    pub total_hours: f64,
+    /// Start and end of darkness, if the sun gets low enough
+    pub dark_start: Option<DateTime<Utc>>,
+    pub dark_end: Option<DateTime<Utc>>,
+}
+
+/// Segments of the night starting on `date` (from local noon) in which
+/// the sky is dark, `constraints` are met and the target is clear of the
+/// meridian flip exclusion. Segments starting after the latest start are
+/// dropped.
+pub fn calculate_imaging_window(
+    coordinates: &Coordinates,
+    location: &ObserverLocation,
+    date: NaiveDate,
+    constraints: &ObservingConstraints,
+    options: &ImagingWindowOptions,
+) -> ImagingWindow {
+    let with_moon = has_moon_constraint(constraints);
+    let night_start = dark_calendar::night_start(location, date);
+    let jd_start = datetime_to_jd(night_start);
+    let flip = (
+        -options.flip_minutes_before / 60.0,
+        options.flip_minutes_after / 60.0,
+    );
+
+    let mut segments = Vec::new();
+    let mut open: Option<DateTime<Utc>> = None;
+    let mut dark_start = None;
+    let mut dark_end = None;
+    let mut close = |start: DateTime<Utc>, end: DateTime<Utc>| {
+        if constraints
+            .latest_start
+            .map_or(true, |latest| start <= latest)
+        {
+            segments.push(ImagingSegment {
+                start,
+                end,
+                duration_hours: (end - start).num_minutes() as f64 / 60.0,
+            });
+        }
+    };
+
+    for i in 0..=IMAGING_SAMPLES_PER_DAY {
+        let jd = jd_start + i as f64 / IMAGING_SAMPLES_PER_DAY as f64;
+        let time = jd_to_datetime(jd);
+        let dark = sun_altitude(location, jd) <= options.max_sun_altitude;
+        match (dark, dark_start, dark_end) {
+            (true, None, _) => dark_start = Some(time),
+            (false, Some(_), None) => dark_end = Some(time),
+            _ => {}
+        }
+
+        let usable = dark && {
+            let state = sky_state(coordinates, location, time, with_moon);
+            let flipping = flip.0 < flip.1 && (flip.0..flip.1).contains(&state.hour_angle);
+            !flipping && violations(constraints, &state, options.default_min_altitude).is_empty()
+        };
+        match (usable, open) {
+            (true, None) => open = Some(time),
+            (false, Some(start)) => {
+                close(start, time);
+                open = None;
+            }
+            _ => {}
+        }
+    }
+    let last = jd_to_datetime(jd_start + 1.0);
+    if let Some(start) = open {
+        close(start, last);
+    }
+    let dark_end = dark_end.or(dark_start.map(|_| last));
+
+    let total_hours = segments.iter().map(|s| s.duration_hours).sum();
+    ImagingWindow {
+        segments,
+        total_hours,
+        dark_start,
+        dark_end,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn location() -> ObserverLocation {
+        ObserverLocation {
+            latitude: 40.0,
+            longitude: -105.0,
+            elevation: 1600.0,
+            ..Default::default()
+        }
+    }
+
+    fn m31() -> SimpleTarget {
+        SimpleTarget {
+            coordinates: Coordinates::from_decimal(0.712, 41.27),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_window_without_constraints_matches_plain_window() {
+        let date = NaiveDate::from_ymd_opt(2024, 10, 15).unwrap();
+        let target = m31();
+        let plain = calculate_visibility_window(&target.coordinates, &location(), date, 20.0);
+        let window = constrained_visibility_window(&target, &location(), date, 20.0);
+        assert_eq!(window.start_time, plain.start_time);
+        assert_eq!(window.duration_hours, plain.duration_hours);
+    }
+
+    #[test]
+    fn test_constraints_narrow_window() {
+        let date = NaiveDate::from_ymd_opt(2024, 10, 15).unwrap();
+        let mut target = m31();
+        let plain = constrained_visibility_window(&target, &location(), date, 20.0);
+
+        target.constraints.min_altitude = Some(60.0);
+        let high = constrained_visibility_window(&target, &location(), date, 20.0);
+        assert!(high.is_visible);
+        assert!(high.duration_hours < plain.duration_hours);
+
+        target.constraints.min_hour_angle = Some(0.0);
+        let west = constrained_visibility_window(&target, &location(), date, 20.0);
+        assert!(west.is_visible);
+        assert!(west.start_time >= high.start_time);
+        let state = sky_state(&target.coordinates, &location(), west.start_time, false);
+        assert!(state.hour_angle >= 0.0);
+
+        target.constraints = ObservingConstraints {
+            max_airmass: Some(1.5),
+            earliest_start: Some(Utc.with_ymd_and_hms(2024, 10, 16, 0, 0, 0).unwrap()),
+            ..Default::default()
+        };
+        let late = constrained_visibility_window(&target, &location(), date, 20.0);
+        assert!(!late.is_visible);
+    }
+
+    #[test]
+    fn test_blocking_constraints() {
+        let date = NaiveDate::from_ymd_opt(2024, 10, 15).unwrap();
+        let mut target = m31();
+        assert!(blocking_constraints(&target, &location(), date, 20.0).is_empty());
+
+        target.constraints.min_altitude = Some(89.5);
+        target.constraints.max_moon_illumination = Some(100.0);
+        assert_eq!(
+            blocking_constraints(&target, &location(), date, 20.0),
+            vec![ConstraintKind::MinAltitude]
+        );
+        assert!(!constrained_visibility_window(&target, &location(), date, 20.0).is_visible);
+    }
+
+    #[test]
+    fn test_imaging_window() {
+        let location = ObserverLocation {
+            timezone_offset: -7,
+            ..location()
+        };
+        let date = NaiveDate::from_ymd_opt(2024, 10, 15).unwrap();
+        let target = m31();
+        let options = ImagingWindowOptions::default();
+
+        let window = calculate_imaging_window(
+            &target.coordinates,
+            &location,
+            date,
+            &target.constraints,
+            &options,
+        );
+        assert_eq!(window.segments.len(), 1);
+        let segment = &window.segments[0];
+        assert!(segment.start >= window.dark_start.unwrap());
+        assert!(segment.end <= window.dark_end.unwrap());
+        assert!(window.total_hours > 6.0);
+
+        // A flip exclusion splits the night around the meridian
+        let options = ImagingWindowOptions {
+            flip_minutes_before: 10.0,
+            flip_minutes_after: 20.0,
+            ..options
+        };
+        let split = calculate_imaging_window(
+            &target.coordinates,
+            &location,
+            date,
+            &target.constraints,
+            &options,
+        );
+        assert_eq!(split.segments.len(), 2);
+        let gap = split.segments[1].start - split.segments[0].end;
+        assert!((25..=40).contains(&gap.num_minutes()));
+        let state = sky_state(&target.coordinates, &location, split.segments[0].end, false);
+        assert!(state.hour_angle.abs() < 0.25);
+
+        let constraints = ObservingConstraints {
+            latest_start: Some(split.segments[0].end),
+            ..Default::default()
+        };
+        let early =
+            calculate_imaging_window(&target.coordinates, &location, date, &constraints, &options);
+        assert_eq!(early.segments.len(), 1);
+    }
+}
@@ -3,10 +3,17 @@
 #[cfg(test)]
 mod tests {
     use super::super::astronomy::ObserverLocation;
+    use super::super::job_queue::JobHandle;
     use super::super::sequence_optimizer::*;
-    use crate::models::common::{BinningMode, ImageType, SequenceEntityStatus, SequenceMode};
-    use crate::models::{Coordinates, SimpleExposure, SimpleSequence, SimpleTarget};
-    use chrono::{NaiveDate, Utc};
+    use crate::models::common::{
+        BinningMode, ImageType, SequenceEntityStatus, SequenceMode, TargetPriority,
+    };
+    use crate::models::{
+        Coordinates, FilterInfo, MountProfile, OverheadProfile, SimpleExposure, SimpleSequence,
+        SimpleTarget,
+    };
+    use chrono::{Duration, NaiveDate, Utc};
+    use std::collections::HashMap;
 
     fn test_location() -> ObserverLocation {
         ObserverLocation {
@@ -14,6 +21,8 @@ mod tests {
             longitude: -74.0060,
             elevation: 10.0,
             timezone_offset: -5,
+            sky_brightness: None,
+            corrections: Default::default(),
         }
     }
 
@@ -28,6 +37,7 @@ mod tests {
         seq
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn create_test_target(
         name: &str,
         ra_h: i32,
@@ -53,6 +63,10 @@ mod tests {
             center_target: true,
             rotate_target: false,
             start_guiding: true,
+            priority: TargetPriority::Normal,
+            min_integration: None,
+            max_integration: None,
+            constraints: Default::default(),
             auto_focus_on_start: true,
             auto_focus_on_filter_change: false,
             auto_focus_after_set_time: false,
@@ -67,6 +81,10 @@ mod tests {
             estimated_start_time: None,
             estimated_end_time: None,
             estimated_duration: None,
+            moving_target: None,
+            notes: None,
+            tags: Vec::new(),
+            metadata: Default::default(),
         }
     }
 
@@ -85,6 +103,8 @@ mod tests {
             progress_count: 0,
             dither: false,
             dither_every: 1,
+            estimated_start_time: None,
+            estimated_end_time: None,
         }
     }
 
@@ -98,7 +118,13 @@ mod tests {
         let location = test_location();
         let date = NaiveDate::from_ymd_opt(2024, 10, 15).unwrap();
 
-        let result = optimize_sequence(&seq, &location, date, OptimizationStrategy::MaxAltitude);
+        let result = optimize_sequence(
+            &seq,
+            &location,
+            date,
+            OptimizationStrategy::MaxAltitude,
+            &MountProfile::default(),
+        );
 
         assert!(result.success);
         assert_eq!(result.original_order.len(), 3);
@@ -111,7 +137,13 @@ mod tests {
         let location = test_location();
         let date = NaiveDate::from_ymd_opt(2024, 10, 15).unwrap();
 
-        let result = optimize_sequence(&seq, &location, date, OptimizationStrategy::TransitTime);
+        let result = optimize_sequence(
+            &seq,
+            &location,
+            date,
+            OptimizationStrategy::TransitTime,
+            &MountProfile::default(),
+        );
 
         assert!(result.success);
     }
@@ -122,8 +154,13 @@ mod tests {
         let location = test_location();
         let date = NaiveDate::from_ymd_opt(2024, 10, 15).unwrap();
 
-        let result =
-            optimize_sequence(&seq, &location, date, OptimizationStrategy::VisibilityStart);
+        let result = optimize_sequence(
+            &seq,
+            &location,
+            date,
+            OptimizationStrategy::VisibilityStart,
+            &MountProfile::default(),
+        );
 
         assert!(result.success);
     }
@@ -139,6 +176,7 @@ mod tests {
             &location,
             date,
             OptimizationStrategy::VisibilityDuration,
+            &MountProfile::default(),
         );
 
         assert!(result.success);
@@ -150,9 +188,17 @@ mod tests {
         let location = test_location();
         let date = NaiveDate::from_ymd_opt(2024, 10, 15).unwrap();
 
-        let result = optimize_sequence(&seq, &location, date, OptimizationStrategy::MinimizeSlew);
+        let result = optimize_sequence(
+            &seq,
+            &location,
+            date,
+            OptimizationStrategy::MinimizeSlew,
+            &MountProfile::default(),
+        );
 
         assert!(result.success);
+        assert_eq!(result.optimized_order.len(), 3);
+        assert!(result.slew_improvement_percent.unwrap() >= 0.0);
     }
 
     #[test]
@@ -161,10 +207,100 @@ mod tests {
         let location = test_location();
         let date = NaiveDate::from_ymd_opt(2024, 10, 15).unwrap();
 
-        let result = optimize_sequence(&seq, &location, date, OptimizationStrategy::Combined);
+        let result = optimize_sequence(
+            &seq,
+            &location,
+            date,
+            OptimizationStrategy::Combined,
+            &MountProfile::default(),
+        );
 
         assert!(result.success);
-        assert!(result.improvements.len() > 0);
+        assert!(!result.improvements.is_empty());
+    }
+
+    #[test]
+    fn test_optimize_sequence_priority_weighted() {
+        // Two targets competing for the same sky all night
+        let mut seq = create_test_sequence();
+        seq.targets.truncate(2);
+        seq.targets[1].coordinates = seq.targets[0].coordinates.clone();
+        for target in &mut seq.targets {
+            target.exposures[0].total_count = 1000;
+        }
+        seq.targets[1].priority = TargetPriority::High;
+        let location = test_location();
+        let date = NaiveDate::from_ymd_opt(2024, 10, 15).unwrap();
+
+        let result = optimize_sequence(
+            &seq,
+            &location,
+            date,
+            OptimizationStrategy::PriorityWeighted,
+            &MountProfile::default(),
+        );
+
+        assert_eq!(result.optimized_order[0], seq.targets[1].id);
+        assert!(result
+            .warnings
+            .iter()
+            .any(|w| w.contains("M31") && w.contains("higher-priority")));
+    }
+
+    #[test]
+    fn test_optimize_sequence_time_windowed() {
+        // M42 rises late while the Ring Nebula sets in the evening; the
+        // Ring goes first even when listed second
+        let mut seq = create_test_sequence();
+        seq.targets = vec![
+            create_test_target("M42", 5, 35, 16.0, 5, 23, 28.0, true),
+            create_test_target("M57", 18, 53, 35.0, 33, 1, 45.0, false),
+        ];
+        for target in &mut seq.targets {
+            target.exposures[0].total_count = 100;
+        }
+        let location = test_location();
+        let date = NaiveDate::from_ymd_opt(2024, 10, 15).unwrap();
+
+        let result = optimize_sequence(
+            &seq,
+            &location,
+            date,
+            OptimizationStrategy::TimeWindowed,
+            &MountProfile::default(),
+        );
+
+        assert_eq!(
+            result.optimized_order,
+            [seq.targets[1].id.clone(), seq.targets[0].id.clone()]
+        );
+        // 100 frames of 65 s each, all above the threshold after waiting
+        // for M42 to rise
+        let hours = result.scheduled_integration_hours.unwrap();
+        assert!(hours > 3.5 && hours <= 3.62, "{}", hours);
+        assert!(result.warnings.is_empty());
+    }
+
+    #[test]
+    fn test_optimize_time_windowed_west_of_greenwich() {
+        // M13 is up all of the short June night in New York
+        let mut seq = create_test_sequence();
+        seq.targets = vec![create_test_target("M13", 16, 41, 41.0, 36, 27, 35.0, false)];
+        seq.targets[0].exposures[0].total_count = 1000;
+        let location = test_location();
+        let date = NaiveDate::from_ymd_opt(2024, 6, 15).unwrap();
+
+        let result = optimize_sequence(
+            &seq,
+            &location,
+            date,
+            OptimizationStrategy::TimeWindowed,
+            &MountProfile::default(),
+        );
+
+        // Scheduling is limited to about four hours of astronomical night
+        let hours = result.scheduled_integration_hours.unwrap();
+        assert!(hours > 3.0 && hours < 6.0, "{}", hours);
     }
 
     // ============================================================================
@@ -177,7 +313,7 @@ mod tests {
         let location = test_location();
         let date = NaiveDate::from_ymd_opt(2024, 10, 15).unwrap();
 
-        let result = detect_conflicts(&seq, &location, date);
+        let result = detect_conflicts(&seq, &location, date, &[]);
 
         // With short exposures, there should be no time conflicts
         assert!(result.conflicts.len() <= 3); // May have visibility warnings
@@ -197,10 +333,31 @@ mod tests {
         let location = test_location();
         let date = NaiveDate::from_ymd_opt(2024, 10, 15).unwrap();
 
-        let result = detect_conflicts(&seq, &location, date);
+        let result = detect_conflicts(&seq, &location, date, &[]);
 
         // Should detect insufficient time conflicts
-        assert!(result.has_conflicts || result.suggestions.len() > 0);
+        assert!(result.has_conflicts || !result.suggestions.is_empty());
+    }
+
+    #[test]
+    fn test_detect_conflicts_with_constraints() {
+        let mut seq = create_test_sequence();
+        let location = test_location();
+        let date = NaiveDate::from_ymd_opt(2024, 10, 15).unwrap();
+        seq.targets[0].constraints.min_altitude = Some(89.9);
+
+        let result = detect_conflicts(&seq, &location, date, &[]);
+        let blocked: Vec<_> = result
+            .conflicts
+            .iter()
+            .filter(|c| c.conflict_type == ConflictType::ConstraintViolation)
+            .collect();
+        assert_eq!(blocked.len(), 1);
+        assert_eq!(blocked[0].target1_name, "M31");
+        assert!(blocked[0].description.contains("minimum altitude"));
+
+        let info = get_schedule_info(&seq, &location, date, None);
+        assert!(!info[0].visibility_window.is_visible);
     }
 
     // ============================================================================
@@ -212,7 +369,7 @@ mod tests {
         let seq = create_test_sequence();
         let start = Utc::now();
 
-        let results = calculate_etas_parallel(&seq, start);
+        let results = calculate_etas_parallel(&seq, start, None);
 
         assert_eq!(results.len(), 3);
 
@@ -227,7 +384,7 @@ mod tests {
         let seq = create_test_sequence();
         let start = Utc::now();
 
-        let results = calculate_etas_parallel(&seq, start);
+        let results = calculate_etas_parallel(&seq, start, None);
 
         // Each target has 10 exposures of 60s + 5s download = 650s
         for result in &results {
@@ -235,6 +392,92 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_exposure_etas_rotate() {
+        let mut target = create_test_target("Rotate", 0, 42, 44.0, 41, 16, 9.0, false);
+        target.mode = SequenceMode::Rotate;
+        target.exposures = ["a", "b"]
+            .iter()
+            .map(|id| SimpleExposure {
+                id: id.to_string(),
+                exposure_time: 60.0,
+                total_count: 10,
+                ..create_test_exposure()
+            })
+            .collect();
+        let start = Utc::now();
+
+        let etas = calculate_exposure_etas(&target, start, 0.0, None);
+        assert_eq!(etas.len(), 2);
+        assert_eq!(etas[0].frame_count, 10);
+        assert_eq!(etas[1].eta_start, start + Duration::seconds(60));
+        assert_eq!(etas[0].eta_end, start + Duration::seconds(1140));
+        assert_eq!(etas[1].eta_end, start + Duration::seconds(1200));
+
+        target.mode = SequenceMode::Standard;
+        let etas = calculate_exposure_etas(&target, start, 0.0, None);
+        assert_eq!(etas[0].eta_end, start + Duration::seconds(600));
+        assert_eq!(etas[1].eta_start, start + Duration::seconds(600));
+    }
+
+    #[test]
+    fn test_exposure_etas_with_overheads() {
+        let mut target = create_test_target("Overheads", 0, 42, 44.0, 41, 16, 9.0, false);
+        target.auto_focus_on_start = false;
+        target.auto_focus_on_filter_change = true;
+        target.exposures = [("a", "L", 2, true), ("b", "Ha", 1, false)]
+            .iter()
+            .map(|&(id, filter, count, dither)| SimpleExposure {
+                id: id.to_string(),
+                filter: Some(FilterInfo {
+                    name: filter.to_string(),
+                    ..Default::default()
+                }),
+                total_count: count,
+                dither,
+                ..create_test_exposure()
+            })
+            .collect();
+        let overheads = OverheadProfile {
+            dither_settle_time: 10.0,
+            filter_change_time: 5.0,
+            autofocus_duration: 100.0,
+            ..Default::default()
+        };
+        let start = Utc::now();
+
+        // a: 0-60, settle, 70-130, settle; filter change and autofocus; b: 245-305
+        let etas = calculate_exposure_etas(&target, start, 0.0, Some(&overheads));
+        assert_eq!(etas[0].eta_end, start + Duration::seconds(130));
+        assert_eq!(etas[1].eta_start, start + Duration::seconds(245));
+        assert_eq!(etas[1].eta_end, start + Duration::seconds(305));
+        assert_eq!(target.runtime_with_overheads(0.0, Some(&overheads)), 305.0);
+
+        let mut seq = SimpleSequence {
+            estimated_download_time: 0.0,
+            targets: vec![target.clone(), target],
+            ..Default::default()
+        };
+        let results = calculate_etas_parallel(&seq, start, Some(&overheads));
+        assert_eq!(results[1].eta_start, Some(start + Duration::seconds(305)));
+        assert_eq!(
+            results[1].exposures[1].eta_end,
+            start + Duration::seconds(610)
+        );
+
+        seq.calculate_etas(Some(&overheads));
+        let second = &seq.targets[1];
+        let target_start = second.estimated_start_time.unwrap();
+        assert_eq!(
+            second.exposures[1].estimated_start_time,
+            Some(target_start + Duration::seconds(245))
+        );
+        assert_eq!(
+            second.exposures[1].estimated_end_time,
+            second.estimated_end_time
+        );
+    }
+
     // ============================================================================
     // Visibility Calculation Tests
     // ============================================================================
@@ -245,7 +488,14 @@ mod tests {
         let location = test_location();
         let date = NaiveDate::from_ymd_opt(2024, 10, 15).unwrap();
 
-        let results = calculate_visibility_parallel(&seq.targets, &location, date, 20.0);
+        let results = calculate_visibility_parallel(
+            &seq.targets,
+            &location,
+            date,
+            20.0,
+            &JobHandle::detached(),
+        )
+        .unwrap();
 
         assert_eq!(results.len(), 3);
     }
@@ -260,7 +510,7 @@ mod tests {
         let location = test_location();
         let date = NaiveDate::from_ymd_opt(2024, 10, 15).unwrap();
 
-        let info = get_schedule_info(&seq, &location, date);
+        let info = get_schedule_info(&seq, &location, date, None);
 
         assert_eq!(info.len(), 3);
 
@@ -294,7 +544,7 @@ mod tests {
         let first_id = seq.targets[0].id.clone();
 
         // Only include first target
-        apply_optimized_order(&mut seq, &[first_id.clone()]);
+        apply_optimized_order(&mut seq, std::slice::from_ref(&first_id));
 
         assert_eq!(seq.targets.len(), 1);
         assert_eq!(seq.targets[0].id, first_id);
@@ -360,6 +610,167 @@ mod tests {
         }
     }
 
+    // ============================================================================
+    // Exposure Count Rebalancing Tests
+    // ============================================================================
+
+    #[test]
+    fn test_rebalance_proportional_fills_night() {
+        let seq = create_test_sequence();
+        let location = test_location();
+        let date = NaiveDate::from_ymd_opt(2024, 10, 15).unwrap();
+
+        let result = rebalance_exposure_counts(
+            &seq,
+            &location,
+            date,
+            RebalanceMode::Proportional,
+            &HashMap::new(),
+        );
+
+        // 65 s per frame, so rounding leaves at most one frame per target
+        assert!(result.available_seconds > 8.0 * 3600.0);
+        assert!(result.proposed_seconds <= result.available_seconds);
+        assert!(result.available_seconds - result.proposed_seconds < 3.0 * 65.0);
+        assert_eq!(result.targets.len(), 3);
+        for target in &result.targets {
+            assert!(target.proposed_seconds <= target.observable_seconds);
+            let frames: i32 = target.exposures.iter().map(|e| e.proposed_count).sum();
+            assert_eq!(frames as f64 * 65.0, target.proposed_seconds);
+        }
+    }
+
+    #[test]
+    fn test_rebalance_uses_local_night_west_of_greenwich() {
+        let seq = create_test_sequence();
+        let location = test_location();
+        let date = NaiveDate::from_ymd_opt(2024, 6, 15).unwrap();
+
+        let result = rebalance_exposure_counts(
+            &seq,
+            &location,
+            date,
+            RebalanceMode::Proportional,
+            &HashMap::new(),
+        );
+
+        // Astronomical darkness in New York in June lasts about four hours
+        assert!(result.available_seconds > 3.0 * 3600.0);
+        assert!(result.available_seconds < 6.0 * 3600.0);
+    }
+
+    #[test]
+    fn test_rebalance_weighted() {
+        let mut seq = create_test_sequence();
+        seq.targets.remove(1);
+        let location = test_location();
+        let date = NaiveDate::from_ymd_opt(2024, 10, 15).unwrap();
+        let weights = HashMap::from([(seq.targets[0].id.clone(), 3.0)]);
+
+        let result =
+            rebalance_exposure_counts(&seq, &location, date, RebalanceMode::Weighted, &weights);
+
+        let heavy = &result.targets[0];
+        let light = &result.targets[1];
+        assert!(heavy.proposed_seconds > light.proposed_seconds);
+        assert!(light.proposed_seconds > 0.0);
+    }
+
+    #[test]
+    fn test_rebalance_weighted_uses_priority() {
+        let mut seq = create_test_sequence();
+        seq.targets.remove(1);
+        seq.targets[1].priority = TargetPriority::Critical;
+        let location = test_location();
+        let date = NaiveDate::from_ymd_opt(2024, 10, 15).unwrap();
+
+        let result = rebalance_exposure_counts(
+            &seq,
+            &location,
+            date,
+            RebalanceMode::Weighted,
+            &HashMap::new(),
+        );
+
+        assert!(result.targets[1].proposed_seconds > result.targets[0].proposed_seconds);
+    }
+
+    #[test]
+    fn test_rebalance_respects_integration_limits() {
+        let mut seq = create_test_sequence();
+        seq.targets[0].max_integration = Some(1800.0);
+        seq.targets[1].min_integration = Some(100.0 * 3600.0);
+        let location = test_location();
+        let date = NaiveDate::from_ymd_opt(2024, 10, 15).unwrap();
+
+        let result = rebalance_exposure_counts(
+            &seq,
+            &location,
+            date,
+            RebalanceMode::Proportional,
+            &HashMap::new(),
+        );
+
+        let capped: i32 = result.targets[0]
+            .exposures
+            .iter()
+            .map(|e| e.proposed_count)
+            .sum();
+        assert!(capped as f64 * 60.0 <= 1800.0);
+        assert!(result.warnings.iter().any(|w| w.contains("M42")));
+    }
+
+    #[test]
+    fn test_rebalance_keeps_completed_frames() {
+        let mut seq = create_test_sequence();
+        seq.targets[0].exposures[0].progress_count = 4;
+        let location = test_location();
+        let date = NaiveDate::from_ymd_opt(2024, 10, 15).unwrap();
+
+        let result = rebalance_exposure_counts(
+            &seq,
+            &location,
+            date,
+            RebalanceMode::Proportional,
+            &HashMap::new(),
+        );
+
+        let proposal = &result.targets[0].exposures[0];
+        assert_eq!(proposal.current_count, 10);
+        assert!(proposal.proposed_count >= 4);
+    }
+
+    #[test]
+    fn test_apply_exposure_counts() {
+        let mut seq = create_test_sequence();
+        seq.targets[0].exposures[0].progress_count = 3;
+        let proposals = vec![
+            ExposureCountProposal {
+                target_id: seq.targets[0].id.clone(),
+                exposure_id: seq.targets[0].exposures[0].id.clone(),
+                filter_name: None,
+                exposure_time: 60.0,
+                current_count: 10,
+                proposed_count: 1,
+            },
+            ExposureCountProposal {
+                target_id: seq.targets[1].id.clone(),
+                exposure_id: seq.targets[1].exposures[0].id.clone(),
+                filter_name: None,
+                exposure_time: 60.0,
+                current_count: 10,
+                proposed_count: 42,
+            },
+        ];
+
+        apply_exposure_counts(&mut seq, &proposals);
+
+        // Never drops below frames already taken
+        assert_eq!(seq.targets[0].exposures[0].total_count, 3);
+        assert_eq!(seq.targets[1].exposures[0].total_count, 42);
+        assert_eq!(seq.targets[2].exposures[0].total_count, 10);
+    }
+
     // ============================================================================
     // Edge Cases
     // ============================================================================
@@ -371,7 +782,13 @@ mod tests {
         let location = test_location();
         let date = NaiveDate::from_ymd_opt(2024, 10, 15).unwrap();
 
-        let result = optimize_sequence(&seq, &location, date, OptimizationStrategy::Combined);
+        let result = optimize_sequence(
+            &seq,
+            &location,
+            date,
+            OptimizationStrategy::Combined,
+            &MountProfile::default(),
+        );
 
         assert!(result.success);
         assert_eq!(result.optimized_order.len(), 0);
@@ -384,7 +801,13 @@ mod tests {
         let location = test_location();
         let date = NaiveDate::from_ymd_opt(2024, 10, 15).unwrap();
 
-        let result = optimize_sequence(&seq, &location, date, OptimizationStrategy::Combined);
+        let result = optimize_sequence(
+            &seq,
+            &location,
+            date,
+            OptimizationStrategy::Combined,
+            &MountProfile::default(),
+        );
 
         assert!(result.success);
         assert_eq!(result.optimized_order.len(), 1);
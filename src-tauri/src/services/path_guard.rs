@@ -0,0 +1,223 @@
+//! Path validation for file commands
+//!
+//! Commands receive paths from the webview, so every path is normalized
+//! and must lie inside an allowed root: the default sequence directory,
+//! the app data directory, the sequence library and directories the user
+//! approved with `add_allowed_directory` (typically the folder of a file
+//! picked in a dialog).
+
+use std::path::{Component, Path, PathBuf};
+
+use crate::error::{AppError, AppResult};
+use crate::services::{file_service, settings_service};
+use crate::state::AppState;
+
+/// Resolve `.` and `..` without touching the file system. Relative paths
+/// are rejected because they would depend on the process directory.
+pub fn normalize_lexically(path: &Path) -> AppResult<PathBuf> {
+    if path.as_os_str().is_empty() {
+        return Err(AppError::InvalidInput("Path is empty".to_string()));
+    }
+    if path.to_string_lossy().contains('\0') {
+        return Err(AppError::InvalidInput(
+            "Path contains a NUL character".to_string(),
+        ));
+    }
+    if !path.is_absolute() {
+        return Err(AppError::InvalidInput(format!(
+            "Path must be absolute: {}",
+            path.display()
+        )));
+    }
+
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::Prefix(_) | Component::RootDir | Component::Normal(_) => {
+                normalized.push(component)
+            }
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !normalized.pop() {
+                    return Err(AppError::InvalidInput(format!(
+                        "Path escapes the file system root: {}",
+                        path.display()
+                    )));
+                }
+            }
+        }
+    }
+    Ok(normalized)
+}
+
+/// Normalize a path and resolve symbolic links in its existing part, so a
+/// link inside an allowed root can't point outside it
+pub fn normalize_path(path: &Path) -> AppResult<PathBuf> {
+    let normalized = normalize_lexically(path)?;
+
+    let mut existing = normalized.as_path();
+    let mut missing = Vec::new();
+    loop {
+        if let Ok(resolved) = existing.canonicalize() {
+            let mut result = resolved;
+            result.extend(missing.iter().rev());
+            return Ok(result);
+        }
+        match (existing.file_name(), existing.parent()) {
+            (Some(name), Some(parent)) => {
+                missing.push(name.to_os_string());
+                existing = parent;
+            }
+            _ => return Ok(normalized),
+        }
+    }
+}
+
+/// Check a normalized path against normalized roots
+pub fn check_path_within(path: &Path, roots: &[PathBuf]) -> AppResult<()> {
+    if roots.iter().any(|root| path.starts_with(root)) {
+        Ok(())
+    } else {
+        Err(AppError::PermissionDenied(format!(
+            "Access denied: {} is outside the allowed directories",
+            path.display()
+        )))
+    }
+}
+
+/// Directories file commands may access
+pub fn allowed_roots(state: &AppState) -> Vec<PathBuf> {
+    let mut roots = vec![
+        file_service::get_default_save_directory(),
+        file_service::get_app_data_directory(),
+    ];
+    roots.extend(settings_service::get_library_directory(state).map(PathBuf::from));
+    roots.extend(
+        settings_service::get_allowed_directories(state)
+            .into_iter()
+            .map(PathBuf::from),
+    );
+
+    let mut normalized: Vec<PathBuf> = roots
+        .iter()
+        .filter_map(|root| normalize_path(root).ok())
+        .collect();
+    normalized.sort();
+    normalized.dedup();
+    normalized
+}
+
+/// Validate a path received from the webview, returning its normalized form
+pub fn check_path(state: &AppState, path: &str) -> AppResult<PathBuf> {
+    let normalized = normalize_path(Path::new(path))?;
+    check_path_within(&normalized, &allowed_roots(state))?;
+    Ok(normalized)
+}
+
+/// Validate a directory about to become an allowed root, returning its
+/// normalized form
+pub fn check_directory(path: &str) -> AppResult<String> {
+    let normalized = normalize_path(Path::new(path))?;
+    if !normalized.is_dir() {
+        return Err(AppError::InvalidInput(format!(
+            "Not a directory: {}",
+            normalized.display()
+        )));
+    }
+    if normalized.parent().is_none() {
+        return Err(AppError::PermissionDenied(
+            "The file system root cannot be allowed".to_string(),
+        ));
+    }
+    Ok(normalized.display().to_string())
+}
+
+/// Approve a directory for file commands. Returns the normalized path.
+pub async fn add_allowed_directory(state: &AppState, path: &str) -> AppResult<String> {
+    let normalized = check_directory(path)?;
+    settings_service::add_allowed_directory(state, &normalized).await?;
+    Ok(normalized)
+}
+
+/// Withdraw a directory approval
+pub async fn remove_allowed_directory(state: &AppState, path: &str) -> AppResult<()> {
+    let normalized = normalize_path(Path::new(path))
+        .map(|p| p.display().to_string())
+        .unwrap_or_else(|_| path.to_string());
+    settings_service::remove_allowed_directory(state, &normalized).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn root() -> PathBuf {
+        std::env::temp_dir()
+            .canonicalize()
+            .unwrap()
+            .join(format!("cobalt-guard-{}", uuid::Uuid::new_v4()))
+    }
+
+    #[test]
+    fn test_normalize_lexically() {
+        let base = root();
+        assert_eq!(
+            normalize_lexically(&base.join("a/./b/../c.json")).unwrap(),
+            base.join("a/c.json")
+        );
+        assert!(normalize_lexically(Path::new("relative/file.json")).is_err());
+        assert!(normalize_lexically(Path::new("")).is_err());
+    }
+
+    #[test]
+    fn test_traversal_outside_root_is_rejected() {
+        let base = root();
+        std::fs::create_dir_all(base.join("allowed")).unwrap();
+        let roots = vec![normalize_path(&base.join("allowed")).unwrap()];
+
+        let inside = normalize_path(&base.join("allowed/new/seq.json")).unwrap();
+        assert!(check_path_within(&inside, &roots).is_ok());
+
+        let escaped = normalize_path(&base.join("allowed/../secret.txt")).unwrap();
+        assert!(check_path_within(&escaped, &roots).is_err());
+
+        // A sibling sharing the root's name as a prefix is not inside it
+        let sibling = normalize_path(&base.join("allowed-other/seq.json")).unwrap();
+        assert!(check_path_within(&sibling, &roots).is_err());
+
+        std::fs::remove_dir_all(base).unwrap();
+    }
+
+    #[test]
+    fn test_check_directory() {
+        let base = root();
+        std::fs::create_dir_all(base.join("library")).unwrap();
+        std::fs::write(base.join("file.txt"), "").unwrap();
+
+        let library = check_directory(&base.join("library/../library").display().to_string());
+        assert_eq!(library.unwrap(), base.join("library").display().to_string());
+        assert!(check_directory(&base.join("file.txt").display().to_string()).is_err());
+        assert!(check_directory(&base.join("missing").display().to_string()).is_err());
+        assert!(check_directory("relative").is_err());
+        #[cfg(unix)]
+        assert!(check_directory("/").is_err());
+
+        std::fs::remove_dir_all(base).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlink_out_of_root_is_rejected() {
+        let base = root();
+        std::fs::create_dir_all(base.join("allowed")).unwrap();
+        std::fs::create_dir_all(base.join("outside")).unwrap();
+        std::os::unix::fs::symlink(base.join("outside"), base.join("allowed/link")).unwrap();
+        let roots = vec![normalize_path(&base.join("allowed")).unwrap()];
+
+        let linked = normalize_path(&base.join("allowed/link/file.txt")).unwrap();
+        assert_eq!(linked, base.join("outside/file.txt"));
+        assert!(check_path_within(&linked, &roots).is_err());
+
+        std::fs::remove_dir_all(base).unwrap();
+    }
+}
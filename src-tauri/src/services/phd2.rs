@@ -0,0 +1,303 @@
+//! PHD2 settle times for the overhead model
+//!
+//! Dither and guiding start overheads come from how long PHD2 actually
+//! takes to settle. They are measured from PHD2's event server, which
+//! reports `GuidingDithered`, `StartGuiding` and `SettleDone` events with
+//! timestamps, or estimated from an exported PHD2 profile (`.phd`) when
+//! PHD2 is not running.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::net::TcpStream;
+
+use crate::models::OverheadProfile;
+
+/// Event server port of the first PHD2 instance
+pub const DEFAULT_EVENT_PORT: u16 = 4400;
+
+/// Guide exposures a dither typically needs to settle, for profile
+/// estimates
+const ESTIMATED_DITHER_FRAMES: f64 = 3.0;
+
+/// Guide exposures needed to start guiding and settle after a slew, for
+/// profile estimates
+const ESTIMATED_START_FRAMES: f64 = 6.0;
+
+/// Settle times measured or estimated from PHD2, in seconds
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Phd2SettleTimes {
+    /// Profile file or event server address
+    pub source: String,
+    /// Typical time from a dither to a settled guider
+    pub dither_settle_time: Option<f64>,
+    /// Typical time from starting guiding to a settled guider
+    pub guiding_start_delay: Option<f64>,
+    pub dither_samples: usize,
+    pub guiding_start_samples: usize,
+    /// Settles that timed out or failed
+    pub failed_settles: usize,
+    pub warnings: Vec<String>,
+}
+
+impl Phd2SettleTimes {
+    /// Replace the overheads PHD2 provided values for
+    pub fn apply_to(&self, overheads: &mut OverheadProfile) {
+        if let Some(seconds) = self.dither_settle_time {
+            overheads.dither_settle_time = seconds;
+        }
+        if let Some(seconds) = self.guiding_start_delay {
+            overheads.guiding_start_delay = seconds;
+        }
+    }
+}
+
+fn round_tenths(seconds: f64) -> f64 {
+    (seconds * 10.0).round() / 10.0
+}
+
+fn median(values: &[f64]) -> Option<f64> {
+    if values.is_empty() {
+        return None;
+    }
+    let mut sorted = values.to_vec();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+    let middle = sorted.len() / 2;
+    let value = if sorted.len() % 2 == 0 {
+        (sorted[middle - 1] + sorted[middle]) / 2.0
+    } else {
+        sorted[middle]
+    };
+    Some(round_tenths(value))
+}
+
+/// Parse an exported PHD2 profile. Lines are `key<TAB>type<TAB>value`.
+pub fn parse_profile(content: &str) -> HashMap<String, String> {
+    content
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.splitn(3, '\t');
+            let key = fields.next()?.trim();
+            let _kind = fields.next()?;
+            let value = fields.next().unwrap_or_default().trim();
+            key.starts_with('/')
+                .then(|| (key.to_string(), value.to_string()))
+        })
+        .collect()
+}
+
+/// Estimate settle times from profile entries. The settle time set in the
+/// profile is a minimum; the estimate adds the guide exposures a settle
+/// usually takes.
+pub fn estimate_from_profile(entries: &HashMap<String, String>, source: &str) -> Phd2SettleTimes {
+    let number = |suffix: &str| {
+        entries
+            .iter()
+            .find(|(key, _)| key.to_lowercase().ends_with(suffix))
+            .and_then(|(_, value)| value.parse::<f64>().ok())
+    };
+
+    let mut times = Phd2SettleTimes {
+        source: source.to_string(),
+        ..Default::default()
+    };
+    let Some(exposure) = number("/exposuredurationms").map(|ms| ms / 1000.0) else {
+        times
+            .warnings
+            .push("The profile has no guide exposure duration".to_string());
+        return times;
+    };
+    if exposure <= 0.0 {
+        times
+            .warnings
+            .push("The profile uses auto exposure; assuming 2 second guide exposures".to_string());
+    }
+    let exposure = if exposure > 0.0 { exposure } else { 2.0 };
+    let minimum = number("/settle/time")
+        .or_else(|| number("/settletime"))
+        .unwrap_or(0.0);
+
+    times.dither_settle_time = Some(round_tenths(
+        minimum.max(exposure * ESTIMATED_DITHER_FRAMES),
+    ));
+    times.guiding_start_delay = Some(round_tenths(minimum + exposure * ESTIMATED_START_FRAMES));
+    times.warnings.push(
+        "Estimated from the guide exposure; measure with PHD2 running for actual settle times"
+            .to_string(),
+    );
+    times
+}
+
+/// Read and estimate settle times from an exported PHD2 profile
+pub async fn import_profile(path: &Path) -> Result<Phd2SettleTimes, String> {
+    let content = tokio::fs::read_to_string(path)
+        .await
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let entries = parse_profile(&content);
+    if entries.is_empty() {
+        return Err(format!("{} is not a PHD2 profile", path.display()));
+    }
+    Ok(estimate_from_profile(&entries, &path.display().to_string()))
+}
+
+/// What a pending settle follows
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum SettleCause {
+    Dither,
+    GuidingStart,
+}
+
+/// Collects settle durations from event server messages
+#[derive(Debug, Default)]
+pub struct SettleRecorder {
+    pending: Option<(SettleCause, f64)>,
+    dither: Vec<f64>,
+    guiding_start: Vec<f64>,
+    failed: usize,
+}
+
+impl SettleRecorder {
+    /// Feed one event server line
+    pub fn record(&mut self, line: &str) {
+        let Ok(event) = serde_json::from_str::<Value>(line) else {
+            return;
+        };
+        let Some(timestamp) = event.get("Timestamp").and_then(Value::as_f64) else {
+            return;
+        };
+
+        match event.get("Event").and_then(Value::as_str) {
+            Some("GuidingDithered") => self.pending = Some((SettleCause::Dither, timestamp)),
+            Some("StartGuiding") => self.pending = Some((SettleCause::GuidingStart, timestamp)),
+            // Settling without a preceding event, e.g. a dither requested
+            // before the recording started, is not counted
+            Some("SettleDone") => {
+                let Some((cause, started)) = self.pending.take() else {
+                    return;
+                };
+                if event.get("Status").and_then(Value::as_i64).unwrap_or(0) != 0 {
+                    self.failed += 1;
+                    return;
+                }
+                let duration = timestamp - started;
+                if duration >= 0.0 {
+                    match cause {
+                        SettleCause::Dither => self.dither.push(duration),
+                        SettleCause::GuidingStart => self.guiding_start.push(duration),
+                    }
+                }
+            }
+            Some("GuidingStopped") => self.pending = None,
+            _ => {}
+        }
+    }
+
+    /// Number of completed settles
+    pub fn settle_count(&self) -> usize {
+        self.dither.len() + self.guiding_start.len()
+    }
+
+    /// Typical settle times of the recorded settles
+    pub fn finish(self, source: &str) -> Phd2SettleTimes {
+        let mut warnings = Vec::new();
+        if self.settle_count() == 0 {
+            warnings.push(
+                "No settles were observed; dither or start guiding while measuring".to_string(),
+            );
+        }
+        Phd2SettleTimes {
+            source: source.to_string(),
+            dither_settle_time: median(&self.dither),
+            guiding_start_delay: median(&self.guiding_start),
+            dither_samples: self.dither.len(),
+            guiding_start_samples: self.guiding_start.len(),
+            failed_settles: self.failed,
+            warnings,
+        }
+    }
+}
+
+/// Listen to PHD2's event server for `duration`, or until `max_settles`
+/// settles completed, and report the typical settle times
+pub async fn measure_settle_times(
+    host: &str,
+    port: u16,
+    duration: Duration,
+    max_settles: usize,
+) -> Result<Phd2SettleTimes, String> {
+    let address = format!("{}:{}", host, port);
+    let stream = tokio::time::timeout(Duration::from_secs(5), TcpStream::connect(&address))
+        .await
+        .map_err(|_| format!("Timed out connecting to PHD2 at {}", address))?
+        .map_err(|e| format!("Failed to connect to PHD2 at {}: {}", address, e))?;
+
+    let mut lines = BufReader::new(stream).lines();
+    let mut recorder = SettleRecorder::default();
+    let deadline = tokio::time::Instant::now() + duration;
+    while recorder.settle_count() < max_settles {
+        match tokio::time::timeout_at(deadline, lines.next_line()).await {
+            Ok(Ok(Some(line))) => recorder.record(&line),
+            Ok(Ok(None)) => break,
+            Ok(Err(e)) => return Err(format!("Lost connection to PHD2: {}", e)),
+            Err(_) => break,
+        }
+    }
+    Ok(recorder.finish(&address))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_estimate_from_profile() {
+        let content = "PHD Profile\n\
+            /profile/1/name\t1\tObservatory\n\
+            /profile/1/ExposureDurationMs\t3\t2000\n\
+            /profile/1/guider/Settle/Time\t3\t10\n";
+        let entries = parse_profile(content);
+        assert_eq!(entries["/profile/1/name"], "Observatory");
+
+        let times = estimate_from_profile(&entries, "test.phd");
+        assert_eq!(times.dither_settle_time, Some(10.0));
+        assert_eq!(times.guiding_start_delay, Some(22.0));
+
+        let times = estimate_from_profile(&HashMap::new(), "empty.phd");
+        assert_eq!(times.dither_settle_time, None);
+        assert!(!times.warnings.is_empty());
+    }
+
+    #[test]
+    fn test_settle_recorder() {
+        let mut recorder = SettleRecorder::default();
+        for line in [
+            r#"{"Event":"Version","Timestamp":1000.0,"PHDVersion":"2.6.13"}"#,
+            r#"{"Event":"StartGuiding","Timestamp":1000.0}"#,
+            r#"{"Event":"SettleDone","Timestamp":1025.0,"Status":0}"#,
+            r#"{"Event":"GuidingDithered","Timestamp":1300.0,"dx":1.2,"dy":-0.8}"#,
+            r#"{"Event":"SettleDone","Timestamp":1312.0,"Status":0}"#,
+            r#"{"Event":"GuidingDithered","Timestamp":1600.0}"#,
+            r#"{"Event":"SettleDone","Timestamp":1608.0,"Status":0}"#,
+            r#"{"Event":"GuidingDithered","Timestamp":1900.0}"#,
+            r#"{"Event":"SettleDone","Timestamp":1960.0,"Status":1,"Error":"timed-out"}"#,
+            "not json",
+        ] {
+            recorder.record(line);
+        }
+
+        let times = recorder.finish("localhost:4400");
+        assert_eq!(times.dither_samples, 2);
+        assert_eq!(times.dither_settle_time, Some(10.0));
+        assert_eq!(times.guiding_start_delay, Some(25.0));
+        assert_eq!(times.failed_settles, 1);
+
+        let mut overheads = OverheadProfile::default();
+        times.apply_to(&mut overheads);
+        assert_eq!(overheads.dither_settle_time, 10.0);
+        assert_eq!(overheads.guiding_start_delay, 25.0);
+    }
+}
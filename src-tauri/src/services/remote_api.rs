@@ -0,0 +1,805 @@
+//! Embedded HTTP API for remote control
+//!
+//! An optional local server, off by default, through which observatory
+//! automation scripts or a phone browser can fetch the plan open in the
+//! editor and push sequences or target lists to it. Every request needs
+//! the token from the settings, either as an `Authorization: Bearer`
+//! header or as a `token` query parameter.
+//!
+//! Changes are pushed to clients as Server-Sent Events on `/api/events`
+//! instead of over a WebSocket, so a browser's `EventSource` or `curl -N`
+//! can follow the plan without a handshake library.
+//!
+//! | Endpoint | Body | Result |
+//! |---|---|---|
+//! | `GET /api/status` | | server and plan summary |
+//! | `GET /api/sequence` | | current simple sequence |
+//! | `PUT /api/sequence` | simple sequence JSON | replaces the plan |
+//! | `POST /api/sequence/load` | `{"path"}` | loads a file into the editor |
+//! | `POST /api/sequence/save` | `{"path"}` | saves the plan to a file |
+//! | `POST /api/targets` | target array or `{"content", "format"}` | appends targets |
+//! | `POST /api/validate` | optional sequence | validation result |
+//! | `POST /api/export?format=` | optional sequence | exported file contents |
+//! | `GET /api/events` | | event stream of plan changes |
+
+use parking_lot::{Mutex, RwLock};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::broadcast;
+
+use crate::models::{RemoteApiSettings, SimpleSequence, SimpleTarget};
+use crate::services::export_service::{export_sequence, ExportFormat, ExportOptions};
+use crate::services::{
+    file_service, import_service, path_guard, serializer, settings_service, validator,
+};
+use crate::state::{AppState, SharedState};
+
+/// Event emitted to the frontend when a client changes the plan
+pub const REMOTE_API_EVENT: &str = "remote-api://received";
+
+const MAX_HEAD_SIZE: usize = 16 * 1024;
+const MAX_BODY_SIZE: usize = 16 * 1024 * 1024;
+
+/// Time allowed for a client to send its request
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Interval of keep-alive comments on the event stream
+const KEEP_ALIVE_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Plan change made by a client
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum RemoteApiEvent {
+    /// The plan was replaced, from a file if `path` is set
+    Sequence {
+        sequence: SimpleSequence,
+        path: Option<String>,
+    },
+    /// Targets were appended to the plan
+    Targets {
+        targets: Vec<SimpleTarget>,
+        format: String,
+    },
+}
+
+/// Receives client changes, e.g. to forward them to the frontend
+pub type RemoteApiListener = Box<dyn Fn(&RemoteApiEvent) + Send + Sync>;
+
+/// Server state and a summary of the plan it serves
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RemoteApiStatus {
+    pub running: bool,
+    /// Address the server listens on
+    pub address: Option<String>,
+    pub sequence_title: Option<String>,
+    pub target_count: usize,
+}
+
+/// Parsed HTTP request
+#[derive(Debug, Clone, Default)]
+pub struct HttpRequest {
+    pub method: String,
+    pub path: String,
+    pub query: HashMap<String, String>,
+    /// Header names in lower case
+    pub headers: HashMap<String, String>,
+    pub body: Vec<u8>,
+}
+
+/// HTTP response
+#[derive(Debug, Clone, PartialEq)]
+pub struct HttpResponse {
+    pub status: u16,
+    pub content_type: &'static str,
+    pub body: String,
+}
+
+struct RunningServer {
+    address: SocketAddr,
+    task: tokio::task::JoinHandle<()>,
+}
+
+/// Server and event streams, part of the [`AppState`](crate::state::AppState)
+pub struct RemoteApiState {
+    server: Mutex<Option<RunningServer>>,
+    /// Bumped on every start and stop to end the event streams of earlier
+    /// servers
+    generation: AtomicU64,
+    /// Preformatted event stream frames
+    events: broadcast::Sender<String>,
+    listener: RwLock<Option<RemoteApiListener>>,
+}
+
+impl Default for RemoteApiState {
+    fn default() -> Self {
+        Self {
+            server: Mutex::new(None),
+            generation: AtomicU64::new(0),
+            events: broadcast::channel(16).0,
+            listener: RwLock::new(None),
+        }
+    }
+}
+
+/// Install or remove the listener for client changes
+pub fn set_remote_api_listener(state: &AppState, listener: Option<RemoteApiListener>) {
+    *state.remote_api.listener.write() = listener;
+}
+
+fn notify(state: &AppState, event: &RemoteApiEvent) {
+    if let Some(listener) = state.remote_api.listener.read().as_ref() {
+        listener(event);
+    }
+}
+
+/// Event stream frame announcing the current plan
+fn sequence_frame(sequence: Option<&SimpleSequence>) -> String {
+    let data = serde_json::to_string(&sequence).unwrap_or_else(|_| "null".to_string());
+    format!("event: sequence\ndata: {}\n\n", data)
+}
+
+fn store_sequence(state: &AppState, sequence: Option<SimpleSequence>) {
+    let frame = sequence_frame(sequence.as_ref());
+    *state.open_sequence.write() = sequence;
+    // Sending fails only when no client is listening
+    let _ = state.remote_api.events.send(frame);
+}
+
+/// Publish the plan open in the editor to clients
+pub fn set_current_sequence(state: &AppState, sequence: Option<SimpleSequence>) {
+    store_sequence(state, sequence);
+}
+
+/// Plan served to clients
+pub fn current_sequence(state: &AppState) -> Option<SimpleSequence> {
+    state.open_sequence.read().clone()
+}
+
+/// Server state
+pub fn server_status(state: &AppState) -> RemoteApiStatus {
+    let address = state
+        .remote_api
+        .server
+        .lock()
+        .as_ref()
+        .map(|s| s.address.to_string());
+    let sequence = state.open_sequence.read();
+    RemoteApiStatus {
+        running: address.is_some(),
+        address,
+        sequence_title: sequence.as_ref().map(|s| s.title.clone()),
+        target_count: sequence.as_ref().map_or(0, |s| s.targets.len()),
+    }
+}
+
+/// Start the server, replacing one that is already running
+pub async fn start_server(
+    state: &SharedState,
+    settings: &RemoteApiSettings,
+) -> Result<RemoteApiStatus, String> {
+    stop_server(state);
+    if settings.token.is_empty() {
+        return Err("Remote API token is not set".to_string());
+    }
+
+    let listener = TcpListener::bind((settings.bind_address.as_str(), settings.port))
+        .await
+        .map_err(|e| {
+            format!(
+                "Failed to listen on {}:{}: {}",
+                settings.bind_address, settings.port, e
+            )
+        })?;
+    let address = listener
+        .local_addr()
+        .map_err(|e| format!("Failed to read listen address: {}", e))?;
+
+    let generation = state.remote_api.generation.fetch_add(1, Ordering::SeqCst) + 1;
+    let token = settings.token.clone();
+    let server_state = state.clone();
+    let task = tokio::spawn(async move {
+        loop {
+            match listener.accept().await {
+                Ok((stream, _)) => {
+                    tokio::spawn(handle_connection(
+                        server_state.clone(),
+                        stream,
+                        token.clone(),
+                        generation,
+                    ));
+                }
+                Err(e) => log::warn!("Remote API failed to accept a connection: {}", e),
+            }
+        }
+    });
+
+    *state.remote_api.server.lock() = Some(RunningServer { address, task });
+    log::info!("Remote API listening on {}", address);
+    Ok(server_status(state))
+}
+
+/// Stop the server and close its event streams
+pub fn stop_server(state: &AppState) {
+    if let Some(server) = state.remote_api.server.lock().take() {
+        server.task.abort();
+        state.remote_api.generation.fetch_add(1, Ordering::SeqCst);
+        log::info!("Remote API on {} stopped", server.address);
+    }
+}
+
+/// Decode `%XX` escapes and `+` in a query component
+fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => decoded.push(b' '),
+            b'%' if i + 2 < bytes.len()
+                && bytes[i + 1].is_ascii_hexdigit()
+                && bytes[i + 2].is_ascii_hexdigit() =>
+            {
+                let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).unwrap_or_default();
+                decoded.push(u8::from_str_radix(hex, 16).unwrap_or_default());
+                i += 2;
+            }
+            byte => decoded.push(byte),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// Parse the request line and headers, without the trailing blank line
+pub fn parse_request_head(head: &str) -> Result<HttpRequest, String> {
+    let mut lines = head.split("\r\n");
+    let request_line = lines.next().unwrap_or_default();
+    let mut parts = request_line.split(' ');
+    let (Some(method), Some(target), Some(version)) = (parts.next(), parts.next(), parts.next())
+    else {
+        return Err(format!("Malformed request line: {}", request_line));
+    };
+    if !version.starts_with("HTTP/1.") {
+        return Err(format!("Unsupported protocol: {}", version));
+    }
+
+    let (path, query_string) = target.split_once('?').unwrap_or((target, ""));
+    let query = query_string
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            (percent_decode(key), percent_decode(value))
+        })
+        .collect();
+
+    let mut headers = HashMap::new();
+    for line in lines.filter(|line| !line.is_empty()) {
+        let (name, value) = line
+            .split_once(':')
+            .ok_or_else(|| format!("Malformed header: {}", line))?;
+        headers.insert(name.trim().to_lowercase(), value.trim().to_string());
+    }
+
+    Ok(HttpRequest {
+        method: method.to_string(),
+        path: percent_decode(path),
+        query,
+        headers,
+        body: Vec::new(),
+    })
+}
+
+/// Read one request from a connection
+async fn read_request<R: AsyncRead + Unpin>(stream: &mut R) -> Result<HttpRequest, String> {
+    let mut buffer = Vec::new();
+    let mut chunk = [0u8; 4096];
+    let head_end = loop {
+        if let Some(position) = buffer.windows(4).position(|w| w == b"\r\n\r\n") {
+            break position;
+        }
+        if buffer.len() > MAX_HEAD_SIZE {
+            return Err("Request head too large".to_string());
+        }
+        let read = stream
+            .read(&mut chunk)
+            .await
+            .map_err(|e| format!("Failed to read request: {}", e))?;
+        if read == 0 {
+            return Err("Connection closed before the request was complete".to_string());
+        }
+        buffer.extend_from_slice(&chunk[..read]);
+    };
+
+    let head = std::str::from_utf8(&buffer[..head_end])
+        .map_err(|_| "Request head is not valid UTF-8".to_string())?;
+    let mut request = parse_request_head(head)?;
+    let length = match request.headers.get("content-length") {
+        Some(value) => value
+            .parse::<usize>()
+            .map_err(|_| format!("Invalid Content-Length: {}", value))?,
+        None => 0,
+    };
+    if length > MAX_BODY_SIZE {
+        return Err("Request body too large".to_string());
+    }
+
+    let mut body = buffer[head_end + 4..].to_vec();
+    while body.len() < length {
+        let read = stream
+            .read(&mut chunk)
+            .await
+            .map_err(|e| format!("Failed to read request body: {}", e))?;
+        if read == 0 {
+            return Err("Connection closed before the body was complete".to_string());
+        }
+        body.extend_from_slice(&chunk[..read]);
+    }
+    body.truncate(length);
+    request.body = body;
+    Ok(request)
+}
+
+/// Compare tokens in time independent of where they differ
+fn tokens_match(given: &str, expected: &str) -> bool {
+    !expected.is_empty()
+        && given.len() == expected.len()
+        && given
+            .bytes()
+            .zip(expected.bytes())
+            .fold(0u8, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+/// Whether the request carries `token`
+pub fn is_authorized(request: &HttpRequest, token: &str) -> bool {
+    let given = request
+        .headers
+        .get("authorization")
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .or_else(|| request.query.get("token").map(String::as_str));
+    given.is_some_and(|given| tokens_match(given.trim(), token))
+}
+
+impl HttpResponse {
+    fn json<T: Serialize>(value: &T) -> Self {
+        match serde_json::to_string(value) {
+            Ok(body) => Self {
+                status: 200,
+                content_type: "application/json",
+                body,
+            },
+            Err(e) => Self::error(500, &format!("Failed to serialize response: {}", e)),
+        }
+    }
+
+    fn error(status: u16, message: &str) -> Self {
+        Self {
+            status,
+            content_type: "application/json",
+            body: serde_json::json!({ "error": message }).to_string(),
+        }
+    }
+
+    fn reason(&self) -> &'static str {
+        match self.status {
+            200 => "OK",
+            400 => "Bad Request",
+            401 => "Unauthorized",
+            404 => "Not Found",
+            405 => "Method Not Allowed",
+            408 => "Request Timeout",
+            422 => "Unprocessable Entity",
+            _ => "Internal Server Error",
+        }
+    }
+
+    /// Serialized response; the connection is closed after it
+    pub fn to_bytes(&self) -> Vec<u8> {
+        format!(
+            "HTTP/1.1 {} {}\r\nContent-Type: {}; charset=utf-8\r\nContent-Length: {}\r\nCache-Control: no-store\r\nConnection: close\r\n\r\n{}",
+            self.status,
+            self.reason(),
+            self.content_type,
+            self.body.len(),
+            self.body
+        )
+        .into_bytes()
+    }
+}
+
+#[derive(Deserialize)]
+struct PathBody {
+    path: String,
+}
+
+/// Targets pushed by a client: target objects, or a file's contents in
+/// any format the importer understands
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum TargetsBody {
+    Targets(Vec<SimpleTarget>),
+    Import {
+        content: String,
+        #[serde(default)]
+        format: String,
+    },
+}
+
+fn body_text(request: &HttpRequest) -> Result<&str, HttpResponse> {
+    std::str::from_utf8(&request.body)
+        .map_err(|_| HttpResponse::error(400, "Request body is not valid UTF-8"))
+}
+
+fn parse_body<T: for<'de> Deserialize<'de>>(request: &HttpRequest) -> Result<T, HttpResponse> {
+    serde_json::from_str(body_text(request)?)
+        .map_err(|e| HttpResponse::error(400, &format!("Invalid request body: {}", e)))
+}
+
+/// The sequence in the body, or the current plan when the body is empty
+fn request_sequence(
+    state: &AppState,
+    request: &HttpRequest,
+) -> Result<SimpleSequence, HttpResponse> {
+    let text = body_text(request)?;
+    if text.trim().is_empty() {
+        return current_sequence(state)
+            .ok_or_else(|| HttpResponse::error(404, "No sequence is open"));
+    }
+    serializer::deserialize_simple_sequence_json(text)
+        .map_err(|e| HttpResponse::error(400, &format!("Invalid sequence: {}", e)))
+}
+
+fn replace_sequence(state: &AppState, sequence: SimpleSequence, path: Option<String>) {
+    store_sequence(state, Some(sequence.clone()));
+    notify(state, &RemoteApiEvent::Sequence { sequence, path });
+}
+
+/// Answer an API request
+pub async fn handle_request(state: &AppState, request: &HttpRequest, token: &str) -> HttpResponse {
+    if !is_authorized(request, token) {
+        return HttpResponse::error(401, "Missing or invalid token");
+    }
+    match route(state, request).await {
+        Ok(response) | Err(response) => response,
+    }
+}
+
+async fn route(state: &AppState, request: &HttpRequest) -> Result<HttpResponse, HttpResponse> {
+    match (request.method.as_str(), request.path.as_str()) {
+        ("GET", "/api/status") => Ok(HttpResponse::json(&server_status(state))),
+        ("GET", "/api/sequence") => current_sequence(state)
+            .map(|sequence| HttpResponse::json(&sequence))
+            .ok_or_else(|| HttpResponse::error(404, "No sequence is open")),
+        ("PUT", "/api/sequence") => {
+            let sequence = serializer::deserialize_simple_sequence_json(body_text(request)?)
+                .map_err(|e| HttpResponse::error(400, &format!("Invalid sequence: {}", e)))?;
+            replace_sequence(state, sequence, None);
+            Ok(HttpResponse::json(&server_status(state)))
+        }
+        ("POST", "/api/sequence/load") => {
+            let body: PathBody = parse_body(request)?;
+            let path = path_guard::check_path(state, &body.path)
+                .map_err(|e| HttpResponse::error(400, &e.to_string()))?;
+            let sequence = file_service::load_simple_sequence(&path)
+                .await
+                .map_err(|e| HttpResponse::error(422, &e.to_string()))?;
+            replace_sequence(state, sequence.clone(), Some(path.display().to_string()));
+            Ok(HttpResponse::json(&sequence))
+        }
+        ("POST", "/api/sequence/save") => {
+            let body: PathBody = parse_body(request)?;
+            let path = path_guard::check_path(state, &body.path)
+                .map_err(|e| HttpResponse::error(400, &e.to_string()))?;
+            let sequence = current_sequence(state)
+                .ok_or_else(|| HttpResponse::error(404, "No sequence is open"))?;
+            file_service::save_simple_sequence(&path, &sequence)
+                .await
+                .map_err(|e| HttpResponse::error(422, &e.to_string()))?;
+            Ok(HttpResponse::json(
+                &serde_json::json!({ "path": path.display().to_string() }),
+            ))
+        }
+        ("POST", "/api/targets") => push_targets(state, request),
+        ("POST", "/api/validate") => {
+            let sequence = request_sequence(state, request)?;
+            Ok(HttpResponse::json(
+                &validator::validate_simple_sequence_with_rules(
+                    &sequence,
+                    &settings_service::list_filters(state),
+                    settings_service::get_active_equipment_profile(state).as_ref(),
+                    &settings_service::get_validation_rule_configs(state),
+                ),
+            ))
+        }
+        ("POST", "/api/export") => {
+            let name = request
+                .query
+                .get("format")
+                .ok_or_else(|| HttpResponse::error(400, "Missing format parameter"))?;
+            let format = ExportFormat::from_name(name).ok_or_else(|| {
+                HttpResponse::error(400, &format!("Unknown export format: {}", name))
+            })?;
+            let sequence = request_sequence(state, request)?;
+            let result = export_sequence(
+                &sequence,
+                &ExportOptions {
+                    format,
+                    ..Default::default()
+                },
+            );
+            if !result.success {
+                return Err(HttpResponse::error(422, &result.errors.join("; ")));
+            }
+            Ok(HttpResponse {
+                status: 200,
+                content_type: "text/plain",
+                body: result.content,
+            })
+        }
+        (
+            _,
+            "/api/status" | "/api/sequence" | "/api/sequence/load" | "/api/sequence/save"
+            | "/api/targets" | "/api/validate" | "/api/export" | "/api/events",
+        ) => Err(HttpResponse::error(405, "Method not allowed")),
+        (_, path) => Err(HttpResponse::error(
+            404,
+            &format!("Unknown endpoint: {}", path),
+        )),
+    }
+}
+
+/// Append pushed targets to the plan, starting a new sequence when none
+/// is open
+fn push_targets(state: &AppState, request: &HttpRequest) -> Result<HttpResponse, HttpResponse> {
+    let (targets, format, warnings) = match parse_body::<TargetsBody>(request)? {
+        TargetsBody::Targets(targets) => (targets, "json".to_string(), Vec::new()),
+        TargetsBody::Import { content, format } => {
+            let result = import_service::detect_and_import(&content, &format)
+                .map_err(|e| HttpResponse::error(422, &e))?;
+            if result.targets.is_empty() {
+                return Err(HttpResponse::error(
+                    422,
+                    &format!("No targets found: {}", result.errors.join("; ")),
+                ));
+            }
+            (result.targets, result.source_format, result.warnings)
+        }
+    };
+
+    let mut sequence = current_sequence(state).unwrap_or_else(|| {
+        let mut sequence = SimpleSequence::new("Remote Targets");
+        sequence.targets.clear();
+        sequence
+    });
+    sequence.targets.extend(targets.iter().cloned());
+    sequence.is_dirty = true;
+    store_sequence(state, Some(sequence));
+    let imported_count = targets.len();
+    notify(state, &RemoteApiEvent::Targets { targets, format });
+
+    Ok(HttpResponse::json(&serde_json::json!({
+        "importedCount": imported_count,
+        "warnings": warnings,
+    })))
+}
+
+async fn handle_connection(
+    state: SharedState,
+    mut stream: TcpStream,
+    token: String,
+    generation: u64,
+) {
+    let response = match tokio::time::timeout(READ_TIMEOUT, read_request(&mut stream)).await {
+        Ok(Ok(request)) => {
+            if request.method == "GET"
+                && request.path == "/api/events"
+                && is_authorized(&request, &token)
+            {
+                stream_events(&state, stream, generation).await;
+                return;
+            }
+            handle_request(&state, &request, &token).await
+        }
+        Ok(Err(e)) => HttpResponse::error(400, &e),
+        Err(_) => HttpResponse::error(408, "Timed out reading the request"),
+    };
+    if let Err(e) = stream.write_all(&response.to_bytes()).await {
+        log::debug!("Remote API failed to write a response: {}", e);
+    }
+    let _ = stream.shutdown().await;
+}
+
+/// Send the current plan and every later change until the client
+/// disconnects or the server stops
+async fn stream_events(state: &AppState, mut stream: TcpStream, generation: u64) {
+    let mut events = state.remote_api.events.subscribe();
+    let head = "HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nCache-Control: no-store\r\nConnection: keep-alive\r\n\r\n";
+    let first = sequence_frame(current_sequence(state).as_ref());
+    if stream.write_all(head.as_bytes()).await.is_err()
+        || stream.write_all(first.as_bytes()).await.is_err()
+    {
+        return;
+    }
+
+    while state.remote_api.generation.load(Ordering::SeqCst) == generation {
+        let frame = match tokio::time::timeout(KEEP_ALIVE_INTERVAL, events.recv()).await {
+            Ok(Ok(frame)) => frame,
+            // Missed frames are superseded by the current plan
+            Ok(Err(broadcast::error::RecvError::Lagged(_))) => {
+                sequence_frame(current_sequence(state).as_ref())
+            }
+            Ok(Err(broadcast::error::RecvError::Closed)) => break,
+            Err(_) => ": keep-alive\n\n".to_string(),
+        };
+        if stream.write_all(frame.as_bytes()).await.is_err() {
+            break;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(method: &str, target: &str, token: Option<&str>, body: &str) -> HttpRequest {
+        let mut head = format!("{} {} HTTP/1.1\r\nHost: localhost", method, target);
+        if let Some(token) = token {
+            head.push_str(&format!("\r\nAuthorization: Bearer {}", token));
+        }
+        let mut request = parse_request_head(&head).unwrap();
+        request.body = body.as_bytes().to_vec();
+        request
+    }
+
+    fn respond(state: &AppState, request: &HttpRequest) -> HttpResponse {
+        tokio::runtime::Runtime::new()
+            .unwrap()
+            .block_on(handle_request(state, request, "secret"))
+    }
+
+    #[test]
+    fn test_parse_request_head() {
+        let request = parse_request_head(
+            "POST /api/export?format=csv&token=a%2Bb+c HTTP/1.1\r\nContent-Length: 12\r\nX-Test:  yes ",
+        )
+        .unwrap();
+        assert_eq!(request.method, "POST");
+        assert_eq!(request.path, "/api/export");
+        assert_eq!(request.query["format"], "csv");
+        assert_eq!(request.query["token"], "a+b c");
+        assert_eq!(request.headers["content-length"], "12");
+        assert_eq!(request.headers["x-test"], "yes");
+
+        // Malformed escapes are kept as they are
+        assert_eq!(percent_decode("%+1%2"), "% 1%2");
+        assert_eq!(percent_decode("%zz%41"), "%zzA");
+
+        assert!(parse_request_head("GET /").is_err());
+        assert!(parse_request_head("GET / SPDY/3").is_err());
+        assert!(parse_request_head("GET / HTTP/1.1\r\nno colon").is_err());
+    }
+
+    #[test]
+    fn test_read_request_body() {
+        let raw = b"PUT /api/sequence HTTP/1.1\r\nContent-Length: 5\r\n\r\nhello, extra";
+        let request = tokio::runtime::Runtime::new()
+            .unwrap()
+            .block_on(read_request(&mut &raw[..]))
+            .unwrap();
+        assert_eq!(request.body, b"hello");
+
+        let truncated = b"PUT /api/sequence HTTP/1.1\r\nContent-Length: 50\r\n\r\nhello";
+        assert!(tokio::runtime::Runtime::new()
+            .unwrap()
+            .block_on(read_request(&mut &truncated[..]))
+            .is_err());
+    }
+
+    #[test]
+    fn test_authorization() {
+        assert!(is_authorized(
+            &request("GET", "/api/status", Some("secret"), ""),
+            "secret"
+        ));
+        assert!(is_authorized(
+            &request("GET", "/api/status?token=secret", None, ""),
+            "secret"
+        ));
+        assert!(!is_authorized(
+            &request("GET", "/api/status", Some("secreT"), ""),
+            "secret"
+        ));
+        assert!(!is_authorized(
+            &request("GET", "/api/status", None, ""),
+            "secret"
+        ));
+        // An unset token never authorizes
+        assert!(!is_authorized(
+            &request("GET", "/api/status", Some(""), ""),
+            ""
+        ));
+
+        let state = AppState::default();
+        let response = respond(&state, &request("GET", "/api/status", None, ""));
+        assert_eq!(response.status, 401);
+    }
+
+    #[test]
+    fn test_routes() {
+        let state = AppState::default();
+        let mut sequence = SimpleSequence::new("Remote");
+        sequence.targets[0].target_name = "M31".to_string();
+        let json = serializer::serialize_simple_sequence_json(&sequence).unwrap();
+
+        let response = respond(
+            &state,
+            &request("PUT", "/api/sequence", Some("secret"), &json),
+        );
+        assert_eq!(response.status, 200);
+        let response = respond(&state, &request("GET", "/api/sequence", Some("secret"), ""));
+        assert!(response.body.contains("M31"));
+
+        let csv = r#"{"content": "Name,RA,Dec\nM42,05:35:17,-05:23:28\n", "format": "csv"}"#;
+        let response = respond(
+            &state,
+            &request("POST", "/api/targets", Some("secret"), csv),
+        );
+        assert_eq!(response.status, 200, "{}", response.body);
+        assert_eq!(current_sequence(&state).unwrap().targets.len(), 2);
+
+        let response = respond(
+            &state,
+            &request(
+                "POST",
+                "/api/export?format=stellarium",
+                Some("secret"),
+                &json,
+            ),
+        );
+        assert_eq!(response.status, 200);
+        assert!(response.body.contains("M31"));
+
+        let response = respond(
+            &state,
+            &request("POST", "/api/validate", Some("secret"), &json),
+        );
+        assert!(response.body.contains("\"valid\""));
+
+        assert_eq!(
+            respond(
+                &state,
+                &request("DELETE", "/api/sequence", Some("secret"), "")
+            )
+            .status,
+            405
+        );
+        assert_eq!(
+            respond(&state, &request("GET", "/api/nothing", Some("secret"), "")).status,
+            404
+        );
+        assert_eq!(
+            respond(
+                &state,
+                &request("POST", "/api/export?format=bmp", Some("secret"), "")
+            )
+            .status,
+            400
+        );
+    }
+
+    #[test]
+    fn test_response_bytes() {
+        let response = HttpResponse::error(404, "Gone");
+        let text = String::from_utf8(response.to_bytes()).unwrap();
+        assert!(text.starts_with("HTTP/1.1 404 Not Found\r\n"));
+        assert!(text.contains(&format!("Content-Length: {}\r\n", response.body.len())));
+        assert!(text.ends_with("{\"error\":\"Gone\"}"));
+    }
+}
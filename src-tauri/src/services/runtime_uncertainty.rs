@@ -0,0 +1,235 @@
+//! Monte Carlo runtime estimation
+//!
+//! A session estimate adds up the nominal exposure, download and overhead
+//! times, which gives one optimistic number. On the night, downloads
+//! jitter, dithers take longer to settle and autofocus runs are triggered
+//! by temperature and focus drift. [`estimate_runtime_distribution`]
+//! samples those parts many times and reports the median and 90th
+//! percentile totals, so a conservative end time can be planned.
+//!
+//! Frame-to-frame variations are independent, so the download and settle
+//! totals are drawn as one normal variate each rather than per frame.
+//! Extra autofocus runs are Poisson distributed over the imaging time.
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::services::slew_route::Rng;
+
+/// Most iterations one estimate may run
+pub const MAX_ITERATIONS: usize = 100_000;
+
+/// Above this mean, Poisson counts are drawn from the normal approximation
+const POISSON_NORMAL_THRESHOLD: f64 = 30.0;
+
+/// Spread of the uncertain parts of a session. Durations are in seconds.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct MonteCarloOptions {
+    /// Number of simulated sessions
+    pub iterations: usize,
+    /// Seed, so the same inputs give the same estimate
+    pub seed: u64,
+    /// Standard deviation of each frame's download time
+    pub download_jitter: f64,
+    /// Standard deviation of each dither settle
+    pub dither_settle_deviation: f64,
+    /// Mean number of extra autofocus runs per hour of exposure, on top of
+    /// the planned ones
+    pub autofocus_runs_per_hour: f64,
+}
+
+impl Default for MonteCarloOptions {
+    fn default() -> Self {
+        Self {
+            iterations: 2000,
+            seed: 1,
+            download_jitter: 1.0,
+            dither_settle_deviation: 5.0,
+            autofocus_runs_per_hour: 0.5,
+        }
+    }
+}
+
+impl MonteCarloOptions {
+    /// Validate the options
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+
+        if self.iterations == 0 || self.iterations > MAX_ITERATIONS {
+            errors.push(format!(
+                "Iterations must be between 1 and {}",
+                MAX_ITERATIONS
+            ));
+        }
+        let spreads = [
+            self.download_jitter,
+            self.dither_settle_deviation,
+            self.autofocus_runs_per_hour,
+        ];
+        if spreads.iter().any(|v| !v.is_finite() || *v < 0.0) {
+            errors.push("Runtime spreads cannot be negative".to_string());
+        }
+
+        errors
+    }
+}
+
+/// Nominal session parts the simulation varies. Durations are in seconds.
+#[derive(Debug, Clone, Default)]
+pub struct SessionVariables {
+    /// Nominal session total
+    pub total_time: f64,
+    /// Frames left to take
+    pub frames: u64,
+    /// Nominal download time of all frames
+    pub download_time: f64,
+    pub dithers: u64,
+    /// Nominal settle time of all dithers
+    pub dither_time: f64,
+    /// Exposure time of all frames, without downloads
+    pub exposure_time: f64,
+    /// Duration of one autofocus run
+    pub autofocus_duration: f64,
+}
+
+/// Spread of simulated session totals. Durations are in seconds.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RuntimeDistribution {
+    pub iterations: usize,
+    pub mean_seconds: f64,
+    pub p50_seconds: f64,
+    pub p90_seconds: f64,
+    /// Finish times when the session starts at the given start
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub p50_finish: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub p90_finish: Option<DateTime<Utc>>,
+}
+
+/// Poisson count with the given mean
+fn poisson(rng: &mut Rng, mean: f64) -> u64 {
+    if mean <= 0.0 {
+        return 0;
+    }
+    if mean > POISSON_NORMAL_THRESHOLD {
+        return (mean + mean.sqrt() * rng.normal()).round().max(0.0) as u64;
+    }
+    // Knuth's method
+    let limit = (-mean).exp();
+    let mut count = 0;
+    let mut product = rng.unit();
+    while product > limit {
+        count += 1;
+        product *= rng.unit();
+    }
+    count
+}
+
+/// Value at percentile `p` (0-100) of sorted `values`, by nearest rank
+fn percentile(values: &[f64], p: f64) -> f64 {
+    let rank = (p / 100.0 * values.len() as f64).ceil() as usize;
+    values[rank.clamp(1, values.len()) - 1]
+}
+
+/// Simulate the session `options.iterations` times and summarize the
+/// totals. Finish times are given when the session `start` is known.
+pub fn estimate_runtime_distribution(
+    session: &SessionVariables,
+    options: &MonteCarloOptions,
+    start: Option<DateTime<Utc>>,
+) -> RuntimeDistribution {
+    let mut rng = Rng::new(options.seed);
+    let iterations = options.iterations.max(1);
+    let download_spread = options.download_jitter * (session.frames as f64).sqrt();
+    let dither_spread = options.dither_settle_deviation * (session.dithers as f64).sqrt();
+    let autofocus_mean = options.autofocus_runs_per_hour * session.exposure_time / 3600.0;
+
+    let mut totals: Vec<f64> = (0..iterations)
+        .map(|_| {
+            let download = (session.download_time + download_spread * rng.normal()).max(0.0);
+            let dither = (session.dither_time + dither_spread * rng.normal()).max(0.0);
+            let autofocus = poisson(&mut rng, autofocus_mean) as f64 * session.autofocus_duration;
+            session.total_time - session.download_time - session.dither_time
+                + download
+                + dither
+                + autofocus
+        })
+        .collect();
+    totals.sort_by(f64::total_cmp);
+
+    let p50 = percentile(&totals, 50.0);
+    let p90 = percentile(&totals, 90.0);
+    let finish = |seconds: f64| start.map(|s| s + Duration::seconds(seconds.round() as i64));
+
+    RuntimeDistribution {
+        iterations,
+        mean_seconds: totals.iter().sum::<f64>() / iterations as f64,
+        p50_seconds: p50,
+        p90_seconds: p90,
+        p50_finish: finish(p50),
+        p90_finish: finish(p90),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn session() -> SessionVariables {
+        SessionVariables {
+            total_time: 36_000.0,
+            frames: 400,
+            download_time: 2000.0,
+            dithers: 100,
+            dither_time: 1500.0,
+            exposure_time: 24_000.0,
+            autofocus_duration: 120.0,
+        }
+    }
+
+    #[test]
+    fn test_runtime_distribution() {
+        let start = Utc.with_ymd_and_hms(2024, 10, 15, 23, 0, 0).unwrap();
+        let options = MonteCarloOptions::default();
+        let result = estimate_runtime_distribution(&session(), &options, Some(start));
+
+        assert_eq!(result.iterations, 2000);
+        assert!(result.p50_seconds <= result.p90_seconds);
+        // About 3.3 extra autofocus runs of 120 s on average
+        assert!((result.mean_seconds - 36_400.0).abs() < 30.0);
+        assert!(result.p90_seconds > 36_000.0);
+        assert_eq!(
+            result.p90_finish,
+            Some(start + Duration::seconds(result.p90_seconds.round() as i64))
+        );
+
+        // Same seed, same estimate
+        let again = estimate_runtime_distribution(&session(), &options, None);
+        assert_eq!(again.p50_seconds, result.p50_seconds);
+        assert!(again.p50_finish.is_none());
+    }
+
+    #[test]
+    fn test_runtime_distribution_without_spread() {
+        let options = MonteCarloOptions {
+            iterations: 10,
+            download_jitter: 0.0,
+            dither_settle_deviation: 0.0,
+            autofocus_runs_per_hour: 0.0,
+            ..Default::default()
+        };
+        let result = estimate_runtime_distribution(&session(), &options, None);
+        assert_eq!(result.p50_seconds, 36_000.0);
+        assert_eq!(result.p90_seconds, 36_000.0);
+
+        let options = MonteCarloOptions {
+            iterations: 0,
+            download_jitter: -1.0,
+            ..Default::default()
+        };
+        assert_eq!(options.validate().len(), 2);
+    }
+}
@@ -0,0 +1,235 @@
+//! Image sampling against the seeing
+//!
+//! Stars are sampled well when the seeing FWHM spans two to three pixels,
+//! Nyquist with some margin. With fewer, stars come out blocky and detail
+//! is lost; with more, their light is spread thinly over pixels without
+//! adding detail, costing signal-to-noise. Binning multiplies the image
+//! scale, so small pixels behind a long focal length can be brought back
+//! into range.
+//!
+//! A guide camera can sample more coarsely than the imaging camera since
+//! guiders centroid stars to a fraction of a pixel, but not arbitrarily so.
+
+use serde::{Deserialize, Serialize};
+
+use crate::models::EquipmentProfile;
+
+/// Fewest pixels across the seeing FWHM for well-sampled stars
+pub const MIN_PIXELS_PER_FWHM: f64 = 2.0;
+
+/// Most pixels across the seeing FWHM for well-sampled stars
+pub const MAX_PIXELS_PER_FWHM: f64 = 3.0;
+
+/// Below this many pixels per FWHM the sampling is grossly coarse
+pub const GROSS_UNDERSAMPLING: f64 = 1.0;
+
+/// Above this many pixels per FWHM the sampling is grossly fine
+pub const GROSS_OVERSAMPLING: f64 = 6.0;
+
+/// Highest binning considered
+pub const MAX_BINNING: i32 = 4;
+
+/// Largest guide to imaging scale ratio that still guides accurately
+pub const MAX_GUIDE_SCALE_RATIO: f64 = 5.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SamplingClass {
+    Undersampled,
+    Optimal,
+    Oversampled,
+}
+
+impl SamplingClass {
+    pub fn of(pixels_per_fwhm: f64) -> Self {
+        if pixels_per_fwhm < MIN_PIXELS_PER_FWHM {
+            SamplingClass::Undersampled
+        } else if pixels_per_fwhm > MAX_PIXELS_PER_FWHM {
+            SamplingClass::Oversampled
+        } else {
+            SamplingClass::Optimal
+        }
+    }
+}
+
+/// Whether validation should warn about this sampling
+pub fn is_gross_mismatch(pixels_per_fwhm: f64) -> bool {
+    !(GROSS_UNDERSAMPLING..=GROSS_OVERSAMPLING).contains(&pixels_per_fwhm)
+}
+
+/// Sampling at one binning
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BinnedSampling {
+    pub binning: i32,
+    /// Image scale in arcseconds per pixel
+    pub image_scale: f64,
+    pub pixels_per_fwhm: f64,
+    pub classification: SamplingClass,
+}
+
+/// Guide camera optics
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GuideOptics {
+    /// Pixel size in microns
+    pub pixel_size: f64,
+    /// Focal length in millimeters
+    pub focal_length: f64,
+}
+
+/// Guide camera scale against the imaging scale
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GuideSampling {
+    /// Guide image scale in arcseconds per pixel
+    pub image_scale: f64,
+    /// Guide scale over the unbinned imaging scale
+    pub scale_ratio: f64,
+    /// The ratio is at most [`MAX_GUIDE_SCALE_RATIO`]
+    pub suitable: bool,
+}
+
+/// Sampling of an equipment profile's camera at a seeing
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SamplingAnalysis {
+    /// Unbinned image scale in arcseconds per pixel
+    pub image_scale: f64,
+    /// Seeing FWHM in arcseconds
+    pub seeing: f64,
+    /// Unbinned pixels across the seeing FWHM
+    pub pixels_per_fwhm: f64,
+    pub classification: SamplingClass,
+    /// Binning closest to the middle of the well-sampled range
+    pub suggested_binning: i32,
+    /// Sampling at each binning up to [`MAX_BINNING`]
+    pub binnings: Vec<BinnedSampling>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub guiding: Option<GuideSampling>,
+}
+
+fn arcsec_per_pixel(pixel_size: f64, focal_length: f64) -> f64 {
+    206.265 * pixel_size / focal_length
+}
+
+/// Binning whose pixels per FWHM are closest, by ratio, to the middle of
+/// the well-sampled range
+pub fn suggested_binning(pixels_per_fwhm: f64) -> i32 {
+    let middle = (MIN_PIXELS_PER_FWHM * MAX_PIXELS_PER_FWHM).sqrt();
+    (1..=MAX_BINNING)
+        .min_by(|&a, &b| {
+            let distance = |bin: i32| (pixels_per_fwhm / bin as f64 / middle).ln().abs();
+            distance(a).total_cmp(&distance(b))
+        })
+        .unwrap_or(1)
+}
+
+/// Sampling of the profile's camera and telescope at `seeing` arcseconds
+/// FWHM
+pub fn analyze_sampling(
+    profile: &EquipmentProfile,
+    seeing: f64,
+) -> Result<SamplingAnalysis, String> {
+    if !(seeing.is_finite() && seeing > 0.0) {
+        return Err("Seeing must be above zero".to_string());
+    }
+    let image_scale = profile.image_scale();
+    if !(image_scale.is_finite() && image_scale > 0.0) {
+        return Err("The profile needs a pixel size and focal length".to_string());
+    }
+
+    let pixels_per_fwhm = seeing / image_scale;
+    let binnings = (1..=MAX_BINNING)
+        .map(|binning| {
+            let pixels = pixels_per_fwhm / binning as f64;
+            BinnedSampling {
+                binning,
+                image_scale: image_scale * binning as f64,
+                pixels_per_fwhm: pixels,
+                classification: SamplingClass::of(pixels),
+            }
+        })
+        .collect();
+
+    Ok(SamplingAnalysis {
+        image_scale,
+        seeing,
+        pixels_per_fwhm,
+        classification: SamplingClass::of(pixels_per_fwhm),
+        suggested_binning: suggested_binning(pixels_per_fwhm),
+        binnings,
+        guiding: None,
+    })
+}
+
+/// Guide camera scale against the profile's unbinned imaging scale
+pub fn analyze_guide_sampling(
+    profile: &EquipmentProfile,
+    guide: &GuideOptics,
+) -> Result<GuideSampling, String> {
+    if !(guide.pixel_size > 0.0 && guide.focal_length > 0.0) {
+        return Err("Guide pixel size and focal length must be above zero".to_string());
+    }
+    let imaging_scale = profile.image_scale();
+    if !(imaging_scale.is_finite() && imaging_scale > 0.0) {
+        return Err("The profile needs a pixel size and focal length".to_string());
+    }
+
+    let image_scale = arcsec_per_pixel(guide.pixel_size, guide.focal_length);
+    let scale_ratio = image_scale / imaging_scale;
+    Ok(GuideSampling {
+        image_scale,
+        scale_ratio,
+        suitable: scale_ratio <= MAX_GUIDE_SCALE_RATIO,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profile(pixel_size: f64, focal_length: f64) -> EquipmentProfile {
+        let mut profile = EquipmentProfile::default();
+        profile.camera.pixel_size = pixel_size;
+        profile.telescope.focal_length = focal_length;
+        profile
+    }
+
+    #[test]
+    fn test_analyze_sampling() {
+        // 3.76 µm at 2000 mm: 0.39"/px, 6.4 px across 2.5" seeing
+        let analysis = analyze_sampling(&profile(3.76, 2000.0), 2.5).unwrap();
+        assert!((analysis.image_scale - 0.3878).abs() < 1e-3);
+        assert_eq!(analysis.classification, SamplingClass::Oversampled);
+        assert!(is_gross_mismatch(analysis.pixels_per_fwhm));
+        assert_eq!(analysis.suggested_binning, 3);
+        assert_eq!(analysis.binnings[2].classification, SamplingClass::Optimal);
+
+        // 3.76 µm at 250 mm: 3.1"/px, under one pixel across 2.5"
+        let analysis = analyze_sampling(&profile(3.76, 250.0), 2.5).unwrap();
+        assert_eq!(analysis.classification, SamplingClass::Undersampled);
+        assert_eq!(analysis.suggested_binning, 1);
+
+        assert!(analyze_sampling(&profile(3.76, 0.0), 2.5).is_err());
+        assert!(analyze_sampling(&profile(3.76, 800.0), 0.0).is_err());
+    }
+
+    #[test]
+    fn test_analyze_guide_sampling() {
+        let imaging = profile(3.76, 800.0);
+        let guide = GuideOptics {
+            pixel_size: 3.75,
+            focal_length: 240.0,
+        };
+        let sampling = analyze_guide_sampling(&imaging, &guide).unwrap();
+        assert!((sampling.scale_ratio - 3.32).abs() < 0.01);
+        assert!(sampling.suitable);
+
+        let short = GuideOptics {
+            focal_length: 120.0,
+            ..guide
+        };
+        assert!(!analyze_guide_sampling(&imaging, &short).unwrap().suitable);
+    }
+}
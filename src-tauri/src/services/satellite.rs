@@ -0,0 +1,549 @@
+//! Satellite transit prediction
+//!
+//! Imports two-line element sets (TLE) and predicts satellites crossing a
+//! target's field of view. Orbits are propagated with a Keplerian model
+//! including J2 secular perturbations and the TLE mean motion derivative,
+//! which is adequate for warnings within a few days of the element epoch.
+
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::f64::consts::PI;
+
+use crate::models::Coordinates;
+use crate::services::astronomy::{
+    apparent_coordinates, datetime_to_jd, gmst, jd_to_datetime, ra_dec_to_alt_az, sun_position,
+    ObserverLocation,
+};
+use crate::state::AppState;
+
+/// Earth gravitational parameter (km^3/s^2)
+const MU: f64 = 398600.4418;
+/// Earth equatorial radius (km)
+const EARTH_RADIUS: f64 = 6378.137;
+/// Earth flattening (WGS84)
+const EARTH_FLATTENING: f64 = 1.0 / 298.257223563;
+/// Second zonal harmonic
+const J2: f64 = 1.08262668e-3;
+/// Coarse search step (seconds)
+const COARSE_STEP: i64 = 20;
+/// Default field of view radius (degrees)
+pub const DEFAULT_TRANSIT_RADIUS: f64 = 1.0;
+
+/// Two-line element set
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Tle {
+    pub name: String,
+    pub norad_id: u32,
+    /// Element epoch (JD, UTC)
+    pub epoch: f64,
+    /// Inclination (degrees)
+    pub inclination: f64,
+    /// Right ascension of the ascending node (degrees)
+    pub raan: f64,
+    pub eccentricity: f64,
+    /// Argument of perigee (degrees)
+    pub arg_perigee: f64,
+    /// Mean anomaly (degrees)
+    pub mean_anomaly: f64,
+    /// Mean motion (revolutions/day)
+    pub mean_motion: f64,
+    /// First derivative of mean motion / 2 (revolutions/day^2)
+    pub mean_motion_dot: f64,
+}
+
+/// Predicted satellite transit through a field of view
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SatelliteTransit {
+    pub satellite: String,
+    pub norad_id: u32,
+    /// Time of closest approach
+    pub time: DateTime<Utc>,
+    /// Closest distance from the field center (degrees)
+    pub separation: f64,
+    /// Satellite altitude above the horizon (degrees)
+    pub altitude: f64,
+    /// Approximate time spent inside the field (seconds)
+    pub duration: f64,
+    /// Whether the satellite is sunlit (and will leave a trail)
+    pub sunlit: bool,
+}
+
+/// TLE import result
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TleImportResult {
+    pub imported: usize,
+    pub catalog_size: usize,
+    pub errors: Vec<String>,
+}
+
+// ============================================================================
+// TLE Parsing
+// ============================================================================
+
+fn tle_checksum_valid(line: &str) -> bool {
+    let Some(expected) = line.chars().nth(68).and_then(|c| c.to_digit(10)) else {
+        return false;
+    };
+    let sum: u32 = line
+        .chars()
+        .take(68)
+        .map(|c| match c {
+            '0'..='9' => c.to_digit(10).unwrap_or(0),
+            '-' => 1,
+            _ => 0,
+        })
+        .sum();
+    sum % 10 == expected
+}
+
+fn field<T: std::str::FromStr>(line: &str, start: usize, end: usize) -> Result<T, String> {
+    line.get(start - 1..end)
+        .unwrap_or("")
+        .trim()
+        .parse()
+        .map_err(|_| format!("Invalid TLE field at columns {}-{}", start, end))
+}
+
+fn parse_tle_lines(name: &str, line1: &str, line2: &str) -> Result<Tle, String> {
+    if !tle_checksum_valid(line1) || !tle_checksum_valid(line2) {
+        return Err(format!("Checksum mismatch in TLE for '{}'", name));
+    }
+
+    let year: i32 = field(line1, 19, 20)?;
+    let year = if year < 57 { 2000 + year } else { 1900 + year };
+    let day: f64 = field(line1, 21, 32)?;
+    let jan1 = NaiveDate::from_ymd_opt(year, 1, 1)
+        .and_then(|d| d.and_hms_opt(0, 0, 0))
+        .ok_or("Invalid TLE epoch")?;
+    let epoch = datetime_to_jd(DateTime::from_naive_utc_and_offset(jan1, Utc)) + day - 1.0;
+
+    let eccentricity: f64 = format!("0.{}", line2.get(26..33).unwrap_or("").trim())
+        .parse()
+        .map_err(|_| "Invalid TLE eccentricity".to_string())?;
+
+    Ok(Tle {
+        name: name.to_string(),
+        norad_id: field(line1, 3, 7)?,
+        epoch,
+        inclination: field(line2, 9, 16)?,
+        raan: field(line2, 18, 25)?,
+        eccentricity,
+        arg_perigee: field(line2, 35, 42)?,
+        mean_anomaly: field(line2, 44, 51)?,
+        mean_motion: field(line2, 53, 63)?,
+        mean_motion_dot: field(line1, 34, 43)?,
+    })
+}
+
+/// Parse two- or three-line TLE content. Invalid sets are reported in the
+/// returned error list and skipped.
+pub fn parse_tle(content: &str) -> (Vec<Tle>, Vec<String>) {
+    let lines: Vec<&str> = content
+        .lines()
+        .map(|l| l.trim_end())
+        .filter(|l| !l.trim().is_empty())
+        .collect();
+
+    let mut tles = Vec::new();
+    let mut errors = Vec::new();
+    let mut i = 0;
+
+    while i < lines.len() {
+        let (name, line1, line2) = if lines[i].starts_with("1 ")
+            && lines.get(i + 1).is_some_and(|l| l.starts_with("2 "))
+        {
+            i += 2;
+            (None, lines[i - 2], lines[i - 1])
+        } else if lines.get(i + 1).is_some_and(|l| l.starts_with("1 "))
+            && lines.get(i + 2).is_some_and(|l| l.starts_with("2 "))
+        {
+            i += 3;
+            (Some(lines[i - 3]), lines[i - 2], lines[i - 1])
+        } else {
+            errors.push(format!("Line {}: not part of a TLE set", i + 1));
+            i += 1;
+            continue;
+        };
+
+        let name = name
+            .map(|n| n.trim_start_matches("0 ").trim().to_string())
+            .unwrap_or_else(|| format!("NORAD {}", line1.get(2..7).unwrap_or("").trim()));
+
+        match parse_tle_lines(&name, line1, line2) {
+            Ok(tle) => tles.push(tle),
+            Err(e) => errors.push(e),
+        }
+    }
+
+    (tles, errors)
+}
+
+// ============================================================================
+// Catalog
+// ============================================================================
+
+/// Add TLEs to the catalog, replacing entries with the same NORAD id
+pub fn add_to_catalog(state: &AppState, tles: Vec<Tle>) -> usize {
+    let mut catalog = state.tle_catalog.write();
+    for tle in tles {
+        match catalog.iter_mut().find(|t| t.norad_id == tle.norad_id) {
+            Some(existing) => *existing = tle,
+            None => catalog.push(tle),
+        }
+    }
+    catalog.len()
+}
+
+/// Parse TLE content and add it to the catalog
+pub fn import_tle(state: &AppState, content: &str) -> TleImportResult {
+    let (tles, errors) = parse_tle(content);
+    let imported = tles.len();
+    TleImportResult {
+        imported,
+        catalog_size: add_to_catalog(state, tles),
+        errors,
+    }
+}
+
+/// Get the loaded TLE catalog
+pub fn get_catalog(state: &AppState) -> Vec<Tle> {
+    state.tle_catalog.read().clone()
+}
+
+/// Clear the TLE catalog
+pub fn clear_catalog(state: &AppState) {
+    state.tle_catalog.write().clear();
+}
+
+// ============================================================================
+// Propagation
+// ============================================================================
+
+/// Satellite position in the true-equator-of-date frame (km)
+pub fn propagate(tle: &Tle, jd: f64) -> [f64; 3] {
+    let dt = (jd - tle.epoch) * 86400.0;
+    let n_dot = tle.mean_motion_dot * 2.0 * PI / 86400.0 / 86400.0;
+    let n0 = tle.mean_motion * 2.0 * PI / 86400.0;
+    let n = n0 + 2.0 * n_dot * dt;
+
+    let e = tle.eccentricity;
+    let a = (MU / (n * n)).cbrt();
+    let inc = tle.inclination.to_radians();
+    let p = a * (1.0 - e * e);
+    let k = 1.5 * J2 * (EARTH_RADIUS / p).powi(2) * n0;
+
+    let raan = tle.raan.to_radians() - k * inc.cos() * dt;
+    let arg_perigee = tle.arg_perigee.to_radians() + k * (2.0 - 2.5 * inc.sin().powi(2)) * dt;
+    let m = tle.mean_anomaly.to_radians()
+        + (n0 + k * (1.0 - e * e).sqrt() * (1.0 - 1.5 * inc.sin().powi(2))) * dt
+        + n_dot * dt * dt;
+    let m = m.rem_euclid(2.0 * PI);
+
+    let mut ecc = m;
+    for _ in 0..20 {
+        let delta = (ecc - e * ecc.sin() - m) / (1.0 - e * ecc.cos());
+        ecc -= delta;
+        if delta.abs() < 1e-12 {
+            break;
+        }
+    }
+
+    let nu = ((1.0 - e * e).sqrt() * ecc.sin()).atan2(ecc.cos() - e);
+    let r = a * (1.0 - e * ecc.cos());
+    let u = arg_perigee + nu;
+
+    [
+        r * (raan.cos() * u.cos() - raan.sin() * u.sin() * inc.cos()),
+        r * (raan.sin() * u.cos() + raan.cos() * u.sin() * inc.cos()),
+        r * u.sin() * inc.sin(),
+    ]
+}
+
+/// Observer position in the true-equator-of-date frame (km)
+fn observer_position(location: &ObserverLocation, jd: f64) -> [f64; 3] {
+    let lat = location.latitude.to_radians();
+    let theta = (gmst(jd) + location.longitude).to_radians();
+    let h = location.elevation / 1000.0;
+
+    let e2 = EARTH_FLATTENING * (2.0 - EARTH_FLATTENING);
+    let c = 1.0 / (1.0 - e2 * lat.sin().powi(2)).sqrt();
+    let xy = (EARTH_RADIUS * c + h) * lat.cos();
+
+    [
+        xy * theta.cos(),
+        xy * theta.sin(),
+        (EARTH_RADIUS * c * (1.0 - e2) + h) * lat.sin(),
+    ]
+}
+
+/// Topocentric RA (hours) / Dec (degrees) of a satellite, equator of date
+pub fn satellite_topocentric(tle: &Tle, location: &ObserverLocation, jd: f64) -> (f64, f64) {
+    let sat = propagate(tle, jd);
+    let obs = observer_position(location, jd);
+    let rho = [sat[0] - obs[0], sat[1] - obs[1], sat[2] - obs[2]];
+    let range = (rho[0] * rho[0] + rho[1] * rho[1] + rho[2] * rho[2]).sqrt();
+
+    let ra = (rho[1].atan2(rho[0]).to_degrees() / 15.0).rem_euclid(24.0);
+    let dec = (rho[2] / range).asin().to_degrees();
+    (ra, dec)
+}
+
+/// Whether the satellite is outside Earth's (cylindrical) shadow
+fn is_sunlit(tle: &Tle, jd: f64) -> bool {
+    let sat = propagate(tle, jd);
+    let (sun_ra, sun_dec) = sun_position(jd);
+    let (ra, dec) = ((sun_ra * 15.0).to_radians(), sun_dec.to_radians());
+    let sun = [dec.cos() * ra.cos(), dec.cos() * ra.sin(), dec.sin()];
+
+    let along = sat[0] * sun[0] + sat[1] * sun[1] + sat[2] * sun[2];
+    if along >= 0.0 {
+        return true;
+    }
+    let perp = [
+        sat[0] - along * sun[0],
+        sat[1] - along * sun[1],
+        sat[2] - along * sun[2],
+    ];
+    (perp[0] * perp[0] + perp[1] * perp[1] + perp[2] * perp[2]).sqrt() > EARTH_RADIUS
+}
+
+/// Upper bound on the apparent angular rate of a satellite (degrees/second),
+/// reached at perigee when it passes through the zenith
+fn max_angular_rate(tle: &Tle) -> f64 {
+    let n = tle.mean_motion * 2.0 * PI / 86400.0;
+    let a = (MU / (n * n)).cbrt();
+    let perigee = a * (1.0 - tle.eccentricity);
+    let speed = (MU * (2.0 / perigee - 1.0 / a)).sqrt();
+    let height = (perigee - EARTH_RADIUS).max(100.0);
+    (speed / height).to_degrees() + 360.0 / 86164.0
+}
+
+fn separation(ra1: f64, dec1: f64, ra2: f64, dec2: f64) -> f64 {
+    let (ra1, dec1) = ((ra1 * 15.0).to_radians(), dec1.to_radians());
+    let (ra2, dec2) = ((ra2 * 15.0).to_radians(), dec2.to_radians());
+    let cos_sep = dec1.sin() * dec2.sin() + dec1.cos() * dec2.cos() * (ra1 - ra2).cos();
+    cos_sep.clamp(-1.0, 1.0).acos().to_degrees()
+}
+
+// ============================================================================
+// Transit Prediction
+// ============================================================================
+
+/// Predict transits of the given satellites through a circular field of
+/// `radius` degrees centered on `coords` between `start` and `end`
+pub fn predict_transits(
+    tles: &[Tle],
+    coords: &Coordinates,
+    location: &ObserverLocation,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    radius: f64,
+) -> Vec<SatelliteTransit> {
+    let mid = start + (end - start) / 2;
+    let target = apparent_coordinates(coords, datetime_to_jd(mid));
+    let (ra, dec) = (target.ra_to_decimal(), target.dec_to_decimal());
+
+    let mut transits: Vec<SatelliteTransit> = Vec::new();
+
+    for tle in tles {
+        // The closest approach is within one coarse step of a sample, so
+        // any sample that close must be within this gate
+        let search = radius + max_angular_rate(tle) * COARSE_STEP as f64;
+        let sep_at = |jd: f64| {
+            let (sat_ra, sat_dec) = satellite_topocentric(tle, location, jd);
+            separation(ra, dec, sat_ra, sat_dec)
+        };
+
+        let mut time = start;
+        while time <= end {
+            let jd = datetime_to_jd(time);
+            if sep_at(jd) < search {
+                // Refine around the coarse sample at 0.5 s resolution
+                let window_start = jd - COARSE_STEP as f64 / 86400.0;
+                let mut best = (f64::MAX, window_start);
+                let mut inside = 0;
+                for i in 0..=(COARSE_STEP * 4) {
+                    let t = window_start + i as f64 * 0.5 / 86400.0;
+                    let sep = sep_at(t);
+                    if sep < radius {
+                        inside += 1;
+                    }
+                    if sep < best.0 {
+                        best = (sep, t);
+                    }
+                }
+
+                // Ternary search for the closest approach between samples
+                let (mut lo, mut hi) = (best.1 - 0.5 / 86400.0, best.1 + 0.5 / 86400.0);
+                for _ in 0..30 {
+                    let m1 = lo + (hi - lo) / 3.0;
+                    let m2 = hi - (hi - lo) / 3.0;
+                    if sep_at(m1) < sep_at(m2) {
+                        hi = m2;
+                    } else {
+                        lo = m1;
+                    }
+                }
+                let closest = (lo + hi) / 2.0;
+                if sep_at(closest) < best.0 {
+                    best = (sep_at(closest), closest);
+                }
+
+                let (sat_ra, sat_dec) = satellite_topocentric(tle, location, best.1);
+                let (altitude, _) = ra_dec_to_alt_az(
+                    sat_ra,
+                    sat_dec,
+                    location.latitude,
+                    location.longitude,
+                    best.1,
+                );
+
+                if best.0 < radius && altitude > 0.0 {
+                    let time = jd_to_datetime(best.1);
+                    let transit = SatelliteTransit {
+                        satellite: tle.name.clone(),
+                        norad_id: tle.norad_id,
+                        time,
+                        separation: best.0,
+                        altitude,
+                        duration: inside as f64 * 0.5,
+                        sunlit: is_sunlit(tle, best.1),
+                    };
+                    // Neighbouring samples refine the same pass; keep the closest
+                    match transits.last_mut() {
+                        Some(last)
+                            if last.norad_id == tle.norad_id
+                                && (time - last.time).num_seconds().abs() < 60 =>
+                        {
+                            if transit.separation < last.separation {
+                                *last = transit;
+                            }
+                        }
+                        _ => transits.push(transit),
+                    }
+                }
+            }
+            time += Duration::seconds(COARSE_STEP);
+        }
+    }
+
+    transits.sort_by_key(|t| t.time);
+    transits
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::CoordinateEpoch;
+
+    const ISS_TLE: &str = "ISS (ZARYA)
+1 25544U 98067A   08264.51782528 -.00002182  00000-0 -11606-4 0  2927
+2 25544  51.6416 247.4627 0006703 130.5360 325.0288 15.72125391563537
+";
+
+    #[test]
+    fn test_parse_tle() {
+        let (tles, errors) = parse_tle(ISS_TLE);
+
+        assert!(errors.is_empty());
+        assert_eq!(tles.len(), 1);
+        let iss = &tles[0];
+        assert_eq!(iss.name, "ISS (ZARYA)");
+        assert_eq!(iss.norad_id, 25544);
+        assert!((iss.eccentricity - 0.0006703).abs() < 1e-9);
+        assert!((iss.mean_motion_dot + 0.00002182).abs() < 1e-12);
+        assert!((iss.epoch - 2454730.01782528).abs() < 1e-6);
+
+        let (_, errors) = parse_tle(&ISS_TLE.replace("2927", "2928"));
+        assert_eq!(errors.len(), 1);
+    }
+
+    #[test]
+    fn test_propagate_orbit_radius() {
+        let (tles, _) = parse_tle(ISS_TLE);
+        let pos = propagate(&tles[0], tles[0].epoch + 0.3);
+        let r = (pos[0] * pos[0] + pos[1] * pos[1] + pos[2] * pos[2]).sqrt();
+
+        assert!(r > EARTH_RADIUS + 300.0 && r < EARTH_RADIUS + 450.0);
+    }
+
+    #[test]
+    fn test_predict_transits_overhead() {
+        let (tles, _) = parse_tle(ISS_TLE);
+        let iss = &tles[0];
+        let jd = iss.epoch + 0.1;
+
+        // Observer directly below the satellite, target where it appears
+        let pos = propagate(iss, jd);
+        let location = ObserverLocation {
+            latitude: (pos[2] / (pos[0] * pos[0] + pos[1] * pos[1]).sqrt())
+                .atan()
+                .to_degrees(),
+            longitude: (pos[1].atan2(pos[0]).to_degrees() - gmst(jd) + 540.0).rem_euclid(360.0)
+                - 180.0,
+            ..Default::default()
+        };
+        let (ra, dec) = satellite_topocentric(iss, &location, jd);
+        let mut coords = Coordinates::from_decimal(ra, dec);
+        coords.epoch = CoordinateEpoch::JNow;
+
+        let time = jd_to_datetime(jd);
+        let transits = predict_transits(
+            &tles,
+            &coords,
+            &location,
+            time - Duration::minutes(5),
+            time + Duration::minutes(5),
+            DEFAULT_TRANSIT_RADIUS,
+        );
+
+        assert_eq!(transits.len(), 1);
+        assert!((transits[0].time - time).num_seconds().abs() <= 2);
+        assert!(transits[0].altitude > 60.0);
+        assert!(transits[0].separation < 0.1);
+    }
+
+    #[test]
+    fn test_predict_transits_between_samples() {
+        let (tles, _) = parse_tle(ISS_TLE);
+        let iss = &tles[0];
+        let jd = iss.epoch + 0.1;
+
+        let pos = propagate(iss, jd);
+        let location = ObserverLocation {
+            latitude: (pos[2] / (pos[0] * pos[0] + pos[1] * pos[1]).sqrt())
+                .atan()
+                .to_degrees(),
+            longitude: (pos[1].atan2(pos[0]).to_degrees() - gmst(jd) + 540.0).rem_euclid(360.0)
+                - 180.0,
+            ..Default::default()
+        };
+        let (ra, dec) = satellite_topocentric(iss, &location, jd);
+        let mut coords = Coordinates::from_decimal(ra, dec);
+        coords.epoch = CoordinateEpoch::JNow;
+
+        // Closest approach falls halfway between two coarse samples, where
+        // the satellite is over ten degrees from the field on either side
+        let time = jd_to_datetime(jd);
+        let start = time - Duration::minutes(5) - Duration::seconds(COARSE_STEP / 2);
+        let half_step = jd + COARSE_STEP as f64 / 2.0 / 86400.0;
+        let (sat_ra, sat_dec) = satellite_topocentric(iss, &location, half_step);
+        assert!(separation(ra, dec, sat_ra, sat_dec) > 10.0);
+
+        let transits = predict_transits(
+            &tles,
+            &coords,
+            &location,
+            start,
+            start + Duration::minutes(10),
+            DEFAULT_TRANSIT_RADIUS,
+        );
+
+        assert_eq!(transits.len(), 1);
+        assert!((transits[0].time - time).num_seconds().abs() <= 2);
+        assert!(transits[0].separation < 0.1);
+    }
+}
@@ -0,0 +1,283 @@
+//! Sequence archives (`.ctes`)
+//!
+//! A sequence archive is a zip file holding a sequence together with what
+//! it needs on another machine: the observing site with its horizon
+//! profile, the equipment profile and target thumbnails.
+//!
+//! Layout:
+//! - `manifest.json`
+//! - `sequence.json`
+//! - `site.json` (optional)
+//! - `equipment.json` (optional)
+//! - `thumbnails/<name>` (optional)
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use tokio::fs;
+
+use crate::models::{EquipmentProfile, ObservingSite, SimpleSequence};
+use crate::services::serializer;
+use crate::services::zip_reader::read_zip;
+use crate::services::zip_writer::ZipWriter;
+
+/// File extension of sequence archives
+pub const SEQUENCE_ARCHIVE_EXTENSION: &str = "ctes";
+
+/// Current archive format version
+pub const SEQUENCE_ARCHIVE_VERSION: u32 = 1;
+
+const MANIFEST_FILE: &str = "manifest.json";
+const SEQUENCE_FILE: &str = "sequence.json";
+const SITE_FILE: &str = "site.json";
+const EQUIPMENT_FILE: &str = "equipment.json";
+const THUMBNAIL_DIRECTORY: &str = "thumbnails/";
+
+/// Image stored in an archive, usually named after its target id
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchiveThumbnail {
+    /// File name, e.g. `<target id>.png`
+    pub name: String,
+    pub data: Vec<u8>,
+}
+
+/// Archive contents
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SequenceArchive {
+    pub sequence: SimpleSequence,
+    /// Observing site, including its horizon profile
+    #[serde(default)]
+    pub site: Option<ObservingSite>,
+    #[serde(default)]
+    pub equipment_profile: Option<EquipmentProfile>,
+    #[serde(default)]
+    pub thumbnails: Vec<ArchiveThumbnail>,
+}
+
+/// Archive manifest
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchiveManifest {
+    pub version: u32,
+    pub app_version: String,
+    pub created_at: DateTime<Utc>,
+    pub title: String,
+    pub files: Vec<String>,
+}
+
+/// Reject names that would escape the thumbnail directory
+fn check_thumbnail_name(name: &str) -> Result<(), String> {
+    if name.is_empty() || name.contains(['/', '\\']) || name == "." || name == ".." {
+        return Err(format!("Invalid thumbnail name: {}", name));
+    }
+    Ok(())
+}
+
+fn to_json<T: Serialize>(value: &T) -> Result<String, String> {
+    serde_json::to_string_pretty(value).map_err(|e| format!("Failed to serialize archive: {}", e))
+}
+
+fn from_json<T: for<'de> Deserialize<'de>>(name: &str, data: &[u8]) -> Result<T, String> {
+    serde_json::from_slice(data).map_err(|e| format!("Invalid {} in archive: {}", name, e))
+}
+
+/// Build the archive bytes
+pub fn write_sequence_archive(archive: &SequenceArchive) -> Result<Vec<u8>, String> {
+    let now = Utc::now();
+    let mut zip = ZipWriter::new(now);
+    let mut files = Vec::new();
+
+    let sequence =
+        serializer::serialize_simple_sequence_json(&archive.sequence).map_err(|e| e.to_string())?;
+    zip.add_file(SEQUENCE_FILE, sequence.as_bytes())?;
+    files.push(SEQUENCE_FILE.to_string());
+
+    if let Some(site) = &archive.site {
+        zip.add_file(SITE_FILE, to_json(site)?.as_bytes())?;
+        files.push(SITE_FILE.to_string());
+    }
+
+    if let Some(profile) = &archive.equipment_profile {
+        zip.add_file(EQUIPMENT_FILE, to_json(profile)?.as_bytes())?;
+        files.push(EQUIPMENT_FILE.to_string());
+    }
+
+    for thumbnail in &archive.thumbnails {
+        check_thumbnail_name(&thumbnail.name)?;
+        let name = format!("{}{}", THUMBNAIL_DIRECTORY, thumbnail.name);
+        zip.add_file(&name, &thumbnail.data)?;
+        files.push(name);
+    }
+
+    let manifest = ArchiveManifest {
+        version: SEQUENCE_ARCHIVE_VERSION,
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        created_at: now,
+        title: archive.sequence.title.clone(),
+        files,
+    };
+    zip.add_file(MANIFEST_FILE, to_json(&manifest)?.as_bytes())?;
+
+    zip.finish()
+}
+
+/// Parse archive bytes
+pub fn read_sequence_archive(bytes: &[u8]) -> Result<SequenceArchive, String> {
+    let mut manifest: Option<ArchiveManifest> = None;
+    let mut sequence = None;
+    let mut site = None;
+    let mut equipment_profile = None;
+    let mut thumbnails = Vec::new();
+
+    for entry in read_zip(bytes)? {
+        match entry.name.as_str() {
+            MANIFEST_FILE => manifest = Some(from_json(MANIFEST_FILE, &entry.data)?),
+            SEQUENCE_FILE => {
+                let json = String::from_utf8(entry.data)
+                    .map_err(|_| format!("Invalid {} in archive", SEQUENCE_FILE))?;
+                sequence = Some(
+                    serializer::deserialize_simple_sequence_json(&json)
+                        .map_err(|e| e.to_string())?,
+                );
+            }
+            SITE_FILE => site = Some(from_json(SITE_FILE, &entry.data)?),
+            EQUIPMENT_FILE => equipment_profile = Some(from_json(EQUIPMENT_FILE, &entry.data)?),
+            name => {
+                if let Some(file) = name.strip_prefix(THUMBNAIL_DIRECTORY) {
+                    if check_thumbnail_name(file).is_ok() {
+                        thumbnails.push(ArchiveThumbnail {
+                            name: file.to_string(),
+                            data: entry.data,
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    let manifest = manifest.ok_or_else(|| "Archive has no manifest".to_string())?;
+    if manifest.version > SEQUENCE_ARCHIVE_VERSION {
+        return Err(format!(
+            "Archive version {} is newer than supported version {}",
+            manifest.version, SEQUENCE_ARCHIVE_VERSION
+        ));
+    }
+
+    Ok(SequenceArchive {
+        sequence: sequence.ok_or_else(|| "Archive has no sequence".to_string())?,
+        site,
+        equipment_profile,
+        thumbnails,
+    })
+}
+
+/// Write a sequence archive to disk
+pub async fn save_sequence_archive(path: &Path, archive: &SequenceArchive) -> Result<(), String> {
+    let bytes = write_sequence_archive(archive)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .await
+            .map_err(|e| format!("Failed to create directory: {}", e))?;
+    }
+    fs::write(path, &bytes)
+        .await
+        .map_err(|e| format!("Failed to write archive: {}", e))
+}
+
+/// Load a sequence archive from disk. The sequence's save path points at
+/// the archive.
+pub async fn load_sequence_archive(path: &Path) -> Result<SequenceArchive, String> {
+    let bytes = fs::read(path)
+        .await
+        .map_err(|e| format!("Failed to read archive: {}", e))?;
+    let mut archive = read_sequence_archive(&bytes)?;
+    archive.sequence.save_path = Some(path.display().to_string());
+    archive.sequence.is_dirty = false;
+    Ok(archive)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{HorizonPoint, SimpleTarget};
+
+    fn archive() -> SequenceArchive {
+        let mut sequence = SimpleSequence::new("Archive".to_string());
+        sequence.targets.push(SimpleTarget::default());
+        SequenceArchive {
+            sequence,
+            site: Some(ObservingSite {
+                name: "Backyard".to_string(),
+                horizon: vec![HorizonPoint {
+                    azimuth: 90.0,
+                    altitude: 25.0,
+                }],
+                ..Default::default()
+            }),
+            equipment_profile: Some(EquipmentProfile::default()),
+            thumbnails: vec![ArchiveThumbnail {
+                name: "m31.png".to_string(),
+                data: vec![0x89, b'P', b'N', b'G'],
+            }],
+        }
+    }
+
+    #[test]
+    fn test_archive_round_trip() {
+        let original = archive();
+        let bytes = write_sequence_archive(&original).unwrap();
+        let loaded = read_sequence_archive(&bytes).unwrap();
+
+        assert_eq!(loaded.sequence.title, "Archive");
+        assert_eq!(
+            loaded.sequence.targets.len(),
+            original.sequence.targets.len()
+        );
+        let site = loaded.site.unwrap();
+        assert_eq!(site.name, "Backyard");
+        assert_eq!(site.horizon.len(), 1);
+        assert_eq!(
+            loaded.equipment_profile.unwrap().id,
+            original.equipment_profile.unwrap().id
+        );
+        assert_eq!(loaded.thumbnails, original.thumbnails);
+    }
+
+    #[test]
+    fn test_archive_without_attachments() {
+        let mut original = archive();
+        original.site = None;
+        original.equipment_profile = None;
+        original.thumbnails.clear();
+
+        let loaded = read_sequence_archive(&write_sequence_archive(&original).unwrap()).unwrap();
+        assert!(loaded.site.is_none());
+        assert!(loaded.equipment_profile.is_none());
+        assert!(loaded.thumbnails.is_empty());
+    }
+
+    #[test]
+    fn test_rejects_unsafe_thumbnail_names() {
+        let mut original = archive();
+        original.thumbnails[0].name = "../evil.png".to_string();
+        assert!(write_sequence_archive(&original).is_err());
+    }
+
+    #[test]
+    fn test_rejects_newer_version() {
+        let mut zip = ZipWriter::new(Utc::now());
+        let manifest = serde_json::json!({
+            "version": SEQUENCE_ARCHIVE_VERSION + 1,
+            "appVersion": "9.9.9",
+            "createdAt": Utc::now(),
+            "title": "Future",
+            "files": [],
+        });
+        zip.add_file(MANIFEST_FILE, manifest.to_string().as_bytes())
+            .unwrap();
+        let error = read_sequence_archive(&zip.finish().unwrap()).unwrap_err();
+        assert!(error.contains("newer"));
+    }
+}
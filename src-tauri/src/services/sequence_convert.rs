@@ -0,0 +1,837 @@
+//! Conversion between simple and editor sequences
+//!
+//! A simple sequence becomes an advanced sequence with one Deep Sky Object
+//! container per target and a Smart Exposure per exposure row, so a plan
+//! started in the simple editor can be refined in the advanced one.
+//!
+//! The way back is best effort: Deep Sky Object containers become targets
+//! and their exposure items become exposure rows. Items the simple format
+//! cannot express are left out and reported as warnings.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+
+use crate::models::{
+    BinningMode, Coordinates, EditorCondition, EditorSequence, EditorSequenceItem, EditorTrigger,
+    FilterInfo, ImageType, SequenceEntityStatus, SequenceMode, SimpleExposure, SimpleSequence,
+    SimpleTarget,
+};
+use crate::services::nina_type_registry;
+use crate::services::validator::get_short_type_name;
+
+const COOL_CAMERA: &str = "NINA.Sequencer.SequenceItem.Camera.CoolCamera";
+const WARM_CAMERA: &str = "NINA.Sequencer.SequenceItem.Camera.WarmCamera";
+const UNPARK_SCOPE: &str = "NINA.Sequencer.SequenceItem.Telescope.UnparkScope";
+const PARK_SCOPE: &str = "NINA.Sequencer.SequenceItem.Telescope.ParkScope";
+const DEEP_SKY_OBJECT: &str = "NINA.Sequencer.Container.DeepSkyObjectContainer";
+const WAIT_FOR_TIME_SPAN: &str = "NINA.Sequencer.SequenceItem.Utility.WaitForTimeSpan";
+const SLEW_TO_RA_DEC: &str = "NINA.Sequencer.SequenceItem.Telescope.SlewScopeToRaDec";
+const CENTER: &str = "NINA.Sequencer.SequenceItem.Platesolving.Center";
+const CENTER_AND_ROTATE: &str = "NINA.Sequencer.SequenceItem.Platesolving.CenterAndRotate";
+const START_GUIDING: &str = "NINA.Sequencer.SequenceItem.Guider.StartGuiding";
+const RUN_AUTOFOCUS: &str = "NINA.Sequencer.SequenceItem.Autofocus.RunAutofocus";
+const SMART_EXPOSURE: &str = "NINA.Sequencer.SequenceItem.Imaging.SmartExposure";
+const SWITCH_FILTER: &str = "NINA.Sequencer.SequenceItem.FilterWheel.SwitchFilter";
+const TAKE_EXPOSURE: &str = "NINA.Sequencer.SequenceItem.Imaging.TakeExposure";
+const LOOP_CONDITION: &str = "NINA.Sequencer.Conditions.LoopCondition";
+const DITHER_AFTER_EXPOSURES: &str = "NINA.Sequencer.Trigger.Guider.DitherAfterExposures";
+const MERIDIAN_FLIP: &str = "NINA.Sequencer.Trigger.MeridianFlip.MeridianFlipTrigger";
+const AF_AFTER_EXPOSURES: &str = "NINA.Sequencer.Trigger.Autofocus.AutofocusAfterExposures";
+const AF_AFTER_FILTER_CHANGE: &str = "NINA.Sequencer.Trigger.Autofocus.AutofocusAfterFilterChange";
+const AF_AFTER_HFR_INCREASE: &str =
+    "NINA.Sequencer.Trigger.Autofocus.AutofocusAfterHFRIncreaseTrigger";
+const AF_AFTER_TEMPERATURE_CHANGE: &str =
+    "NINA.Sequencer.Trigger.Autofocus.AutofocusAfterTemperatureChangeTrigger";
+const AF_AFTER_TIME: &str = "NINA.Sequencer.Trigger.Autofocus.AutofocusAfterTimeTrigger";
+
+/// Samples NINA averages before comparing HFR
+const HFR_SAMPLE_SIZE: i32 = 10;
+
+/// Result of [`convert_editor_to_simple`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SimpleConversion {
+    pub sequence: SimpleSequence,
+    /// Items and triggers left out of the simple sequence
+    pub warnings: Vec<String>,
+}
+
+// ============================================================================
+// Simple to editor
+// ============================================================================
+
+fn full_type(type_path: &str) -> String {
+    format!("{}, NINA.Sequencer", type_path)
+}
+
+/// Display name and category of a built-in type
+fn type_names(type_path: &str) -> (String, String) {
+    match nina_type_registry::find_type(type_path) {
+        Some(def) => (def.name.to_string(), def.category.to_string()),
+        None => (get_short_type_name(type_path), "Unknown".to_string()),
+    }
+}
+
+fn item_data(fields: Vec<(&str, Value)>) -> HashMap<String, Value> {
+    fields
+        .into_iter()
+        .map(|(key, value)| (key.to_string(), value))
+        .collect()
+}
+
+fn new_item(type_path: &str, fields: Vec<(&str, Value)>) -> EditorSequenceItem {
+    let (name, category) = type_names(type_path);
+    let is_container = nina_type_registry::is_container_type(type_path, &[]);
+    EditorSequenceItem {
+        id: uuid::Uuid::new_v4().to_string(),
+        item_type: full_type(type_path),
+        name,
+        category,
+        icon: None,
+        description: None,
+        status: SequenceEntityStatus::Created,
+        is_expanded: is_container.then_some(true),
+        data: item_data(fields),
+        items: is_container.then(Vec::new),
+        conditions: is_container.then(Vec::new),
+        triggers: is_container.then(Vec::new),
+    }
+}
+
+fn new_condition(type_path: &str, fields: Vec<(&str, Value)>) -> EditorCondition {
+    let (name, category) = type_names(type_path);
+    EditorCondition {
+        id: uuid::Uuid::new_v4().to_string(),
+        condition_type: full_type(type_path),
+        name,
+        category,
+        icon: None,
+        data: item_data(fields),
+    }
+}
+
+fn new_trigger(type_path: &str, fields: Vec<(&str, Value)>) -> EditorTrigger {
+    let (name, category) = type_names(type_path);
+    EditorTrigger {
+        id: uuid::Uuid::new_v4().to_string(),
+        trigger_type: full_type(type_path),
+        name,
+        category,
+        icon: None,
+        data: item_data(fields),
+        trigger_items: None,
+    }
+}
+
+/// NINA `Target` of a Deep Sky Object container
+fn nina_target(target: &SimpleTarget) -> Value {
+    let c = &target.coordinates;
+    json!({
+        "TargetName": target.target_name,
+        "Rotation": target.position_angle,
+        "InputCoordinates": {
+            "RAHours": c.ra_hours,
+            "RAMinutes": c.ra_minutes,
+            "RASeconds": c.ra_seconds,
+            "NegativeDec": c.negative_dec,
+            "DecDegrees": c.dec_degrees,
+            "DecMinutes": c.dec_minutes,
+            "DecSeconds": c.dec_seconds,
+        },
+    })
+}
+
+/// NINA `FilterInfo` of a Switch Filter item
+fn nina_filter(filter: &FilterInfo) -> Value {
+    json!({
+        "_name": filter.name,
+        "_position": filter.position,
+        "_focusOffset": filter.focus_offset.unwrap_or(0),
+    })
+}
+
+/// Smart Exposure running the remaining frames of an exposure row
+fn smart_exposure(exposure: &SimpleExposure) -> EditorSequenceItem {
+    let mut item = new_item(SMART_EXPOSURE, Vec::new());
+    if let Some(filter) = &exposure.filter {
+        item.name = format!("Smart Exposure ({})", filter.name);
+    }
+
+    let mut children = Vec::new();
+    if let Some(filter) = &exposure.filter {
+        children.push(new_item(
+            SWITCH_FILTER,
+            vec![("Filter", nina_filter(filter))],
+        ));
+    }
+    children.push(new_item(
+        TAKE_EXPOSURE,
+        vec![
+            ("ExposureTime", json!(exposure.exposure_time)),
+            ("Gain", json!(exposure.gain)),
+            ("Offset", json!(exposure.offset)),
+            ("ImageType", json!(exposure.image_type.to_string())),
+            ("ExposureCount", json!(0)),
+            (
+                "Binning",
+                json!({ "X": exposure.binning.x, "Y": exposure.binning.y }),
+            ),
+        ],
+    ));
+    item.items = Some(children);
+
+    item.conditions = Some(vec![new_condition(
+        LOOP_CONDITION,
+        vec![
+            ("Iterations", json!(exposure.remaining())),
+            ("CompletedIterations", json!(0)),
+        ],
+    )]);
+    if exposure.dither && exposure.dither_every > 0 {
+        item.triggers = Some(vec![new_trigger(
+            DITHER_AFTER_EXPOSURES,
+            vec![("AfterExposures", json!(exposure.dither_every))],
+        )]);
+    }
+    item
+}
+
+/// Autofocus triggers of a target
+fn autofocus_triggers(target: &SimpleTarget) -> Vec<EditorTrigger> {
+    let mut triggers = Vec::new();
+    if target.auto_focus_on_filter_change {
+        triggers.push(new_trigger(AF_AFTER_FILTER_CHANGE, Vec::new()));
+    }
+    if target.auto_focus_after_set_time {
+        triggers.push(new_trigger(
+            AF_AFTER_TIME,
+            vec![("Amount", json!(target.auto_focus_set_time))],
+        ));
+    }
+    if target.auto_focus_after_set_exposures {
+        triggers.push(new_trigger(
+            AF_AFTER_EXPOSURES,
+            vec![("AfterExposures", json!(target.auto_focus_set_exposures))],
+        ));
+    }
+    if target.auto_focus_after_temperature_change {
+        triggers.push(new_trigger(
+            AF_AFTER_TEMPERATURE_CHANGE,
+            vec![(
+                "Amount",
+                json!(target.auto_focus_after_temperature_change_amount),
+            )],
+        ));
+    }
+    if target.auto_focus_after_hfr_change {
+        triggers.push(new_trigger(
+            AF_AFTER_HFR_INCREASE,
+            vec![
+                ("Amount", json!(target.auto_focus_after_hfr_change_amount)),
+                ("SampleSize", json!(HFR_SAMPLE_SIZE)),
+            ],
+        ));
+    }
+    triggers
+}
+
+/// Deep Sky Object container for a target
+fn target_container(target: &SimpleTarget) -> EditorSequenceItem {
+    let mut container = new_item(DEEP_SKY_OBJECT, vec![("Target", nina_target(target))]);
+    if !target.target_name.is_empty() {
+        container.name = target.target_name.clone();
+    }
+
+    let mut items = Vec::new();
+    if target.delay > 0 {
+        items.push(new_item(
+            WAIT_FOR_TIME_SPAN,
+            vec![("Time", json!(target.delay))],
+        ));
+    }
+    if target.center_target && target.rotate_target {
+        items.push(new_item(
+            CENTER_AND_ROTATE,
+            vec![
+                ("Inherited", json!(true)),
+                ("Rotation", json!(target.position_angle)),
+            ],
+        ));
+    } else if target.center_target {
+        items.push(new_item(CENTER, vec![("Inherited", json!(true))]));
+    } else if target.slew_to_target {
+        items.push(new_item(SLEW_TO_RA_DEC, vec![("Inherited", json!(true))]));
+    }
+    if target.start_guiding {
+        items.push(new_item(
+            START_GUIDING,
+            vec![("ForceCalibration", json!(false))],
+        ));
+    }
+    if target.auto_focus_on_start {
+        items.push(new_item(RUN_AUTOFOCUS, Vec::new()));
+    }
+    items.extend(
+        target
+            .exposures
+            .iter()
+            .filter(|e| e.enabled && e.remaining() > 0)
+            .map(smart_exposure),
+    );
+
+    container.items = Some(items);
+    container.triggers = Some(autofocus_triggers(target));
+    container
+}
+
+/// Convert a simple sequence to an editor sequence. Start and end options
+/// become camera and mount items, each target a Deep Sky Object container
+/// and each enabled exposure row a Smart Exposure looping over the frames
+/// still to take. Targets in rotate mode run their rows one after another.
+pub fn convert_simple_to_editor(sequence: &SimpleSequence) -> EditorSequence {
+    let mut editor = EditorSequence::new(sequence.title.clone());
+
+    let start = &sequence.start_options;
+    if start.cool_camera_at_sequence_start {
+        editor.start_items.push(new_item(
+            COOL_CAMERA,
+            vec![
+                ("Temperature", json!(start.cool_camera_temperature)),
+                ("Duration", json!(start.cool_camera_duration)),
+            ],
+        ));
+    }
+    if start.unpark_mount_at_sequence_start {
+        editor.start_items.push(new_item(UNPARK_SCOPE, Vec::new()));
+    }
+    if start.do_meridian_flip {
+        editor
+            .global_triggers
+            .push(new_trigger(MERIDIAN_FLIP, Vec::new()));
+    }
+
+    editor.target_items = sequence.targets.iter().map(target_container).collect();
+
+    let end = &sequence.end_options;
+    if end.warm_cam_at_sequence_end {
+        editor.end_items.push(new_item(
+            WARM_CAMERA,
+            vec![("Duration", json!(end.warm_camera_duration))],
+        ));
+    }
+    if end.park_mount_at_sequence_end {
+        editor.end_items.push(new_item(PARK_SCOPE, Vec::new()));
+    }
+
+    editor
+}
+
+// ============================================================================
+// Editor to simple
+// ============================================================================
+
+/// Value of a key in item data or a NINA object, in any letter case
+fn field<'a>(data: &'a HashMap<String, Value>, key: &str) -> Option<&'a Value> {
+    data.iter()
+        .find(|(k, _)| k.eq_ignore_ascii_case(key))
+        .map(|(_, v)| v)
+}
+
+fn value_field<'a>(value: &'a Value, key: &str) -> Option<&'a Value> {
+    value
+        .as_object()?
+        .iter()
+        .find(|(k, _)| k.eq_ignore_ascii_case(key))
+        .map(|(_, v)| v)
+}
+
+fn number(data: &HashMap<String, Value>, key: &str) -> Option<f64> {
+    field(data, key).and_then(Value::as_f64)
+}
+
+fn short_type(full_type: &str) -> String {
+    get_short_type_name(full_type)
+}
+
+/// Read NINA `InputCoordinates`
+fn read_coordinates(value: &Value) -> Option<Coordinates> {
+    let number = |key| value_field(value, key).and_then(Value::as_f64);
+    let dec_degrees = number("decDegrees").unwrap_or(0.0);
+    let negative_dec = value_field(value, "negativeDec")
+        .and_then(Value::as_bool)
+        .unwrap_or(false)
+        || dec_degrees < 0.0;
+    Some(Coordinates::new(
+        number("raHours")? as i32,
+        number("raMinutes").unwrap_or(0.0) as i32,
+        number("raSeconds").unwrap_or(0.0),
+        dec_degrees.abs() as i32,
+        number("decMinutes").unwrap_or(0.0) as i32,
+        number("decSeconds").unwrap_or(0.0),
+        negative_dec,
+    ))
+}
+
+/// Read a NINA `FilterInfo`, with or without the field underscores
+fn read_filter(value: &Value) -> Option<FilterInfo> {
+    let get = |key: &str| value_field(value, &format!("_{}", key)).or(value_field(value, key));
+    let name = get("name")?.as_str()?.to_string();
+    Some(FilterInfo {
+        name,
+        position: get("position").and_then(Value::as_i64).unwrap_or(0) as i32,
+        focus_offset: get("focusOffset").and_then(Value::as_i64).map(|o| o as i32),
+        auto_focus_exposure_time: None,
+    })
+}
+
+fn read_image_type(value: Option<&Value>) -> ImageType {
+    value
+        .and_then(Value::as_str)
+        .and_then(|s| serde_json::from_value(json!(s.to_uppercase())).ok())
+        .unwrap_or_default()
+}
+
+/// Iterations of a container's loop conditions, `None` without any
+fn loop_iterations(conditions: &[EditorCondition]) -> Option<i32> {
+    conditions
+        .iter()
+        .filter(|c| short_type(&c.condition_type) == "LoopCondition")
+        .filter_map(|c| number(&c.data, "iterations"))
+        .map(|n| n.max(0.0) as i32)
+        .min()
+}
+
+/// Target state while walking a Deep Sky Object container
+struct TargetReader<'a> {
+    target: SimpleTarget,
+    filter: Option<FilterInfo>,
+    warnings: &'a mut Vec<String>,
+}
+
+/// Loop count and dithering inherited from enclosing containers
+#[derive(Clone, Copy)]
+struct ExposureScope {
+    iterations: i32,
+    dither_every: Option<i32>,
+}
+
+impl TargetReader<'_> {
+    fn warn(&mut self, message: String) {
+        self.warnings
+            .push(format!("Target '{}': {}", self.target.target_name, message));
+    }
+
+    fn read_triggers(&mut self, triggers: &[EditorTrigger], scope: &mut ExposureScope) {
+        for trigger in triggers {
+            let amount = number(&trigger.data, "amount");
+            let target = &mut self.target;
+            match short_type(&trigger.trigger_type).as_str() {
+                "DitherAfterExposures" => {
+                    let every = number(&trigger.data, "afterExposures").unwrap_or(1.0);
+                    scope.dither_every = Some((every as i32).max(1));
+                }
+                "AutofocusAfterFilterChange" => target.auto_focus_on_filter_change = true,
+                "AutofocusAfterTimeTrigger" => {
+                    target.auto_focus_after_set_time = true;
+                    if let Some(minutes) = amount {
+                        target.auto_focus_set_time = minutes as i32;
+                    }
+                }
+                "AutofocusAfterExposures" => {
+                    target.auto_focus_after_set_exposures = true;
+                    if let Some(count) = number(&trigger.data, "afterExposures") {
+                        target.auto_focus_set_exposures = count as i32;
+                    }
+                }
+                "AutofocusAfterTemperatureChangeTrigger" => {
+                    target.auto_focus_after_temperature_change = true;
+                    if let Some(amount) = amount {
+                        target.auto_focus_after_temperature_change_amount = amount;
+                    }
+                }
+                "AutofocusAfterHFRIncreaseTrigger" => {
+                    target.auto_focus_after_hfr_change = true;
+                    if let Some(amount) = amount {
+                        target.auto_focus_after_hfr_change_amount = amount;
+                    }
+                }
+                _ => self.warn(format!("trigger '{}' was left out", trigger.name)),
+            }
+        }
+    }
+
+    fn read_exposure(&mut self, item: &EditorSequenceItem, scope: ExposureScope) {
+        let data = &item.data;
+        let count = match number(data, "totalExposureCount") {
+            Some(total) => total as i32 * scope.iterations,
+            None => scope.iterations,
+        };
+        let binning = field(data, "binning")
+            .map(|b| BinningMode {
+                x: value_field(b, "x").and_then(Value::as_i64).unwrap_or(1) as i32,
+                y: value_field(b, "y").and_then(Value::as_i64).unwrap_or(1) as i32,
+            })
+            .unwrap_or_default();
+
+        self.target.exposures.push(SimpleExposure {
+            exposure_time: number(data, "exposureTime").unwrap_or(60.0),
+            image_type: read_image_type(field(data, "imageType")),
+            filter: self.filter.clone(),
+            binning,
+            gain: number(data, "gain").unwrap_or(-1.0) as i32,
+            offset: number(data, "offset").unwrap_or(-1.0) as i32,
+            total_count: count,
+            dither: scope.dither_every.is_some(),
+            dither_every: scope.dither_every.unwrap_or(1),
+            ..Default::default()
+        });
+    }
+
+    fn read_items(&mut self, items: &[EditorSequenceItem], scope: ExposureScope) {
+        for item in items {
+            let target = &mut self.target;
+            match short_type(&item.item_type).as_str() {
+                "SlewScopeToRaDec" => target.slew_to_target = true,
+                "Center" => {
+                    target.slew_to_target = true;
+                    target.center_target = true;
+                }
+                "CenterAndRotate" => {
+                    target.slew_to_target = true;
+                    target.center_target = true;
+                    target.rotate_target = true;
+                    if let Some(rotation) = number(&item.data, "rotation") {
+                        target.position_angle = rotation;
+                        target.rotation = rotation;
+                    }
+                }
+                "StartGuiding" => target.start_guiding = true,
+                "RunAutofocus" => target.auto_focus_on_start = true,
+                "WaitForTimeSpan" => {
+                    target.delay = number(&item.data, "time").unwrap_or(0.0) as i32;
+                }
+                "SwitchFilter" => {
+                    self.filter = field(&item.data, "filter").and_then(read_filter);
+                }
+                "TakeExposure" | "TakeManyExposures" | "TakeSubframeExposure" => {
+                    self.read_exposure(item, scope);
+                }
+                _ if item.is_container() => self.read_container(item, scope),
+                _ => self.warn(format!("item '{}' was left out", item.name)),
+            }
+        }
+    }
+
+    fn read_container(&mut self, item: &EditorSequenceItem, outer: ExposureScope) {
+        let mut scope = outer;
+        if let Some(iterations) = loop_iterations(item.conditions.as_deref().unwrap_or_default()) {
+            scope.iterations *= iterations;
+        }
+        self.read_triggers(item.triggers.as_deref().unwrap_or_default(), &mut scope);
+        for condition in item.conditions.iter().flatten() {
+            if short_type(&condition.condition_type) != "LoopCondition" {
+                self.warn(format!("condition '{}' was left out", condition.name));
+            }
+        }
+        self.read_items(item.items.as_deref().unwrap_or_default(), scope);
+    }
+}
+
+/// Target of a Deep Sky Object container
+fn read_target(container: &EditorSequenceItem, warnings: &mut Vec<String>) -> SimpleTarget {
+    let nina_target = field(&container.data, "target");
+    let target_name = nina_target
+        .and_then(|t| value_field(t, "targetName"))
+        .and_then(Value::as_str)
+        .filter(|name| !name.is_empty())
+        .unwrap_or(&container.name)
+        .to_string();
+    let coordinates = nina_target
+        .and_then(|t| value_field(t, "inputCoordinates"))
+        .and_then(read_coordinates);
+    let rotation = nina_target
+        .and_then(|t| value_field(t, "rotation"))
+        .and_then(Value::as_f64)
+        .unwrap_or(0.0);
+    if coordinates.is_none() {
+        warnings.push(format!("Target '{}': no coordinates", target_name));
+    }
+
+    let target = SimpleTarget {
+        name: target_name.clone(),
+        target_name,
+        coordinates: coordinates.unwrap_or_default(),
+        position_angle: rotation,
+        rotation,
+        mode: SequenceMode::Standard,
+        slew_to_target: false,
+        center_target: false,
+        rotate_target: false,
+        start_guiding: false,
+        auto_focus_on_start: false,
+        exposures: Vec::new(),
+        ..Default::default()
+    };
+    let mut reader = TargetReader {
+        target,
+        filter: None,
+        warnings,
+    };
+    let scope = ExposureScope {
+        iterations: 1,
+        dither_every: None,
+    };
+    reader.read_container(container, scope);
+    reader.target
+}
+
+/// Collect targets from Deep Sky Object containers, looking into other
+/// containers
+fn read_target_items(
+    items: &[EditorSequenceItem],
+    targets: &mut Vec<SimpleTarget>,
+    warnings: &mut Vec<String>,
+) {
+    for item in items {
+        if short_type(&item.item_type) == "DeepSkyObjectContainer" {
+            targets.push(read_target(item, warnings));
+        } else if item.is_container() {
+            read_target_items(item.items.as_deref().unwrap_or_default(), targets, warnings);
+        } else {
+            warnings.push(format!(
+                "Item '{}' outside a target was left out",
+                item.name
+            ));
+        }
+    }
+}
+
+/// Convert an editor sequence to a simple sequence, as far as the simple
+/// format can express it. Deep Sky Object containers become targets and
+/// their exposure items exposure rows, counted by the loop conditions
+/// around them; camera and mount items at the start and end set the start
+/// and end options. Anything else is reported in the warnings.
+pub fn convert_editor_to_simple(sequence: &EditorSequence) -> SimpleConversion {
+    let mut simple = SimpleSequence::new(sequence.title.clone());
+    let mut warnings = Vec::new();
+
+    let start = &mut simple.start_options;
+    start.cool_camera_at_sequence_start = false;
+    start.unpark_mount_at_sequence_start = false;
+    start.do_meridian_flip = false;
+    for item in &sequence.start_items {
+        match short_type(&item.item_type).as_str() {
+            "CoolCamera" => {
+                start.cool_camera_at_sequence_start = true;
+                if let Some(temperature) = number(&item.data, "temperature") {
+                    start.cool_camera_temperature = temperature;
+                }
+                if let Some(duration) = number(&item.data, "duration") {
+                    start.cool_camera_duration = duration as i32;
+                }
+            }
+            "UnparkScope" => start.unpark_mount_at_sequence_start = true,
+            _ => warnings.push(format!("Start item '{}' was left out", item.name)),
+        }
+    }
+    for trigger in &sequence.global_triggers {
+        if short_type(&trigger.trigger_type) == "MeridianFlipTrigger" {
+            start.do_meridian_flip = true;
+        } else {
+            warnings.push(format!("Trigger '{}' was left out", trigger.name));
+        }
+    }
+
+    let end = &mut simple.end_options;
+    end.warm_cam_at_sequence_end = false;
+    end.park_mount_at_sequence_end = false;
+    for item in &sequence.end_items {
+        match short_type(&item.item_type).as_str() {
+            "WarmCamera" => {
+                end.warm_cam_at_sequence_end = true;
+                if let Some(duration) = number(&item.data, "duration") {
+                    end.warm_camera_duration = duration as i32;
+                }
+            }
+            "ParkScope" => end.park_mount_at_sequence_end = true,
+            _ => warnings.push(format!("End item '{}' was left out", item.name)),
+        }
+    }
+
+    simple.targets.clear();
+    read_target_items(&sequence.target_items, &mut simple.targets, &mut warnings);
+    if simple.targets.is_empty() {
+        warnings.push("The sequence has no Deep Sky Object containers".to_string());
+    }
+    simple.selected_target_id = simple.targets.first().map(|t| t.id.clone());
+    simple.active_target_id = simple.selected_target_id.clone();
+
+    SimpleConversion {
+        sequence: simple,
+        warnings,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::validator;
+
+    fn sample_sequence() -> SimpleSequence {
+        let mut sequence = SimpleSequence::new("Autumn");
+        let mut target = SimpleTarget {
+            target_name: "M31".to_string(),
+            coordinates: Coordinates::new(0, 42, 44.3, 41, 16, 9.0, false),
+            position_angle: 35.0,
+            rotate_target: true,
+            delay: 30,
+            auto_focus_after_set_time: true,
+            auto_focus_set_time: 45,
+            ..Default::default()
+        };
+        target.exposures = vec![
+            SimpleExposure {
+                exposure_time: 300.0,
+                filter: Some(FilterInfo {
+                    name: "Ha".to_string(),
+                    position: 4,
+                    focus_offset: Some(25),
+                    auto_focus_exposure_time: None,
+                }),
+                gain: 100,
+                offset: 10,
+                total_count: 20,
+                progress_count: 5,
+                dither: true,
+                dither_every: 3,
+                ..Default::default()
+            },
+            SimpleExposure {
+                enabled: false,
+                ..Default::default()
+            },
+        ];
+        sequence.targets = vec![target];
+        sequence
+    }
+
+    #[test]
+    fn test_simple_to_editor() {
+        let editor = convert_simple_to_editor(&sample_sequence());
+        assert_eq!(editor.title, "Autumn");
+
+        let start: Vec<String> = editor
+            .start_items
+            .iter()
+            .map(|i| short_type(&i.item_type))
+            .collect();
+        assert_eq!(start, ["CoolCamera", "UnparkScope"]);
+        let end: Vec<String> = editor
+            .end_items
+            .iter()
+            .map(|i| short_type(&i.item_type))
+            .collect();
+        assert_eq!(end, ["WarmCamera", "ParkScope"]);
+        assert_eq!(editor.global_triggers.len(), 1);
+
+        let container = &editor.target_items[0];
+        assert_eq!(container.name, "M31");
+        assert_eq!(
+            container.data["Target"]["InputCoordinates"]["RAMinutes"],
+            42
+        );
+        let children: Vec<String> = container
+            .items
+            .iter()
+            .flatten()
+            .map(|i| short_type(&i.item_type))
+            .collect();
+        assert_eq!(
+            children,
+            [
+                "WaitForTimeSpan",
+                "CenterAndRotate",
+                "StartGuiding",
+                "RunAutofocus",
+                "SmartExposure"
+            ]
+        );
+
+        // Only the enabled row, looping over the frames left
+        let smart = &container.items.as_ref().unwrap()[4];
+        assert_eq!(smart.conditions.as_ref().unwrap()[0].data["Iterations"], 15);
+        assert_eq!(
+            smart.triggers.as_ref().unwrap()[0].data["AfterExposures"],
+            3
+        );
+        let exposure = &smart.items.as_ref().unwrap()[1];
+        assert_eq!(exposure.data["ExposureTime"], 300.0);
+        assert_eq!(exposure.data["ImageType"], "LIGHT");
+
+        assert!(validator::validate_editor_sequence(&editor, &[]).valid);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let original = sample_sequence();
+        let result = convert_editor_to_simple(&convert_simple_to_editor(&original));
+        assert!(result.warnings.is_empty(), "{:?}", result.warnings);
+
+        let sequence = result.sequence;
+        assert!(sequence.start_options.cool_camera_at_sequence_start);
+        assert!(sequence.end_options.park_mount_at_sequence_end);
+        assert_eq!(sequence.targets.len(), 1);
+
+        let target = &sequence.targets[0];
+        assert_eq!(target.target_name, "M31");
+        assert_eq!(target.coordinates.ra_minutes, 42);
+        assert_eq!(target.coordinates.dec_degrees, 41);
+        assert_eq!(target.position_angle, 35.0);
+        assert_eq!(target.delay, 30);
+        assert!(target.center_target && target.rotate_target);
+        assert!(target.auto_focus_after_set_time);
+        assert_eq!(target.auto_focus_set_time, 45);
+
+        assert_eq!(target.exposures.len(), 1);
+        let exposure = &target.exposures[0];
+        assert_eq!(exposure.total_count, 15);
+        assert_eq!(exposure.filter.as_ref().unwrap().name, "Ha");
+        assert_eq!(exposure.filter.as_ref().unwrap().focus_offset, Some(25));
+        assert!(exposure.dither);
+        assert_eq!(exposure.dither_every, 3);
+        assert_eq!(exposure.gain, 100);
+    }
+
+    #[test]
+    fn test_editor_to_simple_warns_about_unsupported_items() {
+        let mut editor = EditorSequence::new("Custom");
+        editor.start_items.push(new_item(
+            "NINA.Sequencer.SequenceItem.Utility.WaitForTime",
+            Vec::new(),
+        ));
+        let mut container = new_item(DEEP_SKY_OBJECT, Vec::new());
+        container.name = "NGC 7000".to_string();
+        container.items = Some(vec![
+            new_item(
+                "NINA.Sequencer.SequenceItem.Focuser.MoveFocuserAbsolute",
+                Vec::new(),
+            ),
+            new_item(TAKE_EXPOSURE, vec![("exposureTime", json!(120.0))]),
+        ]);
+        container.conditions = Some(vec![new_condition(
+            LOOP_CONDITION,
+            vec![("iterations", json!(8))],
+        )]);
+        editor.target_items.push(container);
+
+        let result = convert_editor_to_simple(&editor);
+        let target = &result.sequence.targets[0];
+        assert_eq!(target.target_name, "NGC 7000");
+        assert_eq!(target.exposures[0].exposure_time, 120.0);
+        assert_eq!(target.exposures[0].total_count, 8);
+        assert!(!result.sequence.start_options.cool_camera_at_sequence_start);
+        // Start item, focuser move and missing coordinates
+        assert_eq!(result.warnings.len(), 3, "{:?}", result.warnings);
+    }
+}
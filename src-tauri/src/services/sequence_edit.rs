@@ -0,0 +1,955 @@
+//! Sequence editing service
+//!
+//! Provides bulk editing operations across the targets and exposures
+//! of a simple sequence, exposure ramps, tag search over its targets and
+//! merging of targets that point at the same field.
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{AppError, AppResult};
+use crate::models::common::{BinningMode, FilterInfo, ImageType, SequenceEntityStatus};
+use crate::models::coordinates::angular_separation;
+use crate::models::{Coordinates, SimpleExposure, SimpleSequence, SimpleTarget};
+use crate::services::calculator;
+
+// ============================================================================
+// Types
+// ============================================================================
+
+/// Selects which exposures a bulk edit applies to. Empty fields match everything.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExposureSelector {
+    /// Only targets with these ids
+    #[serde(default)]
+    pub target_ids: Option<Vec<String>>,
+    /// Only targets whose name contains this text (case-insensitive)
+    #[serde(default)]
+    pub target_name_contains: Option<String>,
+    /// Only exposures using this filter (case-insensitive)
+    #[serde(default)]
+    pub filter_name: Option<String>,
+    /// Only exposures of this image type
+    #[serde(default)]
+    pub image_type: Option<ImageType>,
+    /// Skip disabled exposures
+    #[serde(default)]
+    pub enabled_only: bool,
+}
+
+/// Filter rename ("Ha" -> "H-alpha")
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FilterRename {
+    pub from: String,
+    pub to: String,
+}
+
+/// Changes to apply to each selected exposure. Unset fields are left untouched.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExposureChangeSet {
+    #[serde(default)]
+    pub gain: Option<i32>,
+    #[serde(default)]
+    pub offset: Option<i32>,
+    #[serde(default)]
+    pub exposure_time: Option<f64>,
+    /// Multiply exposure time by this factor (applied after `exposure_time`)
+    #[serde(default)]
+    pub exposure_time_scale: Option<f64>,
+    #[serde(default)]
+    pub total_count: Option<i32>,
+    #[serde(default)]
+    pub binning: Option<BinningMode>,
+    #[serde(default)]
+    pub image_type: Option<ImageType>,
+    #[serde(default)]
+    pub enabled: Option<bool>,
+    #[serde(default)]
+    pub dither: Option<bool>,
+    #[serde(default)]
+    pub dither_every: Option<i32>,
+    /// Replace the filter entirely
+    #[serde(default)]
+    pub filter: Option<FilterInfo>,
+    /// Rename a filter wherever it is used
+    #[serde(default)]
+    pub rename_filter: Option<FilterRename>,
+}
+
+/// An exposure touched by a bulk edit
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AffectedExposure {
+    pub target_id: String,
+    pub target_name: String,
+    pub exposure_id: String,
+    pub exposure_index: usize,
+    /// Human-readable changes, e.g. "gain: -1 -> 100"
+    pub changes: Vec<String>,
+}
+
+/// Bulk edit result
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BulkEditResult {
+    /// The edited sequence (unchanged when `dry_run` is set)
+    pub sequence: SimpleSequence,
+    pub affected: Vec<AffectedExposure>,
+    pub dry_run: bool,
+}
+
+/// Result of [`set_exposures_enabled`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExposureToggleResult {
+    pub sequence: SimpleSequence,
+    /// Number of exposures whose `enabled` flag changed
+    pub changed: usize,
+}
+
+/// Setting an exposure ramp varies
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum RampParameter {
+    #[default]
+    ExposureTime,
+    Gain,
+}
+
+/// Spacing of the ramp steps
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum RampScaling {
+    /// Equal differences between steps
+    #[default]
+    Linear,
+    /// Equal ratios between steps, as for twilight flats or a bright core
+    /// bracketed over several stops
+    Logarithmic,
+}
+
+/// A series of exposures whose exposure time or gain runs from `start` to
+/// `end`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExposureRamp {
+    pub start: f64,
+    pub end: f64,
+    pub steps: usize,
+    #[serde(default)]
+    pub scaling: RampScaling,
+    #[serde(default)]
+    pub parameter: RampParameter,
+}
+
+// ============================================================================
+// Bulk Edit
+// ============================================================================
+
+impl ExposureSelector {
+    fn matches_target(&self, target: &SimpleTarget) -> bool {
+        if let Some(ref ids) = self.target_ids {
+            if !ids.contains(&target.id) {
+                return false;
+            }
+        }
+        if let Some(ref text) = self.target_name_contains {
+            let text = text.to_lowercase();
+            if !target.target_name.to_lowercase().contains(&text)
+                && !target.name.to_lowercase().contains(&text)
+            {
+                return false;
+            }
+        }
+        true
+    }
+
+    fn matches_exposure(&self, exposure: &SimpleExposure) -> bool {
+        if self.enabled_only && !exposure.enabled {
+            return false;
+        }
+        if let Some(image_type) = self.image_type {
+            if exposure.image_type != image_type {
+                return false;
+            }
+        }
+        if let Some(ref name) = self.filter_name {
+            match exposure.filter {
+                Some(ref f) if f.name.eq_ignore_ascii_case(name) => {}
+                _ => return false,
+            }
+        }
+        true
+    }
+}
+
+impl ExposureChangeSet {
+    /// Validate the change set
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+
+        if matches!(self.exposure_time, Some(t) if t <= 0.0) {
+            errors.push("Exposure time must be positive".to_string());
+        }
+        if matches!(self.exposure_time_scale, Some(s) if s <= 0.0) {
+            errors.push("Exposure time scale must be positive".to_string());
+        }
+        if matches!(self.total_count, Some(c) if c < 0) {
+            errors.push("Total count cannot be negative".to_string());
+        }
+        if matches!(self.binning, Some(b) if b.x < 1 || b.y < 1) {
+            errors.push("Binning must be at least 1".to_string());
+        }
+        if matches!(self.dither_every, Some(d) if d < 1) {
+            errors.push("Dither every must be at least 1".to_string());
+        }
+
+        errors
+    }
+
+    /// Apply to an exposure, returning a description of each change made
+    fn apply(&self, exposure: &mut SimpleExposure) -> Vec<String> {
+        let mut changes = Vec::new();
+
+        macro_rules! set_field {
+            ($field:ident, $label:expr) => {
+                if let Some(value) = self.$field {
+                    if exposure.$field != value {
+                        changes.push(format!("{}: {:?} -> {:?}", $label, exposure.$field, value));
+                        exposure.$field = value;
+                    }
+                }
+            };
+        }
+
+        set_field!(gain, "gain");
+        set_field!(offset, "offset");
+        set_field!(exposure_time, "exposureTime");
+        set_field!(total_count, "totalCount");
+        set_field!(binning, "binning");
+        set_field!(image_type, "imageType");
+        set_field!(enabled, "enabled");
+        set_field!(dither, "dither");
+        set_field!(dither_every, "ditherEvery");
+
+        if let Some(scale) = self.exposure_time_scale {
+            if scale != 1.0 {
+                let new_time = exposure.exposure_time * scale;
+                changes.push(format!(
+                    "exposureTime: {:?} -> {:?}",
+                    exposure.exposure_time, new_time
+                ));
+                exposure.exposure_time = new_time;
+            }
+        }
+
+        if let Some(ref filter) = self.filter {
+            let old_name = exposure.filter.as_ref().map(|f| f.name.as_str());
+            if old_name != Some(filter.name.as_str()) {
+                changes.push(format!(
+                    "filter: {} -> {}",
+                    old_name.unwrap_or("(none)"),
+                    filter.name
+                ));
+            }
+            exposure.filter = Some(filter.clone());
+        }
+
+        if let Some(ref rename) = self.rename_filter {
+            if let Some(ref mut filter) = exposure.filter {
+                if filter.name.eq_ignore_ascii_case(&rename.from) && filter.name != rename.to {
+                    changes.push(format!("filter: {} -> {}", filter.name, rename.to));
+                    filter.name = rename.to.clone();
+                }
+            }
+        }
+
+        changes
+    }
+}
+
+/// Apply a change set to all exposures matched by the selector.
+///
+/// With `dry_run` the returned sequence is the unmodified input and
+/// `affected` lists what would change.
+pub fn bulk_edit_exposures(
+    sequence: &SimpleSequence,
+    selector: &ExposureSelector,
+    changes: &ExposureChangeSet,
+    dry_run: bool,
+) -> Result<BulkEditResult, String> {
+    let errors = changes.validate();
+    if !errors.is_empty() {
+        return Err(errors.join("; "));
+    }
+
+    let mut edited = sequence.clone();
+    let mut affected = Vec::new();
+
+    for target in edited
+        .targets
+        .iter_mut()
+        .filter(|t| selector.matches_target(t))
+    {
+        for (index, exposure) in target.exposures.iter_mut().enumerate() {
+            if !selector.matches_exposure(exposure) {
+                continue;
+            }
+
+            let applied = changes.apply(exposure);
+            if !applied.is_empty() {
+                affected.push(AffectedExposure {
+                    target_id: target.id.clone(),
+                    target_name: target.target_name.clone(),
+                    exposure_id: exposure.id.clone(),
+                    exposure_index: index,
+                    changes: applied,
+                });
+            }
+        }
+    }
+
+    if dry_run {
+        edited = sequence.clone();
+    } else if !affected.is_empty() {
+        edited.is_dirty = true;
+    }
+
+    Ok(BulkEditResult {
+        sequence: edited,
+        affected,
+        dry_run,
+    })
+}
+
+/// Enable or disable every exposure matched by the selector, returning
+/// how many actually changed
+pub fn set_exposures_enabled(
+    sequence: &mut SimpleSequence,
+    selector: &ExposureSelector,
+    enabled: bool,
+) -> usize {
+    let mut changed = 0;
+    for target in sequence
+        .targets
+        .iter_mut()
+        .filter(|t| selector.matches_target(t))
+    {
+        for exposure in target
+            .exposures
+            .iter_mut()
+            .filter(|e| selector.matches_exposure(e) && e.enabled != enabled)
+        {
+            exposure.enabled = enabled;
+            changed += 1;
+        }
+    }
+    if changed > 0 {
+        sequence.is_dirty = true;
+    }
+    changed
+}
+
+// ============================================================================
+// Exposure Ramps
+// ============================================================================
+
+/// Most steps one ramp may have
+pub const MAX_RAMP_STEPS: usize = 100;
+
+/// Values of the ramp steps, from `start` to `end`
+fn ramp_values(ramp: &ExposureRamp) -> Result<Vec<f64>, String> {
+    if ramp.steps == 0 || ramp.steps > MAX_RAMP_STEPS {
+        return Err(format!(
+            "A ramp needs between 1 and {} steps",
+            MAX_RAMP_STEPS
+        ));
+    }
+    if !ramp.start.is_finite() || !ramp.end.is_finite() {
+        return Err("Ramp start and end must be numbers".to_string());
+    }
+    let lowest = ramp.start.min(ramp.end);
+    match ramp.parameter {
+        RampParameter::ExposureTime if lowest <= 0.0 => {
+            return Err("Ramp exposure times must be above zero".to_string());
+        }
+        RampParameter::Gain if lowest < 0.0 => {
+            return Err("Ramp gains cannot be negative".to_string());
+        }
+        _ => {}
+    }
+    if ramp.scaling == RampScaling::Logarithmic && lowest <= 0.0 {
+        return Err("A logarithmic ramp needs a start and end above zero".to_string());
+    }
+
+    let last = (ramp.steps - 1).max(1) as f64;
+    Ok((0..ramp.steps)
+        .map(|i| {
+            let t = i as f64 / last;
+            match ramp.scaling {
+                RampScaling::Linear => ramp.start + (ramp.end - ramp.start) * t,
+                RampScaling::Logarithmic => ramp.start * (ramp.end / ramp.start).powf(t),
+            }
+        })
+        .collect())
+}
+
+/// Exposures of a ramp, each a copy of `template` with its own id and the
+/// step's exposure time (to 0.01 s) or gain (rounded)
+pub fn generate_exposure_ramp(
+    ramp: &ExposureRamp,
+    template: &SimpleExposure,
+) -> Result<Vec<SimpleExposure>, String> {
+    Ok(ramp_values(ramp)?
+        .into_iter()
+        .map(|value| {
+            let mut exposure = template.clone();
+            exposure.id = uuid::Uuid::new_v4().to_string();
+            exposure.progress_count = 0;
+            exposure.status = SequenceEntityStatus::Created;
+            match ramp.parameter {
+                RampParameter::ExposureTime => {
+                    exposure.exposure_time = ((value * 100.0).round() / 100.0).max(0.01)
+                }
+                RampParameter::Gain => exposure.gain = value.round() as i32,
+            }
+            exposure
+        })
+        .collect())
+}
+
+/// Append a ramp to the target's exposures, returning the new exposures' ids
+pub fn append_exposure_ramp(
+    sequence: &mut SimpleSequence,
+    target_id: &str,
+    ramp: &ExposureRamp,
+    template: &SimpleExposure,
+) -> AppResult<Vec<String>> {
+    let exposures = generate_exposure_ramp(ramp, template).map_err(AppError::InvalidInput)?;
+    let target = sequence
+        .find_target_mut(target_id)
+        .ok_or_else(|| AppError::NotFound(format!("Target not found: {}", target_id)))?;
+
+    let ids = exposures.iter().map(|e| e.id.clone()).collect();
+    target.exposures.extend(exposures);
+    sequence.is_dirty = true;
+    Ok(ids)
+}
+
+// ============================================================================
+// Tag Search
+// ============================================================================
+
+/// Find targets carrying the given tags (case-insensitive). With
+/// `match_all` a target needs every tag, otherwise any one of them.
+pub fn search_targets_by_tag<'a>(
+    sequence: &'a SimpleSequence,
+    tags: &[String],
+    match_all: bool,
+) -> Vec<&'a SimpleTarget> {
+    let tags: Vec<&str> = tags
+        .iter()
+        .map(|t| t.trim())
+        .filter(|t| !t.is_empty())
+        .collect();
+    if tags.is_empty() {
+        return Vec::new();
+    }
+
+    sequence
+        .targets
+        .iter()
+        .filter(|target| {
+            if match_all {
+                tags.iter().all(|tag| target.has_tag(tag))
+            } else {
+                tags.iter().any(|tag| target.has_tag(tag))
+            }
+        })
+        .collect()
+}
+
+// ============================================================================
+// Duplicate Merge
+// ============================================================================
+
+/// Targets pointing at essentially the same field
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DuplicateTargetGroup {
+    /// Target ids in sequence order
+    pub target_ids: Vec<String>,
+    pub target_names: Vec<String>,
+    /// Largest separation between two targets of the group
+    pub max_separation_arcmin: f64,
+}
+
+/// How exposures are combined when targets are merged
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum MergeStrategy {
+    /// Append all exposures of the merged targets
+    Append,
+    /// Combine exposures with the same settings, adding up their counts
+    #[default]
+    SumMatching,
+    /// Combine exposures with the same settings, keeping the larger count.
+    /// Suits overlapping lists that plan the same frames.
+    MaxMatching,
+}
+
+/// Merge result
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MergeTargetsResult {
+    pub sequence: SimpleSequence,
+    /// The target the others were merged into
+    pub merged_target_id: String,
+    pub removed_target_ids: Vec<String>,
+}
+
+/// Angular distance between two targets in arcminutes
+pub fn separation_arcmin(a: &SimpleTarget, b: &SimpleTarget) -> f64 {
+    let degrees = angular_separation(&a.coordinates, &b.coordinates);
+    // Rounding can push the cosine just past 1 for identical positions
+    if degrees.is_nan() {
+        0.0
+    } else {
+        degrees * 60.0
+    }
+}
+
+/// Group targets lying within `tolerance_arcmin` of each other. Groups are
+/// chained, so A-B and B-C put A, B and C together. Moving targets are
+/// skipped since their coordinates change over time.
+pub fn find_duplicate_targets(
+    sequence: &SimpleSequence,
+    tolerance_arcmin: f64,
+) -> Vec<DuplicateTargetGroup> {
+    let targets: Vec<&SimpleTarget> = sequence
+        .targets
+        .iter()
+        .filter(|t| t.moving_target.is_none())
+        .collect();
+
+    let coordinates: Vec<&Coordinates> = targets.iter().map(|t| &t.coordinates).collect();
+    let separations = calculator::separation_matrix(&coordinates);
+    let arcmin = |i: usize, j: usize| separations[i][j] * 60.0;
+
+    // Union-find over target indices
+    let mut parent: Vec<usize> = (0..targets.len()).collect();
+    fn root(parent: &mut [usize], mut i: usize) -> usize {
+        while parent[i] != i {
+            parent[i] = parent[parent[i]];
+            i = parent[i];
+        }
+        i
+    }
+    for i in 0..targets.len() {
+        for j in i + 1..targets.len() {
+            if arcmin(i, j) <= tolerance_arcmin {
+                let (a, b) = (root(&mut parent, i), root(&mut parent, j));
+                parent[a.max(b)] = a.min(b);
+            }
+        }
+    }
+
+    let mut members: Vec<Vec<usize>> = vec![Vec::new(); targets.len()];
+    for i in 0..targets.len() {
+        let r = root(&mut parent, i);
+        members[r].push(i);
+    }
+
+    members
+        .into_iter()
+        .filter(|group| group.len() > 1)
+        .map(|group| {
+            let mut max_separation: f64 = 0.0;
+            for (n, &i) in group.iter().enumerate() {
+                for &j in &group[n + 1..] {
+                    max_separation = max_separation.max(arcmin(i, j));
+                }
+            }
+            DuplicateTargetGroup {
+                target_ids: group.iter().map(|&i| targets[i].id.clone()).collect(),
+                target_names: group
+                    .iter()
+                    .map(|&i| targets[i].target_name.clone())
+                    .collect(),
+                max_separation_arcmin: max_separation,
+            }
+        })
+        .collect()
+}
+
+fn same_settings(a: &SimpleExposure, b: &SimpleExposure) -> bool {
+    let filter_name = |e: &SimpleExposure| e.filter.as_ref().map(|f| f.name.to_lowercase());
+    a.image_type == b.image_type
+        && a.exposure_time == b.exposure_time
+        && a.gain == b.gain
+        && a.offset == b.offset
+        && a.binning == b.binning
+        && filter_name(a) == filter_name(b)
+}
+
+fn merge_exposures(
+    into: &mut Vec<SimpleExposure>,
+    from: &[SimpleExposure],
+    strategy: MergeStrategy,
+) {
+    for exposure in from {
+        let existing = match strategy {
+            MergeStrategy::Append => None,
+            _ => into.iter_mut().find(|e| same_settings(e, exposure)),
+        };
+        match existing {
+            Some(existing) => {
+                if strategy == MergeStrategy::SumMatching {
+                    existing.total_count += exposure.total_count;
+                    existing.progress_count += exposure.progress_count;
+                } else {
+                    existing.total_count = existing.total_count.max(exposure.total_count);
+                    existing.progress_count = existing.progress_count.max(exposure.progress_count);
+                }
+                existing.enabled |= exposure.enabled;
+            }
+            None => into.push(exposure.clone()),
+        }
+    }
+}
+
+/// Merge targets into the first of them in sequence order. The merged
+/// target keeps its own settings and gains the exposures, tags, notes and
+/// missing metadata of the others, and the highest priority among them.
+pub fn merge_targets(
+    sequence: &SimpleSequence,
+    target_ids: &[String],
+    strategy: MergeStrategy,
+) -> AppResult<MergeTargetsResult> {
+    for id in target_ids {
+        if !sequence.targets.iter().any(|t| &t.id == id) {
+            return Err(AppError::NotFound(format!("Target not found: {}", id)));
+        }
+    }
+
+    let mut edited = sequence.clone();
+    let mut merged: Vec<SimpleTarget> = Vec::new();
+    edited.targets.retain(|t| {
+        if target_ids.contains(&t.id) {
+            merged.push(t.clone());
+        }
+        // Keep the first selected target as the merge destination
+        !target_ids.contains(&t.id) || merged.len() == 1
+    });
+    if merged.len() < 2 {
+        return Err(AppError::InvalidInput(
+            "At least two different targets are needed to merge".to_string(),
+        ));
+    }
+
+    let merged_target_id = merged[0].id.clone();
+    let removed_target_ids: Vec<String> = merged[1..].iter().map(|t| t.id.clone()).collect();
+    let target = edited
+        .targets
+        .iter_mut()
+        .find(|t| t.id == merged_target_id)
+        .ok_or_else(|| "Merged target disappeared".to_string())?;
+
+    for other in &merged[1..] {
+        merge_exposures(&mut target.exposures, &other.exposures, strategy);
+        target.priority = target.priority.max(other.priority);
+        for tag in &other.tags {
+            if !target.has_tag(tag) {
+                target.tags.push(tag.clone());
+            }
+        }
+        if let Some(notes) = other.notes.as_ref().filter(|n| !n.trim().is_empty()) {
+            target.notes = Some(match target.notes.take().filter(|n| !n.trim().is_empty()) {
+                Some(existing) => format!("{}\n\n{}", existing, notes),
+                None => notes.clone(),
+            });
+        }
+        for (key, value) in &other.metadata {
+            target
+                .metadata
+                .entry(key.clone())
+                .or_insert_with(|| value.clone());
+        }
+    }
+
+    // Selection and the running target follow the merge
+    for id in [&mut edited.selected_target_id, &mut edited.active_target_id] {
+        if id
+            .as_ref()
+            .is_some_and(|id| removed_target_ids.contains(id))
+        {
+            *id = Some(merged_target_id.clone());
+        }
+    }
+    edited.is_dirty = true;
+
+    Ok(MergeTargetsResult {
+        sequence: edited,
+        merged_target_id,
+        removed_target_ids,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_sequence() -> SimpleSequence {
+        let mut sequence = SimpleSequence::new("Test");
+        sequence.targets = ["M31", "M42", "NGC 7000"]
+            .iter()
+            .map(|name| SimpleTarget {
+                target_name: name.to_string(),
+                exposures: ["Ha", "OIII"]
+                    .iter()
+                    .map(|f| SimpleExposure {
+                        filter: Some(FilterInfo {
+                            name: f.to_string(),
+                            ..Default::default()
+                        }),
+                        ..Default::default()
+                    })
+                    .collect(),
+                ..Default::default()
+            })
+            .collect();
+        sequence
+    }
+
+    #[test]
+    fn test_bulk_edit_dry_run() {
+        let sequence = create_sequence();
+        let changes = ExposureChangeSet {
+            gain: Some(100),
+            ..Default::default()
+        };
+
+        let result =
+            bulk_edit_exposures(&sequence, &ExposureSelector::default(), &changes, true).unwrap();
+
+        assert!(result.dry_run);
+        assert_eq!(result.affected.len(), 6);
+        assert!(result.sequence.targets[0]
+            .exposures
+            .iter()
+            .all(|e| e.gain == -1));
+    }
+
+    #[test]
+    fn test_bulk_edit_rename_filter_and_scale() {
+        let sequence = create_sequence();
+        let selector = ExposureSelector {
+            target_name_contains: Some("m4".to_string()),
+            filter_name: Some("ha".to_string()),
+            ..Default::default()
+        };
+        let changes = ExposureChangeSet {
+            exposure_time_scale: Some(0.5),
+            rename_filter: Some(FilterRename {
+                from: "Ha".to_string(),
+                to: "H-alpha".to_string(),
+            }),
+            ..Default::default()
+        };
+
+        let result = bulk_edit_exposures(&sequence, &selector, &changes, false).unwrap();
+
+        assert_eq!(result.affected.len(), 1);
+        assert_eq!(result.affected[0].target_name, "M42");
+        let exposure = &result.sequence.targets[1].exposures[0];
+        assert_eq!(exposure.filter.as_ref().unwrap().name, "H-alpha");
+        assert_eq!(exposure.exposure_time, 30.0);
+        assert!(result.sequence.is_dirty);
+    }
+
+    #[test]
+    fn test_set_exposures_enabled() {
+        let mut sequence = create_sequence();
+        let selector = ExposureSelector {
+            filter_name: Some("HA".to_string()),
+            ..Default::default()
+        };
+
+        assert_eq!(set_exposures_enabled(&mut sequence, &selector, false), 3);
+        assert!(sequence.is_dirty);
+        for target in &sequence.targets {
+            assert!(!target.exposures[0].enabled);
+            assert!(target.exposures[1].enabled);
+        }
+
+        // Only exposures that change are counted
+        assert_eq!(set_exposures_enabled(&mut sequence, &selector, false), 0);
+        let selector = ExposureSelector {
+            image_type: Some(ImageType::Light),
+            target_name_contains: Some("M31".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(set_exposures_enabled(&mut sequence, &selector, true), 1);
+        assert!(sequence.targets[0].exposures[0].enabled);
+    }
+
+    #[test]
+    fn test_search_targets_by_tag() {
+        let mut sequence = create_sequence();
+        sequence.targets[0].tags = vec!["Galaxy".to_string(), "Autumn".to_string()];
+        sequence.targets[1].tags = vec!["nebula".to_string(), "autumn".to_string()];
+
+        let tags = |list: &[&str]| list.iter().map(|t| t.to_string()).collect::<Vec<_>>();
+
+        let any = search_targets_by_tag(&sequence, &tags(&["galaxy", "NEBULA"]), false);
+        assert_eq!(any.len(), 2);
+
+        let all = search_targets_by_tag(&sequence, &tags(&["autumn", "galaxy"]), true);
+        assert_eq!(all.len(), 1);
+        assert_eq!(all[0].target_name, "M31");
+
+        assert!(search_targets_by_tag(&sequence, &tags(&[" "]), false).is_empty());
+    }
+
+    #[test]
+    fn test_bulk_edit_rejects_invalid_changes() {
+        let changes = ExposureChangeSet {
+            exposure_time_scale: Some(0.0),
+            ..Default::default()
+        };
+
+        assert!(bulk_edit_exposures(
+            &create_sequence(),
+            &ExposureSelector::default(),
+            &changes,
+            false
+        )
+        .is_err());
+    }
+
+    fn overlapping_sequence() -> SimpleSequence {
+        use crate::models::Coordinates;
+
+        let mut sequence = create_sequence();
+        sequence.targets[0].coordinates = Coordinates::new(0, 42, 44.0, 41, 16, 9.0, false);
+        sequence.targets[1].coordinates = Coordinates::new(5, 35, 16.0, 5, 23, 28.0, true);
+        sequence.targets[2].coordinates = Coordinates::new(0, 42, 46.0, 41, 16, 30.0, false);
+        sequence.targets[2].target_name = "Andromeda".to_string();
+        sequence
+    }
+
+    #[test]
+    fn test_find_duplicate_targets() {
+        let sequence = overlapping_sequence();
+
+        let groups = find_duplicate_targets(&sequence, 1.0);
+        assert_eq!(groups.len(), 1);
+        assert_eq!(
+            groups[0].target_ids,
+            vec![
+                sequence.targets[0].id.clone(),
+                sequence.targets[2].id.clone()
+            ]
+        );
+        assert!(groups[0].max_separation_arcmin < 1.0);
+
+        assert!(find_duplicate_targets(&sequence, 0.1).is_empty());
+    }
+
+    #[test]
+    fn test_merge_targets_combines_exposures() {
+        let mut sequence = overlapping_sequence();
+        sequence.targets[2].exposures[0].total_count = 5;
+        sequence.targets[2].exposures[1].filter = Some(FilterInfo {
+            name: "SII".to_string(),
+            ..Default::default()
+        });
+        sequence.targets[2].tags = vec!["galaxy".to_string()];
+        sequence.selected_target_id = Some(sequence.targets[2].id.clone());
+        let ids = vec![
+            sequence.targets[2].id.clone(),
+            sequence.targets[0].id.clone(),
+        ];
+
+        let result = merge_targets(&sequence, &ids, MergeStrategy::SumMatching).unwrap();
+        assert_eq!(result.merged_target_id, sequence.targets[0].id);
+        assert_eq!(result.sequence.targets.len(), 2);
+        let merged = &result.sequence.targets[0];
+        let filters: Vec<&str> = merged
+            .exposures
+            .iter()
+            .map(|e| e.filter.as_ref().unwrap().name.as_str())
+            .collect();
+        assert_eq!(filters, vec!["Ha", "OIII", "SII"]);
+        assert_eq!(merged.exposures[0].total_count, 15);
+        assert_eq!(merged.tags, vec!["galaxy".to_string()]);
+        assert_eq!(
+            result.sequence.selected_target_id,
+            Some(result.merged_target_id.clone())
+        );
+
+        let result = merge_targets(&sequence, &ids, MergeStrategy::MaxMatching).unwrap();
+        assert_eq!(result.sequence.targets[0].exposures[0].total_count, 10);
+
+        let result = merge_targets(&sequence, &ids, MergeStrategy::Append).unwrap();
+        assert_eq!(result.sequence.targets[0].exposures.len(), 4);
+
+        assert!(merge_targets(&sequence, &ids[..1], MergeStrategy::Append).is_err());
+        assert_eq!(
+            merge_targets(&sequence, &["missing".to_string()], MergeStrategy::Append).unwrap_err(),
+            AppError::NotFound("Target not found: missing".to_string())
+        );
+    }
+
+    #[test]
+    fn test_append_exposure_ramp() {
+        let mut sequence = create_sequence();
+        let target_id = sequence.targets[1].id.clone();
+        let template = SimpleExposure {
+            image_type: ImageType::Flat,
+            total_count: 3,
+            progress_count: 2,
+            ..Default::default()
+        };
+        let ramp = ExposureRamp {
+            start: 1.0,
+            end: 16.0,
+            steps: 5,
+            scaling: RampScaling::Logarithmic,
+            parameter: RampParameter::ExposureTime,
+        };
+
+        let ids = append_exposure_ramp(&mut sequence, &target_id, &ramp, &template).unwrap();
+        let exposures = &sequence.targets[1].exposures;
+        assert_eq!(ids.len(), 5);
+        assert_eq!(exposures.len(), 7);
+        let times: Vec<f64> = exposures[2..].iter().map(|e| e.exposure_time).collect();
+        assert_eq!(times, [1.0, 2.0, 4.0, 8.0, 16.0]);
+        assert!(exposures[2..]
+            .iter()
+            .all(|e| e.image_type == ImageType::Flat
+                && e.total_count == 3
+                && e.progress_count == 0));
+        assert!(sequence.is_dirty);
+
+        let gains = ExposureRamp {
+            start: 200.0,
+            end: 0.0,
+            steps: 3,
+            scaling: RampScaling::Linear,
+            parameter: RampParameter::Gain,
+        };
+        let exposures = generate_exposure_ramp(&gains, &template).unwrap();
+        let values: Vec<i32> = exposures.iter().map(|e| e.gain).collect();
+        assert_eq!(values, [200, 100, 0]);
+
+        let bad = ExposureRamp {
+            scaling: RampScaling::Logarithmic,
+            ..gains
+        };
+        assert!(generate_exposure_ramp(&bad, &template).is_err());
+        assert!(append_exposure_ramp(&mut sequence, "missing", &ramp, &template).is_err());
+    }
+}
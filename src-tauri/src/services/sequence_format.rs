@@ -0,0 +1,224 @@
+//! Sequence file format detection
+//!
+//! Tells the sequence formats the app opens apart by content rather than
+//! extension, so one open dialog covers all of them: simple and editor
+//! sequences saved by the app, NINA sequences and templates, and legacy
+//! NINA XML target sets.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::Path;
+
+use crate::models::{EditorSequence, SimpleSequence};
+use crate::services::nina_type_registry::NinaTypeSchema;
+use crate::services::{file_service, import_service, nina_serializer, serializer};
+
+/// Format of a sequence file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SequenceFormat {
+    SimpleJson,
+    EditorJson,
+    /// NINA sequence with a root container
+    NinaSequence,
+    /// NINA template: a container without the root
+    NinaTemplate,
+    /// Legacy NINA target set (`ArrayOfCaptureSequenceList`)
+    XmlTargetSet,
+}
+
+/// Sequence loaded by [`load_any_sequence`], tagged with its format
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "format", rename_all = "camelCase")]
+pub enum LoadedSequence {
+    SimpleJson {
+        sequence: SimpleSequence,
+    },
+    EditorJson {
+        sequence: EditorSequence,
+    },
+    NinaSequence {
+        sequence: EditorSequence,
+    },
+    NinaTemplate {
+        sequence: EditorSequence,
+    },
+    XmlTargetSet {
+        sequence: SimpleSequence,
+        /// Targets that could not be read
+        warnings: Vec<String>,
+    },
+}
+
+impl LoadedSequence {
+    pub fn format(&self) -> SequenceFormat {
+        match self {
+            LoadedSequence::SimpleJson { .. } => SequenceFormat::SimpleJson,
+            LoadedSequence::EditorJson { .. } => SequenceFormat::EditorJson,
+            LoadedSequence::NinaSequence { .. } => SequenceFormat::NinaSequence,
+            LoadedSequence::NinaTemplate { .. } => SequenceFormat::NinaTemplate,
+            LoadedSequence::XmlTargetSet { .. } => SequenceFormat::XmlTargetSet,
+        }
+    }
+}
+
+/// Format of JSON content
+fn detect_json_format(value: &Value) -> Option<SequenceFormat> {
+    let object = value.as_object()?;
+    if let Some(type_name) = object.get("$type").and_then(|v| v.as_str()) {
+        return if type_name.contains("SequenceRootContainer") {
+            Some(SequenceFormat::NinaSequence)
+        } else if type_name.contains("Container") {
+            Some(SequenceFormat::NinaTemplate)
+        } else {
+            None
+        };
+    }
+
+    if object.get("targets").is_some_and(Value::is_array) {
+        Some(SequenceFormat::SimpleJson)
+    } else if object.get("targetItems").is_some_and(Value::is_array) {
+        Some(SequenceFormat::EditorJson)
+    } else {
+        None
+    }
+}
+
+/// Format of file content, `None` if it is no sequence format
+pub fn detect_format(content: &str) -> Option<SequenceFormat> {
+    let content = content.trim_start_matches('\u{feff}').trim_start();
+    if content.starts_with('<') {
+        return content
+            .contains("CaptureSequenceList")
+            .then_some(SequenceFormat::XmlTargetSet);
+    }
+    let value: Value = serde_json::from_str(content).ok()?;
+    detect_json_format(&value)
+}
+
+/// Load sequence content in whatever format it has. `name` titles
+/// target sets, which carry no title of their own; `stable_nina_ids`
+/// and `plugins` are passed on to [`nina_serializer::import_from_nina`].
+pub fn parse_any_sequence(
+    content: &str,
+    name: &str,
+    stable_nina_ids: bool,
+    plugins: &[NinaTypeSchema],
+) -> Result<LoadedSequence, String> {
+    let format = detect_format(content).ok_or("Not a sequence file in a known format")?;
+    let content = content.trim_start_matches('\u{feff}');
+
+    match format {
+        SequenceFormat::SimpleJson => serializer::deserialize_simple_sequence_json(content)
+            .map(|sequence| LoadedSequence::SimpleJson { sequence })
+            .map_err(|e| e.to_string()),
+        SequenceFormat::EditorJson => serializer::deserialize_editor_sequence_json(content)
+            .map(|sequence| LoadedSequence::EditorJson { sequence })
+            .map_err(|e| e.to_string()),
+        SequenceFormat::NinaSequence => {
+            nina_serializer::import_from_nina(content, stable_nina_ids, plugins)
+                .map(|sequence| LoadedSequence::NinaSequence { sequence })
+        }
+        SequenceFormat::NinaTemplate => {
+            nina_serializer::import_from_nina(content, stable_nina_ids, plugins)
+                .map(|sequence| LoadedSequence::NinaTemplate { sequence })
+        }
+        SequenceFormat::XmlTargetSet => {
+            let mut result = import_service::import_nina_xml_target_set(content)?;
+            result.sequence.title = name.to_string();
+            Ok(LoadedSequence::XmlTargetSet {
+                sequence: result.sequence,
+                warnings: result.warnings,
+            })
+        }
+    }
+}
+
+/// Load a sequence file in whatever format it has
+pub async fn load_any_sequence(
+    path: &Path,
+    stable_nina_ids: bool,
+    plugins: &[NinaTypeSchema],
+) -> Result<LoadedSequence, String> {
+    let content = file_service::read_file(path)
+        .await
+        .map_err(|e| e.to_string())?;
+    let name = path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("Target Set");
+
+    let mut loaded = parse_any_sequence(&content, name, stable_nina_ids, plugins)?;
+    if let LoadedSequence::SimpleJson { sequence } = &mut loaded {
+        sequence.save_path = Some(path.display().to_string());
+        sequence.is_dirty = false;
+    }
+    Ok(loaded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::SimpleTarget;
+
+    #[test]
+    fn test_detect_format() {
+        let simple =
+            serializer::serialize_simple_sequence_json(&SimpleSequence::default()).unwrap();
+        assert_eq!(detect_format(&simple), Some(SequenceFormat::SimpleJson));
+
+        let editor =
+            serializer::serialize_editor_sequence_json(&EditorSequence::new("Plan")).unwrap();
+        assert_eq!(detect_format(&editor), Some(SequenceFormat::EditorJson));
+
+        let nina = nina_serializer::export_to_nina(&EditorSequence::new("Plan"), &[]).unwrap();
+        assert_eq!(detect_format(&nina), Some(SequenceFormat::NinaSequence));
+
+        let template =
+            r#"{"$type":"NINA.Sequencer.Container.SequentialContainer, NINA.Sequencer"}"#;
+        assert_eq!(detect_format(template), Some(SequenceFormat::NinaTemplate));
+
+        let target_set = serializer::export_to_xml(&SimpleSequence::default()).unwrap();
+        assert_eq!(
+            detect_format(&format!("\u{feff}{}", target_set)),
+            Some(SequenceFormat::XmlTargetSet)
+        );
+
+        assert_eq!(detect_format("<Voyager></Voyager>"), None);
+        assert_eq!(detect_format(r#"{"name":"M31"}"#), None);
+        assert_eq!(detect_format("Name,RA,Dec"), None);
+    }
+
+    #[test]
+    fn test_parse_target_set() {
+        let sequence = SimpleSequence {
+            targets: vec![
+                SimpleTarget {
+                    target_name: "M31".to_string(),
+                    ..Default::default()
+                },
+                SimpleTarget {
+                    target_name: "M42".to_string(),
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        };
+        let content = serializer::export_to_xml(&sequence).unwrap();
+
+        let loaded = parse_any_sequence(&content, "Autumn", false, &[]).unwrap();
+        assert_eq!(loaded.format(), SequenceFormat::XmlTargetSet);
+        let LoadedSequence::XmlTargetSet { sequence, .. } = loaded else {
+            unreachable!();
+        };
+        assert_eq!(sequence.title, "Autumn");
+        let names: Vec<&str> = sequence
+            .targets
+            .iter()
+            .map(|t| t.target_name.as_str())
+            .collect();
+        assert_eq!(names, ["M31", "M42"]);
+
+        assert!(parse_any_sequence("{}", "Empty", false, &[]).is_err());
+    }
+}
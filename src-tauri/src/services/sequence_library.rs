@@ -0,0 +1,607 @@
+//! Sequence library
+//!
+//! Indexes a folder of sequence files (simple, editor and NINA JSON, and
+//! `.ctes` archives) so they can be searched without opening each file.
+//! The index is kept in the app data directory; a refresh only re-reads
+//! files whose size or modification time changed.
+
+use chrono::{DateTime, Utc};
+use parking_lot::RwLock;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use tokio::fs;
+
+use crate::error::{AppError, AppResult};
+use crate::models::{EditorSequence, EditorSequenceItem, SimpleSequence};
+use crate::services::nina_type_registry::{self, NinaTypeSchema};
+use crate::services::{file_service, nina_serializer, sequence_archive, validator};
+use crate::state::AppState;
+
+/// File extensions considered for the library
+const LIBRARY_EXTENSIONS: &[&str] = &["json", sequence_archive::SEQUENCE_ARCHIVE_EXTENSION];
+
+/// Sequence file format
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum LibrarySequenceKind {
+    Simple,
+    Editor,
+    Nina,
+    Archive,
+}
+
+/// Indexed sequence file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LibraryEntry {
+    pub path: String,
+    pub file_name: String,
+    pub kind: LibrarySequenceKind,
+    pub title: String,
+    pub target_count: usize,
+    pub target_names: Vec<String>,
+    /// Planned integration time in seconds
+    pub total_integration: f64,
+    pub modified_at: DateTime<Utc>,
+    pub size: u64,
+}
+
+/// File that could not be indexed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LibraryIndexError {
+    pub path: String,
+    pub message: String,
+}
+
+/// Library index of one directory
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LibraryIndex {
+    pub directory: String,
+    pub indexed_at: DateTime<Utc>,
+    pub entries: Vec<LibraryEntry>,
+    #[serde(default)]
+    pub errors: Vec<LibraryIndexError>,
+}
+
+/// Sort order of search results
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum LibrarySort {
+    /// Most recently modified first
+    #[default]
+    Modified,
+    Title,
+    /// Longest integration first
+    Integration,
+}
+
+/// Library search query
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LibrarySearchQuery {
+    /// Whitespace-separated terms, each matched against title, file name
+    /// and target names, ignoring case. Empty matches everything.
+    #[serde(default)]
+    pub text: String,
+    #[serde(default)]
+    pub kind: Option<LibrarySequenceKind>,
+    #[serde(default)]
+    pub sort: LibrarySort,
+    #[serde(default)]
+    pub limit: Option<usize>,
+}
+
+/// Loaded index and refresh flag, part of the
+/// [`AppState`](crate::state::AppState)
+#[derive(Default)]
+pub struct LibraryState {
+    index: RwLock<Option<LibraryIndex>>,
+    refreshing: AtomicBool,
+}
+
+/// Sequence summary stored in the index
+struct SequenceSummary {
+    kind: LibrarySequenceKind,
+    title: String,
+    target_names: Vec<String>,
+    total_integration: f64,
+}
+
+fn summarize_simple(sequence: &SimpleSequence, kind: LibrarySequenceKind) -> SequenceSummary {
+    SequenceSummary {
+        kind,
+        title: sequence.title.clone(),
+        target_names: sequence
+            .targets
+            .iter()
+            .map(|t| t.target_name.clone())
+            .collect(),
+        total_integration: sequence.targets.iter().map(|t| t.integration_time()).sum(),
+    }
+}
+
+fn data_number(data: &HashMap<String, Value>, key: &str) -> Option<f64> {
+    data.iter()
+        .find(|(k, _)| k.eq_ignore_ascii_case(key))
+        .and_then(|(_, v)| v.as_f64())
+}
+
+/// Integration of exposure items, multiplied by the iterations of
+/// enclosing loop conditions
+fn editor_integration(items: &[EditorSequenceItem], repeat: f64) -> f64 {
+    items
+        .iter()
+        .map(|item| {
+            let iterations = item
+                .conditions
+                .iter()
+                .flatten()
+                .filter(|c| validator::get_short_type_name(&c.condition_type) == "LoopCondition")
+                .filter_map(|c| data_number(&c.data, "iterations"))
+                .fold(1.0, |acc, n| acc * n.max(0.0));
+            let repeat = repeat * iterations;
+
+            let own = if validator::get_short_type_name(&item.item_type) == "TakeExposure" {
+                data_number(&item.data, "exposureTime").unwrap_or(0.0)
+            } else {
+                0.0
+            };
+            own * repeat + editor_integration(item.items.as_deref().unwrap_or_default(), repeat)
+        })
+        .sum()
+}
+
+fn summarize_editor(sequence: &EditorSequence, kind: LibrarySequenceKind) -> SequenceSummary {
+    fn target_names(items: &[EditorSequenceItem], names: &mut Vec<String>) {
+        for item in items {
+            if validator::get_short_type_name(&item.item_type) == "DeepSkyObjectContainer" {
+                names.push(item.name.clone());
+            }
+            target_names(item.items.as_deref().unwrap_or_default(), names);
+        }
+    }
+
+    let mut names = Vec::new();
+    for items in [
+        &sequence.start_items,
+        &sequence.target_items,
+        &sequence.end_items,
+    ] {
+        target_names(items, &mut names);
+    }
+
+    SequenceSummary {
+        kind,
+        title: sequence.title.clone(),
+        target_names: names,
+        total_integration: [
+            &sequence.start_items,
+            &sequence.target_items,
+            &sequence.end_items,
+        ]
+        .iter()
+        .map(|items| editor_integration(items, 1.0))
+        .sum(),
+    }
+}
+
+/// Summarize a JSON sequence file, detecting its format
+fn summarize_json(content: &str, plugins: &[NinaTypeSchema]) -> Result<SequenceSummary, String> {
+    let value: Value = serde_json::from_str(content).map_err(|e| format!("Invalid JSON: {}", e))?;
+
+    if value.get("$type").is_some() {
+        let sequence = nina_serializer::import_from_nina(content, false, plugins)?;
+        Ok(summarize_editor(&sequence, LibrarySequenceKind::Nina))
+    } else if value.get("targetItems").is_some() {
+        let sequence: EditorSequence =
+            serde_json::from_value(value).map_err(|e| format!("Invalid editor sequence: {}", e))?;
+        Ok(summarize_editor(&sequence, LibrarySequenceKind::Editor))
+    } else if value.get("targets").is_some() {
+        let sequence: SimpleSequence =
+            serde_json::from_value(value).map_err(|e| format!("Invalid sequence: {}", e))?;
+        Ok(summarize_simple(&sequence, LibrarySequenceKind::Simple))
+    } else {
+        Err("Not a sequence file".to_string())
+    }
+}
+
+/// Summarize the raw contents of a library file
+fn summarize_file(
+    path: &Path,
+    bytes: &[u8],
+    plugins: &[NinaTypeSchema],
+) -> Result<SequenceSummary, String> {
+    let extension = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or_default()
+        .to_lowercase();
+
+    if extension == sequence_archive::SEQUENCE_ARCHIVE_EXTENSION {
+        let archive = sequence_archive::read_sequence_archive(bytes)?;
+        Ok(summarize_simple(
+            &archive.sequence,
+            LibrarySequenceKind::Archive,
+        ))
+    } else {
+        let content = std::str::from_utf8(bytes).map_err(|_| "File is not UTF-8".to_string())?;
+        summarize_json(content, plugins)
+    }
+}
+
+/// Candidate file found while scanning
+struct LibraryFile {
+    path: PathBuf,
+    modified_at: DateTime<Utc>,
+    size: u64,
+}
+
+/// Find library files below `directory`, skipping hidden entries
+async fn scan_directory(directory: &Path) -> Result<Vec<LibraryFile>, String> {
+    let mut files = Vec::new();
+    let mut pending = vec![directory.to_path_buf()];
+
+    while let Some(dir) = pending.pop() {
+        let mut entries = fs::read_dir(&dir)
+            .await
+            .map_err(|e| format!("Failed to read {}: {}", dir.display(), e))?;
+
+        while let Some(entry) = entries.next_entry().await.map_err(|e| e.to_string())? {
+            let path = entry.path();
+            let hidden = path
+                .file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(|n| n.starts_with('.'));
+            if hidden {
+                continue;
+            }
+
+            let Ok(metadata) = entry.metadata().await else {
+                continue;
+            };
+            if metadata.is_dir() {
+                pending.push(path);
+                continue;
+            }
+
+            let extension = path
+                .extension()
+                .and_then(|e| e.to_str())
+                .unwrap_or_default()
+                .to_lowercase();
+            if !LIBRARY_EXTENSIONS.contains(&extension.as_str()) {
+                continue;
+            }
+
+            files.push(LibraryFile {
+                path,
+                modified_at: metadata
+                    .modified()
+                    .map(DateTime::<Utc>::from)
+                    .unwrap_or_else(|_| Utc::now()),
+                size: metadata.len(),
+            });
+        }
+    }
+
+    Ok(files)
+}
+
+fn index_path() -> PathBuf {
+    file_service::get_app_data_directory().join("library_index.json")
+}
+
+/// Load the index, from memory or from the index file
+pub async fn get_library_index(state: &AppState) -> Option<LibraryIndex> {
+    if let Some(index) = state.library.index.read().clone() {
+        return Some(index);
+    }
+
+    let content = fs::read_to_string(index_path()).await.ok()?;
+    let index: LibraryIndex = serde_json::from_str(&content)
+        .map_err(|e| log::warn!("Ignoring unreadable library index: {}", e))
+        .ok()?;
+    *state.library.index.write() = Some(index.clone());
+    Some(index)
+}
+
+/// Build the index of `directory` from the previous index and a scan.
+/// Entries of unchanged files are reused; NINA files are read with the
+/// `plugins` types.
+async fn build_index(
+    directory: &Path,
+    previous: Option<LibraryIndex>,
+    plugins: Vec<NinaTypeSchema>,
+) -> Result<LibraryIndex, String> {
+    let directory_name = directory.display().to_string();
+    let mut known: HashMap<String, LibraryEntry> = previous
+        .filter(|index| index.directory == directory_name)
+        .map(|index| {
+            index
+                .entries
+                .into_iter()
+                .map(|entry| (entry.path.clone(), entry))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let mut entries = Vec::new();
+    let mut changed = Vec::new();
+    for file in scan_directory(directory).await? {
+        let path = file.path.display().to_string();
+        match known.remove(&path) {
+            Some(entry) if entry.size == file.size && entry.modified_at == file.modified_at => {
+                entries.push(entry)
+            }
+            _ => {
+                let bytes = fs::read(&file.path).await.map_err(|e| e.to_string());
+                changed.push((file, bytes));
+            }
+        }
+    }
+
+    // Parsing is CPU-bound; spread it across cores off the async runtime
+    let parsed = tokio::task::spawn_blocking(move || {
+        changed
+            .into_par_iter()
+            .map(|(file, bytes)| {
+                let summary = bytes.and_then(|bytes| summarize_file(&file.path, &bytes, &plugins));
+                (file, summary)
+            })
+            .collect::<Vec<_>>()
+    })
+    .await
+    .map_err(|e| format!("Library indexing failed: {}", e))?;
+
+    let mut errors = Vec::new();
+    for (file, summary) in parsed {
+        let path = file.path.display().to_string();
+        match summary {
+            Ok(summary) => entries.push(LibraryEntry {
+                file_name: file
+                    .path
+                    .file_name()
+                    .map(|n| n.to_string_lossy().into_owned())
+                    .unwrap_or_default(),
+                path,
+                kind: summary.kind,
+                title: summary.title,
+                target_count: summary.target_names.len(),
+                target_names: summary.target_names,
+                total_integration: summary.total_integration,
+                modified_at: file.modified_at,
+                size: file.size,
+            }),
+            Err(message) => errors.push(LibraryIndexError { path, message }),
+        }
+    }
+    entries.sort_by(|a, b| a.path.cmp(&b.path));
+
+    Ok(LibraryIndex {
+        directory: directory_name,
+        indexed_at: Utc::now(),
+        entries,
+        errors,
+    })
+}
+
+/// Re-index `directory` and persist the index. Only one refresh runs at a
+/// time; a concurrent call fails.
+pub async fn refresh_library_index(state: &AppState, directory: &Path) -> AppResult<LibraryIndex> {
+    if !directory.is_dir() {
+        return Err(AppError::NotFound(format!(
+            "Library directory not found: {}",
+            directory.display()
+        )));
+    }
+    if state.library.refreshing.swap(true, Ordering::SeqCst) {
+        return Err(AppError::InvalidInput(
+            "Library index refresh already in progress".to_string(),
+        ));
+    }
+
+    let result = async {
+        let plugins = nina_type_registry::plugin_types(state);
+        let index = build_index(directory, get_library_index(state).await, plugins)
+            .await
+            .map_err(AppError::Io)?;
+
+        let path = index_path();
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .await
+                .map_err(|e| AppError::Io(format!("Failed to create app data directory: {}", e)))?;
+        }
+        let content = serde_json::to_string(&index)
+            .map_err(|e| AppError::Internal(format!("Failed to serialize library index: {}", e)))?;
+        fs::write(&path, content)
+            .await
+            .map_err(|e| AppError::Io(format!("Failed to write library index: {}", e)))?;
+
+        *state.library.index.write() = Some(index.clone());
+        Ok(index)
+    }
+    .await;
+
+    state.library.refreshing.store(false, Ordering::SeqCst);
+    result
+}
+
+/// Filter and sort index entries
+pub fn search_library(index: &LibraryIndex, query: &LibrarySearchQuery) -> Vec<LibraryEntry> {
+    let terms: Vec<String> = query
+        .text
+        .split_whitespace()
+        .map(|t| t.to_lowercase())
+        .collect();
+
+    let mut results: Vec<LibraryEntry> = index
+        .entries
+        .iter()
+        .filter(|entry| query.kind.map_or(true, |kind| entry.kind == kind))
+        .filter(|entry| {
+            let haystack = format!(
+                "{}\n{}\n{}",
+                entry.title,
+                entry.file_name,
+                entry.target_names.join("\n")
+            )
+            .to_lowercase();
+            terms.iter().all(|term| haystack.contains(term.as_str()))
+        })
+        .cloned()
+        .collect();
+
+    match query.sort {
+        LibrarySort::Modified => results.sort_by_key(|e| std::cmp::Reverse(e.modified_at)),
+        LibrarySort::Title => results.sort_by_key(|e| e.title.to_lowercase()),
+        LibrarySort::Integration => {
+            results.sort_by(|a, b| b.total_integration.total_cmp(&a.total_integration))
+        }
+    }
+    if let Some(limit) = query.limit {
+        results.truncate(limit);
+    }
+    results
+}
+
+/// Search the current index
+pub async fn search_sequence_library(
+    state: &AppState,
+    query: &LibrarySearchQuery,
+) -> Result<Vec<LibraryEntry>, String> {
+    let index = get_library_index(state)
+        .await
+        .ok_or_else(|| "Sequence library has not been indexed".to_string())?;
+    Ok(search_library(&index, query))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::SimpleTarget;
+    use serde_json::json;
+
+    fn editor_item(item_type: &str, name: &str, data: Value) -> EditorSequenceItem {
+        serde_json::from_value(json!({
+            "id": uuid::Uuid::new_v4().to_string(),
+            "type": item_type,
+            "name": name,
+            "category": "",
+            "status": "CREATED",
+            "data": data,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_summarize_simple_json() {
+        let mut sequence = SimpleSequence::new("Autumn");
+        let mut target = SimpleTarget {
+            target_name: "M31".to_string(),
+            ..Default::default()
+        };
+        target.exposures[0].exposure_time = 120.0;
+        target.exposures[0].total_count = 10;
+        sequence.targets = vec![target];
+
+        let summary = summarize_json(&serde_json::to_string(&sequence).unwrap(), &[]).unwrap();
+        assert_eq!(summary.kind, LibrarySequenceKind::Simple);
+        assert_eq!(summary.title, "Autumn");
+        assert_eq!(summary.target_names, vec!["M31"]);
+        assert_eq!(summary.total_integration, 1200.0);
+    }
+
+    #[test]
+    fn test_summarize_editor_json() {
+        let mut container = editor_item(
+            "NINA.Sequencer.Container.DeepSkyObjectContainer, NINA.Sequencer",
+            "M42",
+            json!({}),
+        );
+        let mut loop_container = editor_item(
+            "NINA.Sequencer.Container.SequentialContainer, NINA.Sequencer",
+            "Loop",
+            json!({}),
+        );
+        loop_container.conditions = Some(vec![serde_json::from_value(json!({
+            "id": "c1",
+            "type": "NINA.Sequencer.Conditions.LoopCondition, NINA.Sequencer",
+            "name": "Loop",
+            "category": "",
+            "data": { "Iterations": 5 },
+        }))
+        .unwrap()]);
+        loop_container.items = Some(vec![editor_item(
+            "NINA.Sequencer.SequenceItem.Imaging.TakeExposure, NINA.Sequencer",
+            "Take Exposure",
+            json!({ "exposureTime": 60 }),
+        )]);
+        container.items = Some(vec![loop_container]);
+
+        let mut sequence = EditorSequence::new("Winter");
+        sequence.target_items = vec![container];
+
+        let summary = summarize_json(&serde_json::to_string(&sequence).unwrap(), &[]).unwrap();
+        assert_eq!(summary.kind, LibrarySequenceKind::Editor);
+        assert_eq!(summary.target_names, vec!["M42"]);
+        assert_eq!(summary.total_integration, 300.0);
+
+        assert!(summarize_json("{\"foo\": 1}", &[]).is_err());
+    }
+
+    fn entry(title: &str, targets: &[&str], integration: f64, minutes_ago: i64) -> LibraryEntry {
+        LibraryEntry {
+            path: format!("/library/{}.json", title),
+            file_name: format!("{}.json", title),
+            kind: LibrarySequenceKind::Simple,
+            title: title.to_string(),
+            target_count: targets.len(),
+            target_names: targets.iter().map(|t| t.to_string()).collect(),
+            total_integration: integration,
+            modified_at: Utc::now() - chrono::Duration::minutes(minutes_ago),
+            size: 100,
+        }
+    }
+
+    #[test]
+    fn test_search_library() {
+        let index = LibraryIndex {
+            directory: "/library".to_string(),
+            indexed_at: Utc::now(),
+            entries: vec![
+                entry("Andromeda mosaic", &["M31 P1", "M31 P2"], 3600.0, 30),
+                entry("Orion", &["M42", "NGC 1977"], 7200.0, 10),
+                entry("Galaxies", &["M81", "M82"], 1800.0, 20),
+            ],
+            errors: Vec::new(),
+        };
+
+        let titles = |query: &LibrarySearchQuery| -> Vec<String> {
+            search_library(&index, query)
+                .into_iter()
+                .map(|e| e.title)
+                .collect()
+        };
+
+        let mut query = LibrarySearchQuery::default();
+        assert_eq!(
+            titles(&query),
+            vec!["Orion", "Galaxies", "Andromeda mosaic"]
+        );
+
+        query.text = "m31 mosaic".to_string();
+        assert_eq!(titles(&query), vec!["Andromeda mosaic"]);
+
+        query.text = "m".to_string();
+        query.sort = LibrarySort::Integration;
+        query.limit = Some(2);
+        assert_eq!(titles(&query), vec!["Orion", "Andromeda mosaic"]);
+    }
+}
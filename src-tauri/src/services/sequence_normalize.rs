@@ -0,0 +1,390 @@
+//! Sequence normalization
+//!
+//! Files from other tools or older versions often carry small defects:
+//! coordinates with 60 seconds or a declination past the pole, negative
+//! position angles, placeholder exposures with nothing to take, ids
+//! copied from another entry, stray whitespace. [`normalize_sequence`]
+//! fixes all of them in one pass and reports each fix.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+use crate::models::{Coordinates, SimpleSequence, SimpleTarget};
+
+/// Kind of fix applied by [`normalize_sequence`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum NormalizationFixKind {
+    Coordinates,
+    Angle,
+    Whitespace,
+    RemovedExposure,
+    RemovedTarget,
+    DuplicateId,
+}
+
+/// A fix applied by [`normalize_sequence`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NormalizationFix {
+    pub kind: NormalizationFixKind,
+    /// Id of the sequence, target or exposure, after any id change
+    pub entity_id: String,
+    pub description: String,
+}
+
+/// Result of [`normalize_sequence`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NormalizationResult {
+    pub sequence: SimpleSequence,
+    pub fixes: Vec<NormalizationFix>,
+}
+
+/// Split a value into whole units, minutes and seconds rounded to 0.01,
+/// rounding before splitting so seconds never reach 60
+fn split_sexagesimal(value: f64) -> (i32, i32, f64) {
+    let total = (value * 360_000.0).round() / 100.0;
+    let units = (total / 3600.0).floor();
+    let minutes = ((total - units * 3600.0) / 60.0).floor();
+    let seconds = ((total - units * 3600.0 - minutes * 60.0) * 100.0).round() / 100.0;
+    (units as i32, minutes as i32, seconds)
+}
+
+/// Coordinates with RA wrapped into [0h, 24h) and Dec clamped to
+/// [-90°, 90°], or `None` when every field is already in range
+fn normalized_coordinates(coordinates: &Coordinates) -> Option<Coordinates> {
+    if coordinates.validate().is_empty() && coordinates.dec_to_decimal().abs() <= 90.0 {
+        return None;
+    }
+
+    let finite = |value: f64| if value.is_finite() { value } else { 0.0 };
+    let ra = coordinates.ra_hours as f64
+        + coordinates.ra_minutes as f64 / 60.0
+        + finite(coordinates.ra_seconds) / 3600.0;
+    let dec = coordinates.dec_degrees.abs() as f64
+        + coordinates.dec_minutes as f64 / 60.0
+        + finite(coordinates.dec_seconds) / 3600.0;
+    let negative = coordinates.negative_dec || coordinates.dec_degrees < 0;
+
+    let (ra_hours, ra_minutes, ra_seconds) = split_sexagesimal(ra.rem_euclid(24.0));
+    let (dec_degrees, dec_minutes, dec_seconds) = split_sexagesimal(dec.min(90.0));
+    Some(Coordinates {
+        ra_hours: ra_hours % 24,
+        ra_minutes,
+        ra_seconds,
+        dec_degrees,
+        dec_minutes,
+        dec_seconds,
+        negative_dec: negative && dec > 0.0,
+        epoch: coordinates.epoch,
+    })
+}
+
+/// Angle wrapped into [0, 360), or `None` when already in range
+fn normalized_angle(angle: f64) -> Option<f64> {
+    if !angle.is_finite() {
+        return Some(0.0);
+    }
+    let wrapped = angle.rem_euclid(360.0);
+    // rem_euclid can round up to 360 for tiny negative angles
+    let wrapped = if wrapped >= 360.0 { 0.0 } else { wrapped };
+    (wrapped != angle).then_some(wrapped)
+}
+
+/// Trim `value` in place, returning whether it changed
+fn trim_in_place(value: &mut String) -> bool {
+    let trimmed = value.trim();
+    if trimmed.len() == value.len() {
+        return false;
+    }
+    *value = trimmed.to_string();
+    true
+}
+
+struct Fixes(Vec<NormalizationFix>);
+
+impl Fixes {
+    fn push(&mut self, kind: NormalizationFixKind, entity_id: &str, description: String) {
+        self.0.push(NormalizationFix {
+            kind,
+            entity_id: entity_id.to_string(),
+            description,
+        });
+    }
+}
+
+fn normalize_target(target: &mut SimpleTarget, fixes: &mut Fixes) {
+    use NormalizationFixKind::*;
+
+    if trim_in_place(&mut target.name) {
+        fixes.push(
+            Whitespace,
+            &target.id,
+            "Trimmed the target name".to_string(),
+        );
+    }
+    if trim_in_place(&mut target.target_name) {
+        fixes.push(
+            Whitespace,
+            &target.id,
+            format!("Trimmed the object name of '{}'", target.name),
+        );
+    }
+
+    if let Some(coordinates) = normalized_coordinates(&target.coordinates) {
+        fixes.push(
+            Coordinates,
+            &target.id,
+            format!(
+                "Coordinates of '{}' set to {} {}",
+                target.name,
+                coordinates.format_ra(),
+                coordinates.format_dec()
+            ),
+        );
+        target.coordinates = coordinates;
+    }
+    if let Some(angle) = normalized_angle(target.position_angle) {
+        fixes.push(
+            Angle,
+            &target.id,
+            format!(
+                "Position angle of '{}' changed from {} to {}",
+                target.name, target.position_angle, angle
+            ),
+        );
+        target.position_angle = angle;
+    }
+    if let Some(angle) = normalized_angle(target.rotation) {
+        fixes.push(
+            Angle,
+            &target.id,
+            format!(
+                "Rotation of '{}' changed from {} to {}",
+                target.name, target.rotation, angle
+            ),
+        );
+        target.rotation = angle;
+    }
+
+    for exposure in &mut target.exposures {
+        if let Some(filter) = &mut exposure.filter {
+            if trim_in_place(&mut filter.name) {
+                fixes.push(
+                    Whitespace,
+                    &exposure.id,
+                    format!("Trimmed the filter name '{}'", filter.name),
+                );
+            }
+        }
+    }
+
+    let name = target.name.clone();
+    target.exposures.retain(|exposure| {
+        if exposure.total_count > 0 {
+            return true;
+        }
+        fixes.push(
+            RemovedExposure,
+            &exposure.id,
+            format!("Removed an exposure with no frames to take from '{}'", name),
+        );
+        false
+    });
+}
+
+/// Give every entity without an id or with an id already taken a new one
+fn deduplicate_ids(sequence: &mut SimpleSequence, fixes: &mut Fixes) {
+    let mut seen = HashSet::new();
+    let mut claim = |id: &mut String, what: &str, fixes: &mut Fixes| {
+        if !id.trim().is_empty() && seen.insert(id.clone()) {
+            return;
+        }
+        let old = std::mem::replace(id, uuid::Uuid::new_v4().to_string());
+        seen.insert(id.clone());
+        let description = if old.trim().is_empty() {
+            format!("Gave {} an id", what)
+        } else {
+            format!("Gave {} a new id in place of the duplicate {}", what, old)
+        };
+        fixes.push(NormalizationFixKind::DuplicateId, id, description);
+    };
+
+    for target in &mut sequence.targets {
+        let what = format!("target '{}'", target.name);
+        claim(&mut target.id, &what, fixes);
+        for exposure in &mut target.exposures {
+            let what = format!("an exposure of '{}'", target.name);
+            claim(&mut exposure.id, &what, fixes);
+        }
+    }
+}
+
+/// Fix common defects in a sequence and report each fix: out-of-range
+/// coordinates, position angles and rotations outside [0, 360), stray
+/// whitespace in names, exposures with no frames, targets left without
+/// exposures, and missing or duplicate ids
+pub fn normalize_sequence(mut sequence: SimpleSequence) -> NormalizationResult {
+    let mut fixes = Fixes(Vec::new());
+
+    if trim_in_place(&mut sequence.title) {
+        fixes.push(
+            NormalizationFixKind::Whitespace,
+            &sequence.id,
+            "Trimmed the sequence title".to_string(),
+        );
+    }
+
+    for target in &mut sequence.targets {
+        normalize_target(target, &mut fixes);
+    }
+    sequence.targets.retain(|target| {
+        if !target.exposures.is_empty() {
+            return true;
+        }
+        fixes.push(
+            NormalizationFixKind::RemovedTarget,
+            &target.id,
+            format!("Removed '{}', which has no exposures", target.name),
+        );
+        false
+    });
+
+    deduplicate_ids(&mut sequence, &mut fixes);
+
+    let exists = |id: &Option<String>| {
+        id.as_ref()
+            .is_some_and(|id| sequence.targets.iter().any(|t| &t.id == id))
+    };
+    if !exists(&sequence.selected_target_id) {
+        sequence.selected_target_id = sequence.targets.first().map(|t| t.id.clone());
+    }
+    if !exists(&sequence.active_target_id) {
+        sequence.active_target_id = None;
+    }
+
+    if !fixes.0.is_empty() {
+        sequence.is_dirty = true;
+    }
+    NormalizationResult {
+        sequence,
+        fixes: fixes.0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{FilterInfo, SimpleExposure};
+
+    fn exposure(id: &str, filter: &str, total_count: i32) -> SimpleExposure {
+        SimpleExposure {
+            id: id.to_string(),
+            filter: Some(FilterInfo {
+                name: filter.to_string(),
+                ..Default::default()
+            }),
+            total_count,
+            ..Default::default()
+        }
+    }
+
+    fn kinds(result: &NormalizationResult) -> Vec<NormalizationFixKind> {
+        result.fixes.iter().map(|fix| fix.kind).collect()
+    }
+
+    #[test]
+    fn test_normalize_sequence() {
+        use NormalizationFixKind as Kind;
+
+        let mut sequence = SimpleSequence::new("Plan");
+        sequence.targets = vec![
+            SimpleTarget {
+                id: "a".to_string(),
+                name: " M31 ".to_string(),
+                target_name: "M31".to_string(),
+                coordinates: Coordinates::new(24, 59, 59.999, 95, 0, 0.0, false),
+                position_angle: -90.0,
+                exposures: vec![exposure("x", "Ha ", 10), exposure("y", "OIII", 0)],
+                ..Default::default()
+            },
+            SimpleTarget {
+                id: "b".to_string(),
+                name: "Empty".to_string(),
+                exposures: vec![exposure("z", "L", 0)],
+                ..Default::default()
+            },
+            SimpleTarget {
+                id: "a".to_string(),
+                name: "M42".to_string(),
+                exposures: vec![exposure("x", "L", 5)],
+                ..Default::default()
+            },
+        ];
+        sequence.selected_target_id = Some("b".to_string());
+
+        let result = normalize_sequence(sequence);
+        assert_eq!(
+            kinds(&result),
+            [
+                Kind::Whitespace,
+                Kind::Coordinates,
+                Kind::Angle,
+                Kind::Whitespace,
+                Kind::RemovedExposure,
+                Kind::RemovedExposure,
+                Kind::RemovedTarget,
+                Kind::DuplicateId,
+                Kind::DuplicateId,
+            ]
+        );
+
+        let sequence = &result.sequence;
+        assert!(sequence.is_dirty);
+        assert_eq!(sequence.targets.len(), 2);
+        let m31 = &sequence.targets[0];
+        assert_eq!(m31.name, "M31");
+        assert_eq!(m31.position_angle, 270.0);
+        assert_eq!(m31.exposures.len(), 1);
+        assert_eq!(m31.exposures[0].filter.as_ref().unwrap().name, "Ha");
+
+        let coordinates = &m31.coordinates;
+        assert_eq!(coordinates.ra_hours, 1);
+        assert_eq!(coordinates.ra_minutes, 0);
+        assert_eq!(coordinates.ra_seconds, 0.0);
+        assert_eq!(coordinates.dec_degrees, 90);
+        assert!(coordinates.validate().is_empty());
+
+        let m42 = &sequence.targets[1];
+        assert_ne!(m42.id, "a");
+        assert_ne!(m42.exposures[0].id, "x");
+        assert_eq!(sequence.selected_target_id.as_deref(), Some("a"));
+    }
+
+    #[test]
+    fn test_normalize_clean_sequence() {
+        let mut sequence = SimpleSequence::new("Plan");
+        sequence.targets[0].coordinates = Coordinates::new(5, 35, 17.3, 5, 23, 28.0, true);
+        sequence.targets[0].exposures = vec![exposure("x", "L", 5)];
+        sequence.targets[0].position_angle = 359.5;
+
+        let result = normalize_sequence(sequence);
+        assert!(result.fixes.is_empty(), "{:?}", result.fixes);
+        assert!(!result.sequence.is_dirty);
+    }
+
+    #[test]
+    fn test_normalized_coordinates() {
+        // Negative degrees without the flag still mean south
+        let coordinates = Coordinates::new(-1, 30, 0.0, -10, 75, 0.0, false);
+        let fixed = normalized_coordinates(&coordinates).unwrap();
+        assert_eq!((fixed.ra_hours, fixed.ra_minutes), (23, 30));
+        assert_eq!((fixed.dec_degrees, fixed.dec_minutes), (11, 15));
+        assert!(fixed.negative_dec);
+
+        assert_eq!(normalized_angle(720.0), Some(0.0));
+        assert_eq!(normalized_angle(f64::NAN), Some(0.0));
+        assert_eq!(normalized_angle(12.5), None);
+    }
+}
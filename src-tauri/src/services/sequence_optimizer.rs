@@ -4,16 +4,42 @@
 //! - Target ordering by altitude/visibility
 //! - Conflict detection
 //! - Runtime optimization
+//! - Exposure count rebalancing to fill the night
 //! - Parallel processing
 
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
 use chrono::{DateTime, Duration, NaiveDate, Utc};
 use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
 
-use crate::models::{Coordinates, SimpleSequence, SimpleTarget};
+use crate::models::{
+    Coordinates, MountProfile, ObservingSite, OverheadProfile, SimpleSequence, SimpleTarget,
+};
 use crate::services::astronomy::{
-    calculate_observation_quality, calculate_visibility_window, ObserverLocation, VisibilityWindow,
+    calculate_observation_quality, calculate_target_rise_set, ObserverLocation, RiseSetTimes,
+    VisibilityWindow,
 };
+use crate::services::dark_calendar::astronomical_night;
+use crate::services::ephemeris::update_moving_targets_for_night;
+use crate::services::job_queue::JobHandle;
+use crate::services::observing_constraints::{
+    blocking_constraints, calculate_imaging_window, constrained_visibility_window,
+    constraints_met_at, ImagingWindow, ImagingWindowOptions,
+};
+use crate::services::satellite::{self, Tle};
+use crate::services::slew_route::{slew_cost_matrix, solve_slew_route, DEFAULT_TIME_BUDGET};
+
+/// Altitude a target must reach to count as visible, unless its
+/// constraints set another minimum
+pub const DEFAULT_MIN_ALTITUDE: f64 = 20.0;
+
+/// Time grid of the time-windowed scheduler
+const SCHEDULE_STEP_MINUTES: i64 = 5;
+
+/// Steps between the start delays the time-windowed scheduler tries
+const SCHEDULE_WAIT_STEPS: usize = 2;
 
 /// Optimization strategy
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -33,6 +59,11 @@ pub enum OptimizationStrategy {
     MoonAvoidance,
     /// Combined optimization score
     Combined,
+    /// Reserve time for higher-priority targets first, then run in time order
+    PriorityWeighted,
+    /// Build the night's schedule target by target, judging each by its
+    /// altitude while it would actually be imaged
+    TimeWindowed,
 }
 
 /// Optimization result
@@ -46,6 +77,14 @@ pub struct OptimizationResult {
     pub warnings: Vec<String>,
     pub estimated_total_runtime: f64,
     pub estimated_slew_time: f64,
+    /// Slew time saved against nearest-neighbor ordering, for
+    /// `MinimizeSlew`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub slew_improvement_percent: Option<f64>,
+    /// Hours of scheduled imaging with the targets above their altitude
+    /// threshold, for `TimeWindowed`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scheduled_integration_hours: Option<f64>,
 }
 
 /// Target scheduling info
@@ -59,6 +98,10 @@ pub struct TargetScheduleInfo {
     pub optimal_end_time: Option<DateTime<Utc>>,
     pub quality_score: f64,
     pub conflicts: Vec<String>,
+    /// Rise and set over the horizon profile, if any, and transit
+    pub rise_set: RiseSetTimes,
+    /// Dark time in which the target meets its constraints
+    pub imaging_window: ImagingWindow,
 }
 
 /// Conflict detection result
@@ -90,6 +133,9 @@ pub enum ConflictType {
     InsufficientTime,
     VisibilityGap,
     MeridianFlip,
+    SatelliteTransit,
+    /// The target rises but its observing constraints are never met
+    ConstraintViolation,
 }
 
 /// Batch calculation result
@@ -100,6 +146,52 @@ pub struct BatchCalculationResult {
     pub runtime: f64,
     pub eta_start: Option<DateTime<Utc>>,
     pub eta_end: Option<DateTime<Utc>>,
+    /// Per exposure row, in the target's execution order
+    pub exposures: Vec<ExposureEta>,
+}
+
+/// When the frames of one exposure row are taken
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExposureEta {
+    pub exposure_id: String,
+    pub frame_count: i32,
+    /// Start of the first frame
+    pub eta_start: DateTime<Utc>,
+    /// End of the last frame
+    pub eta_end: DateTime<Utc>,
+}
+
+/// ETAs of a target's exposure rows when it starts at `start_time`,
+/// including the dither, filter change and autofocus overheads when given.
+/// In rotate mode the rows are interleaved and finish close together.
+pub fn calculate_exposure_etas(
+    target: &SimpleTarget,
+    start_time: DateTime<Utc>,
+    download_time: f64,
+    overheads: Option<&OverheadProfile>,
+) -> Vec<ExposureEta> {
+    let mut etas: Vec<ExposureEta> = Vec::new();
+
+    for frame in target.frame_timings(download_time, overheads) {
+        let exposure = &target.exposures[frame.exposure_index];
+        let frame_start = start_time + Duration::seconds(frame.start as i64);
+        let frame_end = start_time + Duration::seconds(frame.end as i64);
+
+        match etas.iter_mut().find(|e| e.exposure_id == exposure.id) {
+            Some(eta) => {
+                eta.frame_count += 1;
+                eta.eta_end = frame_end;
+            }
+            None => etas.push(ExposureEta {
+                exposure_id: exposure.id.clone(),
+                frame_count: 1,
+                eta_start: frame_start,
+                eta_end: frame_end,
+            }),
+        }
+    }
+    etas
 }
 
 // ============================================================================
@@ -112,22 +204,21 @@ pub fn optimize_sequence(
     location: &ObserverLocation,
     date: NaiveDate,
     strategy: OptimizationStrategy,
+    mount: &MountProfile,
 ) -> OptimizationResult {
     let original_order: Vec<String> = sequence.targets.iter().map(|t| t.id.clone()).collect();
     let mut improvements = Vec::new();
     let mut warnings = Vec::new();
+    let mut slew_improvement_percent = None;
+    let mut scheduled_integration_hours = None;
 
     // Calculate visibility for all targets
     let mut target_info: Vec<(String, &SimpleTarget, VisibilityWindow, f64)> = sequence
         .targets
         .iter()
         .map(|target| {
-            let window = calculate_visibility_window(
-                &target.coordinates,
-                location,
-                date,
-                20.0, // minimum altitude
-            );
+            let window =
+                constrained_visibility_window(target, location, date, DEFAULT_MIN_ALTITUDE);
             let quality = if window.is_visible {
                 calculate_observation_quality(
                     &target.coordinates,
@@ -149,11 +240,11 @@ pub fn optimize_sequence(
             improvements.push("Ordered by maximum altitude".to_string());
         }
         OptimizationStrategy::TransitTime => {
-            target_info.sort_by(|a, b| a.2.max_altitude_time.cmp(&b.2.max_altitude_time));
+            target_info.sort_by_key(|t| t.2.max_altitude_time);
             improvements.push("Ordered by transit time".to_string());
         }
         OptimizationStrategy::VisibilityStart => {
-            target_info.sort_by(|a, b| a.2.start_time.cmp(&b.2.start_time));
+            target_info.sort_by_key(|t| t.2.start_time);
             improvements.push("Ordered by visibility window start".to_string());
         }
         OptimizationStrategy::VisibilityDuration => {
@@ -162,8 +253,16 @@ pub fn optimize_sequence(
             improvements.push("Ordered by visibility duration".to_string());
         }
         OptimizationStrategy::MinimizeSlew => {
-            target_info = optimize_slew_order(target_info, location, date);
+            let (ordered, improvement) = optimize_slew_order(target_info, mount);
+            target_info = ordered;
             improvements.push("Optimized to minimize slew time".to_string());
+            if let Some(percent) = improvement.filter(|p| *p > 0.0) {
+                improvements.push(format!(
+                    "Slew time {:.1}% shorter than nearest-neighbor ordering",
+                    percent
+                ));
+            }
+            slew_improvement_percent = improvement;
         }
         OptimizationStrategy::MoonAvoidance => {
             target_info.sort_by(|a, b| b.3.partial_cmp(&a.3).unwrap());
@@ -180,6 +279,51 @@ pub fn optimize_sequence(
             });
             improvements.push("Combined optimization applied".to_string());
         }
+        OptimizationStrategy::PriorityWeighted => {
+            let (ordered, unplaced) =
+                priority_schedule_order(target_info, sequence.estimated_download_time);
+            target_info = ordered;
+            for (_, target, window, _) in &target_info[target_info.len() - unplaced..] {
+                if window.is_visible {
+                    warnings.push(format!(
+                        "Target '{}' ({:?} priority) lost its window to higher-priority targets",
+                        target.target_name, target.priority
+                    ));
+                }
+            }
+            improvements.push("Scheduled higher-priority targets first".to_string());
+        }
+        OptimizationStrategy::TimeWindowed => {
+            match time_windowed_order(
+                target_info.clone(),
+                location,
+                date,
+                sequence.estimated_download_time,
+                mount,
+            ) {
+                Some(schedule) => {
+                    for (_, target, window, _) in &schedule.unplaced {
+                        if window.is_visible {
+                            warnings.push(format!(
+                                "Target '{}' has no time left above its altitude threshold",
+                                target.target_name
+                            ));
+                        }
+                    }
+                    let hours = schedule.integration_seconds / 3600.0;
+                    improvements.push(format!(
+                        "Scheduled by altitude at observing time: {:.1} h above threshold",
+                        hours
+                    ));
+                    scheduled_integration_hours = Some((hours * 100.0).round() / 100.0);
+                    target_info = schedule.ordered;
+                    target_info.extend(schedule.unplaced);
+                }
+                None => warnings.push(
+                    "No astronomical darkness on this night; order left unchanged".to_string(),
+                ),
+            }
+        }
     }
 
     // Check for targets with no visibility
@@ -196,7 +340,7 @@ pub fn optimize_sequence(
 
     // Calculate estimated times
     let estimated_total_runtime = calculate_total_runtime(sequence);
-    let estimated_slew_time = estimate_slew_time(&target_info, location, date);
+    let estimated_slew_time = estimate_slew_time(&target_info, mount);
 
     OptimizationResult {
         success: true,
@@ -206,73 +350,235 @@ pub fn optimize_sequence(
         warnings,
         estimated_total_runtime,
         estimated_slew_time,
+        slew_improvement_percent,
+        scheduled_integration_hours,
     }
 }
 
-/// Optimize order to minimize slew time (greedy nearest neighbor)
+/// Targets with their visibility window and quality score
+type ScoredTargets<'a> = Vec<(String, &'a SimpleTarget, VisibilityWindow, f64)>;
+
+/// Optimize order to minimize slew time, starting from the first visible
+/// target. Also returns how much shorter the route is than the
+/// nearest-neighbor one, in percent.
 fn optimize_slew_order<'a>(
-    mut targets: Vec<(String, &'a SimpleTarget, VisibilityWindow, f64)>,
-    _location: &ObserverLocation,
-    _date: NaiveDate,
-) -> Vec<(String, &'a SimpleTarget, VisibilityWindow, f64)> {
+    targets: ScoredTargets<'a>,
+    mount: &MountProfile,
+) -> (ScoredTargets<'a>, Option<f64>) {
     if targets.len() <= 2 {
-        return targets;
+        return (targets, None);
     }
 
-    let mut result = Vec::with_capacity(targets.len());
-
-    // Start with the first visible target
-    let first_idx = targets
+    let start = targets
         .iter()
         .position(|(_, _, w, _)| w.is_visible)
         .unwrap_or(0);
-    result.push(targets.remove(first_idx));
+    let coordinates: Vec<&Coordinates> = targets.iter().map(|t| &t.1.coordinates).collect();
+    let costs = slew_cost_matrix(&coordinates, mount);
+    let route = solve_slew_route(&costs, start, DEFAULT_TIME_BUDGET);
 
-    while !targets.is_empty() {
-        let last = result.last().unwrap();
-        let last_coords = &last.1.coordinates;
+    let mut slots: Vec<_> = targets.into_iter().map(Some).collect();
+    let ordered = route
+        .order
+        .iter()
+        .map(|&i| slots[i].take().unwrap())
+        .collect();
+    (ordered, Some(route.improvement_percent()))
+}
 
-        // Find nearest target
-        let mut min_dist = f64::MAX;
-        let mut min_idx = 0;
+/// Reserve observing time target by target in priority order (ties by
+/// combined score), each in the earliest free part of its visibility
+/// window. Returns the targets ordered by reserved start time, followed
+/// by the ones that found no free time, and the number of those.
+fn priority_schedule_order(
+    mut targets: Vec<(String, &SimpleTarget, VisibilityWindow, f64)>,
+    download_time: f64,
+) -> (Vec<(String, &SimpleTarget, VisibilityWindow, f64)>, usize) {
+    let score = |t: &(String, &SimpleTarget, VisibilityWindow, f64)| {
+        t.2.max_altitude / 90.0 * 30.0 + t.3 * 0.5 + t.2.duration_hours / 12.0 * 20.0
+    };
+    targets.sort_by(|a, b| {
+        b.1.priority
+            .cmp(&a.1.priority)
+            .then_with(|| score(b).total_cmp(&score(a)))
+    });
+
+    let mut reserved: Vec<(DateTime<Utc>, DateTime<Utc>)> = Vec::new();
+    let mut placed = Vec::new();
+    let mut unplaced = Vec::new();
+
+    for entry in targets {
+        let window = &entry.2;
+        let window_length = window.end_time - window.start_time;
+        let runtime = Duration::seconds(entry.1.runtime(download_time) as i64);
+        let needed = runtime.min(window_length);
+
+        let mut start = window.start_time;
+        let slot = if !window.is_visible {
+            None
+        } else {
+            loop {
+                let end = start + needed;
+                if end > window.end_time {
+                    break None;
+                }
+                match reserved.iter().find(|(s, e)| *s < end && start < *e) {
+                    Some((_, busy_end)) => start = *busy_end,
+                    None => break Some((start, end)),
+                }
+            }
+        };
 
-        for (idx, (_, target, _, _)) in targets.iter().enumerate() {
-            let dist = angular_distance(last_coords, &target.coordinates);
-            if dist < min_dist {
-                min_dist = dist;
-                min_idx = idx;
+        match slot {
+            Some(slot) => {
+                reserved.push(slot);
+                placed.push((slot.0, entry));
             }
+            None => unplaced.push(entry),
         }
-
-        result.push(targets.remove(min_idx));
     }
 
-    result
+    placed.sort_by_key(|(start, _)| *start);
+    let unplaced_count = unplaced.len();
+    let mut ordered: Vec<_> = placed.into_iter().map(|(_, entry)| entry).collect();
+    ordered.extend(unplaced);
+    (ordered, unplaced_count)
 }
 
-/// Calculate angular distance between two coordinates
-fn angular_distance(c1: &Coordinates, c2: &Coordinates) -> f64 {
-    crate::models::coordinates::angular_separation(c1, c2)
+/// Night schedule built by [`time_windowed_order`]
+struct TimeWindowedSchedule<'a> {
+    /// Scheduled targets in observing order, followed by targets with
+    /// nothing left to image
+    ordered: Vec<(String, &'a SimpleTarget, VisibilityWindow, f64)>,
+    /// Targets with work left that found no usable time
+    unplaced: Vec<(String, &'a SimpleTarget, VisibilityWindow, f64)>,
+    /// Imaging time with the targets within their constraints
+    integration_seconds: f64,
+}
+
+/// Build the schedule of the night of `date` one target at a time. From
+/// the end of the previous target (plus the slew to the next), every
+/// remaining target is tried at every later start on the time grid and
+/// judged by the time it would spend within its constraints while being
+/// imaged. A candidate scores its share of usable time in the time it
+/// takes up, including any wait before it starts, plus the share of the
+/// target's remaining usable time tonight it would use, so targets about
+/// to set go first. Returns `None` without astronomical darkness.
+fn time_windowed_order<'a>(
+    targets: Vec<(String, &'a SimpleTarget, VisibilityWindow, f64)>,
+    location: &ObserverLocation,
+    date: NaiveDate,
+    download_time: f64,
+    mount: &MountProfile,
+) -> Option<TimeWindowedSchedule<'a>> {
+    let (dusk, dawn) = astronomical_night(location, date)?;
+
+    let step = Duration::minutes(SCHEDULE_STEP_MINUTES);
+    let step_seconds = step.num_seconds() as f64;
+    let slots = ((dawn - dusk).num_seconds() as f64 / step_seconds).ceil() as usize;
+
+    let (pending, done): (Vec<_>, Vec<_>) = targets
+        .into_iter()
+        .partition(|t| t.1.runtime(download_time) > 0.0);
+
+    // Usable slots before each slot, per target
+    let usable_before: Vec<Vec<usize>> = pending
+        .par_iter()
+        .map(|(_, target, _, _)| {
+            let mut counts = Vec::with_capacity(slots + 1);
+            counts.push(0);
+            for slot in 0..slots {
+                let mid = dusk + step * slot as i32 + step / 2;
+                let usable = constraints_met_at(target, location, mid, DEFAULT_MIN_ALTITUDE);
+                counts.push(counts[slot] + usize::from(usable));
+            }
+            counts
+        })
+        .collect();
+
+    let mut remaining: Vec<usize> = (0..pending.len()).collect();
+    let mut order: Vec<usize> = Vec::new();
+    let mut clock = 0.0;
+    let mut integration_seconds = 0.0;
+
+    loop {
+        let previous = order.last().map(|&i| &pending[i].1.coordinates);
+        // (position in remaining, score, start, imaging seconds, usable seconds)
+        let best = remaining
+            .par_iter()
+            .enumerate()
+            .filter_map(|(position, &i)| {
+                let target = pending[i].1;
+                let slew = previous.map_or(0.0, |from| {
+                    mount.slew_time_between(from, &target.coordinates)
+                });
+                let earliest = clock + slew;
+                let first_slot = (earliest / step_seconds).ceil() as usize;
+                if first_slot >= slots {
+                    return None;
+                }
+                let counts = &usable_before[i];
+                let usable_left = (counts[slots] - counts[first_slot]) as f64 * step_seconds;
+                if usable_left <= 0.0 {
+                    return None;
+                }
+                let runtime = target.runtime(download_time);
+
+                (first_slot..slots)
+                    .step_by(SCHEDULE_WAIT_STEPS)
+                    .filter_map(|start_slot| {
+                        let run_slots =
+                            ((runtime / step_seconds).ceil() as usize).min(slots - start_slot);
+                        let imaging = runtime.min(run_slots as f64 * step_seconds);
+                        let usable = ((counts[start_slot + run_slots] - counts[start_slot]) as f64
+                            * step_seconds)
+                            .min(imaging);
+                        if usable <= 0.0 {
+                            return None;
+                        }
+                        let start = start_slot as f64 * step_seconds;
+                        let occupied = start - clock + imaging;
+                        let score = usable / occupied + usable / usable_left;
+                        Some((position, score, start, imaging, usable))
+                    })
+                    .max_by(|a, b| a.1.total_cmp(&b.1))
+            })
+            .max_by(|a, b| a.1.total_cmp(&b.1).then_with(|| b.0.cmp(&a.0)));
+
+        let Some((position, _, start, imaging, usable)) = best else {
+            break;
+        };
+        order.push(remaining.remove(position));
+        clock = start + imaging;
+        integration_seconds += usable;
+    }
+
+    let mut entries: Vec<_> = pending.into_iter().map(Some).collect();
+    let mut ordered: Vec<_> = order.iter().map(|&i| entries[i].take().unwrap()).collect();
+    ordered.extend(done);
+    let unplaced = entries.into_iter().flatten().collect();
+
+    Some(TimeWindowedSchedule {
+        ordered,
+        unplaced,
+        integration_seconds,
+    })
 }
 
 /// Estimate total slew time
 fn estimate_slew_time(
     targets: &[(String, &SimpleTarget, VisibilityWindow, f64)],
-    _location: &ObserverLocation,
-    _date: NaiveDate,
+    mount: &MountProfile,
 ) -> f64 {
     if targets.len() < 2 {
         return 0.0;
     }
 
-    let slew_speed = 3.0; // degrees per second (typical)
-    let settle_time = 5.0; // seconds
-
     let mut total_slew = 0.0;
 
     for i in 1..targets.len() {
-        let dist = angular_distance(&targets[i - 1].1.coordinates, &targets[i].1.coordinates);
-        total_slew += dist / slew_speed + settle_time;
+        total_slew +=
+            mount.slew_time_between(&targets[i - 1].1.coordinates, &targets[i].1.coordinates);
     }
 
     total_slew
@@ -287,6 +593,7 @@ pub fn detect_conflicts(
     sequence: &SimpleSequence,
     location: &ObserverLocation,
     date: NaiveDate,
+    tles: &[Tle],
 ) -> ConflictResult {
     let mut conflicts = Vec::new();
     let mut suggestions = Vec::new();
@@ -298,7 +605,8 @@ pub fn detect_conflicts(
         .targets
         .iter()
         .map(|target| {
-            let window = calculate_visibility_window(&target.coordinates, location, date, 20.0);
+            let window =
+                constrained_visibility_window(target, location, date, DEFAULT_MIN_ALTITUDE);
             let runtime = target.runtime(download_time);
             (
                 target.id.clone(),
@@ -312,6 +620,24 @@ pub fn detect_conflicts(
     // Check for visibility conflicts
     for (i, (id1, name1, window1, runtime1)) in target_info.iter().enumerate() {
         if !window1.is_visible {
+            let blocking =
+                blocking_constraints(&sequence.targets[i], location, date, DEFAULT_MIN_ALTITUDE);
+            if !blocking.is_empty() {
+                let labels: Vec<&str> = blocking.iter().map(|k| k.label()).collect();
+                conflicts.push(ScheduleConflict {
+                    target1_id: id1.clone(),
+                    target1_name: name1.clone(),
+                    target2_id: String::new(),
+                    target2_name: String::new(),
+                    conflict_type: ConflictType::ConstraintViolation,
+                    description: format!(
+                        "Target '{}' never meets its {} on this date",
+                        name1,
+                        labels.join(", ")
+                    ),
+                });
+                continue;
+            }
             conflicts.push(ScheduleConflict {
                 target1_id: id1.clone(),
                 target1_name: name1.clone(),
@@ -371,8 +697,57 @@ pub fn detect_conflicts(
         }
     }
 
+    // Check for satellite transits through each visible target
+    if !tles.is_empty() {
+        let mut transit_count = 0;
+        for (target, (id, name, window, _)) in sequence.targets.iter().zip(&target_info) {
+            if !window.is_visible {
+                continue;
+            }
+            for transit in satellite::predict_transits(
+                tles,
+                &target.coordinates,
+                location,
+                window.start_time,
+                window.end_time,
+                satellite::DEFAULT_TRANSIT_RADIUS,
+            ) {
+                transit_count += 1;
+                conflicts.push(ScheduleConflict {
+                    target1_id: id.clone(),
+                    target1_name: name.clone(),
+                    target2_id: transit.norad_id.to_string(),
+                    target2_name: transit.satellite.clone(),
+                    conflict_type: ConflictType::SatelliteTransit,
+                    description: format!(
+                        "{} crosses '{}' at {} UTC ({:.2}° from center{})",
+                        transit.satellite,
+                        name,
+                        transit.time.format("%H:%M:%S"),
+                        transit.separation,
+                        if transit.sunlit { ", sunlit" } else { "" }
+                    ),
+                });
+            }
+        }
+        if transit_count > 0 {
+            suggestions.push(
+                "Use sigma-clipping rejection when stacking to remove satellite trails".to_string(),
+            );
+        }
+    }
+
     // Generate suggestions
-    if !conflicts.is_empty() {
+    if conflicts
+        .iter()
+        .any(|c| c.conflict_type == ConflictType::ConstraintViolation)
+    {
+        suggestions.push("Relax the observing constraints of blocked targets".to_string());
+    }
+    if conflicts
+        .iter()
+        .any(|c| c.conflict_type != ConflictType::SatelliteTransit)
+    {
         suggestions.push("Consider splitting the session across multiple nights".to_string());
         suggestions.push("Prioritize targets with shorter visibility windows".to_string());
         suggestions.push("Reduce exposure counts for conflicting targets".to_string());
@@ -389,25 +764,30 @@ pub fn detect_conflicts(
 // Parallel Calculations
 // ============================================================================
 
-/// Calculate ETAs for all targets in parallel
+/// Calculate ETAs for all targets and their exposure rows in parallel,
+/// including the dither, filter change and autofocus overheads when given
 pub fn calculate_etas_parallel(
     sequence: &SimpleSequence,
     start_time: DateTime<Utc>,
+    overheads: Option<&OverheadProfile>,
 ) -> Vec<BatchCalculationResult> {
     let download_time = sequence.estimated_download_time;
 
     // Use parallel iterator for large sequences
     if sequence.targets.len() > 10 {
+        let runtimes: Vec<f64> = sequence
+            .targets
+            .par_iter()
+            .map(|t| t.runtime_with_overheads(download_time, overheads))
+            .collect();
+
         let results: Vec<_> = sequence
             .targets
             .par_iter()
             .enumerate()
             .map(|(idx, target)| {
-                let runtime = target.runtime(download_time);
-                let offset: i64 = sequence.targets[..idx]
-                    .iter()
-                    .map(|t| t.runtime(download_time) as i64)
-                    .sum();
+                let runtime = runtimes[idx];
+                let offset: i64 = runtimes[..idx].iter().map(|&r| r as i64).sum();
 
                 let eta_start = start_time + Duration::seconds(offset);
                 let eta_end = eta_start + Duration::seconds(runtime as i64);
@@ -417,6 +797,7 @@ pub fn calculate_etas_parallel(
                     runtime,
                     eta_start: Some(eta_start),
                     eta_end: Some(eta_end),
+                    exposures: calculate_exposure_etas(target, eta_start, download_time, overheads),
                 }
             })
             .collect();
@@ -428,7 +809,7 @@ pub fn calculate_etas_parallel(
         let mut current_time = start_time;
 
         for target in &sequence.targets {
-            let runtime = target.runtime(download_time);
+            let runtime = target.runtime_with_overheads(download_time, overheads);
             let eta_end = current_time + Duration::seconds(runtime as i64);
 
             results.push(BatchCalculationResult {
@@ -436,6 +817,7 @@ pub fn calculate_etas_parallel(
                 runtime,
                 eta_start: Some(current_time),
                 eta_end: Some(eta_end),
+                exposures: calculate_exposure_etas(target, current_time, download_time, overheads),
             });
 
             current_time = eta_end;
@@ -451,28 +833,72 @@ pub fn calculate_visibility_parallel(
     location: &ObserverLocation,
     date: NaiveDate,
     min_altitude: f64,
-) -> Vec<(String, VisibilityWindow)> {
+    job: &JobHandle,
+) -> Result<Vec<(String, VisibilityWindow)>, String> {
+    let done = AtomicUsize::new(0);
     targets
         .par_iter()
         .map(|target| {
-            let window =
-                calculate_visibility_window(&target.coordinates, location, date, min_altitude);
-            (target.id.clone(), window)
+            job.checkpoint()?;
+            let window = constrained_visibility_window(target, location, date, min_altitude);
+            job.report(
+                done.fetch_add(1, Ordering::Relaxed) + 1,
+                targets.len(),
+                None,
+            );
+            Ok((target.id.clone(), window))
         })
         .collect()
 }
 
-/// Get scheduling info for all targets
+/// Score each night from `start` up to the night before `end` (just
+/// `start` when both are the same) by the quality and visibility hours of
+/// the sequence's visible targets
+pub fn score_observation_dates(
+    sequence: &SimpleSequence,
+    location: &ObserverLocation,
+    start: NaiveDate,
+    end: NaiveDate,
+    job: &JobHandle,
+) -> Result<Vec<(NaiveDate, f64)>, String> {
+    let last = if end > start {
+        end.pred_opt().unwrap_or(end)
+    } else {
+        end
+    };
+    let total = (last - start).num_days().max(0) as usize + 1;
+    let mut sequence = sequence.clone();
+    let mut scores = Vec::with_capacity(total);
+
+    for (i, date) in start.iter_days().take(total).enumerate() {
+        job.checkpoint()?;
+        update_moving_targets_for_night(&mut sequence, location, date);
+        let score: f64 = get_schedule_info(&sequence, location, date, None)
+            .iter()
+            .filter(|info| info.visibility_window.is_visible)
+            .map(|info| info.quality_score + info.visibility_window.duration_hours * 5.0)
+            .sum();
+        scores.push((date, score));
+        job.report(i + 1, total, Some(date.format("%Y-%m-%d").to_string()));
+    }
+
+    Ok(scores)
+}
+
+/// Get scheduling info for all targets. Rise and set times are measured
+/// against the horizon profile of `site` when given.
 pub fn get_schedule_info(
     sequence: &SimpleSequence,
     location: &ObserverLocation,
     date: NaiveDate,
+    site: Option<&ObservingSite>,
 ) -> Vec<TargetScheduleInfo> {
     sequence
         .targets
         .par_iter()
         .map(|target| {
-            let window = calculate_visibility_window(&target.coordinates, location, date, 20.0);
+            let window =
+                constrained_visibility_window(target, location, date, DEFAULT_MIN_ALTITUDE);
             let quality = if window.is_visible {
                 calculate_observation_quality(
                     &target.coordinates,
@@ -485,6 +911,7 @@ pub fn get_schedule_info(
                     altitude_score: 0.0,
                     moon_score: 0.0,
                     twilight_score: 0.0,
+                    sky_factor: 1.0,
                     recommendations: vec!["Target not visible".to_string()],
                 }
             };
@@ -506,11 +933,371 @@ pub fn get_schedule_info(
                 optimal_end_time: optimal_end,
                 quality_score: quality.score,
                 conflicts: vec![],
+                rise_set: calculate_target_rise_set(&target.coordinates, location, date, 0.0, site),
+                imaging_window: calculate_imaging_window(
+                    &target.coordinates,
+                    location,
+                    date,
+                    &target.constraints,
+                    &ImagingWindowOptions::default(),
+                ),
             }
         })
         .collect()
 }
 
+// ============================================================================
+// Exposure Count Rebalancing
+// ============================================================================
+
+/// Minimum altitude used when measuring how long a target is observable
+const REBALANCE_MIN_ALTITUDE: f64 = 20.0;
+
+/// Altitude sampling step when measuring observable time
+const REBALANCE_STEP_MINUTES: i64 = 5;
+
+/// How dark time is shared between targets when rebalancing
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum RebalanceMode {
+    /// Keep the current ratio of imaging time between targets
+    Proportional,
+    /// Share time by per-target weight
+    Weighted,
+}
+
+/// Proposed new count for one exposure row
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExposureCountProposal {
+    pub target_id: String,
+    pub exposure_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub filter_name: Option<String>,
+    pub exposure_time: f64,
+    pub current_count: i32,
+    pub proposed_count: i32,
+}
+
+/// Rebalanced time budget for one target
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TargetRebalance {
+    pub target_id: String,
+    pub target_name: String,
+    /// Dark time with the target above the minimum altitude
+    pub observable_seconds: f64,
+    pub current_seconds: f64,
+    pub proposed_seconds: f64,
+    pub exposures: Vec<ExposureCountProposal>,
+}
+
+/// Result of rebalancing exposure counts to fit a night
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RebalanceResult {
+    pub mode: RebalanceMode,
+    pub available_seconds: f64,
+    pub current_seconds: f64,
+    pub proposed_seconds: f64,
+    pub targets: Vec<TargetRebalance>,
+    pub warnings: Vec<String>,
+}
+
+/// Exposure row taking part in a rebalance
+struct RebalanceRow {
+    index: usize,
+    exposure_time: f64,
+    frame_seconds: f64,
+    remaining: i32,
+    proposed: i32,
+}
+
+/// Scale the remaining frames of every target so the sequence fills the
+/// dark time of the night of `date`. Each target gets a share of the night
+/// (by current runtime, or by `weights` keyed by target id, falling back
+/// to the target's priority weight) capped at the time it is observable
+/// and at its maximum integration; the sequence itself is not modified.
+pub fn rebalance_exposure_counts(
+    sequence: &SimpleSequence,
+    location: &ObserverLocation,
+    date: NaiveDate,
+    mode: RebalanceMode,
+    weights: &HashMap<String, f64>,
+) -> RebalanceResult {
+    let download_time = sequence.estimated_download_time;
+    let mut warnings = Vec::new();
+
+    let dark = astronomical_night(location, date);
+    if dark.is_none() {
+        warnings.push("No astronomical darkness on this night".to_string());
+    }
+    let available = dark
+        .map(|(dusk, dawn)| (dawn - dusk).num_seconds() as f64)
+        .unwrap_or(0.0);
+
+    let mut targets: Vec<TargetRebalance> = Vec::new();
+    let mut rows: Vec<Vec<RebalanceRow>> = Vec::new();
+    let mut shares: Vec<f64> = Vec::new();
+    let mut capacities: Vec<f64> = Vec::new();
+    let mut budget = available;
+
+    for target in &sequence.targets {
+        let target_rows: Vec<RebalanceRow> = target
+            .exposures
+            .iter()
+            .enumerate()
+            .filter(|(_, e)| e.enabled && e.remaining() > 0)
+            .map(|(index, e)| RebalanceRow {
+                index,
+                exposure_time: e.exposure_time,
+                frame_seconds: e.exposure_time + download_time,
+                remaining: e.remaining(),
+                proposed: 0,
+            })
+            .filter(|row| row.frame_seconds > 0.0)
+            .collect();
+        if target_rows.is_empty() {
+            continue;
+        }
+
+        let current: f64 = target_rows
+            .iter()
+            .map(|r| r.remaining as f64 * r.frame_seconds)
+            .sum();
+        let observable = dark
+            .map(|(dusk, dawn)| observable_seconds(target, location, dusk, dawn))
+            .unwrap_or(0.0);
+        if observable <= 0.0 {
+            warnings.push(format!(
+                "{} never rises above {:.0}° during darkness",
+                target.target_name, REBALANCE_MIN_ALTITUDE
+            ));
+        }
+
+        let share = match mode {
+            RebalanceMode::Proportional => current,
+            RebalanceMode::Weighted => weights
+                .get(&target.id)
+                .copied()
+                .unwrap_or_else(|| target.priority.weight())
+                .max(0.0),
+        };
+
+        // Wall-clock time left before the target reaches its maximum
+        // integration, at the current mix of exposures
+        let mut capacity = (observable - target.delay as f64).max(0.0);
+        if let Some(max) = target.max_integration {
+            let integration: f64 = target_rows
+                .iter()
+                .map(|r| r.remaining as f64 * r.exposure_time)
+                .sum();
+            if integration > 0.0 {
+                let allowed = (max - target.completed_integration_time()).max(0.0);
+                capacity = capacity.min(allowed * current / integration);
+            }
+        }
+
+        budget -= target.delay as f64;
+        capacities.push(capacity);
+        shares.push(share);
+        rows.push(target_rows);
+        targets.push(TargetRebalance {
+            target_id: target.id.clone(),
+            target_name: target.target_name.clone(),
+            observable_seconds: observable,
+            current_seconds: current,
+            proposed_seconds: 0.0,
+            exposures: Vec::new(),
+        });
+    }
+
+    let allocations = allocate_time(budget.max(0.0), &shares, &capacities);
+
+    // Scale each row by its target's allocation, then top up with whole
+    // frames while they still fit
+    let mut used: Vec<f64> = Vec::with_capacity(targets.len());
+    for ((info, target_rows), allocation) in targets.iter().zip(rows.iter_mut()).zip(&allocations) {
+        let factor = if info.current_seconds > 0.0 {
+            allocation / info.current_seconds
+        } else {
+            0.0
+        };
+        for row in target_rows.iter_mut() {
+            row.proposed = (row.remaining as f64 * factor).floor() as i32;
+        }
+        let mut spent = rows_seconds(target_rows);
+        while let Some(row) = next_fill_row(target_rows, factor, allocation - spent) {
+            row.proposed += 1;
+            spent += row.frame_seconds;
+        }
+        used.push(spent);
+    }
+
+    // Hand time lost to rounding to targets that can still use it
+    let mut leftover = budget.max(0.0) - used.iter().sum::<f64>();
+    loop {
+        let mut added = false;
+        for (i, target_rows) in rows.iter_mut().enumerate() {
+            if shares[i] <= 0.0 {
+                continue;
+            }
+            let room = leftover.min(capacities[i] - used[i]);
+            let factor = if targets[i].current_seconds > 0.0 {
+                allocations[i] / targets[i].current_seconds
+            } else {
+                0.0
+            };
+            if let Some(row) = next_fill_row(target_rows, factor, room) {
+                row.proposed += 1;
+                used[i] += row.frame_seconds;
+                leftover -= row.frame_seconds;
+                added = true;
+            }
+        }
+        if !added {
+            break;
+        }
+    }
+
+    for ((info, target_rows), seconds) in targets.iter_mut().zip(&rows).zip(&used) {
+        let Some(target) = sequence.find_target(&info.target_id) else {
+            continue;
+        };
+        info.proposed_seconds = *seconds;
+        if let Some(min) = target.min_integration {
+            let integration = target.completed_integration_time()
+                + target_rows
+                    .iter()
+                    .map(|r| r.proposed as f64 * r.exposure_time)
+                    .sum::<f64>();
+            if integration < min {
+                warnings.push(format!(
+                    "{} gets {:.1} h of integration, below its minimum of {:.1} h",
+                    target.target_name,
+                    integration / 3600.0,
+                    min / 3600.0
+                ));
+            }
+        }
+        info.exposures = target_rows
+            .iter()
+            .map(|row| {
+                let exposure = &target.exposures[row.index];
+                ExposureCountProposal {
+                    target_id: target.id.clone(),
+                    exposure_id: exposure.id.clone(),
+                    filter_name: exposure.filter.as_ref().map(|f| f.name.clone()),
+                    exposure_time: exposure.exposure_time,
+                    current_count: exposure.total_count,
+                    proposed_count: exposure.progress_count.max(0) + row.proposed,
+                }
+            })
+            .collect();
+    }
+
+    let current_seconds: f64 = targets.iter().map(|t| t.current_seconds).sum();
+    let proposed_seconds: f64 = targets.iter().map(|t| t.proposed_seconds).sum();
+    if available > 0.0 && budget <= 0.0 {
+        warnings.push("Target delays use up all of the dark time".to_string());
+    }
+
+    RebalanceResult {
+        mode,
+        available_seconds: available,
+        current_seconds,
+        proposed_seconds,
+        targets,
+        warnings,
+    }
+}
+
+/// Apply accepted exposure count proposals to the sequence
+pub fn apply_exposure_counts(sequence: &mut SimpleSequence, proposals: &[ExposureCountProposal]) {
+    for proposal in proposals {
+        let Some(target) = sequence.find_target_mut(&proposal.target_id) else {
+            continue;
+        };
+        if let Some(exposure) = target
+            .exposures
+            .iter_mut()
+            .find(|e| e.id == proposal.exposure_id)
+        {
+            exposure.total_count = proposal.proposed_count.max(exposure.progress_count);
+        }
+    }
+}
+
+/// Seconds between `start` and `end` with the target above
+/// [`REBALANCE_MIN_ALTITUDE`] and within its constraints
+fn observable_seconds(
+    target: &SimpleTarget,
+    location: &ObserverLocation,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> f64 {
+    let step = Duration::minutes(REBALANCE_STEP_MINUTES);
+
+    let mut seconds = 0.0;
+    let mut time = start;
+    while time < end {
+        let slice_end = (time + step).min(end);
+        let mid = time + (slice_end - time) / 2;
+        if constraints_met_at(target, location, mid, REBALANCE_MIN_ALTITUDE) {
+            seconds += (slice_end - time).num_seconds() as f64;
+        }
+        time = slice_end;
+    }
+    seconds
+}
+
+/// Share `budget` by `shares`, never giving a target more than its
+/// capacity; time a capped target cannot use goes to the others
+fn allocate_time(budget: f64, shares: &[f64], capacities: &[f64]) -> Vec<f64> {
+    let mut allocations = vec![0.0; shares.len()];
+    let mut open: Vec<usize> = (0..shares.len()).filter(|&i| shares[i] > 0.0).collect();
+    let mut remaining = budget;
+
+    while !open.is_empty() && remaining > 0.0 {
+        let total: f64 = open.iter().map(|&i| shares[i]).sum();
+        let capped: Vec<usize> = open
+            .iter()
+            .copied()
+            .filter(|&i| remaining * shares[i] / total >= capacities[i])
+            .collect();
+        if capped.is_empty() {
+            for &i in &open {
+                allocations[i] = remaining * shares[i] / total;
+            }
+            break;
+        }
+        for &i in &capped {
+            allocations[i] = capacities[i];
+            remaining -= capacities[i];
+        }
+        open.retain(|i| !capped.contains(i));
+    }
+
+    allocations
+}
+
+fn rows_seconds(rows: &[RebalanceRow]) -> f64 {
+    rows.iter()
+        .map(|r| r.proposed as f64 * r.frame_seconds)
+        .sum()
+}
+
+/// Row furthest below its scaled count whose next frame fits in `room`
+fn next_fill_row(rows: &mut [RebalanceRow], factor: f64, room: f64) -> Option<&mut RebalanceRow> {
+    rows.iter_mut()
+        .filter(|r| r.frame_seconds <= room + 1e-6)
+        .min_by(|a, b| {
+            let ratio =
+                |r: &RebalanceRow| r.proposed as f64 / (r.remaining as f64 * factor).max(1e-9);
+            ratio(a).total_cmp(&ratio(b))
+        })
+}
+
 // ============================================================================
 // Helper Functions
 // ============================================================================
@@ -586,6 +1373,8 @@ mod tests {
             longitude: -74.0,
             elevation: 0.0,
             timezone_offset: -5,
+            sky_brightness: None,
+            corrections: Default::default(),
         }
     }
 
@@ -595,7 +1384,13 @@ mod tests {
         let location = test_location();
         let date = NaiveDate::from_ymd_opt(2024, 10, 15).unwrap();
 
-        let result = optimize_sequence(&seq, &location, date, OptimizationStrategy::MaxAltitude);
+        let result = optimize_sequence(
+            &seq,
+            &location,
+            date,
+            OptimizationStrategy::MaxAltitude,
+            &MountProfile::default(),
+        );
         assert!(result.success);
     }
 
@@ -605,7 +1400,7 @@ mod tests {
         let location = test_location();
         let date = NaiveDate::from_ymd_opt(2024, 10, 15).unwrap();
 
-        let result = detect_conflicts(&seq, &location, date);
+        let result = detect_conflicts(&seq, &location, date, &[]);
         // Default sequence should have minimal conflicts
         assert!(result.conflicts.len() <= 1);
     }
@@ -615,7 +1410,7 @@ mod tests {
         let seq = SimpleSequence::default();
         let start = Utc::now();
 
-        let results = calculate_etas_parallel(&seq, start);
+        let results = calculate_etas_parallel(&seq, start, None);
         assert_eq!(results.len(), seq.targets.len());
     }
 
@@ -0,0 +1,298 @@
+//! Sequence progress summary
+//!
+//! Acquired vs planned frame counts per target and per filter for the
+//! progress dashboard, with the remaining imaging time and a projected
+//! finish date. Frames found by an image library scan count as acquired
+//! when the library holds more than the recorded progress.
+
+use std::collections::HashMap;
+
+use chrono::{Duration, NaiveDate};
+use serde::{Deserialize, Serialize};
+
+use crate::models::{EquipmentProfile, SimpleExposure, SimpleSequence};
+use crate::services::calculator;
+use crate::services::image_library::{subtract_acquired_from_sequence, ImageLibraryAnalysis};
+
+/// Usable imaging hours per night when none are given
+pub const DEFAULT_NIGHTLY_HOURS: f64 = 4.0;
+
+/// Acquired vs planned frames of one filter
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FilterProgress {
+    pub filter: Option<String>,
+    pub planned_frames: i32,
+    pub acquired_frames: i32,
+    pub percent_complete: f64,
+    /// Seconds
+    pub planned_integration: f64,
+    /// Seconds
+    pub acquired_integration: f64,
+    /// Imaging time still needed in seconds
+    pub remaining_seconds: f64,
+}
+
+/// Progress of one target
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TargetProgress {
+    pub target_id: String,
+    pub target_name: String,
+    pub planned_frames: i32,
+    pub acquired_frames: i32,
+    pub percent_complete: f64,
+    pub remaining_seconds: f64,
+    pub is_complete: bool,
+    pub filters: Vec<FilterProgress>,
+}
+
+/// Progress of a whole sequence
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SequenceProgressSummary {
+    pub planned_frames: i32,
+    pub acquired_frames: i32,
+    pub percent_complete: f64,
+    pub remaining_seconds: f64,
+    pub targets: Vec<TargetProgress>,
+    /// Totals per filter across all targets
+    pub filters: Vec<FilterProgress>,
+    pub nightly_hours: f64,
+    pub nights_remaining: u32,
+    /// Last night needed, `None` once everything is acquired
+    pub projected_finish_date: Option<String>,
+    /// Frames counted from the image library beyond the recorded progress
+    pub library_frames: i32,
+    /// Library objects that matched no target
+    pub unmatched_objects: Vec<String>,
+}
+
+fn percent(acquired: i32, planned: i32) -> f64 {
+    if planned > 0 {
+        (acquired as f64 / planned as f64 * 100.0).min(100.0)
+    } else {
+        100.0
+    }
+}
+
+fn add_to_filters(
+    filters: &mut Vec<FilterProgress>,
+    filter: Option<&str>,
+    exposure: &SimpleExposure,
+    acquired: i32,
+    remaining_seconds: f64,
+) {
+    let index = match filters.iter().position(|f| f.filter.as_deref() == filter) {
+        Some(index) => index,
+        None => {
+            filters.push(FilterProgress {
+                filter: filter.map(str::to_string),
+                planned_frames: 0,
+                acquired_frames: 0,
+                percent_complete: 0.0,
+                planned_integration: 0.0,
+                acquired_integration: 0.0,
+                remaining_seconds: 0.0,
+            });
+            filters.len() - 1
+        }
+    };
+    let entry = &mut filters[index];
+    entry.planned_frames += exposure.total_count.max(0);
+    entry.acquired_frames += acquired;
+    entry.planned_integration += exposure.total_count.max(0) as f64 * exposure.exposure_time;
+    entry.acquired_integration += acquired as f64 * exposure.exposure_time;
+    entry.remaining_seconds += remaining_seconds;
+    entry.percent_complete = percent(entry.acquired_frames, entry.planned_frames);
+}
+
+/// Summarize the progress of `sequence`. Remaining time uses the profile's
+/// download times and dither overheads when given; the finish date assumes
+/// `nightly_hours` of imaging per night starting on `start_date`.
+pub fn summarize_progress(
+    sequence: &SimpleSequence,
+    profile: Option<&EquipmentProfile>,
+    library: Option<(&ImageLibraryAnalysis, f64)>,
+    nightly_hours: f64,
+    start_date: NaiveDate,
+) -> SequenceProgressSummary {
+    // Frames per exposure found in the library
+    let mut library_counts: HashMap<String, i32> = HashMap::new();
+    let mut unmatched_objects = Vec::new();
+    if let Some((analysis, tolerance_arcmin)) = library {
+        let result = subtract_acquired_from_sequence(sequence, analysis, tolerance_arcmin);
+        for adjustment in result.adjustments {
+            *library_counts.entry(adjustment.exposure_id).or_default() += adjustment.acquired;
+        }
+        unmatched_objects = result.unmatched_objects;
+    }
+
+    let mut targets = Vec::new();
+    let mut filters: Vec<FilterProgress> = Vec::new();
+    let mut library_frames = 0;
+
+    for target in &sequence.targets {
+        let mut progress = TargetProgress {
+            target_id: target.id.clone(),
+            target_name: target.target_name.clone(),
+            planned_frames: 0,
+            acquired_frames: 0,
+            percent_complete: 0.0,
+            remaining_seconds: 0.0,
+            is_complete: false,
+            filters: Vec::new(),
+        };
+
+        for exposure in target.exposures.iter().filter(|e| e.enabled) {
+            let recorded = exposure
+                .progress_count
+                .clamp(0, exposure.total_count.max(0));
+            let from_library = library_counts.get(&exposure.id).copied().unwrap_or(0);
+            let acquired = recorded.max(from_library.min(exposure.total_count.max(0)));
+            library_frames += acquired - recorded;
+
+            let outstanding = SimpleExposure {
+                progress_count: acquired,
+                ..exposure.clone()
+            };
+            let remaining_seconds = match profile {
+                Some(p) => calculator::calculate_exposure_runtime(
+                    &outstanding,
+                    p.download_time(&exposure.binning),
+                    Some(&p.overheads),
+                ),
+                None => outstanding.runtime(sequence.estimated_download_time),
+            };
+
+            let filter = exposure.filter.as_ref().map(|f| f.name.as_str());
+            add_to_filters(
+                &mut progress.filters,
+                filter,
+                exposure,
+                acquired,
+                remaining_seconds,
+            );
+            add_to_filters(&mut filters, filter, exposure, acquired, remaining_seconds);
+            progress.planned_frames += exposure.total_count.max(0);
+            progress.acquired_frames += acquired;
+            progress.remaining_seconds += remaining_seconds;
+        }
+
+        progress.percent_complete = percent(progress.acquired_frames, progress.planned_frames);
+        progress.is_complete = progress.acquired_frames >= progress.planned_frames;
+        targets.push(progress);
+    }
+
+    let planned_frames = targets.iter().map(|t| t.planned_frames).sum();
+    let acquired_frames = targets.iter().map(|t| t.acquired_frames).sum();
+    let remaining_seconds: f64 = targets.iter().map(|t| t.remaining_seconds).sum();
+
+    let nightly_seconds = nightly_hours.max(0.1) * 3600.0;
+    let nights_remaining = (remaining_seconds / nightly_seconds).ceil() as u32;
+    let projected_finish_date = (nights_remaining > 0).then(|| {
+        (start_date + Duration::days(nights_remaining as i64 - 1))
+            .format("%Y-%m-%d")
+            .to_string()
+    });
+
+    SequenceProgressSummary {
+        planned_frames,
+        acquired_frames,
+        percent_complete: percent(acquired_frames, planned_frames),
+        remaining_seconds,
+        targets,
+        filters,
+        nightly_hours,
+        nights_remaining,
+        projected_finish_date,
+        library_frames,
+        unmatched_objects,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::FilterInfo;
+    use crate::services::image_library::{AcquiredGroup, AcquiredTarget};
+
+    fn exposure(filter: &str, total_count: i32, progress_count: i32) -> SimpleExposure {
+        SimpleExposure {
+            filter: Some(FilterInfo {
+                name: filter.to_string(),
+                ..Default::default()
+            }),
+            exposure_time: 300.0,
+            total_count,
+            progress_count,
+            ..Default::default()
+        }
+    }
+
+    fn sequence() -> SimpleSequence {
+        let mut sequence = SimpleSequence::new("Progress");
+        sequence.estimated_download_time = 0.0;
+        let target = &mut sequence.targets[0];
+        target.target_name = "M31".to_string();
+        target.exposures = vec![exposure("L", 40, 10), exposure("Ha", 20, 0)];
+        sequence
+    }
+
+    #[test]
+    fn test_summarize_progress() {
+        let date = NaiveDate::from_ymd_opt(2024, 10, 15).unwrap();
+        let summary = summarize_progress(&sequence(), None, None, 2.0, date);
+
+        assert_eq!(summary.planned_frames, 60);
+        assert_eq!(summary.acquired_frames, 10);
+        assert_eq!(summary.targets[0].filters.len(), 2);
+        assert_eq!(summary.targets[0].filters[0].percent_complete, 25.0);
+        // 50 frames of 300 s is 250 minutes, three nights of two hours
+        assert_eq!(summary.remaining_seconds, 15000.0);
+        assert_eq!(summary.nights_remaining, 3);
+        assert_eq!(summary.projected_finish_date.as_deref(), Some("2024-10-17"));
+    }
+
+    #[test]
+    fn test_summarize_progress_with_library() {
+        let seq = sequence();
+        let analysis = ImageLibraryAnalysis {
+            directory: String::new(),
+            scanned_files: 25,
+            light_frames: 25,
+            calibration_frames: 0,
+            unidentified_frames: 0,
+            targets: vec![AcquiredTarget {
+                object_name: "M31".to_string(),
+                ra: None,
+                dec: None,
+                frame_count: 25,
+                total_integration: 7500.0,
+                groups: vec![
+                    AcquiredGroup {
+                        filter: Some("L".to_string()),
+                        exposure_time: 300.0,
+                        frame_count: 5,
+                    },
+                    AcquiredGroup {
+                        filter: Some("Ha".to_string()),
+                        exposure_time: 300.0,
+                        frame_count: 20,
+                    },
+                ],
+            }],
+            errors: Vec::new(),
+        };
+
+        let date = NaiveDate::from_ymd_opt(2024, 10, 15).unwrap();
+        let summary = summarize_progress(&seq, None, Some((&analysis, 10.0)), 4.0, date);
+        // Recorded L progress exceeds the library; Ha comes from the library
+        assert_eq!(summary.acquired_frames, 30);
+        assert_eq!(summary.library_frames, 20);
+        assert!(summary
+            .filters
+            .iter()
+            .any(|f| f.filter.as_deref() == Some("Ha") && f.percent_complete == 100.0));
+    }
+}
@@ -0,0 +1,352 @@
+//! Sequence search service
+//!
+//! Case-insensitive text search over both sequence models. Every hit
+//! carries the indices needed to reach the match, so the frontend can
+//! jump straight to it.
+
+use serde::{Deserialize, Serialize};
+
+use crate::models::{EditorSequence, EditorSequenceItem, EditorTrigger, SimpleSequence};
+use crate::services::validator::get_short_type_name;
+
+/// What a search hit matched
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SearchHitKind {
+    TargetName,
+    TargetNotes,
+    TargetTag,
+    FilterName,
+    ItemName,
+    /// Short NINA type name, reported only when the name did not match
+    ItemType,
+    Condition,
+    Trigger,
+}
+
+/// Area of an editor sequence
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum EditorArea {
+    Start,
+    Target,
+    End,
+    GlobalTriggers,
+}
+
+/// Search hit with the path to the matching element
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchHit {
+    pub kind: SearchHitKind,
+    /// Text that matched
+    pub text: String,
+    /// Id of the matching target, exposure, item, condition or trigger
+    pub id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target_index: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exposure_index: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub area: Option<EditorArea>,
+    /// Indices into nested `items`, starting from the area's item list
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub item_path: Vec<usize>,
+    /// Condition index on the item at `item_path`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub condition_index: Option<usize>,
+    /// Trigger index on the item at `item_path`, or in the global triggers
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub trigger_index: Option<usize>,
+}
+
+impl SearchHit {
+    fn new(kind: SearchHitKind, text: &str, id: &str) -> Self {
+        Self {
+            kind,
+            text: text.to_string(),
+            id: id.to_string(),
+            target_index: None,
+            exposure_index: None,
+            area: None,
+            item_path: Vec::new(),
+            condition_index: None,
+            trigger_index: None,
+        }
+    }
+}
+
+fn matches(text: &str, query: &str) -> bool {
+    text.to_lowercase().contains(query)
+}
+
+/// Search target names, notes, tags and filter names of a simple sequence
+pub fn search_simple_sequence(sequence: &SimpleSequence, query: &str) -> Vec<SearchHit> {
+    let query = query.trim().to_lowercase();
+    let mut hits = Vec::new();
+    if query.is_empty() {
+        return hits;
+    }
+
+    for (target_index, target) in sequence.targets.iter().enumerate() {
+        let target_hit = |kind, text: &str| SearchHit {
+            target_index: Some(target_index),
+            ..SearchHit::new(kind, text, &target.id)
+        };
+
+        if matches(&target.target_name, &query) {
+            hits.push(target_hit(SearchHitKind::TargetName, &target.target_name));
+        } else if matches(&target.name, &query) {
+            hits.push(target_hit(SearchHitKind::TargetName, &target.name));
+        }
+        if let Some(ref notes) = target.notes {
+            if matches(notes, &query) {
+                hits.push(target_hit(SearchHitKind::TargetNotes, notes));
+            }
+        }
+        for tag in target.tags.iter().filter(|t| matches(t, &query)) {
+            hits.push(target_hit(SearchHitKind::TargetTag, tag));
+        }
+
+        for (exposure_index, exposure) in target.exposures.iter().enumerate() {
+            if let Some(ref filter) = exposure.filter {
+                if matches(&filter.name, &query) {
+                    hits.push(SearchHit {
+                        target_index: Some(target_index),
+                        exposure_index: Some(exposure_index),
+                        ..SearchHit::new(SearchHitKind::FilterName, &filter.name, &exposure.id)
+                    });
+                }
+            }
+        }
+    }
+
+    hits
+}
+
+/// Search item, condition and trigger names and types of an editor
+/// sequence, including nested containers
+pub fn search_editor_sequence(sequence: &EditorSequence, query: &str) -> Vec<SearchHit> {
+    let query = query.trim().to_lowercase();
+    let mut hits = Vec::new();
+    if query.is_empty() {
+        return hits;
+    }
+
+    let areas = [
+        (EditorArea::Start, &sequence.start_items),
+        (EditorArea::Target, &sequence.target_items),
+        (EditorArea::End, &sequence.end_items),
+    ];
+    for (area, items) in areas {
+        search_items(items, area, &mut Vec::new(), &query, &mut hits);
+    }
+    search_triggers(
+        &sequence.global_triggers,
+        EditorArea::GlobalTriggers,
+        &[],
+        &query,
+        &mut hits,
+    );
+
+    hits
+}
+
+fn search_items(
+    items: &[EditorSequenceItem],
+    area: EditorArea,
+    path: &mut Vec<usize>,
+    query: &str,
+    hits: &mut Vec<SearchHit>,
+) {
+    for (index, item) in items.iter().enumerate() {
+        path.push(index);
+
+        let item_hit = |kind, text: &str| SearchHit {
+            area: Some(area),
+            item_path: path.clone(),
+            ..SearchHit::new(kind, text, &item.id)
+        };
+        let short_type = get_short_type_name(&item.item_type);
+        if matches(&item.name, query) {
+            hits.push(item_hit(SearchHitKind::ItemName, &item.name));
+        } else if matches(&short_type, query) {
+            hits.push(item_hit(SearchHitKind::ItemType, &short_type));
+        }
+
+        for (condition_index, condition) in item.conditions.iter().flatten().enumerate() {
+            let short_type = get_short_type_name(&condition.condition_type);
+            let text = if matches(&condition.name, query) {
+                &condition.name
+            } else if matches(&short_type, query) {
+                &short_type
+            } else {
+                continue;
+            };
+            hits.push(SearchHit {
+                area: Some(area),
+                item_path: path.clone(),
+                condition_index: Some(condition_index),
+                ..SearchHit::new(SearchHitKind::Condition, text, &condition.id)
+            });
+        }
+
+        if let Some(ref triggers) = item.triggers {
+            search_triggers(triggers, area, path, query, hits);
+        }
+        if let Some(ref children) = item.items {
+            search_items(children, area, path, query, hits);
+        }
+
+        path.pop();
+    }
+}
+
+fn search_triggers(
+    triggers: &[EditorTrigger],
+    area: EditorArea,
+    path: &[usize],
+    query: &str,
+    hits: &mut Vec<SearchHit>,
+) {
+    for (trigger_index, trigger) in triggers.iter().enumerate() {
+        let short_type = get_short_type_name(&trigger.trigger_type);
+        let text = if matches(&trigger.name, query) {
+            &trigger.name
+        } else if matches(&short_type, query) {
+            &short_type
+        } else {
+            continue;
+        };
+        hits.push(SearchHit {
+            area: Some(area),
+            item_path: path.to_vec(),
+            trigger_index: Some(trigger_index),
+            ..SearchHit::new(SearchHitKind::Trigger, text, &trigger.id)
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{FilterInfo, SequenceEntityStatus, SimpleExposure, SimpleTarget};
+    use std::collections::HashMap;
+
+    fn item(
+        name: &str,
+        item_type: &str,
+        items: Option<Vec<EditorSequenceItem>>,
+    ) -> EditorSequenceItem {
+        EditorSequenceItem {
+            id: uuid::Uuid::new_v4().to_string(),
+            item_type: item_type.to_string(),
+            name: name.to_string(),
+            category: String::new(),
+            icon: None,
+            description: None,
+            status: SequenceEntityStatus::Created,
+            is_expanded: None,
+            data: HashMap::new(),
+            items,
+            conditions: None,
+            triggers: None,
+        }
+    }
+
+    #[test]
+    fn test_search_simple_sequence() {
+        let mut sequence = SimpleSequence::new("Search");
+        sequence.targets = vec![
+            SimpleTarget {
+                target_name: "M31".to_string(),
+                notes: Some("Check Ha gradient".to_string()),
+                ..Default::default()
+            },
+            SimpleTarget {
+                target_name: "NGC 7000".to_string(),
+                tags: vec!["Hydrogen".to_string()],
+                exposures: vec![
+                    SimpleExposure::default(),
+                    SimpleExposure {
+                        filter: Some(FilterInfo {
+                            name: "Ha".to_string(),
+                            ..Default::default()
+                        }),
+                        ..Default::default()
+                    },
+                ],
+                ..Default::default()
+            },
+        ];
+
+        let hits = search_simple_sequence(&sequence, " HA ");
+
+        let kinds: Vec<SearchHitKind> = hits.iter().map(|h| h.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![SearchHitKind::TargetNotes, SearchHitKind::FilterName]
+        );
+        assert_eq!(hits[1].target_index, Some(1));
+        assert_eq!(hits[1].exposure_index, Some(1));
+        assert_eq!(
+            search_simple_sequence(&sequence, "hydro")[0].kind,
+            SearchHitKind::TargetTag
+        );
+        assert!(search_simple_sequence(&sequence, "").is_empty());
+    }
+
+    #[test]
+    fn test_search_editor_sequence_paths() {
+        let mut container = item(
+            "Deep Sky Object",
+            "NINA.Sequencer.Container.DeepSkyObjectContainer, NINA.Sequencer",
+            Some(vec![
+                item(
+                    "Slew",
+                    "NINA.Sequencer.SequenceItem.Telescope.SlewScopeToRaDec, NINA.Sequencer",
+                    None,
+                ),
+                item(
+                    "Run Autofocus",
+                    "NINA.Sequencer.SequenceItem.Autofocus.RunAutofocus, NINA.Sequencer",
+                    None,
+                ),
+            ]),
+        );
+        container.triggers = Some(vec![EditorTrigger {
+            id: "trigger".to_string(),
+            trigger_type:
+                "NINA.Sequencer.Trigger.Autofocus.AutofocusAfterFilterChange, NINA.Sequencer"
+                    .to_string(),
+            name: "AF After Filter Change".to_string(),
+            category: String::new(),
+            icon: None,
+            data: HashMap::new(),
+            trigger_items: None,
+        }]);
+
+        let mut sequence = EditorSequence::new("Search");
+        sequence.start_items = vec![item(
+            "Cool",
+            "NINA.Sequencer.SequenceItem.Camera.CoolCamera, NINA.Sequencer",
+            None,
+        )];
+        sequence.target_items = vec![container];
+
+        let hits = search_editor_sequence(&sequence, "autofocus");
+        assert_eq!(hits.len(), 2);
+        assert_eq!(hits[0].kind, SearchHitKind::Trigger);
+        assert_eq!(hits[0].item_path, vec![0]);
+        assert_eq!(hits[0].trigger_index, Some(0));
+        assert_eq!(hits[1].kind, SearchHitKind::ItemName);
+        assert_eq!(hits[1].area, Some(EditorArea::Target));
+        assert_eq!(hits[1].item_path, vec![0, 1]);
+
+        // Type names are matched when the display name does not
+        let hits = search_editor_sequence(&sequence, "coolcamera");
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].kind, SearchHitKind::ItemType);
+        assert_eq!(hits[0].area, Some(EditorArea::Start));
+    }
+}
@@ -0,0 +1,507 @@
+//! Open sequence sessions
+//!
+//! Sequences opened through the backend are held here as the authoritative
+//! copy. The frontend edits them by session id instead of sending the whole
+//! sequence with every command, and saves or discards them on close.
+//!
+//! Edits arrive as JSON merge patches (RFC 7396) or JSON patches (RFC 6902)
+//! against the sequence's JSON form. A session only counts as changed when
+//! an edit actually changes the sequence.
+//!
+//! Windows can be bound to a session, so several windows show different
+//! sequences side by side. Every change is reported to the session listener
+//! and reaches all windows as a `session://changed` event.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+use json_patch::Patch;
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::error::{AppError, AppResult};
+use crate::models::SimpleSequence;
+
+/// Sequence open in the backend
+#[derive(Debug, Clone)]
+pub struct SequenceSession {
+    pub id: String,
+    /// File the sequence was opened from or last saved to
+    pub path: Option<PathBuf>,
+    pub sequence: SimpleSequence,
+    /// Changed since opened or last saved
+    pub dirty: bool,
+    /// Number of updates applied since opened
+    pub revision: u64,
+    pub opened_at: DateTime<Utc>,
+}
+
+/// Summary of an open sequence
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionInfo {
+    pub id: String,
+    pub path: Option<String>,
+    pub title: String,
+    pub target_count: usize,
+    pub dirty: bool,
+    pub revision: u64,
+    pub opened_at: DateTime<Utc>,
+}
+
+impl From<&SequenceSession> for SessionInfo {
+    fn from(session: &SequenceSession) -> Self {
+        Self {
+            id: session.id.clone(),
+            path: session.path.as_ref().map(|p| p.display().to_string()),
+            title: session.sequence.title.clone(),
+            target_count: session.sequence.targets.len(),
+            dirty: session.dirty,
+            revision: session.revision,
+            opened_at: session.opened_at,
+        }
+    }
+}
+
+/// Event emitted to the frontend for each [`SessionChange`]
+pub const SESSION_CHANGED_EVENT: &str = "session://changed";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SessionChangeKind {
+    Opened,
+    Updated,
+    Saved,
+    Closed,
+}
+
+/// Change to an open sequence. Windows compare the revision with the one
+/// they show to tell their own edits from other windows' edits.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionChange {
+    pub kind: SessionChangeKind,
+    pub session: SessionInfo,
+}
+
+/// Callback notified of every session change
+pub type SessionListener = Box<dyn Fn(&SessionChange) + Send + Sync>;
+
+/// Window bound to a session
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WindowBinding {
+    /// Tauri window label
+    pub label: String,
+    pub session_id: String,
+}
+
+fn not_found(id: &str) -> AppError {
+    AppError::NotFound(format!("Sequence session not found: {}", id))
+}
+
+/// Open sequences by session id, part of the
+/// [`AppState`](crate::state::AppState)
+#[derive(Default)]
+pub struct SessionStore {
+    sessions: RwLock<HashMap<String, SequenceSession>>,
+    /// Session of each bound window, by window label
+    windows: RwLock<HashMap<String, String>>,
+    listener: RwLock<Option<SessionListener>>,
+}
+
+impl SessionStore {
+    /// Install or remove the listener for session changes
+    pub fn set_listener(&self, listener: Option<SessionListener>) {
+        *self.listener.write() = listener;
+    }
+
+    fn notify(&self, kind: SessionChangeKind, session: &SessionInfo) {
+        if let Some(listener) = self.listener.read().as_ref() {
+            listener(&SessionChange {
+                kind,
+                session: session.clone(),
+            });
+        }
+    }
+
+    /// Start a session for `sequence`. A file that is already open keeps
+    /// its session, so opening it twice doesn't fork the edits.
+    pub fn open(&self, sequence: SimpleSequence, path: Option<PathBuf>) -> SessionInfo {
+        let info = {
+            let mut sessions = self.sessions.write();
+            if let Some(existing) = path
+                .as_ref()
+                .and_then(|path| sessions.values().find(|s| s.path.as_ref() == Some(path)))
+            {
+                return SessionInfo::from(existing);
+            }
+
+            let session = SequenceSession {
+                id: uuid::Uuid::new_v4().to_string(),
+                path,
+                sequence,
+                dirty: false,
+                revision: 0,
+                opened_at: Utc::now(),
+            };
+            let info = SessionInfo::from(&session);
+            sessions.insert(session.id.clone(), session);
+            info
+        };
+        self.notify(SessionChangeKind::Opened, &info);
+        info
+    }
+
+    /// Open sessions, oldest first
+    pub fn list(&self) -> Vec<SessionInfo> {
+        let mut infos: Vec<SessionInfo> = self
+            .sessions
+            .read()
+            .values()
+            .map(SessionInfo::from)
+            .collect();
+        infos.sort_by_key(|info| info.opened_at);
+        infos
+    }
+
+    pub fn info(&self, id: &str) -> AppResult<SessionInfo> {
+        self.sessions
+            .read()
+            .get(id)
+            .map(SessionInfo::from)
+            .ok_or_else(|| not_found(id))
+    }
+
+    /// Copy of the session's sequence
+    pub fn sequence(&self, id: &str) -> AppResult<SimpleSequence> {
+        self.sessions
+            .read()
+            .get(id)
+            .map(|s| s.sequence.clone())
+            .ok_or_else(|| not_found(id))
+    }
+
+    /// Copy of the whole session
+    pub fn session(&self, id: &str) -> AppResult<SequenceSession> {
+        self.sessions
+            .read()
+            .get(id)
+            .cloned()
+            .ok_or_else(|| not_found(id))
+    }
+
+    /// Change the JSON form of the session's sequence with `edit`. The
+    /// result must still be a sequence with the same id; otherwise, or when
+    /// `edit` fails, the sequence stays as it was. The session is marked
+    /// dirty and its revision bumped only when the sequence changed. With
+    /// `base_revision`, the edit is refused when the sequence changed since
+    /// that revision.
+    pub fn edit_json<F>(
+        &self,
+        id: &str,
+        base_revision: Option<u64>,
+        edit: F,
+    ) -> AppResult<SessionInfo>
+    where
+        F: FnOnce(&mut Value) -> AppResult<()>,
+    {
+        let info = {
+            let mut sessions = self.sessions.write();
+            let session = sessions.get_mut(id).ok_or_else(|| not_found(id))?;
+            if let Some(base) = base_revision.filter(|&base| base != session.revision) {
+                return Err(AppError::InvalidInput(format!(
+                    "Invalid base revision {}: the sequence is at revision {}",
+                    base, session.revision
+                )));
+            }
+
+            let before = serde_json::to_value(&session.sequence)
+                .map_err(|e| AppError::Internal(format!("Failed to serialize sequence: {}", e)))?;
+            let mut after = before.clone();
+            edit(&mut after)?;
+            if after == before {
+                return Ok(SessionInfo::from(&*session));
+            }
+
+            let sequence: SimpleSequence = serde_json::from_value(after).map_err(|e| {
+                AppError::InvalidInput(format!(
+                    "Invalid patch: the result is not a sequence: {}",
+                    e
+                ))
+            })?;
+            if sequence.id != session.sequence.id {
+                return Err(AppError::InvalidInput(
+                    "Invalid patch: the sequence id cannot change".to_string(),
+                ));
+            }
+            session.sequence = sequence;
+            session.dirty = true;
+            session.revision += 1;
+            SessionInfo::from(&*session)
+        };
+        self.notify(SessionChangeKind::Updated, &info);
+        Ok(info)
+    }
+
+    /// Apply a JSON merge patch (RFC 7396) to the session's sequence
+    pub fn update(&self, id: &str, patch: &Value) -> AppResult<SessionInfo> {
+        self.edit_json(id, None, |value| {
+            json_patch::merge(value, patch);
+            Ok(())
+        })
+    }
+
+    /// Apply a JSON patch (RFC 6902) to the session's sequence. All
+    /// operations apply or none do. With `base_revision`, the patch is
+    /// refused when the sequence changed since that revision.
+    pub fn apply_patch(
+        &self,
+        id: &str,
+        patch: &Patch,
+        base_revision: Option<u64>,
+    ) -> AppResult<SessionInfo> {
+        self.edit_json(id, base_revision, |value| {
+            json_patch::patch(value, patch)
+                .map_err(|e| AppError::InvalidInput(format!("Invalid patch: {}", e)))
+        })
+    }
+
+    /// Record that the session was saved to `path` at `revision`. Updates
+    /// made while saving keep the session dirty.
+    pub fn mark_saved(&self, id: &str, path: &Path, revision: u64) -> AppResult<SessionInfo> {
+        let info = {
+            let mut sessions = self.sessions.write();
+            let session = sessions.get_mut(id).ok_or_else(|| not_found(id))?;
+            session.path = Some(path.to_path_buf());
+            if session.revision == revision {
+                session.dirty = false;
+            }
+            SessionInfo::from(&*session)
+        };
+        self.notify(SessionChangeKind::Saved, &info);
+        Ok(info)
+    }
+
+    /// End the session, returning it. Windows bound to it are unbound.
+    pub fn close(&self, id: &str) -> AppResult<SequenceSession> {
+        let session = self
+            .sessions
+            .write()
+            .remove(id)
+            .ok_or_else(|| not_found(id))?;
+        self.windows
+            .write()
+            .retain(|_, session_id| session_id != id);
+        self.notify(SessionChangeKind::Closed, &SessionInfo::from(&session));
+        Ok(session)
+    }
+
+    /// Bind a window to a session, replacing its previous binding
+    pub fn bind_window(&self, label: &str, id: &str) -> AppResult<WindowBinding> {
+        self.info(id)?;
+        self.windows
+            .write()
+            .insert(label.to_string(), id.to_string());
+        Ok(WindowBinding {
+            label: label.to_string(),
+            session_id: id.to_string(),
+        })
+    }
+
+    /// Session the window is bound to
+    pub fn window_session(&self, label: &str) -> Option<SessionInfo> {
+        let id = self.windows.read().get(label).cloned()?;
+        self.info(&id).ok()
+    }
+
+    /// Labels of the windows bound to a session
+    pub fn session_windows(&self, id: &str) -> Vec<String> {
+        let mut labels: Vec<String> = self
+            .windows
+            .read()
+            .iter()
+            .filter(|(_, session_id)| *session_id == id)
+            .map(|(label, _)| label.clone())
+            .collect();
+        labels.sort();
+        labels
+    }
+
+    /// Unbind a closed window. Its session closes too when no other window
+    /// shows it and it has no unsaved changes; unsaved sessions stay open
+    /// so the changes can still be saved.
+    pub fn release_window(&self, label: &str) {
+        let Some(id) = self.windows.write().remove(label) else {
+            return;
+        };
+        let clean = self.info(&id).is_ok_and(|info| !info.dirty);
+        if clean && self.session_windows(&id).is_empty() {
+            let _ = self.close(&id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorCode;
+    use crate::models::SimpleTarget;
+    use serde_json::json;
+
+    fn sequence() -> SimpleSequence {
+        SimpleSequence {
+            title: "Spring galaxies".to_string(),
+            targets: vec![SimpleTarget::default()],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_update_and_save() {
+        let store = SessionStore::default();
+        let path = PathBuf::from("/tmp/spring.json");
+        let opened = store.open(sequence(), Some(path.clone()));
+        assert!(!opened.dirty);
+
+        // Opening the same file again reuses the session
+        assert_eq!(store.open(sequence(), Some(path.clone())).id, opened.id);
+        assert_eq!(store.list().len(), 1);
+
+        let updated = store
+            .update(&opened.id, &json!({ "title": "Spring galaxies II" }))
+            .unwrap();
+        assert!(updated.dirty);
+        assert_eq!(updated.revision, 1);
+        assert_eq!(
+            store.sequence(&opened.id).unwrap().title,
+            "Spring galaxies II"
+        );
+        assert_eq!(store.sequence(&opened.id).unwrap().targets.len(), 1);
+
+        // A save of an older revision leaves the session dirty
+        assert!(store.mark_saved(&opened.id, &path, 0).unwrap().dirty);
+        assert!(!store.mark_saved(&opened.id, &path, 1).unwrap().dirty);
+
+        store.close(&opened.id).unwrap();
+        assert_eq!(
+            store.info(&opened.id).unwrap_err().code(),
+            ErrorCode::NotFound
+        );
+    }
+
+    #[test]
+    fn test_invalid_patch_keeps_sequence() {
+        let store = SessionStore::default();
+        let id = store.open(sequence(), None).id;
+
+        let error = store
+            .update(&id, &json!({ "targets": "none" }))
+            .unwrap_err();
+        assert_eq!(error.code(), ErrorCode::InvalidInput);
+        assert!(error.to_string().starts_with("Invalid patch"));
+
+        let info = store.info(&id).unwrap();
+        assert!(!info.dirty);
+        assert_eq!(info.target_count, 1);
+    }
+
+    #[test]
+    fn test_apply_json_patch() {
+        let store = SessionStore::default();
+        let id = store.open(sequence(), None).id;
+        let patch = |ops: Value| serde_json::from_value::<Patch>(ops).unwrap();
+
+        let info = store
+            .apply_patch(
+                &id,
+                &patch(json!([
+                    { "op": "replace", "path": "/title", "value": "Autumn" },
+                    { "op": "copy", "from": "/targets/0", "path": "/targets/-" },
+                ])),
+                Some(0),
+            )
+            .unwrap();
+        assert!(info.dirty);
+        assert_eq!(info.revision, 1);
+        assert_eq!(info.title, "Autumn");
+        assert_eq!(info.target_count, 2);
+
+        // A failing test operation undoes the operations before it
+        let error = store
+            .apply_patch(
+                &id,
+                &patch(json!([
+                    { "op": "remove", "path": "/targets/1" },
+                    { "op": "test", "path": "/title", "value": "Spring" },
+                ])),
+                None,
+            )
+            .unwrap_err();
+        assert_eq!(error.code(), ErrorCode::InvalidInput);
+        assert!(error.to_string().starts_with("Invalid patch"));
+        assert_eq!(store.info(&id).unwrap().target_count, 2);
+
+        // Stale revisions, id changes and no-op patches leave the session alone
+        assert!(store.apply_patch(&id, &patch(json!([])), Some(0)).is_err());
+        assert!(store
+            .apply_patch(
+                &id,
+                &patch(json!([{ "op": "replace", "path": "/id", "value": "other" }])),
+                None,
+            )
+            .is_err());
+        let unchanged = store
+            .apply_patch(
+                &id,
+                &patch(json!([{ "op": "replace", "path": "/title", "value": "Autumn" }])),
+                Some(1),
+            )
+            .unwrap();
+        assert_eq!(unchanged.revision, 1);
+    }
+
+    #[test]
+    fn test_window_bindings_and_changes() {
+        use std::sync::{Arc, Mutex};
+
+        let store = SessionStore::default();
+        let changes = Arc::new(Mutex::new(Vec::new()));
+        let received = changes.clone();
+        store.set_listener(Some(Box::new(move |change| {
+            received.lock().unwrap().push(change.kind);
+        })));
+
+        let first = store.open(sequence(), None).id;
+        let second = store.open(sequence(), None).id;
+        store.bind_window("main", &first).unwrap();
+        store.bind_window("sequence-1", &second).unwrap();
+        store.bind_window("sequence-2", &second).unwrap();
+        assert!(store.bind_window("sequence-3", "missing").is_err());
+        assert_eq!(store.window_session("main").unwrap().id, first);
+        assert_eq!(store.session_windows(&second), ["sequence-1", "sequence-2"]);
+
+        // A clean session closes with the last window showing it
+        store.release_window("sequence-1");
+        assert!(store.info(&second).is_ok());
+        store.release_window("sequence-2");
+        assert!(store.info(&second).is_err());
+
+        // An unsaved one stays open
+        store.update(&first, &json!({ "title": "Edited" })).unwrap();
+        store.release_window("main");
+        assert!(store.info(&first).unwrap().dirty);
+
+        assert_eq!(
+            *changes.lock().unwrap(),
+            [
+                SessionChangeKind::Opened,
+                SessionChangeKind::Opened,
+                SessionChangeKind::Closed,
+                SessionChangeKind::Updated,
+            ]
+        );
+    }
+}
@@ -0,0 +1,399 @@
+//! Integration breakdowns for sequence statistics
+//!
+//! Totals per filter, image type and exposure length, dither events and
+//! the disk space the frames will take, for planning narrowband balance
+//! and storage. Only enabled exposures count.
+//!
+//! Storage estimates assume uncompressed FITS frames at the camera's bit
+//! depth; the destination check compares the remaining frames against
+//! the free space of the image drive.
+
+use serde::{Deserialize, Serialize};
+
+use crate::models::{BinningMode, CameraProfile, ImageType, SimpleExposure, SimpleSequence};
+use crate::services::file_service::DiskSpace;
+
+/// FITS files are written in blocks of this size
+const FITS_BLOCK_SIZE: u64 = 2880;
+
+/// Header blocks of a typical capture FITS file
+const FITS_HEADER_BLOCKS: u64 = 2;
+
+/// Free space always kept beyond the frames
+const MIN_HEADROOM_BYTES: u64 = 1 << 30;
+
+/// Share of the frames' size kept free beyond them
+const HEADROOM_FRACTION: f64 = 0.1;
+
+/// Frames and integration of one filter
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FilterIntegration {
+    pub filter: Option<String>,
+    pub frame_count: i32,
+    pub remaining_frames: i32,
+    /// Seconds
+    pub total_integration: f64,
+    /// Seconds
+    pub remaining_integration: f64,
+    /// Share of the sequence's light integration
+    pub percent_of_integration: f64,
+}
+
+/// Frames and integration of one image type
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImageTypeIntegration {
+    pub image_type: ImageType,
+    pub frame_count: i32,
+    /// Seconds
+    pub total_integration: f64,
+}
+
+/// Frames taken with one exposure length
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExposureLengthCount {
+    /// Seconds
+    pub exposure_time: f64,
+    pub frame_count: i32,
+    /// Seconds
+    pub total_integration: f64,
+}
+
+/// Totals by filter, image type and exposure length
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IntegrationBreakdown {
+    /// Light frames per filter
+    pub filters: Vec<FilterIntegration>,
+    pub image_types: Vec<ImageTypeIntegration>,
+    /// Ordered by exposure length
+    pub exposure_lengths: Vec<ExposureLengthCount>,
+    /// Dithers still to be done
+    pub dither_events: i32,
+}
+
+/// Disk space of one target's frames
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TargetStorage {
+    pub target_id: String,
+    pub target_name: String,
+    pub total_bytes: u64,
+    pub remaining_bytes: u64,
+}
+
+/// Disk space of the sequence's frames
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageEstimate {
+    /// Full frame size in pixels the estimate is based on
+    pub frame_width: u32,
+    pub frame_height: u32,
+    pub bit_depth: u32,
+    /// Size of an unbinned frame file
+    pub bytes_per_frame: u64,
+    pub total_bytes: u64,
+    pub remaining_bytes: u64,
+    pub targets: Vec<TargetStorage>,
+}
+
+/// Free space of an image destination compared with the frames to come
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DestinationSpaceCheck {
+    pub path: String,
+    pub available_bytes: u64,
+    pub total_bytes: u64,
+    pub required_bytes: u64,
+    /// Required bytes plus headroom
+    pub recommended_bytes: u64,
+    /// Whether the recommended space is available
+    pub sufficient: bool,
+    pub warning: Option<String>,
+}
+
+fn enabled_exposures(sequence: &SimpleSequence) -> impl Iterator<Item = &SimpleExposure> {
+    sequence
+        .targets
+        .iter()
+        .flat_map(|t| t.exposures.iter())
+        .filter(|e| e.enabled)
+}
+
+/// Break the sequence's frames down by filter, image type and exposure
+/// length
+pub fn integration_breakdown(sequence: &SimpleSequence) -> IntegrationBreakdown {
+    let mut breakdown = IntegrationBreakdown::default();
+
+    for exposure in enabled_exposures(sequence) {
+        let frames = exposure.total_count.max(0);
+        let integration = frames as f64 * exposure.exposure_time;
+        breakdown.dither_events += exposure.dither_count();
+
+        if exposure.image_type == ImageType::Light {
+            let filter = exposure.filter.as_ref().map(|f| f.name.clone());
+            let index = match breakdown.filters.iter().position(|f| f.filter == filter) {
+                Some(index) => index,
+                None => {
+                    breakdown.filters.push(FilterIntegration {
+                        filter,
+                        frame_count: 0,
+                        remaining_frames: 0,
+                        total_integration: 0.0,
+                        remaining_integration: 0.0,
+                        percent_of_integration: 0.0,
+                    });
+                    breakdown.filters.len() - 1
+                }
+            };
+            let entry = &mut breakdown.filters[index];
+            entry.frame_count += frames;
+            entry.remaining_frames += exposure.remaining();
+            entry.total_integration += integration;
+            entry.remaining_integration += exposure.remaining() as f64 * exposure.exposure_time;
+        }
+
+        match breakdown
+            .image_types
+            .iter_mut()
+            .find(|t| t.image_type == exposure.image_type)
+        {
+            Some(entry) => {
+                entry.frame_count += frames;
+                entry.total_integration += integration;
+            }
+            None => breakdown.image_types.push(ImageTypeIntegration {
+                image_type: exposure.image_type,
+                frame_count: frames,
+                total_integration: integration,
+            }),
+        }
+
+        match breakdown
+            .exposure_lengths
+            .iter_mut()
+            .find(|l| l.exposure_time == exposure.exposure_time)
+        {
+            Some(entry) => {
+                entry.frame_count += frames;
+                entry.total_integration += integration;
+            }
+            None => breakdown.exposure_lengths.push(ExposureLengthCount {
+                exposure_time: exposure.exposure_time,
+                frame_count: frames,
+                total_integration: integration,
+            }),
+        }
+    }
+
+    let light_integration: f64 = breakdown.filters.iter().map(|f| f.total_integration).sum();
+    if light_integration > 0.0 {
+        for filter in &mut breakdown.filters {
+            filter.percent_of_integration = filter.total_integration / light_integration * 100.0;
+        }
+    }
+    breakdown
+        .exposure_lengths
+        .sort_by(|a, b| a.exposure_time.total_cmp(&b.exposure_time));
+    breakdown
+}
+
+/// Size of one FITS frame of the camera at the given binning
+pub fn frame_bytes(camera: &CameraProfile, binning: &BinningMode) -> u64 {
+    let width = camera.width as u64 / binning.x.max(1) as u64;
+    let height = camera.height as u64 / binning.y.max(1) as u64;
+    let bytes_per_pixel = (camera.bit_depth.max(1) as u64).div_ceil(8);
+    let data = width * height * bytes_per_pixel;
+    (FITS_HEADER_BLOCKS + data.div_ceil(FITS_BLOCK_SIZE)) * FITS_BLOCK_SIZE
+}
+
+/// Disk space the sequence's frames take with the camera, `None` when the
+/// camera's sensor size is unknown
+pub fn estimate_storage(
+    sequence: &SimpleSequence,
+    camera: &CameraProfile,
+) -> Option<StorageEstimate> {
+    if camera.width == 0 || camera.height == 0 {
+        return None;
+    }
+
+    let targets: Vec<TargetStorage> = sequence
+        .targets
+        .iter()
+        .map(|target| {
+            let mut storage = TargetStorage {
+                target_id: target.id.clone(),
+                target_name: target.target_name.clone(),
+                total_bytes: 0,
+                remaining_bytes: 0,
+            };
+            for exposure in target.exposures.iter().filter(|e| e.enabled) {
+                let bytes = frame_bytes(camera, &exposure.binning);
+                storage.total_bytes += exposure.total_count.max(0) as u64 * bytes;
+                storage.remaining_bytes += exposure.remaining() as u64 * bytes;
+            }
+            storage
+        })
+        .collect();
+
+    Some(StorageEstimate {
+        frame_width: camera.width,
+        frame_height: camera.height,
+        bit_depth: camera.bit_depth,
+        bytes_per_frame: frame_bytes(camera, &BinningMode::default()),
+        total_bytes: targets.iter().map(|t| t.total_bytes).sum(),
+        remaining_bytes: targets.iter().map(|t| t.remaining_bytes).sum(),
+        targets,
+    })
+}
+
+fn gigabytes(bytes: u64) -> String {
+    format!("{:.1} GB", bytes as f64 / 1e9)
+}
+
+/// Compare the free space at `path` with `required_bytes` of frames plus
+/// headroom
+pub fn check_destination_space(
+    path: &str,
+    space: DiskSpace,
+    required_bytes: u64,
+) -> DestinationSpaceCheck {
+    let headroom = MIN_HEADROOM_BYTES.max((required_bytes as f64 * HEADROOM_FRACTION) as u64);
+    let recommended_bytes = required_bytes + headroom;
+    let available = space.available_bytes;
+
+    let warning = if available < required_bytes {
+        Some(format!(
+            "Only {} free at {}, but the frames need {}",
+            gigabytes(available),
+            path,
+            gigabytes(required_bytes)
+        ))
+    } else if available < recommended_bytes {
+        Some(format!(
+            "{} free at {} leaves less than {} after the frames",
+            gigabytes(available),
+            path,
+            gigabytes(headroom)
+        ))
+    } else {
+        None
+    };
+
+    DestinationSpaceCheck {
+        path: path.to_string(),
+        available_bytes: available,
+        total_bytes: space.total_bytes,
+        required_bytes,
+        recommended_bytes,
+        sufficient: warning.is_none(),
+        warning,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::FilterInfo;
+
+    fn exposure(filter: &str, exposure_time: f64, total_count: i32) -> SimpleExposure {
+        SimpleExposure {
+            filter: Some(FilterInfo {
+                name: filter.to_string(),
+                ..Default::default()
+            }),
+            exposure_time,
+            total_count,
+            ..Default::default()
+        }
+    }
+
+    fn sequence() -> SimpleSequence {
+        let mut sequence = SimpleSequence::new("Statistics");
+        sequence.targets[0].exposures = vec![
+            exposure("Ha", 300.0, 20),
+            exposure("OIII", 300.0, 10),
+            exposure("Ha", 600.0, 5),
+            SimpleExposure {
+                image_type: ImageType::Dark,
+                ..exposure("Ha", 300.0, 10)
+            },
+        ];
+        sequence.targets[0].exposures[0].dither = true;
+        sequence.targets[0].exposures[0].dither_every = 5;
+        sequence
+    }
+
+    #[test]
+    fn test_integration_breakdown() {
+        let breakdown = integration_breakdown(&sequence());
+
+        assert_eq!(breakdown.filters.len(), 2);
+        let ha = &breakdown.filters[0];
+        assert_eq!(ha.frame_count, 25);
+        assert_eq!(ha.total_integration, 9000.0);
+        assert_eq!(ha.percent_of_integration, 75.0);
+
+        assert_eq!(breakdown.image_types.len(), 2);
+        assert_eq!(breakdown.exposure_lengths[0].exposure_time, 300.0);
+        assert_eq!(breakdown.exposure_lengths[0].frame_count, 40);
+        assert_eq!(breakdown.exposure_lengths[1].frame_count, 5);
+        assert_eq!(breakdown.dither_events, 4);
+    }
+
+    #[test]
+    fn test_estimate_storage() {
+        let camera = CameraProfile {
+            width: 1000,
+            height: 1000,
+            ..Default::default()
+        };
+        // 2,000,000 bytes of data round up to 695 blocks, plus the header
+        assert_eq!(frame_bytes(&camera, &BinningMode::default()), 697 * 2880);
+        let binned = frame_bytes(&camera, &BinningMode { x: 2, y: 2 });
+        assert!(binned < 200 * 2880);
+
+        let mut seq = sequence();
+        seq.targets[0].exposures[1].progress_count = 10;
+        let storage = estimate_storage(&seq, &camera).unwrap();
+        assert_eq!(storage.total_bytes, 45 * 697 * 2880);
+        assert_eq!(storage.remaining_bytes, 35 * 697 * 2880);
+
+        let unknown = CameraProfile {
+            width: 0,
+            ..Default::default()
+        };
+        assert!(estimate_storage(&seq, &unknown).is_none());
+
+        let eight_bit = CameraProfile {
+            bit_depth: 8,
+            ..camera
+        };
+        assert_eq!(
+            frame_bytes(&eight_bit, &BinningMode::default()),
+            (2 + 348) * 2880
+        );
+    }
+
+    #[test]
+    fn test_check_destination_space() {
+        let gb = 1_000_000_000;
+        let space = DiskSpace {
+            available_bytes: 50 * gb,
+            total_bytes: 500 * gb,
+        };
+
+        let check = check_destination_space("/images", space, 20 * gb);
+        assert!(check.sufficient);
+        assert_eq!(check.recommended_bytes, 22 * gb);
+
+        let check = check_destination_space("/images", space, 48 * gb);
+        assert!(!check.sufficient);
+        assert!(check.warning.unwrap().contains("leaves less than"));
+
+        let check = check_destination_space("/images", space, 60 * gb);
+        assert!(check.warning.unwrap().starts_with("Only 50.0 GB free"));
+    }
+}
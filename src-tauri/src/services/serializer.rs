@@ -3,6 +3,7 @@
 //! Handles conversion between different formats (JSON, CSV, XML)
 
 use crate::models::*;
+use crate::services::csv_io;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -67,9 +68,14 @@ pub fn export_to_csv(sequence: &SimpleSequence) -> Result<String> {
             target.coordinates.dec_seconds
         );
 
-        output.push_str(&format!(
-            "{},{},{},{:.1}\n",
-            target.target_name, ra_str, dec_str, target.position_angle
+        output.push_str(&csv_io::write_record(
+            &[
+                target.target_name.clone(),
+                ra_str,
+                dec_str,
+                format!("{:.1}", target.position_angle),
+            ],
+            ',',
         ));
     }
 
@@ -78,24 +84,20 @@ pub fn export_to_csv(sequence: &SimpleSequence) -> Result<String> {
 
 /// Import targets from CSV
 pub fn import_from_csv(csv_content: &str) -> Result<Vec<SimpleTarget>> {
-    let lines: Vec<&str> = csv_content.lines().collect();
-    if lines.len() < 2 {
+    let records = csv_io::read_records(csv_content, None).map_err(SerializerError::Csv)?;
+    if records.len() < 2 {
         return Err(SerializerError::Csv(
             "CSV file is empty or has no data rows".into(),
         ));
     }
 
-    let header = lines[0].to_lowercase();
-    let headers: Vec<&str> = header.split(',').map(|h| h.trim()).collect();
+    let headers: Vec<String> = records[0].fields.iter().map(|h| h.to_lowercase()).collect();
 
-    let is_telescopius = headers.contains(&"pane") || headers.contains(&"familiar name");
+    let is_telescopius = headers.iter().any(|h| h == "pane" || h == "familiar name");
     let mut targets = Vec::new();
 
-    for line in lines.iter().skip(1) {
-        let values: Vec<&str> = line
-            .split(',')
-            .map(|v| v.trim().trim_matches('"'))
-            .collect();
+    for record in records.iter().skip(1) {
+        let values = &record.fields;
         if values.len() < headers.len() {
             continue;
         }
@@ -103,8 +105,8 @@ pub fn import_from_csv(csv_content: &str) -> Result<Vec<SimpleTarget>> {
         let get_value = |key: &str| -> Option<&str> {
             headers
                 .iter()
-                .position(|h| *h == key)
-                .and_then(|i| values.get(i).copied())
+                .position(|h| h == key)
+                .and_then(|i| values.get(i).map(String::as_str))
         };
 
         let (name, ra_str, dec_str, pa) = if is_telescopius {
@@ -199,7 +201,7 @@ pub fn export_to_xml(sequence: &SimpleSequence) -> Result<String> {
 
         xml.push_str(&format!(
             r#"
-  <CaptureSequenceList TargetName="{}" Mode="{:?}" Delay="{}" SlewToTarget="{}" CenterTarget="{}" RotateTarget="{}" StartGuiding="{}" AutoFocusOnStart="{}" AutoFocusOnFilterChange="{}">
+  <CaptureSequenceList TargetName="{}" Mode="{:?}" Delay="{}" SlewToTarget="{}" CenterTarget="{}" RotateTarget="{}" StartGuiding="{}" AutoFocusOnStart="{}" AutoFocusOnFilterChange="{}" AutoFocusAfterSetTime="{}" AutoFocusSetTime="{}" AutoFocusAfterSetExposures="{}" AutoFocusSetExposures="{}" AutoFocusAfterTemperatureChange="{}" AutoFocusAfterTemperatureChangeAmount="{}" AutoFocusAfterHFRChange="{}" AutoFocusAfterHFRChangeAmount="{}">
     <Coordinates>
       <RA>{}</RA>
       <Dec>{}</Dec>
@@ -216,6 +218,14 @@ pub fn export_to_xml(sequence: &SimpleSequence) -> Result<String> {
             target.start_guiding,
             target.auto_focus_on_start,
             target.auto_focus_on_filter_change,
+            target.auto_focus_after_set_time,
+            target.auto_focus_set_time,
+            target.auto_focus_after_set_exposures,
+            target.auto_focus_set_exposures,
+            target.auto_focus_after_temperature_change,
+            target.auto_focus_after_temperature_change_amount,
+            target.auto_focus_after_hfr_change,
+            target.auto_focus_after_hfr_change_amount,
             ra_degrees,
             dec_degrees,
             target.position_angle
@@ -223,10 +233,15 @@ pub fn export_to_xml(sequence: &SimpleSequence) -> Result<String> {
 
         for exp in &target.exposures {
             let filter_xml = if let Some(f) = &exp.filter {
+                let focus_offset = f
+                    .focus_offset
+                    .map(|offset| format!("<FocusOffset>{}</FocusOffset>", offset))
+                    .unwrap_or_default();
                 format!(
-                    "<Name>{}</Name><Position>{}</Position>",
+                    "<Name>{}</Name><Position>{}</Position>{}",
                     escape_xml(&f.name),
-                    f.position
+                    f.position,
+                    focus_offset
                 )
             } else {
                 String::new()
@@ -297,21 +312,25 @@ mod tests {
     use super::*;
 
     fn create_test_sequence() -> SimpleSequence {
-        let mut seq = SimpleSequence::default();
-        seq.title = "Test Sequence".to_string();
-
-        let mut target = SimpleTarget::default();
-        target.target_name = "M31".to_string();
-        target.coordinates = Coordinates::from_decimal(0.712, 41.27);
-        target.position_angle = 45.0;
+        let exposure = SimpleExposure {
+            exposure_time: 60.0,
+            total_count: 10,
+            ..Default::default()
+        };
 
-        let mut exposure = SimpleExposure::default();
-        exposure.exposure_time = 60.0;
-        exposure.total_count = 10;
-        target.exposures = vec![exposure];
+        let target = SimpleTarget {
+            target_name: "M31".to_string(),
+            coordinates: Coordinates::from_decimal(0.712, 41.27),
+            position_angle: 45.0,
+            exposures: vec![exposure],
+            ..Default::default()
+        };
 
-        seq.targets = vec![target];
-        seq
+        SimpleSequence {
+            title: "Test Sequence".to_string(),
+            targets: vec![target],
+            ..Default::default()
+        }
     }
 
     #[test]
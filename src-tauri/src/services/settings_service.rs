@@ -1,17 +1,223 @@
 //! Application settings service
 
-use once_cell::sync::Lazy;
-use parking_lot::RwLock;
-use std::path::PathBuf;
-use std::sync::Arc;
+use chrono::{DateTime, Utc};
+use parking_lot::{RwLock, RwLockReadGuard};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use tokio::fs;
+use tokio::sync::Mutex;
 
-use crate::models::AppSettings;
-use crate::services::file_service;
+use crate::error::{AppError, AppResult, ResultExt};
+use crate::models::{
+    AppSettings, BackupRetentionPolicy, EquipmentProfile, FilterInfo, FilterSet, ObservingSite,
+    RecentFileMetadata, RemoteApiSettings, SimpleSequence, UnitPreferences, ValidationRuleConfig,
+    SETTINGS_VERSION,
+};
+use crate::services::astronomy::ObserverLocation;
+use crate::services::{file_service, path_guard, sequence_statistics};
+use crate::state::AppState;
 
-/// Global settings instance
-static SETTINGS: Lazy<Arc<RwLock<AppSettings>>> =
-    Lazy::new(|| Arc::new(RwLock::new(AppSettings::default())));
+/// Settings in memory, part of the [`AppState`](crate::state::AppState)
+#[derive(Default)]
+pub struct SettingsState {
+    current: RwLock<AppSettings>,
+    /// Report of the last [`load_settings`], when it migrated or warned
+    migration_report: RwLock<Option<SettingsMigrationReport>>,
+    /// Held from reading to storing an update, so concurrent updates don't
+    /// drop each other's changes
+    update_lock: Mutex<()>,
+}
+
+impl SettingsState {
+    /// Current settings
+    pub fn get(&self) -> AppSettings {
+        self.current.read().clone()
+    }
+
+    /// Read the settings in place
+    pub fn read(&self) -> RwLockReadGuard<'_, AppSettings> {
+        self.current.read()
+    }
+
+    fn set(&self, settings: AppSettings) {
+        *self.current.write() = settings;
+    }
+
+    pub fn migration_report(&self) -> Option<SettingsMigrationReport> {
+        self.migration_report.read().clone()
+    }
+}
+
+/// Outcome of migrating a settings file to [`SETTINGS_VERSION`]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SettingsMigrationReport {
+    pub from_version: u32,
+    pub to_version: u32,
+    /// Descriptions of the applied migrations, in order
+    pub applied: Vec<String>,
+    /// Copy of the settings file taken before migrating
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub backup_path: Option<String>,
+    pub warnings: Vec<String>,
+}
+
+/// Migration from `from` to `from + 1`, working on the settings JSON
+struct SettingsMigration {
+    from: u32,
+    description: &'static str,
+    migrate: fn(&mut Value) -> Result<(), String>,
+}
+
+/// Ordered settings migrations. Append new migrations here when bumping
+/// [`SETTINGS_VERSION`].
+const MIGRATIONS: &[SettingsMigration] = &[SettingsMigration {
+    from: 0,
+    description: "Fill in settings missing from files written before versioning",
+    migrate: fill_missing_defaults,
+}];
+
+/// Add default values for top-level fields the file does not have
+fn fill_missing_defaults(settings: &mut Value) -> Result<(), String> {
+    let defaults = serde_json::to_value(AppSettings::default()).map_err(|e| e.to_string())?;
+    let (Value::Object(settings), Value::Object(defaults)) = (settings, defaults) else {
+        return Err("Settings file is not a JSON object".to_string());
+    };
+    for (key, value) in defaults {
+        settings.entry(key).or_insert(value);
+    }
+    Ok(())
+}
+
+/// Run the migrations needed to bring settings JSON to [`SETTINGS_VERSION`]
+pub fn migrate_settings_value(settings: &mut Value) -> Result<SettingsMigrationReport, String> {
+    let from_version = settings.get("version").and_then(Value::as_u64).unwrap_or(0) as u32;
+    let mut report = SettingsMigrationReport {
+        from_version,
+        to_version: from_version,
+        ..Default::default()
+    };
+
+    if from_version > SETTINGS_VERSION {
+        report.warnings.push(format!(
+            "Settings were written by a newer version ({}); unknown settings are ignored",
+            from_version
+        ));
+        return Ok(report);
+    }
+
+    for migration in MIGRATIONS.iter().filter(|m| m.from >= from_version) {
+        (migration.migrate)(settings).map_err(|e| {
+            format!(
+                "Settings migration failed ({}): {}",
+                migration.description, e
+            )
+        })?;
+        report.applied.push(migration.description.to_string());
+        report.to_version = migration.from + 1;
+    }
+    settings["version"] = Value::from(report.to_version);
+
+    Ok(report)
+}
+
+/// Get the migration report of the last settings load, if there is
+/// anything to report
+pub fn get_settings_migration_report(state: &AppState) -> Option<SettingsMigrationReport> {
+    state.settings.migration_report()
+}
+
+/// Settings tied to one machine, left out of settings profiles
+const MACHINE_SETTINGS: &[&str] = &[
+    "lastDirectory",
+    "recentFiles",
+    "pinnedFiles",
+    "recentFileMetadata",
+    "windowWidth",
+    "windowHeight",
+    "windowX",
+    "windowY",
+    "windowMaximized",
+    "libraryDirectory",
+    "allowedDirectories",
+];
+
+/// Portable copy of the settings for moving them between machines
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SettingsProfile {
+    pub exported_at: DateTime<Utc>,
+    /// Settings JSON without machine-specific fields
+    pub settings: Value,
+}
+
+/// Create a profile from settings
+pub fn create_settings_profile(settings: &AppSettings) -> AppResult<SettingsProfile> {
+    let mut value = serde_json::to_value(settings)
+        .map_err(|e| AppError::Internal(format!("Failed to serialize settings: {}", e)))?;
+    if let Value::Object(map) = &mut value {
+        map.retain(|key, _| !MACHINE_SETTINGS.contains(&key.as_str()));
+    }
+
+    Ok(SettingsProfile {
+        exported_at: Utc::now(),
+        settings: value,
+    })
+}
+
+/// Apply a profile on top of `current`, keeping machine-specific settings.
+/// Profiles from older versions are migrated first.
+pub fn apply_settings_profile(
+    current: &AppSettings,
+    profile: &SettingsProfile,
+) -> AppResult<AppSettings> {
+    let mut imported = profile.settings.clone();
+    if !imported.is_object() {
+        return Err(AppError::Parse(
+            "Settings profile holds no settings".to_string(),
+        ));
+    }
+    migrate_settings_value(&mut imported).map_err(AppError::Parse)?;
+
+    let mut merged = serde_json::to_value(current)
+        .map_err(|e| AppError::Internal(format!("Failed to serialize settings: {}", e)))?;
+    if let (Value::Object(merged), Value::Object(imported)) = (&mut merged, imported) {
+        for (key, value) in imported {
+            if !MACHINE_SETTINGS.contains(&key.as_str()) {
+                merged.insert(key, value);
+            }
+        }
+    }
+
+    serde_json::from_value(merged)
+        .map_err(|e| AppError::Parse(format!("Invalid settings profile: {}", e)))
+}
+
+/// Write the current settings to a profile file
+pub async fn export_settings_profile(state: &AppState, path: &Path) -> AppResult<()> {
+    let profile = create_settings_profile(&get_settings(state))?;
+    let content = serde_json::to_string_pretty(&profile)
+        .map_err(|e| AppError::Internal(format!("Failed to serialize settings profile: {}", e)))?;
+
+    fs::write(path, content)
+        .await
+        .context("Failed to write settings profile")
+}
+
+/// Replace the portable settings with those of a profile file
+pub async fn import_settings_profile(state: &AppState, path: &Path) -> AppResult<AppSettings> {
+    let content = fs::read_to_string(path)
+        .await
+        .context("Failed to read settings profile")?;
+    let profile: SettingsProfile = serde_json::from_str(&content)
+        .map_err(|e| AppError::Parse(format!("Failed to parse settings profile: {}", e)))?;
+
+    let settings = apply_settings_profile(&get_settings(state), &profile)?;
+    save_settings(state, &settings).await?;
+    Ok(settings)
+}
 
 /// Get settings file path
 fn get_settings_path() -> PathBuf {
@@ -19,108 +225,309 @@ fn get_settings_path() -> PathBuf {
 }
 
 /// Load settings from file
-pub async fn load_settings() -> Result<AppSettings, String> {
+pub async fn load_settings(state: &AppState) -> AppResult<AppSettings> {
     let path = get_settings_path();
 
     if !path.exists() {
         let settings = AppSettings::default();
-        save_settings(&settings).await?;
+        save_settings(state, &settings).await?;
         return Ok(settings);
     }
 
     let contents = fs::read_to_string(&path)
         .await
-        .map_err(|e| format!("Failed to read settings: {}", e))?;
+        .context("Failed to read settings")?;
 
-    let settings: AppSettings =
-        serde_json::from_str(&contents).map_err(|e| format!("Failed to parse settings: {}", e))?;
+    let mut value: Value = serde_json::from_str(&contents)
+        .map_err(|e| AppError::Parse(format!("Failed to parse settings: {}", e)))?;
+    let mut report = migrate_settings_value(&mut value).map_err(AppError::Parse)?;
 
-    // Update global instance
-    *SETTINGS.write() = settings.clone();
+    let settings: AppSettings = serde_json::from_value(value)
+        .map_err(|e| AppError::Parse(format!("Failed to parse settings: {}", e)))?;
+
+    if report.applied.is_empty() {
+        state.settings.set(settings.clone());
+    } else {
+        // Keep the original file in case a migration lost something
+        let backup_path =
+            path.with_file_name(format!("settings.v{}.bak.json", report.from_version));
+        fs::copy(&path, &backup_path)
+            .await
+            .context("Failed to back up settings before migration")?;
+        report.backup_path = Some(backup_path.display().to_string());
+        log::info!(
+            "Migrated settings from version {} to {}",
+            report.from_version,
+            report.to_version
+        );
+        save_settings(state, &settings).await?;
+    }
+    for warning in &report.warnings {
+        log::warn!("{}", warning);
+    }
+    let noteworthy = !report.applied.is_empty() || !report.warnings.is_empty();
+    *state.settings.migration_report.write() = noteworthy.then_some(report);
 
     Ok(settings)
 }
 
 /// Save settings to file
-pub async fn save_settings(settings: &AppSettings) -> Result<(), String> {
+pub async fn save_settings(state: &AppState, settings: &AppSettings) -> AppResult<()> {
+    let _update = state.settings.update_lock.lock().await;
+    write_settings(state, settings).await
+}
+
+/// Save settings edited in the frontend. The library folder and the file
+/// command allowlist are kept: they only change through the commands that
+/// validate them.
+pub async fn save_frontend_settings(
+    state: &AppState,
+    settings: AppSettings,
+) -> AppResult<AppSettings> {
+    update_settings(state, |current| {
+        *current = AppSettings {
+            library_directory: current.library_directory.take(),
+            allowed_directories: std::mem::take(&mut current.allowed_directories),
+            ..settings
+        };
+    })
+    .await
+}
+
+/// Write settings to file and make them current
+async fn write_settings(state: &AppState, settings: &AppSettings) -> AppResult<()> {
     let path = get_settings_path();
+    // Settings from the frontend may not carry the version
+    let settings = &AppSettings {
+        version: SETTINGS_VERSION,
+        ..settings.clone()
+    };
 
     // Create parent directory if it doesn't exist
     if let Some(parent) = path.parent() {
         fs::create_dir_all(parent)
             .await
-            .map_err(|e| format!("Failed to create settings directory: {}", e))?;
+            .context("Failed to create settings directory")?;
     }
 
     let contents = serde_json::to_string_pretty(settings)
-        .map_err(|e| format!("Failed to serialize settings: {}", e))?;
+        .map_err(|e| AppError::Internal(format!("Failed to serialize settings: {}", e)))?;
 
     fs::write(&path, contents)
         .await
-        .map_err(|e| format!("Failed to write settings: {}", e))?;
+        .context("Failed to write settings")?;
 
-    // Update global instance
-    *SETTINGS.write() = settings.clone();
+    state.settings.set(settings.clone());
 
     Ok(())
 }
 
 /// Get current settings
-pub fn get_settings() -> AppSettings {
-    SETTINGS.read().clone()
+pub fn get_settings(state: &AppState) -> AppSettings {
+    state.settings.get()
 }
 
-/// Update settings
-pub async fn update_settings<F>(updater: F) -> Result<AppSettings, String>
+/// Update settings. Updates run one at a time, each starting from the
+/// settings the previous one saved.
+pub async fn update_settings<F>(state: &AppState, updater: F) -> AppResult<AppSettings>
 where
     F: FnOnce(&mut AppSettings),
 {
-    let mut settings = get_settings();
+    let _update = state.settings.update_lock.lock().await;
+    let mut settings = get_settings(state);
     updater(&mut settings);
-    save_settings(&settings).await?;
+    write_settings(state, &settings).await?;
     Ok(settings)
 }
 
+/// Keep pinned files and the newest other files up to the maximum, and
+/// drop metadata of files no longer listed
+fn trim_recent_files(settings: &mut AppSettings) {
+    let pinned = &settings.pinned_files;
+    let mut unpinned = 0;
+    settings.recent_files.retain(|path| {
+        if pinned.contains(path) {
+            return true;
+        }
+        unpinned += 1;
+        unpinned <= settings.max_recent_files
+    });
+
+    let recent = &settings.recent_files;
+    settings
+        .recent_file_metadata
+        .retain(|path, _| recent.contains(path));
+}
+
+/// Move a file to the front of the recent files
+fn push_recent_file(settings: &mut AppSettings, path: &str) {
+    settings.recent_files.retain(|p| p != path);
+    settings.recent_files.insert(0, path.to_string());
+    trim_recent_files(settings);
+}
+
 /// Add file to recent files list
-pub async fn add_recent_file(path: &str) -> Result<(), String> {
-    update_settings(|settings| {
-        // Remove if already exists
-        settings.recent_files.retain(|p| p != path);
-        // Add to front
-        settings.recent_files.insert(0, path.to_string());
-        // Trim to max size
-        settings.recent_files.truncate(settings.max_recent_files);
+pub async fn add_recent_file(state: &AppState, path: &str) -> AppResult<()> {
+    update_settings(state, |settings| push_recent_file(settings, path)).await?;
+    Ok(())
+}
+
+/// Add a sequence file to the recent files, noting what the sequence holds
+pub async fn add_recent_sequence_file(
+    state: &AppState,
+    path: &str,
+    sequence: &SimpleSequence,
+) -> AppResult<()> {
+    let target_count = sequence.targets.len();
+    let total_integration = sequence_statistics::integration_breakdown(sequence)
+        .filters
+        .iter()
+        .map(|f| f.total_integration)
+        .sum();
+
+    update_settings(state, |settings| {
+        push_recent_file(settings, path);
+        let metadata = settings
+            .recent_file_metadata
+            .entry(path.to_string())
+            .or_default();
+        metadata.target_count = Some(target_count);
+        metadata.total_integration = Some(total_integration);
+    })
+    .await?;
+    Ok(())
+}
+
+/// Note the format a recent file was last exported to. Files not in the
+/// recent files are left alone.
+pub async fn record_recent_file_export(
+    state: &AppState,
+    path: &str,
+    format: &str,
+) -> AppResult<()> {
+    if !state.settings.read().recent_files.iter().any(|p| p == path) {
+        return Ok(());
+    }
+    update_settings(state, |settings| {
+        if settings.recent_files.iter().any(|p| p == path) {
+            settings
+                .recent_file_metadata
+                .entry(path.to_string())
+                .or_default()
+                .last_export_format = Some(format.to_string());
+        }
+    })
+    .await?;
+    Ok(())
+}
+
+/// Pin or unpin a recent file. Pinning a file not in the list adds it.
+pub async fn set_recent_file_pinned(state: &AppState, path: &str, pinned: bool) -> AppResult<()> {
+    update_settings(state, |settings| {
+        settings.pinned_files.retain(|p| p != path);
+        if pinned {
+            settings.pinned_files.push(path.to_string());
+            if !settings.recent_files.iter().any(|p| p == path) {
+                settings.recent_files.insert(0, path.to_string());
+            }
+        }
+        trim_recent_files(settings);
     })
     .await?;
     Ok(())
 }
 
-/// Remove file from recent files list
-pub async fn remove_recent_file(path: &str) -> Result<(), String> {
-    update_settings(|settings| {
+/// Remove file from recent files list, unpinning it
+pub async fn remove_recent_file(state: &AppState, path: &str) -> AppResult<()> {
+    update_settings(state, |settings| {
         settings.recent_files.retain(|p| p != path);
+        settings.pinned_files.retain(|p| p != path);
+        trim_recent_files(settings);
     })
     .await?;
     Ok(())
 }
 
-/// Clear recent files list
-pub async fn clear_recent_files() -> Result<(), String> {
-    update_settings(|settings| {
-        settings.recent_files.clear();
+/// Clear recent files list, keeping pinned files
+pub async fn clear_recent_files(state: &AppState) -> AppResult<()> {
+    update_settings(state, |settings| {
+        let pinned = &settings.pinned_files;
+        settings.recent_files.retain(|p| pinned.contains(p));
+        trim_recent_files(settings);
     })
     .await?;
     Ok(())
 }
 
 /// Get recent files
-pub fn get_recent_files() -> Vec<String> {
-    SETTINGS.read().recent_files.clone()
+pub fn get_recent_files(state: &AppState) -> Vec<String> {
+    state.settings.read().recent_files.clone()
+}
+
+/// Recent file checked against the file system
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecentFileDetails {
+    pub path: String,
+    pub pinned: bool,
+    pub exists: bool,
+    /// Title read from the file
+    pub title: Option<String>,
+    pub size: Option<u64>,
+    pub modified: Option<DateTime<Utc>>,
+    #[serde(flatten)]
+    pub metadata: RecentFileMetadata,
+}
+
+/// Recent files, pinned ones first, each checked for existence and with
+/// the title read from the file
+pub async fn get_recent_file_details(state: &AppState) -> Vec<RecentFileDetails> {
+    let settings = get_settings(state);
+    let mut details = Vec::with_capacity(settings.recent_files.len());
+
+    for path in &settings.recent_files {
+        // Entries stored before paths were checked may lie outside the
+        // allowed directories; those are reported as missing
+        let file = path_guard::check_path(state, path).ok();
+        let info = match &file {
+            Some(file) => fs::metadata(file).await.ok().filter(|m| m.is_file()),
+            None => None,
+        };
+        let title = match (&file, &info) {
+            (Some(file), Some(_)) => file_service::read_sequence_title(file)
+                .await
+                .unwrap_or_else(|e| {
+                    log::debug!("Failed to read title of {}: {}", path, e);
+                    None
+                }),
+            _ => None,
+        };
+
+        details.push(RecentFileDetails {
+            path: path.clone(),
+            pinned: settings.pinned_files.contains(path),
+            exists: info.is_some(),
+            title,
+            size: info.as_ref().map(|m| m.len()),
+            modified: info
+                .as_ref()
+                .and_then(|m| m.modified().ok())
+                .map(DateTime::<Utc>::from),
+            metadata: settings
+                .recent_file_metadata
+                .get(path)
+                .cloned()
+                .unwrap_or_default(),
+        });
+    }
+
+    details.sort_by_key(|d| !d.pinned);
+    details
 }
 
 /// Update last directory
-pub async fn set_last_directory(path: &str) -> Result<(), String> {
-    update_settings(|settings| {
+pub async fn set_last_directory(state: &AppState, path: &str) -> AppResult<()> {
+    update_settings(state, |settings| {
         settings.last_directory = Some(path.to_string());
     })
     .await?;
@@ -128,19 +535,20 @@ pub async fn set_last_directory(path: &str) -> Result<(), String> {
 }
 
 /// Get last directory
-pub fn get_last_directory() -> Option<String> {
-    SETTINGS.read().last_directory.clone()
+pub fn get_last_directory(state: &AppState) -> Option<String> {
+    state.settings.read().last_directory.clone()
 }
 
 /// Update window state
 pub async fn save_window_state(
+    state: &AppState,
     width: u32,
     height: u32,
     x: Option<i32>,
     y: Option<i32>,
     maximized: bool,
-) -> Result<(), String> {
-    update_settings(|settings| {
+) -> AppResult<()> {
+    update_settings(state, |settings| {
         settings.window_width = Some(width);
         settings.window_height = Some(height);
         settings.window_x = x;
@@ -152,8 +560,10 @@ pub async fn save_window_state(
 }
 
 /// Get window state
-pub fn get_window_state() -> (Option<u32>, Option<u32>, Option<i32>, Option<i32>, bool) {
-    let settings = SETTINGS.read();
+pub fn get_window_state(
+    state: &AppState,
+) -> (Option<u32>, Option<u32>, Option<i32>, Option<i32>, bool) {
+    let settings = state.settings.read();
     (
         settings.window_width,
         settings.window_height,
@@ -164,8 +574,8 @@ pub fn get_window_state() -> (Option<u32>, Option<u32>, Option<i32>, Option<i32>
 }
 
 /// Update theme
-pub async fn set_theme(theme: &str) -> Result<(), String> {
-    update_settings(|settings| {
+pub async fn set_theme(state: &AppState, theme: &str) -> AppResult<()> {
+    update_settings(state, |settings| {
         settings.theme = theme.to_string();
     })
     .await?;
@@ -173,13 +583,13 @@ pub async fn set_theme(theme: &str) -> Result<(), String> {
 }
 
 /// Get theme
-pub fn get_theme() -> String {
-    SETTINGS.read().theme.clone()
+pub fn get_theme(state: &AppState) -> String {
+    state.settings.read().theme.clone()
 }
 
 /// Update language
-pub async fn set_language(language: &str) -> Result<(), String> {
-    update_settings(|settings| {
+pub async fn set_language(state: &AppState, language: &str) -> AppResult<()> {
+    update_settings(state, |settings| {
         settings.language = language.to_string();
     })
     .await?;
@@ -187,13 +597,27 @@ pub async fn set_language(language: &str) -> Result<(), String> {
 }
 
 /// Get language
-pub fn get_language() -> String {
-    SETTINGS.read().language.clone()
+pub fn get_language(state: &AppState) -> String {
+    state.settings.read().language.clone()
+}
+
+/// Update unit and locale preferences
+pub async fn set_unit_preferences(state: &AppState, preferences: UnitPreferences) -> AppResult<()> {
+    update_settings(state, |settings| {
+        settings.unit_preferences = preferences;
+    })
+    .await?;
+    Ok(())
+}
+
+/// Get unit and locale preferences
+pub fn get_unit_preferences(state: &AppState) -> UnitPreferences {
+    state.settings.read().unit_preferences
 }
 
 /// Update estimated download time
-pub async fn set_estimated_download_time(seconds: f64) -> Result<(), String> {
-    update_settings(|settings| {
+pub async fn set_estimated_download_time(state: &AppState, seconds: f64) -> AppResult<()> {
+    update_settings(state, |settings| {
         settings.estimated_download_time = seconds;
     })
     .await?;
@@ -201,6 +625,538 @@ pub async fn set_estimated_download_time(seconds: f64) -> Result<(), String> {
 }
 
 /// Get estimated download time
-pub fn get_estimated_download_time() -> f64 {
-    SETTINGS.read().estimated_download_time
+pub fn get_estimated_download_time(state: &AppState) -> f64 {
+    state.settings.read().estimated_download_time
+}
+
+/// Get validation rule overrides
+pub fn get_validation_rule_configs(state: &AppState) -> HashMap<String, ValidationRuleConfig> {
+    state.settings.read().validation_rules.clone()
+}
+
+/// Set or clear the override for a validation rule. An empty config
+/// restores the rule's defaults.
+pub async fn set_validation_rule_config(
+    state: &AppState,
+    rule_id: &str,
+    config: Option<ValidationRuleConfig>,
+) -> AppResult<()> {
+    let config = config.filter(|c| *c != ValidationRuleConfig::default());
+    update_settings(state, |settings| match config {
+        Some(config) => {
+            settings
+                .validation_rules
+                .insert(rule_id.to_string(), config);
+        }
+        None => {
+            settings.validation_rules.remove(rule_id);
+        }
+    })
+    .await?;
+    Ok(())
+}
+
+/// Whether sequence files are backed up before being overwritten
+pub fn get_backup_on_save(state: &AppState) -> bool {
+    state.settings.read().backup_on_save
+}
+
+/// Enable or disable backup-on-save
+pub async fn set_backup_on_save(state: &AppState, enabled: bool) -> AppResult<()> {
+    update_settings(state, |settings| settings.backup_on_save = enabled).await?;
+    Ok(())
+}
+
+/// Get backup retention policy
+pub fn get_backup_retention(state: &AppState) -> BackupRetentionPolicy {
+    state.settings.read().backup_retention
+}
+
+/// Set backup retention policy
+pub async fn set_backup_retention(
+    state: &AppState,
+    policy: BackupRetentionPolicy,
+) -> AppResult<()> {
+    update_settings(state, |settings| settings.backup_retention = policy).await?;
+    Ok(())
+}
+
+/// Get the sequence library folder
+pub fn get_library_directory(state: &AppState) -> Option<String> {
+    state.settings.read().library_directory.clone()
+}
+
+/// Set the sequence library folder
+pub async fn set_library_directory(state: &AppState, path: Option<String>) -> AppResult<()> {
+    update_settings(state, |settings| settings.library_directory = path).await?;
+    Ok(())
+}
+
+/// Get directories approved for file commands
+pub fn get_allowed_directories(state: &AppState) -> Vec<String> {
+    state.settings.read().allowed_directories.clone()
+}
+
+/// Approve a directory for file commands
+pub async fn add_allowed_directory(state: &AppState, path: &str) -> AppResult<()> {
+    if get_allowed_directories(state).iter().any(|p| p == path) {
+        return Ok(());
+    }
+    update_settings(state, |settings| {
+        settings.allowed_directories.push(path.to_string())
+    })
+    .await?;
+    Ok(())
+}
+
+/// Withdraw a directory approval
+pub async fn remove_allowed_directory(state: &AppState, path: &str) -> AppResult<()> {
+    update_settings(state, |settings| {
+        settings.allowed_directories.retain(|p| p != path)
+    })
+    .await?;
+    Ok(())
+}
+
+/// Get the remote API settings, generating a token if none is set yet
+pub async fn get_remote_api_settings(state: &AppState) -> AppResult<RemoteApiSettings> {
+    let settings = state.settings.read().remote_api.clone();
+    if settings.token.is_empty() {
+        return regenerate_remote_api_token(state).await;
+    }
+    Ok(settings)
+}
+
+/// Save the remote API settings. An empty token is replaced by a new one.
+pub async fn set_remote_api_settings(
+    state: &AppState,
+    mut remote_api: RemoteApiSettings,
+) -> AppResult<RemoteApiSettings> {
+    if remote_api.token.is_empty() {
+        remote_api.token = generate_api_token();
+    }
+    let settings = update_settings(state, |settings| settings.remote_api = remote_api).await?;
+    Ok(settings.remote_api)
+}
+
+/// Replace the remote API token, invalidating the old one
+pub async fn regenerate_remote_api_token(state: &AppState) -> AppResult<RemoteApiSettings> {
+    let token = generate_api_token();
+    let settings = update_settings(state, |settings| settings.remote_api.token = token).await?;
+    Ok(settings.remote_api)
+}
+
+fn generate_api_token() -> String {
+    format!(
+        "{}{}",
+        uuid::Uuid::new_v4().simple(),
+        uuid::Uuid::new_v4().simple()
+    )
+}
+
+/// List saved equipment profiles
+pub fn list_equipment_profiles(state: &AppState) -> Vec<EquipmentProfile> {
+    state.settings.read().equipment_profiles.clone()
+}
+
+/// Get equipment profile by id
+pub fn get_equipment_profile(state: &AppState, id: &str) -> Option<EquipmentProfile> {
+    state
+        .settings
+        .read()
+        .equipment_profiles
+        .iter()
+        .find(|p| p.id == id)
+        .cloned()
+}
+
+/// Add or update an equipment profile
+pub async fn save_equipment_profile(state: &AppState, profile: EquipmentProfile) -> AppResult<()> {
+    let errors = profile.validate();
+    if !errors.is_empty() {
+        return Err(AppError::Validation(errors));
+    }
+
+    update_settings(state, |settings| {
+        match settings
+            .equipment_profiles
+            .iter_mut()
+            .find(|p| p.id == profile.id)
+        {
+            Some(existing) => *existing = profile,
+            None => settings.equipment_profiles.push(profile),
+        }
+    })
+    .await?;
+    Ok(())
+}
+
+/// Delete an equipment profile
+pub async fn delete_equipment_profile(state: &AppState, id: &str) -> AppResult<()> {
+    if get_equipment_profile(state, id).is_none() {
+        return Err(AppError::NotFound(format!(
+            "Equipment profile not found: {}",
+            id
+        )));
+    }
+
+    update_settings(state, |settings| {
+        settings.equipment_profiles.retain(|p| p.id != id);
+        if settings.active_equipment_profile_id.as_deref() == Some(id) {
+            settings.active_equipment_profile_id = None;
+        }
+    })
+    .await?;
+    Ok(())
+}
+
+/// Set the active equipment profile (None to clear)
+pub async fn set_active_equipment_profile(state: &AppState, id: Option<String>) -> AppResult<()> {
+    if let Some(ref id) = id {
+        if get_equipment_profile(state, id).is_none() {
+            return Err(AppError::NotFound(format!(
+                "Equipment profile not found: {}",
+                id
+            )));
+        }
+    }
+
+    update_settings(state, |settings| {
+        settings.active_equipment_profile_id = id;
+    })
+    .await?;
+    Ok(())
+}
+
+/// Get the active equipment profile, if any
+pub fn get_active_equipment_profile(state: &AppState) -> Option<EquipmentProfile> {
+    let settings = state.settings.read();
+    let id = settings.active_equipment_profile_id.as_ref()?;
+    settings
+        .equipment_profiles
+        .iter()
+        .find(|p| &p.id == id)
+        .cloned()
+}
+
+/// List saved filter sets
+pub fn list_filter_sets(state: &AppState) -> Vec<FilterSet> {
+    state.settings.read().filter_sets.clone()
+}
+
+/// Add or update a filter set
+pub async fn save_filter_set(state: &AppState, filter_set: FilterSet) -> AppResult<()> {
+    let errors = filter_set.validate();
+    if !errors.is_empty() {
+        return Err(AppError::Validation(errors));
+    }
+
+    update_settings(state, |settings| {
+        match settings
+            .filter_sets
+            .iter_mut()
+            .find(|s| s.id == filter_set.id)
+        {
+            Some(existing) => *existing = filter_set,
+            None => settings.filter_sets.push(filter_set),
+        }
+    })
+    .await?;
+    Ok(())
+}
+
+/// Delete a filter set
+pub async fn delete_filter_set(state: &AppState, id: &str) -> AppResult<()> {
+    if !state.settings.read().filter_sets.iter().any(|s| s.id == id) {
+        return Err(AppError::NotFound(format!("Filter set not found: {}", id)));
+    }
+
+    update_settings(state, |settings| {
+        settings.filter_sets.retain(|s| s.id != id);
+        if settings.active_filter_set_id.as_deref() == Some(id) {
+            settings.active_filter_set_id = None;
+        }
+    })
+    .await?;
+    Ok(())
+}
+
+/// Set the active filter set (None to clear)
+pub async fn set_active_filter_set(state: &AppState, id: Option<String>) -> AppResult<()> {
+    if let Some(ref id) = id {
+        if !state
+            .settings
+            .read()
+            .filter_sets
+            .iter()
+            .any(|s| &s.id == id)
+        {
+            return Err(AppError::NotFound(format!("Filter set not found: {}", id)));
+        }
+    }
+
+    update_settings(state, |settings| {
+        settings.active_filter_set_id = id;
+    })
+    .await?;
+    Ok(())
+}
+
+/// Get the active filter set, if any
+pub fn get_active_filter_set(state: &AppState) -> Option<FilterSet> {
+    let settings = state.settings.read();
+    let id = settings.active_filter_set_id.as_ref()?;
+    settings.filter_sets.iter().find(|s| &s.id == id).cloned()
+}
+
+/// Get the filters currently available: the active filter set, or the
+/// active equipment profile's filters when no filter set is active
+pub fn list_filters(state: &AppState) -> Vec<FilterInfo> {
+    if let Some(filter_set) = get_active_filter_set(state) {
+        return filter_set.filters;
+    }
+
+    get_active_equipment_profile(state)
+        .map(|p| p.filters)
+        .unwrap_or_default()
+}
+
+/// List saved observing sites
+pub fn list_sites(state: &AppState) -> Vec<ObservingSite> {
+    state.settings.read().observing_sites.clone()
+}
+
+/// Get observing site by id
+pub fn get_site(state: &AppState, id: &str) -> Option<ObservingSite> {
+    state
+        .settings
+        .read()
+        .observing_sites
+        .iter()
+        .find(|s| s.id == id)
+        .cloned()
+}
+
+/// Add or update an observing site
+pub async fn save_site(state: &AppState, site: ObservingSite) -> AppResult<()> {
+    let errors = site.validate();
+    if !errors.is_empty() {
+        return Err(AppError::Validation(errors));
+    }
+
+    update_settings(state, |settings| {
+        match settings
+            .observing_sites
+            .iter_mut()
+            .find(|s| s.id == site.id)
+        {
+            Some(existing) => *existing = site,
+            None => settings.observing_sites.push(site),
+        }
+    })
+    .await?;
+    Ok(())
+}
+
+/// Delete an observing site
+pub async fn delete_site(state: &AppState, id: &str) -> AppResult<()> {
+    if get_site(state, id).is_none() {
+        return Err(AppError::NotFound(format!(
+            "Observing site not found: {}",
+            id
+        )));
+    }
+
+    update_settings(state, |settings| {
+        settings.observing_sites.retain(|s| s.id != id);
+        if settings.active_site_id.as_deref() == Some(id) {
+            settings.active_site_id = None;
+        }
+    })
+    .await?;
+    Ok(())
+}
+
+/// Add a new observing site, returning it with its assigned id. The first
+/// site added becomes the active site.
+pub async fn add_site(state: &AppState, mut site: ObservingSite) -> AppResult<ObservingSite> {
+    let errors = site.validate();
+    if !errors.is_empty() {
+        return Err(AppError::Validation(errors));
+    }
+
+    if site.id.is_empty() || get_site(state, &site.id).is_some() {
+        site.id = uuid::Uuid::new_v4().to_string();
+    }
+
+    let added = site.clone();
+    update_settings(state, |settings| {
+        if settings.active_site_id.is_none() {
+            settings.active_site_id = Some(site.id.clone());
+        }
+        settings.observing_sites.push(site);
+    })
+    .await?;
+    Ok(added)
+}
+
+/// Set the active observing site (None to clear)
+pub async fn set_active_site(state: &AppState, id: Option<String>) -> AppResult<()> {
+    if let Some(ref id) = id {
+        if get_site(state, id).is_none() {
+            return Err(AppError::NotFound(format!(
+                "Observing site not found: {}",
+                id
+            )));
+        }
+    }
+
+    update_settings(state, |settings| {
+        settings.active_site_id = id;
+    })
+    .await?;
+    Ok(())
+}
+
+/// Get the active observing site, if any
+pub fn get_active_site(state: &AppState) -> Option<ObservingSite> {
+    let settings = state.settings.read();
+    let id = settings.active_site_id.as_ref()?;
+    settings
+        .observing_sites
+        .iter()
+        .find(|s| &s.id == id)
+        .cloned()
+}
+
+/// Resolve the observer location for a command: an explicit site id wins,
+/// then an explicit location, then the active site
+pub fn resolve_location(
+    state: &AppState,
+    location: Option<ObserverLocation>,
+    site_id: Option<String>,
+) -> AppResult<ObserverLocation> {
+    if let Some(id) = site_id {
+        return get_site(state, &id)
+            .map(|site| ObserverLocation::from(&site))
+            .ok_or_else(|| AppError::NotFound(format!("Observing site not found: {}", id)));
+    }
+
+    location
+        .or_else(|| get_active_site(state).map(|site| ObserverLocation::from(&site)))
+        .ok_or_else(|| {
+            AppError::InvalidInput("No location given and no active observing site".to_string())
+        })
+}
+
+/// Site whose horizon profile applies to a command: the given site, or
+/// the active site when no explicit location is given either
+pub fn resolve_site(
+    state: &AppState,
+    location_given: bool,
+    site_id: Option<&str>,
+) -> Option<ObservingSite> {
+    match site_id {
+        Some(id) => get_site(state, id),
+        None if location_given => None,
+        None => get_active_site(state),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_pinned_recent_files_stay() {
+        let mut settings = AppSettings {
+            max_recent_files: 2,
+            pinned_files: vec!["pinned.json".to_string()],
+            ..Default::default()
+        };
+        push_recent_file(&mut settings, "pinned.json");
+        settings
+            .recent_file_metadata
+            .insert("pinned.json".to_string(), RecentFileMetadata::default());
+        for path in ["a.json", "b.json", "c.json"] {
+            push_recent_file(&mut settings, path);
+            settings
+                .recent_file_metadata
+                .insert(path.to_string(), RecentFileMetadata::default());
+        }
+
+        assert_eq!(settings.recent_files, ["c.json", "b.json", "pinned.json"]);
+        let mut described: Vec<&String> = settings.recent_file_metadata.keys().collect();
+        described.sort();
+        assert_eq!(described, ["b.json", "c.json", "pinned.json"]);
+    }
+
+    #[test]
+    fn test_migrate_unversioned_settings() {
+        // A file from before versioning, missing fields that are now required
+        let mut value = json!({
+            "recentFiles": ["a.json"],
+            "theme": "dark",
+        });
+
+        let report = migrate_settings_value(&mut value).unwrap();
+
+        assert_eq!(report.from_version, 0);
+        assert_eq!(report.to_version, SETTINGS_VERSION);
+        assert_eq!(report.applied.len(), MIGRATIONS.len());
+        let settings: AppSettings = serde_json::from_value(value).unwrap();
+        assert_eq!(settings.version, SETTINGS_VERSION);
+        assert_eq!(settings.theme, "dark");
+        assert_eq!(settings.recent_files, vec!["a.json"]);
+        assert_eq!(
+            settings.max_recent_files,
+            AppSettings::default().max_recent_files
+        );
+    }
+
+    #[test]
+    fn test_settings_profile_keeps_machine_settings() {
+        let laptop = AppSettings {
+            theme: "dark".to_string(),
+            recent_files: vec!["laptop.json".to_string()],
+            window_width: Some(1920),
+            ..Default::default()
+        };
+        let profile = create_settings_profile(&laptop).unwrap();
+        assert!(profile.settings.get("recentFiles").is_none());
+        assert!(profile.settings.get("windowWidth").is_none());
+
+        let observatory = AppSettings {
+            recent_files: vec!["observatory.json".to_string()],
+            ..Default::default()
+        };
+        let merged = apply_settings_profile(&observatory, &profile).unwrap();
+        assert_eq!(merged.theme, "dark");
+        assert_eq!(merged.recent_files, vec!["observatory.json"]);
+        assert_eq!(merged.window_width, observatory.window_width);
+
+        // Profiles from before versioning are migrated
+        let old = SettingsProfile {
+            exported_at: Utc::now(),
+            settings: json!({ "language": "de" }),
+        };
+        let merged = apply_settings_profile(&observatory, &old).unwrap();
+        assert_eq!(merged.language, "de");
+        assert_eq!(merged.version, SETTINGS_VERSION);
+    }
+
+    #[test]
+    fn test_migrate_current_and_newer_settings() {
+        let mut value = serde_json::to_value(AppSettings::default()).unwrap();
+        let report = migrate_settings_value(&mut value).unwrap();
+        assert!(report.applied.is_empty());
+        assert!(report.warnings.is_empty());
+
+        let mut value = json!({ "version": SETTINGS_VERSION + 1 });
+        let report = migrate_settings_value(&mut value).unwrap();
+        assert!(report.applied.is_empty());
+        assert_eq!(report.warnings.len(), 1);
+        assert_eq!(value["version"], json!(SETTINGS_VERSION + 1));
+    }
 }
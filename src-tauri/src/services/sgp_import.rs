@@ -0,0 +1,388 @@
+//! Sequence Generator Pro import
+//!
+//! Converts SGP `.sgf` sequence files (Json.NET serialized) into a
+//! `SimpleSequence`. SGP target groups become targets and their events
+//! become exposures. Field names differ between SGP versions, so each
+//! value is looked up through a table of known aliases.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::models::common::{BinningMode, FilterInfo, ImageType, SequenceEntityStatus};
+use crate::models::{Coordinates, SimpleExposure, SimpleSequence, SimpleTarget};
+
+/// SGP import result
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SgpImportResult {
+    pub sequence: SimpleSequence,
+    pub warnings: Vec<String>,
+}
+
+// ============================================================================
+// Mapping Tables
+// ============================================================================
+
+/// SGP numeric event types
+const SGP_EVENT_TYPES: &[(i64, ImageType)] = &[
+    (0, ImageType::Light),
+    (1, ImageType::Dark),
+    (2, ImageType::Bias),
+    (3, ImageType::Flat),
+    (4, ImageType::Snapshot),
+];
+
+/// SGP event type names
+const SGP_EVENT_TYPE_NAMES: &[(&str, ImageType)] = &[
+    ("light", ImageType::Light),
+    ("dark", ImageType::Dark),
+    ("bias", ImageType::Bias),
+    ("flat", ImageType::Flat),
+    ("test", ImageType::Snapshot),
+    ("snapshot", ImageType::Snapshot),
+];
+
+const SEQUENCE_NAME_KEYS: &[&str] = &["strName", "Name", "strSequenceName"];
+const TARGET_GROUP_KEYS: &[&str] = &["arEventGroups", "EventGroups", "Targets"];
+const TARGET_NAME_KEYS: &[&str] = &["strName", "Name", "strTargetName", "TargetName"];
+const TARGET_ENABLED_KEYS: &[&str] = &["bEnabled", "Enabled", "IsEnabled"];
+const REFERENCE_KEYS: &[&str] = &["siReference", "Reference", "Target"];
+const RA_HOURS_KEYS: &[&str] = &["nRightAscension", "nRaHours", "RightAscension", "RA"];
+const DEC_KEYS: &[&str] = &["nDeclination", "nDecDegs", "Declination", "Dec"];
+const POSITION_ANGLE_KEYS: &[&str] = &["nPosAngle", "PositionAngle", "Rotation"];
+const EVENT_KEYS: &[&str] = &["Events", "arEvents", "EventList"];
+const EVENT_ENABLED_KEYS: &[&str] = &["bEnabled", "Enabled", "IsEnabled"];
+const EVENT_TYPE_KEYS: &[&str] = &["nEventType", "EventType", "strEventType", "FrameType"];
+const EXPOSURE_TIME_KEYS: &[&str] = &["nExposureTime", "ExposureTime", "Exposure"];
+const REPEAT_KEYS: &[&str] = &["nRepeat", "Repeat", "Count"];
+const COMPLETE_KEYS: &[&str] = &["nNumComplete", "NumComplete", "Completed"];
+const BINNING_KEYS: &[&str] = &["nBinning", "Binning", "nBin"];
+const GAIN_KEYS: &[&str] = &["nGain", "Gain"];
+const OFFSET_KEYS: &[&str] = &["nOffset", "Offset"];
+const FILTER_NAME_KEYS: &[&str] = &["strFilter", "FilterName", "Filter"];
+const FILTER_INDEX_KEYS: &[&str] = &["nFilterIndex", "FilterIndex"];
+const DITHER_KEYS: &[&str] = &["bDither", "Dither"];
+const FILTER_LIST_KEYS: &[&str] = &["arFilterNames", "FilterNames", "Filters"];
+
+// ============================================================================
+// Helpers
+// ============================================================================
+
+/// Unwrap Json.NET `{"$values": [...]}` collections
+fn as_array(value: &Value) -> Option<&Vec<Value>> {
+    value
+        .as_array()
+        .or_else(|| value.get("$values").and_then(|v| v.as_array()))
+}
+
+fn lookup<'a>(obj: &'a Value, keys: &[&str]) -> Option<&'a Value> {
+    keys.iter()
+        .find_map(|k| obj.get(*k))
+        .filter(|v| !v.is_null())
+}
+
+fn lookup_f64(obj: &Value, keys: &[&str]) -> Option<f64> {
+    lookup(obj, keys).and_then(|v| {
+        v.as_f64()
+            .or_else(|| v.as_str().and_then(|s| s.trim().parse().ok()))
+    })
+}
+
+fn lookup_str(obj: &Value, keys: &[&str]) -> Option<String> {
+    lookup(obj, keys)
+        .and_then(|v| v.as_str())
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+}
+
+fn lookup_bool(obj: &Value, keys: &[&str]) -> Option<bool> {
+    lookup(obj, keys).and_then(|v| v.as_bool())
+}
+
+/// Map an SGP event type (numeric or name) to an image type
+fn map_event_type(value: &Value) -> Option<ImageType> {
+    if let Some(n) = value.as_i64() {
+        return SGP_EVENT_TYPES
+            .iter()
+            .find(|(code, _)| *code == n)
+            .map(|(_, t)| *t);
+    }
+
+    let name = value.as_str()?.trim().to_lowercase();
+    SGP_EVENT_TYPE_NAMES
+        .iter()
+        .find(|(n, _)| *n == name)
+        .map(|(_, t)| *t)
+}
+
+// ============================================================================
+// Import
+// ============================================================================
+
+/// Parse SGP `.sgf` content into a simple sequence
+pub fn import_sgp_sequence(content: &str) -> Result<SgpImportResult, String> {
+    let root: Value =
+        serde_json::from_str(content).map_err(|e| format!("Invalid SGP sequence file: {}", e))?;
+
+    let groups = lookup(&root, TARGET_GROUP_KEYS)
+        .and_then(as_array)
+        .ok_or("SGP sequence has no target groups")?;
+
+    let filter_names: Vec<String> = lookup(&root, FILTER_LIST_KEYS)
+        .and_then(as_array)
+        .map(|names| {
+            names
+                .iter()
+                .filter_map(|n| {
+                    n.as_str()
+                        .map(String::from)
+                        .or_else(|| lookup_str(n, &["strName", "Name"]))
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let mut sequence = SimpleSequence::new(
+        lookup_str(&root, SEQUENCE_NAME_KEYS).unwrap_or_else(|| "SGP Import".to_string()),
+    );
+    sequence.targets.clear();
+
+    let mut warnings = Vec::new();
+
+    for (index, group) in groups.iter().enumerate() {
+        let name =
+            lookup_str(group, TARGET_NAME_KEYS).unwrap_or_else(|| format!("Target {}", index + 1));
+
+        let reference = lookup(group, REFERENCE_KEYS).unwrap_or(group);
+        let coordinates = match (
+            lookup_f64(reference, RA_HOURS_KEYS),
+            lookup_f64(reference, DEC_KEYS),
+        ) {
+            (Some(ra), Some(dec)) if (0.0..24.0).contains(&ra) && (-90.0..=90.0).contains(&dec) => {
+                Coordinates::from_decimal(ra, dec)
+            }
+            _ => {
+                warnings.push(format!("Target '{}': missing or invalid coordinates", name));
+                Coordinates::default()
+            }
+        };
+        let position_angle = lookup_f64(reference, POSITION_ANGLE_KEYS).unwrap_or(0.0);
+
+        let mut target = SimpleTarget {
+            name: name.clone(),
+            target_name: name.clone(),
+            coordinates,
+            position_angle,
+            rotation: position_angle,
+            exposures: Vec::new(),
+            ..Default::default()
+        };
+
+        if lookup_bool(group, TARGET_ENABLED_KEYS) == Some(false) {
+            target.status = SequenceEntityStatus::Disabled;
+        }
+
+        let events = lookup(group, EVENT_KEYS)
+            .and_then(as_array)
+            .cloned()
+            .unwrap_or_default();
+
+        for (event_index, event) in events.iter().enumerate() {
+            let image_type = match lookup(event, EVENT_TYPE_KEYS) {
+                Some(v) => map_event_type(v).unwrap_or_else(|| {
+                    warnings.push(format!(
+                        "Target '{}', event {}: unknown event type {}, using Light",
+                        name,
+                        event_index + 1,
+                        v
+                    ));
+                    ImageType::Light
+                }),
+                None => ImageType::Light,
+            };
+
+            let filter = if let Some(filter_name) = lookup_str(event, FILTER_NAME_KEYS) {
+                let position = filter_names
+                    .iter()
+                    .position(|n| n.eq_ignore_ascii_case(&filter_name));
+                if position.is_none() {
+                    warnings.push(format!(
+                        "Target '{}', event {}: filter '{}' is not in the profile's filter list, check its position",
+                        name,
+                        event_index + 1,
+                        filter_name
+                    ));
+                }
+                Some((filter_name, position.unwrap_or(0) as i32))
+            } else if let Some(idx) = lookup_f64(event, FILTER_INDEX_KEYS) {
+                // SGP filter indexes are 1-based into the profile's filter list
+                let idx = idx as i64;
+                match usize::try_from(idx - 1)
+                    .ok()
+                    .and_then(|i| filter_names.get(i).map(|n| (n.clone(), i as i32)))
+                {
+                    Some(filter) => Some(filter),
+                    None => {
+                        warnings.push(format!(
+                            "Target '{}', event {}: filter index {} is outside the profile's {} filters, filter dropped",
+                            name,
+                            event_index + 1,
+                            idx,
+                            filter_names.len()
+                        ));
+                        None
+                    }
+                }
+            } else {
+                None
+            }
+            .map(|(name, position)| FilterInfo {
+                name,
+                position,
+                ..Default::default()
+            });
+
+            let bin = lookup_f64(event, BINNING_KEYS)
+                .map(|b| b as i32)
+                .unwrap_or(1);
+
+            let mut exposure = SimpleExposure {
+                enabled: lookup_bool(event, EVENT_ENABLED_KEYS).unwrap_or(true),
+                exposure_time: lookup_f64(event, EXPOSURE_TIME_KEYS).unwrap_or(0.0),
+                image_type,
+                filter,
+                binning: BinningMode {
+                    x: bin.max(1),
+                    y: bin.max(1),
+                },
+                gain: lookup_f64(event, GAIN_KEYS).map(|g| g as i32).unwrap_or(-1),
+                offset: lookup_f64(event, OFFSET_KEYS)
+                    .map(|o| o as i32)
+                    .unwrap_or(-1),
+                total_count: lookup_f64(event, REPEAT_KEYS)
+                    .map(|r| r as i32)
+                    .unwrap_or(1),
+                progress_count: lookup_f64(event, COMPLETE_KEYS)
+                    .map(|c| c as i32)
+                    .unwrap_or(0),
+                dither: lookup_bool(event, DITHER_KEYS).unwrap_or(false),
+                ..Default::default()
+            };
+            exposure.progress_count = exposure.progress_count.clamp(0, exposure.total_count);
+
+            if exposure.exposure_time <= 0.0 && image_type != ImageType::Bias {
+                warnings.push(format!(
+                    "Target '{}', event {}: missing exposure time",
+                    name,
+                    event_index + 1
+                ));
+            }
+
+            target.exposures.push(exposure);
+        }
+
+        if target.exposures.is_empty() {
+            warnings.push(format!("Target '{}' has no events", name));
+        }
+
+        sequence.targets.push(target);
+    }
+
+    sequence.selected_target_id = sequence.targets.first().map(|t| t.id.clone());
+    sequence.active_target_id = sequence.selected_target_id.clone();
+
+    Ok(SgpImportResult { sequence, warnings })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = r#"{
+        "$id": "1",
+        "strName": "Autumn Targets",
+        "arFilterNames": { "$values": ["Lum", "Red", "Green", "Blue", "Ha"] },
+        "arEventGroups": {
+            "$values": [
+                {
+                    "strName": "M31",
+                    "siReference": { "nRightAscension": 0.7123, "nDeclination": 41.269, "nPosAngle": 35.0 },
+                    "Events": {
+                        "$values": [
+                            { "nEventType": 0, "nExposureTime": 300, "nRepeat": 20, "nNumComplete": 5, "nFilterIndex": 5, "nBinning": 1, "bEnabled": true },
+                            { "nEventType": "Dark", "nExposureTime": 300, "nRepeat": 10, "strFilter": "Lum", "nBinning": 2 }
+                        ]
+                    }
+                },
+                { "strName": "Unplaced", "Events": [] }
+            ]
+        }
+    }"#;
+
+    #[test]
+    fn test_import_sgp_sequence() {
+        let result = import_sgp_sequence(SAMPLE).unwrap();
+        let sequence = &result.sequence;
+
+        assert_eq!(sequence.title, "Autumn Targets");
+        assert_eq!(sequence.targets.len(), 2);
+
+        let m31 = &sequence.targets[0];
+        assert_eq!(m31.target_name, "M31");
+        assert!((m31.coordinates.ra_to_decimal() - 0.7123).abs() < 1e-4);
+        assert_eq!(m31.position_angle, 35.0);
+        assert_eq!(m31.exposures.len(), 2);
+
+        let light = &m31.exposures[0];
+        assert_eq!(light.image_type, ImageType::Light);
+        assert_eq!(light.total_count, 20);
+        assert_eq!(light.progress_count, 5);
+        assert_eq!(light.filter.as_ref().unwrap().name, "Ha");
+        assert_eq!(light.filter.as_ref().unwrap().position, 4);
+
+        let dark = &m31.exposures[1];
+        assert_eq!(dark.image_type, ImageType::Dark);
+        assert_eq!(dark.binning, BinningMode { x: 2, y: 2 });
+        assert_eq!(dark.filter.as_ref().unwrap().position, 0);
+
+        assert_eq!(result.warnings.len(), 2);
+    }
+
+    #[test]
+    fn test_import_sgp_filter_warnings() {
+        let content = r#"{
+            "strName": "Filters",
+            "arFilterNames": ["Lum", "Red"],
+            "arEventGroups": [{
+                "strName": "M42",
+                "siReference": { "nRightAscension": 5.59, "nDeclination": -5.39 },
+                "Events": [
+                    { "nExposureTime": 60, "nFilterIndex": 0 },
+                    { "nExposureTime": 60, "nFilterIndex": 3 },
+                    { "nExposureTime": 60, "nFilterIndex": 2 },
+                    { "nExposureTime": 60, "strFilter": "OIII" }
+                ]
+            }]
+        }"#;
+        let result = import_sgp_sequence(content).unwrap();
+        let exposures = &result.sequence.targets[0].exposures;
+
+        // Index 0 and an index past the list have no filter
+        assert!(exposures[0].filter.is_none());
+        assert!(exposures[1].filter.is_none());
+        let red = exposures[2].filter.as_ref().unwrap();
+        assert_eq!((red.name.as_str(), red.position), ("Red", 1));
+        assert_eq!(exposures[3].filter.as_ref().unwrap().name, "OIII");
+
+        assert_eq!(result.warnings.len(), 3);
+        assert!(result.warnings[0].contains("filter index 0"));
+        assert!(result.warnings[1].contains("filter index 3"));
+        assert!(result.warnings[2].contains("'OIII'"));
+    }
+
+    #[test]
+    fn test_import_sgp_invalid() {
+        assert!(import_sgp_sequence("not json").is_err());
+        assert!(import_sgp_sequence("{}").is_err());
+    }
+}
@@ -0,0 +1,817 @@
+//! Sequence execution simulator
+//!
+//! Steps through a simple sequence on a simulated clock for a given night,
+//! applying the overheads the plain runtime sum ignores: slews and settling,
+//! centering, autofocus triggers (time, exposure count, filter change and a
+//! linear temperature model), meridian flips and dithering. Waits for a
+//! target to rise are stepped minute by minute.
+
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::models::coordinates::angular_separation;
+use crate::models::{
+    Coordinates, EquipmentProfile, OverheadProfile, SimpleExposure, SimpleSequence, SimpleTarget,
+};
+use crate::services::astronomy::{datetime_to_jd, hour_angle, observed_alt_az, ObserverLocation};
+use crate::services::dark_calendar;
+
+/// Step used while waiting for a target to rise
+const WAIT_STEP_MINUTES: i64 = 1;
+
+/// Longest wait for a target before it is skipped
+const MAX_WAIT_HOURS: i64 = 12;
+
+/// Simulation options. Durations are in seconds.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SimulationOptions {
+    /// Simulation start, defaults to astronomical dusk
+    pub start_time: Option<DateTime<Utc>>,
+    /// Minimum altitude for imaging in degrees
+    pub min_altitude: f64,
+    /// Wait for targets below `min_altitude` instead of imaging them
+    pub wait_for_altitude: bool,
+    /// Overrides the equipment profile's autofocus durations
+    pub autofocus_duration: Option<f64>,
+    pub center_duration: f64,
+    pub rotate_duration: f64,
+    /// Overrides the equipment profile's guiding start delay
+    pub guiding_start_duration: Option<f64>,
+    /// Dither plus guider settle, overrides the equipment profile's
+    pub dither_duration: Option<f64>,
+    /// Flip, re-center and guider restart, overrides the equipment
+    /// profile's pier flip time
+    pub meridian_flip_duration: Option<f64>,
+    pub filter_change_duration: f64,
+    pub unpark_duration: f64,
+    pub park_duration: f64,
+    /// Ambient temperature at the start of the night in °C
+    pub start_temperature: f64,
+    /// Temperature drop over the night in °C per hour
+    pub temperature_drop_per_hour: f64,
+}
+
+impl Default for SimulationOptions {
+    fn default() -> Self {
+        Self {
+            start_time: None,
+            min_altitude: 20.0,
+            wait_for_altitude: true,
+            autofocus_duration: None,
+            center_duration: 60.0,
+            rotate_duration: 30.0,
+            guiding_start_duration: None,
+            dither_duration: None,
+            meridian_flip_duration: None,
+            filter_change_duration: 5.0,
+            unpark_duration: 10.0,
+            park_duration: 60.0,
+            start_temperature: 10.0,
+            temperature_drop_per_hour: 0.5,
+        }
+    }
+}
+
+/// Kind of simulated event
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SimulationEventKind {
+    CoolCamera,
+    Unpark,
+    Wait,
+    Delay,
+    Slew,
+    Center,
+    Rotate,
+    StartGuiding,
+    Autofocus,
+    FilterChange,
+    Exposure,
+    Dither,
+    MeridianFlip,
+    Park,
+    WarmCamera,
+}
+
+/// Simulated event on the timeline
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SimulationEvent {
+    pub kind: SimulationEventKind,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exposure_id: Option<String>,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    pub duration_seconds: f64,
+    pub description: String,
+}
+
+/// Simulation result
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SimulationResult {
+    pub start_time: DateTime<Utc>,
+    /// End time with all simulated overheads
+    pub end_time: DateTime<Utc>,
+    /// End time from the plain runtime sum
+    pub naive_end_time: DateTime<Utc>,
+    pub total_seconds: f64,
+    pub imaging_seconds: f64,
+    pub overhead_seconds: f64,
+    pub waiting_seconds: f64,
+    pub autofocus_count: u32,
+    pub meridian_flip_count: u32,
+    pub dither_count: u32,
+    pub exposures_taken: u32,
+    /// Astronomical dawn ending the night, if any
+    pub dawn: Option<DateTime<Utc>>,
+    pub events: Vec<SimulationEvent>,
+    pub warnings: Vec<String>,
+}
+
+/// Simulation state
+struct Simulation<'a> {
+    location: &'a ObserverLocation,
+    options: &'a SimulationOptions,
+    overheads: OverheadProfile,
+    start: DateTime<Utc>,
+    clock: DateTime<Utc>,
+    events: Vec<SimulationEvent>,
+    warnings: Vec<String>,
+}
+
+impl Simulation<'_> {
+    fn push(
+        &mut self,
+        kind: SimulationEventKind,
+        target: Option<&SimpleTarget>,
+        exposure: Option<&SimpleExposure>,
+        seconds: f64,
+        description: String,
+    ) {
+        if seconds <= 0.0 {
+            return;
+        }
+        let start = self.clock;
+        self.clock = start + Duration::milliseconds((seconds * 1000.0) as i64);
+        self.events.push(SimulationEvent {
+            kind,
+            target_id: target.map(|t| t.id.clone()),
+            exposure_id: exposure.map(|e| e.id.clone()),
+            start,
+            end: self.clock,
+            duration_seconds: seconds,
+            description,
+        });
+    }
+
+    fn altitude(&self, coords: &Coordinates, time: DateTime<Utc>) -> f64 {
+        let jd = datetime_to_jd(time);
+        observed_alt_az(
+            coords.ra_to_decimal(),
+            coords.dec_to_decimal(),
+            self.location,
+            jd,
+        )
+        .0
+    }
+
+    fn hour_angle(&self, coords: &Coordinates, time: DateTime<Utc>) -> f64 {
+        hour_angle(
+            coords.ra_to_decimal(),
+            self.location.longitude,
+            datetime_to_jd(time),
+        )
+    }
+
+    fn temperature(&self) -> f64 {
+        let hours = (self.clock - self.start).num_seconds() as f64 / 3600.0;
+        self.options.start_temperature - self.options.temperature_drop_per_hour * hours
+    }
+
+    /// Step the clock until the target reaches the minimum altitude.
+    /// Returns false when it does not rise within the wait limit.
+    fn wait_for_target(&mut self, target: &SimpleTarget) -> bool {
+        let limit = self.clock + Duration::hours(MAX_WAIT_HOURS);
+        let mut time = self.clock;
+        while self.altitude(&target.coordinates, time) < self.options.min_altitude {
+            time += Duration::minutes(WAIT_STEP_MINUTES);
+            if time > limit {
+                return false;
+            }
+        }
+
+        let seconds = (time - self.clock).num_seconds() as f64;
+        self.push(
+            SimulationEventKind::Wait,
+            Some(target),
+            None,
+            seconds,
+            format!(
+                "Waiting for {} to reach {:.0}°",
+                target.target_name, self.options.min_altitude
+            ),
+        );
+        true
+    }
+}
+
+/// Autofocus bookkeeping for one target
+struct FocusState {
+    last_time: DateTime<Utc>,
+    last_temperature: f64,
+    exposures_since: i32,
+}
+
+/// Simulate running `sequence` on the night of `date`
+pub fn simulate_sequence(
+    sequence: &SimpleSequence,
+    location: &ObserverLocation,
+    date: NaiveDate,
+    profile: Option<&EquipmentProfile>,
+    options: &SimulationOptions,
+) -> SimulationResult {
+    let twilight = dark_calendar::night_twilight(location, date);
+    let dawn = twilight.astronomical_dawn.or(twilight.sunrise);
+    let start = options
+        .start_time
+        .or(twilight.astronomical_dusk)
+        .or(twilight.sunset)
+        // Polar day or night: start at 21:00 local time
+        .unwrap_or_else(|| dark_calendar::night_start(location, date) + Duration::hours(9));
+
+    let mount = profile.map(|p| p.mount.clone()).unwrap_or_default();
+    let mut overheads = profile.map(|p| p.overheads.clone()).unwrap_or_default();
+    if let Some(seconds) = options.autofocus_duration {
+        overheads.autofocus_duration = seconds;
+        overheads.autofocus_durations.clear();
+    }
+    if let Some(seconds) = options.guiding_start_duration {
+        overheads.guiding_start_delay = seconds;
+    }
+    if let Some(seconds) = options.dither_duration {
+        overheads.dither_settle_time = seconds;
+    }
+    let download_time = |exposure: &SimpleExposure| match profile {
+        Some(p) => p.download_time(&exposure.binning),
+        None => sequence.estimated_download_time,
+    };
+
+    let mut sim = Simulation {
+        location,
+        options,
+        overheads,
+        start,
+        clock: start,
+        events: Vec::new(),
+        warnings: Vec::new(),
+    };
+
+    let start_options = &sequence.start_options;
+    if start_options.cool_camera_at_sequence_start {
+        sim.push(
+            SimulationEventKind::CoolCamera,
+            None,
+            None,
+            start_options.cool_camera_duration as f64,
+            format!(
+                "Cooling camera to {:.0}°C",
+                start_options.cool_camera_temperature
+            ),
+        );
+    }
+    if start_options.unpark_mount_at_sequence_start {
+        sim.push(
+            SimulationEventKind::Unpark,
+            None,
+            None,
+            options.unpark_duration,
+            "Unparking mount".to_string(),
+        );
+    }
+
+    let mut previous_coords: Option<Coordinates> = None;
+    let mut exposures_taken = 0u32;
+
+    for target in &sequence.targets {
+        let plan = target.execution_plan();
+        if plan.is_empty() {
+            continue;
+        }
+
+        if options.wait_for_altitude && !sim.wait_for_target(target) {
+            sim.warnings.push(format!(
+                "{} does not reach {:.0}° and was skipped",
+                target.target_name, options.min_altitude
+            ));
+            continue;
+        }
+
+        if target.delay > 0 {
+            sim.push(
+                SimulationEventKind::Delay,
+                Some(target),
+                None,
+                target.delay as f64,
+                "Target delay".to_string(),
+            );
+        }
+
+        if target.slew_to_target {
+            let (distance, duration) = match &previous_coords {
+                Some(prev) => (
+                    angular_separation(prev, &target.coordinates),
+                    mount.slew_time_between(prev, &target.coordinates),
+                ),
+                // From the park position, taken as the zenith
+                None => {
+                    let distance = 90.0 - sim.altitude(&target.coordinates, sim.clock);
+                    (distance, mount.slew_time(distance.max(0.0)))
+                }
+            };
+            sim.push(
+                SimulationEventKind::Slew,
+                Some(target),
+                None,
+                duration,
+                format!("Slew {:.1}° to {}", distance, target.target_name),
+            );
+        }
+        previous_coords = Some(target.coordinates.clone());
+
+        if target.center_target {
+            sim.push(
+                SimulationEventKind::Center,
+                Some(target),
+                None,
+                options.center_duration,
+                format!("Centering {}", target.target_name),
+            );
+        }
+        if target.rotate_target {
+            sim.push(
+                SimulationEventKind::Rotate,
+                Some(target),
+                None,
+                options.rotate_duration,
+                format!("Rotating to {:.1}°", target.position_angle),
+            );
+        }
+        if target.start_guiding {
+            sim.push(
+                SimulationEventKind::StartGuiding,
+                Some(target),
+                None,
+                sim.overheads.guiding_start_delay,
+                "Starting guiding".to_string(),
+            );
+        }
+
+        let mut focus = FocusState {
+            last_time: sim.clock,
+            last_temperature: sim.temperature(),
+            exposures_since: 0,
+        };
+        if target.auto_focus_on_start {
+            let filter = target.exposures[plan[0].exposure_index].filter.as_ref();
+            let filter = filter.map(|f| f.name.as_str());
+            run_autofocus(&mut sim, target, filter, &mut focus, "target start");
+        }
+
+        let mut flipped = !start_options.do_meridian_flip
+            || sim.hour_angle(&target.coordinates, sim.clock) >= 0.0;
+        let mut below_warned = false;
+        let mut current_filter: Option<String> = None;
+
+        for step in plan {
+            let exposure = &target.exposures[step.exposure_index];
+
+            let filter = exposure.filter.as_ref().map(|f| f.name.clone());
+            if current_filter.is_some() && filter != current_filter {
+                sim.push(
+                    SimulationEventKind::FilterChange,
+                    Some(target),
+                    Some(exposure),
+                    options.filter_change_duration,
+                    format!("Filter change to {}", filter.as_deref().unwrap_or("none")),
+                );
+                if target.auto_focus_on_filter_change {
+                    let filter = filter.as_deref();
+                    run_autofocus(&mut sim, target, filter, &mut focus, "filter change");
+                }
+            }
+            current_filter = filter;
+
+            if let Some(reason) = autofocus_due(&sim, target, &focus) {
+                let filter = current_filter.as_deref();
+                run_autofocus(&mut sim, target, filter, &mut focus, reason);
+            }
+
+            if !flipped && sim.hour_angle(&target.coordinates, sim.clock) >= 0.0 {
+                sim.push(
+                    SimulationEventKind::MeridianFlip,
+                    Some(target),
+                    None,
+                    options
+                        .meridian_flip_duration
+                        .unwrap_or(mount.pier_flip_time),
+                    format!("Meridian flip for {}", target.target_name),
+                );
+                flipped = true;
+            }
+
+            if !below_warned && sim.altitude(&target.coordinates, sim.clock) < options.min_altitude
+            {
+                sim.warnings.push(format!(
+                    "{} is below {:.0}° at {}",
+                    target.target_name,
+                    options.min_altitude,
+                    sim.clock.format("%H:%M UTC")
+                ));
+                below_warned = true;
+            }
+
+            sim.push(
+                SimulationEventKind::Exposure,
+                Some(target),
+                Some(exposure),
+                exposure.exposure_time + download_time(exposure),
+                format!(
+                    "{:.0}s {} exposure",
+                    exposure.exposure_time,
+                    exposure
+                        .filter
+                        .as_ref()
+                        .map(|f| f.name.as_str())
+                        .unwrap_or("unfiltered")
+                ),
+            );
+            exposures_taken += 1;
+            focus.exposures_since += 1;
+
+            if step.dither_after {
+                sim.push(
+                    SimulationEventKind::Dither,
+                    Some(target),
+                    Some(exposure),
+                    sim.overheads.dither_settle_time,
+                    "Dither".to_string(),
+                );
+            }
+        }
+    }
+
+    let end_options = &sequence.end_options;
+    if end_options.park_mount_at_sequence_end {
+        sim.push(
+            SimulationEventKind::Park,
+            None,
+            None,
+            options.park_duration,
+            "Parking mount".to_string(),
+        );
+    }
+    if end_options.warm_cam_at_sequence_end {
+        sim.push(
+            SimulationEventKind::WarmCamera,
+            None,
+            None,
+            end_options.warm_camera_duration as f64,
+            "Warming camera".to_string(),
+        );
+    }
+
+    let last_exposure_end = sim
+        .events
+        .iter()
+        .rev()
+        .find(|e| e.kind == SimulationEventKind::Exposure)
+        .map(|e| e.end);
+    if let (Some(dawn), Some(end)) = (dawn, last_exposure_end) {
+        if end > dawn {
+            sim.warnings.push(format!(
+                "Imaging runs {} minutes past astronomical dawn",
+                (end - dawn).num_minutes()
+            ));
+        }
+    }
+
+    let naive_runtime = match profile {
+        Some(p) => {
+            crate::services::calculator::calculate_sequence_runtime_with_profile(sequence, p)
+        }
+        None => sequence.total_runtime(),
+    };
+    let seconds_of = |kind: SimulationEventKind| -> f64 {
+        sim.events
+            .iter()
+            .filter(|e| e.kind == kind)
+            .map(|e| e.duration_seconds)
+            .sum()
+    };
+    let count_of = |kind: SimulationEventKind| -> u32 {
+        sim.events.iter().filter(|e| e.kind == kind).count() as u32
+    };
+
+    let total_seconds = (sim.clock - start).num_milliseconds() as f64 / 1000.0;
+    let imaging_seconds = seconds_of(SimulationEventKind::Exposure);
+    let waiting_seconds = seconds_of(SimulationEventKind::Wait);
+
+    SimulationResult {
+        start_time: start,
+        end_time: sim.clock,
+        naive_end_time: start + Duration::milliseconds((naive_runtime * 1000.0) as i64),
+        total_seconds,
+        imaging_seconds,
+        overhead_seconds: total_seconds - imaging_seconds - waiting_seconds,
+        waiting_seconds,
+        autofocus_count: count_of(SimulationEventKind::Autofocus),
+        meridian_flip_count: count_of(SimulationEventKind::MeridianFlip),
+        dither_count: count_of(SimulationEventKind::Dither),
+        exposures_taken,
+        dawn,
+        events: sim.events,
+        warnings: sim.warnings,
+    }
+}
+
+/// Reason an autofocus run is due before the next exposure, if any
+fn autofocus_due(
+    sim: &Simulation,
+    target: &SimpleTarget,
+    focus: &FocusState,
+) -> Option<&'static str> {
+    let minutes = (sim.clock - focus.last_time).num_seconds() as f64 / 60.0;
+    if target.auto_focus_after_set_time
+        && target.auto_focus_set_time > 0
+        && minutes >= target.auto_focus_set_time as f64
+    {
+        return Some("elapsed time");
+    }
+    if target.auto_focus_after_set_exposures
+        && target.auto_focus_set_exposures > 0
+        && focus.exposures_since >= target.auto_focus_set_exposures
+    {
+        return Some("exposure count");
+    }
+    if target.auto_focus_after_temperature_change
+        && (sim.temperature() - focus.last_temperature).abs()
+            >= target.auto_focus_after_temperature_change_amount
+    {
+        return Some("temperature change");
+    }
+    None
+}
+
+fn run_autofocus(
+    sim: &mut Simulation,
+    target: &SimpleTarget,
+    filter: Option<&str>,
+    focus: &mut FocusState,
+    reason: &str,
+) {
+    let seconds = sim.overheads.autofocus_time(filter);
+    sim.push(
+        SimulationEventKind::Autofocus,
+        Some(target),
+        None,
+        seconds,
+        format!("Autofocus ({})", reason),
+    );
+    focus.last_time = sim.clock;
+    focus.last_temperature = sim.temperature();
+    focus.exposures_since = 0;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{FilterInfo, SequenceMode};
+
+    fn test_location() -> ObserverLocation {
+        ObserverLocation {
+            latitude: 40.0,
+            longitude: -74.0,
+            elevation: 0.0,
+            timezone_offset: -5,
+            sky_brightness: None,
+            corrections: Default::default(),
+        }
+    }
+
+    fn test_date() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 10, 15).unwrap()
+    }
+
+    fn m31_sequence() -> SimpleSequence {
+        let mut seq = SimpleSequence::new("Simulation");
+        let target = &mut seq.targets[0];
+        target.target_name = "M31".to_string();
+        target.coordinates = Coordinates::from_decimal(0.712, 41.27);
+        seq
+    }
+
+    #[test]
+    fn test_simulation_adds_overheads() {
+        let seq = m31_sequence();
+        let result = simulate_sequence(
+            &seq,
+            &test_location(),
+            test_date(),
+            None,
+            &SimulationOptions::default(),
+        );
+
+        assert_eq!(result.exposures_taken, 10);
+        assert_eq!(result.autofocus_count, 1);
+        assert!(result.end_time > result.naive_end_time);
+        assert!(result.overhead_seconds > 0.0);
+        assert!(result
+            .events
+            .windows(2)
+            .all(|pair| pair[0].end <= pair[1].start));
+    }
+
+    #[test]
+    fn test_simulation_night_west_of_greenwich() {
+        let result = simulate_sequence(
+            &m31_sequence(),
+            &test_location(),
+            test_date(),
+            None,
+            &SimulationOptions::default(),
+        );
+
+        // Dusk on the local evening falls after 00:00 UTC, dawn the next morning
+        let midnight = DateTime::<Utc>::from_naive_utc_and_offset(
+            test_date()
+                .succ_opt()
+                .unwrap()
+                .and_hms_opt(0, 0, 0)
+                .unwrap(),
+            Utc,
+        );
+        let dawn = result.dawn.unwrap();
+        assert!(result.start_time > midnight - Duration::hours(1));
+        assert!(result.start_time < midnight + Duration::hours(1));
+        assert!(dawn > midnight + Duration::hours(9));
+        assert!(dawn < midnight + Duration::hours(11));
+    }
+
+    #[test]
+    fn test_simulation_dithers_and_filter_changes() {
+        let mut seq = m31_sequence();
+        let target = &mut seq.targets[0];
+        target.mode = SequenceMode::Rotate;
+        target.auto_focus_on_filter_change = true;
+        target.auto_focus_on_start = false;
+        target.exposures = ["L", "R"]
+            .iter()
+            .map(|name| SimpleExposure {
+                filter: Some(FilterInfo {
+                    name: name.to_string(),
+                    ..Default::default()
+                }),
+                total_count: 4,
+                dither: true,
+                dither_every: 2,
+                ..Default::default()
+            })
+            .collect();
+
+        let result = simulate_sequence(
+            &seq,
+            &test_location(),
+            test_date(),
+            None,
+            &SimulationOptions::default(),
+        );
+
+        let filter_changes = result
+            .events
+            .iter()
+            .filter(|e| e.kind == SimulationEventKind::FilterChange)
+            .count();
+        assert_eq!(result.exposures_taken, 8);
+        assert_eq!(filter_changes, 7);
+        assert_eq!(result.autofocus_count, 7);
+        assert_eq!(result.dither_count, 4);
+    }
+
+    #[test]
+    fn test_simulation_uses_profile_overheads() {
+        let mut seq = m31_sequence();
+        let target = &mut seq.targets[0];
+        target.start_guiding = true;
+        target.exposures[0].dither = true;
+        target.exposures[0].filter = Some(FilterInfo {
+            name: "Ha".to_string(),
+            ..Default::default()
+        });
+        let mut profile = EquipmentProfile::default();
+        profile.overheads.dither_settle_time = 25.0;
+        profile.overheads.guiding_start_delay = 45.0;
+        profile.overheads.autofocus_durations = vec![crate::models::FilterAutofocusTime {
+            filter: "Ha".to_string(),
+            seconds: 240.0,
+        }];
+
+        let duration = |result: &SimulationResult, kind| {
+            result
+                .events
+                .iter()
+                .find(|e| e.kind == kind)
+                .map(|e| e.duration_seconds)
+        };
+        let result = simulate_sequence(
+            &seq,
+            &test_location(),
+            test_date(),
+            Some(&profile),
+            &SimulationOptions::default(),
+        );
+        assert_eq!(
+            duration(&result, SimulationEventKind::Autofocus),
+            Some(240.0)
+        );
+        assert_eq!(duration(&result, SimulationEventKind::Dither), Some(25.0));
+        assert_eq!(
+            duration(&result, SimulationEventKind::StartGuiding),
+            Some(45.0)
+        );
+
+        let options = SimulationOptions {
+            autofocus_duration: Some(60.0),
+            ..Default::default()
+        };
+        let result = simulate_sequence(
+            &seq,
+            &test_location(),
+            test_date(),
+            Some(&profile),
+            &options,
+        );
+        assert_eq!(
+            duration(&result, SimulationEventKind::Autofocus),
+            Some(60.0)
+        );
+    }
+
+    #[test]
+    fn test_simulation_meridian_flip() {
+        // M31 transits around 04:40 UTC from New York in mid October
+        let mut seq = m31_sequence();
+        seq.targets[0].exposures[0].total_count = 60;
+        seq.targets[0].exposures[0].exposure_time = 300.0;
+        let options = SimulationOptions {
+            start_time: Some(
+                DateTime::parse_from_rfc3339("2024-10-16T02:00:00Z")
+                    .unwrap()
+                    .with_timezone(&Utc),
+            ),
+            ..Default::default()
+        };
+
+        let result = simulate_sequence(&seq, &test_location(), test_date(), None, &options);
+        assert_eq!(result.meridian_flip_count, 1);
+    }
+
+    #[test]
+    fn test_simulation_temperature_autofocus() {
+        let mut seq = m31_sequence();
+        let target = &mut seq.targets[0];
+        target.auto_focus_after_temperature_change = true;
+        target.auto_focus_after_temperature_change_amount = 1.0;
+        target.exposures[0].total_count = 60;
+        target.exposures[0].exposure_time = 300.0;
+        seq.start_options.do_meridian_flip = false;
+
+        // About five hours of imaging with a 1°C/h drop
+        let options = SimulationOptions {
+            temperature_drop_per_hour: 1.0,
+            ..Default::default()
+        };
+        let result = simulate_sequence(&seq, &test_location(), test_date(), None, &options);
+        assert!(result.autofocus_count >= 5);
+    }
+
+    #[test]
+    fn test_simulation_skips_target_never_visible() {
+        let mut seq = m31_sequence();
+        seq.targets[0].target_name = "Southern".to_string();
+        seq.targets[0].coordinates = Coordinates::from_decimal(0.0, -80.0);
+
+        let result = simulate_sequence(
+            &seq,
+            &test_location(),
+            test_date(),
+            None,
+            &SimulationOptions::default(),
+        );
+        assert_eq!(result.exposures_taken, 0);
+        assert!(result.warnings.iter().any(|w| w.contains("Southern")));
+    }
+}
@@ -0,0 +1,271 @@
+//! Slew model calibration
+//!
+//! Fits a mount's axis rates, acceleration and settle time to a log of
+//! slews it actually made. The fit minimizes the squared error of the
+//! predicted slew times by coordinate descent, starting from a straight
+//! line through the durations against the longer axis movement.
+
+use serde::{Deserialize, Serialize};
+
+use crate::models::coordinates::axis_separation;
+use crate::models::{Coordinates, MountProfile};
+
+/// Slews needed for a fit
+const MIN_RECORDS: usize = 3;
+
+const MAX_ITERATIONS: usize = 2000;
+
+/// Lowest rate and acceleration the fit may reach
+const MIN_RATE: f64 = 0.05;
+
+/// Relative RMS error above which the fit gets a warning
+const NOISY_FIT_RATIO: f64 = 0.15;
+
+/// One slew from the log
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SlewRecord {
+    /// Right ascension axis movement in degrees
+    pub ra_distance: f64,
+    /// Declination axis movement in degrees
+    pub dec_distance: f64,
+    /// Slew plus settle, in seconds
+    pub duration: f64,
+}
+
+impl SlewRecord {
+    pub fn between(from: &Coordinates, to: &Coordinates, duration: f64) -> Self {
+        let (ra_distance, dec_distance) = axis_separation(from, to);
+        Self {
+            ra_distance,
+            dec_distance,
+            duration,
+        }
+    }
+}
+
+/// Fitted mount model
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SlewCalibration {
+    /// The input mount with the fitted rates, acceleration and settle time
+    pub mount: MountProfile,
+    pub samples: usize,
+    /// Root mean square error of the fitted slew times, in seconds
+    pub rms_error: f64,
+    pub warnings: Vec<String>,
+}
+
+fn squared_error(mount: &MountProfile, records: &[SlewRecord]) -> f64 {
+    records
+        .iter()
+        .map(|r| (mount.slew_time_axes(r.ra_distance, r.dec_distance) - r.duration).powi(2))
+        .sum()
+}
+
+/// Least squares line `duration = intercept + slope * distance`
+fn fit_line(points: &[(f64, f64)]) -> Option<(f64, f64)> {
+    let n = points.len() as f64;
+    let mean_x = points.iter().map(|p| p.0).sum::<f64>() / n;
+    let mean_y = points.iter().map(|p| p.1).sum::<f64>() / n;
+    let var_x: f64 = points.iter().map(|p| (p.0 - mean_x).powi(2)).sum();
+    if var_x <= f64::EPSILON {
+        return None;
+    }
+    let cov: f64 = points.iter().map(|p| (p.0 - mean_x) * (p.1 - mean_y)).sum();
+    let slope = cov / var_x;
+    Some((mean_y - slope * mean_x, slope))
+}
+
+fn round_to(value: f64, places: i32) -> f64 {
+    let factor = 10f64.powi(places);
+    (value * factor).round() / factor
+}
+
+/// Fit `mount`'s slew model to logged slews
+pub fn calibrate_slew_model(
+    mount: &MountProfile,
+    records: &[SlewRecord],
+) -> Result<SlewCalibration, String> {
+    let records: Vec<SlewRecord> = records
+        .iter()
+        .filter(|r| r.duration > 0.0 && r.ra_distance.max(r.dec_distance) > 0.0)
+        .cloned()
+        .collect();
+    if records.len() < MIN_RECORDS {
+        return Err(format!(
+            "At least {} slews with a distance and duration are needed",
+            MIN_RECORDS
+        ));
+    }
+
+    let mut warnings = Vec::new();
+    let ra_led = records.iter().any(|r| r.ra_distance > r.dec_distance);
+    let dec_led = records.iter().any(|r| r.dec_distance > r.ra_distance);
+    // Without slews led by one axis its rate cannot be told apart
+    let separate_rates = ra_led && dec_led;
+    if !separate_rates {
+        warnings.push(format!(
+            "No slews mainly in {}; both axes get the same rate",
+            if ra_led {
+                "declination"
+            } else {
+                "right ascension"
+            }
+        ));
+    }
+
+    let points: Vec<(f64, f64)> = records
+        .iter()
+        .map(|r| (r.ra_distance.max(r.dec_distance), r.duration))
+        .collect();
+    let (intercept, slope) = fit_line(&points)
+        .filter(|(_, slope)| *slope > 0.0)
+        .unwrap_or((mount.settle_time, 1.0 / mount.ra_rate()));
+    let rate = (1.0 / slope).max(MIN_RATE);
+
+    // Parameters: RA rate, Dec rate, acceleration, settle time
+    let mut params = [rate, rate, rate, intercept.max(0.0)];
+    let mut steps = [rate / 2.0, rate / 2.0, rate / 2.0, 1.0];
+    let minimums = [MIN_RATE, MIN_RATE, MIN_RATE, 0.0];
+    let model = |params: &[f64; 4]| MountProfile {
+        ra_slew_rate: Some(params[0]),
+        dec_slew_rate: Some(if separate_rates { params[1] } else { params[0] }),
+        acceleration: params[2],
+        settle_time: params[3],
+        ..mount.clone()
+    };
+
+    let mut best = squared_error(&model(&params), &records);
+    for _ in 0..MAX_ITERATIONS {
+        let mut improved = false;
+        for i in 0..params.len() {
+            if i == 1 && !separate_rates {
+                continue;
+            }
+            for direction in [1.0, -1.0] {
+                let mut candidate = params;
+                candidate[i] = (candidate[i] + direction * steps[i]).max(minimums[i]);
+                let error = squared_error(&model(&candidate), &records);
+                if error < best {
+                    best = error;
+                    params = candidate;
+                    improved = true;
+                    break;
+                }
+            }
+        }
+        if !improved {
+            steps.iter_mut().for_each(|step| *step /= 2.0);
+            if steps.iter().all(|step| *step < 1e-4) {
+                break;
+            }
+        }
+    }
+
+    let fitted = model(&params);
+    let rms_error = (best / records.len() as f64).sqrt();
+    let mean_duration = records.iter().map(|r| r.duration).sum::<f64>() / records.len() as f64;
+    if rms_error > NOISY_FIT_RATIO * mean_duration {
+        warnings.push(
+            "Logged slews vary a lot from the model; check for pier flips or interrupted slews"
+                .to_string(),
+        );
+    }
+
+    let ra_rate = round_to(fitted.ra_rate(), 2);
+    let dec_rate = round_to(fitted.dec_rate(), 2);
+    Ok(SlewCalibration {
+        mount: MountProfile {
+            slew_rate: ra_rate.min(dec_rate),
+            ra_slew_rate: Some(ra_rate),
+            dec_slew_rate: Some(dec_rate),
+            acceleration: round_to(fitted.acceleration, 2),
+            settle_time: round_to(fitted.settle_time, 1),
+            ..fitted
+        },
+        samples: records.len(),
+        rms_error: round_to(rms_error, 2),
+        warnings,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn records_from(mount: &MountProfile, slews: &[(f64, f64)]) -> Vec<SlewRecord> {
+        slews
+            .iter()
+            .map(|&(ra_distance, dec_distance)| SlewRecord {
+                ra_distance,
+                dec_distance,
+                duration: mount.slew_time_axes(ra_distance, dec_distance),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_calibrate_recovers_mount_model() {
+        let actual = MountProfile {
+            ra_slew_rate: Some(4.0),
+            dec_slew_rate: Some(2.5),
+            acceleration: 1.5,
+            settle_time: 8.0,
+            ..Default::default()
+        };
+        let slews = [
+            (60.0, 10.0),
+            (120.0, 5.0),
+            (25.0, 2.0),
+            (5.0, 40.0),
+            (10.0, 70.0),
+            (2.0, 15.0),
+            (3.0, 1.0),
+            (90.0, 90.0),
+        ];
+        let calibration =
+            calibrate_slew_model(&MountProfile::default(), &records_from(&actual, &slews)).unwrap();
+
+        let mount = &calibration.mount;
+        assert_eq!(calibration.samples, 8);
+        assert!(calibration.rms_error < 0.5);
+        assert!(calibration.warnings.is_empty());
+        assert!((mount.ra_rate() - 4.0).abs() < 0.2);
+        assert!((mount.dec_rate() - 2.5).abs() < 0.2);
+        assert!((mount.settle_time - 8.0).abs() < 1.0);
+        assert_eq!(mount.pier_flip_time, MountProfile::default().pier_flip_time);
+    }
+
+    #[test]
+    fn test_calibrate_single_axis_log() {
+        let actual = MountProfile {
+            slew_rate: 2.0,
+            settle_time: 3.0,
+            ..Default::default()
+        };
+        let records = records_from(&actual, &[(20.0, 1.0), (40.0, 5.0), (80.0, 0.0)]);
+        let calibration = calibrate_slew_model(&MountProfile::default(), &records).unwrap();
+        assert_eq!(calibration.warnings.len(), 1);
+        assert_eq!(calibration.mount.ra_rate(), calibration.mount.dec_rate());
+        assert!((calibration.mount.ra_rate() - 2.0).abs() < 0.2);
+    }
+
+    #[test]
+    fn test_calibrate_needs_records() {
+        let records = vec![
+            SlewRecord::between(
+                &Coordinates::from_decimal(1.0, 10.0),
+                &Coordinates::from_decimal(2.0, 20.0),
+                12.0,
+            ),
+            SlewRecord {
+                ra_distance: 10.0,
+                dec_distance: 0.0,
+                duration: 0.0,
+            },
+        ];
+        assert_eq!(records[0].ra_distance, 15.0);
+        assert!(calibrate_slew_model(&MountProfile::default(), &records).is_err());
+    }
+}
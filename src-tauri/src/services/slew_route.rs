@@ -0,0 +1,281 @@
+//! Slew route solver
+//!
+//! Orders targets for the least total slew time: an open travelling
+//! salesman path from a fixed first target. The nearest-neighbor route is
+//! the baseline. Several independently seeded simulated annealing runs
+//! over 2-opt moves share a time budget in parallel, each finished by a
+//! full 2-opt descent, and the shortest route wins.
+
+use std::time::{Duration, Instant};
+
+use rayon::prelude::*;
+
+use crate::models::{Coordinates, MountProfile};
+
+/// Time the annealing runs may take together
+pub const DEFAULT_TIME_BUDGET: Duration = Duration::from_millis(200);
+
+/// Moves per annealing run and target pair, before the time budget
+const MOVES_PER_PAIR: usize = 2000;
+const MAX_MOVES: usize = 2_000_000;
+
+/// Moves between deadline checks
+const DEADLINE_CHECK_INTERVAL: usize = 256;
+
+/// Final temperature relative to the starting one
+const COOLING_RANGE: f64 = 1e-3;
+
+/// Ordered route through the targets
+#[derive(Debug, Clone)]
+pub struct SlewRoute {
+    /// Target indices in visiting order
+    pub order: Vec<usize>,
+    pub cost: f64,
+    /// Cost of the nearest-neighbor route
+    pub greedy_cost: f64,
+}
+
+impl SlewRoute {
+    /// How much shorter the route is than the nearest-neighbor one
+    pub fn improvement_percent(&self) -> f64 {
+        if self.greedy_cost <= 0.0 {
+            return 0.0;
+        }
+        ((self.greedy_cost - self.cost) / self.greedy_cost * 100.0).max(0.0)
+    }
+}
+
+/// Slew times between every pair of positions
+pub fn slew_cost_matrix(coordinates: &[&Coordinates], mount: &MountProfile) -> Vec<Vec<f64>> {
+    coordinates
+        .par_iter()
+        .map(|from| {
+            coordinates
+                .iter()
+                .map(|to| mount.slew_time_between(from, to))
+                .collect()
+        })
+        .collect()
+}
+
+fn route_cost(costs: &[Vec<f64>], order: &[usize]) -> f64 {
+    order.windows(2).map(|pair| costs[pair[0]][pair[1]]).sum()
+}
+
+/// Route that always moves on to the closest unvisited target
+pub fn nearest_neighbor_route(costs: &[Vec<f64>], start: usize) -> Vec<usize> {
+    let mut order = vec![start];
+    let mut visited = vec![false; costs.len()];
+    visited[start] = true;
+
+    while order.len() < costs.len() {
+        let last = order[order.len() - 1];
+        let next = (0..costs.len())
+            .filter(|&i| !visited[i])
+            .min_by(|&a, &b| costs[last][a].total_cmp(&costs[last][b]))
+            .unwrap();
+        visited[next] = true;
+        order.push(next);
+    }
+
+    order
+}
+
+/// Cost change from reversing `order[i..=j]`, with `i >= 1`
+fn reversal_delta(costs: &[Vec<f64>], order: &[usize], i: usize, j: usize) -> f64 {
+    let before = order[i - 1];
+    let mut delta = costs[before][order[j]] - costs[before][order[i]];
+    if let Some(&after) = order.get(j + 1) {
+        delta += costs[order[i]][after] - costs[order[j]][after];
+    }
+    delta
+}
+
+/// Reverse segments while that shortens the route. The first target stays
+/// in place.
+fn two_opt(costs: &[Vec<f64>], order: &mut [usize]) {
+    let n = order.len();
+    let mut improved = true;
+    while improved {
+        improved = false;
+        for i in 1..n.saturating_sub(1) {
+            for j in i + 1..n {
+                if reversal_delta(costs, order, i, j) < -1e-9 {
+                    order[i..=j].reverse();
+                    improved = true;
+                }
+            }
+        }
+    }
+}
+
+/// xorshift64* generator, enough for picking moves and sampling
+/// simulated sessions
+pub(crate) struct Rng(u64);
+
+impl Rng {
+    pub(crate) fn new(seed: u64) -> Self {
+        Self(seed.wrapping_mul(0x9E37_79B9_7F4A_7C15) | 1)
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    /// Uniform in `[0, 1)`
+    pub(crate) fn unit(&mut self) -> f64 {
+        (self.next() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Uniform in `low..high`
+    fn range(&mut self, low: usize, high: usize) -> usize {
+        low + (self.next() % (high - low) as u64) as usize
+    }
+
+    /// Standard normal, by the Box-Muller transform
+    pub(crate) fn normal(&mut self) -> f64 {
+        let u = 1.0 - self.unit();
+        let v = self.unit();
+        (-2.0 * u.ln()).sqrt() * (2.0 * std::f64::consts::PI * v).cos()
+    }
+}
+
+fn anneal(costs: &[Vec<f64>], initial: &[usize], seed: u64, deadline: Instant) -> Vec<usize> {
+    let n = initial.len();
+    let mut rng = Rng::new(seed);
+    let mut current = initial.to_vec();
+    let mut current_cost = route_cost(costs, &current);
+    let mut best = current.clone();
+    let mut best_cost = current_cost;
+
+    let moves = (MOVES_PER_PAIR * n * n).min(MAX_MOVES);
+    // Start by accepting moves that lengthen the route by about half an
+    // average slew
+    let mut temperature = (current_cost / (n - 1) as f64 * 0.5).max(1e-6);
+    let cooling = COOLING_RANGE.powf(1.0 / moves as f64);
+
+    for step in 0..moves {
+        if step % DEADLINE_CHECK_INTERVAL == 0 && Instant::now() >= deadline {
+            break;
+        }
+        let a = rng.range(1, n);
+        let b = rng.range(1, n);
+        if a != b {
+            let (i, j) = (a.min(b), a.max(b));
+            let delta = reversal_delta(costs, &current, i, j);
+            if delta < 0.0 || rng.unit() < (-delta / temperature).exp() {
+                current[i..=j].reverse();
+                current_cost += delta;
+                if current_cost < best_cost - 1e-9 {
+                    best_cost = current_cost;
+                    best.clone_from(&current);
+                }
+            }
+        }
+        temperature *= cooling;
+    }
+
+    two_opt(costs, &mut best);
+    best
+}
+
+/// Shortest route found from `start` through every target within the
+/// time budget. Never worse than the nearest-neighbor route.
+pub fn solve_slew_route(costs: &[Vec<f64>], start: usize, budget: Duration) -> SlewRoute {
+    if costs.is_empty() {
+        return SlewRoute {
+            order: Vec::new(),
+            cost: 0.0,
+            greedy_cost: 0.0,
+        };
+    }
+
+    let greedy = nearest_neighbor_route(costs, start);
+    let greedy_cost = route_cost(costs, &greedy);
+
+    let mut polished = greedy.clone();
+    two_opt(costs, &mut polished);
+    let mut candidates = vec![polished];
+
+    if costs.len() > 3 {
+        let deadline = Instant::now() + budget;
+        let runs = rayon::current_num_threads().max(1) as u64;
+        candidates.par_extend(
+            (0..runs)
+                .into_par_iter()
+                .map(|seed| anneal(costs, &greedy, seed + 1, deadline)),
+        );
+    }
+
+    let order = candidates
+        .into_iter()
+        .min_by(|a, b| route_cost(costs, a).total_cmp(&route_cost(costs, b)))
+        .unwrap();
+    SlewRoute {
+        cost: route_cost(costs, &order),
+        order,
+        greedy_cost,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line_costs(positions: &[f64]) -> Vec<Vec<f64>> {
+        positions
+            .iter()
+            .map(|a| positions.iter().map(|b| (a - b).abs()).collect())
+            .collect()
+    }
+
+    fn is_permutation(order: &[usize], n: usize) -> bool {
+        let mut sorted = order.to_vec();
+        sorted.sort_unstable();
+        sorted == (0..n).collect::<Vec<_>>()
+    }
+
+    #[test]
+    fn test_beats_nearest_neighbor_on_a_line() {
+        // Nearest neighbor goes 0 → 2 → -3 → 7.5 for 17.5; -3 first is 13.5
+        let costs = line_costs(&[0.0, 2.0, -3.0, 7.5]);
+        assert_eq!(nearest_neighbor_route(&costs, 0), [0, 1, 2, 3]);
+
+        let route = solve_slew_route(&costs, 0, DEFAULT_TIME_BUDGET);
+        assert_eq!(route.order, [0, 2, 1, 3]);
+        assert_eq!(route.greedy_cost, 17.5);
+        assert_eq!(route.cost, 13.5);
+        assert!((route.improvement_percent() - 4.0 / 17.5 * 100.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_solve_many_targets() {
+        let mut rng = Rng::new(42);
+        let coordinates: Vec<Coordinates> = (0..30)
+            .map(|_| Coordinates::from_decimal(rng.unit() * 24.0, rng.unit() * 120.0 - 40.0))
+            .collect();
+        let refs: Vec<&Coordinates> = coordinates.iter().collect();
+        let costs = slew_cost_matrix(&refs, &MountProfile::default());
+        assert_eq!(costs.len(), 30);
+        assert_eq!(costs[3][7], costs[7][3]);
+
+        let route = solve_slew_route(&costs, 5, DEFAULT_TIME_BUDGET);
+        assert_eq!(route.order[0], 5);
+        assert!(is_permutation(&route.order, 30));
+        assert!(route.cost <= route.greedy_cost);
+        assert!((route.cost - route_cost(&costs, &route.order)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_small_routes() {
+        assert!(solve_slew_route(&[], 0, DEFAULT_TIME_BUDGET)
+            .order
+            .is_empty());
+        let route = solve_slew_route(&line_costs(&[0.0, 2.0]), 1, DEFAULT_TIME_BUDGET);
+        assert_eq!(route.order, [1, 0]);
+        assert_eq!(route.improvement_percent(), 0.0);
+    }
+}
@@ -0,0 +1,79 @@
+//! Identifiers for sequence entities
+//!
+//! New ids are random UUIDs. Stable ids are derived from a seed instead,
+//! so the same entity gets the same id every time a file is read and
+//! files stay diffable across import and export. Both can be written in
+//! the GUID formats .NET understands; NINA itself writes the hyphenated
+//! form.
+
+use serde::{Deserialize, Serialize};
+use uuid::{Builder, Uuid};
+
+/// Text form of an id, after the .NET GUID format specifiers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum IdFormat {
+    /// `xxxxxxxx-xxxx-xxxx-xxxx-xxxxxxxxxxxx` (.NET `D`), as NINA writes GUIDs
+    #[default]
+    Hyphenated,
+    /// 32 digits without hyphens (.NET `N`)
+    Simple,
+    /// Hyphenated in braces (.NET `B`)
+    Braced,
+    /// Hyphenated in parentheses (.NET `P`)
+    Parenthesized,
+}
+
+impl IdFormat {
+    pub fn format(self, id: Uuid) -> String {
+        match self {
+            IdFormat::Hyphenated => id.hyphenated().to_string(),
+            IdFormat::Simple => id.simple().to_string(),
+            IdFormat::Braced => id.braced().to_string(),
+            IdFormat::Parenthesized => format!("({})", id.hyphenated()),
+        }
+    }
+}
+
+/// FNV-1a over 128 bits; fixed, unlike the std hashers, so ids derived
+/// from a seed never change between builds
+fn fnv1a_128(bytes: &[u8]) -> u128 {
+    const OFFSET: u128 = 0x6c62272e07bb014262b821756295c58d;
+    const PRIME: u128 = 0x0000000001000000000000000000013b;
+    bytes.iter().fold(OFFSET, |hash, &b| {
+        (hash ^ u128::from(b)).wrapping_mul(PRIME)
+    })
+}
+
+/// Id derived from `seed`: the same seed always gives the same id
+pub fn stable_id(seed: &str) -> Uuid {
+    Builder::from_custom_bytes(fnv1a_128(seed.as_bytes()).to_be_bytes()).into_uuid()
+}
+
+/// New id in `format`, derived from `seed` when given and random otherwise
+pub fn generate_id(format: IdFormat, seed: Option<&str>) -> String {
+    let id = match seed {
+        Some(seed) => stable_id(seed),
+        None => Uuid::new_v4(),
+    };
+    format.format(id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stable_id() {
+        let id = stable_id("target/0");
+        assert_eq!(id, stable_id("target/0"));
+        assert_ne!(id, stable_id("target/1"));
+        assert_eq!(id.get_version_num(), 8);
+
+        let text = generate_id(IdFormat::Braced, Some("target/0"));
+        assert_eq!(text, format!("{{{}}}", id));
+        assert_eq!(Uuid::parse_str(&text).unwrap(), id);
+        assert_eq!(generate_id(IdFormat::Simple, None).len(), 32);
+        assert!(generate_id(IdFormat::Parenthesized, None).starts_with('('));
+    }
+}
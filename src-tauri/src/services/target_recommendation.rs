@@ -0,0 +1,345 @@
+//! Tonight's target recommendations
+//!
+//! Ranks the built-in deep-sky catalog for one night by how much of the
+//! darkness each object spends above the minimum altitude, how high it
+//! gets, how much the Moon interferes and how well it fills the field of
+//! view. Nights without astronomical darkness fall back to nautical
+//! darkness so short summer nights still get suggestions.
+
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::models::coordinates::angular_separation;
+use crate::models::{Coordinates, EquipmentProfile, SimpleTarget};
+use crate::services::astronomy::{
+    datetime_to_jd, moon_illumination, moon_position, observed_alt_az, ra_dec_to_alt_az,
+    sun_altitude, ObserverLocation,
+};
+use crate::services::dark_calendar::night_start;
+use crate::services::dso_catalog::{search_catalog, CatalogFilter, DeepSkyObject};
+
+/// Sampling step through the night
+const SAMPLE_MINUTES: i64 = 10;
+
+const ASTRONOMICAL_DARK_ALTITUDE: f64 = -18.0;
+const NAUTICAL_DARK_ALTITUDE: f64 = -12.0;
+
+/// Altitude above which the altitude score is full
+const FULL_SCORE_ALTITUDE: f64 = 70.0;
+
+/// Moon separation beyond which the Moon no longer costs score
+const MOON_CLEAR_SEPARATION: f64 = 90.0;
+
+// Score weights, summing to 100
+const VISIBILITY_WEIGHT: f64 = 40.0;
+const ALTITUDE_WEIGHT: f64 = 25.0;
+const MOON_WEIGHT: f64 = 20.0;
+const FOV_WEIGHT: f64 = 15.0;
+
+/// Camera field of view in arcminutes
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FieldOfView {
+    pub width: f64,
+    pub height: f64,
+}
+
+impl FieldOfView {
+    /// Field of view of the profile's camera and telescope, if the sensor
+    /// and focal length are known
+    pub fn from_profile(profile: &EquipmentProfile) -> Option<Self> {
+        let scale = profile.image_scale();
+        if scale <= 0.0 || profile.camera.width == 0 || profile.camera.height == 0 {
+            return None;
+        }
+        Some(Self {
+            width: scale * profile.camera.width as f64 / 60.0,
+            height: scale * profile.camera.height as f64 / 60.0,
+        })
+    }
+
+    /// Fraction of the short side of the field the object spans
+    pub fn fill(&self, size_arcmin: f64) -> f64 {
+        size_arcmin / self.width.min(self.height)
+    }
+}
+
+/// Score from 0 to 1 for how well an object fills the field: objects
+/// spanning a quarter to nine tenths of it frame well, smaller ones are
+/// lost and larger ones need a mosaic
+fn fov_fit(fill: f64) -> f64 {
+    if fill < 0.25 {
+        fill / 0.25
+    } else if fill <= 0.9 {
+        1.0
+    } else {
+        0.9 / fill
+    }
+}
+
+/// Catalog object suggested for the night
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TargetRecommendation {
+    pub object: DeepSkyObject,
+    /// 0 to 100
+    pub score: f64,
+    /// Hours of darkness spent above the minimum altitude
+    pub visible_hours: f64,
+    pub visible_from: DateTime<Utc>,
+    pub visible_until: DateTime<Utc>,
+    pub max_altitude: f64,
+    pub max_altitude_time: DateTime<Utc>,
+    /// Closest approach to the Moon while visible and the Moon is up
+    pub moon_separation: Option<f64>,
+    /// Fraction of the field of view's short side the object spans
+    pub fov_fill: Option<f64>,
+    /// Ready-made sequence target
+    pub target: SimpleTarget,
+}
+
+/// One dark sample of the night
+struct NightSample {
+    time: DateTime<Utc>,
+    jd: f64,
+    /// Moon position when it is above the horizon
+    moon: Option<Coordinates>,
+}
+
+/// Dark samples of the night starting on `date`
+fn dark_samples(location: &ObserverLocation, date: NaiveDate) -> Vec<NightSample> {
+    let start = night_start(location, date);
+    let samples: Vec<(DateTime<Utc>, f64, f64)> = (0..=24 * 60 / SAMPLE_MINUTES)
+        .map(|step| {
+            let time = start + Duration::minutes(step * SAMPLE_MINUTES);
+            let jd = datetime_to_jd(time);
+            (time, jd, sun_altitude(location, jd))
+        })
+        .collect();
+
+    let limit = if samples
+        .iter()
+        .any(|(_, _, sun)| *sun < ASTRONOMICAL_DARK_ALTITUDE)
+    {
+        ASTRONOMICAL_DARK_ALTITUDE
+    } else {
+        NAUTICAL_DARK_ALTITUDE
+    };
+
+    samples
+        .into_iter()
+        .filter(|(_, _, sun)| *sun < limit)
+        .map(|(time, jd, _)| {
+            let (ra, dec, _) = moon_position(jd);
+            let (moon_alt, _) =
+                ra_dec_to_alt_az(ra, dec, location.latitude, location.longitude, jd);
+            NightSample {
+                time,
+                jd,
+                moon: (moon_alt > 0.0).then(|| Coordinates::from_decimal(ra, dec)),
+            }
+        })
+        .collect()
+}
+
+fn round2(value: f64) -> f64 {
+    (value * 100.0).round() / 100.0
+}
+
+fn recommend(
+    object: &DeepSkyObject,
+    location: &ObserverLocation,
+    samples: &[NightSample],
+    illumination: f64,
+    fov: Option<&FieldOfView>,
+    min_altitude: f64,
+) -> Option<TargetRecommendation> {
+    let coordinates = object.coordinates();
+    let step_hours = SAMPLE_MINUTES as f64 / 60.0;
+
+    let mut visible: Vec<&NightSample> = Vec::new();
+    let mut max_altitude = f64::MIN;
+    let mut max_altitude_time = samples.first()?.time;
+    let mut moon_factor = 0.0;
+    let mut moon_separation: Option<f64> = None;
+
+    for sample in samples {
+        let (alt, _) = observed_alt_az(object.ra_hours, object.dec_degrees, location, sample.jd);
+        if alt > max_altitude {
+            max_altitude = alt;
+            max_altitude_time = sample.time;
+        }
+        if alt < min_altitude {
+            continue;
+        }
+        visible.push(sample);
+        moon_factor += match &sample.moon {
+            Some(moon) => {
+                let separation = angular_separation(&coordinates, moon);
+                moon_separation = Some(moon_separation.map_or(separation, |s| s.min(separation)));
+                let clearance = (separation / MOON_CLEAR_SEPARATION).min(1.0);
+                1.0 - illumination / 100.0 * (1.0 - clearance)
+            }
+            None => 1.0,
+        };
+    }
+
+    let visible_hours = visible.len() as f64 * step_hours;
+    let dark_hours = samples.len() as f64 * step_hours;
+    if visible.is_empty() || visible_hours < (dark_hours / 2.0).min(1.0) {
+        return None;
+    }
+
+    let altitude_span = (FULL_SCORE_ALTITUDE - min_altitude).max(1.0);
+    let fov_fill = fov.map(|f| f.fill(object.size_arcmin));
+
+    let mut score = VISIBILITY_WEIGHT * (visible_hours / dark_hours).min(1.0)
+        + ALTITUDE_WEIGHT * ((max_altitude - min_altitude) / altitude_span).clamp(0.0, 1.0)
+        + MOON_WEIGHT * moon_factor / visible.len() as f64;
+    match fov_fill {
+        Some(fill) => score += FOV_WEIGHT * fov_fit(fill),
+        None => score *= 100.0 / (100.0 - FOV_WEIGHT),
+    }
+
+    let name = object.display_name();
+    Some(TargetRecommendation {
+        object: *object,
+        score: round2(score),
+        visible_hours: round2(visible_hours),
+        visible_from: visible[0].time,
+        visible_until: visible[visible.len() - 1].time + Duration::minutes(SAMPLE_MINUTES),
+        max_altitude: round2(max_altitude),
+        max_altitude_time,
+        moon_separation: moon_separation.map(round2),
+        fov_fill: fov_fill.map(round2),
+        target: SimpleTarget {
+            name: name.clone(),
+            target_name: name,
+            coordinates,
+            ..Default::default()
+        },
+    })
+}
+
+/// Best catalog objects for the night starting on `date`, highest score
+/// first
+pub fn recommend_targets_tonight(
+    location: &ObserverLocation,
+    date: NaiveDate,
+    filter: &CatalogFilter,
+    fov: Option<&FieldOfView>,
+    min_altitude: f64,
+    limit: usize,
+) -> Vec<TargetRecommendation> {
+    let samples = dark_samples(location, date);
+    if samples.is_empty() {
+        return Vec::new();
+    }
+    let illumination = moon_illumination(samples[samples.len() / 2].jd);
+
+    let mut recommendations: Vec<TargetRecommendation> = search_catalog(filter)
+        .into_iter()
+        .filter_map(|object| recommend(object, location, &samples, illumination, fov, min_altitude))
+        .collect();
+    recommendations.sort_by(|a, b| b.score.total_cmp(&a.score));
+    recommendations.truncate(limit);
+    recommendations
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::dso_catalog::DsoType;
+
+    fn munich() -> ObserverLocation {
+        ObserverLocation {
+            latitude: 48.14,
+            longitude: 11.58,
+            elevation: 520.0,
+            timezone_offset: 2,
+            ..Default::default()
+        }
+    }
+
+    fn october_new_moon() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 10, 2).unwrap()
+    }
+
+    #[test]
+    fn test_recommend_targets_tonight() {
+        let recommendations = recommend_targets_tonight(
+            &munich(),
+            october_new_moon(),
+            &CatalogFilter::default(),
+            None,
+            30.0,
+            10,
+        );
+        assert_eq!(recommendations.len(), 10);
+        assert!(recommendations
+            .windows(2)
+            .all(|pair| pair[0].score >= pair[1].score));
+
+        let ids: Vec<&str> = recommendations.iter().map(|r| r.object.id).collect();
+        // Autumn sky from central Europe; the summer Milky Way has set and
+        // the far south never rises high enough
+        assert!(!ids.contains(&"M8"));
+        assert!(!ids.contains(&"NGC 3372"));
+
+        let best = &recommendations[0];
+        assert!(best.visible_hours >= 5.0);
+        assert!(best.max_altitude >= 60.0);
+        assert!(best.visible_from < best.visible_until);
+        assert!(best.fov_fill.is_none());
+        assert_eq!(best.target.name, best.object.display_name());
+        assert_eq!(
+            best.target.coordinates.ra_hours,
+            best.object.ra_hours as i32
+        );
+    }
+
+    #[test]
+    fn test_field_of_view_fit() {
+        let profile = EquipmentProfile::default();
+        let fov = FieldOfView::from_profile(&profile).unwrap();
+        // 3.76 µm pixels at 500 mm: 1.55"/px over 6248 x 4176 pixels
+        assert!((fov.width - 161.5).abs() < 1.0);
+        assert!((fov.height - 107.9).abs() < 1.0);
+
+        let filter = CatalogFilter {
+            object_types: vec![DsoType::PlanetaryNebula, DsoType::Galaxy],
+            catalogs: vec!["M".to_string()],
+            ..Default::default()
+        };
+        let recommendations =
+            recommend_targets_tonight(&munich(), october_new_moon(), &filter, Some(&fov), 30.0, 50);
+        let fill = |id: &str| {
+            recommendations
+                .iter()
+                .find(|r| r.object.id == id)
+                .and_then(|r| r.fov_fill)
+                .unwrap()
+        };
+        // Both high in the autumn sky; the Andromeda Galaxy overfills the
+        // field and the Little Dumbbell is lost in it
+        let m31_fill = fill("M31");
+        let m76_fill = fill("M76");
+        assert!(m31_fill > 1.0);
+        assert!(m76_fill < 0.05);
+        assert!(fov_fit(m76_fill) < fov_fit(m31_fill));
+        assert_eq!(fov_fit(0.5), 1.0);
+    }
+
+    #[test]
+    fn test_no_recommendations_without_darkness() {
+        let location = ObserverLocation {
+            latitude: 70.0,
+            longitude: 20.0,
+            ..Default::default()
+        };
+        let date = NaiveDate::from_ymd_opt(2024, 6, 21).unwrap();
+        let recommendations =
+            recommend_targets_tonight(&location, date, &CatalogFilter::default(), None, 30.0, 10);
+        assert!(recommendations.is_empty());
+    }
+}
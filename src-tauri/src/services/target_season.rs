@@ -0,0 +1,269 @@
+//! Imaging season of a single target
+//!
+//! Usable hours for every night of a year: the time the target spends
+//! above the minimum altitude during astronomical darkness. Runs of
+//! nights that reach the required hours form the imaging season, which
+//! may wrap from December into January for targets best seen in winter.
+
+use chrono::{Datelike, Duration, NaiveDate};
+use serde::{Deserialize, Serialize};
+
+use crate::models::Coordinates;
+use crate::services::astronomy::{datetime_to_jd, observed_alt_az, sun_altitude, ObserverLocation};
+use crate::services::dark_calendar::night_start;
+use crate::services::job_queue::JobHandle;
+
+/// Sampling step through each night
+const SAMPLE_MINUTES: i64 = 10;
+
+const ASTRONOMICAL_DARK_ALTITUDE: f64 = -18.0;
+
+/// Usable hours of one night
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SeasonNight {
+    pub date: String,
+    pub dark_hours: f64,
+    /// Astronomical darkness with the target above the minimum altitude
+    pub usable_hours: f64,
+}
+
+/// Consecutive nights with enough usable hours
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SeasonWindow {
+    pub start_date: String,
+    /// Last night of the window; before `start_date` when the window wraps
+    /// into January
+    pub end_date: String,
+    pub nights: usize,
+    pub total_usable_hours: f64,
+    /// Night with the most usable hours
+    pub best_date: String,
+}
+
+/// Nightly usable hours and imaging windows of a target over a year
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TargetSeason {
+    pub year: i32,
+    pub min_altitude: f64,
+    pub min_dark_hours: f64,
+    pub nights: Vec<SeasonNight>,
+    /// Windows in calendar order of their start
+    pub windows: Vec<SeasonWindow>,
+    /// Window with the most usable hours
+    pub best_window: Option<SeasonWindow>,
+    pub total_usable_hours: f64,
+}
+
+fn round_hours(hours: f64) -> f64 {
+    (hours * 100.0).round() / 100.0
+}
+
+/// Dark and usable hours of the night starting on `date`
+fn usable_hours(
+    ra: f64,
+    dec: f64,
+    location: &ObserverLocation,
+    date: NaiveDate,
+    min_altitude: f64,
+) -> (f64, f64) {
+    let start = night_start(location, date);
+    let step_hours = SAMPLE_MINUTES as f64 / 60.0;
+    let mut dark_hours = 0.0;
+    let mut usable_hours = 0.0;
+
+    for step in 0..24 * 60 / SAMPLE_MINUTES {
+        let jd = datetime_to_jd(start + Duration::minutes(step * SAMPLE_MINUTES));
+        if sun_altitude(location, jd) >= ASTRONOMICAL_DARK_ALTITUDE {
+            continue;
+        }
+        dark_hours += step_hours;
+        if observed_alt_az(ra, dec, location, jd).0 >= min_altitude {
+            usable_hours += step_hours;
+        }
+    }
+
+    (dark_hours, usable_hours)
+}
+
+fn season_window(nights: &[SeasonNight], indices: &[usize]) -> SeasonWindow {
+    let best = indices
+        .iter()
+        .copied()
+        .max_by(|&a, &b| nights[a].usable_hours.total_cmp(&nights[b].usable_hours))
+        .unwrap_or(indices[0]);
+
+    SeasonWindow {
+        start_date: nights[indices[0]].date.clone(),
+        end_date: nights[indices[indices.len() - 1]].date.clone(),
+        nights: indices.len(),
+        total_usable_hours: round_hours(indices.iter().map(|&i| nights[i].usable_hours).sum()),
+        best_date: nights[best].date.clone(),
+    }
+}
+
+/// Runs of nights with at least `min_dark_hours` usable hours, joining a
+/// run at the end of the year with one at the start
+fn find_windows(nights: &[SeasonNight], min_dark_hours: f64) -> Vec<SeasonWindow> {
+    let mut runs: Vec<Vec<usize>> = Vec::new();
+    let mut current: Vec<usize> = Vec::new();
+
+    for (i, night) in nights.iter().enumerate() {
+        if night.usable_hours >= min_dark_hours && night.usable_hours > 0.0 {
+            current.push(i);
+        } else if !current.is_empty() {
+            runs.push(std::mem::take(&mut current));
+        }
+    }
+    if !current.is_empty() {
+        runs.push(current);
+    }
+
+    // A run ending on the last night continues into the first one
+    if runs.len() > 1 && runs[0][0] == 0 && runs[runs.len() - 1].last() == Some(&(nights.len() - 1))
+    {
+        let first = runs.remove(0);
+        runs.last_mut().unwrap().extend(first);
+    }
+
+    let mut windows: Vec<SeasonWindow> = runs
+        .iter()
+        .map(|indices| season_window(nights, indices))
+        .collect();
+    windows.sort_by(|a, b| a.start_date.cmp(&b.start_date));
+    windows
+}
+
+/// Usable hours for each night of `year` and the resulting imaging season
+pub fn calculate_target_season(
+    coords: &Coordinates,
+    location: &ObserverLocation,
+    year: i32,
+    min_altitude: f64,
+    min_dark_hours: f64,
+    job: &JobHandle,
+) -> Result<TargetSeason, String> {
+    let first =
+        NaiveDate::from_ymd_opt(year, 1, 1).ok_or_else(|| format!("Invalid year: {}", year))?;
+    let ra = coords.ra_to_decimal();
+    let dec = coords.dec_to_decimal();
+    let days = first
+        .iter_days()
+        .take_while(|date| date.year() == year)
+        .count();
+
+    let nights: Vec<SeasonNight> = first
+        .iter_days()
+        .take(days)
+        .enumerate()
+        .map(|(i, date)| {
+            job.checkpoint()?;
+            let (dark_hours, usable) = usable_hours(ra, dec, location, date, min_altitude);
+            job.report(i + 1, days, None);
+            Ok(SeasonNight {
+                date: date.format("%Y-%m-%d").to_string(),
+                dark_hours: round_hours(dark_hours),
+                usable_hours: round_hours(usable),
+            })
+        })
+        .collect::<Result<_, String>>()?;
+
+    let windows = find_windows(&nights, min_dark_hours);
+    let best_window = windows
+        .iter()
+        .max_by(|a, b| a.total_usable_hours.total_cmp(&b.total_usable_hours))
+        .cloned();
+
+    Ok(TargetSeason {
+        year,
+        min_altitude,
+        min_dark_hours,
+        total_usable_hours: round_hours(nights.iter().map(|n| n.usable_hours).sum()),
+        nights,
+        windows,
+        best_window,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn munich() -> ObserverLocation {
+        ObserverLocation {
+            latitude: 48.14,
+            longitude: 11.58,
+            elevation: 520.0,
+            timezone_offset: 1,
+            ..Default::default()
+        }
+    }
+
+    fn night(date: &str, usable_hours: f64) -> SeasonNight {
+        SeasonNight {
+            date: date.to_string(),
+            dark_hours: 8.0,
+            usable_hours,
+        }
+    }
+
+    #[test]
+    fn test_calculate_target_season() {
+        // M42 is a winter target
+        let m42 = Coordinates::from_decimal(5.59, -5.45);
+        let season =
+            calculate_target_season(&m42, &munich(), 2024, 25.0, 3.0, &JobHandle::detached())
+                .unwrap();
+        assert_eq!(season.nights.len(), 366);
+
+        let june = &season.nights[172];
+        assert_eq!(june.date, "2024-06-21");
+        assert_eq!(june.usable_hours, 0.0);
+        let january = &season.nights[14];
+        assert!(january.usable_hours > 3.0);
+        assert!(january.usable_hours <= january.dark_hours);
+
+        let best = season.best_window.unwrap();
+        assert!(best.nights > 60);
+        // Season wraps from autumn into spring
+        assert!(best.end_date < best.start_date);
+        assert!(best.start_date.as_str() > "2024-09-01");
+        assert!(best.end_date.as_str() < "2024-04-01");
+
+        assert!(calculate_target_season(
+            &m42,
+            &munich(),
+            300_000,
+            25.0,
+            3.0,
+            &JobHandle::detached()
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_find_windows() {
+        let nights = vec![
+            night("2024-01-01", 4.0),
+            night("2024-01-02", 1.0),
+            night("2024-01-03", 3.0),
+            night("2024-01-04", 5.0),
+            night("2024-01-05", 0.5),
+            night("2024-01-06", 2.5),
+        ];
+        let windows = find_windows(&nights, 2.0);
+        assert_eq!(windows.len(), 2);
+        assert_eq!(windows[0].start_date, "2024-01-03");
+        assert_eq!(windows[0].best_date, "2024-01-04");
+        assert_eq!(windows[0].total_usable_hours, 8.0);
+        // Last night joins the first
+        assert_eq!(windows[1].start_date, "2024-01-06");
+        assert_eq!(windows[1].end_date, "2024-01-01");
+        assert_eq!(windows[1].nights, 2);
+        assert_eq!(windows[1].best_date, "2024-01-01");
+
+        assert!(find_windows(&nights, 6.0).is_empty());
+    }
+}
@@ -0,0 +1,335 @@
+//! Target propagation into Deep Sky Object containers
+//!
+//! NINA instructions inside a Deep Sky Object container keep their own
+//! copy of the target's coordinates and rotation. After the target is
+//! edited, [`sync_target_into_container`] writes the new values into the
+//! container and the instructions that hold them, so slews and centering
+//! don't go to the old position.
+//!
+//! The container's `Target` may be in NINA's shape (`TargetName`,
+//! `InputCoordinates`) or the editor's (`name`, `ra`, `dec`); it is
+//! updated in the shape it has. Nested Deep Sky Object containers have
+//! their own target and are left alone.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+
+use crate::models::{EditorSequenceItem, EditorTarget};
+use crate::services::validator::get_short_type_name;
+
+/// Items holding target coordinates
+const COORDINATE_ITEMS: &[&str] = &["SlewScopeToRaDec", "Center", "CenterAndRotate"];
+
+/// Items holding the target rotation
+const ROTATION_ITEMS: &[&str] = &["CenterAndRotate", "SolveAndRotate"];
+
+/// Result of [`sync_target_into_container`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TargetSyncResult {
+    pub container: EditorSequenceItem,
+    /// Ids of the items that were changed, the container included
+    pub updated_item_ids: Vec<String>,
+}
+
+/// Key of a data field in any letter case, `default` if missing
+fn field_key(data: &HashMap<String, Value>, keys: &[&str], default: &str) -> String {
+    data.keys()
+        .find(|k| keys.iter().any(|key| k.eq_ignore_ascii_case(key)))
+        .cloned()
+        .unwrap_or_else(|| default.to_string())
+}
+
+/// Set `value` under whichever of `keys` the object has, in its letter
+/// case, or under the first key
+fn set_object_field(object: &mut Map<String, Value>, keys: &[&str], value: Value) {
+    let key = object
+        .keys()
+        .find(|k| keys.iter().any(|key| k.eq_ignore_ascii_case(key)))
+        .cloned()
+        .unwrap_or_else(|| keys[0].to_string());
+    object.insert(key, value);
+}
+
+/// Write the target position into NINA `InputCoordinates`, keeping any
+/// other fields such as `$type`
+fn write_coordinates(value: &mut Value, target: &EditorTarget) {
+    if !value.is_object() {
+        *value = json!({});
+    }
+    let Some(object) = value.as_object_mut() else {
+        return;
+    };
+    set_object_field(object, &["RAHours"], json!(target.ra.hours));
+    set_object_field(object, &["RAMinutes"], json!(target.ra.minutes));
+    set_object_field(object, &["RASeconds"], json!(target.ra.seconds));
+    set_object_field(object, &["NegativeDec"], json!(target.dec.negative));
+    set_object_field(object, &["DecDegrees"], json!(target.dec.degrees));
+    set_object_field(object, &["DecMinutes"], json!(target.dec.minutes));
+    set_object_field(object, &["DecSeconds"], json!(target.dec.seconds));
+}
+
+/// Write the target into a container's `Target`, in the shape it has
+fn write_container_target(value: &mut Value, target: &EditorTarget) {
+    let editor_shape = value
+        .as_object()
+        .is_some_and(|o| o.keys().any(|k| k == "ra" || k == "dec"));
+    if editor_shape || value.is_null() {
+        *value = json!(target);
+        return;
+    }
+    if !value.is_object() {
+        *value = json!({});
+    }
+    let Some(object) = value.as_object_mut() else {
+        return;
+    };
+    set_object_field(object, &["TargetName"], json!(target.name));
+    set_object_field(object, &["Rotation"], json!(target.rotation));
+    if let Some(key) = object
+        .keys()
+        .find(|k| k.eq_ignore_ascii_case("PositionAngle"))
+        .cloned()
+    {
+        object.insert(key, json!(target.rotation));
+    }
+    let key = object
+        .keys()
+        .find(|k| k.eq_ignore_ascii_case("InputCoordinates"))
+        .cloned()
+        .unwrap_or_else(|| "InputCoordinates".to_string());
+    write_coordinates(object.entry(key).or_insert(Value::Null), target);
+}
+
+/// Update the known instructions in `items`, collecting the changed ids
+fn sync_items(items: &mut [EditorSequenceItem], target: &EditorTarget, updated: &mut Vec<String>) {
+    for item in items {
+        let short_type = get_short_type_name(&item.item_type);
+        if short_type == "DeepSkyObjectContainer" {
+            continue;
+        }
+
+        let mut changed = false;
+        if COORDINATE_ITEMS.contains(&short_type.as_str()) {
+            let key = field_key(
+                &item.data,
+                &["Coordinates", "InputCoordinates"],
+                "Coordinates",
+            );
+            write_coordinates(item.data.entry(key).or_insert(Value::Null), target);
+            changed = true;
+        }
+        if ROTATION_ITEMS.contains(&short_type.as_str()) {
+            let keys: Vec<String> = item
+                .data
+                .keys()
+                .filter(|k| {
+                    k.eq_ignore_ascii_case("Rotation") || k.eq_ignore_ascii_case("PositionAngle")
+                })
+                .cloned()
+                .collect();
+            if keys.is_empty() {
+                item.data
+                    .insert("Rotation".to_string(), json!(target.rotation));
+            }
+            for key in keys {
+                item.data.insert(key, json!(target.rotation));
+            }
+            changed = true;
+        }
+        if changed {
+            updated.push(item.id.clone());
+        }
+
+        sync_items(
+            item.items.as_deref_mut().unwrap_or_default(),
+            target,
+            updated,
+        );
+        for trigger in item.triggers.iter_mut().flatten() {
+            sync_items(
+                trigger.trigger_items.as_deref_mut().unwrap_or_default(),
+                target,
+                updated,
+            );
+        }
+    }
+}
+
+/// Write `target` into a Deep Sky Object container: its `Target`, its
+/// name when that followed the old target name, and the coordinates and
+/// rotation of the slew, center and rotate instructions inside it
+pub fn sync_target_into_container(
+    container: &mut EditorSequenceItem,
+    target: &EditorTarget,
+) -> Result<Vec<String>, String> {
+    if get_short_type_name(&container.item_type) != "DeepSkyObjectContainer" {
+        return Err(format!(
+            "'{}' is not a Deep Sky Object container",
+            container.name
+        ));
+    }
+
+    let key = field_key(&container.data, &["Target"], "Target");
+    let target_value = container.data.entry(key).or_insert(Value::Null);
+    let old_name = target_value
+        .as_object()
+        .and_then(|o| {
+            o.iter()
+                .find(|(k, _)| k.eq_ignore_ascii_case("TargetName") || *k == "name")
+                .map(|(_, v)| v)
+        })
+        .and_then(Value::as_str)
+        .map(str::to_string);
+    write_container_target(target_value, target);
+
+    let follows_target = match &old_name {
+        Some(old_name) => container.name == *old_name,
+        None => true,
+    };
+    if follows_target && !target.name.is_empty() {
+        container.name = target.name.clone();
+    }
+
+    let mut updated = vec![container.id.clone()];
+    sync_items(
+        container.items.as_deref_mut().unwrap_or_default(),
+        target,
+        &mut updated,
+    );
+    for trigger in container.triggers.iter_mut().flatten() {
+        sync_items(
+            trigger.trigger_items.as_deref_mut().unwrap_or_default(),
+            target,
+            &mut updated,
+        );
+    }
+    Ok(updated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{DecCoord, RaCoord};
+
+    fn item(short_type: &str, name: &str, data: Value, items: Vec<Value>) -> Value {
+        json!({
+            "id": name,
+            "type": format!("NINA.Sequencer.{}, NINA.Sequencer", short_type),
+            "name": name,
+            "category": "",
+            "status": "CREATED",
+            "data": data,
+            "items": items,
+        })
+    }
+
+    fn target() -> EditorTarget {
+        EditorTarget {
+            name: "M33".to_string(),
+            ra: RaCoord {
+                hours: 1,
+                minutes: 33,
+                seconds: 50.9,
+            },
+            dec: DecCoord {
+                degrees: 30,
+                minutes: 39,
+                seconds: 36.0,
+                negative: false,
+            },
+            rotation: 90.0,
+        }
+    }
+
+    #[test]
+    fn test_sync_target_into_container() {
+        let inner = item(
+            "Container.SequentialContainer",
+            "loop",
+            json!({}),
+            vec![item(
+                "SequenceItem.Telescope.SlewScopeToRaDec",
+                "slew",
+                json!({ "Inherited": true }),
+                vec![],
+            )],
+        );
+        let value = item(
+            "Container.DeepSkyObjectContainer",
+            "M31",
+            json!({ "target": {
+                "TargetName": "M31",
+                "Rotation": 0,
+                "InputCoordinates": { "$type": "NINA.Astrometry.InputCoordinates", "RAHours": 0 },
+            }}),
+            vec![
+                item(
+                    "SequenceItem.Platesolving.CenterAndRotate",
+                    "center",
+                    json!({ "Inherited": true, "Rotation": 0 }),
+                    vec![],
+                ),
+                item(
+                    "SequenceItem.Autofocus.RunAutofocus",
+                    "af",
+                    json!({}),
+                    vec![],
+                ),
+                inner,
+                item(
+                    "Container.DeepSkyObjectContainer",
+                    "other",
+                    json!({}),
+                    vec![],
+                ),
+            ],
+        );
+        let mut container: EditorSequenceItem = serde_json::from_value(value).unwrap();
+
+        let updated = sync_target_into_container(&mut container, &target()).unwrap();
+        assert_eq!(updated, ["M31", "center", "slew"]);
+        assert_eq!(container.name, "M33");
+
+        let nina_target = &container.data["target"];
+        assert_eq!(nina_target["TargetName"], "M33");
+        assert_eq!(nina_target["Rotation"], 90.0);
+        let coordinates = &nina_target["InputCoordinates"];
+        assert_eq!(coordinates["RAMinutes"], 33);
+        assert_eq!(coordinates["$type"], "NINA.Astrometry.InputCoordinates");
+
+        let items = container.items.as_ref().unwrap();
+        assert_eq!(items[0].data["Rotation"], 90.0);
+        assert_eq!(items[0].data["Coordinates"]["DecMinutes"], 39);
+        let slew = &items[2].items.as_ref().unwrap()[0];
+        assert_eq!(slew.data["Coordinates"]["RAHours"], 1);
+        assert!(items[3].data.is_empty());
+    }
+
+    #[test]
+    fn test_sync_editor_shaped_target() {
+        let value = item(
+            "Container.DeepSkyObjectContainer",
+            "Andromeda",
+            json!({ "Target": { "name": "M31", "ra": {}, "dec": {}, "rotation": 0 } }),
+            vec![],
+        );
+        let mut container: EditorSequenceItem = serde_json::from_value(value).unwrap();
+        sync_target_into_container(&mut container, &target()).unwrap();
+
+        // A name of its own is kept
+        assert_eq!(container.name, "Andromeda");
+        assert_eq!(container.data["Target"]["name"], "M33");
+        assert_eq!(container.data["Target"]["dec"]["degrees"], 30);
+
+        let mut slew: EditorSequenceItem = serde_json::from_value(item(
+            "SequenceItem.Telescope.SlewScopeToRaDec",
+            "slew",
+            json!({}),
+            vec![],
+        ))
+        .unwrap();
+        assert!(sync_target_into_container(&mut slew, &target()).is_err());
+    }
+}
@@ -0,0 +1,379 @@
+//! Template bundles
+//!
+//! A bundle is a single JSON file holding templates of any kind, meant for
+//! sharing between installations. Imported templates are checked like
+//! freshly saved ones, and name or id collisions with existing templates
+//! are resolved by an [`ImportConflictPolicy`].
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tokio::fs;
+
+use crate::error::{AppError, AppResult, ResultExt};
+use crate::services::template_service::{
+    self, ExposureSetTemplate, SimpleSequenceTemplate, TargetTemplate, TemplateMetadata,
+};
+
+/// Current bundle format version
+pub const TEMPLATE_BUNDLE_VERSION: u32 = 1;
+
+/// Template of any kind inside a bundle
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum BundledTemplate {
+    Sequence(SimpleSequenceTemplate),
+    Target(TargetTemplate),
+    ExposureSet(ExposureSetTemplate),
+}
+
+impl BundledTemplate {
+    pub fn metadata(&self) -> &TemplateMetadata {
+        match self {
+            Self::Sequence(t) => &t.metadata,
+            Self::Target(t) => &t.metadata,
+            Self::ExposureSet(t) => &t.metadata,
+        }
+    }
+
+    fn metadata_mut(&mut self) -> &mut TemplateMetadata {
+        match self {
+            Self::Sequence(t) => &mut t.metadata,
+            Self::Target(t) => &mut t.metadata,
+            Self::ExposureSet(t) => &mut t.metadata,
+        }
+    }
+
+    fn directory(&self) -> PathBuf {
+        match self {
+            Self::Sequence(_) => template_service::get_simple_templates_directory(),
+            Self::Target(_) => template_service::get_target_templates_directory(),
+            Self::ExposureSet(_) => template_service::get_exposure_templates_directory(),
+        }
+    }
+
+    /// Check the contents the same way saving a template does
+    fn check(&self) -> AppResult<()> {
+        match self {
+            Self::Sequence(_) => Ok(()),
+            Self::Target(t) => template_service::check_target_template(&t.target, &t.variables),
+            Self::ExposureSet(t) => {
+                template_service::check_exposure_set_template(&t.exposures, &t.variables)
+            }
+        }
+    }
+}
+
+/// Shareable set of templates
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TemplateBundle {
+    pub version: u32,
+    pub exported_at: DateTime<Utc>,
+    pub templates: Vec<BundledTemplate>,
+}
+
+/// What to do when an imported template has the id or name of an
+/// existing template of the same kind
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ImportConflictPolicy {
+    /// Import under a new id and a free name
+    #[default]
+    Rename,
+    /// Replace the existing template. Built-in templates are never
+    /// replaced; those collisions fall back to renaming.
+    Overwrite,
+    /// Keep the existing template
+    Skip,
+}
+
+/// Result of importing a bundle
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TemplateBundleImportResult {
+    pub imported: Vec<TemplateMetadata>,
+    /// Names of templates left out because of [`ImportConflictPolicy::Skip`]
+    pub skipped: Vec<String>,
+}
+
+/// Load a stored or built-in template of any kind by id
+async fn load_any_template(id: &str) -> AppResult<BundledTemplate> {
+    if let Ok(t) = template_service::load_simple_sequence_template(id).await {
+        return Ok(BundledTemplate::Sequence(t));
+    }
+    if let Ok(t) = template_service::load_target_template(id).await {
+        return Ok(BundledTemplate::Target(t));
+    }
+    if let Ok(t) = template_service::load_exposure_set_template(id).await {
+        return Ok(BundledTemplate::ExposureSet(t));
+    }
+    Err(AppError::NotFound(format!("Template not found: {}", id)))
+}
+
+/// Collect the given templates into a bundle
+pub async fn create_template_bundle(ids: &[String]) -> AppResult<TemplateBundle> {
+    let mut templates = Vec::with_capacity(ids.len());
+    for id in ids {
+        templates.push(load_any_template(id).await?);
+    }
+
+    Ok(TemplateBundle {
+        version: TEMPLATE_BUNDLE_VERSION,
+        exported_at: Utc::now(),
+        templates,
+    })
+}
+
+/// Write the given templates to a bundle file
+pub async fn export_template_bundle(ids: &[String], path: &Path) -> AppResult<usize> {
+    if ids.is_empty() {
+        return Err(AppError::InvalidInput("No templates selected".to_string()));
+    }
+
+    let bundle = create_template_bundle(ids).await?;
+    let content = serde_json::to_string_pretty(&bundle)
+        .map_err(|e| AppError::Internal(format!("Failed to serialize template bundle: {}", e)))?;
+
+    fs::write(path, content)
+        .await
+        .context("Failed to write template bundle")?;
+
+    Ok(bundle.templates.len())
+}
+
+/// Parse bundle JSON, rejecting bundles from newer versions
+pub fn parse_template_bundle(content: &str) -> AppResult<TemplateBundle> {
+    let bundle: TemplateBundle = serde_json::from_str(content)
+        .map_err(|e| AppError::Parse(format!("Failed to parse template bundle: {}", e)))?;
+
+    if bundle.version > TEMPLATE_BUNDLE_VERSION {
+        return Err(AppError::Unsupported(format!(
+            "Template bundle version {} is newer than supported version {}",
+            bundle.version, TEMPLATE_BUNDLE_VERSION
+        )));
+    }
+
+    Ok(bundle)
+}
+
+/// First free name of the form "<name> (n)"
+fn unique_name(name: &str, existing: &[TemplateMetadata]) -> String {
+    let taken = |candidate: &str| {
+        existing
+            .iter()
+            .any(|m| m.name.eq_ignore_ascii_case(candidate))
+    };
+    (2..)
+        .map(|n| format!("{} ({})", name, n))
+        .find(|candidate| !taken(candidate))
+        .expect("unbounded range yields a free name")
+}
+
+/// Prepare an imported template's metadata against the existing templates
+/// of its kind. Returns `false` when the template should be skipped.
+fn resolve_conflict(
+    template: &mut BundledTemplate,
+    existing: &[TemplateMetadata],
+    policy: ImportConflictPolicy,
+) -> bool {
+    let metadata = template.metadata_mut();
+    metadata.is_builtin = false;
+    metadata.updated_at = Utc::now();
+
+    let conflict = existing
+        .iter()
+        .find(|m| m.id == metadata.id || m.name.eq_ignore_ascii_case(&metadata.name));
+    let Some(conflict) = conflict else {
+        // Ids name the template file, so only UUIDs are kept
+        metadata.id = uuid::Uuid::parse_str(&metadata.id)
+            .unwrap_or_else(|_| uuid::Uuid::new_v4())
+            .to_string();
+        return true;
+    };
+
+    match policy {
+        ImportConflictPolicy::Skip => false,
+        ImportConflictPolicy::Overwrite if !conflict.is_builtin => {
+            metadata.id = conflict.id.clone();
+            metadata.created_at = conflict.created_at;
+            true
+        }
+        _ => {
+            metadata.id = uuid::Uuid::new_v4().to_string();
+            if existing
+                .iter()
+                .any(|m| m.name.eq_ignore_ascii_case(&metadata.name))
+            {
+                metadata.name = unique_name(&metadata.name, existing);
+            }
+            true
+        }
+    }
+}
+
+async fn existing_templates(template: &BundledTemplate) -> AppResult<Vec<TemplateMetadata>> {
+    match template {
+        BundledTemplate::Sequence(_) => template_service::list_simple_sequence_templates().await,
+        BundledTemplate::Target(_) => template_service::list_target_templates().await,
+        BundledTemplate::ExposureSet(_) => template_service::list_exposure_set_templates().await,
+    }
+}
+
+/// Import all templates of a bundle file. Nothing is written unless every
+/// template in the bundle is valid.
+pub async fn import_template_bundle(
+    path: &Path,
+    policy: ImportConflictPolicy,
+) -> AppResult<TemplateBundleImportResult> {
+    let content = fs::read_to_string(path)
+        .await
+        .context("Failed to read template bundle")?;
+    let bundle = parse_template_bundle(&content)?;
+
+    for template in &bundle.templates {
+        template.check().context(template.metadata().name.clone())?;
+    }
+
+    template_service::ensure_template_directories().await?;
+
+    let mut result = TemplateBundleImportResult::default();
+    for mut template in bundle.templates {
+        let mut existing = existing_templates(&template).await?;
+        // Earlier templates of this bundle count as existing too
+        existing.extend(result.imported.iter().cloned());
+
+        if !resolve_conflict(&mut template, &existing, policy) {
+            result.skipped.push(template.metadata().name.clone());
+            continue;
+        }
+
+        let metadata = template.metadata().clone();
+        let content = match &template {
+            BundledTemplate::Sequence(t) => serde_json::to_string_pretty(t),
+            BundledTemplate::Target(t) => serde_json::to_string_pretty(t),
+            BundledTemplate::ExposureSet(t) => serde_json::to_string_pretty(t),
+        }
+        .map_err(|e| AppError::Internal(format!("Failed to serialize template: {}", e)))?;
+
+        let path = template.directory().join(format!("{}.json", metadata.id));
+        fs::write(&path, content)
+            .await
+            .context("Failed to save template")?;
+
+        result.imported.push(metadata);
+    }
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::builtin_templates;
+
+    fn metadata(id: &str, name: &str, is_builtin: bool) -> TemplateMetadata {
+        TemplateMetadata {
+            id: id.to_string(),
+            name: name.to_string(),
+            description: String::new(),
+            category: "exposure".to_string(),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            tags: Vec::new(),
+            is_builtin,
+        }
+    }
+
+    fn bundled(id: &str, name: &str) -> BundledTemplate {
+        BundledTemplate::ExposureSet(ExposureSetTemplate {
+            metadata: metadata(id, name, false),
+            variables: Vec::new(),
+            exposures: Vec::new(),
+        })
+    }
+
+    #[test]
+    fn test_bundle_round_trip() {
+        let bundle = TemplateBundle {
+            version: TEMPLATE_BUNDLE_VERSION,
+            exported_at: Utc::now(),
+            templates: vec![
+                BundledTemplate::Target(builtin_templates::target_templates().remove(0)),
+                bundled("a", "Mine"),
+            ],
+        };
+
+        let json = serde_json::to_string(&bundle).unwrap();
+        assert!(json.contains("\"kind\":\"target\""));
+        assert!(json.contains("\"kind\":\"exposureSet\""));
+
+        let parsed = parse_template_bundle(&json).unwrap();
+        assert_eq!(parsed.templates.len(), 2);
+        assert!(parsed.templates.iter().all(|t| t.check().is_ok()));
+
+        let newer = json.replacen("\"version\":1", "\"version\":99", 1);
+        assert!(parse_template_bundle(&newer).is_err());
+    }
+
+    #[test]
+    fn test_resolve_conflict_policies() {
+        let existing = vec![
+            metadata("a", "Mine", false),
+            metadata("b", "Mine (2)", false),
+            metadata("builtin-x", "Builtin", true),
+        ];
+
+        let id = uuid::Uuid::new_v4().to_string();
+        let mut template = bundled(&id, "Other");
+        assert!(resolve_conflict(
+            &mut template,
+            &existing,
+            ImportConflictPolicy::Skip
+        ));
+        assert_eq!(template.metadata().id, id);
+
+        let mut template = bundled("../escape", "Elsewhere");
+        assert!(resolve_conflict(
+            &mut template,
+            &existing,
+            ImportConflictPolicy::Skip
+        ));
+        assert!(uuid::Uuid::parse_str(&template.metadata().id).is_ok());
+
+        let mut template = bundled("new", "mine");
+        assert!(!resolve_conflict(
+            &mut template,
+            &existing,
+            ImportConflictPolicy::Skip
+        ));
+
+        let mut template = bundled("new", "Mine");
+        assert!(resolve_conflict(
+            &mut template,
+            &existing,
+            ImportConflictPolicy::Overwrite
+        ));
+        assert_eq!(template.metadata().id, "a");
+
+        let mut template = bundled("a", "Mine");
+        assert!(resolve_conflict(
+            &mut template,
+            &existing,
+            ImportConflictPolicy::Rename
+        ));
+        assert_ne!(template.metadata().id, "a");
+        assert_eq!(template.metadata().name, "Mine (3)");
+
+        // Built-in templates are renamed around rather than replaced
+        let mut template = bundled("builtin-x", "Builtin");
+        assert!(resolve_conflict(
+            &mut template,
+            &existing,
+            ImportConflictPolicy::Overwrite
+        ));
+        assert_ne!(template.metadata().id, "builtin-x");
+        assert_eq!(template.metadata().name, "Builtin (2)");
+        assert!(!template.metadata().is_builtin);
+    }
+}
@@ -2,11 +2,16 @@
 
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
 use std::path::PathBuf;
 use tokio::fs;
 
-use crate::models::{EditorSequence, SimpleExposure, SimpleSequence, SimpleTarget};
-use crate::services::file_service;
+use crate::error::{AppError, AppResult, ResultExt};
+use crate::models::{
+    EditorSequence, SequenceEntityStatus, SimpleExposure, SimpleSequence, SimpleTarget,
+};
+use crate::services::{builtin_templates, file_service};
 
 /// Template metadata
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -30,20 +35,50 @@ pub struct SimpleSequenceTemplate {
     pub sequence: SimpleSequence,
 }
 
-/// Target template
+/// Value type of a template variable
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TemplateVariableType {
+    #[default]
+    String,
+    Number,
+    Integer,
+    Boolean,
+}
+
+/// Variable declared by a template and referenced as `{{name}}`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TemplateVariable {
+    pub name: String,
+    #[serde(default)]
+    pub var_type: TemplateVariableType,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+/// Target template. The target is kept as JSON so that any field can
+/// hold a `{{variable}}` placeholder.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct TargetTemplate {
     pub metadata: TemplateMetadata,
-    pub target: SimpleTarget,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub variables: Vec<TemplateVariable>,
+    pub target: Value,
 }
 
-/// Exposure set template
+/// Exposure set template, with exposures kept as JSON like
+/// [`TargetTemplate`]
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ExposureSetTemplate {
     pub metadata: TemplateMetadata,
-    pub exposures: Vec<SimpleExposure>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub variables: Vec<TemplateVariable>,
+    pub exposures: Vec<Value>,
 }
 
 /// Editor sequence template
@@ -80,7 +115,7 @@ pub fn get_editor_templates_directory() -> PathBuf {
 }
 
 /// Ensure template directories exist
-pub async fn ensure_template_directories() -> Result<(), String> {
+pub async fn ensure_template_directories() -> AppResult<()> {
     let dirs = [
         get_simple_templates_directory(),
         get_target_templates_directory(),
@@ -91,7 +126,7 @@ pub async fn ensure_template_directories() -> Result<(), String> {
     for dir in dirs {
         fs::create_dir_all(&dir)
             .await
-            .map_err(|e| format!("Failed to create template directory: {}", e))?;
+            .context("Failed to create template directory")?;
     }
 
     Ok(())
@@ -104,7 +139,7 @@ pub async fn save_simple_sequence_template(
     category: &str,
     tags: Vec<String>,
     sequence: SimpleSequence,
-) -> Result<TemplateMetadata, String> {
+) -> AppResult<TemplateMetadata> {
     ensure_template_directories().await?;
 
     let id = uuid::Uuid::new_v4().to_string();
@@ -128,40 +163,51 @@ pub async fn save_simple_sequence_template(
 
     let path = get_simple_templates_directory().join(format!("{}.json", id));
     let content = serde_json::to_string_pretty(&template)
-        .map_err(|e| format!("Failed to serialize template: {}", e))?;
+        .map_err(|e| AppError::Internal(format!("Failed to serialize template: {}", e)))?;
 
     fs::write(&path, content)
         .await
-        .map_err(|e| format!("Failed to save template: {}", e))?;
+        .context("Failed to save template")?;
 
     Ok(metadata)
 }
 
 /// Load simple sequence template
-pub async fn load_simple_sequence_template(id: &str) -> Result<SimpleSequenceTemplate, String> {
+pub async fn load_simple_sequence_template(id: &str) -> AppResult<SimpleSequenceTemplate> {
+    if let Some(template) = builtin_templates::simple_sequence_templates()
+        .into_iter()
+        .find(|t| t.metadata.id == id)
+    {
+        return Ok(template);
+    }
+
     let path = get_simple_templates_directory().join(format!("{}.json", id));
 
     let content = fs::read_to_string(&path)
         .await
-        .map_err(|e| format!("Failed to read template: {}", e))?;
+        .context("Failed to read template")?;
 
-    serde_json::from_str(&content).map_err(|e| format!("Failed to parse template: {}", e))
+    serde_json::from_str(&content)
+        .map_err(|e| AppError::Parse(format!("Failed to parse template: {}", e)))
 }
 
 /// List simple sequence templates
-pub async fn list_simple_sequence_templates() -> Result<Vec<TemplateMetadata>, String> {
+pub async fn list_simple_sequence_templates() -> AppResult<Vec<TemplateMetadata>> {
     let dir = get_simple_templates_directory();
 
+    let mut templates: Vec<TemplateMetadata> = builtin_templates::simple_sequence_templates()
+        .into_iter()
+        .map(|t| t.metadata)
+        .collect();
     if !dir.exists() {
-        return Ok(Vec::new());
+        return Ok(templates);
     }
 
-    let mut templates = Vec::new();
     let mut entries = fs::read_dir(&dir)
         .await
-        .map_err(|e| format!("Failed to read templates directory: {}", e))?;
+        .context("Failed to read templates directory")?;
 
-    while let Some(entry) = entries.next_entry().await.map_err(|e| e.to_string())? {
+    while let Some(entry) = entries.next_entry().await? {
         let path = entry.path();
         if path.extension().and_then(|e| e.to_str()) == Some("json") {
             if let Ok(content) = fs::read_to_string(&path).await {
@@ -179,34 +225,46 @@ pub async fn list_simple_sequence_templates() -> Result<Vec<TemplateMetadata>, S
 }
 
 /// Delete simple sequence template
-pub async fn delete_simple_sequence_template(id: &str) -> Result<(), String> {
+pub async fn delete_simple_sequence_template(id: &str) -> AppResult<()> {
+    if id.starts_with(builtin_templates::BUILTIN_ID_PREFIX) {
+        return Err(AppError::InvalidInput(
+            "Cannot delete builtin template".to_string(),
+        ));
+    }
+
     let path = get_simple_templates_directory().join(format!("{}.json", id));
 
     if !path.exists() {
-        return Err("Template not found".to_string());
+        return Err(AppError::NotFound("Template not found".to_string()));
     }
 
     // Check if it's a builtin template
     if let Ok(content) = fs::read_to_string(&path).await {
         if let Ok(template) = serde_json::from_str::<SimpleSequenceTemplate>(&content) {
             if template.metadata.is_builtin {
-                return Err("Cannot delete builtin template".to_string());
+                return Err(AppError::InvalidInput(
+                    "Cannot delete builtin template".to_string(),
+                ));
             }
         }
     }
 
     fs::remove_file(&path)
         .await
-        .map_err(|e| format!("Failed to delete template: {}", e))
+        .context("Failed to delete template")
 }
 
-/// Save target template
+/// Save target template. Placeholders must refer to declared variables,
+/// and the target must be valid once the defaults are filled in.
 pub async fn save_target_template(
     name: &str,
     description: &str,
     tags: Vec<String>,
-    target: SimpleTarget,
-) -> Result<TemplateMetadata, String> {
+    target: Value,
+    variables: Vec<TemplateVariable>,
+) -> AppResult<TemplateMetadata> {
+    check_target_template(&target, &variables)?;
+
     ensure_template_directories().await?;
 
     let id = uuid::Uuid::new_v4().to_string();
@@ -225,45 +283,57 @@ pub async fn save_target_template(
 
     let template = TargetTemplate {
         metadata: metadata.clone(),
+        variables,
         target,
     };
 
     let path = get_target_templates_directory().join(format!("{}.json", id));
     let content = serde_json::to_string_pretty(&template)
-        .map_err(|e| format!("Failed to serialize template: {}", e))?;
+        .map_err(|e| AppError::Internal(format!("Failed to serialize template: {}", e)))?;
 
     fs::write(&path, content)
         .await
-        .map_err(|e| format!("Failed to save template: {}", e))?;
+        .context("Failed to save template")?;
 
     Ok(metadata)
 }
 
 /// Load target template
-pub async fn load_target_template(id: &str) -> Result<TargetTemplate, String> {
+pub async fn load_target_template(id: &str) -> AppResult<TargetTemplate> {
+    if let Some(template) = builtin_templates::target_templates()
+        .into_iter()
+        .find(|t| t.metadata.id == id)
+    {
+        return Ok(template);
+    }
+
     let path = get_target_templates_directory().join(format!("{}.json", id));
 
     let content = fs::read_to_string(&path)
         .await
-        .map_err(|e| format!("Failed to read template: {}", e))?;
+        .context("Failed to read template")?;
 
-    serde_json::from_str(&content).map_err(|e| format!("Failed to parse template: {}", e))
+    serde_json::from_str(&content)
+        .map_err(|e| AppError::Parse(format!("Failed to parse template: {}", e)))
 }
 
 /// List target templates
-pub async fn list_target_templates() -> Result<Vec<TemplateMetadata>, String> {
+pub async fn list_target_templates() -> AppResult<Vec<TemplateMetadata>> {
     let dir = get_target_templates_directory();
 
+    let mut templates: Vec<TemplateMetadata> = builtin_templates::target_templates()
+        .into_iter()
+        .map(|t| t.metadata)
+        .collect();
     if !dir.exists() {
-        return Ok(Vec::new());
+        return Ok(templates);
     }
 
-    let mut templates = Vec::new();
     let mut entries = fs::read_dir(&dir)
         .await
-        .map_err(|e| format!("Failed to read templates directory: {}", e))?;
+        .context("Failed to read templates directory")?;
 
-    while let Some(entry) = entries.next_entry().await.map_err(|e| e.to_string())? {
+    while let Some(entry) = entries.next_entry().await? {
         let path = entry.path();
         if path.extension().and_then(|e| e.to_str()) == Some("json") {
             if let Ok(content) = fs::read_to_string(&path).await {
@@ -278,13 +348,16 @@ pub async fn list_target_templates() -> Result<Vec<TemplateMetadata>, String> {
     Ok(templates)
 }
 
-/// Save exposure set template
+/// Save exposure set template, checked like [`save_target_template`]
 pub async fn save_exposure_set_template(
     name: &str,
     description: &str,
     tags: Vec<String>,
-    exposures: Vec<SimpleExposure>,
-) -> Result<TemplateMetadata, String> {
+    exposures: Vec<Value>,
+    variables: Vec<TemplateVariable>,
+) -> AppResult<TemplateMetadata> {
+    check_exposure_set_template(&exposures, &variables)?;
+
     ensure_template_directories().await?;
 
     let id = uuid::Uuid::new_v4().to_string();
@@ -303,45 +376,57 @@ pub async fn save_exposure_set_template(
 
     let template = ExposureSetTemplate {
         metadata: metadata.clone(),
+        variables,
         exposures,
     };
 
     let path = get_exposure_templates_directory().join(format!("{}.json", id));
     let content = serde_json::to_string_pretty(&template)
-        .map_err(|e| format!("Failed to serialize template: {}", e))?;
+        .map_err(|e| AppError::Internal(format!("Failed to serialize template: {}", e)))?;
 
     fs::write(&path, content)
         .await
-        .map_err(|e| format!("Failed to save template: {}", e))?;
+        .context("Failed to save template")?;
 
     Ok(metadata)
 }
 
 /// Load exposure set template
-pub async fn load_exposure_set_template(id: &str) -> Result<ExposureSetTemplate, String> {
+pub async fn load_exposure_set_template(id: &str) -> AppResult<ExposureSetTemplate> {
+    if let Some(template) = builtin_templates::exposure_templates()
+        .into_iter()
+        .find(|t| t.metadata.id == id)
+    {
+        return Ok(template);
+    }
+
     let path = get_exposure_templates_directory().join(format!("{}.json", id));
 
     let content = fs::read_to_string(&path)
         .await
-        .map_err(|e| format!("Failed to read template: {}", e))?;
+        .context("Failed to read template")?;
 
-    serde_json::from_str(&content).map_err(|e| format!("Failed to parse template: {}", e))
+    serde_json::from_str(&content)
+        .map_err(|e| AppError::Parse(format!("Failed to parse template: {}", e)))
 }
 
 /// List exposure set templates
-pub async fn list_exposure_set_templates() -> Result<Vec<TemplateMetadata>, String> {
+pub async fn list_exposure_set_templates() -> AppResult<Vec<TemplateMetadata>> {
     let dir = get_exposure_templates_directory();
 
+    let mut templates: Vec<TemplateMetadata> = builtin_templates::exposure_templates()
+        .into_iter()
+        .map(|t| t.metadata)
+        .collect();
     if !dir.exists() {
-        return Ok(Vec::new());
+        return Ok(templates);
     }
 
-    let mut templates = Vec::new();
     let mut entries = fs::read_dir(&dir)
         .await
-        .map_err(|e| format!("Failed to read templates directory: {}", e))?;
+        .context("Failed to read templates directory")?;
 
-    while let Some(entry) = entries.next_entry().await.map_err(|e| e.to_string())? {
+    while let Some(entry) = entries.next_entry().await? {
         let path = entry.path();
         if path.extension().and_then(|e| e.to_str()) == Some("json") {
             if let Ok(content) = fs::read_to_string(&path).await {
@@ -355,3 +440,394 @@ pub async fn list_exposure_set_templates() -> Result<Vec<TemplateMetadata>, Stri
     templates.sort_by(|a, b| a.name.cmp(&b.name));
     Ok(templates)
 }
+
+/// Check that a target template is valid once its defaults are filled in
+pub fn check_target_template(target: &Value, variables: &[TemplateVariable]) -> AppResult<()> {
+    let defaults = resolve_variables(variables, &HashMap::new(), true)?;
+    let resolved = substitute_variables(target, &defaults)?;
+    serde_json::from_value::<SimpleTarget>(resolved)
+        .map_err(|e| AppError::InvalidInput(format!("Invalid target template: {}", e)))?;
+    Ok(())
+}
+
+/// Check exposure set template contents like [`check_target_template`]
+pub fn check_exposure_set_template(
+    exposures: &[Value],
+    variables: &[TemplateVariable],
+) -> AppResult<()> {
+    let defaults = resolve_variables(variables, &HashMap::new(), true)?;
+    for exposure in exposures {
+        let resolved = substitute_variables(exposure, &defaults)?;
+        serde_json::from_value::<SimpleExposure>(resolved)
+            .map_err(|e| AppError::InvalidInput(format!("Invalid exposure template: {}", e)))?;
+    }
+    Ok(())
+}
+
+/// Save a user copy of a built-in template. The copy is named
+/// "<name> (copy)" unless `name` is given.
+pub async fn clone_builtin_template(id: &str, name: Option<&str>) -> AppResult<TemplateMetadata> {
+    let copy_name = |metadata: &TemplateMetadata| {
+        name.map(str::to_string)
+            .unwrap_or_else(|| format!("{} (copy)", metadata.name))
+    };
+
+    if let Some(template) = builtin_templates::simple_sequence_templates()
+        .into_iter()
+        .find(|t| t.metadata.id == id)
+    {
+        let metadata = template.metadata;
+        return save_simple_sequence_template(
+            &copy_name(&metadata),
+            &metadata.description,
+            &metadata.category,
+            metadata.tags.clone(),
+            template.sequence,
+        )
+        .await;
+    }
+    if let Some(template) = builtin_templates::target_templates()
+        .into_iter()
+        .find(|t| t.metadata.id == id)
+    {
+        let metadata = template.metadata;
+        return save_target_template(
+            &copy_name(&metadata),
+            &metadata.description,
+            metadata.tags.clone(),
+            template.target,
+            template.variables,
+        )
+        .await;
+    }
+    if let Some(template) = builtin_templates::exposure_templates()
+        .into_iter()
+        .find(|t| t.metadata.id == id)
+    {
+        let metadata = template.metadata;
+        return save_exposure_set_template(
+            &copy_name(&metadata),
+            &metadata.description,
+            metadata.tags.clone(),
+            template.exposures,
+            template.variables,
+        )
+        .await;
+    }
+
+    Err(AppError::NotFound(format!(
+        "Builtin template not found: {}",
+        id
+    )))
+}
+
+// ============================================================================
+// Template Variables
+// ============================================================================
+
+/// Resolve variable values, falling back to defaults and coercing to the
+/// declared type. With `placeholder_missing`, variables without a value or
+/// default resolve to a neutral value of their type, which is enough to
+/// check the shape of a template when it is saved.
+pub fn resolve_variables(
+    variables: &[TemplateVariable],
+    values: &HashMap<String, Value>,
+    placeholder_missing: bool,
+) -> AppResult<HashMap<String, Value>> {
+    let mut resolved = HashMap::new();
+
+    for variable in variables {
+        if variable.name.is_empty()
+            || !variable
+                .name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_')
+        {
+            return Err(AppError::InvalidInput(format!(
+                "Invalid template variable name: '{}'",
+                variable.name
+            )));
+        }
+
+        let value = match values.get(&variable.name).or(variable.default.as_ref()) {
+            Some(value) => coerce_variable(variable, value)?,
+            None if placeholder_missing => match variable.var_type {
+                TemplateVariableType::String => Value::String(String::new()),
+                TemplateVariableType::Number | TemplateVariableType::Integer => Value::from(0),
+                TemplateVariableType::Boolean => Value::Bool(false),
+            },
+            None => {
+                return Err(AppError::InvalidInput(format!(
+                    "Missing value for template variable '{}'",
+                    variable.name
+                )))
+            }
+        };
+        resolved.insert(variable.name.clone(), value);
+    }
+
+    Ok(resolved)
+}
+
+fn coerce_variable(variable: &TemplateVariable, value: &Value) -> AppResult<Value> {
+    let invalid = || {
+        AppError::InvalidInput(format!(
+            "Template variable '{}' expects a {:?} value, got {}",
+            variable.name, variable.var_type, value
+        ))
+    };
+    let text = value.as_str().map(str::trim);
+
+    match variable.var_type {
+        TemplateVariableType::String => Ok(match value {
+            Value::String(_) => value.clone(),
+            other => Value::String(other.to_string()),
+        }),
+        // Whole numbers stay integers so they also fit integer fields
+        TemplateVariableType::Number => value
+            .as_f64()
+            .or_else(|| text.and_then(|t| t.parse().ok()))
+            .filter(|n| n.is_finite())
+            .map(|n| {
+                if n.fract() == 0.0 && n.abs() < i64::MAX as f64 {
+                    Value::from(n as i64)
+                } else {
+                    Value::from(n)
+                }
+            })
+            .ok_or_else(invalid),
+        TemplateVariableType::Integer => value
+            .as_i64()
+            .or_else(|| text.and_then(|t| t.parse().ok()))
+            .map(Value::from)
+            .ok_or_else(invalid),
+        TemplateVariableType::Boolean => value
+            .as_bool()
+            .or_else(|| text.and_then(|t| t.parse().ok()))
+            .map(Value::Bool)
+            .ok_or_else(invalid),
+    }
+}
+
+/// Replace `{{name}}` placeholders in every string of `value`. A string
+/// that is a single placeholder takes the variable's typed value; otherwise
+/// the value is spliced into the text.
+pub fn substitute_variables(value: &Value, variables: &HashMap<String, Value>) -> AppResult<Value> {
+    Ok(match value {
+        Value::String(text) => substitute_text(text, variables)?,
+        Value::Array(items) => Value::Array(
+            items
+                .iter()
+                .map(|item| substitute_variables(item, variables))
+                .collect::<Result<_, _>>()?,
+        ),
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .map(|(key, item)| Ok((key.clone(), substitute_variables(item, variables)?)))
+                .collect::<AppResult<_>>()?,
+        ),
+        other => other.clone(),
+    })
+}
+
+fn substitute_text(text: &str, variables: &HashMap<String, Value>) -> AppResult<Value> {
+    let lookup = |name: &str| {
+        variables
+            .get(name)
+            .ok_or_else(|| AppError::InvalidInput(format!("Unknown template variable '{}'", name)))
+    };
+
+    let mut output = String::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("{{") {
+        let Some(end) = rest[start..].find("}}") else {
+            break;
+        };
+        let name = rest[start + 2..start + end].trim();
+        let value = lookup(name)?;
+
+        // The whole string is one placeholder: keep the value's type
+        if output.is_empty() && start == 0 && start + end + 2 == rest.len() {
+            return Ok(value.clone());
+        }
+
+        output.push_str(&rest[..start]);
+        match value {
+            Value::String(s) => output.push_str(s),
+            other => output.push_str(&other.to_string()),
+        }
+        rest = &rest[start + end + 2..];
+    }
+    output.push_str(rest);
+
+    Ok(Value::String(output))
+}
+
+/// Instantiate a target template with fresh IDs and reset progress
+pub fn instantiate_target_template(
+    template: &TargetTemplate,
+    values: &HashMap<String, Value>,
+) -> AppResult<SimpleTarget> {
+    let variables = resolve_variables(&template.variables, values, false)?;
+    let resolved = substitute_variables(&template.target, &variables)?;
+    let mut target: SimpleTarget = serde_json::from_value(resolved).map_err(|e| {
+        AppError::InvalidInput(format!("Failed to instantiate target template: {}", e))
+    })?;
+
+    target.id = uuid::Uuid::new_v4().to_string();
+    target.status = SequenceEntityStatus::Created;
+    for exposure in &mut target.exposures {
+        reset_exposure(exposure);
+    }
+
+    Ok(target)
+}
+
+/// Instantiate an exposure set template with fresh IDs and reset progress
+pub fn instantiate_exposure_template(
+    template: &ExposureSetTemplate,
+    values: &HashMap<String, Value>,
+) -> AppResult<Vec<SimpleExposure>> {
+    let variables = resolve_variables(&template.variables, values, false)?;
+    template
+        .exposures
+        .iter()
+        .map(|exposure| {
+            let resolved = substitute_variables(exposure, &variables)?;
+            let mut exposure: SimpleExposure = serde_json::from_value(resolved).map_err(|e| {
+                AppError::InvalidInput(format!("Failed to instantiate exposure template: {}", e))
+            })?;
+            reset_exposure(&mut exposure);
+            Ok(exposure)
+        })
+        .collect()
+}
+
+fn reset_exposure(exposure: &mut SimpleExposure) {
+    exposure.id = uuid::Uuid::new_v4().to_string();
+    exposure.progress_count = 0;
+    exposure.status = SequenceEntityStatus::Created;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorCode;
+    use crate::models::FilterInfo;
+    use serde_json::json;
+
+    fn variable(
+        name: &str,
+        var_type: TemplateVariableType,
+        default: Option<Value>,
+    ) -> TemplateVariable {
+        TemplateVariable {
+            name: name.to_string(),
+            var_type,
+            default,
+            description: None,
+        }
+    }
+
+    fn metadata() -> TemplateMetadata {
+        TemplateMetadata {
+            id: "template".to_string(),
+            name: "LRGB standard".to_string(),
+            description: String::new(),
+            category: "exposure".to_string(),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            tags: Vec::new(),
+            is_builtin: false,
+        }
+    }
+
+    #[test]
+    fn test_substitute_variables() {
+        let variables = HashMap::from([
+            ("exposure_time".to_string(), json!(300)),
+            ("filter".to_string(), json!("Ha")),
+        ]);
+        let value = json!({
+            "exposureTime": "{{exposure_time}}",
+            "name": "{{ filter }} {{exposure_time}}s",
+            "nested": ["{{filter}}", 1, true],
+        });
+
+        let result = substitute_variables(&value, &variables).unwrap();
+        assert_eq!(
+            result,
+            json!({ "exposureTime": 300, "name": "Ha 300s", "nested": ["Ha", 1, true] })
+        );
+        let error = substitute_variables(&json!("{{gain}}"), &variables).unwrap_err();
+        assert_eq!(error.code(), ErrorCode::InvalidInput);
+        assert!(error.to_string().contains("gain"));
+    }
+
+    #[test]
+    fn test_resolve_variables() {
+        let variables = vec![
+            variable(
+                "exposure_time",
+                TemplateVariableType::Number,
+                Some(json!(120)),
+            ),
+            variable("count", TemplateVariableType::Integer, None),
+            variable("dither", TemplateVariableType::Boolean, Some(json!("true"))),
+        ];
+
+        let values = HashMap::from([("count".to_string(), json!("20"))]);
+        let resolved = resolve_variables(&variables, &values, false).unwrap();
+        assert_eq!(resolved["exposure_time"], json!(120));
+        assert_eq!(resolved["count"], json!(20));
+        assert_eq!(resolved["dither"], json!(true));
+
+        assert!(resolve_variables(&variables, &HashMap::new(), false).is_err());
+        assert_eq!(
+            resolve_variables(&variables, &HashMap::new(), true).unwrap()["count"],
+            json!(0)
+        );
+        let bad = HashMap::from([("count".to_string(), json!("many"))]);
+        assert!(resolve_variables(&variables, &bad, false).is_err());
+    }
+
+    #[test]
+    fn test_instantiate_exposure_template() {
+        let mut exposure = serde_json::to_value(SimpleExposure {
+            filter: Some(FilterInfo::default()),
+            progress_count: 4,
+            ..Default::default()
+        })
+        .unwrap();
+        exposure["exposureTime"] = json!("{{exposure_time}}");
+        exposure["totalCount"] = json!("{{count}}");
+        exposure["filter"]["name"] = json!("{{filter}}");
+
+        let template = ExposureSetTemplate {
+            metadata: metadata(),
+            variables: vec![
+                variable(
+                    "exposure_time",
+                    TemplateVariableType::Number,
+                    Some(json!(60)),
+                ),
+                variable("count", TemplateVariableType::Number, Some(json!(10))),
+                variable("filter", TemplateVariableType::String, None),
+            ],
+            exposures: vec![exposure],
+        };
+
+        let values = HashMap::from([
+            ("filter".to_string(), json!("L")),
+            ("exposure_time".to_string(), json!(180.5)),
+        ]);
+        let exposures = instantiate_exposure_template(&template, &values).unwrap();
+        assert_eq!(exposures[0].exposure_time, 180.5);
+        assert_eq!(exposures[0].total_count, 10);
+        assert_eq!(exposures[0].progress_count, 0);
+        assert_eq!(exposures[0].filter.as_ref().unwrap().name, "L");
+
+        // The filter has no default
+        assert!(instantiate_exposure_template(&template, &HashMap::new()).is_err());
+    }
+}
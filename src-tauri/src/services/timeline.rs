@@ -0,0 +1,407 @@
+//! Sequence timeline service
+//!
+//! Turns a simulated night into Gantt-style segments with absolute
+//! timestamps: twilight bands, target blocks, exposure blocks and point
+//! events such as meridian flips and autofocus runs. The frontend only
+//! has to lay the segments out by lane.
+
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::models::{EquipmentProfile, SimpleSequence};
+use crate::services::astronomy::ObserverLocation;
+use crate::services::dark_calendar;
+use crate::services::simulator::{
+    simulate_sequence, SimulationEvent, SimulationEventKind, SimulationOptions,
+};
+
+/// Lane holding the twilight bands
+pub const SKY_LANE: &str = "sky";
+
+/// Lane holding sequence start/end events (cooling, parking)
+pub const SEQUENCE_LANE: &str = "sequence";
+
+/// Kind of timeline segment
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TimelineSegmentKind {
+    /// Sun between 0° and -6°
+    CivilTwilight,
+    /// Sun between -6° and -12°
+    NauticalTwilight,
+    /// Sun between -12° and -18°
+    AstronomicalTwilight,
+    /// Sun below -18°
+    Night,
+    /// Whole time spent on a target, from slew to last exposure
+    Target,
+    /// Consecutive frames of one exposure row
+    Exposure,
+    Slew,
+    Centering,
+    Autofocus,
+    MeridianFlip,
+    FilterChange,
+    Wait,
+    /// Camera cooling/warming, unpark and park
+    Setup,
+}
+
+/// Renderable timeline segment
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TimelineSegment {
+    pub kind: TimelineSegmentKind,
+    /// Row the segment is drawn in: a target id, [`SKY_LANE`] or
+    /// [`SEQUENCE_LANE`]
+    pub lane: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exposure_id: Option<String>,
+    pub label: String,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    /// Number of frames in an exposure block
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub frame_count: Option<u32>,
+}
+
+/// Timeline lane description, in display order
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TimelineLane {
+    pub id: String,
+    pub label: String,
+}
+
+/// Timeline of a scheduled night
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SequenceTimeline {
+    pub date: String,
+    /// Visible range: the night plus the simulated sequence
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    pub lanes: Vec<TimelineLane>,
+    pub segments: Vec<TimelineSegment>,
+    pub warnings: Vec<String>,
+}
+
+/// Build the timeline for running `sequence` on the night of `date`
+pub fn build_sequence_timeline(
+    sequence: &SimpleSequence,
+    location: &ObserverLocation,
+    date: NaiveDate,
+    profile: Option<&EquipmentProfile>,
+    options: &SimulationOptions,
+) -> SequenceTimeline {
+    let simulation = simulate_sequence(sequence, location, date, profile, options);
+
+    let mut segments = twilight_segments(location, date);
+    let mut lanes = vec![
+        TimelineLane {
+            id: SKY_LANE.to_string(),
+            label: "Sky".to_string(),
+        },
+        TimelineLane {
+            id: SEQUENCE_LANE.to_string(),
+            label: sequence.title.clone(),
+        },
+    ];
+
+    for target in &sequence.targets {
+        let events: Vec<&SimulationEvent> = simulation
+            .events
+            .iter()
+            .filter(|e| e.target_id.as_deref() == Some(target.id.as_str()))
+            .collect();
+        let (Some(first), Some(last)) = (events.first(), events.last()) else {
+            continue;
+        };
+
+        lanes.push(TimelineLane {
+            id: target.id.clone(),
+            label: target.target_name.clone(),
+        });
+
+        // The block starts once waiting for the target is over
+        let block_start = events
+            .iter()
+            .find(|e| e.kind != SimulationEventKind::Wait)
+            .map(|e| e.start)
+            .unwrap_or(first.start);
+        segments.push(TimelineSegment {
+            kind: TimelineSegmentKind::Target,
+            lane: target.id.clone(),
+            target_id: Some(target.id.clone()),
+            exposure_id: None,
+            label: target.target_name.clone(),
+            start: block_start,
+            end: last.end,
+            frame_count: None,
+        });
+
+        segments.extend(exposure_blocks(&target.id, &events));
+    }
+
+    for event in &simulation.events {
+        let Some((kind, label)) = event_segment(event) else {
+            continue;
+        };
+        let lane = event
+            .target_id
+            .clone()
+            .unwrap_or_else(|| SEQUENCE_LANE.to_string());
+        segments.push(TimelineSegment {
+            kind,
+            lane,
+            target_id: event.target_id.clone(),
+            exposure_id: None,
+            label,
+            start: event.start,
+            end: event.end,
+            frame_count: None,
+        });
+    }
+
+    let start = segments
+        .iter()
+        .map(|s| s.start)
+        .min()
+        .unwrap_or(simulation.start_time);
+    let end = segments
+        .iter()
+        .map(|s| s.end)
+        .max()
+        .unwrap_or(simulation.end_time);
+
+    SequenceTimeline {
+        date: date.format("%Y-%m-%d").to_string(),
+        start,
+        end,
+        lanes,
+        segments,
+        warnings: simulation.warnings,
+    }
+}
+
+/// Twilight bands from sunset on `date` to sunrise the next morning
+fn twilight_segments(location: &ObserverLocation, date: NaiveDate) -> Vec<TimelineSegment> {
+    let twilight = dark_calendar::night_twilight(location, date);
+
+    let bands = [
+        (
+            TimelineSegmentKind::CivilTwilight,
+            "Civil twilight",
+            twilight.sunset,
+            twilight.civil_dusk,
+        ),
+        (
+            TimelineSegmentKind::NauticalTwilight,
+            "Nautical twilight",
+            twilight.civil_dusk,
+            twilight.nautical_dusk,
+        ),
+        (
+            TimelineSegmentKind::AstronomicalTwilight,
+            "Astronomical twilight",
+            twilight.nautical_dusk,
+            twilight.astronomical_dusk,
+        ),
+        (
+            TimelineSegmentKind::Night,
+            "Night",
+            twilight.astronomical_dusk,
+            twilight.astronomical_dawn,
+        ),
+        (
+            TimelineSegmentKind::AstronomicalTwilight,
+            "Astronomical twilight",
+            twilight.astronomical_dawn,
+            twilight.nautical_dawn,
+        ),
+        (
+            TimelineSegmentKind::NauticalTwilight,
+            "Nautical twilight",
+            twilight.nautical_dawn,
+            twilight.civil_dawn,
+        ),
+        (
+            TimelineSegmentKind::CivilTwilight,
+            "Civil twilight",
+            twilight.civil_dawn,
+            twilight.sunrise,
+        ),
+    ];
+
+    bands
+        .into_iter()
+        .filter_map(|(kind, label, start, end)| match (start, end) {
+            (Some(start), Some(end)) if end > start && end - start < Duration::hours(24) => {
+                Some(TimelineSegment {
+                    kind,
+                    lane: SKY_LANE.to_string(),
+                    target_id: None,
+                    exposure_id: None,
+                    label: label.to_string(),
+                    start,
+                    end,
+                    frame_count: None,
+                })
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// Merge runs of frames from the same exposure row into blocks. Dithers
+/// between frames stay inside a block; anything else ends it.
+fn exposure_blocks(target_id: &str, events: &[&SimulationEvent]) -> Vec<TimelineSegment> {
+    let mut blocks: Vec<TimelineSegment> = Vec::new();
+    let mut open = false;
+
+    for event in events {
+        match event.kind {
+            SimulationEventKind::Exposure => {
+                let extends = open
+                    && blocks
+                        .last()
+                        .is_some_and(|b| b.exposure_id == event.exposure_id);
+                if extends {
+                    let block = blocks.last_mut().unwrap();
+                    block.end = event.end;
+                    block.frame_count = block.frame_count.map(|n| n + 1);
+                    block.label = exposure_label(&event.description, block.frame_count);
+                } else {
+                    blocks.push(TimelineSegment {
+                        kind: TimelineSegmentKind::Exposure,
+                        lane: target_id.to_string(),
+                        target_id: Some(target_id.to_string()),
+                        exposure_id: event.exposure_id.clone(),
+                        label: exposure_label(&event.description, Some(1)),
+                        start: event.start,
+                        end: event.end,
+                        frame_count: Some(1),
+                    });
+                }
+                open = true;
+            }
+            SimulationEventKind::Dither => {}
+            _ => open = false,
+        }
+    }
+
+    blocks
+}
+
+fn exposure_label(description: &str, frames: Option<u32>) -> String {
+    format!("{} × {}", frames.unwrap_or(1), description)
+}
+
+/// Segment kind and label for a point event, None for events drawn as
+/// part of target or exposure blocks
+fn event_segment(event: &SimulationEvent) -> Option<(TimelineSegmentKind, String)> {
+    let kind = match event.kind {
+        SimulationEventKind::Slew => TimelineSegmentKind::Slew,
+        SimulationEventKind::Center | SimulationEventKind::Rotate => TimelineSegmentKind::Centering,
+        SimulationEventKind::Autofocus => TimelineSegmentKind::Autofocus,
+        SimulationEventKind::MeridianFlip => TimelineSegmentKind::MeridianFlip,
+        SimulationEventKind::FilterChange => TimelineSegmentKind::FilterChange,
+        SimulationEventKind::Wait => TimelineSegmentKind::Wait,
+        SimulationEventKind::CoolCamera
+        | SimulationEventKind::Unpark
+        | SimulationEventKind::Park
+        | SimulationEventKind::WarmCamera => TimelineSegmentKind::Setup,
+        SimulationEventKind::Delay
+        | SimulationEventKind::StartGuiding
+        | SimulationEventKind::Exposure
+        | SimulationEventKind::Dither => return None,
+    };
+    Some((kind, event.description.clone()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Coordinates, FilterInfo, SimpleExposure};
+
+    fn test_location() -> ObserverLocation {
+        ObserverLocation {
+            latitude: 40.0,
+            longitude: -74.0,
+            elevation: 0.0,
+            timezone_offset: -5,
+            sky_brightness: None,
+            corrections: Default::default(),
+        }
+    }
+
+    fn test_sequence() -> SimpleSequence {
+        let mut seq = SimpleSequence::new("Timeline");
+        let target = &mut seq.targets[0];
+        target.target_name = "M31".to_string();
+        target.coordinates = Coordinates::from_decimal(0.712, 41.27);
+        target.exposures = ["Ha", "OIII"]
+            .iter()
+            .map(|name| SimpleExposure {
+                filter: Some(FilterInfo {
+                    name: name.to_string(),
+                    ..Default::default()
+                }),
+                total_count: 5,
+                dither: true,
+                ..Default::default()
+            })
+            .collect();
+        seq
+    }
+
+    #[test]
+    fn test_timeline_segments() {
+        let seq = test_sequence();
+        let date = NaiveDate::from_ymd_opt(2024, 10, 15).unwrap();
+        let timeline = build_sequence_timeline(
+            &seq,
+            &test_location(),
+            date,
+            None,
+            &SimulationOptions::default(),
+        );
+
+        let count = |kind| timeline.segments.iter().filter(|s| s.kind == kind).count();
+        assert_eq!(count(TimelineSegmentKind::Night), 1);
+        assert_eq!(count(TimelineSegmentKind::CivilTwilight), 2);
+        assert_eq!(count(TimelineSegmentKind::Target), 1);
+        assert_eq!(count(TimelineSegmentKind::Autofocus), 1);
+
+        // The local night straddles 00:00 UTC west of Greenwich
+        let night = timeline
+            .segments
+            .iter()
+            .find(|s| s.kind == TimelineSegmentKind::Night)
+            .unwrap();
+        let midnight = DateTime::<Utc>::from_naive_utc_and_offset(
+            date.succ_opt().unwrap().and_hms_opt(0, 0, 0).unwrap(),
+            Utc,
+        );
+        assert!(night.start > midnight - Duration::hours(1));
+        assert!(night.start < midnight + Duration::hours(1));
+        assert!(night.end > midnight + Duration::hours(9));
+        assert!(night.end < midnight + Duration::hours(11));
+
+        // Dithered frames of each row merge into one block per filter
+        let blocks: Vec<_> = timeline
+            .segments
+            .iter()
+            .filter(|s| s.kind == TimelineSegmentKind::Exposure)
+            .collect();
+        assert_eq!(blocks.len(), 2);
+        assert!(blocks.iter().all(|b| b.frame_count == Some(5)));
+        assert!(blocks[0].end <= blocks[1].start);
+
+        assert_eq!(timeline.lanes.len(), 3);
+        assert!(timeline.segments.iter().all(|s| s.start >= timeline.start));
+        assert!(timeline.segments.iter().all(|s| s.end <= timeline.end));
+    }
+}
@@ -0,0 +1,98 @@
+//! Unit and locale aware formatting
+//!
+//! Formats numbers, times of day, lengths and temperatures according to
+//! the user's [`UnitPreferences`].
+
+use chrono::Timelike;
+
+use crate::models::{ClockFormat, UnitPreferences, UnitSystem};
+
+const FEET_PER_METER: f64 = 3.280_84;
+
+/// Replace the decimal point of already formatted numbers
+pub fn localize_decimal(text: &str, units: &UnitPreferences) -> String {
+    if units.decimal_separator == '.' {
+        text.to_string()
+    } else {
+        text.replace('.', &units.decimal_separator.to_string())
+    }
+}
+
+/// Format a number with a fixed number of decimals
+pub fn format_number(value: f64, decimals: usize, units: &UnitPreferences) -> String {
+    localize_decimal(&format!("{:.*}", decimals, value), units)
+}
+
+/// Format a time of day in the preferred clock format
+pub fn format_clock(time: &impl Timelike, with_seconds: bool, units: &UnitPreferences) -> String {
+    let (hour, suffix) = match units.clock_format {
+        ClockFormat::TwentyFourHour => (time.hour(), ""),
+        ClockFormat::TwelveHour => {
+            let (pm, hour) = time.hour12();
+            (hour, if pm { " PM" } else { " AM" })
+        }
+    };
+
+    let mut text = format!("{:02}:{:02}", hour, time.minute());
+    if with_seconds {
+        text.push_str(&format!(":{:02}", time.second()));
+    }
+    text.push_str(suffix);
+    text
+}
+
+/// Format a length given in meters, e.g. a site elevation
+pub fn format_length(meters: f64, units: &UnitPreferences) -> String {
+    match units.unit_system {
+        UnitSystem::Metric => format!("{} m", format_number(meters, 0, units)),
+        UnitSystem::Imperial => format!("{} ft", format_number(meters * FEET_PER_METER, 0, units)),
+    }
+}
+
+/// Format a temperature given in degrees Celsius
+pub fn format_temperature(celsius: f64, units: &UnitPreferences) -> String {
+    match units.unit_system {
+        UnitSystem::Metric => format!("{} °C", format_number(celsius, 1, units)),
+        UnitSystem::Imperial => {
+            format!("{} °F", format_number(celsius * 9.0 / 5.0 + 32.0, 1, units))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveTime;
+
+    #[test]
+    fn test_locale_formats() {
+        let german = UnitPreferences::for_locale("de-DE");
+        assert_eq!(german.decimal_separator, ',');
+        assert_eq!(german.csv_delimiter, ';');
+        assert_eq!(german.clock_format, ClockFormat::TwentyFourHour);
+        assert_eq!(format_number(1234.567, 2, &german), "1234,57");
+        assert_eq!(format_temperature(-2.5, &german), "-2,5 °C");
+
+        let us = UnitPreferences::for_locale("en_US");
+        assert_eq!(us.unit_system, UnitSystem::Imperial);
+        assert_eq!(format_length(1000.0, &us), "3281 ft");
+        assert_eq!(format_temperature(0.0, &us), "32.0 °F");
+
+        assert_eq!(
+            UnitPreferences::for_locale("en-GB").clock_format,
+            ClockFormat::TwentyFourHour
+        );
+    }
+
+    #[test]
+    fn test_format_clock() {
+        let time = NaiveTime::from_hms_opt(21, 5, 9).unwrap();
+        let mut units = UnitPreferences::default();
+        assert_eq!(format_clock(&time, true, &units), "21:05:09");
+
+        units.clock_format = ClockFormat::TwelveHour;
+        assert_eq!(format_clock(&time, false, &units), "09:05 PM");
+        let midnight = NaiveTime::from_hms_opt(0, 30, 0).unwrap();
+        assert_eq!(format_clock(&midnight, false, &units), "12:30 AM");
+    }
+}
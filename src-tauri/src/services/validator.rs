@@ -1,17 +1,1031 @@
 //! Validation service for sequences and targets
+//!
+//! Checks are named rules with a default severity and, for some, a
+//! threshold. Users can disable rules or override severity and threshold
+//! through [`ValidationRuleConfig`]s stored in the settings; every reported
+//! issue carries the id of the rule that raised it.
+
+use std::collections::{HashMap, HashSet};
+
+use serde::{Deserialize, Serialize};
 
 use crate::models::*;
+use crate::services::nina_type_registry::{self, NinaTypeSchema};
+use crate::services::sampling;
+
+// ============================================================================
+// Rules
+// ============================================================================
+
+/// Built-in validation rule
+#[derive(Debug, Clone, Copy)]
+pub struct ValidationRule {
+    pub id: &'static str,
+    pub name: &'static str,
+    pub description: &'static str,
+    pub severity: ValidationSeverity,
+    /// Default threshold for rules that compare against a limit
+    pub threshold: Option<f64>,
+    pub threshold_unit: Option<&'static str>,
+}
+
+const fn rule(
+    id: &'static str,
+    name: &'static str,
+    description: &'static str,
+    severity: ValidationSeverity,
+) -> ValidationRule {
+    ValidationRule {
+        id,
+        name,
+        description,
+        severity,
+        threshold: None,
+        threshold_unit: None,
+    }
+}
+
+const fn threshold_rule(
+    id: &'static str,
+    name: &'static str,
+    description: &'static str,
+    severity: ValidationSeverity,
+    threshold: f64,
+    unit: &'static str,
+) -> ValidationRule {
+    ValidationRule {
+        id,
+        name,
+        description,
+        severity,
+        threshold: Some(threshold),
+        threshold_unit: Some(unit),
+    }
+}
+
+use ValidationSeverity::{Error, Info, Warning};
+
+/// All built-in rules
+pub const VALIDATION_RULES: &[ValidationRule] = &[
+    rule(
+        "sequence.title",
+        "Sequence title",
+        "The sequence must have a title",
+        Error,
+    ),
+    rule(
+        "sequence.targets",
+        "Sequence targets",
+        "The sequence must contain at least one target",
+        Error,
+    ),
+    rule(
+        "sequence.duplicate-target",
+        "Duplicate targets",
+        "Two targets share the same name",
+        Info,
+    ),
+    threshold_rule(
+        "sequence.max-runtime",
+        "Long sequence",
+        "Total runtime exceeds the threshold",
+        Info,
+        12.0,
+        "hours",
+    ),
+    rule(
+        "target.name",
+        "Target name",
+        "Every target must have a name",
+        Error,
+    ),
+    rule(
+        "target.coordinates",
+        "Target coordinates",
+        "Coordinates must be within range",
+        Error,
+    ),
+    rule(
+        "target.integration-limits",
+        "Integration limits",
+        "Minimum and maximum integration must be consistent",
+        Error,
+    ),
+    rule(
+        "target.constraints",
+        "Observing constraints",
+        "Constraint ranges must be valid and consistent",
+        Error,
+    ),
+    rule(
+        "target.no-exposures",
+        "Target without exposures",
+        "A target has no enabled exposures",
+        Warning,
+    ),
+    rule(
+        "exposure.time",
+        "Exposure time",
+        "Exposure time must be positive",
+        Error,
+    ),
+    rule(
+        "exposure.count",
+        "Exposure counts",
+        "Total and progress counts cannot be negative",
+        Error,
+    ),
+    rule(
+        "exposure.dither",
+        "Dither interval",
+        "Dither every must be at least 1",
+        Error,
+    ),
+    threshold_rule(
+        "exposure.max-time",
+        "Long exposure",
+        "Exposure time exceeds the threshold",
+        Warning,
+        1200.0,
+        "seconds",
+    ),
+    threshold_rule(
+        "exposure.sampling",
+        "Sampling",
+        "Binned image scale is far too coarse or fine for the threshold seeing",
+        Warning,
+        2.5,
+        "arcsec",
+    ),
+    rule(
+        "filter.unknown",
+        "Unknown filter",
+        "Filter is not in the active filter set",
+        Warning,
+    ),
+    rule(
+        "filter.position",
+        "Filter position",
+        "Filter position differs from the active filter set",
+        Warning,
+    ),
+    rule(
+        "filter.focus-offset",
+        "Missing focus offset",
+        "A target changes filters without refocusing and a filter has no focus offset",
+        Warning,
+    ),
+    rule(
+        "editor.title",
+        "Editor sequence title",
+        "The editor sequence must have a title",
+        Error,
+    ),
+    rule(
+        "editor.item",
+        "Item name and type",
+        "Every sequence item needs a name and a type",
+        Error,
+    ),
+    rule(
+        "editor.empty-container",
+        "Empty container",
+        "A container has no items",
+        Info,
+    ),
+    rule(
+        "editor.unknown-type",
+        "Unknown NINA type",
+        "Item, condition or trigger type is not a known NINA type",
+        Warning,
+    ),
+    rule(
+        "editor.loop-condition",
+        "Loop conditions",
+        "A container would never run or may run forever",
+        Warning,
+    ),
+    rule(
+        "editor.item-placement",
+        "Triggers and conditions placement",
+        "Only containers can have triggers and conditions",
+        Error,
+    ),
+    rule(
+        "editor.smart-exposure",
+        "Smart Exposure settings",
+        "A Smart Exposure needs a Take Exposure item with an exposure time",
+        Error,
+    ),
+    rule(
+        "editor.coordinates",
+        "Item coordinates",
+        "RA/Dec coordinates must be within range",
+        Error,
+    ),
+    rule(
+        "editor.field-range",
+        "Field ranges",
+        "Numeric fields must be within the range of the type schema",
+        Error,
+    ),
+];
+
+/// Look up a built-in rule
+pub fn find_rule(id: &str) -> Option<&'static ValidationRule> {
+    VALIDATION_RULES.iter().find(|r| r.id == id)
+}
+
+/// Rule with the user's overrides applied
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ValidationRuleInfo {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    pub enabled: bool,
+    pub severity: ValidationSeverity,
+    pub default_severity: ValidationSeverity,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub threshold: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default_threshold: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub threshold_unit: Option<String>,
+}
+
+/// List all rules with `configs` applied
+pub fn list_validation_rules(
+    configs: &HashMap<String, ValidationRuleConfig>,
+) -> Vec<ValidationRuleInfo> {
+    VALIDATION_RULES
+        .iter()
+        .map(|rule| {
+            let config = configs.get(rule.id);
+            ValidationRuleInfo {
+                id: rule.id.to_string(),
+                name: rule.name.to_string(),
+                description: rule.description.to_string(),
+                enabled: config.and_then(|c| c.enabled).unwrap_or(true),
+                severity: config.and_then(|c| c.severity).unwrap_or(rule.severity),
+                default_severity: rule.severity,
+                threshold: config.and_then(|c| c.threshold).or(rule.threshold),
+                default_threshold: rule.threshold,
+                threshold_unit: rule.threshold_unit.map(str::to_string),
+            }
+        })
+        .collect()
+}
+
+/// Check a rule override before it is stored
+pub fn validate_rule_config(id: &str, config: &ValidationRuleConfig) -> Result<(), String> {
+    let rule = find_rule(id).ok_or_else(|| format!("Unknown validation rule: {}", id))?;
+    if let Some(threshold) = config.threshold {
+        if rule.threshold.is_none() {
+            return Err(format!("Rule {} has no threshold", id));
+        }
+        if !threshold.is_finite() || threshold < 0.0 {
+            return Err("Threshold must be a non-negative number".to_string());
+        }
+    }
+    Ok(())
+}
+
+/// Collects issues while applying rule configuration
+struct RuleEngine<'a> {
+    configs: &'a HashMap<String, ValidationRuleConfig>,
+    /// Plugin types checked alongside the built-in ones
+    plugins: &'a [NinaTypeSchema],
+    issues: Vec<ValidationIssue>,
+}
+
+impl<'a> RuleEngine<'a> {
+    fn new(configs: &'a HashMap<String, ValidationRuleConfig>) -> Self {
+        Self {
+            configs,
+            plugins: &[],
+            issues: Vec::new(),
+        }
+    }
+
+    fn is_enabled(&self, id: &str) -> bool {
+        self.configs.get(id).and_then(|c| c.enabled).unwrap_or(true)
+    }
+
+    /// Threshold of an enabled rule
+    fn threshold(&self, id: &str) -> Option<f64> {
+        if !self.is_enabled(id) {
+            return None;
+        }
+        self.configs
+            .get(id)
+            .and_then(|c| c.threshold)
+            .or_else(|| find_rule(id).and_then(|r| r.threshold))
+    }
+
+    fn report(
+        &mut self,
+        id: &str,
+        message: impl Into<String>,
+        target_index: Option<usize>,
+        exposure_index: Option<usize>,
+    ) {
+        self.push(id, message.into(), target_index, exposure_index, None);
+    }
+
+    fn report_item(&mut self, id: &str, item_id: &str, message: impl Into<String>) {
+        self.push(id, message.into(), None, None, Some(item_id.to_string()));
+    }
+
+    fn push(
+        &mut self,
+        id: &str,
+        message: String,
+        target_index: Option<usize>,
+        exposure_index: Option<usize>,
+        item_id: Option<String>,
+    ) {
+        if !self.is_enabled(id) {
+            return;
+        }
+        let severity = self
+            .configs
+            .get(id)
+            .and_then(|c| c.severity)
+            .or_else(|| find_rule(id).map(|r| r.severity))
+            .unwrap_or(Error);
+        self.issues.push(ValidationIssue {
+            rule_id: id.to_string(),
+            severity,
+            message,
+            target_index,
+            exposure_index,
+            item_id,
+        });
+    }
+
+    fn finish(self) -> ValidationResult {
+        ValidationResult::from_issues(self.issues)
+    }
+
+    fn check_sequence(&mut self, sequence: &SimpleSequence) {
+        if sequence.title.is_empty() {
+            self.report("sequence.title", "Sequence title is required", None, None);
+        }
+        if sequence.targets.is_empty() {
+            self.report(
+                "sequence.targets",
+                "At least one target is required",
+                None,
+                None,
+            );
+        }
+
+        let mut seen = HashSet::new();
+        for (index, target) in sequence.targets.iter().enumerate() {
+            self.check_target(target, Some(index));
+            let name = target.target_name.trim().to_lowercase();
+            if !name.is_empty() && !seen.insert(name) {
+                self.report(
+                    "sequence.duplicate-target",
+                    format!("Target '{}' appears more than once", target.target_name),
+                    Some(index),
+                    None,
+                );
+            }
+        }
+
+        if let Some(hours) = self.threshold("sequence.max-runtime") {
+            let runtime = sequence.total_runtime() / 3600.0;
+            if runtime > hours {
+                self.report(
+                    "sequence.max-runtime",
+                    format!(
+                        "Sequence runs for {:.1} h, more than {:.1} h",
+                        runtime, hours
+                    ),
+                    None,
+                    None,
+                );
+            }
+        }
+    }
+
+    fn check_target(&mut self, target: &SimpleTarget, index: Option<usize>) {
+        if target.target_name.is_empty() {
+            self.report("target.name", "Target name is required", index, None);
+        }
+        for message in target.coordinates.validate() {
+            self.report("target.coordinates", message, index, None);
+        }
 
-/// Validate a simple sequence
+        if target.min_integration.is_some_and(|v| v < 0.0) {
+            self.report(
+                "target.integration-limits",
+                "Minimum integration cannot be negative",
+                index,
+                None,
+            );
+        }
+        if target.max_integration.is_some_and(|v| v <= 0.0) {
+            self.report(
+                "target.integration-limits",
+                "Maximum integration must be positive",
+                index,
+                None,
+            );
+        }
+        if let (Some(min), Some(max)) = (target.min_integration, target.max_integration) {
+            if min > max {
+                self.report(
+                    "target.integration-limits",
+                    "Minimum integration exceeds maximum integration",
+                    index,
+                    None,
+                );
+            }
+        }
+
+        for message in target.constraints.validate() {
+            self.report("target.constraints", message, index, None);
+        }
+
+        if !target.exposures.iter().any(|e| e.enabled) {
+            self.report(
+                "target.no-exposures",
+                format!("Target '{}' has no enabled exposures", target.target_name),
+                index,
+                None,
+            );
+        }
+
+        for (exposure_index, exposure) in target.exposures.iter().enumerate() {
+            self.check_exposure(exposure, index, Some(exposure_index));
+        }
+    }
+
+    fn check_exposure(
+        &mut self,
+        exposure: &SimpleExposure,
+        target_index: Option<usize>,
+        index: Option<usize>,
+    ) {
+        if exposure.exposure_time <= 0.0 {
+            self.report(
+                "exposure.time",
+                "Exposure time must be positive",
+                target_index,
+                index,
+            );
+        }
+        if exposure.total_count < 0 {
+            self.report(
+                "exposure.count",
+                "Total count cannot be negative",
+                target_index,
+                index,
+            );
+        }
+        if exposure.progress_count < 0 {
+            self.report(
+                "exposure.count",
+                "Progress count cannot be negative",
+                target_index,
+                index,
+            );
+        }
+        if exposure.dither_every < 1 {
+            self.report(
+                "exposure.dither",
+                "Dither every must be at least 1",
+                target_index,
+                index,
+            );
+        }
+        if let Some(max) = self.threshold("exposure.max-time") {
+            if exposure.exposure_time > max {
+                self.report(
+                    "exposure.max-time",
+                    format!(
+                        "Exposure time {:.0} s is longer than {:.0} s",
+                        exposure.exposure_time, max
+                    ),
+                    target_index,
+                    index,
+                );
+            }
+        }
+    }
+
+    fn check_filters(&mut self, sequence: &SimpleSequence, filters: &[FilterInfo]) {
+        for (target_index, target) in sequence.targets.iter().enumerate() {
+            for (i, exposure) in target.exposures.iter().enumerate() {
+                let Some(ref filter) = exposure.filter else {
+                    continue;
+                };
+                if filter.name.is_empty() {
+                    continue;
+                }
+
+                match filters
+                    .iter()
+                    .find(|f| f.name.eq_ignore_ascii_case(&filter.name))
+                {
+                    None => self.report(
+                        "filter.unknown",
+                        format!(
+                            "Target '{}', exposure {}: filter '{}' is not in the active filter set",
+                            target.target_name,
+                            i + 1,
+                            filter.name
+                        ),
+                        Some(target_index),
+                        Some(i),
+                    ),
+                    Some(known) if known.position != filter.position => self.report(
+                        "filter.position",
+                        format!(
+                            "Target '{}', exposure {}: filter '{}' position {} does not match filter set position {}",
+                            target.target_name,
+                            i + 1,
+                            filter.name,
+                            filter.position,
+                            known.position
+                        ),
+                        Some(target_index),
+                        Some(i),
+                    ),
+                    Some(_) => {}
+                }
+            }
+        }
+        self.check_focus_offsets(sequence, filters);
+    }
+
+    /// Light frames whose binned image scale grossly mismatches the seeing
+    /// threshold, reported once per target and binning
+    fn check_sampling(&mut self, sequence: &SimpleSequence, profile: &EquipmentProfile) {
+        let Some(seeing) = self.threshold("exposure.sampling") else {
+            return;
+        };
+        let Ok(analysis) = sampling::analyze_sampling(profile, seeing) else {
+            return;
+        };
+
+        for (target_index, target) in sequence.targets.iter().enumerate() {
+            let mut seen = HashSet::new();
+            for (i, exposure) in target.exposures.iter().enumerate() {
+                let binning = exposure.binning.x.max(exposure.binning.y).max(1);
+                if !exposure.enabled
+                    || exposure.image_type != ImageType::Light
+                    || !seen.insert(binning)
+                {
+                    continue;
+                }
+                let pixels_per_fwhm = analysis.pixels_per_fwhm / binning as f64;
+                if !sampling::is_gross_mismatch(pixels_per_fwhm) {
+                    continue;
+                }
+                let state = if pixels_per_fwhm < sampling::MIN_PIXELS_PER_FWHM {
+                    "undersampled"
+                } else {
+                    "oversampled"
+                };
+                let advice = if analysis.suggested_binning == binning {
+                    "no binning fits better".to_string()
+                } else {
+                    format!(
+                        "binning {0}x{0} suits it better",
+                        analysis.suggested_binning
+                    )
+                };
+                self.report(
+                    "exposure.sampling",
+                    format!(
+                        "Target '{}', exposure {}: binning {}x{} gives {:.2}\"/px, {} at {:.1}\" seeing ({:.1} px per FWHM); {}",
+                        target.target_name,
+                        i + 1,
+                        exposure.binning.x,
+                        exposure.binning.y,
+                        analysis.image_scale * binning as f64,
+                        state,
+                        seeing,
+                        pixels_per_fwhm,
+                        advice
+                    ),
+                    Some(target_index),
+                    Some(i),
+                );
+            }
+        }
+    }
+
+    /// Filters without a focus offset on targets that switch filters but
+    /// don't refocus after a change, reported once per target and filter
+    fn check_focus_offsets(&mut self, sequence: &SimpleSequence, filters: &[FilterInfo]) {
+        for (target_index, target) in sequence.targets.iter().enumerate() {
+            if target.auto_focus_on_filter_change {
+                continue;
+            }
+            let used: Vec<(usize, &FilterInfo)> = target
+                .exposures
+                .iter()
+                .enumerate()
+                .filter(|(_, e)| e.enabled)
+                .filter_map(|(i, e)| e.filter.as_ref().map(|f| (i, f)))
+                .filter(|(_, f)| !f.name.is_empty())
+                .collect();
+            let names: HashSet<String> = used.iter().map(|(_, f)| f.name.to_lowercase()).collect();
+            if names.len() < 2 {
+                continue;
+            }
+
+            let mut reported = HashSet::new();
+            for (i, filter) in used {
+                let offset = filter.focus_offset.or_else(|| {
+                    filters
+                        .iter()
+                        .find(|f| f.name.eq_ignore_ascii_case(&filter.name))
+                        .and_then(|f| f.focus_offset)
+                });
+                if offset.is_none() && reported.insert(filter.name.to_lowercase()) {
+                    self.report(
+                        "filter.focus-offset",
+                        format!(
+                            "Target '{}', exposure {}: filter '{}' has no focus offset and the target does not refocus after filter changes",
+                            target.target_name,
+                            i + 1,
+                            filter.name
+                        ),
+                        Some(target_index),
+                        Some(i),
+                    );
+                }
+            }
+        }
+    }
+
+    fn check_editor_sequence(&mut self, sequence: &EditorSequence) {
+        if sequence.title.is_empty() {
+            self.report("editor.title", "Sequence title is empty", None, None);
+        }
+        for item in sequence.all_items() {
+            self.check_editor_item(item);
+        }
+        for trigger in &sequence.global_triggers {
+            self.check_editor_trigger(trigger);
+        }
+    }
+
+    fn check_editor_item(&mut self, item: &EditorSequenceItem) {
+        if item.name.is_empty() {
+            self.report_item(
+                "editor.item",
+                &item.id,
+                format!("Item {} has no name", item.id),
+            );
+        }
+        if item.item_type.is_empty() {
+            self.report_item(
+                "editor.item",
+                &item.id,
+                format!("Item {} has no type", item.id),
+            );
+        } else if !nina_type_registry::is_known_item_type(&item.item_type) {
+            self.report_item(
+                "editor.unknown-type",
+                &item.id,
+                format!(
+                    "Item '{}' has unknown type {}",
+                    item.name,
+                    nina_type_registry::type_path(&item.item_type)
+                ),
+            );
+        }
+
+        if nina_type_registry::is_container_type(&item.item_type, self.plugins) {
+            if item.items.as_ref().map_or(true, |i| i.is_empty()) {
+                self.report_item(
+                    "editor.empty-container",
+                    &item.id,
+                    format!("Container '{}' has no items", item.name),
+                );
+            }
+            self.check_loop_conditions(item);
+        } else {
+            if item.triggers.as_ref().is_some_and(|t| !t.is_empty()) {
+                self.report_item(
+                    "editor.item-placement",
+                    &item.id,
+                    format!(
+                        "Item '{}' is not a container and cannot have triggers",
+                        item.name
+                    ),
+                );
+            }
+            if item.conditions.as_ref().is_some_and(|c| !c.is_empty()) {
+                self.report_item(
+                    "editor.item-placement",
+                    &item.id,
+                    format!(
+                        "Item '{}' is not a container and cannot have conditions",
+                        item.name
+                    ),
+                );
+            }
+        }
+
+        if is_type(&item.item_type, "SmartExposure") {
+            self.check_smart_exposure(item);
+        }
+        self.check_item_coordinates(item);
+        self.check_field_ranges(&item.id, &item.name, &item.item_type, &item.data);
+
+        for condition in item.conditions.iter().flatten() {
+            self.check_field_ranges(
+                &item.id,
+                &condition.name,
+                &condition.condition_type,
+                &condition.data,
+            );
+            if !nina_type_registry::is_known_condition_type(&condition.condition_type) {
+                self.report_item(
+                    "editor.unknown-type",
+                    &item.id,
+                    format!(
+                        "Condition '{}' on '{}' has unknown type {}",
+                        condition.name,
+                        item.name,
+                        nina_type_registry::type_path(&condition.condition_type)
+                    ),
+                );
+            }
+        }
+        for trigger in item.triggers.iter().flatten() {
+            self.check_editor_trigger(trigger);
+        }
+        for child in item.items.iter().flatten() {
+            self.check_editor_item(child);
+        }
+    }
+
+    fn check_editor_trigger(&mut self, trigger: &EditorTrigger) {
+        self.check_field_ranges(
+            &trigger.id,
+            &trigger.name,
+            &trigger.trigger_type,
+            &trigger.data,
+        );
+        if !nina_type_registry::is_known_trigger_type(&trigger.trigger_type) {
+            self.report_item(
+                "editor.unknown-type",
+                &trigger.id,
+                format!(
+                    "Trigger '{}' has unknown type {}",
+                    trigger.name,
+                    nina_type_registry::type_path(&trigger.trigger_type)
+                ),
+            );
+        }
+        for item in trigger.trigger_items.iter().flatten() {
+            self.check_editor_item(item);
+        }
+    }
+
+    /// Flag containers whose loop conditions never let them run, or that
+    /// have nothing to end the loop
+    fn check_loop_conditions(&mut self, item: &EditorSequenceItem) {
+        let conditions = item.conditions.as_deref().unwrap_or_default();
+
+        for condition in conditions {
+            if !is_type(&condition.condition_type, "LoopCondition") {
+                continue;
+            }
+            let iterations = data_field(&condition.data, "iterations").and_then(|v| v.as_f64());
+            if iterations.is_some_and(|n| n < 1.0) {
+                self.report_item(
+                    "editor.loop-condition",
+                    &item.id,
+                    format!("Container '{}' loops 0 times and will never run", item.name),
+                );
+            }
+        }
+
+        // Looping while safe only ends when the safety monitor reports unsafe
+        if !conditions.is_empty()
+            && conditions
+                .iter()
+                .all(|c| is_type(&c.condition_type, "SafetyMonitorCondition"))
+        {
+            self.report_item(
+                "editor.loop-condition",
+                &item.id,
+                format!(
+                    "Container '{}' has no condition that ends the loop and may run forever",
+                    item.name
+                ),
+            );
+        }
+
+        if conditions.is_empty() && is_type(&item.item_type, "SmartExposure") {
+            self.report_item(
+                "editor.loop-condition",
+                &item.id,
+                format!(
+                    "Smart Exposure '{}' has no loop condition and takes a single exposure",
+                    item.name
+                ),
+            );
+        }
+    }
+
+    fn check_smart_exposure(&mut self, item: &EditorSequenceItem) {
+        let exposure = item
+            .items
+            .iter()
+            .flatten()
+            .find(|child| is_type(&child.item_type, "TakeExposure"));
+        let Some(exposure) = exposure else {
+            self.report_item(
+                "editor.smart-exposure",
+                &item.id,
+                format!("Smart Exposure '{}' has no Take Exposure item", item.name),
+            );
+            return;
+        };
+
+        let time = data_field(&exposure.data, "exposureTime").and_then(|v| v.as_f64());
+        if !time.is_some_and(|t| t > 0.0) {
+            self.report_item(
+                "editor.smart-exposure",
+                &item.id,
+                format!(
+                    "Smart Exposure '{}' has no positive exposure time",
+                    item.name
+                ),
+            );
+        }
+    }
+
+    fn check_item_coordinates(&mut self, item: &EditorSequenceItem) {
+        let coordinates = data_field(&item.data, "coordinates")
+            .or_else(|| data_field(&item.data, "inputCoordinates"))
+            .or_else(|| {
+                data_field(&item.data, "target").and_then(|t| value_field(t, "inputCoordinates"))
+            })
+            .and_then(input_coordinates);
+        if let Some(coordinates) = coordinates {
+            for message in coordinates.validate() {
+                self.report_item(
+                    "editor.coordinates",
+                    &item.id,
+                    format!("Item '{}': {}", item.name, message),
+                );
+            }
+        }
+    }
+
+    /// Check numeric data against the built-in or plugin schema of a type
+    fn check_field_ranges(
+        &mut self,
+        item_id: &str,
+        name: &str,
+        full_type: &str,
+        data: &HashMap<String, serde_json::Value>,
+    ) {
+        let Some(schema) = nina_type_registry::get_schema(full_type, self.plugins) else {
+            return;
+        };
+        for field in &schema.fields {
+            let Some(value) = data_field(data, &field.name).and_then(|v| v.as_f64()) else {
+                continue;
+            };
+            let below = field.min.is_some_and(|min| value < min);
+            let above = field.max.is_some_and(|max| value > max);
+            if !below && !above {
+                continue;
+            }
+            let range = match (field.min, field.max) {
+                (Some(min), Some(max)) => format!("between {} and {}", min, max),
+                (Some(min), None) => format!("at least {}", min),
+                (None, Some(max)) => format!("at most {}", max),
+                (None, None) => unreachable!(),
+            };
+            self.report_item(
+                "editor.field-range",
+                item_id,
+                format!("'{}': {} must be {}", name, field.name, range),
+            );
+        }
+    }
+}
+
+/// Whether a NINA type string names the given class
+fn is_type(full_type: &str, class_name: &str) -> bool {
+    nina_type_registry::type_path(full_type)
+        .rsplit('.')
+        .next()
+        .is_some_and(|name| name == class_name)
+}
+
+/// Item data lookup that accepts both the camelCase keys of imported
+/// sequences and the PascalCase keys written by the editor
+fn data_field<'a>(
+    data: &'a HashMap<String, serde_json::Value>,
+    key: &str,
+) -> Option<&'a serde_json::Value> {
+    data.iter()
+        .find(|(k, _)| k.eq_ignore_ascii_case(key))
+        .map(|(_, v)| v)
+}
+
+fn value_field<'a>(value: &'a serde_json::Value, key: &str) -> Option<&'a serde_json::Value> {
+    value
+        .as_object()?
+        .iter()
+        .find(|(k, _)| k.eq_ignore_ascii_case(key))
+        .map(|(_, v)| v)
+}
+
+/// Read NINA `InputCoordinates`. A negative `DecDegrees` is treated as a
+/// southern declination.
+fn input_coordinates(value: &serde_json::Value) -> Option<Coordinates> {
+    let number = |key| value_field(value, key).and_then(|v| v.as_f64());
+    let ra_hours = number("raHours")?;
+    let dec_degrees = number("decDegrees").unwrap_or(0.0);
+    let negative_dec = value_field(value, "negativeDec")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false)
+        || dec_degrees < 0.0;
+
+    Some(Coordinates::new(
+        ra_hours as i32,
+        number("raMinutes").unwrap_or(0.0) as i32,
+        number("raSeconds").unwrap_or(0.0),
+        dec_degrees.abs() as i32,
+        number("decMinutes").unwrap_or(0.0) as i32,
+        number("decSeconds").unwrap_or(0.0),
+        negative_dec,
+    ))
+}
+
+// ============================================================================
+// Validation
+// ============================================================================
+
+/// Validate a simple sequence with the default rules
 pub fn validate_simple_sequence(sequence: &SimpleSequence) -> ValidationResult {
-    let errors = sequence.validate();
-    ValidationResult::with_errors(errors)
+    validate_simple_sequence_with_rules(sequence, &[], None, &HashMap::new())
 }
 
-/// Validate an editor sequence
-pub fn validate_editor_sequence(sequence: &EditorSequence) -> ValidationResult {
-    let errors = sequence.validate();
-    ValidationResult::with_errors(errors)
+/// Validate a simple sequence with rule overrides. Filter rules run only
+/// when `filters` is not empty, sampling rules only with a `profile`.
+pub fn validate_simple_sequence_with_rules(
+    sequence: &SimpleSequence,
+    filters: &[FilterInfo],
+    profile: Option<&EquipmentProfile>,
+    configs: &HashMap<String, ValidationRuleConfig>,
+) -> ValidationResult {
+    let mut engine = RuleEngine::new(configs);
+    engine.check_sequence(sequence);
+    if !filters.is_empty() {
+        engine.check_filters(sequence, filters);
+    }
+    if let Some(profile) = profile {
+        engine.check_sampling(sequence, profile);
+    }
+    engine.finish()
+}
+
+/// Check exposure filters against a filter set, returning warnings for
+/// unknown filter names, mismatched positions and missing focus offsets
+pub fn validate_sequence_filters(sequence: &SimpleSequence, filters: &[FilterInfo]) -> Vec<String> {
+    let configs = HashMap::new();
+    let mut engine = RuleEngine::new(&configs);
+    engine.check_filters(sequence, filters);
+    engine.issues.into_iter().map(|i| i.message).collect()
+}
+
+/// Validate an editor sequence with the default rules
+pub fn validate_editor_sequence(
+    sequence: &EditorSequence,
+    plugins: &[NinaTypeSchema],
+) -> ValidationResult {
+    validate_editor_sequence_with_rules(sequence, &HashMap::new(), plugins)
+}
+
+/// Validate an editor sequence with rule overrides
+pub fn validate_editor_sequence_with_rules(
+    sequence: &EditorSequence,
+    configs: &HashMap<String, ValidationRuleConfig>,
+    plugins: &[NinaTypeSchema],
+) -> ValidationResult {
+    let mut engine = RuleEngine {
+        plugins,
+        ..RuleEngine::new(configs)
+    };
+    engine.check_editor_sequence(sequence);
+    engine.finish()
 }
 
 /// Validate coordinates
@@ -22,14 +1036,18 @@ pub fn validate_coordinates(coords: &Coordinates) -> ValidationResult {
 
 /// Validate a simple target
 pub fn validate_simple_target(target: &SimpleTarget) -> ValidationResult {
-    let errors = target.validate();
-    ValidationResult::with_errors(errors)
+    let configs = HashMap::new();
+    let mut engine = RuleEngine::new(&configs);
+    engine.check_target(target, None);
+    engine.finish()
 }
 
 /// Validate a simple exposure
 pub fn validate_simple_exposure(exposure: &SimpleExposure) -> ValidationResult {
-    let errors = exposure.validate();
-    ValidationResult::with_errors(errors)
+    let configs = HashMap::new();
+    let mut engine = RuleEngine::new(&configs);
+    engine.check_exposure(exposure, None, None);
+    engine.finish()
 }
 
 /// Validate JSON string as NINA sequence
@@ -65,6 +1083,7 @@ pub fn validate_nina_json(json: &str) -> ValidationResult {
                 valid: errors.is_empty(),
                 errors,
                 warnings,
+                issues: Vec::new(),
             }
         }
         Err(e) => ValidationResult::error(format!("Invalid JSON: {}", e)),
@@ -72,10 +1091,8 @@ pub fn validate_nina_json(json: &str) -> ValidationResult {
 }
 
 /// Check if a type string represents a container
-pub fn is_container_type(type_str: &str) -> bool {
-    type_str.contains("Container")
-        || type_str.contains("SmartExposure")
-        || type_str.contains("InstructionSet")
+pub fn is_container_type(type_str: &str, plugins: &[NinaTypeSchema]) -> bool {
+    nina_type_registry::is_container_type(type_str, plugins)
 }
 
 /// Get short type name from full NINA type string
@@ -109,6 +1126,141 @@ pub fn get_type_category(full_type: &str) -> String {
 mod tests {
     use super::*;
 
+    fn sequence_with_exposure(exposure_time: f64) -> SimpleSequence {
+        let mut sequence = SimpleSequence::new("Rules");
+        sequence.targets = vec![SimpleTarget {
+            target_name: "M42".to_string(),
+            exposures: vec![SimpleExposure {
+                exposure_time,
+                ..Default::default()
+            }],
+            ..Default::default()
+        }];
+        sequence
+    }
+
+    #[test]
+    fn test_issues_carry_rule_ids() {
+        let result = validate_simple_sequence(&sequence_with_exposure(0.0));
+        assert!(!result.valid);
+        let issue = &result.issues[0];
+        assert_eq!(issue.rule_id, "exposure.time");
+        assert_eq!(issue.severity, ValidationSeverity::Error);
+        assert_eq!(issue.target_index, Some(0));
+        assert_eq!(issue.exposure_index, Some(0));
+        assert_eq!(result.errors, vec!["Exposure time must be positive"]);
+    }
+
+    #[test]
+    fn test_rule_threshold_override() {
+        let sequence = sequence_with_exposure(900.0);
+        let defaults = validate_simple_sequence(&sequence);
+        assert!(defaults.warnings.is_empty());
+
+        let configs = HashMap::from([(
+            "exposure.max-time".to_string(),
+            ValidationRuleConfig {
+                threshold: Some(600.0),
+                ..Default::default()
+            },
+        )]);
+        let result = validate_simple_sequence_with_rules(&sequence, &[], None, &configs);
+        assert!(result.valid);
+        assert_eq!(result.warnings.len(), 1);
+        assert_eq!(result.issues[0].rule_id, "exposure.max-time");
+    }
+
+    #[test]
+    fn test_rule_disable_and_severity() {
+        let sequence = sequence_with_exposure(-1.0);
+
+        let disabled = HashMap::from([(
+            "exposure.time".to_string(),
+            ValidationRuleConfig {
+                enabled: Some(false),
+                ..Default::default()
+            },
+        )]);
+        let result = validate_simple_sequence_with_rules(&sequence, &[], None, &disabled);
+        assert!(result.valid);
+        assert!(result.issues.is_empty());
+
+        let downgraded = HashMap::from([(
+            "exposure.time".to_string(),
+            ValidationRuleConfig {
+                severity: Some(ValidationSeverity::Warning),
+                ..Default::default()
+            },
+        )]);
+        let result = validate_simple_sequence_with_rules(&sequence, &[], None, &downgraded);
+        assert!(result.valid);
+        assert_eq!(result.warnings, vec!["Exposure time must be positive"]);
+    }
+
+    #[test]
+    fn test_sampling_rule() {
+        let mut sequence = sequence_with_exposure(300.0);
+        let target = &mut sequence.targets[0];
+        target.exposures.push(target.exposures[0].clone());
+        target.exposures.push(SimpleExposure {
+            binning: BinningMode { x: 3, y: 3 },
+            ..target.exposures[0].clone()
+        });
+        // 0.39"/px: 6.4 px across the default 2.5" seeing unbinned
+        let mut profile = EquipmentProfile::default();
+        profile.camera.pixel_size = 3.76;
+        profile.telescope.focal_length = 2000.0;
+
+        let result =
+            validate_simple_sequence_with_rules(&sequence, &[], Some(&profile), &HashMap::new());
+        assert!(result.valid);
+        assert_eq!(result.warnings.len(), 1);
+        assert_eq!(result.issues[0].rule_id, "exposure.sampling");
+        assert_eq!(result.issues[0].exposure_index, Some(0));
+        assert!(result.warnings[0].contains("oversampled"));
+        assert!(result.warnings[0].contains("binning 3x3 suits"));
+
+        // 5.2 px across 2" seeing is within range
+        let configs = HashMap::from([(
+            "exposure.sampling".to_string(),
+            ValidationRuleConfig {
+                threshold: Some(2.0),
+                ..Default::default()
+            },
+        )]);
+        let result = validate_simple_sequence_with_rules(&sequence, &[], Some(&profile), &configs);
+        assert!(result.warnings.is_empty());
+    }
+
+    #[test]
+    fn test_list_and_check_rule_configs() {
+        let configs = HashMap::from([(
+            "sequence.max-runtime".to_string(),
+            ValidationRuleConfig {
+                enabled: Some(false),
+                threshold: Some(8.0),
+                ..Default::default()
+            },
+        )]);
+        let rules = list_validation_rules(&configs);
+        assert_eq!(rules.len(), VALIDATION_RULES.len());
+        let runtime = rules
+            .iter()
+            .find(|r| r.id == "sequence.max-runtime")
+            .unwrap();
+        assert!(!runtime.enabled);
+        assert_eq!(runtime.threshold, Some(8.0));
+        assert_eq!(runtime.default_threshold, Some(12.0));
+
+        let threshold = ValidationRuleConfig {
+            threshold: Some(10.0),
+            ..Default::default()
+        };
+        assert!(validate_rule_config("exposure.max-time", &threshold).is_ok());
+        assert!(validate_rule_config("exposure.time", &threshold).is_err());
+        assert!(validate_rule_config("no.such-rule", &threshold).is_err());
+    }
+
     #[test]
     fn test_get_short_type_name() {
         assert_eq!(
@@ -128,10 +1280,214 @@ mod tests {
     #[test]
     fn test_is_container_type() {
         assert!(is_container_type(
-            "NINA.Sequencer.Container.SequentialContainer, NINA.Sequencer"
+            "NINA.Sequencer.Container.SequentialContainer, NINA.Sequencer",
+            &[],
         ));
         assert!(!is_container_type(
-            "NINA.Sequencer.SequenceItem.Camera.CoolCamera, NINA.Sequencer"
+            "NINA.Sequencer.SequenceItem.Camera.CoolCamera, NINA.Sequencer",
+            &[],
         ));
     }
+
+    #[test]
+    fn test_validate_sequence_filters() {
+        let filter = |name: &str, position| FilterInfo {
+            name: name.to_string(),
+            position,
+            ..Default::default()
+        };
+        let mut sequence = SimpleSequence::default();
+        sequence.targets[0].exposures = vec![
+            SimpleExposure {
+                filter: Some(filter("Ha", 1)),
+                ..Default::default()
+            },
+            SimpleExposure {
+                filter: Some(filter("OIII", 5)),
+                ..Default::default()
+            },
+            SimpleExposure {
+                filter: Some(filter("SII", 3)),
+                ..Default::default()
+            },
+        ];
+        let library = vec![filter("Ha", 1), filter("OIII", 2)];
+
+        sequence.targets[0].auto_focus_on_filter_change = true;
+        let warnings = validate_sequence_filters(&sequence, &library);
+        assert_eq!(warnings.len(), 2);
+        assert!(warnings[0].contains("position 5"));
+        assert!(warnings[1].contains("'SII'"));
+
+        // Without refocusing, filters need focus offsets
+        sequence.targets[0].auto_focus_on_filter_change = false;
+        sequence.targets[0].exposures[1]
+            .filter
+            .as_mut()
+            .unwrap()
+            .focus_offset = Some(15);
+        let library = vec![
+            FilterInfo {
+                focus_offset: Some(-20),
+                ..filter("Ha", 1)
+            },
+            filter("OIII", 2),
+        ];
+        let warnings = validate_sequence_filters(&sequence, &library);
+        assert_eq!(warnings.len(), 3);
+        assert!(warnings[2].contains("'SII' has no focus offset"));
+    }
+
+    fn editor_item(
+        name: &str,
+        class: &str,
+        data: serde_json::Value,
+        items: Option<Vec<EditorSequenceItem>>,
+    ) -> EditorSequenceItem {
+        EditorSequenceItem {
+            id: name.to_string(),
+            item_type: format!("NINA.Sequencer.{}, NINA.Sequencer", class),
+            name: name.to_string(),
+            category: String::new(),
+            icon: None,
+            description: None,
+            status: SequenceEntityStatus::Created,
+            is_expanded: None,
+            data: serde_json::from_value(data).unwrap(),
+            items,
+            conditions: None,
+            triggers: None,
+        }
+    }
+
+    fn rule_ids(result: &ValidationResult) -> Vec<(&str, &str)> {
+        result
+            .issues
+            .iter()
+            .map(|i| (i.rule_id.as_str(), i.item_id.as_deref().unwrap_or_default()))
+            .collect()
+    }
+
+    #[test]
+    fn test_editor_semantic_checks() {
+        let mut smart = editor_item(
+            "Smart",
+            "SequenceItem.Imaging.SmartExposure",
+            serde_json::json!({}),
+            Some(vec![editor_item(
+                "Exposure",
+                "SequenceItem.Imaging.TakeExposure",
+                serde_json::json!({ "ExposureTime": 0 }),
+                None,
+            )]),
+        );
+        smart.conditions = Some(vec![EditorCondition {
+            id: "loop".to_string(),
+            condition_type: "NINA.Sequencer.Conditions.LoopCondition, NINA.Sequencer".to_string(),
+            name: "Loop".to_string(),
+            category: String::new(),
+            icon: None,
+            data: HashMap::from([("iterations".to_string(), serde_json::json!(0))]),
+        }]);
+
+        let mut slew = editor_item(
+            "Slew",
+            "SequenceItem.Telescope.SlewScopeToRaDec",
+            serde_json::json!({ "coordinates": { "RAHours": 25, "DecDegrees": -10 } }),
+            None,
+        );
+        slew.triggers = Some(vec![EditorTrigger {
+            id: "trigger".to_string(),
+            trigger_type: "NINA.Sequencer.Trigger.Guider.DitherAfterExposures, NINA.Sequencer"
+                .to_string(),
+            name: "Dither".to_string(),
+            category: String::new(),
+            icon: None,
+            data: HashMap::new(),
+            trigger_items: None,
+        }]);
+
+        let mut sequence = EditorSequence::new("Semantic");
+        sequence.target_items = vec![
+            smart,
+            slew,
+            editor_item(
+                "Typo",
+                "SequenceItem.Imaging.TakeExposures",
+                serde_json::json!({}),
+                None,
+            ),
+        ];
+
+        let result = validate_editor_sequence(&sequence, &[]);
+        assert_eq!(
+            rule_ids(&result),
+            vec![
+                ("editor.loop-condition", "Smart"),
+                ("editor.smart-exposure", "Smart"),
+                ("editor.item-placement", "Slew"),
+                ("editor.coordinates", "Slew"),
+                ("editor.unknown-type", "Typo"),
+            ]
+        );
+        assert!(result.errors[2].contains("RA hours"));
+    }
+
+    #[test]
+    fn test_editor_dso_coordinates_in_range() {
+        let mut sequence = EditorSequence::new("DSO");
+        sequence.target_items = vec![editor_item(
+            "M42",
+            "Container.DeepSkyObjectContainer",
+            serde_json::json!({ "Target": { "InputCoordinates": {
+                "RAHours": 5, "RAMinutes": 35, "RASeconds": 17.3,
+                "DecDegrees": -5, "DecMinutes": 23, "DecSeconds": 28
+            } } }),
+            Some(vec![editor_item(
+                "Center",
+                "SequenceItem.Platesolving.Center",
+                serde_json::json!({ "Inherited": true }),
+                None,
+            )]),
+        )];
+
+        let result = validate_editor_sequence(&sequence, &[]);
+        assert!(result.valid, "{:?}", result.issues);
+        assert!(result.issues.is_empty());
+    }
+
+    #[test]
+    fn test_editor_field_ranges() {
+        let mut sequence = EditorSequence::new("Ranges");
+        sequence.start_items = vec![
+            editor_item(
+                "Slew",
+                "SequenceItem.Telescope.SlewScopeToAltAz",
+                serde_json::json!({ "Altitude": 95, "azimuth": 180 }),
+                None,
+            ),
+            editor_item(
+                "Cool",
+                "SequenceItem.Camera.CoolCamera",
+                serde_json::json!({ "Temperature": -10, "Duration": -5 }),
+                None,
+            ),
+        ];
+
+        let result = validate_editor_sequence(&sequence, &[]);
+        assert_eq!(
+            rule_ids(&result),
+            vec![
+                ("editor.field-range", "Slew"),
+                ("editor.field-range", "Cool")
+            ]
+        );
+        assert_eq!(
+            result.errors,
+            vec![
+                "'Slew': Altitude must be between -90 and 90",
+                "'Cool': Duration must be at least 0",
+            ]
+        );
+    }
 }
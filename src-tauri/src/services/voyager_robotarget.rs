@@ -0,0 +1,445 @@
+//! Voyager RoboTarget JSON
+//!
+//! Reads and writes RoboTarget target lists: targets with J2000
+//! coordinates (RA in hours), scheduling constraints prefixed `C_` and
+//! their shots. Constraints map onto the target's
+//! [`ObservingConstraints`].
+
+use chrono::{DateTime, NaiveDate, Utc};
+use serde_json::{json, Map, Value};
+
+use crate::models::common::{
+    BinningMode, FilterInfo, ImageType, SequenceEntityStatus, TargetPriority,
+};
+use crate::models::{
+    Coordinates, ObservingConstraints, SimpleExposure, SimpleSequence, SimpleTarget,
+};
+use crate::services::export_service::{ExportOptions, ExportResult};
+use crate::services::import_service::ImportResult;
+
+const SOURCE_FORMAT: &str = "Voyager RoboTarget";
+
+/// Numeric RoboTarget constraints and the fields they map to
+type ConstraintField = fn(&mut ObservingConstraints) -> &mut Option<f64>;
+const NUMERIC_CONSTRAINTS: &[(&str, ConstraintField)] = &[
+    ("C_AltMin", |c| &mut c.min_altitude),
+    ("C_AirMassMax", |c| &mut c.max_airmass),
+    ("C_HAStart", |c| &mut c.min_hour_angle),
+    ("C_HAEnd", |c| &mut c.max_hour_angle),
+    ("C_MoonDistanceDegree", |c| &mut c.min_moon_separation),
+    ("C_MoonPhaseMax", |c| &mut c.max_moon_illumination),
+];
+
+/// RoboTarget shot types
+const SHOT_TYPES: &[(i64, ImageType)] = &[
+    (0, ImageType::Light),
+    (1, ImageType::Bias),
+    (2, ImageType::Dark),
+    (3, ImageType::Flat),
+];
+
+/// RoboTarget priorities run from 0 (very low) to 4 (first)
+const PRIORITIES: &[(i64, TargetPriority)] = &[
+    (0, TargetPriority::Low),
+    (1, TargetPriority::Low),
+    (2, TargetPriority::Normal),
+    (3, TargetPriority::High),
+    (4, TargetPriority::Critical),
+];
+
+const TARGET_LIST_KEYS: &[&str] = &["Targets", "targets", "list", "ParamRet"];
+const TARGET_NAME_KEYS: &[&str] = &["TargetName", "Name"];
+const SHOT_LIST_KEYS: &[&str] = &["Shots", "shots", "list"];
+
+// ============================================================================
+// Helpers
+// ============================================================================
+
+fn lookup<'a>(obj: &'a Value, keys: &[&str]) -> Option<&'a Value> {
+    keys.iter()
+        .find_map(|k| obj.get(*k))
+        .filter(|v| !v.is_null())
+}
+
+fn lookup_f64(obj: &Value, key: &str) -> Option<f64> {
+    lookup(obj, &[key]).and_then(|v| {
+        v.as_f64()
+            .or_else(|| v.as_str().and_then(|s| s.trim().parse().ok()))
+    })
+}
+
+fn lookup_str(obj: &Value, key: &str) -> Option<String> {
+    lookup(obj, &[key])
+        .and_then(|v| v.as_str())
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+}
+
+/// Target list of an export: a bare array or an object holding one
+fn target_list(root: &Value) -> Option<&Vec<Value>> {
+    root.as_array().or_else(|| {
+        lookup(root, TARGET_LIST_KEYS).and_then(|list| {
+            list.as_array()
+                .or_else(|| lookup(list, TARGET_LIST_KEYS).and_then(|v| v.as_array()))
+        })
+    })
+}
+
+/// Date constraint: RFC 3339, or a plain date meaning its start
+fn lookup_datetime(obj: &Value, key: &str) -> Option<DateTime<Utc>> {
+    let value = lookup_str(obj, key)?;
+    DateTime::parse_from_rfc3339(&value)
+        .map(|dt| dt.with_timezone(&Utc))
+        .ok()
+        .or_else(|| {
+            NaiveDate::parse_from_str(&value, "%Y-%m-%d")
+                .ok()
+                .and_then(|date| date.and_hms_opt(0, 0, 0))
+                .map(|dt| dt.and_utc())
+        })
+}
+
+/// Whether JSON content looks like a RoboTarget export
+pub fn is_robotarget_json(content: &str) -> bool {
+    let trimmed = content.trim_start();
+    (trimmed.starts_with('{') || trimmed.starts_with('['))
+        && content.contains("\"RAJ2000\"")
+        && content.contains("\"DECJ2000\"")
+}
+
+// ============================================================================
+// Import
+// ============================================================================
+
+fn parse_shot(shot: &Value, warnings: &mut Vec<String>, target_name: &str) -> SimpleExposure {
+    let image_type = match lookup(shot, &["Type"]).and_then(|v| v.as_i64()) {
+        Some(code) => SHOT_TYPES
+            .iter()
+            .find(|(c, _)| *c == code)
+            .map(|(_, t)| *t)
+            .unwrap_or_else(|| {
+                warnings.push(format!(
+                    "Target '{}': unknown shot type {}, using Light",
+                    target_name, code
+                ));
+                ImageType::Light
+            }),
+        None => ImageType::Light,
+    };
+    let binning = lookup_f64(shot, "Bin").map_or(1, |b| b.max(1.0) as i32);
+    let filter = lookup_str(shot, "FilterName").map(|name| FilterInfo {
+        name,
+        position: lookup_f64(shot, "FilterIndex").map_or(0, |i| i as i32),
+        ..Default::default()
+    });
+    let total_count = lookup_f64(shot, "Num").map_or(1, |n| n.max(0.0) as i32);
+
+    SimpleExposure {
+        enabled: lookup(shot, &["Enabled"]).and_then(|v| v.as_bool()) != Some(false),
+        exposure_time: lookup_f64(shot, "Exposure").unwrap_or(60.0),
+        image_type,
+        filter,
+        binning: BinningMode {
+            x: binning,
+            y: binning,
+        },
+        gain: lookup_f64(shot, "Gain").map_or(-1, |g| g as i32),
+        offset: lookup_f64(shot, "Offset").map_or(-1, |o| o as i32),
+        total_count,
+        progress_count: lookup_f64(shot, "Done")
+            .map_or(0, |d| (d.max(0.0) as i32).min(total_count)),
+        ..Default::default()
+    }
+}
+
+fn parse_target(
+    item: &Value,
+    index: usize,
+    warnings: &mut Vec<String>,
+) -> Result<SimpleTarget, String> {
+    let name = lookup(item, TARGET_NAME_KEYS)
+        .and_then(|v| v.as_str())
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(str::to_string)
+        .unwrap_or_else(|| format!("Target {}", index + 1));
+
+    let coordinates = match (lookup_f64(item, "RAJ2000"), lookup_f64(item, "DECJ2000")) {
+        (Some(ra), Some(dec)) if (0.0..24.0).contains(&ra) && (-90.0..=90.0).contains(&dec) => {
+            Coordinates::from_decimal(ra, dec)
+        }
+        _ => return Err(format!("Target '{}': missing or invalid coordinates", name)),
+    };
+    let position_angle = lookup_f64(item, "PA").unwrap_or(0.0);
+
+    let mut target = SimpleTarget {
+        name: name.clone(),
+        target_name: name.clone(),
+        coordinates,
+        position_angle,
+        rotation: position_angle,
+        exposures: Vec::new(),
+        notes: lookup_str(item, "Note"),
+        tags: lookup_str(item, "Tag")
+            .map(|tags| {
+                tags.split(',')
+                    .map(str::trim)
+                    .filter(|t| !t.is_empty())
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_default(),
+        ..Default::default()
+    };
+
+    if let Some(priority) = lookup(item, &["Priority"]).and_then(|v| v.as_i64()) {
+        target.priority = PRIORITIES
+            .iter()
+            .find(|(p, _)| *p == priority)
+            .map_or(TargetPriority::Normal, |(_, p)| *p);
+    }
+    if lookup(item, &["Enabled"]).and_then(|v| v.as_bool()) == Some(false) {
+        target.status = SequenceEntityStatus::Disabled;
+    }
+    for (key, field) in NUMERIC_CONSTRAINTS {
+        *field(&mut target.constraints) = lookup_f64(item, key);
+    }
+    target.constraints.earliest_start = lookup_datetime(item, "C_DateStart");
+    target.constraints.latest_start = lookup_datetime(item, "C_DateEnd");
+
+    if let Some(shots) = lookup(item, SHOT_LIST_KEYS).and_then(|v| v.as_array()) {
+        target.exposures = shots
+            .iter()
+            .map(|shot| parse_shot(shot, warnings, &name))
+            .collect();
+    }
+
+    Ok(target)
+}
+
+/// Parse a RoboTarget JSON export
+pub fn parse_robotarget_json(content: &str) -> ImportResult {
+    let mut result = ImportResult {
+        success: false,
+        targets: Vec::new(),
+        errors: Vec::new(),
+        warnings: Vec::new(),
+        source_format: SOURCE_FORMAT.to_string(),
+        total_rows: 0,
+        imported_count: 0,
+        skipped_count: 0,
+    };
+
+    let root: Value = match serde_json::from_str(content) {
+        Ok(root) => root,
+        Err(e) => {
+            result
+                .errors
+                .push(format!("Invalid RoboTarget JSON: {}", e));
+            return result;
+        }
+    };
+    let Some(items) = target_list(&root) else {
+        result
+            .errors
+            .push("RoboTarget JSON has no target list".to_string());
+        return result;
+    };
+
+    for (index, item) in items.iter().enumerate() {
+        match parse_target(item, index, &mut result.warnings) {
+            Ok(target) => result.targets.push(target),
+            Err(e) => result.warnings.push(e),
+        }
+    }
+
+    result.success = true;
+    result.total_rows = items.len();
+    result.imported_count = result.targets.len();
+    result.skipped_count = result.total_rows - result.imported_count;
+    result
+}
+
+// ============================================================================
+// Export
+// ============================================================================
+
+fn shot_json(exposure: &SimpleExposure, options: &ExportOptions) -> Value {
+    let shot_type = SHOT_TYPES
+        .iter()
+        .find(|(_, t)| *t == exposure.image_type)
+        .map_or(0, |(code, _)| *code);
+    let mut shot = json!({
+        "FilterIndex": exposure.filter.as_ref().map_or(0, |f| f.position),
+        "FilterName": exposure.filter.as_ref().map_or("", |f| f.name.as_str()),
+        "Exposure": exposure.exposure_time,
+        "Num": exposure.total_count,
+        "Bin": exposure.binning.x,
+        "Gain": exposure.gain,
+        "Offset": exposure.offset,
+        "Type": shot_type,
+        "Enabled": exposure.enabled,
+    });
+    if options.include_progress {
+        shot["Done"] = json!(exposure.progress_count);
+    }
+    shot
+}
+
+fn target_json(target: &SimpleTarget, options: &ExportOptions) -> Value {
+    let priority = PRIORITIES
+        .iter()
+        .rev()
+        .find(|(_, p)| *p == target.priority)
+        .map_or(2, |(code, _)| *code);
+
+    let mut item = Map::new();
+    item.insert("TargetName".into(), json!(target.target_name));
+    item.insert("RAJ2000".into(), json!(target.coordinates.ra_to_decimal()));
+    item.insert(
+        "DECJ2000".into(),
+        json!(target.coordinates.dec_to_decimal()),
+    );
+    item.insert("PA".into(), json!(target.position_angle));
+    item.insert("Priority".into(), json!(priority));
+    item.insert(
+        "Enabled".into(),
+        json!(target.status != SequenceEntityStatus::Disabled),
+    );
+    item.insert("Note".into(), json!(target.notes.as_deref().unwrap_or("")));
+    item.insert("Tag".into(), json!(target.tags.join(",")));
+    let mut constraints = target.constraints.clone();
+    for (key, field) in NUMERIC_CONSTRAINTS {
+        if let Some(value) = *field(&mut constraints) {
+            item.insert(key.to_string(), json!(value));
+        }
+    }
+    for (key, value) in [
+        ("C_DateStart", constraints.earliest_start),
+        ("C_DateEnd", constraints.latest_start),
+    ] {
+        if let Some(value) = value {
+            item.insert(key.to_string(), json!(value.to_rfc3339()));
+        }
+    }
+    if options.include_exposures {
+        let shots: Vec<Value> = target
+            .exposures
+            .iter()
+            .map(|e| shot_json(e, options))
+            .collect();
+        item.insert("Shots".into(), Value::Array(shots));
+    }
+    Value::Object(item)
+}
+
+/// Export targets as RoboTarget JSON
+pub fn export_to_robotarget_json(
+    sequence: &SimpleSequence,
+    options: &ExportOptions,
+) -> ExportResult {
+    let targets: Vec<Value> = sequence
+        .targets
+        .iter()
+        .map(|t| target_json(t, options))
+        .collect();
+    let export = json!({
+        "SequenceName": sequence.title,
+        "Targets": targets,
+    });
+
+    match serde_json::to_string_pretty(&export) {
+        Ok(content) => ExportResult {
+            success: true,
+            content,
+            format: SOURCE_FORMAT.to_string(),
+            target_count: sequence.targets.len(),
+            errors: vec![],
+        },
+        Err(e) => ExportResult {
+            success: false,
+            content: String::new(),
+            format: SOURCE_FORMAT.to_string(),
+            target_count: 0,
+            errors: vec![e.to_string()],
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXPORT: &str = r#"{
+  "Targets": [
+    {
+      "TargetName": "M 31",
+      "RAJ2000": 0.712305,
+      "DECJ2000": 41.26875,
+      "PA": 35.0,
+      "Priority": 3,
+      "Note": "Core needs short subs",
+      "Tag": "galaxy, autumn",
+      "C_AltMin": 30,
+      "C_MoonDistanceDegree": 60,
+      "C_MoonPhaseMax": 40,
+      "C_DateStart": "2024-10-01",
+      "Shots": [
+        {"FilterIndex": 1, "FilterName": "L", "Exposure": 120, "Num": 40, "Bin": 1, "Gain": 100, "Offset": 10, "Type": 0},
+        {"FilterIndex": 4, "FilterName": "Ha", "Exposure": 300, "Num": 20, "Bin": 2, "Type": 0, "Enabled": false}
+      ]
+    },
+    {"TargetName": "No coordinates"}
+  ]
+}"#;
+
+    #[test]
+    fn test_parse_robotarget_json() {
+        assert!(is_robotarget_json(EXPORT));
+        let result = parse_robotarget_json(EXPORT);
+
+        assert!(result.success);
+        assert_eq!(result.imported_count, 1);
+        assert_eq!(result.skipped_count, 1);
+        assert_eq!(result.warnings.len(), 1);
+
+        let target = &result.targets[0];
+        assert_eq!(target.target_name, "M 31");
+        assert!((target.coordinates.ra_to_decimal() - 0.712305).abs() < 1e-4);
+        assert_eq!(target.priority, TargetPriority::High);
+        assert_eq!(target.tags, vec!["galaxy", "autumn"]);
+        assert_eq!(target.constraints.min_altitude, Some(30.0));
+        assert_eq!(target.constraints.max_moon_illumination, Some(40.0));
+        assert_eq!(
+            target.constraints.earliest_start.unwrap().to_rfc3339(),
+            "2024-10-01T00:00:00+00:00"
+        );
+
+        assert_eq!(target.exposures.len(), 2);
+        assert_eq!(target.exposures[0].total_count, 40);
+        assert_eq!(target.exposures[0].gain, 100);
+        assert_eq!(target.exposures[1].binning.x, 2);
+        assert!(!target.exposures[1].enabled);
+    }
+
+    #[test]
+    fn test_robotarget_round_trip() {
+        let mut sequence = SimpleSequence::new("Autumn");
+        sequence.targets = parse_robotarget_json(EXPORT).targets;
+
+        let exported = export_to_robotarget_json(&sequence, &ExportOptions::default());
+        assert!(exported.success);
+        assert!(is_robotarget_json(&exported.content));
+
+        let imported = parse_robotarget_json(&exported.content);
+        let (a, b) = (&sequence.targets[0], &imported.targets[0]);
+        assert_eq!(a.target_name, b.target_name);
+        assert_eq!(a.priority, b.priority);
+        assert_eq!(a.constraints, b.constraints);
+        assert_eq!(a.exposures.len(), b.exposures.len());
+        assert_eq!(b.exposures[1].filter.as_ref().unwrap().name, "Ha");
+
+        assert!(!parse_robotarget_json("{\"Targets\": 3}").success);
+        assert!(!parse_robotarget_json("not json").success);
+    }
+}
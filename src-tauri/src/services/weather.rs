@@ -0,0 +1,404 @@
+//! Weather forecast service
+//!
+//! Provides an extensible weather provider interface used to fetch night
+//! forecasts and weight scheduling decisions by cloud cover. Built-in
+//! providers:
+//! - Open-Meteo (free, no API key)
+
+use chrono::{DateTime, Duration, NaiveDate, NaiveDateTime, Utc};
+use once_cell::sync::Lazy;
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+use crate::error::{AppError, AppResult};
+use crate::services::astronomy::ObserverLocation;
+use crate::services::dark_calendar;
+use crate::state::AppState;
+
+/// Default provider id
+pub const DEFAULT_WEATHER_PROVIDER: &str = "open-meteo";
+
+const REQUEST_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(15);
+
+static CLIENT: Lazy<reqwest::Client> = Lazy::new(|| {
+    reqwest::Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .build()
+        .unwrap_or_default()
+});
+
+/// Hourly forecast sample
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HourlyForecast {
+    pub time: DateTime<Utc>,
+    /// Total cloud cover (percent)
+    pub cloud_cover: Option<f64>,
+    /// Relative humidity (percent)
+    pub humidity: Option<f64>,
+    /// Wind speed (km/h)
+    pub wind_speed: Option<f64>,
+    /// Temperature (°C)
+    pub temperature: Option<f64>,
+}
+
+/// Forecast summary for one night (dusk to dawn)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NightForecast {
+    pub date: String,
+    pub provider: String,
+    pub night_start: DateTime<Utc>,
+    pub night_end: DateTime<Utc>,
+    pub hourly: Vec<HourlyForecast>,
+    /// Average cloud cover over the night (percent), None without data
+    pub average_cloud_cover: Option<f64>,
+    /// Hours with cloud cover below 30%
+    pub clear_hours: f64,
+}
+
+/// Weather provider description
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WeatherProviderInfo {
+    pub id: String,
+    pub name: String,
+    /// Maximum forecast range in days
+    pub max_days: u32,
+}
+
+/// Weather provider interface. Providers describe how to request a
+/// forecast and how to parse the response; fetching is shared.
+pub trait WeatherProvider: Send + Sync {
+    fn id(&self) -> &str;
+    fn name(&self) -> &str;
+    fn max_days(&self) -> u32;
+    /// Build the request URL for hourly data covering `start..=end` (UTC dates)
+    fn forecast_url(&self, location: &ObserverLocation, start: NaiveDate, end: NaiveDate)
+        -> String;
+    /// Parse a response body into hourly samples
+    fn parse_forecast(&self, body: &str) -> Result<Vec<HourlyForecast>, String>;
+}
+
+// ============================================================================
+// Open-Meteo Provider
+// ============================================================================
+
+/// Open-Meteo forecast API (https://open-meteo.com)
+pub struct OpenMeteoProvider;
+
+#[derive(Deserialize)]
+struct OpenMeteoResponse {
+    hourly: OpenMeteoHourly,
+}
+
+#[derive(Deserialize)]
+struct OpenMeteoHourly {
+    time: Vec<String>,
+    #[serde(default)]
+    cloud_cover: Vec<Option<f64>>,
+    #[serde(default)]
+    relative_humidity_2m: Vec<Option<f64>>,
+    #[serde(default)]
+    wind_speed_10m: Vec<Option<f64>>,
+    #[serde(default)]
+    temperature_2m: Vec<Option<f64>>,
+}
+
+impl WeatherProvider for OpenMeteoProvider {
+    fn id(&self) -> &str {
+        "open-meteo"
+    }
+
+    fn name(&self) -> &str {
+        "Open-Meteo"
+    }
+
+    fn max_days(&self) -> u32 {
+        16
+    }
+
+    fn forecast_url(
+        &self,
+        location: &ObserverLocation,
+        start: NaiveDate,
+        end: NaiveDate,
+    ) -> String {
+        format!(
+            "https://api.open-meteo.com/v1/forecast?latitude={:.4}&longitude={:.4}\
+             &hourly=cloud_cover,relative_humidity_2m,wind_speed_10m,temperature_2m\
+             &timezone=UTC&start_date={}&end_date={}",
+            location.latitude,
+            location.longitude,
+            start.format("%Y-%m-%d"),
+            end.format("%Y-%m-%d")
+        )
+    }
+
+    fn parse_forecast(&self, body: &str) -> Result<Vec<HourlyForecast>, String> {
+        let response: OpenMeteoResponse = serde_json::from_str(body)
+            .map_err(|e| format!("Invalid Open-Meteo response: {}", e))?;
+        let hourly = response.hourly;
+        let value = |values: &[Option<f64>], i: usize| values.get(i).copied().flatten();
+
+        hourly
+            .time
+            .iter()
+            .enumerate()
+            .map(|(i, time)| {
+                let time = NaiveDateTime::parse_from_str(time, "%Y-%m-%dT%H:%M")
+                    .map_err(|e| format!("Invalid forecast time '{}': {}", time, e))?;
+                Ok(HourlyForecast {
+                    time: DateTime::from_naive_utc_and_offset(time, Utc),
+                    cloud_cover: value(&hourly.cloud_cover, i),
+                    humidity: value(&hourly.relative_humidity_2m, i),
+                    wind_speed: value(&hourly.wind_speed_10m, i),
+                    temperature: value(&hourly.temperature_2m, i),
+                })
+            })
+            .collect()
+    }
+}
+
+// ============================================================================
+// Provider Registry
+// ============================================================================
+
+/// Registered weather providers, part of the
+/// [`AppState`](crate::state::AppState)
+pub struct WeatherProviders {
+    providers: RwLock<Vec<Arc<dyn WeatherProvider>>>,
+}
+
+impl Default for WeatherProviders {
+    fn default() -> Self {
+        Self {
+            providers: RwLock::new(vec![Arc::new(OpenMeteoProvider)]),
+        }
+    }
+}
+
+/// Register a weather provider, replacing any provider with the same id
+pub fn register_provider(state: &AppState, provider: Arc<dyn WeatherProvider>) {
+    let mut providers = state.weather_providers.providers.write();
+    providers.retain(|p| p.id() != provider.id());
+    providers.push(provider);
+}
+
+/// Get a provider by id
+pub fn get_provider(state: &AppState, id: &str) -> Option<Arc<dyn WeatherProvider>> {
+    state
+        .weather_providers
+        .providers
+        .read()
+        .iter()
+        .find(|p| p.id() == id)
+        .cloned()
+}
+
+/// List registered providers
+pub fn list_providers(state: &AppState) -> Vec<WeatherProviderInfo> {
+    state
+        .weather_providers
+        .providers
+        .read()
+        .iter()
+        .map(|p| WeatherProviderInfo {
+            id: p.id().to_string(),
+            name: p.name().to_string(),
+            max_days: p.max_days(),
+        })
+        .collect()
+}
+
+// ============================================================================
+// Forecasts
+// ============================================================================
+
+/// Fetch hourly forecast data from a provider
+pub async fn fetch_hourly_forecast(
+    provider: &dyn WeatherProvider,
+    location: &ObserverLocation,
+    start: NaiveDate,
+    end: NaiveDate,
+) -> Result<Vec<HourlyForecast>, String> {
+    let url = provider.forecast_url(location, start, end);
+    let response = CLIENT
+        .get(&url)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to fetch forecast from {}: {}", provider.name(), e))?;
+
+    if !response.status().is_success() {
+        return Err(format!(
+            "{} returned HTTP {}",
+            provider.name(),
+            response.status()
+        ));
+    }
+
+    let body = response
+        .text()
+        .await
+        .map_err(|e| format!("Failed to read forecast response: {}", e))?;
+    provider.parse_forecast(&body)
+}
+
+/// Summarize hourly data for the night starting on `date`
+pub fn summarize_night(
+    hourly: &[HourlyForecast],
+    location: &ObserverLocation,
+    date: NaiveDate,
+    provider: &str,
+) -> NightForecast {
+    let twilight = dark_calendar::night_twilight(location, date);
+    let local_noon = dark_calendar::night_start(location, date);
+
+    let night_start = twilight
+        .astronomical_dusk
+        .or(twilight.nautical_dusk)
+        .or(twilight.sunset)
+        .unwrap_or(local_noon + Duration::hours(6));
+    let night_end = twilight
+        .astronomical_dawn
+        .or(twilight.nautical_dawn)
+        .or(twilight.sunrise)
+        .filter(|t| *t > night_start)
+        .unwrap_or(local_noon + Duration::hours(18));
+
+    let night: Vec<HourlyForecast> = hourly
+        .iter()
+        .filter(|h| h.time >= night_start - Duration::minutes(30) && h.time <= night_end)
+        .cloned()
+        .collect();
+
+    let covers: Vec<f64> = night.iter().filter_map(|h| h.cloud_cover).collect();
+    let average_cloud_cover = if covers.is_empty() {
+        None
+    } else {
+        Some(covers.iter().sum::<f64>() / covers.len() as f64)
+    };
+
+    NightForecast {
+        date: date.format("%Y-%m-%d").to_string(),
+        provider: provider.to_string(),
+        night_start,
+        night_end,
+        clear_hours: covers.iter().filter(|c| **c < 30.0).count() as f64,
+        average_cloud_cover,
+        hourly: night,
+    }
+}
+
+/// Fetch night forecasts for each date in `start..=end`
+pub async fn get_night_forecasts(
+    state: &AppState,
+    provider_id: &str,
+    location: &ObserverLocation,
+    start: NaiveDate,
+    end: NaiveDate,
+) -> AppResult<Vec<NightForecast>> {
+    let provider = get_provider(state, provider_id).ok_or_else(|| {
+        AppError::InvalidInput(format!("Unknown weather provider: {}", provider_id))
+    })?;
+
+    // Nights extend into the following UTC day
+    let fetch_end = end.succ_opt().unwrap_or(end);
+    let hourly = fetch_hourly_forecast(provider.as_ref(), location, start, fetch_end)
+        .await
+        .map_err(AppError::Network)?;
+
+    let mut forecasts = Vec::new();
+    let mut current = start;
+    while current <= end {
+        forecasts.push(summarize_night(&hourly, location, current, provider.id()));
+        match current.succ_opt() {
+            Some(next) => current = next,
+            None => break,
+        }
+    }
+    Ok(forecasts)
+}
+
+/// Score multiplier for a night: 1.0 when clear, 0.0 when overcast, and
+/// 1.0 when no forecast data is available
+pub fn cloud_cover_weight(forecast: &NightForecast) -> f64 {
+    forecast
+        .average_cloud_cover
+        .map(|c| (1.0 - c / 100.0).clamp(0.0, 1.0))
+        .unwrap_or(1.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_location() -> ObserverLocation {
+        ObserverLocation {
+            latitude: 40.0,
+            longitude: -74.0,
+            elevation: 0.0,
+            timezone_offset: -5,
+            sky_brightness: None,
+            corrections: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_open_meteo_parse_and_summarize() {
+        let times: Vec<String> = (0..48)
+            .map(|h| format!("\"2024-10-{:02}T{:02}:00\"", 1 + h / 24, h % 24))
+            .collect();
+        let covers: Vec<String> = (0..48)
+            .map(|h| if h < 28 { "100" } else { "0" }.to_string())
+            .collect();
+        let body = format!(
+            r#"{{"hourly":{{"time":[{}],"cloud_cover":[{}]}}}}"#,
+            times.join(","),
+            covers.join(",")
+        );
+
+        let hourly = OpenMeteoProvider.parse_forecast(&body).unwrap();
+        assert_eq!(hourly.len(), 48);
+        assert!(hourly[0].humidity.is_none());
+
+        let date = NaiveDate::from_ymd_opt(2024, 10, 1).unwrap();
+        let night = summarize_night(&hourly, &test_location(), date, "open-meteo");
+
+        // Dusk and dawn of the local night, which straddle 00:00 UTC here
+        let utc = |d, h, m| {
+            NaiveDate::from_ymd_opt(2024, 10, d)
+                .unwrap()
+                .and_hms_opt(h, m, 0)
+        };
+        assert!(night.night_start.naive_utc() > utc(1, 23, 30).unwrap());
+        assert!(night.night_start.naive_utc() < utc(2, 1, 0).unwrap());
+        assert!(night.night_end.naive_utc() > utc(2, 9, 0).unwrap());
+        assert!(night.night_end.naive_utc() < utc(2, 10, 30).unwrap());
+
+        // Overcast until 04:00 UTC (23:00 local), then clear
+        let average = night.average_cloud_cover.unwrap();
+        assert!(average > 0.0 && average < 100.0);
+        assert!(night.clear_hours > 0.0);
+        assert!(cloud_cover_weight(&night) > 0.0 && cloud_cover_weight(&night) < 1.0);
+    }
+
+    #[test]
+    fn test_provider_registry() {
+        let state = AppState::default();
+        assert!(get_provider(&state, DEFAULT_WEATHER_PROVIDER).is_some());
+        assert!(get_provider(&state, "unknown").is_none());
+        assert!(list_providers(&state)
+            .iter()
+            .any(|p| p.id == DEFAULT_WEATHER_PROVIDER));
+
+        let url = OpenMeteoProvider.forecast_url(
+            &test_location(),
+            NaiveDate::from_ymd_opt(2024, 10, 1).unwrap(),
+            NaiveDate::from_ymd_opt(2024, 10, 2).unwrap(),
+        );
+        assert!(url.contains("start_date=2024-10-01"));
+        assert!(url.contains("cloud_cover"));
+    }
+}
@@ -0,0 +1,161 @@
+//! XISF header reading
+//!
+//! An XISF file starts with the `XISF0100` signature, the XML header
+//! length and the XML header itself. Observation data is taken from the
+//! embedded FITS keywords, falling back to XISF properties.
+
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+use crate::services::fits_header::{header_info, parse_string, FitsCard, FitsHeaderInfo};
+use crate::services::xml_tree::XmlElement;
+
+/// Extensions of XISF files
+pub const XISF_EXTENSIONS: &[&str] = &["xisf"];
+
+const SIGNATURE: &[u8] = b"XISF0100";
+
+/// Prefix before the XML header: signature, length and reserved bytes
+const PREFIX_SIZE: usize = 16;
+
+/// Upper bound on the XML header, against corrupt length fields
+const MAX_HEADER_SIZE: usize = 16 * 1024 * 1024;
+
+/// XISF properties and the FITS keywords they stand in for
+const PROPERTY_KEYWORDS: &[(&str, &str)] = &[
+    ("Observation:Object:Name", "OBJECT"),
+    ("Observation:Object:RA", "RA"),
+    ("Observation:Object:Dec", "DEC"),
+    ("Observation:Time:Start", "DATE-OBS"),
+    ("Instrument:ExposureTime", "EXPTIME"),
+    ("Instrument:Filter:Name", "FILTER"),
+    ("Instrument:Camera:Gain", "GAIN"),
+    ("Instrument:Camera:XBinning", "XBINNING"),
+    ("Instrument:Camera:YBinning", "YBINNING"),
+    ("Instrument:Telescope:Name", "TELESCOP"),
+    ("Instrument:Camera:Name", "INSTRUME"),
+];
+
+/// Parse the XML header into observation info
+pub fn parse_xisf_header_xml(xml: &str) -> Result<FitsHeaderInfo, String> {
+    let document = XmlElement::parse(xml)?;
+
+    let mut cards: Vec<FitsCard> = document
+        .find_all(&["FITSKeyword"])
+        .into_iter()
+        .filter_map(|keyword| {
+            let key = keyword.attribute("name")?.trim().to_uppercase();
+            let raw = keyword.attribute("value").unwrap_or("").trim();
+            let is_string = raw.starts_with('\'');
+            let value = if is_string {
+                parse_string(raw)
+            } else {
+                raw.to_string()
+            };
+            Some(FitsCard {
+                key,
+                value,
+                is_string,
+            })
+        })
+        .collect();
+
+    // Properties come after the keywords, so keywords take precedence
+    for property in document.find_all(&["Property"]) {
+        let Some(id) = property.attribute("id") else {
+            continue;
+        };
+        let Some((_, key)) = PROPERTY_KEYWORDS.iter().find(|(p, _)| *p == id) else {
+            continue;
+        };
+        let value = property
+            .attribute("value")
+            .unwrap_or_else(|| property.text())
+            .trim()
+            .to_string();
+        cards.push(FitsCard {
+            key: key.to_string(),
+            value,
+            is_string: property.attribute("type") == Some("String"),
+        });
+    }
+
+    Ok(header_info(&[cards]))
+}
+
+/// Extract the XML header from the start of an XISF file
+pub fn xisf_header_xml(bytes: &[u8]) -> Result<&str, String> {
+    if bytes.len() < PREFIX_SIZE || &bytes[..SIGNATURE.len()] != SIGNATURE {
+        return Err("Not an XISF file".to_string());
+    }
+    let length = u32::from_le_bytes([bytes[8], bytes[9], bytes[10], bytes[11]]) as usize;
+    let xml = bytes
+        .get(PREFIX_SIZE..PREFIX_SIZE + length)
+        .ok_or("Truncated XISF header")?;
+    std::str::from_utf8(xml)
+        .map(|xml| xml.trim_end_matches('\0'))
+        .map_err(|_| "XISF header is not UTF-8".to_string())
+}
+
+/// Parse the header of XISF file content
+pub fn parse_xisf_header(data: &[u8]) -> Result<FitsHeaderInfo, String> {
+    xisf_header_xml(data).and_then(parse_xisf_header_xml)
+}
+
+/// Read the header of an XISF file without loading its data
+pub fn read_xisf_header_file(path: &Path) -> Result<FitsHeaderInfo, String> {
+    let mut file =
+        File::open(path).map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    let mut prefix = [0u8; PREFIX_SIZE];
+    file.read_exact(&mut prefix)
+        .map_err(|_| format!("{}: Not an XISF file", path.display()))?;
+    let length = u32::from_le_bytes([prefix[8], prefix[9], prefix[10], prefix[11]]) as usize;
+    if length > MAX_HEADER_SIZE {
+        return Err(format!("{}: XISF header too large", path.display()));
+    }
+
+    let mut bytes = prefix.to_vec();
+    bytes.resize(PREFIX_SIZE + length, 0);
+    file.read_exact(&mut bytes[PREFIX_SIZE..])
+        .map_err(|_| format!("{}: Truncated XISF header", path.display()))?;
+
+    parse_xisf_header(&bytes).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn xisf_file(xml: &str) -> Vec<u8> {
+        let mut bytes = SIGNATURE.to_vec();
+        bytes.extend((xml.len() as u32).to_le_bytes());
+        bytes.extend([0u8; 4]);
+        bytes.extend(xml.as_bytes());
+        bytes
+    }
+
+    #[test]
+    fn test_parse_xisf_header() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<xisf version="1.0" xmlns="http://www.pixinsight.com/xisf">
+  <Image geometry="100:100:1" sampleFormat="UInt16" location="attachment:4096:20000">
+    <FITSKeyword name="OBJECT" value="'NGC 7000'" comment="Target"/>
+    <FITSKeyword name="EXPTIME" value="300." comment=""/>
+    <FITSKeyword name="OBJCTRA" value="'20 59 17'" comment=""/>
+    <FITSKeyword name="OBJCTDEC" value="'+44 31 44'" comment=""/>
+    <Property id="Instrument:Filter:Name" type="String" value="Ha"/>
+    <Property id="Instrument:ExposureTime" type="Float32" value="600"/>
+  </Image>
+</xisf>"#;
+        let bytes = xisf_file(xml);
+
+        let info = parse_xisf_header(&bytes).unwrap();
+        assert_eq!(info.object_name.as_deref(), Some("NGC 7000"));
+        assert_eq!(info.exposure_time, Some(300.0));
+        assert_eq!(info.filter.as_deref(), Some("Ha"));
+        assert!((info.ra.unwrap() - 314.82).abs() < 0.01);
+
+        assert!(xisf_header_xml(b"SIMPLE  =  T").is_err());
+    }
+}
@@ -0,0 +1,183 @@
+//! Minimal XML element tree
+//!
+//! Built with quick-xml for importers that need to look around in small
+//! documents. Namespace prefixes are dropped from element and attribute
+//! names, entities are decoded and CDATA sections are read as text.
+
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+
+/// XML element with its attributes, text and child elements
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct XmlElement {
+    /// Local name, without namespace prefix
+    pub name: String,
+    pub attributes: Vec<(String, String)>,
+    /// Concatenated text and CDATA directly inside the element
+    pub text: String,
+    pub children: Vec<XmlElement>,
+}
+
+fn matches(name: &str, names: &[&str]) -> bool {
+    names.iter().any(|n| n.eq_ignore_ascii_case(name))
+}
+
+fn element(start: &BytesStart) -> Result<XmlElement, String> {
+    let name = String::from_utf8_lossy(start.local_name().as_ref()).into_owned();
+    let mut attributes = Vec::new();
+    for attribute in start.attributes().with_checks(false) {
+        let attribute = attribute.map_err(|e| format!("Invalid attribute in <{}>: {}", name, e))?;
+        let key = String::from_utf8_lossy(attribute.key.local_name().as_ref()).into_owned();
+        let value = attribute
+            .unescape_value()
+            .map(|v| v.into_owned())
+            .unwrap_or_else(|_| String::from_utf8_lossy(&attribute.value).into_owned());
+        attributes.push((key, value));
+    }
+    Ok(XmlElement {
+        name,
+        attributes,
+        ..Default::default()
+    })
+}
+
+impl XmlElement {
+    /// Parse a document. The returned element is an unnamed container
+    /// holding the document's root element(s).
+    pub fn parse(content: &str) -> Result<XmlElement, String> {
+        let mut reader = Reader::from_str(content);
+        let mut stack = vec![XmlElement::default()];
+
+        loop {
+            let event = reader.read_event().map_err(|e| {
+                format!("Invalid XML at position {}: {}", reader.error_position(), e)
+            })?;
+            match event {
+                Event::Start(start) => stack.push(element(&start)?),
+                Event::Empty(start) => {
+                    let element = element(&start)?;
+                    if let Some(parent) = stack.last_mut() {
+                        parent.children.push(element);
+                    }
+                }
+                Event::End(_) => {
+                    let element = stack.pop().filter(|_| !stack.is_empty());
+                    match (element, stack.last_mut()) {
+                        (Some(element), Some(parent)) => parent.children.push(element),
+                        _ => return Err("Unbalanced XML end tag".to_string()),
+                    }
+                }
+                Event::Text(text) => {
+                    let text = text
+                        .unescape()
+                        .map(|t| t.into_owned())
+                        .unwrap_or_else(|_| String::from_utf8_lossy(&text).into_owned());
+                    if let Some(current) = stack.last_mut() {
+                        current.text.push_str(&text);
+                    }
+                }
+                Event::CData(data) => {
+                    let text = data.decode().map_err(|e| format!("Invalid CDATA: {}", e))?;
+                    if let Some(current) = stack.last_mut() {
+                        current.text.push_str(&text);
+                    }
+                }
+                Event::Eof => break,
+                _ => {}
+            }
+        }
+
+        if stack.len() != 1 {
+            return Err("Unexpected end of XML document".to_string());
+        }
+        Ok(stack.pop().unwrap_or_default())
+    }
+
+    /// Attribute value by local name, ignoring case
+    pub fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Trimmed text of the element
+    pub fn text(&self) -> &str {
+        self.text.trim()
+    }
+
+    /// First child with one of the names, ignoring case
+    pub fn child(&self, names: &[&str]) -> Option<&XmlElement> {
+        self.children.iter().find(|c| matches(&c.name, names))
+    }
+
+    /// Non-empty text of the first child with one of the names, falling
+    /// back to an attribute of that name
+    pub fn value(&self, names: &[&str]) -> Option<&str> {
+        self.child(names)
+            .map(XmlElement::text)
+            .filter(|t| !t.is_empty())
+            .or_else(|| {
+                names
+                    .iter()
+                    .find_map(|name| self.attribute(name))
+                    .map(str::trim)
+                    .filter(|t| !t.is_empty())
+            })
+    }
+
+    /// Children with one of the names
+    pub fn children_named<'a>(
+        &'a self,
+        names: &'a [&'a str],
+    ) -> impl Iterator<Item = &'a XmlElement> + 'a {
+        self.children
+            .iter()
+            .filter(move |c| matches(&c.name, names))
+    }
+
+    /// Outermost descendants with one of the names, in document order.
+    /// Matches nested inside a match are not returned.
+    pub fn find_all<'a>(&'a self, names: &[&str]) -> Vec<&'a XmlElement> {
+        let mut found = Vec::new();
+        for child in &self.children {
+            if matches(&child.name, names) {
+                found.push(child);
+            } else {
+                found.extend(child.find_all(names));
+            }
+        }
+        found
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_attributes_namespaces_and_cdata() {
+        let xml = r#"<?xml version="1.0"?>
+<ns:List xmlns:ns="urn:test" xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance">
+  <ns:Target Name="M31 &amp; M32" xsi:nil="false">
+    <Notes><![CDATA[Use <L> filter & guide]]></Notes>
+    <Empty/>
+  </ns:Target>
+</ns:List>"#;
+        let document = XmlElement::parse(xml).unwrap();
+        let targets = document.find_all(&["target"]);
+
+        assert_eq!(targets.len(), 1);
+        assert_eq!(targets[0].attribute("name"), Some("M31 & M32"));
+        assert_eq!(targets[0].attribute("nil"), Some("false"));
+        assert_eq!(targets[0].value(&["Notes"]), Some("Use <L> filter & guide"));
+        assert_eq!(targets[0].value(&["Name"]), Some("M31 & M32"));
+        assert!(targets[0].child(&["Empty"]).is_some());
+    }
+
+    #[test]
+    fn test_rejects_malformed_xml() {
+        assert!(XmlElement::parse("<a><b></a>").is_err());
+        assert!(XmlElement::parse("<a>").is_err());
+    }
+}
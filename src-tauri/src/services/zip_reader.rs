@@ -0,0 +1,188 @@
+//! Minimal zip archive reader
+//!
+//! Reads archives written by [`ZipWriter`](super::zip_writer::ZipWriter) and
+//! ordinary zip tools: stored and deflated entries, no zip64, no encryption.
+
+use flate2::read::DeflateDecoder;
+use flate2::Crc;
+use std::io::Read;
+
+const LOCAL_HEADER_SIGNATURE: u32 = 0x0403_4b50;
+const CENTRAL_HEADER_SIGNATURE: u32 = 0x0201_4b50;
+const END_OF_CENTRAL_DIRECTORY_SIGNATURE: u32 = 0x0605_4b50;
+const END_OF_CENTRAL_DIRECTORY_SIZE: usize = 22;
+const METHOD_STORED: u16 = 0;
+const METHOD_DEFLATE: u16 = 8;
+/// Largest uncompressed entry accepted
+const MAX_ENTRY_SIZE: usize = 64 * 1024 * 1024;
+/// Largest uncompressed archive accepted
+const MAX_TOTAL_SIZE: usize = 256 * 1024 * 1024;
+
+/// File read from an archive
+#[derive(Debug, Clone)]
+pub struct ZipEntry {
+    pub name: String,
+    pub data: Vec<u8>,
+}
+
+fn u16_at(bytes: &[u8], offset: usize) -> Result<u16, String> {
+    bytes
+        .get(offset..offset + 2)
+        .map(|b| u16::from_le_bytes([b[0], b[1]]))
+        .ok_or_else(|| "Truncated zip archive".to_string())
+}
+
+fn u32_at(bytes: &[u8], offset: usize) -> Result<u32, String> {
+    bytes
+        .get(offset..offset + 4)
+        .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .ok_or_else(|| "Truncated zip archive".to_string())
+}
+
+fn slice(bytes: &[u8], offset: usize, len: usize) -> Result<&[u8], String> {
+    bytes
+        .get(offset..offset + len)
+        .ok_or_else(|| "Truncated zip archive".to_string())
+}
+
+/// Locate the end of central directory record, which is followed by an
+/// archive comment of up to 64 KB
+fn find_end_of_central_directory(bytes: &[u8]) -> Result<usize, String> {
+    if bytes.len() < END_OF_CENTRAL_DIRECTORY_SIZE {
+        return Err("Not a zip archive".to_string());
+    }
+    let last = bytes.len() - END_OF_CENTRAL_DIRECTORY_SIZE;
+    let first = last.saturating_sub(u16::MAX as usize);
+    (first..=last)
+        .rev()
+        .find(|&offset| u32_at(bytes, offset) == Ok(END_OF_CENTRAL_DIRECTORY_SIGNATURE))
+        .ok_or_else(|| "Not a zip archive".to_string())
+}
+
+/// Read all file entries of an archive, in central directory order
+pub fn read_zip(bytes: &[u8]) -> Result<Vec<ZipEntry>, String> {
+    let end = find_end_of_central_directory(bytes)?;
+    let count = u16_at(bytes, end + 10)? as usize;
+    let mut offset = u32_at(bytes, end + 16)? as usize;
+
+    let mut entries = Vec::new();
+    let mut total = 0usize;
+    for _ in 0..count {
+        if u32_at(bytes, offset)? != CENTRAL_HEADER_SIGNATURE {
+            return Err("Corrupt zip central directory".to_string());
+        }
+        let method = u16_at(bytes, offset + 10)?;
+        let crc = u32_at(bytes, offset + 16)?;
+        let compressed_size = u32_at(bytes, offset + 20)? as usize;
+        let size = u32_at(bytes, offset + 24)? as usize;
+        let name_length = u16_at(bytes, offset + 28)? as usize;
+        let extra_length = u16_at(bytes, offset + 30)? as usize;
+        let comment_length = u16_at(bytes, offset + 32)? as usize;
+        let local_offset = u32_at(bytes, offset + 42)? as usize;
+        let name = String::from_utf8_lossy(slice(bytes, offset + 46, name_length)?).into_owned();
+        offset += 46 + name_length + extra_length + comment_length;
+
+        if name.ends_with('/') {
+            continue;
+        }
+
+        if size > MAX_ENTRY_SIZE || total + size > MAX_TOTAL_SIZE {
+            return Err(format!("Zip entry {} is too large", name));
+        }
+        total += size;
+
+        if u32_at(bytes, local_offset)? != LOCAL_HEADER_SIGNATURE {
+            return Err(format!("Corrupt zip entry: {}", name));
+        }
+        let data_offset = local_offset
+            + 30
+            + u16_at(bytes, local_offset + 26)? as usize
+            + u16_at(bytes, local_offset + 28)? as usize;
+        let raw = slice(bytes, data_offset, compressed_size)?;
+
+        let data = match method {
+            METHOD_STORED => raw.to_vec(),
+            METHOD_DEFLATE => {
+                // Never trust the declared size: read one byte past it so a
+                // deflate bomb stops there instead of exhausting memory
+                let mut data = Vec::new();
+                DeflateDecoder::new(raw)
+                    .take(size as u64 + 1)
+                    .read_to_end(&mut data)
+                    .map_err(|e| format!("Failed to decompress {}: {}", name, e))?;
+                data
+            }
+            _ => {
+                return Err(format!(
+                    "Unsupported compression method {} for {}",
+                    method, name
+                ))
+            }
+        };
+
+        if data.len() > size {
+            return Err(format!("Zip entry {} exceeds its declared size", name));
+        }
+        let mut actual = Crc::new();
+        actual.update(&data);
+        if data.len() != size || actual.sum() != crc {
+            return Err(format!("Checksum mismatch for {}", name));
+        }
+
+        entries.push(ZipEntry { name, data });
+    }
+
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::zip_writer::ZipWriter;
+    use chrono::Utc;
+
+    #[test]
+    fn test_read_written_archive() {
+        let mut zip = ZipWriter::new(Utc::now());
+        zip.add_file("sequence.json", b"{\"title\":\"Night\"}")
+            .unwrap();
+        zip.add_file("thumbnails/m31.png", &[0u8, 1, 2, 3]).unwrap();
+        let bytes = zip.finish().unwrap();
+
+        let entries = read_zip(&bytes).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].name, "sequence.json");
+        assert_eq!(entries[0].data, b"{\"title\":\"Night\"}");
+        assert_eq!(entries[1].data, vec![0u8, 1, 2, 3]);
+    }
+
+    #[test]
+    fn test_rejects_corrupt_archive() {
+        assert!(read_zip(b"not a zip").is_err());
+
+        let mut zip = ZipWriter::new(Utc::now());
+        zip.add_file("a.txt", b"hello hello hello").unwrap();
+        let mut bytes = zip.finish().unwrap();
+        // Flip the stored checksum of the local and central headers
+        bytes[14] ^= 0xff;
+        let directory = u32_at(&bytes, bytes.len() - 6).unwrap() as usize;
+        bytes[directory + 16] ^= 0xff;
+        assert!(read_zip(&bytes).is_err());
+    }
+
+    #[test]
+    fn test_rejects_entry_larger_than_declared() {
+        let mut zip = ZipWriter::new(Utc::now());
+        zip.add_file("bomb.bin", &vec![0u8; 1024 * 1024]).unwrap();
+        let mut bytes = zip.finish().unwrap();
+        // Declare a tiny uncompressed size in the central directory
+        let directory = u32_at(&bytes, bytes.len() - 6).unwrap() as usize;
+        bytes[directory + 24..directory + 28].copy_from_slice(&16u32.to_le_bytes());
+
+        let error = read_zip(&bytes).unwrap_err();
+        assert!(error.contains("exceeds its declared size"));
+
+        bytes[directory + 24..directory + 28].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(read_zip(&bytes).unwrap_err().contains("too large"));
+    }
+}
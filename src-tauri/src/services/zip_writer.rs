@@ -0,0 +1,194 @@
+//! Minimal zip archive writer
+//!
+//! Writes deflate-compressed entries into an in-memory archive. Only what
+//! support bundles need is supported: no zip64, no directory entries,
+//! no encryption.
+
+use chrono::{DateTime, Datelike, Timelike, Utc};
+use flate2::write::DeflateEncoder;
+use flate2::{Compression, Crc};
+use std::io::Write;
+
+const LOCAL_HEADER_SIGNATURE: u32 = 0x0403_4b50;
+const CENTRAL_HEADER_SIGNATURE: u32 = 0x0201_4b50;
+const END_OF_CENTRAL_DIRECTORY_SIGNATURE: u32 = 0x0605_4b50;
+/// Version 2.0, the first with deflate
+const VERSION: u16 = 20;
+/// Bit 11: names are UTF-8
+const FLAGS: u16 = 0x0800;
+const METHOD_DEFLATE: u16 = 8;
+
+struct CentralEntry {
+    name: String,
+    crc: u32,
+    compressed_size: u32,
+    size: u32,
+    offset: u32,
+}
+
+/// In-memory zip archive
+pub struct ZipWriter {
+    buffer: Vec<u8>,
+    entries: Vec<CentralEntry>,
+    time: u16,
+    date: u16,
+}
+
+fn put_u16(buffer: &mut Vec<u8>, value: u16) {
+    buffer.extend_from_slice(&value.to_le_bytes());
+}
+
+fn put_u32(buffer: &mut Vec<u8>, value: u32) {
+    buffer.extend_from_slice(&value.to_le_bytes());
+}
+
+fn to_u32(value: usize) -> Result<u32, String> {
+    u32::try_from(value).map_err(|_| "Zip archive exceeds 4 GB".to_string())
+}
+
+impl ZipWriter {
+    /// Create an archive whose entries are all stamped with `modified`
+    pub fn new(modified: DateTime<Utc>) -> Self {
+        // MS-DOS format, which starts in 1980 and has two-second resolution
+        let year = (modified.year() - 1980).clamp(0, 127) as u16;
+        Self {
+            buffer: Vec::new(),
+            entries: Vec::new(),
+            time: ((modified.hour() as u16) << 11)
+                | ((modified.minute() as u16) << 5)
+                | (modified.second() as u16 / 2),
+            date: (year << 9) | ((modified.month() as u16) << 5) | modified.day() as u16,
+        }
+    }
+
+    /// Add a file. `name` uses `/` as separator.
+    pub fn add_file(&mut self, name: &str, data: &[u8]) -> Result<(), String> {
+        let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+        encoder
+            .write_all(data)
+            .map_err(|e| format!("Failed to compress {}: {}", name, e))?;
+        let compressed = encoder
+            .finish()
+            .map_err(|e| format!("Failed to compress {}: {}", name, e))?;
+
+        let mut crc = Crc::new();
+        crc.update(data);
+
+        let entry = CentralEntry {
+            name: name.to_string(),
+            crc: crc.sum(),
+            compressed_size: to_u32(compressed.len())?,
+            size: to_u32(data.len())?,
+            offset: to_u32(self.buffer.len())?,
+        };
+
+        let buffer = &mut self.buffer;
+        put_u32(buffer, LOCAL_HEADER_SIGNATURE);
+        put_u16(buffer, VERSION);
+        put_u16(buffer, FLAGS);
+        put_u16(buffer, METHOD_DEFLATE);
+        put_u16(buffer, self.time);
+        put_u16(buffer, self.date);
+        put_u32(buffer, entry.crc);
+        put_u32(buffer, entry.compressed_size);
+        put_u32(buffer, entry.size);
+        put_u16(buffer, name.len() as u16);
+        put_u16(buffer, 0);
+        buffer.extend_from_slice(name.as_bytes());
+        buffer.extend_from_slice(&compressed);
+
+        self.entries.push(entry);
+        Ok(())
+    }
+
+    /// Write the central directory and return the archive bytes
+    pub fn finish(mut self) -> Result<Vec<u8>, String> {
+        let directory_offset = to_u32(self.buffer.len())?;
+
+        let buffer = &mut self.buffer;
+        for entry in &self.entries {
+            put_u32(buffer, CENTRAL_HEADER_SIGNATURE);
+            put_u16(buffer, VERSION);
+            put_u16(buffer, VERSION);
+            put_u16(buffer, FLAGS);
+            put_u16(buffer, METHOD_DEFLATE);
+            put_u16(buffer, self.time);
+            put_u16(buffer, self.date);
+            put_u32(buffer, entry.crc);
+            put_u32(buffer, entry.compressed_size);
+            put_u32(buffer, entry.size);
+            put_u16(buffer, entry.name.len() as u16);
+            // Extra field, comment, disk number, internal and external attributes
+            put_u16(buffer, 0);
+            put_u16(buffer, 0);
+            put_u16(buffer, 0);
+            put_u16(buffer, 0);
+            put_u32(buffer, 0);
+            put_u32(buffer, entry.offset);
+            buffer.extend_from_slice(entry.name.as_bytes());
+        }
+
+        let directory_size = to_u32(buffer.len())? - directory_offset;
+        let count = u16::try_from(self.entries.len())
+            .map_err(|_| "Too many files for a zip archive".to_string())?;
+        put_u32(buffer, END_OF_CENTRAL_DIRECTORY_SIGNATURE);
+        put_u16(buffer, 0);
+        put_u16(buffer, 0);
+        put_u16(buffer, count);
+        put_u16(buffer, count);
+        put_u32(buffer, directory_size);
+        put_u32(buffer, directory_offset);
+        put_u16(buffer, 0);
+
+        Ok(self.buffer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use flate2::read::DeflateDecoder;
+    use std::io::Read;
+
+    fn u16_at(bytes: &[u8], offset: usize) -> u16 {
+        u16::from_le_bytes([bytes[offset], bytes[offset + 1]])
+    }
+
+    fn u32_at(bytes: &[u8], offset: usize) -> u32 {
+        u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap())
+    }
+
+    #[test]
+    fn test_zip_round_trip() {
+        let modified = Utc.with_ymd_and_hms(2025, 6, 15, 22, 30, 10).unwrap();
+        let mut zip = ZipWriter::new(modified);
+        zip.add_file("logs/a.log", b"hello hello hello").unwrap();
+        zip.add_file("settings.json", b"{}").unwrap();
+        let bytes = zip.finish().unwrap();
+
+        // End of central directory: two entries
+        let end = bytes.len() - 22;
+        assert_eq!(u32_at(&bytes, end), END_OF_CENTRAL_DIRECTORY_SIGNATURE);
+        assert_eq!(u16_at(&bytes, end + 10), 2);
+        let directory = u32_at(&bytes, end + 16) as usize;
+        assert_eq!(u32_at(&bytes, directory), CENTRAL_HEADER_SIGNATURE);
+
+        // First local entry decompresses to the original data
+        assert_eq!(u32_at(&bytes, 0), LOCAL_HEADER_SIGNATURE);
+        assert_eq!(u16_at(&bytes, 12), (45 << 9) | (6 << 5) | 15);
+        let compressed_size = u32_at(&bytes, 18) as usize;
+        let name_length = u16_at(&bytes, 26) as usize;
+        assert_eq!(&bytes[30..30 + name_length], b"logs/a.log");
+        let data = &bytes[30 + name_length..30 + name_length + compressed_size];
+        let mut inflated = String::new();
+        DeflateDecoder::new(data)
+            .read_to_string(&mut inflated)
+            .unwrap();
+        assert_eq!(inflated, "hello hello hello");
+
+        let mut crc = Crc::new();
+        crc.update(inflated.as_bytes());
+        assert_eq!(u32_at(&bytes, 14), crc.sum());
+    }
+}
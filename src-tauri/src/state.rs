@@ -0,0 +1,65 @@
+//! Shared application state
+//!
+//! State the commands share: settings, the internal clipboard, sequences
+//! open in backend sessions and their autosaves, the plan open in the
+//! editor, the log buffer, background jobs, connections and calculation
+//! caches. Each part has its own lock, so a command copying to the
+//! clipboard never waits on one saving settings.
+//!
+//! The app creates one instance and hands it to Tauri as [`SharedState`].
+//! Commands take it as `State<'_, SharedState>` and pass `&AppState` on to
+//! the services; background tasks hold a clone of the handle. Tests build
+//! isolated ones with `AppState::default()`.
+
+use std::sync::Arc;
+
+use parking_lot::RwLock;
+
+use crate::models::SimpleSequence;
+use crate::services::altitude_curve::CurveCache;
+use crate::services::auto_save::AutoSaveState;
+use crate::services::clipboard_service::ClipboardState;
+use crate::services::file_watcher::FileWatchState;
+use crate::services::import_preview::ImportPreview;
+use crate::services::job_queue::JobQueue;
+use crate::services::log_service::LogState;
+use crate::services::nina_remote::NinaRemoteState;
+use crate::services::nina_type_registry::PluginTypes;
+use crate::services::remote_api::RemoteApiState;
+use crate::services::satellite::Tle;
+use crate::services::sequence_library::LibraryState;
+use crate::services::sequence_session::SessionStore;
+use crate::services::settings_service::SettingsState;
+use crate::services::weather::WeatherProviders;
+
+#[derive(Default)]
+pub struct AppState {
+    pub settings: SettingsState,
+    pub clipboard: ClipboardState,
+    /// Sequences open in backend sessions
+    pub sessions: SessionStore,
+    /// Autosave snapshots of the sessions
+    pub auto_save: AutoSaveState,
+    /// Plan open in the editor, as last published
+    pub open_sequence: RwLock<Option<SimpleSequence>>,
+    pub logs: LogState,
+    pub altitude_curves: RwLock<CurveCache>,
+    /// Import previews waiting to be committed, oldest first
+    pub import_previews: RwLock<Vec<ImportPreview>>,
+    pub jobs: JobQueue,
+    /// Sequence file watched for external changes
+    pub file_watch: FileWatchState,
+    /// Connection to NINA's Advanced API
+    pub nina: NinaRemoteState,
+    pub remote_api: RemoteApiState,
+    /// Loaded TLE catalog
+    pub tle_catalog: RwLock<Vec<Tle>>,
+    /// Sequence library index
+    pub library: LibraryState,
+    pub weather_providers: WeatherProviders,
+    /// Types from `plugin_types.json`
+    pub plugin_types: PluginTypes,
+}
+
+/// Handle to the state, as managed by Tauri
+pub type SharedState = Arc<AppState>;
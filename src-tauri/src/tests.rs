@@ -7,38 +7,45 @@
 mod integration_tests {
     use crate::models::*;
     use crate::services::*;
+    use crate::state::AppState;
 
     fn create_test_sequence() -> SimpleSequence {
-        let mut seq = SimpleSequence::default();
-        seq.title = "Test Sequence".to_string();
-        seq.estimated_download_time = 5.0;
-
-        let mut target = SimpleTarget::default();
-        target.target_name = "M31 - Andromeda".to_string();
-        target.coordinates = Coordinates::from_decimal(0.712, 41.27);
+        let exposure = SimpleExposure {
+            exposure_time: 60.0,
+            total_count: 10,
+            ..Default::default()
+        };
 
-        let mut exposure = SimpleExposure::default();
-        exposure.exposure_time = 60.0;
-        exposure.total_count = 10;
-        target.exposures = vec![exposure];
+        let target = SimpleTarget {
+            target_name: "M31 - Andromeda".to_string(),
+            coordinates: Coordinates::from_decimal(0.712, 41.27),
+            exposures: vec![exposure],
+            ..Default::default()
+        };
 
-        seq.targets = vec![target];
-        seq
+        SimpleSequence {
+            title: "Test Sequence".to_string(),
+            estimated_download_time: 5.0,
+            targets: vec![target],
+            ..Default::default()
+        }
     }
 
     fn create_test_target() -> SimpleTarget {
-        let mut target = SimpleTarget::default();
-        target.name = "Test Target".to_string();
-        target.target_name = "M31".to_string();
-        target.coordinates = Coordinates::from_decimal(0.712, 41.27);
-        target
+        SimpleTarget {
+            name: "Test Target".to_string(),
+            target_name: "M31".to_string(),
+            coordinates: Coordinates::from_decimal(0.712, 41.27),
+            ..Default::default()
+        }
     }
 
     fn create_test_exposure() -> SimpleExposure {
-        let mut exp = SimpleExposure::default();
-        exp.exposure_time = 60.0;
-        exp.total_count = 10;
-        exp
+        SimpleExposure {
+            exposure_time: 60.0,
+            total_count: 10,
+            ..Default::default()
+        }
     }
 
     // ==================== Model Tests ====================
@@ -97,8 +104,10 @@ mod integration_tests {
         let valid = Coordinates::from_decimal(12.0, 45.0);
         assert!(valid.validate().is_empty());
 
-        let mut invalid = Coordinates::default();
-        invalid.ra_hours = 25; // Invalid
+        let invalid = Coordinates {
+            ra_hours: 25, // Invalid
+            ..Default::default()
+        };
         assert!(!invalid.validate().is_empty());
     }
 
@@ -106,14 +115,62 @@ mod integration_tests {
 
     #[test]
     fn test_exposure_runtime() {
-        let mut exp = SimpleExposure::default();
-        exp.exposure_time = 60.0;
-        exp.total_count = 10;
+        let exp = SimpleExposure {
+            exposure_time: 60.0,
+            total_count: 10,
+            ..Default::default()
+        };
 
-        let runtime = calculator::calculate_exposure_runtime(&exp, 5.0);
+        let runtime = calculator::calculate_exposure_runtime(&exp, 5.0, None);
         assert_eq!(runtime, 650.0); // (60 + 5) * 10
     }
 
+    #[test]
+    fn test_exposure_runtime_with_dither_overhead() {
+        let exp = SimpleExposure {
+            exposure_time: 60.0,
+            total_count: 10,
+            dither: true,
+            dither_every: 3,
+            ..Default::default()
+        };
+        let overheads = OverheadProfile {
+            dither_settle_time: 20.0,
+            ..Default::default()
+        };
+
+        let runtime = calculator::calculate_exposure_runtime(&exp, 5.0, Some(&overheads));
+        assert_eq!(runtime, 710.0); // (60 + 5) * 10 + 3 dithers * 20
+    }
+
+    #[test]
+    fn test_autofocus_overhead_per_filter() {
+        let mut target = create_test_target();
+        target.auto_focus_on_start = true;
+        target.auto_focus_on_filter_change = true;
+        target.exposures.clear();
+        for name in ["L", "Ha", "Ha"] {
+            target.exposures.push(SimpleExposure {
+                total_count: 1,
+                filter: Some(FilterInfo {
+                    name: name.to_string(),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            });
+        }
+        let overheads = OverheadProfile {
+            autofocus_duration: 100.0,
+            autofocus_durations: vec![FilterAutofocusTime {
+                filter: "Ha".to_string(),
+                seconds: 300.0,
+            }],
+            ..Default::default()
+        };
+
+        assert_eq!(calculator::autofocus_overhead(&target, &overheads), 400.0);
+    }
+
     #[test]
     fn test_target_runtime() {
         let mut target = create_test_target();
@@ -171,7 +228,7 @@ mod integration_tests {
     fn test_moon_phase() {
         use chrono::Utc;
         let phase = calculator::calculate_moon_phase(Utc::now());
-        assert!(phase >= 0.0 && phase <= 1.0);
+        assert!((0.0..=1.0).contains(&phase));
     }
 
     // ==================== Validator Tests ====================
@@ -199,6 +256,26 @@ mod integration_tests {
         assert!(!result.valid);
     }
 
+    #[test]
+    fn test_validate_target_integration_limits() {
+        let mut target = create_test_target();
+        target.min_integration = Some(7200.0);
+        target.max_integration = Some(3600.0);
+        assert!(!validator::validate_simple_target(&target).valid);
+
+        target.max_integration = Some(10800.0);
+        assert!(validator::validate_simple_target(&target).valid);
+    }
+
+    #[test]
+    fn test_target_priority_defaults_when_missing() {
+        let mut json = serde_json::to_value(create_test_target()).unwrap();
+        json.as_object_mut().unwrap().remove("priority");
+        let target: SimpleTarget = serde_json::from_value(json).unwrap();
+        assert_eq!(target.priority, TargetPriority::Normal);
+        assert!(target.min_integration.is_none());
+    }
+
     #[test]
     fn test_validate_exposure_invalid_time() {
         let mut exp = create_test_exposure();
@@ -217,16 +294,20 @@ mod integration_tests {
 
     #[test]
     fn test_validate_coordinates_invalid_ra() {
-        let mut coords = Coordinates::default();
-        coords.ra_hours = 30;
+        let coords = Coordinates {
+            ra_hours: 30,
+            ..Default::default()
+        };
         let result = validator::validate_coordinates(&coords);
         assert!(!result.valid);
     }
 
     #[test]
     fn test_validate_coordinates_invalid_dec() {
-        let mut coords = Coordinates::default();
-        coords.dec_degrees = 100;
+        let coords = Coordinates {
+            dec_degrees: 100,
+            ..Default::default()
+        };
         let result = validator::validate_coordinates(&coords);
         assert!(!result.valid);
     }
@@ -235,64 +316,86 @@ mod integration_tests {
 
     #[test]
     fn test_clipboard_copy_paste_target() {
+        let clipboard = AppState::default().clipboard;
         let target = create_test_target();
-        clipboard_service::copy_target(target.clone());
+        clipboard.copy_target(target.clone());
 
-        assert!(clipboard_service::has_clipboard_content());
-        assert!(clipboard_service::has_clipboard_content_type("target"));
+        assert!(clipboard.has_content());
+        assert!(clipboard.has_content_type("target"));
 
-        let pasted = clipboard_service::paste_target().unwrap();
+        let pasted = clipboard.paste_target().unwrap();
         assert_ne!(pasted.id, target.id);
         assert!(pasted.name.contains("Copy"));
     }
 
     #[test]
     fn test_clipboard_copy_paste_exposure() {
+        let clipboard = AppState::default().clipboard;
         let exp = create_test_exposure();
-        clipboard_service::copy_exposure(exp.clone());
+        clipboard.copy_exposure(exp.clone());
 
-        assert!(clipboard_service::has_clipboard_content());
+        assert!(clipboard.has_content());
 
-        let pasted = clipboard_service::paste_exposure().unwrap();
+        let pasted = clipboard.paste_exposure().unwrap();
         assert_ne!(pasted.id, exp.id);
     }
 
     #[test]
     fn test_clipboard_copy_multiple_targets() {
+        let clipboard = AppState::default().clipboard;
         let targets = vec![create_test_target(), create_test_target()];
-        clipboard_service::copy_targets(targets);
+        clipboard.copy_targets(targets);
 
-        let pasted = clipboard_service::paste_targets().unwrap();
+        let pasted = clipboard.paste_targets().unwrap();
         assert_eq!(pasted.len(), 2);
     }
 
     #[test]
     fn test_clipboard_clear() {
-        clipboard_service::copy_target(create_test_target());
-        clipboard_service::clear_clipboard();
-        assert!(!clipboard_service::has_clipboard_content());
+        let clipboard = AppState::default().clipboard;
+        clipboard.copy_target(create_test_target());
+        clipboard.clear();
+        assert!(!clipboard.has_content());
+    }
+
+    // ==================== Settings Service Tests ====================
+
+    #[test]
+    fn test_resolve_location() {
+        let state = AppState::default();
+        let location = astronomy::ObserverLocation {
+            latitude: 51.5,
+            ..Default::default()
+        };
+        let resolved = settings_service::resolve_location(&state, Some(location), None).unwrap();
+        assert_eq!(resolved.latitude, 51.5);
+
+        let missing = settings_service::resolve_location(&state, None, Some("missing".to_string()));
+        assert!(missing.is_err());
     }
 
     // ==================== Log Service Tests ====================
 
     #[test]
     fn test_log_service_basic() {
-        log_service::log_info("test", "Test message");
-        log_service::log_debug("test", "Debug message");
-        log_service::log_warning("test", "Warning message");
-        log_service::log_error("test", "Error message");
+        let state = AppState::default();
+        log_service::log_info(&state, "test", "Test message");
+        log_service::log_debug(&state, "test", "Debug message");
+        log_service::log_warning(&state, "test", "Warning message");
+        log_service::log_error(&state, "test", "Error message");
 
-        let logs = log_service::get_recent_logs(10, None);
+        let logs = log_service::get_recent_logs(&state, 10, None);
         assert!(!logs.is_empty());
     }
 
     #[test]
     fn test_log_service_filter_by_level() {
-        log_service::clear_log_buffer();
-        log_service::log_info("test", "Info");
-        log_service::log_error("test", "Error");
+        let state = AppState::default();
+        log_service::clear_log_buffer(&state);
+        log_service::log_info(&state, "test", "Info");
+        log_service::log_error(&state, "test", "Error");
 
-        let errors = log_service::get_recent_logs(10, Some(log_service::LogLevel::Error));
+        let errors = log_service::get_recent_logs(&state, 10, Some(log_service::LogLevel::Error));
         assert!(errors
             .iter()
             .all(|l| matches!(l.level, log_service::LogLevel::Error)));
@@ -300,11 +403,12 @@ mod integration_tests {
 
     #[test]
     fn test_log_service_filter_by_category() {
-        log_service::clear_log_buffer();
-        log_service::log_info("category1", "Message 1");
-        log_service::log_info("category2", "Message 2");
+        let state = AppState::default();
+        log_service::clear_log_buffer(&state);
+        log_service::log_info(&state, "category1", "Message 1");
+        log_service::log_info(&state, "category2", "Message 2");
 
-        let filtered = log_service::get_logs_by_category("category1", 10);
+        let filtered = log_service::get_logs_by_category(&state, "category1", 10);
         assert!(filtered.iter().all(|l| l.category == "category1"));
     }
 
@@ -356,6 +460,10 @@ mod integration_tests {
             center_target: true,
             rotate_target: false,
             start_guiding: true,
+            priority: TargetPriority::Normal,
+            min_integration: None,
+            max_integration: None,
+            constraints: Default::default(),
             auto_focus_on_start: true,
             auto_focus_on_filter_change: false,
             auto_focus_after_set_time: false,
@@ -380,10 +488,16 @@ mod integration_tests {
                 progress_count: 0,
                 dither: false,
                 dither_every: 1,
+                estimated_start_time: None,
+                estimated_end_time: None,
             }],
             estimated_start_time: None,
             estimated_end_time: None,
             estimated_duration: None,
+            moving_target: None,
+            notes: None,
+            tags: Vec::new(),
+            metadata: Default::default(),
         };
         sequence.targets.push(target);
 
@@ -413,8 +527,13 @@ mod integration_tests {
         assert!(runtime > 0.0);
 
         // Calculate ETAs
-        calculator::calculate_sequence_etas(&mut sequence);
+        calculator::calculate_sequence_etas(&mut sequence, None);
         assert!(sequence.overall_duration.is_some());
+        let target = &sequence.targets[0];
+        assert_eq!(
+            target.exposures[0].estimated_start_time,
+            target.estimated_start_time
+        );
     }
 
     #[test]
@@ -444,16 +563,16 @@ mod integration_tests {
         };
 
         // Validate
-        let validation = validator::validate_editor_sequence(&sequence);
+        let validation = validator::validate_editor_sequence(&sequence, &[]);
         assert!(validation.valid);
 
         // Export to NINA format
-        let nina_json = nina_serializer::export_to_nina(&sequence).unwrap();
+        let nina_json = nina_serializer::export_to_nina(&sequence, &[]).unwrap();
         assert!(!nina_json.is_empty());
         assert!(nina_json.contains("SequenceRootContainer"));
 
         // Import back
-        let imported = nina_serializer::import_from_nina(&nina_json).unwrap();
+        let imported = nina_serializer::import_from_nina(&nina_json, false, &[]).unwrap();
         assert_eq!(imported.title, sequence.title);
         assert_eq!(imported.start_items.len(), sequence.start_items.len());
     }
@@ -483,6 +602,10 @@ mod integration_tests {
                     center_target: true,
                     rotate_target: false,
                     start_guiding: true,
+                    priority: TargetPriority::Normal,
+                    min_integration: None,
+                    max_integration: None,
+                    constraints: Default::default(),
                     auto_focus_on_start: false,
                     auto_focus_on_filter_change: false,
                     auto_focus_after_set_time: false,
@@ -497,6 +620,10 @@ mod integration_tests {
                     estimated_start_time: None,
                     estimated_end_time: None,
                     estimated_duration: None,
+                    moving_target: None,
+                    notes: None,
+                    tags: Vec::new(),
+                    metadata: Default::default(),
                 },
                 SimpleTarget {
                     id: "t2".to_string(),
@@ -513,6 +640,10 @@ mod integration_tests {
                     center_target: true,
                     rotate_target: false,
                     start_guiding: true,
+                    priority: TargetPriority::Normal,
+                    min_integration: None,
+                    max_integration: None,
+                    constraints: Default::default(),
                     auto_focus_on_start: false,
                     auto_focus_on_filter_change: false,
                     auto_focus_after_set_time: false,
@@ -527,6 +658,10 @@ mod integration_tests {
                     estimated_start_time: None,
                     estimated_end_time: None,
                     estimated_duration: None,
+                    moving_target: None,
+                    notes: None,
+                    tags: Vec::new(),
+                    metadata: Default::default(),
                 },
             ],
             selected_target_id: None,
@@ -601,6 +736,44 @@ mod integration_tests {
         assert!(matches!(mode, SequenceMode::Standard));
     }
 
+    #[test]
+    fn test_execution_plan_rotate() {
+        let mut target = SimpleTarget {
+            mode: SequenceMode::Rotate,
+            ..Default::default()
+        };
+        target.exposures = vec![
+            SimpleExposure {
+                total_count: 2,
+                dither: true,
+                dither_every: 2,
+                ..Default::default()
+            },
+            SimpleExposure {
+                total_count: 1,
+                ..Default::default()
+            },
+        ];
+        let order = |target: &SimpleTarget| {
+            target
+                .execution_plan()
+                .iter()
+                .map(|s| (s.exposure_index, s.frame, s.dither_after))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            order(&target),
+            vec![(0, 1, false), (1, 1, false), (0, 2, true)]
+        );
+
+        target.mode = SequenceMode::Standard;
+        assert_eq!(
+            order(&target),
+            vec![(0, 1, false), (0, 2, true), (1, 1, false)]
+        );
+    }
+
     // ==================== Additional Calculator Tests ====================
 
     #[test]
@@ -637,7 +810,7 @@ mod integration_tests {
     fn test_moon_illumination() {
         use chrono::Utc;
         let illumination = calculator::calculate_moon_illumination(Utc::now());
-        assert!(illumination >= 0.0 && illumination <= 100.0);
+        assert!((0.0..=100.0).contains(&illumination));
     }
 
     // ==================== Additional Serializer Tests ====================
@@ -706,7 +879,7 @@ mod integration_tests {
             global_triggers: vec![],
         };
 
-        let json = nina_serializer::export_to_nina(&seq).unwrap();
+        let json = nina_serializer::export_to_nina(&seq, &[]).unwrap();
         assert!(json.contains("Conditions"));
     }
 
@@ -735,13 +908,16 @@ mod integration_tests {
     #[test]
     fn test_is_container_type() {
         assert!(validator::is_container_type(
-            "NINA.Sequencer.Container.SequentialContainer, NINA.Sequencer"
+            "NINA.Sequencer.Container.SequentialContainer, NINA.Sequencer",
+            &[],
         ));
         assert!(validator::is_container_type(
-            "NINA.Sequencer.SequenceItem.Imaging.SmartExposure, NINA.Sequencer"
+            "NINA.Sequencer.SequenceItem.Imaging.SmartExposure, NINA.Sequencer",
+            &[],
         ));
         assert!(!validator::is_container_type(
-            "NINA.Sequencer.SequenceItem.Camera.CoolCamera, NINA.Sequencer"
+            "NINA.Sequencer.SequenceItem.Camera.CoolCamera, NINA.Sequencer",
+            &[],
         ));
     }
 
@@ -761,7 +937,7 @@ mod integration_tests {
     fn test_disabled_exposure_runtime() {
         let mut exp = create_test_exposure();
         exp.enabled = false;
-        let runtime = calculator::calculate_exposure_runtime(&exp, 5.0);
+        let runtime = calculator::calculate_exposure_runtime(&exp, 5.0, None);
         assert_eq!(runtime, 0.0);
     }
 